@@ -0,0 +1,71 @@
+//! Instruction types
+
+use solana_program::program_error::ProgramError;
+
+/// Instructions supported by the AssociatedTokenAccount program, selected by
+/// the first byte of the instruction data
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AssociatedTokenAccountInstruction {
+    /// Creates an associated token account for the given wallet address and
+    /// token mint.  Fails if the account already exists.  Empty instruction
+    /// data also selects this instruction, for backwards compatibility.
+    ///
+    ///   0. `[writeable,signer]` Funding account (must be a system account)
+    ///   1. `[writeable]` Associated token account address to be created
+    ///   2. `[]` Wallet address for the new associated token account
+    ///   3. `[]` The token mint for the new associated token account
+    ///   4. `[]` System program
+    ///   5. `[]` SPL Token program
+    ///   6. `[]` Rent sysvar
+    Create,
+    /// Creates an associated token account for the given wallet address and
+    /// token mint, if it does not exist yet.  Succeeds without doing anything
+    /// if the account already exists with the given wallet as owner and the
+    /// given mint.
+    ///
+    ///   0. `[writeable,signer]` Funding account (must be a system account)
+    ///   1. `[writeable]` Associated token account address to be created
+    ///   2. `[]` Wallet address for the new associated token account
+    ///   3. `[]` The token mint for the new associated token account
+    ///   4. `[]` System program
+    ///   5. `[]` SPL Token program
+    ///   6. `[]` Rent sysvar
+    CreateIdempotent,
+    /// Transfers all tokens from an associated token account owned by another
+    /// associated token account, to the wallet's associated token account for
+    /// the same mint, and closes it.  Tokens sent to the associated token
+    /// account of an associated token account address are otherwise stranded,
+    /// since nobody can sign for the owner.
+    ///
+    ///   0. `[writeable]` Nested associated token account, owned by account 3
+    ///   1. `[]` Token mint for the nested associated token account
+    ///   2. `[writeable]` Wallet's associated token account for the mint of
+    ///      account 1, receiving the tokens
+    ///   3. `[]` Owner associated token account address, owned by the wallet
+    ///   4. `[]` Token mint for the owner associated token account
+    ///   5. `[writeable,signer]` Wallet address, owner of account 3, receiving
+    ///      the lamports of the closed account
+    ///   6. `[]` SPL Token program
+    RecoverNested,
+}
+
+impl AssociatedTokenAccountInstruction {
+    /// Unpacks a byte buffer into an [AssociatedTokenAccountInstruction]
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        Ok(match input {
+            [] | [0] => Self::Create,
+            [1] => Self::CreateIdempotent,
+            [2] => Self::RecoverNested,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        })
+    }
+
+    /// Packs an [AssociatedTokenAccountInstruction] into a byte buffer
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            Self::Create => vec![0],
+            Self::CreateIdempotent => vec![1],
+            Self::RecoverNested => vec![2],
+        }
+    }
+}
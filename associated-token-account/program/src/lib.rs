@@ -3,13 +3,14 @@
 #![forbid(unsafe_code)]
 
 mod entrypoint;
+pub mod instruction;
 pub mod processor;
 
 // Export current SDK types for downstream users building with a different SDK version
+use instruction::AssociatedTokenAccountInstruction;
 pub use solana_program;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
-    program_pack::Pack,
     pubkey::Pubkey,
     sysvar,
 };
@@ -69,6 +70,46 @@ pub fn create_associated_token_account(
     funding_address: &Pubkey,
     wallet_address: &Pubkey,
     spl_token_mint_address: &Pubkey,
+) -> Instruction {
+    build_create_instruction(
+        funding_address,
+        wallet_address,
+        spl_token_mint_address,
+        vec![],
+    )
+}
+
+/// Create an associated token account for the given wallet address and token
+/// mint, if it does not exist yet
+///
+/// Accounts expected by this instruction:
+///
+///   0. `[writeable,signer]` Funding account (must be a system account)
+///   1. `[writeable]` Associated token account address to be created
+///   2. `[]` Wallet address for the new associated token account
+///   3. `[]` The token mint for the new associated token account
+///   4. `[]` System program
+///   5. `[]` SPL Token program
+///   6. `[]` Rent sysvar
+///
+pub fn create_associated_token_account_idempotent(
+    funding_address: &Pubkey,
+    wallet_address: &Pubkey,
+    spl_token_mint_address: &Pubkey,
+) -> Instruction {
+    build_create_instruction(
+        funding_address,
+        wallet_address,
+        spl_token_mint_address,
+        AssociatedTokenAccountInstruction::CreateIdempotent.pack(),
+    )
+}
+
+fn build_create_instruction(
+    funding_address: &Pubkey,
+    wallet_address: &Pubkey,
+    spl_token_mint_address: &Pubkey,
+    data: Vec<u8>,
 ) -> Instruction {
     let associated_account_address =
         get_associated_token_address(wallet_address, spl_token_mint_address);
@@ -84,6 +125,50 @@ pub fn create_associated_token_account(
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
         ],
-        data: vec![],
+        data,
+    }
+}
+
+/// Recover the tokens of a nested associated token account, owned by the
+/// wallet's associated token account for `owner_token_mint_address`, into the
+/// wallet's associated token account for `nested_token_mint_address`, and close
+/// the nested account
+///
+/// Accounts expected by this instruction:
+///
+///   0. `[writeable]` Nested associated token account, owned by account 3
+///   1. `[]` Token mint for the nested associated token account
+///   2. `[writeable]` Wallet's associated token account for the mint of
+///      account 1, receiving the tokens
+///   3. `[]` Owner associated token account address, owned by the wallet
+///   4. `[]` Token mint for the owner associated token account
+///   5. `[writeable,signer]` Wallet address, owner of account 3, receiving
+///      the lamports of the closed account
+///   6. `[]` SPL Token program
+///
+pub fn recover_nested(
+    wallet_address: &Pubkey,
+    owner_token_mint_address: &Pubkey,
+    nested_token_mint_address: &Pubkey,
+) -> Instruction {
+    let owner_associated_account_address =
+        get_associated_token_address(wallet_address, owner_token_mint_address);
+    let destination_associated_account_address =
+        get_associated_token_address(wallet_address, nested_token_mint_address);
+    let nested_associated_account_address =
+        get_associated_token_address(&owner_associated_account_address, nested_token_mint_address);
+
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(nested_associated_account_address, false),
+            AccountMeta::new_readonly(*nested_token_mint_address, false),
+            AccountMeta::new(destination_associated_account_address, false),
+            AccountMeta::new_readonly(owner_associated_account_address, false),
+            AccountMeta::new_readonly(*owner_token_mint_address, false),
+            AccountMeta::new(*wallet_address, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: AssociatedTokenAccountInstruction::RecoverNested.pack(),
     }
 }
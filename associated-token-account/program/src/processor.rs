@@ -1,6 +1,6 @@
 //! Program state processor
 
-use crate::*;
+use crate::{instruction::AssociatedTokenAccountInstruction, *};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -12,12 +12,36 @@ use solana_program::{
     system_instruction,
     sysvar::Sysvar,
 };
+use spl_token::{
+    extension::StateWithExtensions,
+    state::{Account, Mint},
+};
 
 /// Instruction processor
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    _input: &[u8],
+    input: &[u8],
+) -> ProgramResult {
+    match AssociatedTokenAccountInstruction::unpack(input)? {
+        AssociatedTokenAccountInstruction::Create => {
+            process_create_associated_token_account(program_id, accounts, false)
+        }
+        AssociatedTokenAccountInstruction::CreateIdempotent => {
+            process_create_associated_token_account(program_id, accounts, true)
+        }
+        AssociatedTokenAccountInstruction::RecoverNested => {
+            process_recover_nested(program_id, accounts)
+        }
+    }
+}
+
+/// Processes [Create](enum.AssociatedTokenAccountInstruction.html) and
+/// [CreateIdempotent](enum.AssociatedTokenAccountInstruction.html) instructions
+fn process_create_associated_token_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    idempotent: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -41,6 +65,21 @@ pub fn process_instruction(
         return Err(ProgramError::InvalidSeeds);
     }
 
+    if idempotent && associated_token_account_info.owner == spl_token_program_id {
+        let associated_token_account =
+            StateWithExtensions::<Account>::unpack(&associated_token_account_info.data.borrow())?
+                .base;
+        if associated_token_account.owner != *wallet_account_info.key {
+            msg!("Error: Associated token account owner does not match wallet address");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if associated_token_account.mint != *spl_token_mint_info.key {
+            msg!("Error: Associated token account mint does not match token mint");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        return Ok(());
+    }
+
     let associated_token_account_signer_seeds: &[&[_]] = &[
         &wallet_account_info.key.to_bytes(),
         &spl_token_program_id.to_bytes(),
@@ -48,10 +87,14 @@ pub fn process_instruction(
         &[bump_seed],
     ];
 
+    // Mints with extensions may require a larger token account
+    let account_len =
+        spl_token::extension::get_account_len_for_mint(&spl_token_mint_info.data.borrow())?;
+
     // Fund the associated token account with the minimum balance to be rent exempt
     let rent = &Rent::from_account_info(rent_sysvar_info)?;
     let required_lamports = rent
-        .minimum_balance(spl_token::state::Account::LEN)
+        .minimum_balance(account_len)
         .max(1)
         .saturating_sub(associated_token_account_info.lamports());
 
@@ -76,10 +119,7 @@ pub fn process_instruction(
 
     msg!("Allocate space for the associated token account");
     invoke_signed(
-        &system_instruction::allocate(
-            associated_token_account_info.key,
-            spl_token::state::Account::LEN as u64,
-        ),
+        &system_instruction::allocate(associated_token_account_info.key, account_len as u64),
         &[
             associated_token_account_info.clone(),
             system_program_info.clone(),
@@ -114,3 +154,139 @@ pub fn process_instruction(
         ],
     )
 }
+
+/// Processes [RecoverNested](enum.AssociatedTokenAccountInstruction.html) instructions
+fn process_recover_nested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let nested_associated_token_account_info = next_account_info(account_info_iter)?;
+    let nested_token_mint_info = next_account_info(account_info_iter)?;
+    let destination_associated_token_account_info = next_account_info(account_info_iter)?;
+    let owner_associated_token_account_info = next_account_info(account_info_iter)?;
+    let owner_token_mint_info = next_account_info(account_info_iter)?;
+    let wallet_account_info = next_account_info(account_info_iter)?;
+    let spl_token_program_info = next_account_info(account_info_iter)?;
+    let spl_token_program_id = spl_token_program_info.key;
+
+    let (owner_associated_token_address, bump_seed) =
+        get_associated_token_address_and_bump_seed_internal(
+            wallet_account_info.key,
+            owner_token_mint_info.key,
+            program_id,
+            spl_token_program_id,
+        );
+    if owner_associated_token_address != *owner_associated_token_account_info.key {
+        msg!("Error: Owner associated address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (nested_associated_token_address, _) = get_associated_token_address_and_bump_seed_internal(
+        owner_associated_token_account_info.key,
+        nested_token_mint_info.key,
+        program_id,
+        spl_token_program_id,
+    );
+    if nested_associated_token_address != *nested_associated_token_account_info.key {
+        msg!("Error: Nested associated address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let (destination_associated_token_address, _) =
+        get_associated_token_address_and_bump_seed_internal(
+            wallet_account_info.key,
+            nested_token_mint_info.key,
+            program_id,
+            spl_token_program_id,
+        );
+    if destination_associated_token_address != *destination_associated_token_account_info.key {
+        msg!("Error: Destination associated address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if !wallet_account_info.is_signer {
+        msg!("Error: Wallet of the owner associated token account must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // The owner associated token account must still belong to the wallet
+    if owner_associated_token_account_info.owner != spl_token_program_id {
+        msg!("Error: Owner associated token account not owned by the SPL Token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let owner_associated_token_account =
+        StateWithExtensions::<Account>::unpack(&owner_associated_token_account_info.data.borrow())?
+            .base;
+    if owner_associated_token_account.owner != *wallet_account_info.key {
+        msg!("Error: Owner associated token account owner does not match wallet address");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if nested_associated_token_account_info.owner != spl_token_program_id {
+        msg!("Error: Nested associated token account not owned by the SPL Token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let amount = StateWithExtensions::<Account>::unpack(
+        &nested_associated_token_account_info.data.borrow(),
+    )?
+    .base
+    .amount;
+
+    if nested_token_mint_info.owner != spl_token_program_id {
+        msg!("Error: Nested token mint not owned by the SPL Token program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let decimals = StateWithExtensions::<Mint>::unpack(&nested_token_mint_info.data.borrow())?
+        .base
+        .decimals;
+
+    // The owner associated token account signs as the nested account's owner
+    let owner_associated_token_account_signer_seeds: &[&[_]] = &[
+        &wallet_account_info.key.to_bytes(),
+        &spl_token_program_id.to_bytes(),
+        &owner_token_mint_info.key.to_bytes(),
+        &[bump_seed],
+    ];
+
+    msg!(
+        "Transfer {} tokens to the wallet associated token account",
+        amount
+    );
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            spl_token_program_id,
+            nested_associated_token_account_info.key,
+            nested_token_mint_info.key,
+            destination_associated_token_account_info.key,
+            owner_associated_token_account_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            nested_associated_token_account_info.clone(),
+            nested_token_mint_info.clone(),
+            destination_associated_token_account_info.clone(),
+            owner_associated_token_account_info.clone(),
+            spl_token_program_info.clone(),
+        ],
+        &[owner_associated_token_account_signer_seeds],
+    )?;
+
+    msg!("Close the nested associated token account");
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            spl_token_program_id,
+            nested_associated_token_account_info.key,
+            wallet_account_info.key,
+            owner_associated_token_account_info.key,
+            &[],
+        )?,
+        &[
+            nested_associated_token_account_info.clone(),
+            wallet_account_info.clone(),
+            owner_associated_token_account_info.clone(),
+            spl_token_program_info.clone(),
+        ],
+        &[owner_associated_token_account_signer_seeds],
+    )
+}
@@ -4,7 +4,7 @@
 use solana_program::{instruction::*, program_pack::Pack, pubkey::Pubkey, system_instruction};
 use solana_program_test::*;
 use solana_sdk::{
-    signature::Signer,
+    signature::{Keypair, Signer},
     transaction::{Transaction, TransactionError},
 };
 use spl_associated_token_account::*;
@@ -242,3 +242,180 @@ async fn test_create_account_mismatch() {
         TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
     );
 }
+
+#[tokio::test]
+async fn test_create_associated_token_account_idempotent() {
+    let wallet_address = Pubkey::new_unique();
+    let token_mint_address = Pubkey::new_unique();
+    let associated_token_address =
+        get_associated_token_address(&wallet_address, &token_mint_address);
+
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test(token_mint_address).start().await;
+    let rent = banks_client.get_rent().await.unwrap();
+    let expected_token_account_balance = rent.minimum_balance(spl_token::state::Account::LEN);
+
+    // Creates the associated account if it does not exist yet
+    let mut transaction = Transaction::new_with_payer(
+        &[create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &wallet_address,
+            &token_mint_address,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let associated_account = banks_client
+        .get_account(associated_token_address)
+        .await
+        .expect("get_account")
+        .expect("associated_account not none");
+    assert_eq!(associated_account.owner, spl_token::id());
+    assert_eq!(associated_account.lamports, expected_token_account_balance);
+
+    // Creating it again fails with the non-idempotent instruction
+    let recent_blockhash = banks_client
+        .get_new_blockhash(&recent_blockhash)
+        .await
+        .unwrap()
+        .0;
+    let mut transaction = Transaction::new_with_payer(
+        &[create_associated_token_account(
+            &payer.pubkey(),
+            &wallet_address,
+            &token_mint_address,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+
+    // But succeeds, without changes, with the idempotent one
+    let mut transaction = Transaction::new_with_payer(
+        &[create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &wallet_address,
+            &token_mint_address,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(
+        banks_client
+            .get_account(associated_token_address)
+            .await
+            .expect("get_account")
+            .expect("associated_account not none"),
+        associated_account
+    );
+}
+
+#[tokio::test]
+async fn test_recover_nested() {
+    let wallet = Keypair::new();
+    let mint = Keypair::new();
+    let decimals = 2;
+
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test(Pubkey::new_unique()).start().await;
+    let rent = banks_client.get_rent().await.unwrap();
+
+    let owner_associated_token_address =
+        get_associated_token_address(&wallet.pubkey(), &mint.pubkey());
+    let nested_associated_token_address =
+        get_associated_token_address(&owner_associated_token_address, &mint.pubkey());
+
+    // Create a mint, the wallet's associated token account, and an associated
+    // token account owned by it, holding some tokens
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                decimals,
+            )
+            .unwrap(),
+            create_associated_token_account(&payer.pubkey(), &wallet.pubkey(), &mint.pubkey()),
+            create_associated_token_account(
+                &payer.pubkey(),
+                &owner_associated_token_address,
+                &mint.pubkey(),
+            ),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &nested_associated_token_address,
+                &payer.pubkey(),
+                &[],
+                100,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &mint], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // Only the wallet may recover the tokens
+    let mut instruction = recover_nested(&wallet.pubkey(), &mint.pubkey(), &mint.pubkey());
+    instruction.accounts[5] = AccountMeta::new(wallet.pubkey(), false);
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[recover_nested(
+            &wallet.pubkey(),
+            &mint.pubkey(),
+            &mint.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &wallet], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // The tokens are back in the wallet's associated token account, and the
+    // nested account is closed
+    let owner_associated_account = banks_client
+        .get_account(owner_associated_token_address)
+        .await
+        .expect("get_account")
+        .expect("owner_associated_account not none");
+    assert_eq!(
+        spl_token::state::Account::unpack(&owner_associated_account.data)
+            .unwrap()
+            .amount,
+        100
+    );
+    assert_eq!(
+        banks_client
+            .get_account(nested_associated_token_address)
+            .await
+            .expect("get_account"),
+        None,
+    );
+    assert_eq!(
+        banks_client.get_balance(wallet.pubkey()).await.unwrap(),
+        rent.minimum_balance(spl_token::state::Account::LEN)
+    );
+}
@@ -26,6 +26,7 @@ use {
         self,
         instruction::{init_lending_market, init_reserve},
         math::WAD,
+        oracle::OracleSource,
         state::{LendingMarket, Reserve, ReserveConfig, ReserveFees},
     },
     std::{borrow::Borrow, process::exit, str::FromStr},
@@ -195,7 +196,8 @@ fn main() {
                         .validator(is_pubkey)
                         .value_name("PUBKEY")
                         .takes_value(true)
-                        .required(true)
+                        .required_unless_one(&["token_swap", "fixed_price"])
+                        .requires("pyth_price")
                         .help("Pyth product account: https://pyth.network/developers/consumers/accounts"),
                 )
                 .arg(
@@ -204,9 +206,35 @@ fn main() {
                         .validator(is_pubkey)
                         .value_name("PUBKEY")
                         .takes_value(true)
-                        .required(true)
+                        .requires("pyth_product")
                         .help("Pyth price account: https://pyth.network/developers/consumers/accounts"),
                 )
+                .arg(
+                    Arg::with_name("token_swap")
+                        .long("token-swap")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .conflicts_with_all(&["pyth_product", "fixed_price"])
+                        .requires("quote_mint")
+                        .help("Token swap pool to price the liquidity by its time-weighted average price, instead of Pyth"),
+                )
+                .arg(
+                    Arg::with_name("quote_mint")
+                        .long("quote-mint")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .requires("token_swap")
+                        .help("Mint of the other token of the token swap pool, worth one unit of the quote currency"),
+                )
+                .arg(
+                    Arg::with_name("fixed_price")
+                        .long("fixed-price")
+                        .takes_value(false)
+                        .conflicts_with("pyth_product")
+                        .help("Price the liquidity at one unit of the quote currency, instead of using Pyth"),
+                )
                 .arg(
                     Arg::with_name("optimal_utilization_rate")
                         .long("optimal-utilization-rate")
@@ -307,6 +335,26 @@ fn main() {
                         .default_value("20")
                         .help("Amount of fee going to host account: [0, 100]"),
                 )
+                .arg(
+                    Arg::with_name("max_confidence_ratio")
+                        .long("max-confidence-ratio")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Maximum oracle confidence interval relative to the price, 0 for no limit: [0, 100]"),
+                )
+                .arg(
+                    Arg::with_name("max_price_deviation")
+                        .long("max-price-deviation")
+                        .validator(is_parsable::<u8>)
+                        .value_name("INTEGER_PERCENT")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0")
+                        .help("Maximum price change between refreshes, 0 for no limit"),
+                )
         )
         .get_matches();
 
@@ -366,8 +414,22 @@ fn main() {
             let lending_market_pubkey = pubkey_of(arg_matches, "lending_market").unwrap();
             let source_liquidity_pubkey = pubkey_of(arg_matches, "source_liquidity").unwrap();
             let ui_amount = value_of(arg_matches, "liquidity_amount").unwrap();
-            let pyth_product_pubkey = pubkey_of(arg_matches, "pyth_product").unwrap();
-            let pyth_price_pubkey = pubkey_of(arg_matches, "pyth_price").unwrap();
+            let (oracle_source, oracle_config_pubkey, oracle_pubkey) =
+                if let Some(token_swap_pubkey) = pubkey_of(arg_matches, "token_swap") {
+                    (
+                        OracleSource::TokenSwap,
+                        pubkey_of(arg_matches, "quote_mint").unwrap(),
+                        token_swap_pubkey,
+                    )
+                } else if arg_matches.is_present("fixed_price") {
+                    (OracleSource::Fixed, Pubkey::default(), Pubkey::default())
+                } else {
+                    (
+                        OracleSource::Pyth,
+                        pubkey_of(arg_matches, "pyth_product").unwrap(),
+                        pubkey_of(arg_matches, "pyth_price").unwrap(),
+                    )
+                };
             let optimal_utilization_rate =
                 value_of(arg_matches, "optimal_utilization_rate").unwrap();
             let loan_to_value_ratio = value_of(arg_matches, "loan_to_value_ratio").unwrap();
@@ -379,6 +441,8 @@ fn main() {
             let borrow_fee = value_of::<f64>(arg_matches, "borrow_fee").unwrap();
             let flash_loan_fee = value_of::<f64>(arg_matches, "flash_loan_fee").unwrap();
             let host_fee_percentage = value_of(arg_matches, "host_fee_percentage").unwrap();
+            let max_confidence_ratio = value_of(arg_matches, "max_confidence_ratio").unwrap();
+            let max_price_deviation = value_of(arg_matches, "max_price_deviation").unwrap();
 
             let borrow_fee_wad = (borrow_fee * WAD as f64) as u64;
             let flash_loan_fee_wad = (flash_loan_fee * WAD as f64) as u64;
@@ -399,13 +463,16 @@ fn main() {
                         flash_loan_fee_wad,
                         host_fee_percentage,
                     },
+                    max_confidence_ratio,
+                    max_price_deviation,
                 },
                 source_liquidity_pubkey,
                 source_liquidity_owner_keypair,
                 lending_market_pubkey,
                 lending_market_owner_keypair,
-                pyth_product_pubkey,
-                pyth_price_pubkey,
+                oracle_source,
+                oracle_config_pubkey,
+                oracle_pubkey,
             )
         }
         _ => unreachable!(),
@@ -478,8 +545,9 @@ fn command_add_reserve(
     source_liquidity_owner_keypair: Keypair,
     lending_market_pubkey: Pubkey,
     lending_market_owner_keypair: Keypair,
-    pyth_product_pubkey: Pubkey,
-    pyth_price_pubkey: Pubkey,
+    oracle_source: OracleSource,
+    oracle_config_pubkey: Pubkey,
+    oracle_pubkey: Pubkey,
 ) -> CommandResult {
     let source_liquidity_account = config.rpc_client.get_account(&source_liquidity_pubkey)?;
     let source_liquidity = Token::unpack_from_slice(source_liquidity_account.data.borrow())?;
@@ -615,6 +683,7 @@ fn command_add_reserve(
                 config.lending_program_id,
                 liquidity_amount,
                 reserve_config,
+                oracle_source,
                 source_liquidity_pubkey,
                 user_collateral_keypair.pubkey(),
                 reserve_keypair.pubkey(),
@@ -623,8 +692,8 @@ fn command_add_reserve(
                 liquidity_fee_receiver_keypair.pubkey(),
                 collateral_mint_keypair.pubkey(),
                 collateral_supply_keypair.pubkey(),
-                pyth_product_pubkey,
-                pyth_price_pubkey,
+                oracle_config_pubkey,
+                oracle_pubkey,
                 lending_market_pubkey,
                 lending_market_owner_keypair.pubkey(),
                 user_transfer_authority_keypair.pubkey(),
@@ -10,4 +10,5 @@ export * from './redeemReserveCollateral';
 export * from './refreshObligation';
 export * from './refreshReserve';
 export * from './repayObligationLiquidity';
+export * from './updateReserveConfig';
 export * from './withdrawObligationCollateral';
@@ -2,7 +2,7 @@ import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import { PublicKey, SYSVAR_CLOCK_PUBKEY, SYSVAR_RENT_PUBKEY, TransactionInstruction } from '@solana/web3.js';
 import { struct, u8 } from 'buffer-layout';
 import { LENDING_PROGRAM_ID } from '../constants';
-import { ReserveConfig, ReserveConfigLayout } from '../state';
+import { OracleSource, ReserveConfig, ReserveConfigLayout } from '../state';
 import { u64 } from '../util';
 import { LendingInstruction } from './instruction';
 
@@ -10,21 +10,28 @@ interface Data {
     instruction: number;
     liquidityAmount: bigint;
     config: ReserveConfig;
+    oracleSource: OracleSource;
 }
 
-const DataLayout = struct<Data>([u8('instruction'), u64('liquidityAmount'), ReserveConfigLayout]);
+const DataLayout = struct<Data>([
+    u8('instruction'),
+    u64('liquidityAmount'),
+    ReserveConfigLayout,
+    u8('oracleSource'),
+]);
 
 export const initReserveInstruction = (
     liquidityAmount: number | bigint,
     config: ReserveConfig,
+    oracleSource: OracleSource,
     sourceLiquidity: PublicKey,
     destinationCollateral: PublicKey,
     reserve: PublicKey,
     liquidityMint: PublicKey,
     liquiditySupply: PublicKey,
     liquidityFeeReceiver: PublicKey,
-    pythProduct: PublicKey,
-    pythPrice: PublicKey,
+    oracleConfig: PublicKey,
+    oracle: PublicKey,
     collateralMint: PublicKey,
     collateralSupply: PublicKey,
     lendingMarket: PublicKey,
@@ -38,6 +45,7 @@ export const initReserveInstruction = (
             instruction: LendingInstruction.InitReserve,
             liquidityAmount: BigInt(liquidityAmount),
             config,
+            oracleSource,
         },
         data
     );
@@ -51,8 +59,8 @@ export const initReserveInstruction = (
         { pubkey: liquidityFeeReceiver, isSigner: false, isWritable: true },
         { pubkey: collateralMint, isSigner: false, isWritable: true },
         { pubkey: collateralSupply, isSigner: false, isWritable: true },
-        { pubkey: pythProduct, isSigner: false, isWritable: false },
-        { pubkey: pythPrice, isSigner: false, isWritable: false },
+        { pubkey: oracleConfig, isSigner: false, isWritable: false },
+        { pubkey: oracle, isSigner: false, isWritable: false },
         { pubkey: lendingMarket, isSigner: false, isWritable: true },
         { pubkey: lendingMarketAuthority, isSigner: false, isWritable: false },
         { pubkey: lendingMarketOwner, isSigner: true, isWritable: false },
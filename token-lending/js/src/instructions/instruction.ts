@@ -14,4 +14,5 @@ export enum LendingInstruction {
     RepayObligationLiquidity = 11,
     LiquidateObligation = 12,
     FlashLoan = 13,
+    UpdateReserveConfig = 14,
 }
@@ -1,7 +1,7 @@
 import { AccountInfo, PublicKey } from '@solana/web3.js';
 import BigNumber from 'bignumber.js';
 import { blob, struct, u8 } from 'buffer-layout';
-import { decimal, Parser, publicKey, u64 } from '../util';
+import { bool, decimal, Parser, publicKey, u128, u64 } from '../util';
 import { LastUpdate, LastUpdateLayout } from './lastUpdate';
 
 export interface Reserve {
@@ -11,6 +11,7 @@ export interface Reserve {
     liquidity: ReserveLiquidity;
     collateral: ReserveCollateral;
     config: ReserveConfig;
+    oracle: ReserveOracle;
 }
 
 export interface ReserveLiquidity {
@@ -40,6 +41,8 @@ export interface ReserveConfig {
     optimalBorrowRate: number;
     maxBorrowRate: number;
     fees: ReserveFees;
+    maxConfidenceRatio: number;
+    maxPriceDeviation: number;
 }
 
 export interface ReserveFees {
@@ -48,6 +51,26 @@ export interface ReserveFees {
     hostFeePercentage: number;
 }
 
+export enum OracleSource {
+    Pyth = 0,
+    TokenSwap = 1,
+    Fixed = 2,
+}
+
+export interface ReserveOracle {
+    source: OracleSource;
+    tokenSwapIsTokenA: boolean;
+    tokenSwapQuoteDecimals: number;
+    tokenSwapObservation: PriceAccumulator;
+    marketPriceConfidence: BigNumber;
+}
+
+export interface PriceAccumulator {
+    lastUpdateTimestamp: bigint;
+    tokenAPriceCumulative: bigint;
+    tokenBPriceCumulative: bigint;
+}
+
 /** @internal */
 export const ReserveLiquidityLayout = struct<ReserveLiquidity>(
     [
@@ -87,10 +110,30 @@ export const ReserveConfigLayout = struct<ReserveConfig>(
         u8('optimalBorrowRate'),
         u8('maxBorrowRate'),
         ReserveFeesLayout,
+        u8('maxConfidenceRatio'),
+        u8('maxPriceDeviation'),
     ],
     'config'
 );
 
+/** @internal */
+export const PriceAccumulatorLayout = struct<PriceAccumulator>(
+    [u64('lastUpdateTimestamp'), u128('tokenAPriceCumulative'), u128('tokenBPriceCumulative')],
+    'tokenSwapObservation'
+);
+
+/** @internal */
+export const ReserveOracleLayout = struct<ReserveOracle>(
+    [
+        u8('source'),
+        bool('tokenSwapIsTokenA'),
+        u8('tokenSwapQuoteDecimals'),
+        PriceAccumulatorLayout,
+        decimal('marketPriceConfidence'),
+    ],
+    'oracle'
+);
+
 /** @internal */
 export const ReserveLayout = struct<Reserve>([
     u8('version'),
@@ -99,7 +142,8 @@ export const ReserveLayout = struct<Reserve>([
     ReserveLiquidityLayout,
     ReserveCollateralLayout,
     ReserveConfigLayout,
-    blob(248, 'padding'),
+    ReserveOracleLayout,
+    blob(187, 'padding'),
 ]);
 
 export const RESERVE_SIZE = ReserveLayout.span;
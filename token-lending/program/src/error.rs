@@ -160,6 +160,12 @@ pub enum LendingError {
     #[error("Not enough liquidity after flash loan")]
     NotEnoughLiquidityAfterFlashLoan,
     // 45
+    /// Oracle price confidence interval is too wide
+    #[error("Oracle price confidence interval is too wide")]
+    OraclePriceConfidenceTooWide,
+    /// Oracle price moved too far since the last update
+    #[error("Oracle price deviation since the last update is too large")]
+    OraclePriceDeviationTooLarge,
 }
 
 impl From<LendingError> for ProgramError {
@@ -2,8 +2,10 @@
 
 use crate::{
     error::LendingError,
+    oracle::OracleSource,
     state::{ReserveConfig, ReserveFees},
 };
+use num_traits::FromPrimitive;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     msg,
@@ -59,8 +61,12 @@ pub enum LendingInstruction {
     ///   5. `[writable]` Reserve liquidity fee receiver - uninitialized.
     ///   6. `[writable]` Reserve collateral SPL Token mint - uninitialized.
     ///   7. `[writable]` Reserve collateral token supply - uninitialized.
-    ///   8. `[]` Pyth product account.
-    ///   9. `[]` Pyth price account.
+    ///   8. `[]` Oracle config account, depending on the oracle source:
+    ///             Pyth product account, token swap quote token mint, or the default pubkey
+    ///             for a fixed price.
+    ///   9. `[]` Oracle account, depending on the oracle source:
+    ///             Pyth price account, token swap account, or the default pubkey for a fixed
+    ///             price.
     ///             This will be used as the reserve liquidity oracle account.
     ///   10 `[]` Lending market account.
     ///   11 `[]` Derived lending market authority.
@@ -74,6 +80,8 @@ pub enum LendingInstruction {
         liquidity_amount: u64,
         /// Reserve configuration values
         config: ReserveConfig,
+        /// Source of the reserve liquidity market price
+        oracle_source: OracleSource,
     },
 
     // 3
@@ -83,7 +91,8 @@ pub enum LendingInstruction {
     ///
     ///   0. `[writable]` Reserve account.
     ///   1. `[]` Reserve liquidity oracle account.
-    ///             Must be the Pyth price account specified at InitReserve.
+    ///             Must be the oracle account specified at InitReserve,
+    ///             or the default pubkey for a fixed price.
     ///   2. `[]` Clock sysvar.
     RefreshReserve,
 
@@ -308,6 +317,22 @@ pub enum LendingInstruction {
         /// The amount that is to be borrowed - u64::MAX for up to 100% of available liquidity
         amount: u64,
     },
+
+    // 14
+    /// Update the configuration of a reserve.  The reserve must be refreshed again before use.
+    ///
+    /// This lets the lending market owner accept a market price that moved by more than the
+    /// maximum price deviation, by lifting the limit for a refresh.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` Reserve account.
+    ///   1. `[]` Lending market account.
+    ///   2. `[signer]` Lending market owner.
+    UpdateReserveConfig {
+        /// Reserve configuration values
+        config: ReserveConfig,
+    },
 }
 
 impl LendingInstruction {
@@ -331,32 +356,14 @@ impl LendingInstruction {
             }
             2 => {
                 let (liquidity_amount, rest) = Self::unpack_u64(rest)?;
-                let (optimal_utilization_rate, rest) = Self::unpack_u8(rest)?;
-                let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
-                let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
-                let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
-                let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
-                let (host_fee_percentage, _rest) = Self::unpack_u8(rest)?;
+                let (config, rest) = Self::unpack_reserve_config(rest)?;
+                let (oracle_source, _rest) = Self::unpack_u8(rest)?;
+                let oracle_source = OracleSource::from_u8(oracle_source)
+                    .ok_or(LendingError::InstructionUnpackError)?;
                 Self::InitReserve {
                     liquidity_amount,
-                    config: ReserveConfig {
-                        optimal_utilization_rate,
-                        loan_to_value_ratio,
-                        liquidation_bonus,
-                        liquidation_threshold,
-                        min_borrow_rate,
-                        optimal_borrow_rate,
-                        max_borrow_rate,
-                        fees: ReserveFees {
-                            borrow_fee_wad,
-                            flash_loan_fee_wad,
-                            host_fee_percentage,
-                        },
-                    },
+                    config,
+                    oracle_source,
                 }
             }
             3 => Self::RefreshReserve,
@@ -394,6 +401,10 @@ impl LendingInstruction {
                 let (amount, _rest) = Self::unpack_u64(rest)?;
                 Self::FlashLoan { amount }
             }
+            14 => {
+                let (config, _rest) = Self::unpack_reserve_config(rest)?;
+                Self::UpdateReserveConfig { config }
+            }
             _ => {
                 msg!("Instruction cannot be unpacked");
                 return Err(LendingError::InstructionUnpackError.into());
@@ -401,6 +412,40 @@ impl LendingInstruction {
         })
     }
 
+    fn unpack_reserve_config(input: &[u8]) -> Result<(ReserveConfig, &[u8]), ProgramError> {
+        let (optimal_utilization_rate, rest) = Self::unpack_u8(input)?;
+        let (loan_to_value_ratio, rest) = Self::unpack_u8(rest)?;
+        let (liquidation_bonus, rest) = Self::unpack_u8(rest)?;
+        let (liquidation_threshold, rest) = Self::unpack_u8(rest)?;
+        let (min_borrow_rate, rest) = Self::unpack_u8(rest)?;
+        let (optimal_borrow_rate, rest) = Self::unpack_u8(rest)?;
+        let (max_borrow_rate, rest) = Self::unpack_u8(rest)?;
+        let (borrow_fee_wad, rest) = Self::unpack_u64(rest)?;
+        let (flash_loan_fee_wad, rest) = Self::unpack_u64(rest)?;
+        let (host_fee_percentage, rest) = Self::unpack_u8(rest)?;
+        let (max_confidence_ratio, rest) = Self::unpack_u8(rest)?;
+        let (max_price_deviation, rest) = Self::unpack_u8(rest)?;
+        Ok((
+            ReserveConfig {
+                optimal_utilization_rate,
+                loan_to_value_ratio,
+                liquidation_bonus,
+                liquidation_threshold,
+                min_borrow_rate,
+                optimal_borrow_rate,
+                max_borrow_rate,
+                fees: ReserveFees {
+                    borrow_fee_wad,
+                    flash_loan_fee_wad,
+                    host_fee_percentage,
+                },
+                max_confidence_ratio,
+                max_price_deviation,
+            },
+            rest,
+        ))
+    }
+
     fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
         if input.len() < 8 {
             msg!("u64 cannot be unpacked");
@@ -471,35 +516,13 @@ impl LendingInstruction {
             }
             Self::InitReserve {
                 liquidity_amount,
-                config:
-                    ReserveConfig {
-                        optimal_utilization_rate,
-                        loan_to_value_ratio,
-                        liquidation_bonus,
-                        liquidation_threshold,
-                        min_borrow_rate,
-                        optimal_borrow_rate,
-                        max_borrow_rate,
-                        fees:
-                            ReserveFees {
-                                borrow_fee_wad,
-                                flash_loan_fee_wad,
-                                host_fee_percentage,
-                            },
-                    },
+                config,
+                oracle_source,
             } => {
                 buf.push(2);
                 buf.extend_from_slice(&liquidity_amount.to_le_bytes());
-                buf.extend_from_slice(&optimal_utilization_rate.to_le_bytes());
-                buf.extend_from_slice(&loan_to_value_ratio.to_le_bytes());
-                buf.extend_from_slice(&liquidation_bonus.to_le_bytes());
-                buf.extend_from_slice(&liquidation_threshold.to_le_bytes());
-                buf.extend_from_slice(&min_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&optimal_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&max_borrow_rate.to_le_bytes());
-                buf.extend_from_slice(&borrow_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&flash_loan_fee_wad.to_le_bytes());
-                buf.extend_from_slice(&host_fee_percentage.to_le_bytes());
+                Self::pack_reserve_config(&config, &mut buf);
+                buf.push(oracle_source as u8);
             }
             Self::RefreshReserve => {
                 buf.push(3);
@@ -542,9 +565,45 @@ impl LendingInstruction {
                 buf.push(13);
                 buf.extend_from_slice(&amount.to_le_bytes());
             }
+            Self::UpdateReserveConfig { config } => {
+                buf.push(14);
+                Self::pack_reserve_config(&config, &mut buf);
+            }
         }
         buf
     }
+
+    fn pack_reserve_config(config: &ReserveConfig, buf: &mut Vec<u8>) {
+        let ReserveConfig {
+            optimal_utilization_rate,
+            loan_to_value_ratio,
+            liquidation_bonus,
+            liquidation_threshold,
+            min_borrow_rate,
+            optimal_borrow_rate,
+            max_borrow_rate,
+            fees:
+                ReserveFees {
+                    borrow_fee_wad,
+                    flash_loan_fee_wad,
+                    host_fee_percentage,
+                },
+            max_confidence_ratio,
+            max_price_deviation,
+        } = *config;
+        buf.extend_from_slice(&optimal_utilization_rate.to_le_bytes());
+        buf.extend_from_slice(&loan_to_value_ratio.to_le_bytes());
+        buf.extend_from_slice(&liquidation_bonus.to_le_bytes());
+        buf.extend_from_slice(&liquidation_threshold.to_le_bytes());
+        buf.extend_from_slice(&min_borrow_rate.to_le_bytes());
+        buf.extend_from_slice(&optimal_borrow_rate.to_le_bytes());
+        buf.extend_from_slice(&max_borrow_rate.to_le_bytes());
+        buf.extend_from_slice(&borrow_fee_wad.to_le_bytes());
+        buf.extend_from_slice(&flash_loan_fee_wad.to_le_bytes());
+        buf.extend_from_slice(&host_fee_percentage.to_le_bytes());
+        buf.extend_from_slice(&max_confidence_ratio.to_le_bytes());
+        buf.extend_from_slice(&max_price_deviation.to_le_bytes());
+    }
 }
 
 /// Creates an 'InitLendingMarket' instruction.
@@ -594,6 +653,7 @@ pub fn init_reserve(
     program_id: Pubkey,
     liquidity_amount: u64,
     config: ReserveConfig,
+    oracle_source: OracleSource,
     source_liquidity_pubkey: Pubkey,
     destination_collateral_pubkey: Pubkey,
     reserve_pubkey: Pubkey,
@@ -602,8 +662,8 @@ pub fn init_reserve(
     reserve_liquidity_fee_receiver_pubkey: Pubkey,
     reserve_collateral_mint_pubkey: Pubkey,
     reserve_collateral_supply_pubkey: Pubkey,
-    pyth_product_pubkey: Pubkey,
-    pyth_price_pubkey: Pubkey,
+    oracle_config_pubkey: Pubkey,
+    oracle_pubkey: Pubkey,
     lending_market_pubkey: Pubkey,
     lending_market_owner_pubkey: Pubkey,
     user_transfer_authority_pubkey: Pubkey,
@@ -621,8 +681,8 @@ pub fn init_reserve(
         AccountMeta::new(reserve_liquidity_fee_receiver_pubkey, false),
         AccountMeta::new(reserve_collateral_mint_pubkey, false),
         AccountMeta::new(reserve_collateral_supply_pubkey, false),
-        AccountMeta::new_readonly(pyth_product_pubkey, false),
-        AccountMeta::new_readonly(pyth_price_pubkey, false),
+        AccountMeta::new_readonly(oracle_config_pubkey, false),
+        AccountMeta::new_readonly(oracle_pubkey, false),
         AccountMeta::new_readonly(lending_market_pubkey, false),
         AccountMeta::new_readonly(lending_market_authority_pubkey, false),
         AccountMeta::new_readonly(lending_market_owner_pubkey, true),
@@ -637,6 +697,7 @@ pub fn init_reserve(
         data: LendingInstruction::InitReserve {
             liquidity_amount,
             config,
+            oracle_source,
         }
         .pack(),
     }
@@ -981,3 +1042,22 @@ pub fn flash_loan(
         data: LendingInstruction::FlashLoan { amount }.pack(),
     }
 }
+
+/// Creates an `UpdateReserveConfig` instruction
+pub fn update_reserve_config(
+    program_id: Pubkey,
+    config: ReserveConfig,
+    reserve_pubkey: Pubkey,
+    lending_market_pubkey: Pubkey,
+    lending_market_owner_pubkey: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(reserve_pubkey, false),
+            AccountMeta::new_readonly(lending_market_pubkey, false),
+            AccountMeta::new_readonly(lending_market_owner_pubkey, true),
+        ],
+        data: LendingInstruction::UpdateReserveConfig { config }.pack(),
+    }
+}
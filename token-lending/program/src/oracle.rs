@@ -0,0 +1,1089 @@
+//! Sources of reserve liquidity market prices
+//!
+//! A reserve is priced by one of several oracles, recorded as its
+//! `OracleSource`.  Each source has an `OracleAdapter` that validates its
+//! accounts when the reserve is initialized, and reads the market price when
+//! the reserve is refreshed, applying its own staleness and confidence rules.
+//! Besides its oracle account, an adapter may read other accounts on refresh,
+//! and fails if it is given accounts it does not expect.
+
+use crate::{
+    error::LendingError,
+    math::{Decimal, TryDiv, TryMul, TrySub},
+    pyth,
+    state::{LendingMarket, ReserveConfig, ReserveLiquidity},
+};
+use num_derive::FromPrimitive;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, UnixTimestamp},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_math::precise_number::{PreciseNumber, ONE};
+use spl_token::state::Mint;
+use spl_token_swap::{
+    curve::calculator::CurveCalculator, oracle::PriceAccumulator, state::SwapVersion,
+};
+use std::convert::TryInto;
+
+/// Source of the market price of a reserve's liquidity
+#[derive(Clone, Copy, Debug, PartialEq, FromPrimitive)]
+pub enum OracleSource {
+    /// Pyth price account, validated against its product account
+    Pyth,
+    /// Time-weighted average price of a token swap pool between the reserve
+    /// liquidity and a token denominated in the quote currency
+    TokenSwap,
+    /// Fixed price of one unit of quote currency, for assets pegged to it
+    Fixed,
+}
+
+impl Default for OracleSource {
+    fn default() -> Self {
+        Self::Pyth
+    }
+}
+
+impl OracleSource {
+    /// Adapter reading prices from this source
+    pub fn adapter(&self) -> &'static dyn OracleAdapter {
+        match self {
+            Self::Pyth => &PythOracle,
+            Self::TokenSwap => &TokenSwapOracle,
+            Self::Fixed => &FixedPriceOracle,
+        }
+    }
+}
+
+/// Adapter between an oracle and the market price of a reserve's liquidity
+pub trait OracleAdapter {
+    /// Validate the oracle accounts of a new reserve, and record the oracle
+    /// and its initial market price in the reserve liquidity.
+    ///
+    /// `oracle_config_info` is the account describing the oracle, and
+    /// `oracle_info` the account read on every refresh.
+    fn init(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        config: &ReserveConfig,
+        oracle_config_info: &AccountInfo,
+        oracle_info: &AccountInfo,
+        lending_market: &LendingMarket,
+        clock: &Clock,
+    ) -> ProgramResult;
+
+    /// Update the market price of the reserve liquidity from its oracle
+    /// account, and the other accounts the oracle reads, failing if the price
+    /// is stale or not trustworthy enough for the reserve config
+    fn refresh(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        config: &ReserveConfig,
+        oracle_info: &AccountInfo,
+        other_oracle_infos: &[AccountInfo],
+        clock: &Clock,
+    ) -> ProgramResult;
+}
+
+fn check_no_other_oracle_accounts(other_oracle_infos: &[AccountInfo]) -> ProgramResult {
+    if !other_oracle_infos.is_empty() {
+        msg!("Oracle does not read any account besides the oracle account");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+    Ok(())
+}
+
+/// Prices from a Pyth price account.
+///
+/// The product account is given at initialization to check that the price is
+/// quoted in the lending market quote currency.  Prices are rejected when they
+/// were not published in the last few slots, when the price is not currently
+/// trading, or when the confidence interval is wider than the maximum ratio of
+/// the reserve config.  The confidence interval is recorded along the price.
+pub struct PythOracle;
+
+impl PythOracle {
+    const STALE_AFTER_SLOTS_ELAPSED: u64 = 5;
+}
+
+impl OracleAdapter for PythOracle {
+    fn init(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        config: &ReserveConfig,
+        pyth_product_info: &AccountInfo,
+        pyth_price_info: &AccountInfo,
+        lending_market: &LendingMarket,
+        clock: &Clock,
+    ) -> ProgramResult {
+        if &lending_market.oracle_program_id != pyth_product_info.owner {
+            msg!("Pyth product account provided is not owned by the lending market oracle program");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if &lending_market.oracle_program_id != pyth_price_info.owner {
+            msg!("Pyth price account provided is not owned by the lending market oracle program");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        let pyth_product_data = pyth_product_info.try_borrow_data()?;
+        let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if pyth_product.magic != pyth::MAGIC {
+            msg!("Pyth product account provided is not a valid Pyth account");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if pyth_product.ver != pyth::VERSION_2 {
+            msg!("Pyth product account provided has a different version than expected");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        if pyth_product.atype != pyth::AccountType::Product as u32 {
+            msg!("Pyth product account provided is not a valid Pyth product account");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
+            .key
+            .as_ref()
+            .try_into()
+            .map_err(|_| LendingError::InvalidAccountInput)?;
+        if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
+            msg!("Pyth product price account does not match the Pyth price provided");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
+        if lending_market.quote_currency != quote_currency {
+            msg!("Lending market quote currency does not match the oracle quote currency");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        liquidity.oracle_pubkey = *pyth_price_info.key;
+        self.refresh(liquidity, config, pyth_price_info, &[], clock)
+    }
+
+    fn refresh(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        config: &ReserveConfig,
+        pyth_price_info: &AccountInfo,
+        other_oracle_infos: &[AccountInfo],
+        clock: &Clock,
+    ) -> ProgramResult {
+        check_no_other_oracle_accounts(other_oracle_infos)?;
+        let (market_price, market_price_confidence) =
+            get_pyth_price(pyth_price_info, config.max_confidence_ratio, clock)?;
+        liquidity.market_price = market_price;
+        liquidity.market_price_confidence = market_price_confidence;
+        Ok(())
+    }
+}
+
+fn get_pyth_product_quote_currency(pyth_product: &pyth::Product) -> Result<[u8; 32], ProgramError> {
+    const LEN: usize = 14;
+    const KEY: &[u8; LEN] = b"quote_currency";
+
+    let mut start = 0;
+    while start < pyth::PROD_ATTR_SIZE {
+        let mut length = pyth_product.attr[start] as usize;
+        start += 1;
+
+        if length == LEN {
+            let mut end = start + length;
+            if end > pyth::PROD_ATTR_SIZE {
+                msg!("Pyth product attribute key length too long");
+                return Err(LendingError::InvalidOracleConfig.into());
+            }
+
+            let key = &pyth_product.attr[start..end];
+            if key == KEY {
+                start += length;
+                length = pyth_product.attr[start] as usize;
+                start += 1;
+
+                end = start + length;
+                if length > 32 || end > pyth::PROD_ATTR_SIZE {
+                    msg!("Pyth product quote currency value too long");
+                    return Err(LendingError::InvalidOracleConfig.into());
+                }
+
+                let mut value = [0u8; 32];
+                value[0..length].copy_from_slice(&pyth_product.attr[start..end]);
+                return Ok(value);
+            }
+        }
+
+        start += length;
+        start += 1 + pyth_product.attr[start] as usize;
+    }
+
+    msg!("Pyth product quote currency not found");
+    Err(LendingError::InvalidOracleConfig.into())
+}
+
+fn get_pyth_price(
+    pyth_price_info: &AccountInfo,
+    max_confidence_ratio: u8,
+    clock: &Clock,
+) -> Result<(Decimal, Decimal), ProgramError> {
+    let pyth_price_data = pyth_price_info.try_borrow_data()?;
+    let pyth_price = pyth::load::<pyth::Price>(&pyth_price_data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if pyth_price.ptype != pyth::PriceType::Price {
+        msg!("Oracle price type is invalid");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    if pyth_price.agg.status != pyth::PriceStatus::Trading {
+        msg!("Oracle price is not trading");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    let slots_elapsed = clock
+        .slot
+        .checked_sub(pyth_price.valid_slot)
+        .ok_or(LendingError::MathOverflow)?;
+    if slots_elapsed >= PythOracle::STALE_AFTER_SLOTS_ELAPSED {
+        msg!("Oracle price is stale");
+        return Err(LendingError::InvalidOracleConfig.into());
+    }
+
+    let price: u64 = pyth_price.agg.price.try_into().map_err(|_| {
+        msg!("Oracle price cannot be negative");
+        LendingError::InvalidOracleConfig
+    })?;
+
+    let confidence = pyth_price.agg.conf;
+    if max_confidence_ratio > 0
+        && confidence as u128 * 100 > price as u128 * max_confidence_ratio as u128
+    {
+        msg!(
+            "Oracle price confidence interval is wider than {}% of the price",
+            max_confidence_ratio
+        );
+        return Err(LendingError::OraclePriceConfidenceTooWide.into());
+    }
+
+    let (market_price, market_price_confidence) = if pyth_price.expo >= 0 {
+        let exponent = pyth_price
+            .expo
+            .try_into()
+            .map_err(|_| LendingError::MathOverflow)?;
+        let zeros = 10u64
+            .checked_pow(exponent)
+            .ok_or(LendingError::MathOverflow)?;
+        (
+            Decimal::from(price).try_mul(zeros)?,
+            Decimal::from(confidence).try_mul(zeros)?,
+        )
+    } else {
+        let exponent = pyth_price
+            .expo
+            .checked_abs()
+            .ok_or(LendingError::MathOverflow)?
+            .try_into()
+            .map_err(|_| LendingError::MathOverflow)?;
+        let decimals = 10u64
+            .checked_pow(exponent)
+            .ok_or(LendingError::MathOverflow)?;
+        (
+            Decimal::from(price).try_div(decimals)?,
+            Decimal::from(confidence).try_div(decimals)?,
+        )
+    };
+
+    Ok((market_price, market_price_confidence))
+}
+
+/// Token swap oracle state of a reserve's liquidity
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenSwapOracleState {
+    /// Whether the reserve liquidity is token A of the swap, rather than B
+    pub is_token_a: bool,
+    /// Decimals of the other token of the swap, the quote token
+    pub quote_decimals: u8,
+    /// Swap price accumulator as of the last market price update
+    pub observation: PriceAccumulator,
+}
+
+/// Time-weighted average prices from a token swap pool.
+///
+/// The swap must pair the reserve liquidity with a quote token, given as the
+/// oracle config account at initialization, whose value is one unit of the
+/// lending market quote currency.  On refresh, the swap's price accumulator is
+/// read as the swap program last recorded it, and is never brought up to the
+/// current time with the balances of the swap's token accounts, since anyone
+/// can send tokens to them.  The accumulator is rejected when it was last
+/// updated more than a window ago, which happens while the pool has no
+/// activity or is empty.  The market price is only updated once the
+/// accumulator has advanced by that window since the last observation, so that
+/// it cannot be moved by trades over a short period.  A new reserve has no
+/// price until the first window has elapsed.
+///
+/// The spread between the spot price given by the balances recorded by the
+/// swap and the average price is recorded as the confidence interval, and the
+/// price is rejected when it is wider than the maximum ratio of the reserve
+/// config.
+pub struct TokenSwapOracle;
+
+impl TokenSwapOracle {
+    const WINDOW_SECONDS: UnixTimestamp = 300;
+
+    fn price_accumulator(swap_info: &AccountInfo) -> Result<PriceAccumulator, ProgramError> {
+        if swap_info.owner != &spl_token_swap::id() {
+            msg!("Token swap account provided is not owned by the token swap program");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        let swap = SwapVersion::unpack(&swap_info.try_borrow_data()?).map_err(|_| {
+            msg!("Token swap account provided is not a valid token swap");
+            LendingError::InvalidOracleConfig
+        })?;
+        swap.price_accumulator().cloned().ok_or_else(|| {
+            msg!("Token swap provided does not record prices");
+            LendingError::InvalidOracleConfig.into()
+        })
+    }
+
+    /// Spot price of token A or B given the swap's recorded balances, as the
+    /// inner value of a `PreciseNumber`
+    fn spot_price(
+        calculator: &dyn CurveCalculator,
+        token_a_balance: u64,
+        token_b_balance: u64,
+        is_token_a: bool,
+    ) -> Option<u128> {
+        let token_a_price =
+            calculator.spot_price(u128::from(token_a_balance), u128::from(token_b_balance))?;
+        let price = if is_token_a {
+            token_a_price
+        } else {
+            PreciseNumber::new(1)?.checked_div(&token_a_price)?
+        };
+        price.value.try_into().ok()
+    }
+
+    /// Market price of one liquidity token in quote currency, from a price
+    /// per raw token unit scaled by the token swap precision
+    fn market_price(
+        price: u128,
+        liquidity_decimals: u8,
+        quote_decimals: u8,
+    ) -> Result<Decimal, ProgramError> {
+        let liquidity_decimals = 10u64
+            .checked_pow(liquidity_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        let quote_decimals = 10u64
+            .checked_pow(quote_decimals as u32)
+            .ok_or(LendingError::MathOverflow)?;
+        Decimal::from(price)
+            .try_mul(liquidity_decimals)?
+            .try_div(ONE as u64)?
+            .try_div(quote_decimals)
+    }
+
+    /// Average price of token A or B over the time between two observations of
+    /// the swap's price accumulator, as the inner value of a `PreciseNumber`
+    fn average_price(
+        older: &PriceAccumulator,
+        newer: &PriceAccumulator,
+        is_token_a: bool,
+    ) -> Option<u128> {
+        let elapsed = newer
+            .last_update_timestamp
+            .checked_sub(older.last_update_timestamp)?;
+        if elapsed <= 0 {
+            return None;
+        }
+        let (older_cumulative, newer_cumulative) = if is_token_a {
+            (
+                older.token_a_price_cumulative,
+                newer.token_a_price_cumulative,
+            )
+        } else {
+            (
+                older.token_b_price_cumulative,
+                newer.token_b_price_cumulative,
+            )
+        };
+        // the cumulative prices wrap around on overflow
+        Some(newer_cumulative.wrapping_sub(older_cumulative) / elapsed as u128)
+    }
+}
+
+impl OracleAdapter for TokenSwapOracle {
+    fn init(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        _config: &ReserveConfig,
+        quote_mint_info: &AccountInfo,
+        swap_info: &AccountInfo,
+        lending_market: &LendingMarket,
+        _clock: &Clock,
+    ) -> ProgramResult {
+        let observation = Self::price_accumulator(swap_info)?;
+        let swap = SwapVersion::unpack(&swap_info.try_borrow_data()?)?;
+        if !swap.is_initialized() {
+            msg!("Token swap provided is not initialized");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        let is_token_a = if swap.token_a_mint() == &liquidity.mint_pubkey
+            && swap.token_b_mint() == quote_mint_info.key
+        {
+            true
+        } else if swap.token_b_mint() == &liquidity.mint_pubkey
+            && swap.token_a_mint() == quote_mint_info.key
+        {
+            false
+        } else {
+            msg!(
+                "Token swap mints do not match the reserve liquidity mint and quote mint provided"
+            );
+            return Err(LendingError::InvalidOracleConfig.into());
+        };
+
+        if quote_mint_info.owner != &lending_market.token_program_id {
+            msg!("Quote token mint is not owned by the lending market token program");
+            return Err(LendingError::InvalidTokenOwner.into());
+        }
+        let quote_mint = Mint::unpack(&quote_mint_info.data.borrow())
+            .map_err(|_| LendingError::InvalidTokenMint)?;
+
+        liquidity.oracle_pubkey = *swap_info.key;
+        liquidity.market_price = Decimal::zero();
+        liquidity.token_swap_oracle = TokenSwapOracleState {
+            is_token_a,
+            quote_decimals: quote_mint.decimals,
+            observation,
+        };
+        Ok(())
+    }
+
+    fn refresh(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        config: &ReserveConfig,
+        swap_info: &AccountInfo,
+        other_oracle_infos: &[AccountInfo],
+        clock: &Clock,
+    ) -> ProgramResult {
+        check_no_other_oracle_accounts(other_oracle_infos)?;
+        let observation = Self::price_accumulator(swap_info)?;
+        let seconds_elapsed = clock
+            .unix_timestamp
+            .saturating_sub(observation.last_update_timestamp);
+        if seconds_elapsed > Self::WINDOW_SECONDS {
+            msg!("Token swap prices were last accumulated too long ago");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        let swap = SwapVersion::unpack(&swap_info.try_borrow_data()?)?;
+        let (token_a_balance, token_b_balance) = swap
+            .token_balances()
+            .ok_or(LendingError::InvalidOracleConfig)?;
+        let calculator = swap.swap_curve().calculator.as_ref();
+
+        let state = &mut liquidity.token_swap_oracle;
+        let window = observation
+            .last_update_timestamp
+            .saturating_sub(state.observation.last_update_timestamp);
+        if window >= Self::WINDOW_SECONDS {
+            let price = Self::average_price(&state.observation, &observation, state.is_token_a)
+                .ok_or(LendingError::MathOverflow)?;
+            liquidity.market_price =
+                Self::market_price(price, liquidity.mint_decimals, state.quote_decimals)?;
+            state.observation = observation;
+        }
+        if liquidity.market_price == Decimal::zero() {
+            msg!("Oracle price is stale");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+
+        let spot_price = Self::spot_price(
+            calculator,
+            token_a_balance,
+            token_b_balance,
+            state.is_token_a,
+        )
+        .ok_or_else(|| {
+            msg!("Token swap has no spot price");
+            LendingError::InvalidOracleConfig
+        })?;
+        let spot_price =
+            Self::market_price(spot_price, liquidity.mint_decimals, state.quote_decimals)?;
+        let confidence = if spot_price > liquidity.market_price {
+            spot_price.try_sub(liquidity.market_price)?
+        } else {
+            liquidity.market_price.try_sub(spot_price)?
+        };
+        if config.max_confidence_ratio > 0
+            && confidence.try_mul(100)?
+                > liquidity
+                    .market_price
+                    .try_mul(config.max_confidence_ratio as u64)?
+        {
+            msg!(
+                "Token swap spot price is further than {}% from the average price",
+                config.max_confidence_ratio
+            );
+            return Err(LendingError::OraclePriceConfidenceTooWide.into());
+        }
+        liquidity.market_price_confidence = confidence;
+        Ok(())
+    }
+}
+
+/// Fixed price of one unit of the lending market quote currency per token.
+///
+/// There is no oracle account: the oracle config and oracle accounts must be
+/// the default pubkey, which the reserve records as its oracle, and the price
+/// is never stale.
+pub struct FixedPriceOracle;
+
+impl OracleAdapter for FixedPriceOracle {
+    fn init(
+        &self,
+        liquidity: &mut ReserveLiquidity,
+        _config: &ReserveConfig,
+        oracle_config_info: &AccountInfo,
+        oracle_info: &AccountInfo,
+        _lending_market: &LendingMarket,
+        _clock: &Clock,
+    ) -> ProgramResult {
+        if oracle_config_info.key != &Pubkey::default() || oracle_info.key != &Pubkey::default() {
+            msg!("Fixed price oracle accounts provided must be the default pubkey");
+            return Err(LendingError::InvalidOracleConfig.into());
+        }
+        liquidity.oracle_pubkey = Pubkey::default();
+        liquidity.market_price = Decimal::one();
+        Ok(())
+    }
+
+    fn refresh(
+        &self,
+        _liquidity: &mut ReserveLiquidity,
+        _config: &ReserveConfig,
+        _oracle_info: &AccountInfo,
+        other_oracle_infos: &[AccountInfo],
+        _clock: &Clock,
+    ) -> ProgramResult {
+        check_no_other_oracle_accounts(other_oracle_infos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::NewReserveLiquidityParams;
+    use spl_token::state::{Account, AccountState};
+    use spl_token_swap::{
+        curve::{
+            base::{CurveType, SwapCurve},
+            constant_product::ConstantProductCurve,
+            fees::Fees,
+        },
+        state::SwapV2,
+    };
+
+    const SWAP_ID: Pubkey = spl_token_swap::id();
+
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                false,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    fn swap_account(
+        token_a_mint: Pubkey,
+        token_b_mint: Pubkey,
+        token_a_balance: u64,
+        token_b_balance: u64,
+        price_accumulator: PriceAccumulator,
+    ) -> TestAccount {
+        let swap = SwapVersion::SwapV2(SwapV2 {
+            is_initialized: true,
+            nonce: 255,
+            token_program_id: spl_token::id(),
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account: Pubkey::new_unique(),
+            fees: Fees::default(),
+            swap_curve: SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(ConstantProductCurve {}),
+            },
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+        });
+        let mut data = vec![0; SwapVersion::V2_LEN];
+        SwapVersion::pack(swap, &mut data).unwrap();
+        TestAccount {
+            key: Pubkey::new_unique(),
+            owner: SWAP_ID,
+            lamports: 0,
+            data,
+        }
+    }
+
+    /// Accumulate the swap's prices from its recorded balances, then record
+    /// new ones, as the swap program does on every change to the balances
+    fn update_swap(
+        swap: &mut TestAccount,
+        token_a_balance: u64,
+        token_b_balance: u64,
+        timestamp: UnixTimestamp,
+    ) {
+        let state = SwapVersion::unpack(&swap.data).unwrap();
+        let (recorded_a_balance, recorded_b_balance) = state.token_balances().unwrap();
+        let mut accumulator = state.price_accumulator().cloned().unwrap();
+        accumulator.update(
+            state.swap_curve().calculator.as_ref(),
+            u128::from(recorded_a_balance),
+            u128::from(recorded_b_balance),
+            timestamp,
+        );
+        SwapVersion::pack_price_accumulator(
+            &accumulator,
+            token_a_balance,
+            token_b_balance,
+            &mut swap.data,
+        )
+        .unwrap();
+    }
+
+    fn swap_token_account(swap: &TestAccount, amount: u64) -> TestAccount {
+        let swap = SwapVersion::unpack(&swap.data).unwrap();
+        let mut data = vec![0; Account::LEN];
+        Account::pack(
+            Account {
+                amount,
+                state: AccountState::Initialized,
+                ..Account::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        TestAccount {
+            key: *swap.token_a_account(),
+            owner: spl_token::id(),
+            lamports: 0,
+            data,
+        }
+    }
+
+    fn mint_account(key: Pubkey, decimals: u8) -> TestAccount {
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                decimals,
+                is_initialized: true,
+                ..Mint::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        TestAccount {
+            key,
+            owner: spl_token::id(),
+            lamports: 0,
+            data,
+        }
+    }
+
+    fn liquidity(mint_pubkey: Pubkey, mint_decimals: u8) -> ReserveLiquidity {
+        ReserveLiquidity::new(NewReserveLiquidityParams {
+            mint_pubkey,
+            mint_decimals,
+            supply_pubkey: Pubkey::new_unique(),
+            fee_receiver: Pubkey::new_unique(),
+            oracle_source: OracleSource::TokenSwap,
+            oracle_pubkey: Pubkey::default(),
+            market_price: Decimal::zero(),
+        })
+    }
+
+    fn lending_market() -> LendingMarket {
+        LendingMarket {
+            token_program_id: spl_token::id(),
+            ..LendingMarket::default()
+        }
+    }
+
+    fn clock(unix_timestamp: UnixTimestamp) -> Clock {
+        Clock {
+            unix_timestamp,
+            ..Clock::default()
+        }
+    }
+
+    #[test]
+    fn existing_reserves_use_pyth() {
+        assert_eq!(OracleSource::default(), OracleSource::Pyth);
+        assert_eq!(OracleSource::Pyth as u8, 0);
+    }
+
+    #[test]
+    fn token_swap_price() {
+        let liquidity_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let mut liquidity = liquidity(liquidity_mint, 9);
+        let mut quote_mint_account = mint_account(quote_mint, 6);
+        // 20 quote tokens of 6 decimals per liquidity token of 9 decimals
+        let mut swap = swap_account(
+            quote_mint,
+            liquidity_mint,
+            20_000_000,
+            1_000_000_000,
+            PriceAccumulator::new(1_000),
+        );
+
+        TokenSwapOracle
+            .init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &quote_mint_account.info(),
+                &swap.info(),
+                &lending_market(),
+                &clock(1_000),
+            )
+            .unwrap();
+        assert_eq!(liquidity.oracle_pubkey, swap.key);
+        assert!(!liquidity.token_swap_oracle.is_token_a);
+        assert_eq!(liquidity.token_swap_oracle.quote_decimals, 6);
+        assert_eq!(liquidity.market_price, Decimal::zero());
+
+        // no price until the swap's accumulator has advanced by a full window
+        assert_eq!(
+            TokenSwapOracle.refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_100)
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+        update_swap(&mut swap, 20_000_000, 1_000_000_000, 1_300);
+        TokenSwapOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_400),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(20u64));
+        assert_eq!(liquidity.market_price_confidence, Decimal::zero());
+        // the accumulator is observed as recorded, not up to the current time
+        let mut observation = PriceAccumulator::new(1_000);
+        observation.update(&ConstantProductCurve {}, 20_000_000, 1_000_000_000, 1_300);
+        assert_eq!(liquidity.token_swap_oracle.observation, observation);
+
+        // the price is kept within the window
+        update_swap(&mut swap, 20_000_000, 1_000_000_000, 1_500);
+        TokenSwapOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_550),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(20u64));
+        assert_eq!(liquidity.token_swap_oracle.observation, observation);
+
+        // the accumulator is rejected once older than the window, while the
+        // pool has no activity
+        assert_eq!(
+            TokenSwapOracle.refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_500 + TokenSwapOracle::WINDOW_SECONDS + 1)
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+
+        // or while the pool is empty, since nothing is accumulated
+        update_swap(&mut swap, 0, 1_000_000_000, 1_700);
+        update_swap(&mut swap, 0, 1_000_000_000, 2_100);
+        assert_eq!(
+            TokenSwapOracle.refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(2_100)
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+    }
+
+    #[test]
+    fn token_swap_price_ignores_donations() {
+        let liquidity_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let mut liquidity = liquidity(liquidity_mint, 9);
+        let mut quote_mint_account = mint_account(quote_mint, 6);
+        let mut swap = swap_account(
+            quote_mint,
+            liquidity_mint,
+            20_000_000,
+            1_000_000_000,
+            PriceAccumulator::new(1_000),
+        );
+        TokenSwapOracle
+            .init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &quote_mint_account.info(),
+                &swap.info(),
+                &lending_market(),
+                &clock(1_000),
+            )
+            .unwrap();
+        update_swap(&mut swap, 20_000_000, 1_000_000_000, 1_300);
+
+        // 20 more quote tokens sent straight to the swap, doubling the price
+        // given by its token account balances, don't move the price
+        let mut donated_token_a = swap_token_account(&swap, 40_000_000);
+        assert_eq!(
+            TokenSwapOracle.refresh(
+                &mut liquidity.clone(),
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[donated_token_a.info()],
+                &clock(1_400)
+            ),
+            Err(LendingError::InvalidAccountInput.into())
+        );
+        TokenSwapOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_400),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(20u64));
+        assert_eq!(liquidity.market_price_confidence, Decimal::zero());
+
+        // until the next instruction records them, and only from then on
+        update_swap(&mut swap, 40_000_000, 1_000_000_000, 1_450);
+        update_swap(&mut swap, 40_000_000, 1_000_000_000, 1_600);
+        TokenSwapOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_600),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(30u64));
+        assert_eq!(liquidity.market_price_confidence, Decimal::from(10u64));
+    }
+
+    #[test]
+    fn token_swap_price_confidence() {
+        let liquidity_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let mut liquidity = liquidity(liquidity_mint, 9);
+        let mut quote_mint_account = mint_account(quote_mint, 6);
+        let mut swap = swap_account(
+            liquidity_mint,
+            quote_mint,
+            1_000_000_000,
+            20_000_000,
+            PriceAccumulator::new(1_000),
+        );
+        let config = ReserveConfig {
+            max_confidence_ratio: 10,
+            ..ReserveConfig::default()
+        };
+
+        TokenSwapOracle
+            .init(
+                &mut liquidity,
+                &config,
+                &quote_mint_account.info(),
+                &swap.info(),
+                &lending_market(),
+                &clock(1_000),
+            )
+            .unwrap();
+        assert!(liquidity.token_swap_oracle.is_token_a);
+        update_swap(&mut swap, 1_000_000_000, 20_000_000, 1_300);
+        TokenSwapOracle
+            .refresh(&mut liquidity, &config, &swap.info(), &[], &clock(1_300))
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(20u64));
+
+        // the spot price moves to 21 within the window, 5% from the average
+        update_swap(&mut swap, 1_000_000_000, 21_000_000, 1_350);
+        TokenSwapOracle
+            .refresh(&mut liquidity, &config, &swap.info(), &[], &clock(1_400))
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::from(20u64));
+        assert_eq!(liquidity.market_price_confidence, Decimal::one());
+
+        // then to 40, 100% from the average
+        update_swap(&mut swap, 1_000_000_000, 40_000_000, 1_400);
+        assert_eq!(
+            TokenSwapOracle.refresh(&mut liquidity, &config, &swap.info(), &[], &clock(1_400)),
+            Err(LendingError::OraclePriceConfidenceTooWide.into())
+        );
+        TokenSwapOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &swap.info(),
+                &[],
+                &clock(1_400),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price_confidence, Decimal::from(20u64));
+    }
+
+    #[test]
+    fn token_swap_mints_must_match() {
+        let liquidity_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let mut liquidity = liquidity(liquidity_mint, 9);
+        let mut quote_mint_account = mint_account(quote_mint, 6);
+
+        let mut swap = swap_account(
+            Pubkey::new_unique(),
+            liquidity_mint,
+            1_000_000_000,
+            20_000_000,
+            PriceAccumulator::new(1_000),
+        );
+        assert_eq!(
+            TokenSwapOracle.init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &quote_mint_account.info(),
+                &swap.info(),
+                &lending_market(),
+                &clock(1_000),
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+
+        let mut swap = swap_account(
+            liquidity_mint,
+            quote_mint,
+            1_000_000_000,
+            20_000_000,
+            PriceAccumulator::new(1_000),
+        );
+        swap.owner = Pubkey::new_unique();
+        assert_eq!(
+            TokenSwapOracle.init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &quote_mint_account.info(),
+                &swap.info(),
+                &lending_market(),
+                &clock(1_000),
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+    }
+
+    #[test]
+    fn fixed_price() {
+        let mut liquidity = liquidity(Pubkey::new_unique(), 6);
+        let mut unused_config = mint_account(Pubkey::default(), 0);
+        let mut unused = mint_account(Pubkey::default(), 0);
+        FixedPriceOracle
+            .init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &unused_config.info(),
+                &unused.info(),
+                &lending_market(),
+                &clock(0),
+            )
+            .unwrap();
+        assert_eq!(liquidity.oracle_pubkey, Pubkey::default());
+        assert_eq!(liquidity.market_price, Decimal::one());
+
+        FixedPriceOracle
+            .refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &unused.info(),
+                &[],
+                &clock(i64::MAX),
+            )
+            .unwrap();
+        assert_eq!(liquidity.market_price, Decimal::one());
+    }
+
+    #[test]
+    fn fixed_price_rejects_accounts() {
+        let mut liquidity = liquidity(Pubkey::new_unique(), 6);
+        let mut unused = mint_account(Pubkey::default(), 0);
+        let mut account = mint_account(Pubkey::new_unique(), 0);
+        assert_eq!(
+            FixedPriceOracle.init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &account.info(),
+                &unused.info(),
+                &lending_market(),
+                &clock(0),
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+        assert_eq!(
+            FixedPriceOracle.init(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &unused.info(),
+                &account.info(),
+                &lending_market(),
+                &clock(0),
+            ),
+            Err(LendingError::InvalidOracleConfig.into())
+        );
+        assert_eq!(
+            FixedPriceOracle.refresh(
+                &mut liquidity,
+                &ReserveConfig::default(),
+                &unused.info(),
+                &[account.info()],
+                &clock(0),
+            ),
+            Err(LendingError::InvalidAccountInput.into())
+        );
+    }
+}
@@ -4,7 +4,7 @@ use crate::{
     error::LendingError,
     instruction::LendingInstruction,
     math::{Decimal, Rate, TryAdd, TryDiv, TryMul, WAD},
-    pyth,
+    oracle::OracleSource,
     state::{
         CalculateBorrowResult, CalculateLiquidationResult, CalculateRepayResult,
         InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
@@ -27,7 +27,6 @@ use solana_program::{
 };
 use spl_token::solana_program::instruction::AccountMeta;
 use spl_token::state::{Account, Mint};
-use std::convert::TryInto;
 
 /// Processes an instruction
 pub fn process_instruction(
@@ -51,9 +50,16 @@ pub fn process_instruction(
         LendingInstruction::InitReserve {
             liquidity_amount,
             config,
+            oracle_source,
         } => {
             msg!("Instruction: Init Reserve");
-            process_init_reserve(program_id, liquidity_amount, config, accounts)
+            process_init_reserve(
+                program_id,
+                liquidity_amount,
+                config,
+                oracle_source,
+                accounts,
+            )
         }
         LendingInstruction::RefreshReserve => {
             msg!("Instruction: Refresh Reserve");
@@ -99,6 +105,10 @@ pub fn process_instruction(
             msg!("Instruction: Flash Loan");
             process_flash_loan(program_id, amount, accounts)
         }
+        LendingInstruction::UpdateReserveConfig { config } => {
+            msg!("Instruction: Update Reserve Config");
+            process_update_reserve_config(program_id, config, accounts)
+        }
     }
 }
 
@@ -167,50 +177,14 @@ fn process_init_reserve(
     program_id: &Pubkey,
     liquidity_amount: u64,
     config: ReserveConfig,
+    oracle_source: OracleSource,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     if liquidity_amount == 0 {
         msg!("Reserve must be initialized with liquidity");
         return Err(LendingError::InvalidAmount.into());
     }
-    if config.optimal_utilization_rate > 100 {
-        msg!("Optimal utilization rate must be in range [0, 100]");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.loan_to_value_ratio >= 100 {
-        msg!("Loan to value ratio must be in range [0, 100)");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.liquidation_bonus > 100 {
-        msg!("Liquidation bonus must be in range [0, 100]");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.liquidation_threshold <= config.loan_to_value_ratio
-        || config.liquidation_threshold > 100
-    {
-        msg!("Liquidation threshold must be in range (LTV, 100]");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.optimal_borrow_rate < config.min_borrow_rate {
-        msg!("Optimal borrow rate must be >= min borrow rate");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.optimal_borrow_rate > config.max_borrow_rate {
-        msg!("Optimal borrow rate must be <= max borrow rate");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.fees.borrow_fee_wad >= WAD {
-        msg!("Borrow fee must be in range [0, 1_000_000_000_000_000_000)");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.fees.flash_loan_fee_wad >= WAD {
-        msg!("Flash loan fee must be in range [0, 1_000_000_000_000_000_000)");
-        return Err(LendingError::InvalidConfig.into());
-    }
-    if config.fees.host_fee_percentage > 100 {
-        msg!("Host fee percentage must be in range [0, 100]");
-        return Err(LendingError::InvalidConfig.into());
-    }
+    validate_reserve_config(&config)?;
 
     let account_info_iter = &mut accounts.iter().peekable();
     let source_liquidity_info = next_account_info(account_info_iter)?;
@@ -221,8 +195,8 @@ fn process_init_reserve(
     let reserve_liquidity_fee_receiver_info = next_account_info(account_info_iter)?;
     let reserve_collateral_mint_info = next_account_info(account_info_iter)?;
     let reserve_collateral_supply_info = next_account_info(account_info_iter)?;
-    let pyth_product_info = next_account_info(account_info_iter)?;
-    let pyth_price_info = next_account_info(account_info_iter)?;
+    let oracle_config_info = next_account_info(account_info_iter)?;
+    let oracle_info = next_account_info(account_info_iter)?;
     let lending_market_info = next_account_info(account_info_iter)?;
     let lending_market_authority_info = next_account_info(account_info_iter)?;
     let lending_market_owner_info = next_account_info(account_info_iter)?;
@@ -262,49 +236,6 @@ fn process_init_reserve(
         return Err(LendingError::InvalidSigner.into());
     }
 
-    if &lending_market.oracle_program_id != pyth_product_info.owner {
-        msg!("Pyth product account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if &lending_market.oracle_program_id != pyth_price_info.owner {
-        msg!("Pyth price account provided is not owned by the lending market oracle program");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let pyth_product_data = pyth_product_info.try_borrow_data()?;
-    let pyth_product = pyth::load::<pyth::Product>(&pyth_product_data)
-        .map_err(|_| ProgramError::InvalidAccountData)?;
-    if pyth_product.magic != pyth::MAGIC {
-        msg!("Pyth product account provided is not a valid Pyth account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.ver != pyth::VERSION_2 {
-        msg!("Pyth product account provided has a different version than expected");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-    if pyth_product.atype != pyth::AccountType::Product as u32 {
-        msg!("Pyth product account provided is not a valid Pyth product account");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let pyth_price_pubkey_bytes: &[u8; 32] = pyth_price_info
-        .key
-        .as_ref()
-        .try_into()
-        .map_err(|_| LendingError::InvalidAccountInput)?;
-    if &pyth_product.px_acc.val != pyth_price_pubkey_bytes {
-        msg!("Pyth product price account does not match the Pyth price provided");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let quote_currency = get_pyth_product_quote_currency(pyth_product)?;
-    if lending_market.quote_currency != quote_currency {
-        msg!("Lending market quote currency does not match the oracle quote currency");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let market_price = get_pyth_price(pyth_price_info, clock)?;
-
     let authority_signer_seeds = &[
         lending_market_info.key.as_ref(),
         &[lending_market.bump_seed],
@@ -324,17 +255,28 @@ fn process_init_reserve(
         return Err(LendingError::InvalidTokenOwner.into());
     }
 
+    let mut liquidity = ReserveLiquidity::new(NewReserveLiquidityParams {
+        mint_pubkey: *reserve_liquidity_mint_info.key,
+        mint_decimals: reserve_liquidity_mint.decimals,
+        supply_pubkey: *reserve_liquidity_supply_info.key,
+        fee_receiver: *reserve_liquidity_fee_receiver_info.key,
+        oracle_source,
+        oracle_pubkey: *oracle_info.key,
+        market_price: Decimal::zero(),
+    });
+    oracle_source.adapter().init(
+        &mut liquidity,
+        &config,
+        oracle_config_info,
+        oracle_info,
+        &lending_market,
+        clock,
+    )?;
+
     reserve.init(InitReserveParams {
         current_slot: clock.slot,
         lending_market: *lending_market_info.key,
-        liquidity: ReserveLiquidity::new(NewReserveLiquidityParams {
-            mint_pubkey: *reserve_liquidity_mint_info.key,
-            mint_decimals: reserve_liquidity_mint.decimals,
-            supply_pubkey: *reserve_liquidity_supply_info.key,
-            fee_receiver: *reserve_liquidity_fee_receiver_info.key,
-            oracle_pubkey: *pyth_price_info.key,
-            market_price,
-        }),
+        liquidity,
         collateral: ReserveCollateral::new(NewReserveCollateralParams {
             mint_pubkey: *reserve_collateral_mint_info.key,
             supply_pubkey: *reserve_collateral_supply_info.key,
@@ -342,6 +284,11 @@ fn process_init_reserve(
         config,
     });
 
+    // Some oracles have no price until the reserve is first refreshed
+    if reserve.liquidity.market_price == Decimal::zero() {
+        reserve.last_update.mark_stale();
+    }
+
     let collateral_amount = reserve.deposit_liquidity(liquidity_amount)?;
     Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
 
@@ -406,11 +353,106 @@ fn process_init_reserve(
     Ok(())
 }
 
+fn validate_reserve_config(config: &ReserveConfig) -> ProgramResult {
+    if config.optimal_utilization_rate > 100 {
+        msg!("Optimal utilization rate must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.loan_to_value_ratio >= 100 {
+        msg!("Loan to value ratio must be in range [0, 100)");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.liquidation_bonus > 100 {
+        msg!("Liquidation bonus must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.liquidation_threshold <= config.loan_to_value_ratio
+        || config.liquidation_threshold > 100
+    {
+        msg!("Liquidation threshold must be in range (LTV, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.optimal_borrow_rate < config.min_borrow_rate {
+        msg!("Optimal borrow rate must be >= min borrow rate");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.optimal_borrow_rate > config.max_borrow_rate {
+        msg!("Optimal borrow rate must be <= max borrow rate");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.fees.borrow_fee_wad >= WAD {
+        msg!("Borrow fee must be in range [0, 1_000_000_000_000_000_000)");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.fees.flash_loan_fee_wad >= WAD {
+        msg!("Flash loan fee must be in range [0, 1_000_000_000_000_000_000)");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.fees.host_fee_percentage > 100 {
+        msg!("Host fee percentage must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.max_confidence_ratio > 100 {
+        msg!("Max confidence ratio must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    if config.max_price_deviation > 100 {
+        msg!("Max price deviation must be in range [0, 100]");
+        return Err(LendingError::InvalidConfig.into());
+    }
+    Ok(())
+}
+
+#[inline(never)] // avoid stack frame limit
+fn process_update_reserve_config(
+    program_id: &Pubkey,
+    config: ReserveConfig,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    validate_reserve_config(&config)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let reserve_info = next_account_info(account_info_iter)?;
+    let lending_market_info = next_account_info(account_info_iter)?;
+    let lending_market_owner_info = next_account_info(account_info_iter)?;
+
+    let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
+    if reserve_info.owner != program_id {
+        msg!("Reserve provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &reserve.lending_market != lending_market_info.key {
+        msg!("Reserve lending market does not match the lending market provided");
+        return Err(LendingError::InvalidAccountInput.into());
+    }
+
+    let lending_market = LendingMarket::unpack(&lending_market_info.data.borrow())?;
+    if lending_market_info.owner != program_id {
+        msg!("Lending market provided is not owned by the lending program");
+        return Err(LendingError::InvalidAccountOwner.into());
+    }
+    if &lending_market.owner != lending_market_owner_info.key {
+        msg!("Lending market owner does not match the lending market owner provided");
+        return Err(LendingError::InvalidMarketOwner.into());
+    }
+    if !lending_market_owner_info.is_signer {
+        msg!("Lending market owner provided must be a signer");
+        return Err(LendingError::InvalidSigner.into());
+    }
+
+    reserve.config = config;
+    reserve.last_update.mark_stale();
+    Reserve::pack(reserve, &mut reserve_info.data.borrow_mut())?;
+
+    Ok(())
+}
+
 fn process_refresh_reserve(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter().peekable();
+    let account_info_iter = &mut accounts.iter();
     let reserve_info = next_account_info(account_info_iter)?;
     let reserve_liquidity_oracle_info = next_account_info(account_info_iter)?;
     let clock = &Clock::from_account_info(next_account_info(account_info_iter)?)?;
+    let other_oracle_infos = account_info_iter.as_slice();
 
     let mut reserve = Reserve::unpack(&reserve_info.data.borrow())?;
     if reserve_info.owner != program_id {
@@ -422,7 +464,16 @@ fn process_refresh_reserve(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pro
         return Err(LendingError::InvalidAccountInput.into());
     }
 
-    reserve.liquidity.market_price = get_pyth_price(reserve_liquidity_oracle_info, clock)?;
+    let previous_price = reserve.liquidity.market_price;
+    let oracle_source = reserve.liquidity.oracle_source;
+    oracle_source.adapter().refresh(
+        &mut reserve.liquidity,
+        &reserve.config,
+        reserve_liquidity_oracle_info,
+        other_oracle_infos,
+        clock,
+    )?;
+    reserve.validate_price_deviation(previous_price)?;
 
     reserve.accrue_interest(clock.slot)?;
     reserve.last_update.update_slot(clock.slot);
@@ -724,10 +775,12 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
             .checked_pow(deposit_reserve.liquidity.mint_decimals as u32)
             .ok_or(LendingError::MathOverflow)?;
 
+        // Deposits are valued at the lower bound of the oracle confidence
+        // interval, and borrows at the upper bound
         let market_value = deposit_reserve
             .collateral_exchange_rate()?
             .decimal_collateral_to_liquidity(collateral.deposited_amount.into())?
-            .try_mul(deposit_reserve.liquidity.market_price)?
+            .try_mul(deposit_reserve.liquidity.market_price_lower_bound()?)?
             .try_div(decimals)?;
         collateral.market_value = market_value;
 
@@ -777,7 +830,7 @@ fn process_refresh_obligation(program_id: &Pubkey, accounts: &[AccountInfo]) ->
 
         let market_value = liquidity
             .borrowed_amount_wads
-            .try_mul(borrow_reserve.liquidity.market_price)?
+            .try_mul(borrow_reserve.liquidity.market_price_upper_bound()?)?
             .try_div(decimals)?;
         liquidity.market_value = market_value;
 
@@ -1717,99 +1770,6 @@ fn unpack_mint(data: &[u8]) -> Result<Mint, LendingError> {
     Mint::unpack(data).map_err(|_| LendingError::InvalidTokenMint)
 }
 
-fn get_pyth_product_quote_currency(pyth_product: &pyth::Product) -> Result<[u8; 32], ProgramError> {
-    const LEN: usize = 14;
-    const KEY: &[u8; LEN] = b"quote_currency";
-
-    let mut start = 0;
-    while start < pyth::PROD_ATTR_SIZE {
-        let mut length = pyth_product.attr[start] as usize;
-        start += 1;
-
-        if length == LEN {
-            let mut end = start + length;
-            if end > pyth::PROD_ATTR_SIZE {
-                msg!("Pyth product attribute key length too long");
-                return Err(LendingError::InvalidOracleConfig.into());
-            }
-
-            let key = &pyth_product.attr[start..end];
-            if key == KEY {
-                start += length;
-                length = pyth_product.attr[start] as usize;
-                start += 1;
-
-                end = start + length;
-                if length > 32 || end > pyth::PROD_ATTR_SIZE {
-                    msg!("Pyth product quote currency value too long");
-                    return Err(LendingError::InvalidOracleConfig.into());
-                }
-
-                let mut value = [0u8; 32];
-                value[0..length].copy_from_slice(&pyth_product.attr[start..end]);
-                return Ok(value);
-            }
-        }
-
-        start += length;
-        start += 1 + pyth_product.attr[start] as usize;
-    }
-
-    msg!("Pyth product quote currency not found");
-    Err(LendingError::InvalidOracleConfig.into())
-}
-
-fn get_pyth_price(pyth_price_info: &AccountInfo, clock: &Clock) -> Result<Decimal, ProgramError> {
-    const STALE_AFTER_SLOTS_ELAPSED: u64 = 5;
-
-    let pyth_price_data = pyth_price_info.try_borrow_data()?;
-    let pyth_price = pyth::load::<pyth::Price>(&pyth_price_data)
-        .map_err(|_| ProgramError::InvalidAccountData)?;
-
-    if pyth_price.ptype != pyth::PriceType::Price {
-        msg!("Oracle price type is invalid");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let slots_elapsed = clock
-        .slot
-        .checked_sub(pyth_price.valid_slot)
-        .ok_or(LendingError::MathOverflow)?;
-    if slots_elapsed >= STALE_AFTER_SLOTS_ELAPSED {
-        msg!("Oracle price is stale");
-        return Err(LendingError::InvalidOracleConfig.into());
-    }
-
-    let price: u64 = pyth_price.agg.price.try_into().map_err(|_| {
-        msg!("Oracle price cannot be negative");
-        LendingError::InvalidOracleConfig
-    })?;
-
-    let market_price = if pyth_price.expo >= 0 {
-        let exponent = pyth_price
-            .expo
-            .try_into()
-            .map_err(|_| LendingError::MathOverflow)?;
-        let zeros = 10u64
-            .checked_pow(exponent)
-            .ok_or(LendingError::MathOverflow)?;
-        Decimal::from(price).try_mul(zeros)?
-    } else {
-        let exponent = pyth_price
-            .expo
-            .checked_abs()
-            .ok_or(LendingError::MathOverflow)?
-            .try_into()
-            .map_err(|_| LendingError::MathOverflow)?;
-        let decimals = 10u64
-            .checked_pow(exponent)
-            .ok_or(LendingError::MathOverflow)?;
-        Decimal::from(price).try_div(decimals)?
-    };
-
-    Ok(market_price)
-}
-
 /// Issue a spl_token `InitializeAccount` instruction.
 #[inline(always)]
 fn spl_token_init_account(params: TokenInitializeAccountParams<'_>) -> ProgramResult {
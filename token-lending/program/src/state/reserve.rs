@@ -2,8 +2,10 @@ use super::*;
 use crate::{
     error::LendingError,
     math::{Decimal, Rate, TryAdd, TryDiv, TryMul, TrySub},
+    oracle::{OracleSource, TokenSwapOracleState},
 };
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use num_traits::FromPrimitive;
 use solana_program::{
     clock::Slot,
     entrypoint::ProgramResult,
@@ -12,6 +14,7 @@ use solana_program::{
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::{Pubkey, PUBKEY_BYTES},
 };
+use spl_token_swap::oracle::PriceAccumulator;
 use std::{
     cmp::Ordering,
     convert::{TryFrom, TryInto},
@@ -124,6 +127,33 @@ impl Reserve {
         self.collateral.exchange_rate(total_liquidity)
     }
 
+    /// Check that the market price of the liquidity moved by at most the
+    /// maximum price deviation since the previous price, set at the last
+    /// update.  There is no limit without a previous price or when the maximum
+    /// deviation is zero.  A lasting move beyond the limit is only accepted
+    /// once the lending market owner changes it with `UpdateReserveConfig`.
+    pub fn validate_price_deviation(&self, previous_price: Decimal) -> ProgramResult {
+        if previous_price == Decimal::zero() || self.config.max_price_deviation == 0 {
+            return Ok(());
+        }
+        let market_price = self.liquidity.market_price;
+        let deviation = if market_price > previous_price {
+            market_price.try_sub(previous_price)?
+        } else {
+            previous_price.try_sub(market_price)?
+        };
+        let max_deviation =
+            previous_price.try_mul(Rate::from_percent(self.config.max_price_deviation))?;
+        if deviation > max_deviation {
+            msg!(
+                "Market price moved by more than {}% since the last update",
+                self.config.max_price_deviation
+            );
+            return Err(LendingError::OraclePriceDeviationTooLarge.into());
+        }
+        Ok(())
+    }
+
     /// Update borrow rate and accrue interest
     pub fn accrue_interest(&mut self, current_slot: Slot) -> ProgramResult {
         let slots_elapsed = self.last_update.slots_elapsed(current_slot)?;
@@ -355,8 +385,12 @@ pub struct ReserveLiquidity {
     pub supply_pubkey: Pubkey,
     /// Reserve liquidity fee receiver address
     pub fee_receiver: Pubkey,
+    /// Reserve liquidity oracle source
+    pub oracle_source: OracleSource,
     /// Reserve liquidity oracle account
     pub oracle_pubkey: Pubkey,
+    /// Reserve liquidity token swap oracle state, if priced by a token swap
+    pub token_swap_oracle: TokenSwapOracleState,
     /// Reserve liquidity available
     pub available_amount: u64,
     /// Reserve liquidity borrowed
@@ -365,6 +399,9 @@ pub struct ReserveLiquidity {
     pub cumulative_borrow_rate_wads: Decimal,
     /// Reserve liquidity market price in quote currency
     pub market_price: Decimal,
+    /// Reserve liquidity market price confidence interval in quote currency,
+    /// zero if the oracle does not provide one
+    pub market_price_confidence: Decimal,
 }
 
 impl ReserveLiquidity {
@@ -375,14 +412,33 @@ impl ReserveLiquidity {
             mint_decimals: params.mint_decimals,
             supply_pubkey: params.supply_pubkey,
             fee_receiver: params.fee_receiver,
+            oracle_source: params.oracle_source,
             oracle_pubkey: params.oracle_pubkey,
+            token_swap_oracle: TokenSwapOracleState::default(),
             available_amount: 0,
             borrowed_amount_wads: Decimal::zero(),
             cumulative_borrow_rate_wads: Decimal::one(),
             market_price: params.market_price,
+            market_price_confidence: Decimal::zero(),
         }
     }
 
+    /// Lower bound of the market price within its confidence interval, used
+    /// to value deposits conservatively
+    pub fn market_price_lower_bound(&self) -> Result<Decimal, ProgramError> {
+        if self.market_price_confidence > self.market_price {
+            Ok(Decimal::zero())
+        } else {
+            self.market_price.try_sub(self.market_price_confidence)
+        }
+    }
+
+    /// Upper bound of the market price within its confidence interval, used
+    /// to value borrows conservatively
+    pub fn market_price_upper_bound(&self) -> Result<Decimal, ProgramError> {
+        self.market_price.try_add(self.market_price_confidence)
+    }
+
     /// Calculate the total reserve supply including active loans
     pub fn total_supply(&self) -> Result<Decimal, ProgramError> {
         Decimal::from(self.available_amount).try_add(self.borrowed_amount_wads)
@@ -477,6 +533,8 @@ pub struct NewReserveLiquidityParams {
     pub supply_pubkey: Pubkey,
     /// Reserve liquidity fee receiver address
     pub fee_receiver: Pubkey,
+    /// Reserve liquidity oracle source
+    pub oracle_source: OracleSource,
     /// Reserve liquidity oracle account
     pub oracle_pubkey: Pubkey,
     /// Reserve liquidity market price in quote currency
@@ -606,6 +664,13 @@ pub struct ReserveConfig {
     pub max_borrow_rate: u8,
     /// Program owner fees assessed, separate from gains due to interest accrual
     pub fees: ReserveFees,
+    /// Max oracle price confidence interval, as a percentage of the price
+    /// 0 if there is no limit
+    pub max_confidence_ratio: u8,
+    /// Max change of the market price between two refreshes, as a percentage,
+    /// beyond which refreshes fail and the reserve stays stale
+    /// 0 if there is no limit
+    pub max_price_deviation: u8,
 }
 
 /// Additional fee information on a reserve
@@ -712,7 +777,7 @@ impl IsInitialized for Reserve {
     }
 }
 
-const RESERVE_LEN: usize = 571; // 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 248
+const RESERVE_LEN: usize = 571; // 1 + 8 + 1 + 32 + 32 + 1 + 32 + 32 + 32 + 8 + 16 + 16 + 16 + 32 + 8 + 32 + 1 + 1 + 1 + 1 + 1 + 1 + 1 + 8 + 8 + 1 + 1 + 1 + 1 + 1 + 1 + 40 + 16 + 187
 impl Pack for Reserve {
     const LEN: usize = RESERVE_LEN;
 
@@ -747,6 +812,13 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            config_max_confidence_ratio,
+            config_max_price_deviation,
+            liquidity_oracle_source,
+            liquidity_token_swap_oracle_is_token_a,
+            liquidity_token_swap_oracle_quote_decimals,
+            liquidity_token_swap_oracle_observation,
+            liquidity_market_price_confidence,
             _padding,
         ) = mut_array_refs![
             output,
@@ -776,7 +848,14 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            1,
+            1,
+            1,
+            1,
+            PriceAccumulator::LEN,
+            16,
+            187
         ];
 
         // reserve
@@ -818,6 +897,28 @@ impl Pack for Reserve {
         *config_fees_borrow_fee_wad = self.config.fees.borrow_fee_wad.to_le_bytes();
         *config_fees_flash_loan_fee_wad = self.config.fees.flash_loan_fee_wad.to_le_bytes();
         *config_fees_host_fee_percentage = self.config.fees.host_fee_percentage.to_le_bytes();
+        *config_max_confidence_ratio = self.config.max_confidence_ratio.to_le_bytes();
+        *config_max_price_deviation = self.config.max_price_deviation.to_le_bytes();
+
+        // oracle
+        *liquidity_oracle_source = (self.liquidity.oracle_source as u8).to_le_bytes();
+        pack_bool(
+            self.liquidity.token_swap_oracle.is_token_a,
+            liquidity_token_swap_oracle_is_token_a,
+        );
+        *liquidity_token_swap_oracle_quote_decimals = self
+            .liquidity
+            .token_swap_oracle
+            .quote_decimals
+            .to_le_bytes();
+        self.liquidity
+            .token_swap_oracle
+            .observation
+            .pack_into_slice(liquidity_token_swap_oracle_observation);
+        pack_decimal(
+            self.liquidity.market_price_confidence,
+            liquidity_market_price_confidence,
+        );
     }
 
     /// Unpacks a byte buffer into a [ReserveInfo](struct.ReserveInfo.html).
@@ -851,6 +952,13 @@ impl Pack for Reserve {
             config_fees_borrow_fee_wad,
             config_fees_flash_loan_fee_wad,
             config_fees_host_fee_percentage,
+            config_max_confidence_ratio,
+            config_max_price_deviation,
+            liquidity_oracle_source,
+            liquidity_token_swap_oracle_is_token_a,
+            liquidity_token_swap_oracle_quote_decimals,
+            liquidity_token_swap_oracle_observation,
+            liquidity_market_price_confidence,
             _padding,
         ) = array_refs![
             input,
@@ -880,7 +988,14 @@ impl Pack for Reserve {
             8,
             8,
             1,
-            248
+            1,
+            1,
+            1,
+            1,
+            1,
+            PriceAccumulator::LEN,
+            16,
+            187
         ];
 
         let version = u8::from_le_bytes(*version);
@@ -889,6 +1004,12 @@ impl Pack for Reserve {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        let oracle_source = OracleSource::from_u8(u8::from_le_bytes(*liquidity_oracle_source))
+            .ok_or_else(|| {
+                msg!("Reserve oracle source cannot be unpacked");
+                ProgramError::InvalidAccountData
+            })?;
+
         Ok(Self {
             version,
             last_update: LastUpdate {
@@ -901,11 +1022,20 @@ impl Pack for Reserve {
                 mint_decimals: u8::from_le_bytes(*liquidity_mint_decimals),
                 supply_pubkey: Pubkey::new_from_array(*liquidity_supply_pubkey),
                 fee_receiver: Pubkey::new_from_array(*liquidity_fee_receiver),
+                oracle_source,
                 oracle_pubkey: Pubkey::new_from_array(*liquidity_oracle_pubkey),
+                token_swap_oracle: TokenSwapOracleState {
+                    is_token_a: unpack_bool(liquidity_token_swap_oracle_is_token_a)?,
+                    quote_decimals: u8::from_le_bytes(*liquidity_token_swap_oracle_quote_decimals),
+                    observation: PriceAccumulator::unpack_from_slice(
+                        liquidity_token_swap_oracle_observation,
+                    )?,
+                },
                 available_amount: u64::from_le_bytes(*liquidity_available_amount),
                 borrowed_amount_wads: unpack_decimal(liquidity_borrowed_amount_wads),
                 cumulative_borrow_rate_wads: unpack_decimal(liquidity_cumulative_borrow_rate_wads),
                 market_price: unpack_decimal(liquidity_market_price),
+                market_price_confidence: unpack_decimal(liquidity_market_price_confidence),
             },
             collateral: ReserveCollateral {
                 mint_pubkey: Pubkey::new_from_array(*collateral_mint_pubkey),
@@ -925,6 +1055,8 @@ impl Pack for Reserve {
                     flash_loan_fee_wad: u64::from_le_bytes(*config_fees_flash_loan_fee_wad),
                     host_fee_percentage: u8::from_le_bytes(*config_fees_host_fee_percentage),
                 },
+                max_confidence_ratio: u8::from_le_bytes(*config_max_confidence_ratio),
+                max_price_deviation: u8::from_le_bytes(*config_max_price_deviation),
             },
         })
     }
@@ -1305,4 +1437,114 @@ mod test {
         assert_eq!(total_fee, 10); // 1% of 1000
         assert_eq!(host_fee, 0); // 0 host fee
     }
+
+    #[test]
+    fn pack_reserve_oracle() {
+        let reserve = Reserve {
+            version: PROGRAM_VERSION,
+            liquidity: ReserveLiquidity {
+                oracle_source: OracleSource::TokenSwap,
+                oracle_pubkey: Pubkey::new_unique(),
+                token_swap_oracle: TokenSwapOracleState {
+                    is_token_a: true,
+                    quote_decimals: 6,
+                    observation: PriceAccumulator {
+                        last_update_timestamp: 1_630_000_000,
+                        token_a_price_cumulative: u128::MAX - 1,
+                        token_b_price_cumulative: 12_345_678_901_234_567_890,
+                    },
+                },
+                market_price: Decimal::from(20u64),
+                market_price_confidence: Decimal::from_percent(3),
+                ..ReserveLiquidity::default()
+            },
+            config: ReserveConfig {
+                max_confidence_ratio: 2,
+                max_price_deviation: 10,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let mut packed = [0u8; Reserve::LEN];
+        Reserve::pack(reserve.clone(), &mut packed).unwrap();
+        assert_eq!(Reserve::unpack(&packed).unwrap(), reserve);
+
+        // reserves created before oracle sources have zeroed padding
+        let reserve = Reserve {
+            version: PROGRAM_VERSION,
+            ..Reserve::default()
+        };
+        Reserve::pack(reserve, &mut packed).unwrap();
+        let unpacked = Reserve::unpack(&packed).unwrap();
+        assert_eq!(unpacked.liquidity.oracle_source, OracleSource::Pyth);
+        assert_eq!(
+            unpacked.liquidity.token_swap_oracle,
+            TokenSwapOracleState::default()
+        );
+    }
+
+    #[test]
+    fn market_price_bounds() {
+        let liquidity = ReserveLiquidity {
+            market_price: Decimal::from(20u64),
+            market_price_confidence: Decimal::one(),
+            ..ReserveLiquidity::default()
+        };
+        assert_eq!(
+            liquidity.market_price_lower_bound().unwrap(),
+            Decimal::from(19u64)
+        );
+        assert_eq!(
+            liquidity.market_price_upper_bound().unwrap(),
+            Decimal::from(21u64)
+        );
+
+        let liquidity = ReserveLiquidity {
+            market_price: Decimal::one(),
+            market_price_confidence: Decimal::from(2u64),
+            ..ReserveLiquidity::default()
+        };
+        assert_eq!(
+            liquidity.market_price_lower_bound().unwrap(),
+            Decimal::zero()
+        );
+    }
+
+    #[test]
+    fn price_deviation() {
+        let mut reserve = Reserve {
+            last_update: LastUpdate::new(1_000),
+            liquidity: ReserveLiquidity {
+                market_price: Decimal::from(110u64),
+                ..ReserveLiquidity::default()
+            },
+            config: ReserveConfig {
+                max_price_deviation: 10,
+                ..ReserveConfig::default()
+            },
+            ..Reserve::default()
+        };
+        let previous_price = Decimal::from(100u64);
+        reserve.validate_price_deviation(previous_price).unwrap();
+        reserve.liquidity.market_price = Decimal::from(90u64);
+        reserve.validate_price_deviation(previous_price).unwrap();
+
+        reserve.liquidity.market_price = Decimal::from(111u64);
+        assert_eq!(
+            reserve.validate_price_deviation(previous_price),
+            Err(LendingError::OraclePriceDeviationTooLarge.into())
+        );
+        // however long ago the previous price was set
+        reserve.liquidity.market_price = Decimal::from(89u64);
+        reserve.last_update = LastUpdate::new(0);
+        assert_eq!(
+            reserve.validate_price_deviation(previous_price),
+            Err(LendingError::OraclePriceDeviationTooLarge.into())
+        );
+
+        // no previous price, or no limit
+        reserve.validate_price_deviation(Decimal::zero()).unwrap();
+        reserve.config.max_price_deviation = 0;
+        reserve.validate_price_deviation(previous_price).unwrap();
+    }
 }
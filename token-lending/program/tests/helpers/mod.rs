@@ -22,6 +22,7 @@ use spl_token_lending::{
         init_obligation, init_reserve, liquidate_obligation, refresh_reserve,
     },
     math::{Decimal, Rate, TryAdd, TryMul},
+    oracle::OracleSource,
     pyth,
     state::{
         InitLendingMarketParams, InitObligationParams, InitReserveParams, LendingMarket,
@@ -53,6 +54,8 @@ pub const TEST_RESERVE_CONFIG: ReserveConfig = ReserveConfig {
         flash_loan_fee_wad: 3_000_000_000_000_000,
         host_fee_percentage: 20,
     },
+    max_confidence_ratio: 10,
+    max_price_deviation: 0,
 };
 
 pub const SOL_PYTH_PRODUCT: &str = "3Mnn2fX6rQyUsyELYms1sBJyChWofzSNRoqYzvgMVz5E";
@@ -342,6 +345,7 @@ pub fn add_reserve(
             mint_decimals: liquidity_mint_decimals,
             supply_pubkey: liquidity_supply_pubkey,
             fee_receiver: liquidity_fee_receiver_pubkey,
+            oracle_source: OracleSource::Pyth,
             oracle_pubkey: oracle.price_pubkey,
             market_price: oracle.price,
         }),
@@ -801,6 +805,7 @@ impl TestReserve {
                     spl_token_lending::id(),
                     liquidity_amount,
                     config,
+                    OracleSource::Pyth,
                     user_liquidity_pubkey,
                     user_collateral_token_keypair.pubkey(),
                     reserve_pubkey,
@@ -1117,6 +1122,8 @@ pub fn add_oracle(
         .unwrap();
 
     pyth_price.valid_slot = 0;
+    // Exact prices, so that deposits and borrows are valued at the price
+    pyth_price.agg.conf = 0;
     pyth_price.agg.price = price
         .try_round_u64()
         .unwrap()
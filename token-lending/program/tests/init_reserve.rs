@@ -13,6 +13,7 @@ use solana_sdk::{
 use spl_token_lending::{
     error::LendingError,
     instruction::init_reserve,
+    oracle::OracleSource,
     processor::process_instruction,
     state::{ReserveFees, INITIAL_COLLATERAL_RATIO},
 };
@@ -112,6 +113,7 @@ async fn test_already_initialized() {
             spl_token_lending::id(),
             42,
             usdc_test_reserve.config,
+            OracleSource::Pyth,
             usdc_test_reserve.user_liquidity_pubkey,
             usdc_test_reserve.user_collateral_pubkey,
             usdc_test_reserve.pubkey,
@@ -232,4 +234,58 @@ async fn test_invalid_fees() {
             )
         );
     }
+
+    // max confidence ratio above 100%
+    {
+        let mut config = TEST_RESERVE_CONFIG;
+        config.max_confidence_ratio = 101;
+
+        assert_eq!(
+            TestReserve::init(
+                "sol".to_owned(),
+                &mut banks_client,
+                &lending_market,
+                &sol_oracle,
+                RESERVE_AMOUNT,
+                config,
+                spl_token::native_mint::id(),
+                sol_user_liquidity_account,
+                &payer,
+                &user_accounts_owner,
+            )
+            .await
+            .unwrap_err(),
+            TransactionError::InstructionError(
+                8,
+                InstructionError::Custom(LendingError::InvalidConfig as u32)
+            )
+        );
+    }
+
+    // max price deviation above 100%
+    {
+        let mut config = TEST_RESERVE_CONFIG;
+        config.max_price_deviation = 101;
+
+        assert_eq!(
+            TestReserve::init(
+                "sol".to_owned(),
+                &mut banks_client,
+                &lending_market,
+                &sol_oracle,
+                RESERVE_AMOUNT,
+                config,
+                spl_token::native_mint::id(),
+                sol_user_liquidity_account,
+                &payer,
+                &user_accounts_owner,
+            )
+            .await
+            .unwrap_err(),
+            TransactionError::InstructionError(
+                8,
+                InstructionError::Custom(LendingError::InvalidConfig as u32)
+            )
+        );
+    }
 }
@@ -0,0 +1,2230 @@
+use {
+    clap::{
+        crate_description, crate_name, crate_version, value_t, App, AppSettings, Arg, ArgMatches,
+        SubCommand,
+    },
+    solana_account_decoder::UiAccountEncoding,
+    solana_clap_utils::{
+        fee_payer::fee_payer_arg,
+        input_parsers::{pubkey_of, value_of},
+        input_validators::{is_amount, is_parsable, is_pubkey, is_url, is_valid_signer},
+        keypair::signer_from_path,
+    },
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    },
+    solana_program::{
+        instruction::Instruction, native_token::lamports_to_sol, program_pack::Pack, pubkey::Pubkey,
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{Keypair, Signer},
+        signers::Signers,
+        system_instruction,
+        transaction::Transaction,
+    },
+    spl_math::{
+        precise_number::{PreciseNumber, ONE},
+        uint::U256,
+    },
+    spl_token::{
+        amount_to_ui_amount,
+        instruction::{approve, initialize_account, initialize_mint, revoke, transfer},
+        state::{Account as Token, Mint},
+        ui_amount_to_amount,
+    },
+    spl_token_swap::{
+        self,
+        curve::{
+            base::{CurveType, SwapCurve},
+            calculator::TradeDirection,
+            concentrated_liquidity::ConcentratedLiquidityCurve,
+            constant_price::ConstantPriceCurve,
+            constant_product::ConstantProductCurve,
+            fees::Fees,
+            offset::OffsetCurve,
+            stable::StableCurve,
+            weighted::WeightedCurve,
+        },
+        instruction::{
+            deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize,
+            initialize_with_admin, swap, withdraw_all_token_types,
+            withdraw_single_token_type_exact_amount_out, DepositAllTokenTypes,
+            DepositSingleTokenTypeExactAmountIn, Swap, WithdrawAllTokenTypes,
+            WithdrawSingleTokenTypeExactAmountOut,
+        },
+        state::{SwapVersion, WeightedSwapV1},
+    },
+    spl_token_swap_client::pool::{decode_swap, get_pool, PoolState},
+    std::{cmp, convert::TryFrom, process::exit},
+    system_instruction::create_account,
+};
+
+struct Config {
+    rpc_client: RpcClient,
+    fee_payer: Box<dyn Signer>,
+    owner: Box<dyn Signer>,
+    swap_program_id: Pubkey,
+    verbose: bool,
+    dry_run: bool,
+}
+
+type Error = Box<dyn std::error::Error>;
+type CommandResult = Result<(), Error>;
+
+const BASIS_POINTS: u64 = 10_000;
+
+macro_rules! unique_signers {
+    ($vec:ident) => {
+        $vec.sort_by_key(|l| l.pubkey());
+        $vec.dedup();
+    };
+}
+
+/// Command line interface, with the given default swap program
+fn app<'a, 'b>(default_swap_program_id: &'a str) -> App<'a, 'b> {
+    let pool_arg = Arg::with_name("pool")
+        .index(1)
+        .validator(is_pubkey)
+        .value_name("POOL_ADDRESS")
+        .takes_value(true)
+        .required(true)
+        .help("Swap account of the pool");
+    let slippage_arg = Arg::with_name("slippage")
+        .long("slippage")
+        .validator(is_slippage)
+        .value_name("DECIMAL_PERCENT")
+        .takes_value(true)
+        .required(true)
+        .default_value("1")
+        .help("Largest change of the quoted amounts accepted while the transaction is pending: [0, 100]");
+
+    App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg({
+            let arg = Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use");
+            if let Some(ref config_file) = *solana_cli_config::CONFIG_FILE {
+                arg.default_value(config_file)
+            } else {
+                arg
+            }
+        })
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster.  Default from the configuration file."),
+        )
+        .arg(
+            fee_payer_arg()
+                .short("p")
+                .global(true)
+        )
+        .arg(
+            Arg::with_name("owner")
+                .long("owner")
+                .validator(is_valid_signer)
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .global(true)
+                .help(
+                    "Owner of the token accounts to trade, deposit, or withdraw from. \
+                     Defaults to the client keypair.",
+                ),
+        )
+        .arg(
+            Arg::with_name("swap_program_id")
+                .long("program")
+                .validator(is_pubkey)
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .required(true)
+                .default_value(default_swap_program_id)
+                .help("Token swap program ID"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .takes_value(false)
+                .global(true)
+                .help("Show additional information"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .takes_value(false)
+                .global(true)
+                .help("Simulate transaction instead of executing"),
+        )
+        .subcommand(
+            SubCommand::with_name("create-pool")
+                .about("Create a new pool, funded from the owner's token accounts")
+                .arg(
+                    Arg::with_name("token_a")
+                        .long("token-a")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Token A account to deposit the initial liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("token_b")
+                        .long("token-b")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Token B account to deposit the initial liquidity from"),
+                )
+                .arg(
+                    Arg::with_name("amount_a")
+                        .long("amount-a")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Initial amount of token A in the pool"),
+                )
+                .arg(
+                    Arg::with_name("amount_b")
+                        .long("amount-b")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Initial amount of token B in the pool"),
+                )
+                .arg(
+                    Arg::with_name("fee_owner")
+                        .long("fee-owner")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Owner of the pool fee account.  Defaults to the owner."),
+                )
+                .arg(
+                    Arg::with_name("admin_authority")
+                        .long("admin")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Admin authority that can change the fees, pause the pool, and ramp its amplification coefficient"),
+                )
+                .arg(
+                    Arg::with_name("pool_decimals")
+                        .long("pool-decimals")
+                        .validator(is_parsable::<u8>)
+                        .value_name("DECIMALS")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("2")
+                        .help("Number of base 10 digits to the right of the decimal place of pool tokens"),
+                )
+                .arg(
+                    Arg::with_name("curve")
+                        .long("curve")
+                        .value_name("CURVE")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(&[
+                            "constant-product",
+                            "constant-price",
+                            "stable",
+                            "offset",
+                            "weighted",
+                            "concentrated-liquidity",
+                        ])
+                        .default_value("constant-product")
+                        .help("Trading curve of the pool"),
+                )
+                .arg(
+                    Arg::with_name("token_b_price")
+                        .long("token-b-price")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required_if("curve", "constant-price")
+                        .help("Constant price curve: amount of token A required to get 1 token B, in base units"),
+                )
+                .arg(
+                    Arg::with_name("amp")
+                        .long("amp")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required_if("curve", "stable")
+                        .help("Stable curve: amplification coefficient"),
+                )
+                .arg(
+                    Arg::with_name("token_b_offset")
+                        .long("token-b-offset")
+                        .validator(is_parsable::<u64>)
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required_if("curve", "offset")
+                        .help("Offset curve: amount of token B added to the pool balance in the invariant, in base units"),
+                )
+                .arg(
+                    Arg::with_name("token_a_weight")
+                        .long("token-a-weight")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required_if("curve", "weighted")
+                        .help("Weighted curve: weight of token A"),
+                )
+                .arg(
+                    Arg::with_name("token_b_weight")
+                        .long("token-b-weight")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required_if("curve", "weighted")
+                        .help("Weighted curve: weight of token B"),
+                )
+                .arg(
+                    Arg::with_name("lower_price")
+                        .long("lower-price")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required_if("curve", "concentrated-liquidity")
+                        .help("Concentrated liquidity curve: lower bound of the price of token A in token B, in base units, scaled by 10^9"),
+                )
+                .arg(
+                    Arg::with_name("upper_price")
+                        .long("upper-price")
+                        .validator(is_parsable::<u64>)
+                        .value_name("INTEGER")
+                        .takes_value(true)
+                        .required_if("curve", "concentrated-liquidity")
+                        .help("Concentrated liquidity curve: upper bound of the price of token A in token B, in base units, scaled by 10^9"),
+                )
+                .arg(
+                    Arg::with_name("trade_fee")
+                        .long("trade-fee")
+                        .validator(is_fraction)
+                        .value_name("NUMERATOR/DENOMINATOR")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("25/10000")
+                        .help("Fee on the amount traded, kept in the pool for liquidity providers"),
+                )
+                .arg(
+                    Arg::with_name("owner_trade_fee")
+                        .long("owner-trade-fee")
+                        .validator(is_fraction)
+                        .value_name("NUMERATOR/DENOMINATOR")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("5/10000")
+                        .help("Fee on the amount traded, minted as pool tokens to the pool fee account"),
+                )
+                .arg(
+                    Arg::with_name("owner_withdraw_fee")
+                        .long("owner-withdraw-fee")
+                        .validator(is_fraction)
+                        .value_name("NUMERATOR/DENOMINATOR")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("0/0")
+                        .help("Fee on the pool tokens withdrawn, transferred to the pool fee account"),
+                )
+                .arg(
+                    Arg::with_name("host_fee")
+                        .long("host-fee")
+                        .validator(is_fraction)
+                        .value_name("NUMERATOR/DENOMINATOR")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("20/100")
+                        .help("Share of the owner trade fee given to the host of the swap"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("Show the state of a pool")
+                .arg(pool_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("swap")
+                .about("Swap one token of a pool for the other")
+                .arg(pool_arg.clone())
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Token account to trade from"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Token account to receive the other token"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Amount of source token to trade"),
+                )
+                .arg(
+                    Arg::with_name("minimum_amount_out")
+                        .long("minimum-amount-out")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .help("Fail without sending the transaction if the quoted amount of destination token is lower"),
+                )
+                .arg(
+                    Arg::with_name("host_fee_account")
+                        .long("host-fee-account")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Pool token account receiving the host fee"),
+                )
+                .arg(slippage_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Deposit both tokens of a pool, or only one of them, for pool tokens")
+                .arg(pool_arg.clone())
+                .arg(
+                    Arg::with_name("pool_token_account")
+                        .long("pool-token-account")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Pool token account to receive the pool tokens"),
+                )
+                .arg(
+                    Arg::with_name("pool_token_amount")
+                        .long("pool-token-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required_unless("amount")
+                        .conflicts_with("amount")
+                        .requires_all(&["token_a", "token_b"])
+                        .help("Deposit both tokens for this amount of pool tokens"),
+                )
+                .arg(
+                    Arg::with_name("token_a")
+                        .long("token-a")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token A account to deposit from, when depositing both tokens"),
+                )
+                .arg(
+                    Arg::with_name("token_b")
+                        .long("token-b")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token B account to deposit from, when depositing both tokens"),
+                )
+                .arg(
+                    Arg::with_name("maximum_token_a_amount")
+                        .long("maximum-token-a-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("pool_token_amount")
+                        .help("Fail without sending the transaction if the quoted amount of token A is higher"),
+                )
+                .arg(
+                    Arg::with_name("maximum_token_b_amount")
+                        .long("maximum-token-b-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("pool_token_amount")
+                        .help("Fail without sending the transaction if the quoted amount of token B is higher"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("source")
+                        .help("Deposit only this amount of one token"),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token A or B account to deposit from, when depositing one token"),
+                )
+                .arg(
+                    Arg::with_name("minimum_pool_token_amount")
+                        .long("minimum-pool-token-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("amount")
+                        .help("Fail without sending the transaction if the quoted amount of pool tokens is lower"),
+                )
+                .arg(slippage_arg.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw")
+                .about("Withdraw both tokens of a pool, or only one of them, for pool tokens")
+                .arg(pool_arg.clone())
+                .arg(
+                    Arg::with_name("pool_token_account")
+                        .long("pool-token-account")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Pool token account to withdraw the pool tokens from"),
+                )
+                .arg(
+                    Arg::with_name("pool_token_amount")
+                        .long("pool-token-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .required_unless("amount")
+                        .conflicts_with("amount")
+                        .requires_all(&["token_a", "token_b"])
+                        .help("Withdraw both tokens for this amount of pool tokens"),
+                )
+                .arg(
+                    Arg::with_name("token_a")
+                        .long("token-a")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token A account to receive token A, when withdrawing both tokens"),
+                )
+                .arg(
+                    Arg::with_name("token_b")
+                        .long("token-b")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token B account to receive token B, when withdrawing both tokens"),
+                )
+                .arg(
+                    Arg::with_name("minimum_token_a_amount")
+                        .long("minimum-token-a-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("pool_token_amount")
+                        .help("Fail without sending the transaction if the quoted amount of token A is lower"),
+                )
+                .arg(
+                    Arg::with_name("minimum_token_b_amount")
+                        .long("minimum-token-b-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("pool_token_amount")
+                        .help("Fail without sending the transaction if the quoted amount of token B is lower"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("destination")
+                        .help("Withdraw only this amount of one token"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .validator(is_pubkey)
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .help("Token A or B account to receive the token, when withdrawing one token"),
+                )
+                .arg(
+                    Arg::with_name("maximum_pool_token_amount")
+                        .long("maximum-pool-token-amount")
+                        .validator(is_amount)
+                        .value_name("DECIMAL_AMOUNT")
+                        .takes_value(true)
+                        .requires("amount")
+                        .help("Fail without sending the transaction if the quoted amount of pool tokens is higher"),
+                )
+                .arg(slippage_arg),
+        )
+        .subcommand(
+            SubCommand::with_name("list-pools")
+                .about("List the pools trading a pair of mints")
+                .arg(
+                    Arg::with_name("mint_a")
+                        .index(1)
+                        .validator(is_pubkey)
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Mint of one of the tokens"),
+                )
+                .arg(
+                    Arg::with_name("mint_b")
+                        .index(2)
+                        .validator(is_pubkey)
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Mint of the other token"),
+                ),
+        )
+}
+
+fn main() {
+    solana_logger::setup_with_default("solana=info");
+
+    let default_swap_program_id: &str = &spl_token_swap::id().to_string();
+
+    let matches = app(default_swap_program_id).get_matches();
+
+    let mut wallet_manager = None;
+    let config = {
+        let cli_config = if let Some(config_file) = matches.value_of("config_file") {
+            solana_cli_config::Config::load(config_file).unwrap_or_default()
+        } else {
+            solana_cli_config::Config::default()
+        };
+        let json_rpc_url = value_t!(matches, "json_rpc_url", String)
+            .unwrap_or_else(|_| cli_config.json_rpc_url.clone());
+
+        let fee_payer = signer_from_path(
+            &matches,
+            matches
+                .value_of("fee_payer")
+                .unwrap_or(&cli_config.keypair_path),
+            "fee_payer",
+            &mut wallet_manager,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
+        let owner = signer_from_path(
+            &matches,
+            matches
+                .value_of("owner")
+                .unwrap_or(&cli_config.keypair_path),
+            "owner",
+            &mut wallet_manager,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
+
+        let swap_program_id = pubkey_of(&matches, "swap_program_id").unwrap();
+        let verbose = matches.is_present("verbose");
+        let dry_run = matches.is_present("dry_run");
+
+        Config {
+            rpc_client: RpcClient::new_with_commitment(json_rpc_url, CommitmentConfig::confirmed()),
+            fee_payer,
+            owner,
+            swap_program_id,
+            verbose,
+            dry_run,
+        }
+    };
+
+    let _ = match matches.subcommand() {
+        ("create-pool", Some(arg_matches)) => {
+            let token_a = pubkey_of(arg_matches, "token_a").unwrap();
+            let token_b = pubkey_of(arg_matches, "token_b").unwrap();
+            let ui_amount_a = value_of(arg_matches, "amount_a").unwrap();
+            let ui_amount_b = value_of(arg_matches, "amount_b").unwrap();
+            let fee_owner =
+                pubkey_of(arg_matches, "fee_owner").unwrap_or_else(|| config.owner.pubkey());
+            let admin_authority = pubkey_of(arg_matches, "admin_authority");
+            let pool_decimals = value_of(arg_matches, "pool_decimals").unwrap();
+            let swap_curve = swap_curve_of(arg_matches);
+            let (trade_fee_numerator, trade_fee_denominator) =
+                fraction_of(arg_matches, "trade_fee").unwrap();
+            let (owner_trade_fee_numerator, owner_trade_fee_denominator) =
+                fraction_of(arg_matches, "owner_trade_fee").unwrap();
+            let (owner_withdraw_fee_numerator, owner_withdraw_fee_denominator) =
+                fraction_of(arg_matches, "owner_withdraw_fee").unwrap();
+            let (host_fee_numerator, host_fee_denominator) =
+                fraction_of(arg_matches, "host_fee").unwrap();
+
+            command_create_pool(
+                &config,
+                token_a,
+                token_b,
+                ui_amount_a,
+                ui_amount_b,
+                fee_owner,
+                admin_authority,
+                pool_decimals,
+                Fees {
+                    trade_fee_numerator,
+                    trade_fee_denominator,
+                    owner_trade_fee_numerator,
+                    owner_trade_fee_denominator,
+                    owner_withdraw_fee_numerator,
+                    owner_withdraw_fee_denominator,
+                    host_fee_numerator,
+                    host_fee_denominator,
+                },
+                swap_curve,
+            )
+        }
+        ("info", Some(arg_matches)) => {
+            let pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            command_info(&config, &pool_address)
+        }
+        ("swap", Some(arg_matches)) => {
+            let pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let source = pubkey_of(arg_matches, "source").unwrap();
+            let destination = pubkey_of(arg_matches, "destination").unwrap();
+            let ui_amount = value_of(arg_matches, "amount").unwrap();
+            let ui_minimum_amount_out = value_of(arg_matches, "minimum_amount_out");
+            let host_fee_account = pubkey_of(arg_matches, "host_fee_account");
+            let slippage_bps = slippage_bps_of(arg_matches, "slippage").unwrap();
+            command_swap(
+                &config,
+                &pool_address,
+                &source,
+                &destination,
+                ui_amount,
+                ui_minimum_amount_out,
+                host_fee_account,
+                slippage_bps,
+            )
+        }
+        ("deposit", Some(arg_matches)) => {
+            let pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let pool_token_account = pubkey_of(arg_matches, "pool_token_account").unwrap();
+            let slippage_bps = slippage_bps_of(arg_matches, "slippage").unwrap();
+            if let Some(ui_pool_token_amount) = value_of(arg_matches, "pool_token_amount") {
+                let token_a = pubkey_of(arg_matches, "token_a").unwrap();
+                let token_b = pubkey_of(arg_matches, "token_b").unwrap();
+                let ui_maximum_token_a_amount = value_of(arg_matches, "maximum_token_a_amount");
+                let ui_maximum_token_b_amount = value_of(arg_matches, "maximum_token_b_amount");
+                command_deposit_all_token_types(
+                    &config,
+                    &pool_address,
+                    &pool_token_account,
+                    ui_pool_token_amount,
+                    &token_a,
+                    &token_b,
+                    ui_maximum_token_a_amount,
+                    ui_maximum_token_b_amount,
+                    slippage_bps,
+                )
+            } else {
+                let ui_amount = value_of(arg_matches, "amount").unwrap();
+                let source = pubkey_of(arg_matches, "source").unwrap();
+                let ui_minimum_pool_token_amount =
+                    value_of(arg_matches, "minimum_pool_token_amount");
+                command_deposit_single_token_type(
+                    &config,
+                    &pool_address,
+                    &pool_token_account,
+                    ui_amount,
+                    &source,
+                    ui_minimum_pool_token_amount,
+                    slippage_bps,
+                )
+            }
+        }
+        ("withdraw", Some(arg_matches)) => {
+            let pool_address = pubkey_of(arg_matches, "pool").unwrap();
+            let pool_token_account = pubkey_of(arg_matches, "pool_token_account").unwrap();
+            let slippage_bps = slippage_bps_of(arg_matches, "slippage").unwrap();
+            if let Some(ui_pool_token_amount) = value_of(arg_matches, "pool_token_amount") {
+                let token_a = pubkey_of(arg_matches, "token_a").unwrap();
+                let token_b = pubkey_of(arg_matches, "token_b").unwrap();
+                let ui_minimum_token_a_amount = value_of(arg_matches, "minimum_token_a_amount");
+                let ui_minimum_token_b_amount = value_of(arg_matches, "minimum_token_b_amount");
+                command_withdraw_all_token_types(
+                    &config,
+                    &pool_address,
+                    &pool_token_account,
+                    ui_pool_token_amount,
+                    &token_a,
+                    &token_b,
+                    ui_minimum_token_a_amount,
+                    ui_minimum_token_b_amount,
+                    slippage_bps,
+                )
+            } else {
+                let ui_amount = value_of(arg_matches, "amount").unwrap();
+                let destination = pubkey_of(arg_matches, "destination").unwrap();
+                let ui_maximum_pool_token_amount =
+                    value_of(arg_matches, "maximum_pool_token_amount");
+                command_withdraw_single_token_type(
+                    &config,
+                    &pool_address,
+                    &pool_token_account,
+                    ui_amount,
+                    &destination,
+                    ui_maximum_pool_token_amount,
+                    slippage_bps,
+                )
+            }
+        }
+        ("list-pools", Some(arg_matches)) => {
+            let mint_a = pubkey_of(arg_matches, "mint_a").unwrap();
+            let mint_b = pubkey_of(arg_matches, "mint_b").unwrap();
+            command_list_pools(&config, &mint_a, &mint_b)
+        }
+        _ => unreachable!(),
+    }
+    .map_err(|err| {
+        eprintln!("{}", err);
+        exit(1);
+    });
+}
+
+// COMMANDS
+
+#[allow(clippy::too_many_arguments)]
+fn command_create_pool(
+    config: &Config,
+    source_token_a: Pubkey,
+    source_token_b: Pubkey,
+    ui_amount_a: f64,
+    ui_amount_b: f64,
+    fee_owner: Pubkey,
+    admin_authority: Option<Pubkey>,
+    pool_decimals: u8,
+    fees: Fees,
+    swap_curve: SwapCurve,
+) -> CommandResult {
+    fees.validate()?;
+    swap_curve.calculator.validate()?;
+
+    let token_a = get_token_account(config, &source_token_a)?;
+    let token_b = get_token_account(config, &source_token_b)?;
+    let mint_a = get_mint(config, &token_a.mint)?;
+    let mint_b = get_mint(config, &token_b.mint)?;
+    let amount_a = ui_amount_to_amount(ui_amount_a, mint_a.decimals);
+    let amount_b = ui_amount_to_amount(ui_amount_b, mint_b.decimals);
+    swap_curve.calculator.validate_supply(amount_a, amount_b)?;
+
+    let swap_keypair = Keypair::new();
+    let (authority, nonce) = Pubkey::find_program_address(
+        &[&swap_keypair.pubkey().to_bytes()[..32]],
+        &config.swap_program_id,
+    );
+    let swap_token_a_keypair = Keypair::new();
+    let swap_token_b_keypair = Keypair::new();
+    let pool_mint_keypair = Keypair::new();
+    let pool_fee_account_keypair = Keypair::new();
+    let pool_token_account_keypair = Keypair::new();
+
+    println!("Creating pool {}", swap_keypair.pubkey());
+    if config.verbose {
+        println!("Pool authority {}", authority);
+        println!("Creating token A account {}", swap_token_a_keypair.pubkey());
+        println!("Creating token B account {}", swap_token_b_keypair.pubkey());
+        println!("Creating pool mint {}", pool_mint_keypair.pubkey());
+        println!(
+            "Creating pool fee account {}",
+            pool_fee_account_keypair.pubkey()
+        );
+        println!(
+            "Creating pool token account {}",
+            pool_token_account_keypair.pubkey()
+        );
+    }
+
+    let swap_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(SwapVersion::LATEST_LEN)?;
+    let pool_mint_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(Mint::LEN)?;
+    let token_account_balance = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(Token::LEN)?;
+    let total_balance = swap_balance + pool_mint_balance + 5 * token_account_balance;
+
+    let mut token_instructions = vec![
+        create_account(
+            &config.fee_payer.pubkey(),
+            &swap_token_a_keypair.pubkey(),
+            token_account_balance,
+            Token::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_account(
+            &spl_token::id(),
+            &swap_token_a_keypair.pubkey(),
+            &token_a.mint,
+            &authority,
+        )?,
+        create_account(
+            &config.fee_payer.pubkey(),
+            &swap_token_b_keypair.pubkey(),
+            token_account_balance,
+            Token::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_account(
+            &spl_token::id(),
+            &swap_token_b_keypair.pubkey(),
+            &token_b.mint,
+            &authority,
+        )?,
+    ];
+    // a concentrated liquidity pool may start with only one of the tokens
+    if amount_a > 0 {
+        token_instructions.push(transfer(
+            &spl_token::id(),
+            &source_token_a,
+            &swap_token_a_keypair.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            amount_a,
+        )?);
+    }
+    if amount_b > 0 {
+        token_instructions.push(transfer(
+            &spl_token::id(),
+            &source_token_b,
+            &swap_token_b_keypair.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            amount_b,
+        )?);
+    }
+
+    let pool_instructions = vec![
+        create_account(
+            &config.fee_payer.pubkey(),
+            &pool_mint_keypair.pubkey(),
+            pool_mint_balance,
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_mint(
+            &spl_token::id(),
+            &pool_mint_keypair.pubkey(),
+            &authority,
+            None,
+            pool_decimals,
+        )?,
+        create_account(
+            &config.fee_payer.pubkey(),
+            &pool_fee_account_keypair.pubkey(),
+            token_account_balance,
+            Token::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_account(
+            &spl_token::id(),
+            &pool_fee_account_keypair.pubkey(),
+            &pool_mint_keypair.pubkey(),
+            &fee_owner,
+        )?,
+        create_account(
+            &config.fee_payer.pubkey(),
+            &pool_token_account_keypair.pubkey(),
+            token_account_balance,
+            Token::LEN as u64,
+            &spl_token::id(),
+        ),
+        initialize_account(
+            &spl_token::id(),
+            &pool_token_account_keypair.pubkey(),
+            &pool_mint_keypair.pubkey(),
+            &config.owner.pubkey(),
+        )?,
+    ];
+
+    // the swap account must be created in the same transaction as it is
+    // initialized, since initialize does not require its signature
+    let initialize_instruction = if let Some(admin_authority) = admin_authority {
+        initialize_with_admin(
+            &config.swap_program_id,
+            &spl_token::id(),
+            &swap_keypair.pubkey(),
+            &authority,
+            &swap_token_a_keypair.pubkey(),
+            &swap_token_b_keypair.pubkey(),
+            &pool_mint_keypair.pubkey(),
+            &pool_fee_account_keypair.pubkey(),
+            &pool_token_account_keypair.pubkey(),
+            &admin_authority,
+            nonce,
+            fees,
+            swap_curve,
+            Default::default(),
+        )?
+    } else {
+        initialize(
+            &config.swap_program_id,
+            &spl_token::id(),
+            &swap_keypair.pubkey(),
+            &authority,
+            &swap_token_a_keypair.pubkey(),
+            &swap_token_b_keypair.pubkey(),
+            &pool_mint_keypair.pubkey(),
+            &pool_fee_account_keypair.pubkey(),
+            &pool_token_account_keypair.pubkey(),
+            nonce,
+            fees,
+            swap_curve,
+        )?
+    };
+    let swap_instructions = vec![
+        create_account(
+            &config.fee_payer.pubkey(),
+            &swap_keypair.pubkey(),
+            swap_balance,
+            SwapVersion::LATEST_LEN as u64,
+            &config.swap_program_id,
+        ),
+        initialize_instruction,
+    ];
+
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    let mut token_transaction =
+        Transaction::new_with_payer(&token_instructions, Some(&config.fee_payer.pubkey()));
+    let mut pool_transaction =
+        Transaction::new_with_payer(&pool_instructions, Some(&config.fee_payer.pubkey()));
+    let mut swap_transaction =
+        Transaction::new_with_payer(&swap_instructions, Some(&config.fee_payer.pubkey()));
+    check_fee_payer_balance(
+        config,
+        total_balance
+            + fee_calculator.calculate_fee(token_transaction.message())
+            + fee_calculator.calculate_fee(pool_transaction.message())
+            + fee_calculator.calculate_fee(swap_transaction.message()),
+    )?;
+
+    let mut token_signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &swap_token_a_keypair,
+        &swap_token_b_keypair,
+    ];
+    unique_signers!(token_signers);
+    token_transaction.sign(&token_signers, recent_blockhash);
+    pool_transaction.sign(
+        &vec![
+            config.fee_payer.as_ref(),
+            &pool_mint_keypair,
+            &pool_fee_account_keypair,
+            &pool_token_account_keypair,
+        ],
+        recent_blockhash,
+    );
+    swap_transaction.sign(
+        &vec![config.fee_payer.as_ref(), &swap_keypair],
+        recent_blockhash,
+    );
+    send_transaction(config, token_transaction)?;
+    send_transaction(config, pool_transaction)?;
+    send_transaction(config, swap_transaction)?;
+    Ok(())
+}
+
+fn command_info(config: &Config, pool_address: &Pubkey) -> CommandResult {
+    let account_data = config.rpc_client.get_account_data(pool_address)?;
+    if let Ok(token_swap) = SwapVersion::unpack_weighted(&account_data) {
+        return command_weighted_info(config, pool_address, &token_swap);
+    }
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let swap = &pool.swap;
+    let mint_a = get_mint(config, swap.token_a_mint())?;
+    let mint_b = get_mint(config, swap.token_b_mint())?;
+    let pool_mint = get_mint(config, swap.pool_mint())?;
+
+    println!("Pool: {}", pool_address);
+    if config.verbose {
+        let authority = Pubkey::create_program_address(
+            &[&pool_address.to_bytes()[..32], &[swap.nonce()]],
+            &config.swap_program_id,
+        )?;
+        println!("Authority: {}", authority);
+        println!("Token Program: {}", swap.token_program_id());
+    }
+    println!("Token A Mint: {}", swap.token_a_mint());
+    println!("Token A Account: {}", swap.token_a_account());
+    println!(
+        "Token A Balance: {}",
+        amount_to_ui_amount(pool.token_a_amount, mint_a.decimals)
+    );
+    println!("Token B Mint: {}", swap.token_b_mint());
+    println!("Token B Account: {}", swap.token_b_account());
+    println!(
+        "Token B Balance: {}",
+        amount_to_ui_amount(pool.token_b_amount, mint_b.decimals)
+    );
+    println!("Pool Token Mint: {}", swap.pool_mint());
+    println!(
+        "Pool Token Supply: {}",
+        amount_to_ui_amount(pool.pool_mint_supply, pool_mint.decimals)
+    );
+    println!("Pool Fee Account: {}", swap.pool_fee_account());
+
+    println!("Curve: {:?}", swap.swap_curve().calculator);
+    if let Some(amp_ramp) = swap.amp_ramp().filter(|amp_ramp| amp_ramp.is_set()) {
+        println!(
+            "Amplification Ramp: {} to {}, from {} to {}, currently {}",
+            amp_ramp.initial_amp,
+            amp_ramp.target_amp,
+            amp_ramp.start_timestamp,
+            amp_ramp.stop_timestamp,
+            amp_ramp.amp(pool.clock.unix_timestamp)
+        );
+    }
+    match pool.spot_price() {
+        Some(spot_price) => println!(
+            "Spot Price: {} token B per token A",
+            ui_price(&spot_price, mint_a.decimals, mint_b.decimals)
+        ),
+        None => println!("Spot Price: none"),
+    }
+
+    let fees = swap.fees();
+    println!(
+        "Trade Fee: {}/{}",
+        fees.trade_fee_numerator, fees.trade_fee_denominator
+    );
+    if let Some(dynamic_fees) = swap.dynamic_fees().filter(|fees| fees.is_enabled()) {
+        let trade_fees = pool.trade_fees()?;
+        println!(
+            "Dynamic Trade Fee: up to {}/{} at a volatility of {} basis points, currently {}/{}",
+            dynamic_fees.max_trade_fee_numerator,
+            fees.trade_fee_denominator,
+            dynamic_fees.max_volatility,
+            trade_fees.trade_fee_numerator,
+            trade_fees.trade_fee_denominator
+        );
+    }
+    println!(
+        "Owner Trade Fee: {}/{}",
+        fees.owner_trade_fee_numerator, fees.owner_trade_fee_denominator
+    );
+    println!(
+        "Owner Withdraw Fee: {}/{}",
+        fees.owner_withdraw_fee_numerator, fees.owner_withdraw_fee_denominator
+    );
+    println!(
+        "Host Fee: {}/{}",
+        fees.host_fee_numerator, fees.host_fee_denominator
+    );
+
+    if let Some(admin_authority) = swap.admin_authority() {
+        println!("Admin Authority: {}", admin_authority);
+    }
+    println!("Paused: {}", swap.is_paused());
+    Ok(())
+}
+
+fn command_weighted_info(
+    config: &Config,
+    pool_address: &Pubkey,
+    token_swap: &WeightedSwapV1,
+) -> CommandResult {
+    let pool_mint = get_mint(config, &token_swap.pool_mint)?;
+
+    println!("Pool: {}", pool_address);
+    if config.verbose {
+        let authority = Pubkey::create_program_address(
+            &[&pool_address.to_bytes()[..32], &[token_swap.nonce]],
+            &config.swap_program_id,
+        )?;
+        println!("Authority: {}", authority);
+        println!("Token Program: {}", token_swap.token_program_id);
+    }
+    let total_weight = token_swap.total_weight();
+    for (index, token) in token_swap.tokens.iter().enumerate() {
+        let mint = get_mint(config, &token.mint)?;
+        let account = get_token_account(config, &token.account)?;
+        println!("Token {} Mint: {}", index, token.mint);
+        println!("Token {} Account: {}", index, token.account);
+        println!(
+            "Token {} Balance: {}",
+            index,
+            amount_to_ui_amount(account.amount, mint.decimals)
+        );
+        println!("Token {} Weight: {}/{}", index, token.weight, total_weight);
+    }
+    println!("Pool Token Mint: {}", token_swap.pool_mint);
+    println!(
+        "Pool Token Supply: {}",
+        amount_to_ui_amount(pool_mint.supply, pool_mint.decimals)
+    );
+    println!("Pool Fee Account: {}", token_swap.pool_fee_account);
+
+    let fees = &token_swap.fees;
+    println!(
+        "Trade Fee: {}/{}",
+        fees.trade_fee_numerator, fees.trade_fee_denominator
+    );
+    if token_swap.dynamic_fees.is_enabled() {
+        println!(
+            "Dynamic Trade Fee: up to {}/{} at a volatility of {} basis points",
+            token_swap.dynamic_fees.max_trade_fee_numerator,
+            fees.trade_fee_denominator,
+            token_swap.dynamic_fees.max_volatility
+        );
+    }
+    println!(
+        "Owner Trade Fee: {}/{}",
+        fees.owner_trade_fee_numerator, fees.owner_trade_fee_denominator
+    );
+    println!(
+        "Owner Withdraw Fee: {}/{}",
+        fees.owner_withdraw_fee_numerator, fees.owner_withdraw_fee_denominator
+    );
+    println!(
+        "Host Fee: {}/{}",
+        fees.host_fee_numerator, fees.host_fee_denominator
+    );
+
+    if token_swap.admin_authority != Pubkey::default() {
+        println!("Admin Authority: {}", token_swap.admin_authority);
+    }
+    println!("Paused: {}", token_swap.is_paused);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_swap(
+    config: &Config,
+    pool_address: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    ui_amount: f64,
+    ui_minimum_amount_out: Option<f64>,
+    host_fee_account: Option<Pubkey>,
+    slippage_bps: u64,
+) -> CommandResult {
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let source_account = get_token_account(config, source)?;
+    let destination_account = get_token_account(config, destination)?;
+    let trade_direction = trade_direction_of(&pool, &source_account.mint)?;
+    let (swap_source, swap_destination, destination_mint) = match trade_direction {
+        TradeDirection::AtoB => (
+            pool.swap.token_a_account(),
+            pool.swap.token_b_account(),
+            pool.swap.token_b_mint(),
+        ),
+        TradeDirection::BtoA => (
+            pool.swap.token_b_account(),
+            pool.swap.token_a_account(),
+            pool.swap.token_a_mint(),
+        ),
+    };
+    if destination_account.mint != *destination_mint {
+        return Err(format!(
+            "Destination account {} does not hold the other token of the pool",
+            destination
+        )
+        .into());
+    }
+    let source_decimals = get_mint(config, &source_account.mint)?.decimals;
+    let destination_decimals = get_mint(config, destination_mint)?.decimals;
+
+    let amount_in = ui_amount_to_amount(ui_amount, source_decimals);
+    let quote = pool.quote_swap_exact_in(amount_in, trade_direction)?;
+    let minimum_amount_out = lower_bound(
+        "amount out",
+        quote.amount_out,
+        ui_minimum_amount_out.map(|ui_amount| ui_amount_to_amount(ui_amount, destination_decimals)),
+        slippage_bps,
+        destination_decimals,
+    )?;
+
+    println!(
+        "Swapping {} for {}, at least {}",
+        amount_to_ui_amount(quote.amount_in, source_decimals),
+        amount_to_ui_amount(quote.amount_out, destination_decimals),
+        amount_to_ui_amount(minimum_amount_out, destination_decimals)
+    );
+    if config.verbose {
+        println!(
+            "Trade fee: {}, owner fee: {}",
+            amount_to_ui_amount(quote.trade_fee, source_decimals),
+            amount_to_ui_amount(quote.owner_fee, source_decimals)
+        );
+        if let Some(price_impact) = quote.price_impact {
+            println!("Price impact: {}%", precise_to_f64(&price_impact) * 100.0);
+        }
+    }
+
+    let user_transfer_authority = Keypair::new();
+    let instructions = vec![
+        approve(
+            &spl_token::id(),
+            source,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            amount_in,
+        )?,
+        swap(
+            &config.swap_program_id,
+            &spl_token::id(),
+            pool_address,
+            &authority_of(config, pool_address, &pool)?,
+            &user_transfer_authority.pubkey(),
+            source,
+            swap_source,
+            swap_destination,
+            destination,
+            pool.swap.pool_mint(),
+            pool.swap.pool_fee_account(),
+            host_fee_account.as_ref(),
+            Swap {
+                amount_in,
+                minimum_amount_out,
+            },
+        )?,
+        revoke(&spl_token::id(), source, &config.owner.pubkey(), &[])?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &user_transfer_authority,
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_deposit_all_token_types(
+    config: &Config,
+    pool_address: &Pubkey,
+    pool_token_account: &Pubkey,
+    ui_pool_token_amount: f64,
+    token_a: &Pubkey,
+    token_b: &Pubkey,
+    ui_maximum_token_a_amount: Option<f64>,
+    ui_maximum_token_b_amount: Option<f64>,
+    slippage_bps: u64,
+) -> CommandResult {
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let decimals_a = get_mint(config, pool.swap.token_a_mint())?.decimals;
+    let decimals_b = get_mint(config, pool.swap.token_b_mint())?.decimals;
+    let pool_decimals = get_mint(config, pool.swap.pool_mint())?.decimals;
+
+    let pool_token_amount = ui_amount_to_amount(ui_pool_token_amount, pool_decimals);
+    let quote = pool.quote_deposit_all_token_types(pool_token_amount)?;
+    let maximum_token_a_amount = upper_bound(
+        "token A amount",
+        quote.token_a_amount,
+        ui_maximum_token_a_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, decimals_a)),
+        slippage_bps,
+        decimals_a,
+    )?;
+    let maximum_token_b_amount = upper_bound(
+        "token B amount",
+        quote.token_b_amount,
+        ui_maximum_token_b_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, decimals_b)),
+        slippage_bps,
+        decimals_b,
+    )?;
+
+    println!(
+        "Depositing {} token A, at most {}, and {} token B, at most {}, for {} pool tokens",
+        amount_to_ui_amount(quote.token_a_amount, decimals_a),
+        amount_to_ui_amount(maximum_token_a_amount, decimals_a),
+        amount_to_ui_amount(quote.token_b_amount, decimals_b),
+        amount_to_ui_amount(maximum_token_b_amount, decimals_b),
+        amount_to_ui_amount(quote.pool_token_amount, pool_decimals)
+    );
+
+    let user_transfer_authority = Keypair::new();
+    let instructions = vec![
+        approve(
+            &spl_token::id(),
+            token_a,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            maximum_token_a_amount,
+        )?,
+        approve(
+            &spl_token::id(),
+            token_b,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            maximum_token_b_amount,
+        )?,
+        deposit_all_token_types(
+            &config.swap_program_id,
+            &spl_token::id(),
+            pool_address,
+            &authority_of(config, pool_address, &pool)?,
+            &user_transfer_authority.pubkey(),
+            token_a,
+            token_b,
+            pool.swap.token_a_account(),
+            pool.swap.token_b_account(),
+            pool.swap.pool_mint(),
+            pool_token_account,
+            DepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            },
+        )?,
+        revoke(&spl_token::id(), token_a, &config.owner.pubkey(), &[])?,
+        revoke(&spl_token::id(), token_b, &config.owner.pubkey(), &[])?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &user_transfer_authority,
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_deposit_single_token_type(
+    config: &Config,
+    pool_address: &Pubkey,
+    pool_token_account: &Pubkey,
+    ui_amount: f64,
+    source: &Pubkey,
+    ui_minimum_pool_token_amount: Option<f64>,
+    slippage_bps: u64,
+) -> CommandResult {
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let source_account = get_token_account(config, source)?;
+    let trade_direction = trade_direction_of(&pool, &source_account.mint)?;
+    let source_decimals = get_mint(config, &source_account.mint)?.decimals;
+    let pool_decimals = get_mint(config, pool.swap.pool_mint())?.decimals;
+
+    let source_token_amount = ui_amount_to_amount(ui_amount, source_decimals);
+    let quote =
+        pool.quote_deposit_single_token_type_exact_amount_in(source_token_amount, trade_direction)?;
+    let minimum_pool_token_amount = lower_bound(
+        "pool token amount",
+        quote.pool_token_amount,
+        ui_minimum_pool_token_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, pool_decimals)),
+        slippage_bps,
+        pool_decimals,
+    )?;
+
+    println!(
+        "Depositing {} for {} pool tokens, at least {}",
+        amount_to_ui_amount(source_token_amount, source_decimals),
+        amount_to_ui_amount(quote.pool_token_amount, pool_decimals),
+        amount_to_ui_amount(minimum_pool_token_amount, pool_decimals)
+    );
+
+    let user_transfer_authority = Keypair::new();
+    let instructions = vec![
+        approve(
+            &spl_token::id(),
+            source,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            source_token_amount,
+        )?,
+        deposit_single_token_type_exact_amount_in(
+            &config.swap_program_id,
+            &spl_token::id(),
+            pool_address,
+            &authority_of(config, pool_address, &pool)?,
+            &user_transfer_authority.pubkey(),
+            source,
+            pool.swap.token_a_account(),
+            pool.swap.token_b_account(),
+            pool.swap.pool_mint(),
+            pool_token_account,
+            DepositSingleTokenTypeExactAmountIn {
+                source_token_amount,
+                minimum_pool_token_amount,
+            },
+        )?,
+        revoke(&spl_token::id(), source, &config.owner.pubkey(), &[])?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &user_transfer_authority,
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn command_withdraw_all_token_types(
+    config: &Config,
+    pool_address: &Pubkey,
+    pool_token_account: &Pubkey,
+    ui_pool_token_amount: f64,
+    token_a: &Pubkey,
+    token_b: &Pubkey,
+    ui_minimum_token_a_amount: Option<f64>,
+    ui_minimum_token_b_amount: Option<f64>,
+    slippage_bps: u64,
+) -> CommandResult {
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let decimals_a = get_mint(config, pool.swap.token_a_mint())?.decimals;
+    let decimals_b = get_mint(config, pool.swap.token_b_mint())?.decimals;
+    let pool_decimals = get_mint(config, pool.swap.pool_mint())?.decimals;
+
+    let pool_token_amount = ui_amount_to_amount(ui_pool_token_amount, pool_decimals);
+    let from_pool_fee_account = pool_token_account == pool.swap.pool_fee_account();
+    let quote = pool.quote_withdraw_all_token_types(pool_token_amount, from_pool_fee_account)?;
+    let minimum_token_a_amount = lower_bound(
+        "token A amount",
+        quote.token_a_amount,
+        ui_minimum_token_a_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, decimals_a)),
+        slippage_bps,
+        decimals_a,
+    )?;
+    let minimum_token_b_amount = lower_bound(
+        "token B amount",
+        quote.token_b_amount,
+        ui_minimum_token_b_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, decimals_b)),
+        slippage_bps,
+        decimals_b,
+    )?;
+
+    println!(
+        "Withdrawing {} token A, at least {}, and {} token B, at least {}, for {} pool tokens",
+        amount_to_ui_amount(quote.token_a_amount, decimals_a),
+        amount_to_ui_amount(minimum_token_a_amount, decimals_a),
+        amount_to_ui_amount(quote.token_b_amount, decimals_b),
+        amount_to_ui_amount(minimum_token_b_amount, decimals_b),
+        amount_to_ui_amount(quote.pool_token_amount, pool_decimals)
+    );
+    if config.verbose {
+        println!(
+            "Withdraw fee: {} pool tokens",
+            amount_to_ui_amount(quote.withdraw_fee, pool_decimals)
+        );
+    }
+
+    let user_transfer_authority = Keypair::new();
+    let instructions = vec![
+        approve(
+            &spl_token::id(),
+            pool_token_account,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            pool_token_amount,
+        )?,
+        withdraw_all_token_types(
+            &config.swap_program_id,
+            &spl_token::id(),
+            pool_address,
+            &authority_of(config, pool_address, &pool)?,
+            &user_transfer_authority.pubkey(),
+            pool.swap.pool_mint(),
+            pool.swap.pool_fee_account(),
+            pool_token_account,
+            pool.swap.token_a_account(),
+            pool.swap.token_b_account(),
+            token_a,
+            token_b,
+            WithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            },
+        )?,
+        revoke(
+            &spl_token::id(),
+            pool_token_account,
+            &config.owner.pubkey(),
+            &[],
+        )?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &user_transfer_authority,
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_withdraw_single_token_type(
+    config: &Config,
+    pool_address: &Pubkey,
+    pool_token_account: &Pubkey,
+    ui_amount: f64,
+    destination: &Pubkey,
+    ui_maximum_pool_token_amount: Option<f64>,
+    slippage_bps: u64,
+) -> CommandResult {
+    let pool = get_pool(&config.rpc_client, pool_address)?;
+    let destination_account = get_token_account(config, destination)?;
+    let trade_direction = trade_direction_of(&pool, &destination_account.mint)?;
+    let destination_decimals = get_mint(config, &destination_account.mint)?.decimals;
+    let pool_decimals = get_mint(config, pool.swap.pool_mint())?.decimals;
+
+    let destination_token_amount = ui_amount_to_amount(ui_amount, destination_decimals);
+    let from_pool_fee_account = pool_token_account == pool.swap.pool_fee_account();
+    let quote = pool.quote_withdraw_single_token_type_exact_amount_out(
+        destination_token_amount,
+        trade_direction,
+        from_pool_fee_account,
+    )?;
+    let maximum_pool_token_amount = upper_bound(
+        "pool token amount",
+        quote.pool_token_amount,
+        ui_maximum_pool_token_amount.map(|ui_amount| ui_amount_to_amount(ui_amount, pool_decimals)),
+        slippage_bps,
+        pool_decimals,
+    )?;
+
+    println!(
+        "Withdrawing {} for {} pool tokens, at most {}",
+        amount_to_ui_amount(destination_token_amount, destination_decimals),
+        amount_to_ui_amount(quote.pool_token_amount, pool_decimals),
+        amount_to_ui_amount(maximum_pool_token_amount, pool_decimals)
+    );
+    if config.verbose {
+        println!(
+            "Withdraw fee: {} pool tokens",
+            amount_to_ui_amount(quote.withdraw_fee, pool_decimals)
+        );
+    }
+
+    let user_transfer_authority = Keypair::new();
+    let instructions = vec![
+        approve(
+            &spl_token::id(),
+            pool_token_account,
+            &user_transfer_authority.pubkey(),
+            &config.owner.pubkey(),
+            &[],
+            maximum_pool_token_amount,
+        )?,
+        withdraw_single_token_type_exact_amount_out(
+            &config.swap_program_id,
+            &spl_token::id(),
+            pool_address,
+            &authority_of(config, pool_address, &pool)?,
+            &user_transfer_authority.pubkey(),
+            pool.swap.pool_mint(),
+            pool.swap.pool_fee_account(),
+            pool_token_account,
+            pool.swap.token_a_account(),
+            pool.swap.token_b_account(),
+            destination,
+            WithdrawSingleTokenTypeExactAmountOut {
+                destination_token_amount,
+                maximum_pool_token_amount,
+            },
+        )?,
+        revoke(
+            &spl_token::id(),
+            pool_token_account,
+            &config.owner.pubkey(),
+            &[],
+        )?,
+    ];
+    let mut signers = vec![
+        config.fee_payer.as_ref(),
+        config.owner.as_ref(),
+        &user_transfer_authority,
+    ];
+    unique_signers!(signers);
+    let transaction = checked_transaction_with_signers(config, &instructions, &signers)?;
+    send_transaction(config, transaction)?;
+    Ok(())
+}
+
+fn command_list_pools(config: &Config, mint_a: &Pubkey, mint_b: &Pubkey) -> CommandResult {
+    let mut count = 0;
+    for filters in swap_account_filters() {
+        let accounts = config.rpc_client.get_program_accounts_with_config(
+            &config.swap_program_id,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            },
+        )?;
+        for (address, account) in accounts {
+            if let Some(listing) =
+                pool_listing(&address, &account.data, mint_a, mint_b, config.verbose)
+            {
+                count += 1;
+                print!("{}", listing);
+            }
+        }
+    }
+    if count == 0 {
+        println!("No pools found");
+    }
+    Ok(())
+}
+
+// HELPERS
+
+fn check_fee_payer_balance(config: &Config, required_balance: u64) -> Result<(), Error> {
+    let balance = config.rpc_client.get_balance(&config.fee_payer.pubkey())?;
+    if balance < required_balance {
+        Err(format!(
+            "Fee payer, {}, has insufficient balance: {} required, {} available",
+            config.fee_payer.pubkey(),
+            lamports_to_sol(required_balance),
+            lamports_to_sol(balance)
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+fn checked_transaction_with_signers<T: Signers>(
+    config: &Config,
+    instructions: &[Instruction],
+    signers: &T,
+) -> Result<Transaction, Error> {
+    let (recent_blockhash, fee_calculator) = config.rpc_client.get_recent_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&config.fee_payer.pubkey()),
+        signers,
+        recent_blockhash,
+    );
+
+    check_fee_payer_balance(config, fee_calculator.calculate_fee(transaction.message()))?;
+    Ok(transaction)
+}
+
+fn send_transaction(
+    config: &Config,
+    transaction: Transaction,
+) -> solana_client::client_error::Result<()> {
+    if config.dry_run {
+        let result = config.rpc_client.simulate_transaction(&transaction)?;
+        println!("Simulate result: {:?}", result);
+    } else {
+        let signature = config
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Signature: {}", signature);
+    }
+    Ok(())
+}
+
+fn get_token_account(config: &Config, address: &Pubkey) -> Result<Token, Error> {
+    let account_data = config.rpc_client.get_account_data(address)?;
+    Token::unpack(&account_data)
+        .map_err(|err| format!("Invalid token account {}: {}", address, err).into())
+}
+
+fn get_mint(config: &Config, address: &Pubkey) -> Result<Mint, Error> {
+    let account_data = config.rpc_client.get_account_data(address)?;
+    Mint::unpack(&account_data).map_err(|err| format!("Invalid mint {}: {}", address, err).into())
+}
+
+/// Authority of a pool, derived from its address and nonce
+fn authority_of(config: &Config, pool_address: &Pubkey, pool: &PoolState) -> Result<Pubkey, Error> {
+    Ok(Pubkey::create_program_address(
+        &[&pool_address.to_bytes()[..32], &[pool.swap.nonce()]],
+        &config.swap_program_id,
+    )?)
+}
+
+/// Direction of a trade, deposit, or withdrawal of the token of `mint`
+fn trade_direction_of(pool: &PoolState, mint: &Pubkey) -> Result<TradeDirection, Error> {
+    if mint == pool.swap.token_a_mint() {
+        Ok(TradeDirection::AtoB)
+    } else if mint == pool.swap.token_b_mint() {
+        Ok(TradeDirection::BtoA)
+    } else {
+        Err(format!("Mint {} is not one of the tokens of the pool", mint).into())
+    }
+}
+
+/// Lower bound to send for a quoted amount: the quote minus the slippage
+/// tolerance, or the user's minimum if higher.  A quote below the user's
+/// minimum fails before anything is sent.
+fn lower_bound(
+    name: &str,
+    quoted_amount: u64,
+    minimum_amount: Option<u64>,
+    slippage_bps: u64,
+    decimals: u8,
+) -> Result<u64, Error> {
+    let bound = u64::try_from(
+        u128::from(quoted_amount) * u128::from(BASIS_POINTS - slippage_bps)
+            / u128::from(BASIS_POINTS),
+    )?;
+    match minimum_amount {
+        Some(minimum_amount) if quoted_amount < minimum_amount => Err(format!(
+            "Quoted {} of {} is below the minimum of {}",
+            name,
+            amount_to_ui_amount(quoted_amount, decimals),
+            amount_to_ui_amount(minimum_amount, decimals)
+        )
+        .into()),
+        Some(minimum_amount) => Ok(cmp::max(bound, minimum_amount)),
+        None => Ok(bound),
+    }
+}
+
+/// Upper bound to send for a quoted amount: the quote plus the slippage
+/// tolerance, or the user's maximum if lower.  A quote above the user's
+/// maximum fails before anything is sent.
+fn upper_bound(
+    name: &str,
+    quoted_amount: u64,
+    maximum_amount: Option<u64>,
+    slippage_bps: u64,
+    decimals: u8,
+) -> Result<u64, Error> {
+    let bound = u64::try_from(
+        (u128::from(quoted_amount) * u128::from(BASIS_POINTS + slippage_bps)
+            + u128::from(BASIS_POINTS - 1))
+            / u128::from(BASIS_POINTS),
+    )
+    .unwrap_or(u64::MAX);
+    match maximum_amount {
+        Some(maximum_amount) if quoted_amount > maximum_amount => Err(format!(
+            "Quoted {} of {} is above the maximum of {}",
+            name,
+            amount_to_ui_amount(quoted_amount, decimals),
+            amount_to_ui_amount(maximum_amount, decimals)
+        )
+        .into()),
+        Some(maximum_amount) => Ok(cmp::min(bound, maximum_amount)),
+        None => Ok(bound),
+    }
+}
+
+/// Filters for the swap accounts of each layout, one request per layout: the
+/// size of a swap account decides the version it is initialized with
+fn swap_account_filters() -> Vec<Vec<RpcFilterType>> {
+    [
+        (SwapVersion::V1_LEN, 1u8),
+        (SwapVersion::WEIGHTED_LEN, 2),
+        (SwapVersion::V2_LEN, 3),
+        (SwapVersion::V3_LEN, 4),
+        (SwapVersion::LATEST_LEN, 5),
+    ]
+    .iter()
+    .map(|(len, version)| {
+        vec![
+            RpcFilterType::DataSize(*len as u64),
+            RpcFilterType::Memcmp(Memcmp {
+                offset: 0, // 0 is the version
+                bytes: MemcmpEncodedBytes::Binary(bs58::encode([*version]).into_string()),
+                encoding: None,
+            }),
+        ]
+    })
+    .collect()
+}
+
+/// Lines listing a swap account if it trades both mints, weighted pools being
+/// listed if both mints are among their tokens
+fn pool_listing(
+    address: &Pubkey,
+    data: &[u8],
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    verbose: bool,
+) -> Option<String> {
+    // uninitialized accounts are skipped
+    let swap = decode_swap(data).ok()?;
+    let mints = (0..swap.num_tokens())
+        .filter_map(|index| swap.token_mint(index))
+        .collect::<Vec<_>>();
+    if mint_a == mint_b || !mints.contains(&mint_a) || !mints.contains(&mint_b) {
+        return None;
+    }
+    let mut listing = format!("Pool: {}\n", address);
+    match SwapVersion::unpack_weighted(data) {
+        Ok(token_swap) => listing += &format!("  Curve: Weighted {:?}\n", token_swap.weights()),
+        Err(_) => listing += &format!("  Curve: {:?}\n", swap.swap_curve().calculator),
+    }
+    if verbose {
+        if mints.len() == 2 {
+            listing += &format!("  Token A Mint: {}\n", mints[0]);
+            listing += &format!("  Token B Mint: {}\n", mints[1]);
+        } else {
+            for (index, mint) in mints.iter().enumerate() {
+                listing += &format!("  Token {} Mint: {}\n", index, mint);
+            }
+        }
+        listing += &format!("  Pool Token Mint: {}\n", swap.pool_mint());
+    }
+    let fees = swap.fees();
+    listing += &format!(
+        "  Trade Fee: {}/{}\n",
+        fees.trade_fee_numerator, fees.trade_fee_denominator
+    );
+    if swap.is_paused() {
+        listing += "  Paused\n";
+    }
+    Some(listing)
+}
+
+/// Price of token A in token B, adjusted for the decimals of their mints
+fn ui_price(price: &PreciseNumber, decimals_a: u8, decimals_b: u8) -> f64 {
+    precise_to_f64(price) * 10f64.powi(i32::from(decimals_a) - i32::from(decimals_b))
+}
+
+fn precise_to_f64(value: &PreciseNumber) -> f64 {
+    // split the value to keep the precision of large numbers
+    let one = U256::from(ONE);
+    let integer = value.value / one;
+    let fraction = value.value % one;
+    integer.as_u128() as f64 + fraction.as_u128() as f64 / ONE as f64
+}
+
+fn swap_curve_of(matches: &ArgMatches<'_>) -> SwapCurve {
+    match matches.value_of("curve").unwrap() {
+        "constant-product" => SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(ConstantProductCurve {}),
+        },
+        "constant-price" => SwapCurve {
+            curve_type: CurveType::ConstantPrice,
+            calculator: Box::new(ConstantPriceCurve {
+                token_b_price: value_of(matches, "token_b_price").unwrap(),
+            }),
+        },
+        "stable" => SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Box::new(StableCurve {
+                amp: value_of(matches, "amp").unwrap(),
+            }),
+        },
+        "offset" => SwapCurve {
+            curve_type: CurveType::Offset,
+            calculator: Box::new(OffsetCurve {
+                token_b_offset: value_of(matches, "token_b_offset").unwrap(),
+            }),
+        },
+        "weighted" => SwapCurve {
+            curve_type: CurveType::Weighted,
+            calculator: Box::new(WeightedCurve {
+                token_a_weight: value_of(matches, "token_a_weight").unwrap(),
+                token_b_weight: value_of(matches, "token_b_weight").unwrap(),
+            }),
+        },
+        "concentrated-liquidity" => SwapCurve {
+            curve_type: CurveType::ConcentratedLiquidity,
+            calculator: Box::new(ConcentratedLiquidityCurve {
+                lower_price: value_of(matches, "lower_price").unwrap(),
+                upper_price: value_of(matches, "upper_price").unwrap(),
+            }),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn parse_fraction(string: &str) -> Option<(u64, u64)> {
+    let mut parts = string.splitn(2, '/');
+    let numerator = parts.next()?.trim().parse().ok()?;
+    let denominator = parts.next()?.trim().parse().ok()?;
+    Some((numerator, denominator))
+}
+
+fn is_fraction(string: String) -> Result<(), String> {
+    parse_fraction(&string)
+        .map(|_| ())
+        .ok_or_else(|| format!("Unable to parse fraction: {}", string))
+}
+
+fn fraction_of(matches: &ArgMatches<'_>, name: &str) -> Option<(u64, u64)> {
+    matches.value_of(name).and_then(parse_fraction)
+}
+
+fn is_slippage(string: String) -> Result<(), String> {
+    match string.parse::<f64>() {
+        Ok(slippage) if (0.0..=100.0).contains(&slippage) => Ok(()),
+        _ => Err(format!(
+            "Slippage must be a percentage from 0 to 100: {}",
+            string
+        )),
+    }
+}
+
+fn slippage_bps_of(matches: &ArgMatches<'_>, name: &str) -> Option<u64> {
+    value_of::<f64>(matches, name).map(|slippage| (slippage * 100.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        spl_token_swap::state::{SwapV4, WeightedToken},
+    };
+
+    fn parse(args: &[&str]) -> Result<ArgMatches<'static>, clap::Error> {
+        app("SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw")
+            .get_matches_from_safe(["spl-token-swap"].iter().chain(args.iter()))
+    }
+
+    #[test]
+    fn parse_arguments() {
+        let mint_a = Pubkey::new_unique().to_string();
+        let mint_b = Pubkey::new_unique().to_string();
+
+        let matches = parse(&["list-pools", &mint_a, &mint_b]).unwrap();
+        let (name, arg_matches) = matches.subcommand();
+        assert_eq!(name, "list-pools");
+        let arg_matches = arg_matches.unwrap();
+        assert_eq!(
+            pubkey_of(arg_matches, "mint_a").unwrap().to_string(),
+            mint_a
+        );
+        assert_eq!(
+            pubkey_of(arg_matches, "mint_b").unwrap().to_string(),
+            mint_b
+        );
+        assert!(parse(&["list-pools", &mint_a]).is_err());
+        assert!(parse(&["list-pools", &mint_a, "not-a-pubkey"]).is_err());
+
+        let matches = parse(&[
+            "create-pool",
+            "--token-a",
+            &mint_a,
+            "--token-b",
+            &mint_b,
+            "--amount-a",
+            "1",
+            "--amount-b",
+            "2",
+            "--curve",
+            "stable",
+            "--amp",
+            "100",
+            "--trade-fee",
+            "3 / 1000",
+        ])
+        .unwrap();
+        let arg_matches = matches.subcommand_matches("create-pool").unwrap();
+        assert_eq!(fraction_of(arg_matches, "trade_fee"), Some((3, 1_000)));
+        assert_eq!(fraction_of(arg_matches, "host_fee"), Some((20, 100)));
+        let swap_curve = swap_curve_of(arg_matches);
+        assert_eq!(swap_curve.curve_type, CurveType::Stable);
+        // the curve parameters are required with their curve
+        assert!(parse(&[
+            "create-pool",
+            "--token-a",
+            &mint_a,
+            "--token-b",
+            &mint_b,
+            "--amount-a",
+            "1",
+            "--amount-b",
+            "2",
+            "--curve",
+            "stable",
+        ])
+        .is_err());
+        assert!(parse(&[
+            "create-pool",
+            "--token-a",
+            &mint_a,
+            "--token-b",
+            &mint_b,
+            "--amount-a",
+            "1",
+            "--amount-b",
+            "2",
+            "--trade-fee",
+            "3",
+        ])
+        .is_err());
+
+        let pool = Pubkey::new_unique().to_string();
+        let swap_args = |slippage: &'static str| {
+            vec![
+                "swap".to_string(),
+                pool.clone(),
+                "--source".to_string(),
+                mint_a.clone(),
+                "--destination".to_string(),
+                mint_b.clone(),
+                "--amount".to_string(),
+                "1".to_string(),
+                "--slippage".to_string(),
+                slippage.to_string(),
+            ]
+        };
+        let args = swap_args("0.5");
+        let matches = parse(&args.iter().map(String::as_str).collect::<Vec<_>>()).unwrap();
+        let arg_matches = matches.subcommand_matches("swap").unwrap();
+        assert_eq!(slippage_bps_of(arg_matches, "slippage"), Some(50));
+        let args = swap_args("101");
+        assert!(parse(&args.iter().map(String::as_str).collect::<Vec<_>>()).is_err());
+    }
+
+    #[test]
+    fn parse_fractions() {
+        assert_eq!(parse_fraction("25/10000"), Some((25, 10_000)));
+        assert_eq!(parse_fraction(" 1 / 2 "), Some((1, 2)));
+        assert_eq!(parse_fraction("1"), None);
+        assert_eq!(parse_fraction("1/2/3"), None);
+        assert_eq!(parse_fraction("-1/2"), None);
+    }
+
+    #[test]
+    fn list_pool_filters() {
+        let filters = swap_account_filters();
+        assert_eq!(filters.len(), 5);
+        assert!(filters.iter().any(|filters| filters
+            == &vec![
+                RpcFilterType::DataSize(SwapVersion::WEIGHTED_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary("3".to_string()),
+                    encoding: None,
+                }),
+            ]));
+        assert!(filters.iter().any(|filters| filters
+            == &vec![
+                RpcFilterType::DataSize(SwapVersion::LATEST_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary("6".to_string()),
+                    encoding: None,
+                }),
+            ]));
+    }
+
+    #[test]
+    fn list_pool_output() {
+        let address = Pubkey::new_unique();
+        let token_a_mint = Pubkey::new_unique();
+        let token_b_mint = Pubkey::new_unique();
+        let pool_mint = Pubkey::new_unique();
+        let mut data = vec![0; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(
+            SwapVersion::SwapV4(SwapV4 {
+                is_initialized: true,
+                token_a_mint,
+                token_b_mint,
+                pool_mint,
+                fees: Fees {
+                    trade_fee_numerator: 25,
+                    trade_fee_denominator: 10_000,
+                    ..Fees::default()
+                },
+                is_paused: true,
+                ..SwapV4::default()
+            }),
+            &mut data,
+        )
+        .unwrap();
+
+        assert_eq!(
+            pool_listing(&address, &data, &token_b_mint, &token_a_mint, false).unwrap(),
+            format!(
+                "Pool: {}\n  Curve: ConstantProductCurve\n  Trade Fee: 25/10000\n  Paused\n",
+                address
+            )
+        );
+        assert_eq!(
+            pool_listing(&address, &data, &token_a_mint, &token_b_mint, true).unwrap(),
+            format!(
+                "Pool: {}\n  Curve: ConstantProductCurve\n  Token A Mint: {}\n  Token B Mint: {}\n  Pool Token Mint: {}\n  Trade Fee: 25/10000\n  Paused\n",
+                address, token_a_mint, token_b_mint, pool_mint
+            )
+        );
+        let other_mint = Pubkey::new_unique();
+        assert!(pool_listing(&address, &data, &token_a_mint, &other_mint, false).is_none());
+        assert!(pool_listing(&address, &data, &token_a_mint, &token_a_mint, false).is_none());
+        assert!(pool_listing(
+            &address,
+            &[0; SwapVersion::LATEST_LEN],
+            &token_a_mint,
+            &token_b_mint,
+            false
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn list_weighted_pool_output() {
+        let address = Pubkey::new_unique();
+        let pool_mint = Pubkey::new_unique();
+        let tokens = (1..=3)
+            .map(|weight| WeightedToken {
+                account: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                weight,
+                balance: 1_000,
+            })
+            .collect::<Vec<_>>();
+        let mut data = vec![0; SwapVersion::WEIGHTED_LEN];
+        SwapVersion::pack(
+            SwapVersion::WeightedSwapV1(WeightedSwapV1 {
+                is_initialized: true,
+                pool_mint,
+                price_accumulators: vec![Default::default(); 2],
+                tokens: tokens.clone(),
+                ..WeightedSwapV1::default()
+            }),
+            &mut data,
+        )
+        .unwrap();
+
+        // any two of the tokens match the pool
+        assert_eq!(
+            pool_listing(&address, &data, &tokens[2].mint, &tokens[1].mint, false).unwrap(),
+            format!(
+                "Pool: {}\n  Curve: Weighted [1, 2, 3]\n  Trade Fee: 0/0\n",
+                address
+            )
+        );
+        assert_eq!(
+            pool_listing(&address, &data, &tokens[0].mint, &tokens[2].mint, true).unwrap(),
+            format!(
+                "Pool: {}\n  Curve: Weighted [1, 2, 3]\n  Token 0 Mint: {}\n  Token 1 Mint: {}\n  Token 2 Mint: {}\n  Pool Token Mint: {}\n  Trade Fee: 0/0\n",
+                address, tokens[0].mint, tokens[1].mint, tokens[2].mint, pool_mint
+            )
+        );
+    }
+}
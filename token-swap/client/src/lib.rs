@@ -0,0 +1,12 @@
+#![deny(missing_docs)]
+
+//! Off-chain client for the token swap program: fetch pools from a cluster
+//! and quote operations on them locally, exactly as the program would
+//! process them.
+
+pub mod oracle;
+pub mod pool;
+pub mod quote;
+
+/// Error returned when fetching or decoding pools
+pub type Error = Box<dyn std::error::Error>;
@@ -0,0 +1,147 @@
+//! Time-weighted average prices from the price accumulators of swaps
+
+use {
+    solana_program::clock::UnixTimestamp,
+    spl_math::{precise_number::PreciseNumber, uint::U256},
+    spl_token_swap::{curve::calculator::CurveCalculator, oracle::PriceAccumulator},
+};
+
+/// Average prices of the tokens in a swap over a period of time
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeWeightedPrices {
+    /// Average price of token A, denominated in token B
+    pub token_a_price: PreciseNumber,
+    /// Average price of token B, denominated in token A
+    pub token_b_price: PreciseNumber,
+}
+
+/// Compute the time-weighted average prices between two observations of the
+/// same swap's accumulator, `older` being the earlier one.
+///
+/// To observe the prices up to the current time, use `current_observation`
+/// for the newer one.
+pub fn time_weighted_average_prices(
+    older: &PriceAccumulator,
+    newer: &PriceAccumulator,
+) -> Option<TimeWeightedPrices> {
+    let elapsed = newer
+        .last_update_timestamp
+        .checked_sub(older.last_update_timestamp)?;
+    if elapsed <= 0 {
+        return None;
+    }
+    let elapsed = elapsed as u128;
+    let average = |older: u128, newer: u128| PreciseNumber {
+        value: U256::from(newer.wrapping_sub(older) / elapsed),
+    };
+    Some(TimeWeightedPrices {
+        token_a_price: average(
+            older.token_a_price_cumulative,
+            newer.token_a_price_cumulative,
+        ),
+        token_b_price: average(
+            older.token_b_price_cumulative,
+            newer.token_b_price_cumulative,
+        ),
+    })
+}
+
+/// Observe a swap's accumulator at the given time, accumulating the prices
+/// given by the token balances recorded by the swap, from
+/// `SwapState::token_balances`, since its last update, as the program would
+/// on the next change to the balances.  The balances of the liquidity accounts
+/// must not be used instead, since anyone can send tokens to them.
+pub fn current_observation(
+    accumulator: &PriceAccumulator,
+    calculator: &dyn CurveCalculator,
+    token_a_balance: u64,
+    token_b_balance: u64,
+    timestamp: UnixTimestamp,
+) -> PriceAccumulator {
+    let mut accumulator = accumulator.clone();
+    accumulator.update(
+        calculator,
+        u128::from(token_a_balance),
+        u128::from(token_b_balance),
+        timestamp,
+    );
+    accumulator
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        spl_token_swap::curve::{
+            constant_price::ConstantPriceCurve, constant_product::ConstantProductCurve,
+        },
+    };
+
+    #[test]
+    fn average_prices() {
+        let curve = ConstantProductCurve {};
+        let start = PriceAccumulator::new(100);
+
+        // price of A is 4 B for 10 seconds, then 1 B for 30 seconds
+        let first = current_observation(&start, &curve, 1_000, 4_000, 110);
+        let second = current_observation(&first, &curve, 2_000, 2_000, 140);
+
+        // (4 * 10 + 1 * 30) / 40 = 1.75
+        let prices = time_weighted_average_prices(&start, &second).unwrap();
+        let expected_a = PreciseNumber::new(7)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(4).unwrap())
+            .unwrap();
+        assert_eq!(prices.token_a_price, expected_a);
+        // (0.25 * 10 + 1 * 30) / 40 = 0.8125
+        let expected_b = PreciseNumber::new(13)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(16).unwrap())
+            .unwrap();
+        assert_eq!(prices.token_b_price, expected_b);
+
+        // only the last period
+        let prices = time_weighted_average_prices(&first, &second).unwrap();
+        assert_eq!(prices.token_a_price, PreciseNumber::new(1).unwrap());
+        assert_eq!(prices.token_b_price, PreciseNumber::new(1).unwrap());
+
+        // observations must be in order
+        assert!(time_weighted_average_prices(&second, &start).is_none());
+        assert!(time_weighted_average_prices(&second, &second).is_none());
+    }
+
+    #[test]
+    fn average_prices_with_overflow() {
+        let curve = ConstantPriceCurve { token_b_price: 2 };
+        let mut older = PriceAccumulator::new(0);
+        older.token_a_price_cumulative = u128::MAX - 1_000;
+        older.token_b_price_cumulative = u128::MAX;
+        let newer = current_observation(&older, &curve, 1_000, 1_000, 1_000);
+        assert!(newer.token_a_price_cumulative < older.token_a_price_cumulative);
+
+        let prices = time_weighted_average_prices(&older, &newer).unwrap();
+        let expected_a = PreciseNumber::new(1)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(2).unwrap())
+            .unwrap();
+        assert_eq!(prices.token_a_price, expected_a);
+        let expected_b = PreciseNumber::new(1)
+            .unwrap()
+            .checked_div(&expected_a)
+            .unwrap();
+        assert_eq!(prices.token_b_price, expected_b);
+    }
+
+    #[test]
+    fn average_prices_across_empty_pool() {
+        let curve = ConstantProductCurve {};
+        let start = PriceAccumulator::new(100);
+
+        // the pool is empty for 10 seconds, then the price of A is 4 B for 10
+        // seconds, which is averaged over the whole time
+        let empty = current_observation(&start, &curve, 0, 4_000, 110);
+        let filled = current_observation(&empty, &curve, 1_000, 4_000, 120);
+        let prices = time_weighted_average_prices(&start, &filled).unwrap();
+        assert_eq!(prices.token_a_price, PreciseNumber::new(4).unwrap());
+    }
+}
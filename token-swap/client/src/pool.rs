@@ -0,0 +1,151 @@
+//! Fetching and decoding token swap pools
+
+use {
+    crate::Error,
+    solana_client::rpc_client::RpcClient,
+    solana_program::{clock::Clock, program_pack::Pack, pubkey::Pubkey, sysvar},
+    spl_token::state::{Account, Mint},
+    spl_token_swap::state::{SwapState, SwapVersion},
+};
+
+/// Snapshot of everything the program reads to process an instruction on a
+/// pool: the swap state, the balances of its token accounts, the supply of
+/// its pool mint, and the clock used for amplification ramps and dynamic fees
+pub struct PoolState {
+    /// Decoded swap state, of any version
+    pub swap: Box<dyn SwapState>,
+    /// Balance of the swap's token A account
+    pub token_a_amount: u64,
+    /// Balance of the swap's token B account
+    pub token_b_amount: u64,
+    /// Supply of the swap's pool mint
+    pub pool_mint_supply: u64,
+    /// Clock at which to quote
+    pub clock: Clock,
+}
+
+impl PoolState {
+    /// Decode a pool from the data of its swap, token A, token B, and pool
+    /// mint accounts.  Weighted swaps of more than two tokens cannot be
+    /// quoted.
+    pub fn decode(
+        swap_data: &[u8],
+        token_a_data: &[u8],
+        token_b_data: &[u8],
+        pool_mint_data: &[u8],
+        clock: Clock,
+    ) -> Result<Self, Error> {
+        let swap = decode_swap(swap_data)?;
+        if swap.num_tokens() != 2 {
+            return Err(format!(
+                "Weighted swaps of {} tokens cannot be quoted",
+                swap.num_tokens()
+            )
+            .into());
+        }
+        let token_a = Account::unpack(token_a_data)
+            .map_err(|err| format!("Invalid token A account: {}", err))?;
+        let token_b = Account::unpack(token_b_data)
+            .map_err(|err| format!("Invalid token B account: {}", err))?;
+        let pool_mint =
+            Mint::unpack(pool_mint_data).map_err(|err| format!("Invalid pool mint: {}", err))?;
+        if token_a.mint != *swap.token_a_mint() {
+            return Err("Token A account does not match the swap".into());
+        }
+        if token_b.mint != *swap.token_b_mint() {
+            return Err("Token B account does not match the swap".into());
+        }
+        Ok(Self {
+            swap,
+            token_a_amount: token_a.amount,
+            token_b_amount: token_b.amount,
+            pool_mint_supply: pool_mint.supply,
+            clock,
+        })
+    }
+}
+
+/// Decode a swap account of any version.  Weighted swaps are seen through
+/// `SwapState` as a swap of their first two tokens, with every token
+/// available through `num_tokens` and `token_account`.
+pub fn decode_swap(data: &[u8]) -> Result<Box<dyn SwapState>, Error> {
+    let swap = SwapVersion::unpack(data).map_err(|err| format!("Invalid swap: {}", err))?;
+    if !swap.is_initialized() {
+        return Err("Swap is not initialized".into());
+    }
+    Ok(swap)
+}
+
+/// Fetch and decode a swap account
+pub fn get_swap(
+    rpc_client: &RpcClient,
+    swap_address: &Pubkey,
+) -> Result<Box<dyn SwapState>, Error> {
+    let account_data = rpc_client.get_account_data(swap_address)?;
+    decode_swap(&account_data).map_err(|err| format!("{}: {}", swap_address, err).into())
+}
+
+/// Fetch a pool, reading all of its accounts and the clock in the same
+/// request so that they are consistent with each other
+pub fn get_pool(rpc_client: &RpcClient, swap_address: &Pubkey) -> Result<PoolState, Error> {
+    let swap = get_swap(rpc_client, swap_address)?;
+    let addresses = [
+        *swap_address,
+        *swap.token_a_account(),
+        *swap.token_b_account(),
+        *swap.pool_mint(),
+        sysvar::clock::id(),
+    ];
+    let accounts = rpc_client.get_multiple_accounts(&addresses)?;
+    let mut account_data = vec![];
+    for (address, account) in addresses.iter().zip(accounts) {
+        let account = account.ok_or_else(|| format!("Account {} not found", address))?;
+        account_data.push(account.data);
+    }
+    let clock = bincode::deserialize::<Clock>(&account_data[4])
+        .map_err(|err| format!("Invalid clock: {}", err))?;
+    PoolState::decode(
+        &account_data[0],
+        &account_data[1],
+        &account_data[2],
+        &account_data[3],
+        clock,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        spl_token_swap::state::{WeightedSwapV1, WeightedToken},
+    };
+
+    #[test]
+    fn decode_weighted_swap() {
+        let tokens = (1..=3)
+            .map(|weight| WeightedToken {
+                account: Pubkey::new_unique(),
+                mint: Pubkey::new_unique(),
+                weight,
+                balance: 1_000,
+            })
+            .collect::<Vec<_>>();
+        let mut data = vec![0; SwapVersion::WEIGHTED_LEN];
+        SwapVersion::pack(
+            SwapVersion::WeightedSwapV1(WeightedSwapV1 {
+                is_initialized: true,
+                price_accumulators: vec![Default::default(); 2],
+                tokens: tokens.clone(),
+                ..WeightedSwapV1::default()
+            }),
+            &mut data,
+        )
+        .unwrap();
+
+        let swap = decode_swap(&data).unwrap();
+        assert_eq!(swap.num_tokens(), 3);
+        assert_eq!(*swap.token_a_account(), tokens[0].account);
+        assert_eq!(swap.token_account(2), Some(&tokens[2].account));
+        assert!(PoolState::decode(&data, &[], &[], &[], Clock::default()).is_err());
+    }
+}
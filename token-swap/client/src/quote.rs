@@ -0,0 +1,603 @@
+//! Quotes for operations on token swap pools, calculated off-chain with the
+//! same curves, fees, and rounding as the program
+
+use {
+    crate::pool::PoolState,
+    spl_math::precise_number::PreciseNumber,
+    spl_token_swap::{
+        curve::{
+            base::{CurveType, SwapCurve},
+            calculator::{CurveCalculator, RoundDirection, TradeDirection},
+            fees::Fees,
+            stable::StableCurve,
+        },
+        error::SwapError,
+    },
+    std::convert::TryFrom,
+};
+
+/// Quote for a swap of one token for the other
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapQuote {
+    /// Amount of source token taken from the user, which may be less than
+    /// the amount offered
+    pub amount_in: u64,
+    /// Amount of destination token given to the user
+    pub amount_out: u64,
+    /// Part of the source token amount kept in the pool for liquidity
+    /// providers
+    pub trade_fee: u64,
+    /// Part of the source token amount paid to the owner of the pool
+    pub owner_fee: u64,
+    /// Pool tokens minted for the owner fee, including the host fee
+    pub owner_fee_pool_tokens: u64,
+    /// Share of the owner fee pool tokens minted to the host fee account, if
+    /// one is given to the swap
+    pub host_fee_pool_tokens: u64,
+    /// Relative difference between the spot price before the swap and the
+    /// price obtained, fees excluded, or `None` if the curve has no spot price
+    /// for the pool balances
+    pub price_impact: Option<PreciseNumber>,
+}
+
+/// Quote for a deposit to or withdrawal from a pool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LiquidityQuote {
+    /// Amount of token A deposited or withdrawn
+    pub token_a_amount: u64,
+    /// Amount of token B deposited or withdrawn
+    pub token_b_amount: u64,
+    /// Pool tokens minted to the user on deposit, or taken from the user on
+    /// withdrawal, including the withdraw fee
+    pub pool_token_amount: u64,
+    /// Part of the pool tokens taken on withdrawal that is transferred to the
+    /// pool fee account instead of being burned
+    pub withdraw_fee: u64,
+}
+
+impl PoolState {
+    /// Curve of the swap at the quote's time, with the amplification
+    /// coefficient of a stable curve ramped, if it differs from the stored one
+    fn ramped_swap_curve(&self) -> Option<SwapCurve> {
+        match self.swap.amp_ramp() {
+            Some(amp_ramp) if self.swap.swap_curve().curve_type == CurveType::Stable => {
+                Some(SwapCurve {
+                    curve_type: CurveType::Stable,
+                    calculator: Box::new(StableCurve {
+                        amp: amp_ramp.amp(self.clock.unix_timestamp),
+                    }),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Fees for a trade at the quote's slot, with the dynamic trade fee
+    /// applied if the swap has one
+    pub fn trade_fees(&self) -> Result<Fees, SwapError> {
+        match (self.swap.dynamic_fees(), self.swap.volatility_accumulator()) {
+            (Some(dynamic_fees), Some(volatility_accumulator)) => {
+                let mut volatility_accumulator = volatility_accumulator.clone();
+                volatility_accumulator.decay(dynamic_fees, self.clock.slot);
+                dynamic_fees
+                    .fees(self.swap.fees(), volatility_accumulator.volatility)
+                    .ok_or(SwapError::FeeCalculationFailure)
+            }
+            _ => Ok(self.swap.fees().clone()),
+        }
+    }
+
+    /// Spot price of token A denominated in token B, ignoring fees
+    pub fn spot_price(&self) -> Option<PreciseNumber> {
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        swap_curve.calculator.spot_price(
+            u128::from(self.token_a_amount),
+            u128::from(self.token_b_amount),
+        )
+    }
+
+    /// Quote a `Swap` of exactly `amount_in` source tokens
+    pub fn quote_swap_exact_in(
+        &self,
+        amount_in: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<SwapQuote, SwapError> {
+        if self.swap.is_paused() {
+            return Err(SwapError::SwapPaused);
+        }
+        let (swap_source_amount, swap_destination_amount) = match trade_direction {
+            TradeDirection::AtoB => (self.token_a_amount, self.token_b_amount),
+            TradeDirection::BtoA => (self.token_b_amount, self.token_a_amount),
+        };
+        let fees = self.trade_fees()?;
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        let result = swap_curve
+            .swap(
+                u128::from(amount_in),
+                u128::from(swap_source_amount),
+                u128::from(swap_destination_amount),
+                trade_direction,
+                &fees,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+
+        let (swap_token_a_amount, swap_token_b_amount) = match trade_direction {
+            TradeDirection::AtoB => (
+                result.new_swap_source_amount,
+                result.new_swap_destination_amount,
+            ),
+            TradeDirection::BtoA => (
+                result.new_swap_destination_amount,
+                result.new_swap_source_amount,
+            ),
+        };
+        let owner_fee_pool_tokens = swap_curve
+            .withdraw_single_token_type_exact_out(
+                result.owner_fee,
+                swap_token_a_amount,
+                swap_token_b_amount,
+                u128::from(self.pool_mint_supply),
+                trade_direction,
+                &fees,
+            )
+            .ok_or(SwapError::FeeCalculationFailure)?;
+        let host_fee_pool_tokens = if owner_fee_pool_tokens > 0 {
+            self.swap
+                .fees()
+                .host_fee(owner_fee_pool_tokens)
+                .ok_or(SwapError::FeeCalculationFailure)?
+        } else {
+            0
+        };
+
+        let price_impact = result
+            .source_amount_swapped
+            .checked_sub(result.trade_fee)
+            .and_then(|amount| amount.checked_sub(result.owner_fee))
+            .and_then(|source_amount_less_fees| {
+                price_impact(
+                    swap_curve.calculator.as_ref(),
+                    u128::from(self.token_a_amount),
+                    u128::from(self.token_b_amount),
+                    trade_direction,
+                    source_amount_less_fees,
+                    result.destination_amount_swapped,
+                )
+            });
+        Ok(SwapQuote {
+            amount_in: to_u64(result.source_amount_swapped)?,
+            amount_out: to_u64(result.destination_amount_swapped)?,
+            trade_fee: to_u64(result.trade_fee)?,
+            owner_fee: to_u64(result.owner_fee)?,
+            owner_fee_pool_tokens: to_u64(owner_fee_pool_tokens)?,
+            host_fee_pool_tokens: to_u64(host_fee_pool_tokens)?,
+            price_impact,
+        })
+    }
+
+    /// Quote the smallest `Swap` that gives at least `amount_out` destination
+    /// tokens.  The program has no exact output swap, so the quote's
+    /// `amount_in` should be passed as the swap's amount in, and `amount_out`
+    /// as its minimum amount out.
+    pub fn quote_swap_exact_out(
+        &self,
+        amount_out: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<SwapQuote, SwapError> {
+        if self.swap.is_paused() {
+            return Err(SwapError::SwapPaused);
+        }
+        let quote_at_least = |amount_in: u64| {
+            self.quote_swap_exact_in(amount_in, trade_direction)
+                .ok()
+                .filter(|quote| quote.amount_out >= amount_out)
+        };
+
+        // find an upper bound, then bisect down to the smallest amount in
+        let mut high = 1u64;
+        let mut best = loop {
+            if let Some(quote) = quote_at_least(high) {
+                break quote;
+            }
+            high = high.checked_mul(2).ok_or(SwapError::ZeroTradingTokens)?;
+        };
+        let mut low = high / 2;
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            match quote_at_least(middle) {
+                Some(quote) => {
+                    high = middle;
+                    best = quote;
+                }
+                None => low = middle,
+            }
+        }
+        Ok(best)
+    }
+
+    /// Quote a `DepositAllTokenTypes` for `pool_token_amount` pool tokens.
+    /// Deposits into an empty pool mint the curve's initial supply instead.
+    pub fn quote_deposit_all_token_types(
+        &self,
+        pool_token_amount: u64,
+    ) -> Result<LiquidityQuote, SwapError> {
+        if self.swap.is_paused() {
+            return Err(SwapError::SwapPaused);
+        }
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        let calculator = &swap_curve.calculator;
+        if !calculator.allows_deposits() {
+            return Err(SwapError::UnsupportedCurveOperation);
+        }
+
+        let current_pool_mint_supply = u128::from(self.pool_mint_supply);
+        let (pool_token_amount, pool_mint_supply) = if current_pool_mint_supply > 0 {
+            (u128::from(pool_token_amount), current_pool_mint_supply)
+        } else {
+            (calculator.new_pool_supply(), calculator.new_pool_supply())
+        };
+        let results = calculator
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount,
+                pool_mint_supply,
+                u128::from(self.token_a_amount),
+                u128::from(self.token_b_amount),
+                RoundDirection::Ceiling,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let token_a_amount = to_u64(results.token_a_amount)?;
+        let token_b_amount = to_u64(results.token_b_amount)?;
+        if token_a_amount == 0 || token_b_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens);
+        }
+        Ok(LiquidityQuote {
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount: to_u64(pool_token_amount)?,
+            withdraw_fee: 0,
+        })
+    }
+
+    /// Quote a `WithdrawAllTokenTypes` of `pool_token_amount` pool tokens.
+    /// Withdrawals from the pool fee account pay no withdraw fee.
+    pub fn quote_withdraw_all_token_types(
+        &self,
+        pool_token_amount: u64,
+        from_pool_fee_account: bool,
+    ) -> Result<LiquidityQuote, SwapError> {
+        let withdraw_fee = if from_pool_fee_account {
+            0
+        } else {
+            self.swap
+                .fees()
+                .owner_withdraw_fee(u128::from(pool_token_amount))
+                .ok_or(SwapError::FeeCalculationFailure)?
+        };
+        let burn_pool_token_amount = u128::from(pool_token_amount)
+            .checked_sub(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (token_a_amount, token_b_amount) =
+            self.withdrawn_token_amounts(burn_pool_token_amount)?;
+        if token_a_amount == 0 && self.token_a_amount != 0 {
+            return Err(SwapError::ZeroTradingTokens);
+        }
+        if token_b_amount == 0 && self.token_b_amount != 0 {
+            return Err(SwapError::ZeroTradingTokens);
+        }
+        Ok(LiquidityQuote {
+            token_a_amount,
+            token_b_amount,
+            pool_token_amount,
+            withdraw_fee: to_u64(withdraw_fee)?,
+        })
+    }
+
+    /// Value of `pool_token_amount` pool tokens in tokens A and B: the share
+    /// of the pool's balances they withdraw, before the withdraw fee
+    pub fn pool_token_value(&self, pool_token_amount: u64) -> Result<(u64, u64), SwapError> {
+        self.withdrawn_token_amounts(u128::from(pool_token_amount))
+    }
+
+    /// Tokens A and B given for burning pool tokens, rounded down and capped
+    /// to the pool's balances
+    fn withdrawn_token_amounts(&self, pool_token_amount: u128) -> Result<(u64, u64), SwapError> {
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        let results = swap_curve
+            .calculator
+            .pool_tokens_to_trading_tokens(
+                pool_token_amount,
+                u128::from(self.pool_mint_supply),
+                u128::from(self.token_a_amount),
+                u128::from(self.token_b_amount),
+                RoundDirection::Floor,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        Ok((
+            std::cmp::min(self.token_a_amount, to_u64(results.token_a_amount)?),
+            std::cmp::min(self.token_b_amount, to_u64(results.token_b_amount)?),
+        ))
+    }
+
+    /// Quote a `DepositSingleTokenTypeExactAmountIn` of `source_token_amount`
+    /// tokens A for `TradeDirection::AtoB`, or tokens B for
+    /// `TradeDirection::BtoA`
+    pub fn quote_deposit_single_token_type_exact_amount_in(
+        &self,
+        source_token_amount: u64,
+        trade_direction: TradeDirection,
+    ) -> Result<LiquidityQuote, SwapError> {
+        if self.swap.is_paused() {
+            return Err(SwapError::SwapPaused);
+        }
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        let pool_mint_supply = u128::from(self.pool_mint_supply);
+        let pool_token_amount = if pool_mint_supply > 0 {
+            swap_curve
+                .deposit_single_token_type(
+                    u128::from(source_token_amount),
+                    u128::from(self.token_a_amount),
+                    u128::from(self.token_b_amount),
+                    pool_mint_supply,
+                    trade_direction,
+                    self.swap.fees(),
+                )
+                .ok_or(SwapError::ZeroTradingTokens)?
+        } else {
+            swap_curve.calculator.new_pool_supply()
+        };
+        let pool_token_amount = to_u64(pool_token_amount)?;
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens);
+        }
+        Ok(single_token_quote(
+            source_token_amount,
+            trade_direction,
+            pool_token_amount,
+            0,
+        ))
+    }
+
+    /// Quote a `WithdrawSingleTokenTypeExactAmountOut` of
+    /// `destination_token_amount` tokens A for `TradeDirection::AtoB`, or
+    /// tokens B for `TradeDirection::BtoA`.  Withdrawals from the pool fee
+    /// account pay no withdraw fee.
+    pub fn quote_withdraw_single_token_type_exact_amount_out(
+        &self,
+        destination_token_amount: u64,
+        trade_direction: TradeDirection,
+        from_pool_fee_account: bool,
+    ) -> Result<LiquidityQuote, SwapError> {
+        let ramped_swap_curve = self.ramped_swap_curve();
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| self.swap.swap_curve());
+        let burn_pool_token_amount = swap_curve
+            .withdraw_single_token_type_exact_out(
+                u128::from(destination_token_amount),
+                u128::from(self.token_a_amount),
+                u128::from(self.token_b_amount),
+                u128::from(self.pool_mint_supply),
+                trade_direction,
+                self.swap.fees(),
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+        let withdraw_fee = if from_pool_fee_account {
+            0
+        } else {
+            self.swap
+                .fees()
+                .owner_withdraw_fee(burn_pool_token_amount)
+                .ok_or(SwapError::FeeCalculationFailure)?
+        };
+        let pool_token_amount = burn_pool_token_amount
+            .checked_add(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let pool_token_amount = to_u64(pool_token_amount)?;
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens);
+        }
+        Ok(single_token_quote(
+            destination_token_amount,
+            trade_direction,
+            pool_token_amount,
+            to_u64(withdraw_fee)?,
+        ))
+    }
+}
+
+fn single_token_quote(
+    token_amount: u64,
+    trade_direction: TradeDirection,
+    pool_token_amount: u64,
+    withdraw_fee: u64,
+) -> LiquidityQuote {
+    let (token_a_amount, token_b_amount) = match trade_direction {
+        TradeDirection::AtoB => (token_amount, 0),
+        TradeDirection::BtoA => (0, token_amount),
+    };
+    LiquidityQuote {
+        token_a_amount,
+        token_b_amount,
+        pool_token_amount,
+        withdraw_fee,
+    }
+}
+
+/// Relative shortfall of the amount swapped out compared to swapping the
+/// amount in, less fees, at the spot price
+fn price_impact(
+    calculator: &dyn CurveCalculator,
+    swap_token_a_amount: u128,
+    swap_token_b_amount: u128,
+    trade_direction: TradeDirection,
+    source_amount_less_fees: u128,
+    destination_amount_swapped: u128,
+) -> Option<PreciseNumber> {
+    let token_a_price = calculator.spot_price(swap_token_a_amount, swap_token_b_amount)?;
+    let source_amount = PreciseNumber::new(source_amount_less_fees)?;
+    let spot_amount = match trade_direction {
+        TradeDirection::AtoB => source_amount.checked_mul(&token_a_price)?,
+        TradeDirection::BtoA => source_amount.checked_div(&token_a_price)?,
+    };
+    let destination_amount = PreciseNumber::new(destination_amount_swapped)?;
+    if destination_amount.greater_than_or_equal(&spot_amount) {
+        return PreciseNumber::new(0);
+    }
+    spot_amount
+        .checked_sub(&destination_amount)?
+        .checked_div(&spot_amount)
+}
+
+fn to_u64(val: u128) -> Result<u64, SwapError> {
+    u64::try_from(val).map_err(|_| SwapError::ConversionFailure)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_program::clock::Clock,
+        spl_token_swap::{
+            curve::stable::{AmpRamp, MIN_RAMP_DURATION},
+            state::SwapV4,
+        },
+    };
+
+    const TOKEN_A_AMOUNT: u64 = 1_000_000_000;
+    const TOKEN_B_AMOUNT: u64 = 1_200_000_000;
+
+    fn stable_pool(amp_ramp: AmpRamp, is_paused: bool, unix_timestamp: i64) -> PoolState {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10000,
+            owner_withdraw_fee_numerator: 30,
+            owner_withdraw_fee_denominator: 10000,
+            host_fee_numerator: 1,
+            host_fee_denominator: 5,
+        };
+        PoolState {
+            swap: Box::new(SwapV4 {
+                is_initialized: true,
+                fees,
+                swap_curve: SwapCurve {
+                    curve_type: CurveType::Stable,
+                    calculator: Box::new(StableCurve { amp: 100 }),
+                },
+                is_paused,
+                amp_ramp,
+                ..SwapV4::default()
+            }),
+            token_a_amount: TOKEN_A_AMOUNT,
+            token_b_amount: TOKEN_B_AMOUNT,
+            pool_mint_supply: 1_000_000_000,
+            clock: Clock {
+                unix_timestamp,
+                ..Clock::default()
+            },
+        }
+    }
+
+    #[test]
+    fn quote_during_amp_ramp() {
+        let amp_ramp = AmpRamp {
+            initial_amp: 100,
+            target_amp: 1_000,
+            start_timestamp: 1_000,
+            stop_timestamp: 1_000 + MIN_RAMP_DURATION,
+        };
+        let pool = stable_pool(amp_ramp, false, 1_000 + MIN_RAMP_DURATION / 2);
+        let quote = pool
+            .quote_swap_exact_in(1_000_000, TradeDirection::AtoB)
+            .unwrap();
+        let expected = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Box::new(StableCurve { amp: 550 }),
+        }
+        .swap(
+            u128::from(1_000_000u64),
+            u128::from(TOKEN_A_AMOUNT),
+            u128::from(TOKEN_B_AMOUNT),
+            TradeDirection::AtoB,
+            pool.swap.fees(),
+        )
+        .unwrap();
+        assert_eq!(
+            u128::from(quote.amount_out),
+            expected.destination_amount_swapped
+        );
+
+        let not_ramped = stable_pool(AmpRamp::default(), false, 1_000 + MIN_RAMP_DURATION / 2);
+        assert_ne!(
+            not_ramped
+                .quote_swap_exact_in(1_000_000, TradeDirection::AtoB)
+                .unwrap()
+                .amount_out,
+            quote.amount_out
+        );
+    }
+
+    #[test]
+    fn quote_paused() {
+        let pool = stable_pool(AmpRamp::default(), true, 0);
+        assert_eq!(
+            pool.quote_swap_exact_in(1_000, TradeDirection::AtoB),
+            Err(SwapError::SwapPaused)
+        );
+        assert_eq!(
+            pool.quote_swap_exact_out(1_000, TradeDirection::BtoA),
+            Err(SwapError::SwapPaused)
+        );
+        assert_eq!(
+            pool.quote_deposit_all_token_types(1_000),
+            Err(SwapError::SwapPaused)
+        );
+        assert_eq!(
+            pool.quote_deposit_single_token_type_exact_amount_in(1_000, TradeDirection::AtoB),
+            Err(SwapError::SwapPaused)
+        );
+
+        // withdrawals are always allowed
+        let quote = pool.quote_withdraw_all_token_types(10_000, false).unwrap();
+        assert_eq!(quote.withdraw_fee, 30);
+        assert_eq!(
+            pool.pool_token_value(10_000 - 30).unwrap(),
+            (quote.token_a_amount, quote.token_b_amount)
+        );
+        pool.quote_withdraw_single_token_type_exact_amount_out(1_000, TradeDirection::BtoA, true)
+            .unwrap();
+    }
+
+    #[test]
+    fn quote_price_impact() {
+        let pool = stable_pool(AmpRamp::default(), false, 0);
+        let small = pool
+            .quote_swap_exact_in(1_000, TradeDirection::BtoA)
+            .unwrap()
+            .price_impact
+            .unwrap();
+        let large = pool
+            .quote_swap_exact_in(500_000_000, TradeDirection::BtoA)
+            .unwrap()
+            .price_impact
+            .unwrap();
+        assert!(small.less_than(&large));
+        assert!(large.less_than(&PreciseNumber::new(1).unwrap()));
+    }
+}
@@ -0,0 +1,521 @@
+//! Check quotes against the program, running instruction sequences through
+//! the fuzzing harness
+
+use {
+    proptest::prelude::*,
+    solana_program::{clock::Clock, program_error::ProgramError},
+    spl_math::precise_number::PreciseNumber,
+    spl_token_swap::{
+        curve::{
+            base::{CurveType, SwapCurve},
+            calculator::TradeDirection,
+            concentrated_liquidity::{ConcentratedLiquidityCurve, PRICE_SCALE},
+            constant_price::ConstantPriceCurve,
+            constant_product::ConstantProductCurve,
+            fees::{DynamicFees, Fees},
+            offset::OffsetCurve,
+            stable::StableCurve,
+            weighted::WeightedCurve,
+        },
+        error::SwapError,
+        instruction::{
+            DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Swap, WithdrawAllTokenTypes,
+            WithdrawSingleTokenTypeExactAmountOut,
+        },
+    },
+    spl_token_swap_client::{pool::PoolState, quote::SwapQuote},
+    spl_token_swap_fuzz::{
+        native_account_data::NativeAccountData, native_token::get_token_balance,
+        native_token_swap::NativeTokenSwap,
+    },
+};
+
+const INITIAL_SWAP_TOKEN_A_AMOUNT: u64 = 100_000_000_000;
+const INITIAL_SWAP_TOKEN_B_AMOUNT: u64 = 300_000_000_000;
+
+const INITIAL_USER_TOKEN_A_AMOUNT: u64 = 100_000_000_000_000;
+const INITIAL_USER_TOKEN_B_AMOUNT: u64 = 300_000_000_000_000;
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Swap {
+        trade_direction: TradeDirection,
+        amount_in: u64,
+    },
+    DepositAllTokenTypes {
+        pool_token_amount: u64,
+    },
+    WithdrawAllTokenTypes {
+        percent: u64,
+    },
+    DepositSingleTokenTypeExactAmountIn {
+        trade_direction: TradeDirection,
+        source_token_amount: u64,
+    },
+    WithdrawSingleTokenTypeExactAmountOut {
+        trade_direction: TradeDirection,
+        destination_token_amount: u64,
+    },
+}
+
+fn trade_direction() -> impl Strategy<Value = TradeDirection> {
+    prop_oneof![Just(TradeDirection::AtoB), Just(TradeDirection::BtoA)]
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        (trade_direction(), 0..10_000_000_000u64).prop_map(|(trade_direction, amount_in)| {
+            Operation::Swap {
+                trade_direction,
+                amount_in,
+            }
+        }),
+        (0..10_000_000_000u64).prop_map(|pool_token_amount| {
+            Operation::DepositAllTokenTypes { pool_token_amount }
+        }),
+        (0..=100u64).prop_map(|percent| Operation::WithdrawAllTokenTypes { percent }),
+        (trade_direction(), 0..10_000_000_000u64).prop_map(
+            |(trade_direction, source_token_amount)| {
+                Operation::DepositSingleTokenTypeExactAmountIn {
+                    trade_direction,
+                    source_token_amount,
+                }
+            }
+        ),
+        (trade_direction(), 0..1_000_000_000u64).prop_map(
+            |(trade_direction, destination_token_amount)| {
+                Operation::WithdrawSingleTokenTypeExactAmountOut {
+                    trade_direction,
+                    destination_token_amount,
+                }
+            }
+        ),
+    ]
+}
+
+fn curve_type() -> impl Strategy<Value = CurveType> {
+    prop_oneof![
+        Just(CurveType::ConstantProduct),
+        Just(CurveType::ConstantPrice),
+        Just(CurveType::Stable),
+        Just(CurveType::Offset),
+        Just(CurveType::Weighted),
+        Just(CurveType::ConcentratedLiquidity),
+    ]
+}
+
+fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
+    SwapCurve {
+        curve_type,
+        calculator: match curve_type {
+            CurveType::ConstantProduct => Box::new(ConstantProductCurve),
+            CurveType::ConstantPrice => Box::new(ConstantPriceCurve {
+                token_b_price: 10_000_000,
+            }),
+            CurveType::Stable => Box::new(StableCurve { amp: 100 }),
+            CurveType::Offset => Box::new(OffsetCurve {
+                token_b_offset: 100_000_000_000,
+            }),
+            CurveType::Weighted => Box::new(WeightedCurve {
+                token_a_weight: 80,
+                token_b_weight: 20,
+            }),
+            CurveType::ConcentratedLiquidity => Box::new(ConcentratedLiquidityCurve {
+                lower_price: PRICE_SCALE / 4,
+                upper_price: PRICE_SCALE * 4,
+            }),
+        },
+    }
+}
+
+fn pool_state(token_swap: &NativeTokenSwap) -> PoolState {
+    PoolState::decode(
+        &token_swap.swap_account.data,
+        &token_swap.token_a_account.data,
+        &token_swap.token_b_account.data,
+        &token_swap.pool_mint_account.data,
+        Clock::default(),
+    )
+    .unwrap()
+}
+
+/// Balances of every account touched by the operations
+#[derive(Debug, PartialEq)]
+struct Balances {
+    user_token_a: u64,
+    user_token_b: u64,
+    user_pool: u64,
+    pool_fee: u64,
+    host_fee: u64,
+}
+
+impl Balances {
+    fn new(
+        token_swap: &NativeTokenSwap,
+        user_token_a_account: &NativeAccountData,
+        user_token_b_account: &NativeAccountData,
+        user_pool_account: &NativeAccountData,
+    ) -> Self {
+        Self {
+            user_token_a: get_token_balance(user_token_a_account),
+            user_token_b: get_token_balance(user_token_b_account),
+            user_pool: get_token_balance(user_pool_account),
+            pool_fee: get_token_balance(&token_swap.pool_fee_account),
+            host_fee: get_token_balance(&token_swap.pool_token_account),
+        }
+    }
+}
+
+fn check_swap_quote(pool: &PoolState, trade_direction: TradeDirection, quote: &SwapQuote) {
+    // the exact out quote never asks for more than the exact in quote
+    let exact_out_quote = pool
+        .quote_swap_exact_out(quote.amount_out, trade_direction)
+        .unwrap();
+    assert!(exact_out_quote.amount_out >= quote.amount_out);
+    assert!(exact_out_quote.amount_in <= quote.amount_in);
+
+    if let Some(price_impact) = &quote.price_impact {
+        assert!(price_impact.less_than_or_equal(&PreciseNumber::new(1).unwrap()));
+    }
+}
+
+fn run_operations(curve_type: CurveType, dynamic_fees: DynamicFees, operations: Vec<Operation>) {
+    let fees = Fees {
+        trade_fee_numerator: 25,
+        trade_fee_denominator: 10000,
+        owner_trade_fee_numerator: 5,
+        owner_trade_fee_denominator: 10000,
+        owner_withdraw_fee_numerator: 30,
+        owner_withdraw_fee_denominator: 10000,
+        host_fee_numerator: 1,
+        host_fee_denominator: 5,
+    };
+    let mut token_swap = NativeTokenSwap::new_with_dynamic_fees(
+        fees,
+        dynamic_fees,
+        get_swap_curve(curve_type),
+        INITIAL_SWAP_TOKEN_A_AMOUNT,
+        INITIAL_SWAP_TOKEN_B_AMOUNT,
+    );
+    let mut user_token_a_account = token_swap.create_token_a_account(INITIAL_USER_TOKEN_A_AMOUNT);
+    let mut user_token_b_account = token_swap.create_token_b_account(INITIAL_USER_TOKEN_B_AMOUNT);
+    let mut user_pool_account = token_swap.create_pool_account();
+
+    for operation in operations {
+        let pool = pool_state(&token_swap);
+        let before = Balances::new(
+            &token_swap,
+            &user_token_a_account,
+            &user_token_b_account,
+            &user_pool_account,
+        );
+        match operation {
+            Operation::Swap {
+                trade_direction,
+                amount_in,
+            } => {
+                let quote = pool.quote_swap_exact_in(amount_in, trade_direction);
+                let instruction = Swap {
+                    amount_in,
+                    minimum_amount_out: quote.as_ref().map_or(0, |quote| quote.amount_out),
+                };
+                let result = match trade_direction {
+                    TradeDirection::AtoB => token_swap.swap_a_to_b(
+                        &mut user_token_a_account,
+                        &mut user_token_b_account,
+                        instruction,
+                    ),
+                    TradeDirection::BtoA => token_swap.swap_b_to_a(
+                        &mut user_token_b_account,
+                        &mut user_token_a_account,
+                        instruction,
+                    ),
+                };
+                let quote = match quote {
+                    Ok(quote) => quote,
+                    Err(error) => {
+                        assert_eq!(result, Err(ProgramError::from(error)));
+                        continue;
+                    }
+                };
+                result.unwrap();
+                check_swap_quote(&pool, trade_direction, &quote);
+                let (user_token_a, user_token_b) = match trade_direction {
+                    TradeDirection::AtoB => (
+                        before.user_token_a - quote.amount_in,
+                        before.user_token_b + quote.amount_out,
+                    ),
+                    TradeDirection::BtoA => (
+                        before.user_token_a + quote.amount_out,
+                        before.user_token_b - quote.amount_in,
+                    ),
+                };
+                assert_eq!(
+                    Balances::new(
+                        &token_swap,
+                        &user_token_a_account,
+                        &user_token_b_account,
+                        &user_pool_account,
+                    ),
+                    Balances {
+                        user_token_a,
+                        user_token_b,
+                        user_pool: before.user_pool,
+                        pool_fee: before.pool_fee + quote.owner_fee_pool_tokens
+                            - quote.host_fee_pool_tokens,
+                        host_fee: before.host_fee + quote.host_fee_pool_tokens,
+                    }
+                );
+            }
+            Operation::DepositAllTokenTypes {
+                mut pool_token_amount,
+            } => {
+                // the harness never deposits a single pool token
+                if pool_token_amount == 1 {
+                    pool_token_amount = 2;
+                }
+                let quote = pool.quote_deposit_all_token_types(pool_token_amount);
+                let result = token_swap.deposit_all_token_types(
+                    &mut user_token_a_account,
+                    &mut user_token_b_account,
+                    &mut user_pool_account,
+                    DepositAllTokenTypes {
+                        pool_token_amount,
+                        maximum_token_a_amount: quote
+                            .as_ref()
+                            .map_or(u64::MAX, |quote| quote.token_a_amount),
+                        maximum_token_b_amount: quote
+                            .as_ref()
+                            .map_or(u64::MAX, |quote| quote.token_b_amount),
+                    },
+                );
+                let quote = match quote {
+                    // the user may not have enough tokens
+                    Ok(quote)
+                        if quote.token_a_amount <= before.user_token_a
+                            && quote.token_b_amount <= before.user_token_b =>
+                    {
+                        quote
+                    }
+                    Ok(_) => {
+                        assert!(result.is_err());
+                        continue;
+                    }
+                    Err(error) => {
+                        assert_eq!(result, Err(ProgramError::from(error)));
+                        continue;
+                    }
+                };
+                result.unwrap();
+                assert_eq!(
+                    Balances::new(
+                        &token_swap,
+                        &user_token_a_account,
+                        &user_token_b_account,
+                        &user_pool_account,
+                    ),
+                    Balances {
+                        user_token_a: before.user_token_a - quote.token_a_amount,
+                        user_token_b: before.user_token_b - quote.token_b_amount,
+                        user_pool: before.user_pool + quote.pool_token_amount,
+                        ..before
+                    }
+                );
+            }
+            Operation::WithdrawAllTokenTypes { percent } => {
+                let mut pool_token_amount = before.user_pool / 100 * percent;
+                // the harness never leaves a single pool token behind
+                if before.user_pool.saturating_sub(pool_token_amount) == 1 {
+                    pool_token_amount = before.user_pool;
+                }
+                let quote = pool.quote_withdraw_all_token_types(pool_token_amount, false);
+                let result = token_swap.withdraw_all_token_types(
+                    &mut user_pool_account,
+                    &mut user_token_a_account,
+                    &mut user_token_b_account,
+                    WithdrawAllTokenTypes {
+                        pool_token_amount,
+                        minimum_token_a_amount: quote
+                            .as_ref()
+                            .map_or(0, |quote| quote.token_a_amount),
+                        minimum_token_b_amount: quote
+                            .as_ref()
+                            .map_or(0, |quote| quote.token_b_amount),
+                    },
+                );
+                let quote = match quote {
+                    Ok(quote) => quote,
+                    Err(error) => {
+                        assert_eq!(result, Err(ProgramError::from(error)));
+                        continue;
+                    }
+                };
+                result.unwrap();
+                assert_eq!(
+                    Balances::new(
+                        &token_swap,
+                        &user_token_a_account,
+                        &user_token_b_account,
+                        &user_pool_account,
+                    ),
+                    Balances {
+                        user_token_a: before.user_token_a + quote.token_a_amount,
+                        user_token_b: before.user_token_b + quote.token_b_amount,
+                        user_pool: before.user_pool - quote.pool_token_amount,
+                        pool_fee: before.pool_fee + quote.withdraw_fee,
+                        ..before
+                    }
+                );
+            }
+            Operation::DepositSingleTokenTypeExactAmountIn {
+                trade_direction,
+                source_token_amount,
+            } => {
+                let quote = pool.quote_deposit_single_token_type_exact_amount_in(
+                    source_token_amount,
+                    trade_direction,
+                );
+                let instruction = DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount: quote
+                        .as_ref()
+                        .map_or(0, |quote| quote.pool_token_amount),
+                };
+                let result = match trade_direction {
+                    TradeDirection::AtoB => token_swap.deposit_single_token_type_exact_amount_in(
+                        &mut user_token_a_account,
+                        &mut user_pool_account,
+                        instruction,
+                    ),
+                    TradeDirection::BtoA => token_swap.deposit_single_token_type_exact_amount_in(
+                        &mut user_token_b_account,
+                        &mut user_pool_account,
+                        instruction,
+                    ),
+                };
+                let quote = match quote {
+                    // the harness always asks for at least two pool tokens, so
+                    // fewer fail the slippage check before any other
+                    Ok(quote) if quote.pool_token_amount >= 2 => quote,
+                    Ok(_) => {
+                        assert_eq!(result, Err(SwapError::ExceededSlippage.into()));
+                        continue;
+                    }
+                    Err(SwapError::ZeroTradingTokens)
+                        if result == Err(SwapError::ExceededSlippage.into()) =>
+                    {
+                        continue;
+                    }
+                    Err(error) => {
+                        assert_eq!(result, Err(ProgramError::from(error)));
+                        continue;
+                    }
+                };
+                result.unwrap();
+                assert_eq!(
+                    Balances::new(
+                        &token_swap,
+                        &user_token_a_account,
+                        &user_token_b_account,
+                        &user_pool_account,
+                    ),
+                    Balances {
+                        user_token_a: before.user_token_a - quote.token_a_amount,
+                        user_token_b: before.user_token_b - quote.token_b_amount,
+                        user_pool: before.user_pool + quote.pool_token_amount,
+                        ..before
+                    }
+                );
+            }
+            Operation::WithdrawSingleTokenTypeExactAmountOut {
+                trade_direction,
+                destination_token_amount,
+            } => {
+                let quote = pool.quote_withdraw_single_token_type_exact_amount_out(
+                    destination_token_amount,
+                    trade_direction,
+                    false,
+                );
+                let instruction = WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount: quote
+                        .as_ref()
+                        .map_or(u64::MAX, |quote| quote.pool_token_amount),
+                };
+                let result = match trade_direction {
+                    TradeDirection::AtoB => token_swap.withdraw_single_token_type_exact_amount_out(
+                        &mut user_pool_account,
+                        &mut user_token_a_account,
+                        instruction,
+                    ),
+                    TradeDirection::BtoA => token_swap.withdraw_single_token_type_exact_amount_out(
+                        &mut user_pool_account,
+                        &mut user_token_b_account,
+                        instruction,
+                    ),
+                };
+                let quote = match quote {
+                    // the user may not have enough pool tokens
+                    Ok(quote) if quote.pool_token_amount <= before.user_pool => quote,
+                    Ok(_) => {
+                        assert!(result.is_err());
+                        continue;
+                    }
+                    Err(error) => {
+                        assert_eq!(result, Err(ProgramError::from(error)));
+                        continue;
+                    }
+                };
+                result.unwrap();
+                assert_eq!(
+                    Balances::new(
+                        &token_swap,
+                        &user_token_a_account,
+                        &user_token_b_account,
+                        &user_pool_account,
+                    ),
+                    Balances {
+                        user_token_a: before.user_token_a + quote.token_a_amount,
+                        user_token_b: before.user_token_b + quote.token_b_amount,
+                        user_pool: before.user_pool - quote.pool_token_amount,
+                        pool_fee: before.pool_fee + quote.withdraw_fee,
+                        ..before
+                    }
+                );
+            }
+        }
+    }
+
+    // the value of the fee account's pool tokens is what it can withdraw
+    let pool = pool_state(&token_swap);
+    let pool_fee = get_token_balance(&token_swap.pool_fee_account);
+    if let Ok(quote) = pool.quote_withdraw_all_token_types(pool_fee, true) {
+        assert_eq!(
+            pool.pool_token_value(pool_fee).unwrap(),
+            (quote.token_a_amount, quote.token_b_amount)
+        );
+    }
+}
+
+proptest! {
+    #[test]
+    fn quotes_match_program(
+        curve_type in curve_type(),
+        operations in prop::collection::vec(operation(), 1..16),
+    ) {
+        run_operations(curve_type, DynamicFees::default(), operations);
+    }
+
+    #[test]
+    fn dynamic_fee_quotes_match_program(
+        curve_type in curve_type(),
+        operations in prop::collection::vec(operation(), 1..16),
+    ) {
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 100,
+            max_volatility: 1_000,
+            decay_period: 10,
+        };
+        run_operations(curve_type, dynamic_fees, operations);
+    }
+}
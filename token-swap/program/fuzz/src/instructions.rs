@@ -7,11 +7,13 @@ use {
         curve::{
             base::{CurveType, SwapCurve},
             calculator::TradeDirection,
+            concentrated_liquidity::{ConcentratedLiquidityCurve, PRICE_SCALE},
             constant_price::ConstantPriceCurve,
             constant_product::ConstantProductCurve,
             fees::Fees,
             offset::OffsetCurve,
             stable::StableCurve,
+            weighted::WeightedCurve,
         },
         error::SwapError,
         instruction::{
@@ -467,6 +469,14 @@ fn get_swap_curve(curve_type: CurveType) -> SwapCurve {
             CurveType::Offset => Box::new(OffsetCurve {
                 token_b_offset: 100_000_000_000,
             }),
+            CurveType::Weighted => Box::new(WeightedCurve {
+                token_a_weight: 80,
+                token_b_weight: 20,
+            }),
+            CurveType::ConcentratedLiquidity => Box::new(ConcentratedLiquidityCurve {
+                lower_price: PRICE_SCALE / 4,
+                upper_price: PRICE_SCALE * 4,
+            }),
         },
     }
 }
@@ -5,7 +5,10 @@ use crate::native_processor::do_process_instruction;
 use crate::native_token;
 
 use spl_token_swap::{
-    curve::{base::SwapCurve, fees::Fees},
+    curve::{
+        base::SwapCurve,
+        fees::{DynamicFees, Fees},
+    },
     instruction::{
         self, DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Swap,
         WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
@@ -46,6 +49,22 @@ impl NativeTokenSwap {
         swap_curve: SwapCurve,
         token_a_amount: u64,
         token_b_amount: u64,
+    ) -> Self {
+        Self::new_with_dynamic_fees(
+            fees,
+            DynamicFees::default(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        )
+    }
+
+    pub fn new_with_dynamic_fees(
+        fees: Fees,
+        dynamic_fees: DynamicFees,
+        swap_curve: SwapCurve,
+        token_a_amount: u64,
+        token_b_amount: u64,
     ) -> Self {
         let mut user_account = NativeAccountData::new(0, system_program::id());
         user_account.is_signer = true;
@@ -76,7 +95,7 @@ impl NativeTokenSwap {
             token_b_amount,
         );
 
-        let init_instruction = instruction::initialize(
+        let init_instruction = instruction::initialize_with_dynamic_fees(
             &spl_token_swap::id(),
             &spl_token::id(),
             &swap_account.key,
@@ -89,6 +108,7 @@ impl NativeTokenSwap {
             nonce,
             fees.clone(),
             swap_curve.clone(),
+            dynamic_fees,
         )
         .unwrap();
 
@@ -1,197 +1,446 @@
-use pyo3::prelude::*;
-use pyo3::types::PyTuple;
-use std::fs::File;
-use std::io::prelude::*;
+//! Reference model of the Curve stable swap math, ported from
+//! https://github.com/curvefi/curve-contract/blob/master/tests/simulation.py
+//!
+//! The model works on any number of coins, with target prices and fees, in
+//! 256-bit integers, following the Python integer operations step by step.
+//! Where the Python model would raise, on overflow, underflow, division by
+//! zero or an index out of range, the model returns `None`.
 
-const FILE_NAME: &str = "simulation.py";
-const FILE_PATH: &str = "sim/simulation.py";
-const MODULE_NAME: &str = "simulation";
+use {spl_math::uint::U256, std::convert::TryFrom};
 
 const DEFAULT_POOL_TOKENS: u128 = 0;
-const DEFAULT_TARGET_PRICE: u128 = 1000000000000000000;
+const DEFAULT_TARGET_PRICE: u128 = 1_000_000_000_000_000_000;
+/// Precision of the target prices
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000_000_000;
+/// Denominator of the fee
+pub const FEE_DENOMINATOR: u128 = 10_000_000_000;
+/// Numerator of the fee commonly used with the model
 pub const MODEL_FEE_NUMERATOR: u128 = 1;
+/// Denominator of the fee commonly used with the model
 pub const MODEL_FEE_DENOMINATOR: u128 = 1000;
+/// Iterations after which the approximations give up converging
+const MAX_ITERATIONS: usize = 1000;
 
+/// Curve pool with its balances and parameters
+#[derive(Clone, Debug, PartialEq)]
 pub struct StableSwapModel {
-    py_src: String,
+    /// Amplification coefficient
     pub amp_factor: u128,
+    /// Balances of the coins
     pub balances: Vec<u128>,
+    /// Number of coins
     pub n_coins: u8,
+    /// Fee taken from the output of exchanges, over `FEE_DENOMINATOR`
     pub fee: u128,
+    /// Target price of each coin, over `PRICE_PRECISION`
     pub target_prices: Vec<u128>,
+    /// Supply of pool tokens
     pub pool_tokens: u128,
 }
 
 impl StableSwapModel {
+    /// Create a pool without fees or pool tokens, with all target prices at 1
     pub fn new(amp_factor: u128, balances: Vec<u128>, n_coins: u8) -> StableSwapModel {
-        let mut src_file = File::open(FILE_PATH).unwrap();
-        let mut src_content = String::new();
-        let _ = src_file.read_to_string(&mut src_content);
-
-        Self {
-            py_src: src_content,
-            amp_factor,
-            balances,
-            n_coins,
-            fee: 0,
-            target_prices: vec![DEFAULT_TARGET_PRICE, DEFAULT_TARGET_PRICE],
-            pool_tokens: DEFAULT_POOL_TOKENS,
-        }
+        Self::new_with_pool_tokens(amp_factor, balances, n_coins, DEFAULT_POOL_TOKENS)
     }
 
+    /// Create a pool without fees, with all target prices at 1 and the given
+    /// supply of pool tokens
     pub fn new_with_pool_tokens(
         amp_factor: u128,
         balances: Vec<u128>,
         n_coins: u8,
         pool_token_amount: u128,
     ) -> StableSwapModel {
-        let mut src_file = File::open(FILE_PATH).unwrap();
-        let mut src_content = String::new();
-        let _ = src_file.read_to_string(&mut src_content);
-
         Self {
-            py_src: src_content,
             amp_factor,
             balances,
             n_coins,
             fee: 0,
-            target_prices: vec![DEFAULT_TARGET_PRICE, DEFAULT_TARGET_PRICE],
+            target_prices: vec![DEFAULT_TARGET_PRICE; n_coins as usize],
             pool_tokens: pool_token_amount,
         }
     }
 
-    pub fn sim_d(&self) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call0(gil.python(), "D")
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_dy(&self, i: u128, j: u128, dx: u128) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(gil.python(), "dy", (i, j, dx))
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_exchange(&self, i: u128, j: u128, dx: u128) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(gil.python(), "exchange", (i, j, dx))
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_xp(&self) -> Vec<u128> {
-        let gil = Python::acquire_gil();
-        return self
-            .call0(gil.python(), "xp")
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_y(&self, i: u128, j: u128, x: u128) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(gil.python(), "y", (i, j, x))
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_y_d(&self, i: u128, d: u128) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(gil.python(), "y_D", (i, d))
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_remove_liquidity_imbalance(&self, amounts: Vec<u128>) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(
-                gil.python(),
-                "remove_liquidity_imbalance",
-                PyTuple::new(gil.python(), amounts.to_vec()),
-            )
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    pub fn sim_calc_withdraw_one_coin(&self, token_amount: u128, i: u128) -> u128 {
-        let gil = Python::acquire_gil();
-        return self
-            .call1(gil.python(), "calc_withdraw_one_coin", (token_amount, i))
-            .unwrap()
-            .extract(gil.python())
-            .unwrap();
-    }
-
-    fn call0(&self, py: Python, method_name: &str) -> Result<PyObject, PyErr> {
-        let sim = PyModule::from_code(py, &self.py_src, FILE_NAME, MODULE_NAME).unwrap();
-        let model = sim
-            .call1(
-                "Curve",
-                (
-                    self.amp_factor,
-                    self.balances.to_vec(),
-                    self.n_coins,
-                    self.fee,
-                    self.target_prices.to_vec(),
-                    self.pool_tokens,
-                ),
-            )
-            .unwrap()
-            .to_object(py);
-        let py_ret = model.as_ref(py).call_method0(method_name);
-        self.extract_py_ret(py, py_ret)
-    }
-
-    fn call1(
-        &self,
-        py: Python,
-        method_name: &str,
-        args: impl IntoPy<Py<PyTuple>>,
-    ) -> Result<PyObject, PyErr> {
-        let sim = PyModule::from_code(py, &self.py_src, FILE_NAME, MODULE_NAME).unwrap();
-        let model = sim
-            .call1(
-                "Curve",
-                (
-                    self.amp_factor,
-                    self.balances.to_vec(),
-                    self.n_coins,
-                    self.fee,
-                    self.target_prices.to_vec(),
-                    self.pool_tokens,
-                ),
-            )
-            .unwrap()
-            .to_object(py);
-        let py_ret = model.as_ref(py).call_method1(method_name, args);
-        self.extract_py_ret(py, py_ret)
-    }
-
-    fn extract_py_ret(&self, py: Python, ret: PyResult<&PyAny>) -> Result<PyObject, PyErr> {
-        match ret {
-            Ok(v) => v.extract(),
-            Err(e) => {
-                e.print_and_set_sys_last_vars(py);
-                panic!("Python execution failed.")
+    /// Stable swap invariant of the pool
+    pub fn sim_d(&self) -> Option<u128> {
+        to_u128(self.d()?)
+    }
+
+    /// Amount of coin `j` received for `dx` of coin `i`, before fees, in
+    /// units of the target price
+    pub fn sim_dy(&self, i: u128, j: u128, dx: u128) -> Option<u128> {
+        let (i, j) = (to_index(i)?, to_index(j)?);
+        let xp = self.xp()?;
+        let x = xp.get(i)?.checked_add(U256::from(dx))?;
+        to_u128(xp.get(j)?.checked_sub(self.y(i, j, x)?)?)
+    }
+
+    /// Amount of coin `j` received for `dx` of coin `i`, without changing
+    /// the balances
+    pub fn sim_exchange(&self, i: u128, j: u128, dx: u128) -> Option<u128> {
+        self.clone().exchange(to_index(i)?, to_index(j)?, dx)
+    }
+
+    /// Balances of the coins, in units of the target price
+    pub fn sim_xp(&self) -> Option<Vec<u128>> {
+        self.xp()?.into_iter().map(to_u128).collect()
+    }
+
+    /// Balance of coin `j`, in units of the target price, once the balance of
+    /// coin `i` is `x`, keeping the invariant
+    pub fn sim_y(&self, i: u128, j: u128, x: u128) -> Option<u128> {
+        to_u128(self.y(to_index(i)?, to_index(j)?, U256::from(x))?)
+    }
+
+    /// Balance of coin `i`, in units of the target price, for the invariant
+    /// to be `d`
+    pub fn sim_y_d(&self, i: u128, d: u128) -> Option<u128> {
+        to_u128(self.y_d(to_index(i)?, U256::from(d))?)
+    }
+
+    /// Pool tokens burned to withdraw the given amounts of each coin,
+    /// including the fee for unbalancing the pool
+    pub fn sim_remove_liquidity_imbalance(&self, amounts: Vec<u128>) -> Option<u128> {
+        let n = U256::from(self.n_coins);
+        let fee = U256::from(self.fee)
+            .checked_mul(n)?
+            .checked_div(U256::from(4).checked_mul(n.checked_sub(U256::one())?)?)?;
+
+        let old_balances = self.balances.clone();
+        let mut new_balances = self.balances.clone();
+        let d0 = self.d()?;
+        for (balance, amount) in new_balances.iter_mut().zip(amounts.iter()) {
+            *balance = balance.checked_sub(*amount)?;
+        }
+        let d1 = self.with_balances(new_balances.clone()).d()?;
+        for (old_balance, new_balance) in old_balances.iter().zip(new_balances.iter_mut()) {
+            let ideal_balance = d1.checked_mul(U256::from(*old_balance))?.checked_div(d0)?;
+            let difference = abs_diff(ideal_balance, U256::from(*new_balance));
+            let fee_amount = fee
+                .checked_mul(difference)?
+                .checked_div(U256::from(FEE_DENOMINATOR))?;
+            *new_balance = new_balance.checked_sub(to_u128(fee_amount)?)?;
+        }
+        let d2 = self.with_balances(new_balances).d()?;
+
+        to_u128(
+            d0.checked_sub(d2)?
+                .checked_mul(U256::from(self.pool_tokens))?
+                .checked_div(d0)?,
+        )
+    }
+
+    /// Amount of coin `i` received for burning `token_amount` pool tokens,
+    /// after the fee
+    pub fn sim_calc_withdraw_one_coin(&self, token_amount: u128, i: u128) -> Option<u128> {
+        let i = to_index(i)?;
+        let xp = self.xp()?;
+        let xp_i = *xp.get(i)?;
+        let fee = if self.fee > 0 {
+            let fee = U256::from(self.fee);
+            let sum = sum(&xp)?;
+            fee.checked_sub(fee.checked_mul(xp_i)?.checked_div(sum)?)?
+                .checked_add(U256::from(500_000))?
+        } else {
+            U256::zero()
+        };
+
+        let d0 = self.d()?;
+        let d1 = d0.checked_sub(
+            U256::from(token_amount)
+                .checked_mul(d0)?
+                .checked_div(U256::from(self.pool_tokens))?,
+        )?;
+        let dy = xp_i.checked_sub(self.y_d(i, d1)?)?;
+
+        to_u128(
+            dy.checked_sub(
+                dy.checked_mul(fee)?
+                    .checked_div(U256::from(FEE_DENOMINATOR))?,
+            )?,
+        )
+    }
+
+    /// Exchange `dx` of coin `i` for coin `j`, updating the balances, and
+    /// return the amount of coin `j` received
+    pub fn exchange(&mut self, i: usize, j: usize, dx: u128) -> Option<u128> {
+        let xp = self.xp()?;
+        let x = xp.get(i)?.checked_add(U256::from(dx))?;
+        let y = self.y(i, j, x)?;
+        let dy = xp.get(j)?.checked_sub(y)?;
+        let fee = dy
+            .checked_mul(U256::from(self.fee))?
+            .checked_div(U256::from(FEE_DENOMINATOR))?;
+
+        if dy.is_zero() {
+            return Some(0);
+        }
+
+        let precision = U256::from(PRICE_PRECISION);
+        let balance_i = x
+            .checked_mul(precision)?
+            .checked_div(U256::from(*self.target_prices.get(i)?))?;
+        let balance_j = y
+            .checked_add(fee)?
+            .checked_mul(precision)?
+            .checked_div(U256::from(*self.target_prices.get(j)?))?;
+        *self.balances.get_mut(i)? = to_u128(balance_i)?;
+        *self.balances.get_mut(j)? = to_u128(balance_j)?;
+        to_u128(dy.checked_sub(fee)?)
+    }
+
+    fn with_balances(&self, balances: Vec<u128>) -> Self {
+        Self {
+            balances,
+            ..self.clone()
+        }
+    }
+
+    /// Balances in units of the target price
+    fn xp(&self) -> Option<Vec<U256>> {
+        if self.balances.len() != self.target_prices.len() {
+            return None;
+        }
+        self.balances
+            .iter()
+            .zip(self.target_prices.iter())
+            .map(|(x, p)| {
+                U256::from(*x)
+                    .checked_mul(U256::from(*p))?
+                    .checked_div(U256::from(PRICE_PRECISION))
+            })
+            .collect()
+    }
+
+    /// Stable swap invariant, calculated iteratively:
+    ///
+    /// D[j+1] = (A * n**n * sum(x_i) - D[j]**(n+1) / (n**n prod(x_i))) / (A * n**n - 1)
+    fn d(&self) -> Option<U256> {
+        let n = U256::from(self.n_coins);
+        let xp = self.xp()?;
+        let s = sum(&xp)?;
+        let ann = U256::from(self.amp_factor).checked_mul(n)?;
+
+        let mut d_prev = U256::zero();
+        let mut d = s;
+        let mut counter = 0;
+        while abs_diff(d, d_prev) > U256::one() {
+            let mut d_p = d;
+            for x in xp.iter() {
+                d_p = d_p
+                    .checked_mul(d)?
+                    .checked_div(n.checked_mul(*x)?.checked_add(U256::one())?)?;
+            }
+            d_prev = d;
+            let numerator = ann
+                .checked_mul(s)?
+                .checked_add(d_p.checked_mul(n)?)?
+                .checked_mul(d)?;
+            let denominator = ann
+                .checked_sub(U256::one())?
+                .checked_mul(d)?
+                .checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+            d = numerator.checked_div(denominator)?;
+
+            counter += 1;
+            if counter > MAX_ITERATIONS {
+                break;
             }
         }
+        Some(d)
+    }
+
+    /// Balance of coin `j`, in units of the target price, once the balance of
+    /// coin `i` is `x`, solving the quadratic equation iteratively:
+    ///
+    /// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+    fn y(&self, i: usize, j: usize, x: U256) -> Option<U256> {
+        let d = self.d()?;
+        let mut xx = self.xp()?;
+        *xx.get_mut(i)? = x;
+        if j >= xx.len() {
+            return None;
+        }
+        let xx = xx
+            .into_iter()
+            .enumerate()
+            .filter(|(k, _)| *k != j)
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+        self.solve_for_remaining(&xx, d)
+    }
+
+    /// Balance of coin `i`, in units of the target price, for the invariant
+    /// to be `d`, solving the same equation as `y`
+    fn y_d(&self, i: usize, d: U256) -> Option<U256> {
+        let xp = self.xp()?;
+        if i >= xp.len() {
+            return None;
+        }
+        let xx = xp
+            .into_iter()
+            .enumerate()
+            .filter(|(k, _)| *k != i)
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>();
+        self.solve_for_remaining(&xx, d)
+    }
+
+    /// Balance of the coin missing from `xx` for the invariant to be `d`
+    fn solve_for_remaining(&self, xx: &[U256], d: U256) -> Option<U256> {
+        let n = U256::from(self.n_coins);
+        let ann = U256::from(self.amp_factor).checked_mul(n)?;
+        let mut c = d;
+        for y in xx.iter() {
+            c = c.checked_mul(d)?.checked_div(y.checked_mul(n)?)?;
+        }
+        c = c.checked_mul(d)?.checked_div(n.checked_mul(ann)?)?;
+        let s = sum(xx)?;
+        solve_quadratic(s.checked_add(d.checked_div(ann)?)?, c, d)
+    }
+}
+
+/// Approximate y**2 + (b - d) * y = c, starting from d, with `b - d` split
+/// so that the intermediate values stay positive
+fn solve_quadratic(b: U256, c: U256, d: U256) -> Option<U256> {
+    let mut y_prev = U256::zero();
+    let mut y = d;
+    let mut counter = 0;
+    while abs_diff(y, y_prev) > U256::one() {
+        y_prev = y;
+        let numerator = y.checked_mul(y)?.checked_add(c)?;
+        let denominator = U256::from(2)
+            .checked_mul(y)?
+            .checked_add(b)?
+            .checked_sub(d)?;
+        y = numerator.checked_div(denominator)?;
+
+        counter += 1;
+        if counter > MAX_ITERATIONS {
+            break;
+        }
+    }
+    Some(y)
+}
+
+fn sum(values: &[U256]) -> Option<U256> {
+    values
+        .iter()
+        .try_fold(U256::zero(), |sum, x| sum.checked_add(*x))
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+fn to_u128(value: U256) -> Option<u128> {
+    if value > U256::from(u128::MAX) {
+        None
+    } else {
+        Some(value.as_u128())
+    }
+}
+
+fn to_index(index: u128) -> Option<usize> {
+    usize::try_from(index).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Expected values come from running the same operations on the Python
+    // simulation
+
+    #[test]
+    fn balanced_pool() {
+        let model = StableSwapModel::new(100, vec![1_000_000, 1_000_000, 1_000_000], 3);
+        assert_eq!(model.sim_d(), Some(3_000_000));
+        assert_eq!(model.sim_y_d(0, 3_000_000), Some(1_000_000));
+        assert_eq!(model.sim_dy(0, 1, 1_000), Some(1_000));
+        assert_eq!(model.sim_exchange(0, 1, 1_000), Some(1_000));
+    }
+
+    #[test]
+    fn imbalanced_pool() {
+        let model = StableSwapModel::new(85, vec![3_000_000_000, 1_000_000_000, 7_000_000_000], 3);
+        assert_eq!(model.sim_d(), Some(10_943_711_129));
+        assert_eq!(model.sim_dy(0, 1, 100_000_000), Some(93_318_964));
+        assert_eq!(model.sim_dy(2, 0, 500_000_000), Some(488_599_729));
+        assert_eq!(model.sim_y(0, 1, 3_100_000_000), Some(906_681_036));
+        assert_eq!(model.sim_y_d(1, 10_000_000_000), Some(244_111_582));
+    }
+
+    #[test]
+    fn exchange_with_fee_and_target_prices() {
+        let mut model = StableSwapModel::new(100, vec![1_000_000_000, 2_000_000_000], 2);
+        // coin 0 is worth twice as much as coin 1
+        model.target_prices = vec![2 * PRICE_PRECISION, PRICE_PRECISION];
+        assert_eq!(model.sim_xp(), Some(vec![2_000_000_000, 2_000_000_000]));
+        assert_eq!(model.sim_dy(0, 1, 10_000_000), Some(9_999_505));
+
+        model.fee = 4_000_000;
+        let received = model.exchange(0, 1, 10_000_000);
+        assert_eq!(received, Some(9_995_506));
+        assert_eq!(model.balances, vec![1_005_000_000, 1_990_004_494]);
+        // the fee stays in the pool, so the invariant grows
+        assert_eq!(model.sim_d(), Some(4_000_003_999));
+    }
+
+    #[test]
+    fn withdraw() {
+        let model =
+            StableSwapModel::new_with_pool_tokens(100, vec![1_000_000, 1_000_000], 2, 2_000_000);
+        assert_eq!(model.sim_calc_withdraw_one_coin(2_000, 0), Some(2_000));
+        assert_eq!(
+            model.sim_remove_liquidity_imbalance(vec![1_000, 1_000]),
+            Some(2_000)
+        );
+
+        let mut model = StableSwapModel::new_with_pool_tokens(
+            50,
+            vec![1_000_000_000, 3_000_000_000],
+            2,
+            3_900_000_000,
+        );
+        model.fee = 4_000_000;
+        assert_eq!(
+            model.sim_calc_withdraw_one_coin(100_000_000, 0),
+            Some(99_720_599)
+        );
+        assert_eq!(
+            model.sim_calc_withdraw_one_coin(100_000_000, 1),
+            Some(103_409_371)
+        );
+        assert_eq!(
+            model.sim_remove_liquidity_imbalance(vec![100_000_000, 0]),
+            Some(100_275_544)
+        );
+        assert_eq!(
+            model.sim_remove_liquidity_imbalance(vec![50_000_000, 150_000_000]),
+            Some(194_999_999)
+        );
     }
 
-    pub fn print_src(&self) {
-        println!("{}", self.py_src);
+    #[test]
+    fn invalid_operations() {
+        let model = StableSwapModel::new(100, vec![1_000_000, 1_000_000], 2);
+        assert_eq!(model.sim_dy(0, 2, 1_000), None);
+        assert_eq!(model.sim_y_d(2, 2_000_000), None);
+        assert_eq!(
+            model.sim_remove_liquidity_imbalance(vec![2_000_000, 0]),
+            None
+        );
+        // no pool tokens to withdraw against
+        assert_eq!(model.sim_calc_withdraw_one_coin(1_000, 0), None);
+        // the target prices must match the coins
+        let mut model = model;
+        model.target_prices.pop();
+        assert_eq!(model.sim_d(), None);
     }
 }
@@ -3,12 +3,12 @@
 use crate::{
     curve::{
         base::{CurveType, SwapCurve},
-        fees::Fees,
+        fees::{DynamicFees, Fees},
     },
     error::SwapError,
 };
 
-use solana_program::program_error::ProgramError;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
 #[cfg(feature = "production")]
 use std::env;
@@ -26,24 +26,56 @@ pub struct SwapConstraints<'a> {
     pub valid_curve_types: &'a [CurveType],
     /// Valid fees
     pub fees: &'a Fees,
+    /// Bounds on the dynamic trade fee, which is not allowed if `None`
+    pub dynamic_fees: Option<&'a DynamicFees>,
 }
 
 impl<'a> SwapConstraints<'a> {
     /// Checks that the provided curve is valid for the given constraints
     pub fn validate_curve(&self, swap_curve: &SwapCurve) -> Result<(), ProgramError> {
-        if self
-            .valid_curve_types
-            .iter()
-            .any(|x| *x == swap_curve.curve_type)
-        {
+        self.validate_curve_type(swap_curve.curve_type)
+    }
+
+    /// Checks that the provided curve type is valid for the given constraints
+    pub fn validate_curve_type(&self, curve_type: CurveType) -> Result<(), ProgramError> {
+        if self.valid_curve_types.contains(&curve_type) {
             Ok(())
         } else {
             Err(SwapError::UnsupportedCurveType.into())
         }
     }
 
-    /// Checks that the provided curve is valid for the given constraints
-    pub fn validate_fees(&self, fees: &Fees) -> Result<(), ProgramError> {
+    /// Checks that the provided admin authority is the owner of the program,
+    /// the only one allowed to change swaps under the constraints
+    pub fn validate_admin_authority(&self, admin_authority: &Pubkey) -> Result<(), ProgramError> {
+        let owner_key = self
+            .owner_key
+            .parse::<Pubkey>()
+            .map_err(|_| SwapError::InvalidOwner)?;
+        if *admin_authority == owner_key {
+            Ok(())
+        } else {
+            Err(SwapError::InvalidAdmin.into())
+        }
+    }
+
+    /// Checks that the provided fees are valid for the given constraints.
+    /// An enabled dynamic fee may not exceed the maximum trade fee of the
+    /// constraints, reach it at a lower volatility, or decay more slowly.
+    pub fn validate_fees(
+        &self,
+        fees: &Fees,
+        dynamic_fees: &DynamicFees,
+    ) -> Result<(), ProgramError> {
+        if dynamic_fees.is_enabled() {
+            match self.dynamic_fees {
+                Some(bounds)
+                    if dynamic_fees.max_trade_fee_numerator <= bounds.max_trade_fee_numerator
+                        && dynamic_fees.max_volatility >= bounds.max_volatility
+                        && dynamic_fees.decay_period <= bounds.decay_period => {}
+                _ => return Err(SwapError::InvalidFee.into()),
+            }
+        }
         if fees.trade_fee_numerator >= self.fees.trade_fee_numerator
             && fees.trade_fee_denominator == self.fees.trade_fee_denominator
             && fees.owner_trade_fee_numerator >= self.fees.owner_trade_fee_numerator
@@ -74,6 +106,12 @@ const FEES: &Fees = &Fees {
     host_fee_denominator: 100,
 };
 #[cfg(feature = "production")]
+const DYNAMIC_FEES: &DynamicFees = &DynamicFees {
+    max_trade_fee_numerator: 100,
+    max_volatility: 1000,
+    decay_period: 9000,
+};
+#[cfg(feature = "production")]
 const VALID_CURVE_TYPES: &[CurveType] = &[CurveType::ConstantPrice, CurveType::ConstantProduct];
 
 /// Fee structure defined by program creator in order to enforce certain
@@ -81,7 +119,9 @@ const VALID_CURVE_TYPES: &[CurveType] = &[CurveType::ConstantPrice, CurveType::C
 /// swapping to ensure the correct fees and account owners are passed.
 /// Fees provided during production build currently are considered min
 /// fees that creator of the pool can specify. Host fee is a fixed
-/// percentage that host receives as a portion of owner fees
+/// percentage that host receives as a portion of owner fees.  Dynamic
+/// trade fees are bounded, so that the trade fee can never exceed 1%.
+/// Only the owner may be the admin authority of a swap.
 pub const SWAP_CONSTRAINTS: Option<SwapConstraints> = {
     #[cfg(feature = "production")]
     {
@@ -89,6 +129,7 @@ pub const SWAP_CONSTRAINTS: Option<SwapConstraints> = {
             owner_key: OWNER_KEY,
             valid_curve_types: VALID_CURVE_TYPES,
             fees: FEES,
+            dynamic_fees: Some(DYNAMIC_FEES),
         })
     }
     #[cfg(not(feature = "production"))]
@@ -134,54 +175,64 @@ mod tests {
             owner_key,
             valid_curve_types: &[curve_type],
             fees: &valid_fees,
+            dynamic_fees: None,
         };
+        let no_dynamic_fees = DynamicFees::default();
 
         constraints.validate_curve(&swap_curve).unwrap();
-        constraints.validate_fees(&valid_fees).unwrap();
+        constraints
+            .validate_fees(&valid_fees, &no_dynamic_fees)
+            .unwrap();
 
         let mut fees = valid_fees.clone();
         fees.trade_fee_numerator = trade_fee_numerator - 1;
         assert_eq!(
             Err(SwapError::InvalidFee.into()),
-            constraints.validate_fees(&fees),
+            constraints.validate_fees(&fees, &no_dynamic_fees),
         );
         fees.trade_fee_numerator = trade_fee_numerator;
 
         // passing higher fee is ok
         fees.trade_fee_numerator = trade_fee_numerator - 1;
-        assert_eq!(constraints.validate_fees(&valid_fees), Ok(()));
+        assert_eq!(
+            constraints.validate_fees(&valid_fees, &no_dynamic_fees),
+            Ok(())
+        );
         fees.trade_fee_numerator = trade_fee_numerator;
 
         fees.trade_fee_denominator = trade_fee_denominator - 1;
         assert_eq!(
             Err(SwapError::InvalidFee.into()),
-            constraints.validate_fees(&fees),
+            constraints.validate_fees(&fees, &no_dynamic_fees),
         );
         fees.trade_fee_denominator = trade_fee_denominator;
 
         fees.trade_fee_denominator = trade_fee_denominator + 1;
         assert_eq!(
             Err(SwapError::InvalidFee.into()),
-            constraints.validate_fees(&fees),
+            constraints.validate_fees(&fees, &no_dynamic_fees),
         );
         fees.trade_fee_denominator = trade_fee_denominator;
 
         fees.owner_trade_fee_numerator = owner_trade_fee_numerator - 1;
         assert_eq!(
             Err(SwapError::InvalidFee.into()),
-            constraints.validate_fees(&fees),
+            constraints.validate_fees(&fees, &no_dynamic_fees),
         );
         fees.owner_trade_fee_numerator = owner_trade_fee_numerator;
 
         // passing higher fee is ok
         fees.owner_trade_fee_numerator = owner_trade_fee_numerator - 1;
-        assert_eq!(constraints.validate_fees(&valid_fees), Ok(()));
+        assert_eq!(
+            constraints.validate_fees(&valid_fees, &no_dynamic_fees),
+            Ok(())
+        );
         fees.owner_trade_fee_numerator = owner_trade_fee_numerator;
 
         fees.owner_trade_fee_denominator = owner_trade_fee_denominator - 1;
         assert_eq!(
             Err(SwapError::InvalidFee.into()),
-            constraints.validate_fees(&fees),
+            constraints.validate_fees(&fees, &no_dynamic_fees),
         );
         fees.owner_trade_fee_denominator = owner_trade_fee_denominator;
 
@@ -194,4 +245,93 @@ mod tests {
             constraints.validate_curve(&swap_curve),
         );
     }
+
+    #[test]
+    fn validate_dynamic_fees() {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10000,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 20,
+            host_fee_denominator: 100,
+        };
+        let max_trade_fee_numerator = 100;
+        let max_volatility = 1000;
+        let decay_period = 9000;
+        let bounds = DynamicFees {
+            max_trade_fee_numerator,
+            max_volatility,
+            decay_period,
+        };
+        let mut constraints = SwapConstraints {
+            owner_key: "",
+            valid_curve_types: &[CurveType::ConstantProduct],
+            fees: &fees,
+            dynamic_fees: Some(&bounds),
+        };
+
+        constraints.validate_fees(&fees, &bounds).unwrap();
+        constraints
+            .validate_fees(&fees, &DynamicFees::default())
+            .unwrap();
+
+        let mut dynamic_fees = bounds.clone();
+        dynamic_fees.max_trade_fee_numerator = max_trade_fee_numerator + 1;
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_fees(&fees, &dynamic_fees),
+        );
+        dynamic_fees.max_trade_fee_numerator = max_trade_fee_numerator;
+
+        dynamic_fees.max_volatility = max_volatility - 1;
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_fees(&fees, &dynamic_fees),
+        );
+        dynamic_fees.max_volatility = max_volatility;
+
+        dynamic_fees.decay_period = decay_period + 1;
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_fees(&fees, &dynamic_fees),
+        );
+        dynamic_fees.decay_period = decay_period;
+
+        // a lower ceiling, slower ramp, or faster decay is ok
+        dynamic_fees.max_trade_fee_numerator = max_trade_fee_numerator - 1;
+        dynamic_fees.max_volatility = max_volatility + 1;
+        dynamic_fees.decay_period = decay_period - 1;
+        assert_eq!(constraints.validate_fees(&fees, &dynamic_fees), Ok(()));
+
+        // no dynamic fee allowed at all
+        constraints.dynamic_fees = None;
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            constraints.validate_fees(&fees, &dynamic_fees),
+        );
+        constraints
+            .validate_fees(&fees, &DynamicFees::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn validate_admin_authority() {
+        let owner_key = Pubkey::new_unique();
+        let owner_key_str = owner_key.to_string();
+        let fees = Fees::default();
+        let constraints = SwapConstraints {
+            owner_key: &owner_key_str,
+            valid_curve_types: &[CurveType::ConstantProduct],
+            fees: &fees,
+            dynamic_fees: None,
+        };
+        constraints.validate_admin_authority(&owner_key).unwrap();
+        assert_eq!(
+            Err(SwapError::InvalidAdmin.into()),
+            constraints.validate_admin_authority(&Pubkey::new_unique()),
+        );
+    }
 }
@@ -7,11 +7,13 @@ use solana_program::{
 
 use crate::curve::{
     calculator::{CurveCalculator, SwapWithoutFeesResult, TradeDirection},
+    concentrated_liquidity::ConcentratedLiquidityCurve,
     constant_price::ConstantPriceCurve,
     constant_product::ConstantProductCurve,
     fees::Fees,
     offset::OffsetCurve,
     stable::StableCurve,
+    weighted::WeightedCurve,
 };
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use std::convert::{TryFrom, TryInto};
@@ -33,6 +35,12 @@ pub enum CurveType {
     Stable,
     /// Offset curve, like Uniswap, but the token B side has a faked offset
     Offset,
+    /// Balancer-style weighted product curve, invariant =
+    /// token_a_amount ^ token_a_weight * token_b_amount ^ token_b_weight
+    Weighted,
+    /// Uniswap V3-style concentrated liquidity curve, a constant product curve
+    /// whose liquidity is entirely between a lower and an upper price
+    ConcentratedLiquidity,
 }
 
 /// Encodes all results of swapping from a source token to a destination token
@@ -223,6 +231,10 @@ impl Pack for SwapCurve {
                 }
                 CurveType::Stable => Box::new(StableCurve::unpack_from_slice(calculator)?),
                 CurveType::Offset => Box::new(OffsetCurve::unpack_from_slice(calculator)?),
+                CurveType::Weighted => Box::new(WeightedCurve::unpack_from_slice(calculator)?),
+                CurveType::ConcentratedLiquidity => {
+                    Box::new(ConcentratedLiquidityCurve::unpack_from_slice(calculator)?)
+                }
             },
         })
     }
@@ -253,6 +265,8 @@ impl TryFrom<u8> for CurveType {
             1 => Ok(CurveType::ConstantPrice),
             2 => Ok(CurveType::Stable),
             3 => Ok(CurveType::Offset),
+            4 => Ok(CurveType::Weighted),
+            5 => Ok(CurveType::ConcentratedLiquidity),
             _ => Err(ProgramError::InvalidAccountData),
         }
     }
@@ -189,6 +189,17 @@ pub trait CurveCalculator: Debug + DynPack {
         swap_token_a_amount: u128,
         swap_token_b_amount: u128,
     ) -> Option<PreciseNumber>;
+
+    /// Calculates the spot price of token A, denominated in token B, given the
+    /// liquidity parameters.
+    ///
+    /// This is the exchange rate of an infinitely small trade, ignoring fees,
+    /// and is used to accumulate the time-weighted average price of the pool.
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber>;
 }
 
 /// Test helpers for curves
@@ -229,6 +229,16 @@ impl CurveCalculator for ConstantPriceCurve {
         };
         PreciseNumber::new(value)
     }
+
+    /// The price never changes: one token B is always worth `token_b_price`
+    /// token A
+    fn spot_price(
+        &self,
+        _swap_token_a_amount: u128,
+        _swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        PreciseNumber::new(1)?.checked_div(&PreciseNumber::new(self.token_b_price as u128)?)
+    }
 }
 
 /// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
@@ -385,6 +395,17 @@ mod tests {
         assert_eq!(result.destination_amount_swapped, 1u128);
     }
 
+    #[test]
+    fn spot_price_is_constant() {
+        let curve = ConstantPriceCurve { token_b_price: 4 };
+        let expected = PreciseNumber::new(1)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(4).unwrap())
+            .unwrap();
+        assert_eq!(curve.spot_price(1_000, 5_000).unwrap(), expected);
+        assert_eq!(curve.spot_price(0, 5_000).unwrap(), expected);
+    }
+
     proptest! {
         #[test]
         fn deposit_token_conversion_a_to_b(
@@ -170,6 +170,12 @@ pub fn normalized_value(
         .sqrt()
 }
 
+/// The spot price of token A in token B for the constant product curve, which
+/// is the ratio of the two token amounts.
+pub fn spot_price(swap_token_a_amount: u128, swap_token_b_amount: u128) -> Option<PreciseNumber> {
+    PreciseNumber::new(swap_token_b_amount)?.checked_div(&PreciseNumber::new(swap_token_a_amount)?)
+}
+
 impl CurveCalculator for ConstantProductCurve {
     /// Constant product swap ensures x * y = constant
     fn swap_without_fees(
@@ -246,6 +252,14 @@ impl CurveCalculator for ConstantProductCurve {
         normalized_value(swap_token_a_amount, swap_token_b_amount)
     }
 
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        spot_price(swap_token_a_amount, swap_token_b_amount)
+    }
+
     fn validate(&self) -> Result<(), SwapError> {
         Ok(())
     }
@@ -414,6 +428,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn spot_price_of_pool() {
+        let curve = ConstantProductCurve {};
+        let price = curve.spot_price(1_000, 5_000).unwrap();
+        assert_eq!(price, PreciseNumber::new(5).unwrap());
+        assert!(curve.spot_price(0, 5_000).is_none());
+    }
+
     proptest! {
         #[test]
         fn deposit_token_conversion(
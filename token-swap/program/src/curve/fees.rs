@@ -3,11 +3,16 @@
 use crate::error::SwapError;
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
+    clock::Slot,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
 };
+use spl_math::precise_number::PreciseNumber;
 use std::convert::TryFrom;
 
+/// Number of basis points in a whole, the unit of volatility
+const BASIS_POINTS: u128 = 10_000;
+
 /// Encapsulates all fee information and calculations for swap operations
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Fees {
@@ -125,6 +130,130 @@ impl Fees {
     }
 }
 
+/// Configuration of the optional dynamic trade fee, which scales the trade fee
+/// with the recent volatility of the pool's price, as measured by its
+/// `VolatilityAccumulator`.
+///
+/// The trade fee numerator goes from `Fees::trade_fee_numerator` when the
+/// pool is calm, up to `max_trade_fee_numerator` once the volatility reaches
+/// `max_volatility`, both over `Fees::trade_fee_denominator`.  All zeros
+/// disable the dynamic fee, leaving the static trade fee.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DynamicFees {
+    /// Trade fee numerator when the volatility is at or above the maximum
+    pub max_trade_fee_numerator: u64,
+    /// Volatility, in basis points of price movement, at which the trade fee
+    /// reaches its maximum
+    pub max_volatility: u64,
+    /// Number of slots for the volatility to decay by half
+    pub decay_period: u64,
+}
+
+impl DynamicFees {
+    /// Whether the dynamic fee is in use
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Calculate the fees for a trade given the current volatility, which
+    /// only differ from the static fees in their trade fee numerator,
+    /// interpolated linearly between the floor and the ceiling
+    pub fn fees(&self, fees: &Fees, volatility: u64) -> Option<Fees> {
+        if !self.is_enabled() {
+            return Some(fees.clone());
+        }
+        let fee_range = self
+            .max_trade_fee_numerator
+            .checked_sub(fees.trade_fee_numerator)?;
+        let volatility = volatility.min(self.max_volatility);
+        let fee_increase = u128::from(fee_range)
+            .checked_mul(u128::from(volatility))?
+            .checked_div(u128::from(self.max_volatility))?;
+        Some(Fees {
+            trade_fee_numerator: fees
+                .trade_fee_numerator
+                .checked_add(u64::try_from(fee_increase).ok()?)?,
+            ..fees.clone()
+        })
+    }
+
+    /// Validate that the dynamic fee is reasonable, given the static fees
+    pub fn validate(&self, fees: &Fees) -> Result<(), SwapError> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        if self.max_volatility == 0
+            || self.decay_period == 0
+            || self.max_trade_fee_numerator < fees.trade_fee_numerator
+        {
+            return Err(SwapError::InvalidFee);
+        }
+        validate_fraction(self.max_trade_fee_numerator, fees.trade_fee_denominator)
+    }
+}
+
+/// Recent volatility of a pool's price, stored with the swap to drive its
+/// dynamic trade fee
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VolatilityAccumulator {
+    /// Sum of the relative price movements caused by recent trades, in basis
+    /// points, decaying over time
+    pub volatility: u64,
+    /// Slot of the last update
+    pub last_update_slot: Slot,
+}
+
+impl VolatilityAccumulator {
+    /// Create a new, calm accumulator, starting at the given slot
+    pub fn new(slot: Slot) -> Self {
+        Self {
+            volatility: 0,
+            last_update_slot: slot,
+        }
+    }
+
+    /// Decay the volatility for the slots elapsed since the last update,
+    /// halving it every `decay_period` slots, and interpolating linearly
+    /// within a period
+    pub fn decay(&mut self, dynamic_fees: &DynamicFees, slot: Slot) {
+        let elapsed = slot.saturating_sub(self.last_update_slot);
+        if dynamic_fees.decay_period > 0 && elapsed > 0 {
+            let halvings = elapsed / dynamic_fees.decay_period;
+            let volatility = if halvings >= 64 {
+                0
+            } else {
+                self.volatility >> halvings
+            };
+            let remainder = u128::from(elapsed % dynamic_fees.decay_period);
+            let decrease =
+                u128::from(volatility) * remainder / (2 * u128::from(dynamic_fees.decay_period));
+            // the decrease is at most half of the volatility, so it fits
+            self.volatility = volatility - decrease as u64;
+        }
+        self.last_update_slot = self.last_update_slot.max(slot);
+    }
+
+    /// Add the relative movement between the spot prices before and after a
+    /// trade, saturating at the maximum
+    pub fn record_price_movement(
+        &mut self,
+        price_before: &PreciseNumber,
+        price_after: &PreciseNumber,
+    ) {
+        let movement = if price_after.greater_than(price_before) {
+            price_after.value - price_before.value
+        } else {
+            price_before.value - price_after.value
+        };
+        let movement = movement
+            .checked_mul(BASIS_POINTS.into())
+            .and_then(|movement| movement.checked_div(price_before.value))
+            .map(|movement| u64::try_from(movement).unwrap_or(u64::MAX))
+            .unwrap_or(0);
+        self.volatility = self.volatility.saturating_add(movement);
+    }
+}
+
 /// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
 impl IsInitialized for Fees {
     fn is_initialized(&self) -> bool {
@@ -183,6 +312,58 @@ impl Pack for Fees {
     }
 }
 
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for DynamicFees {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+
+impl Sealed for DynamicFees {}
+impl Pack for DynamicFees {
+    const LEN: usize = 24;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 24];
+        let (max_trade_fee_numerator, max_volatility, decay_period) =
+            mut_array_refs![output, 8, 8, 8];
+        *max_trade_fee_numerator = self.max_trade_fee_numerator.to_le_bytes();
+        *max_volatility = self.max_volatility.to_le_bytes();
+        *decay_period = self.decay_period.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<DynamicFees, ProgramError> {
+        let input = array_ref![input, 0, 24];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (max_trade_fee_numerator, max_volatility, decay_period) = array_refs![input, 8, 8, 8];
+        Ok(Self {
+            max_trade_fee_numerator: u64::from_le_bytes(*max_trade_fee_numerator),
+            max_volatility: u64::from_le_bytes(*max_volatility),
+            decay_period: u64::from_le_bytes(*decay_period),
+        })
+    }
+}
+
+impl Sealed for VolatilityAccumulator {}
+impl Pack for VolatilityAccumulator {
+    const LEN: usize = 16;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 16];
+        let (volatility, last_update_slot) = mut_array_refs![output, 8, 8];
+        *volatility = self.volatility.to_le_bytes();
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<VolatilityAccumulator, ProgramError> {
+        let input = array_ref![input, 0, 16];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (volatility, last_update_slot) = array_refs![input, 8, 8];
+        Ok(Self {
+            volatility: u64::from_le_bytes(*volatility),
+            last_update_slot: Slot::from_le_bytes(*last_update_slot),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +406,131 @@ mod tests {
         let unpacked = Fees::unpack_from_slice(&packed).unwrap();
         assert_eq!(fees, unpacked);
     }
+
+    #[test]
+    fn pack_dynamic_fees() {
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 100,
+            max_volatility: 500,
+            decay_period: 9_000,
+        };
+        let mut packed = [0u8; DynamicFees::LEN];
+        Pack::pack_into_slice(&dynamic_fees, &mut packed[..]);
+        let unpacked = DynamicFees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(dynamic_fees, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&100u64.to_le_bytes());
+        packed.extend_from_slice(&500u64.to_le_bytes());
+        packed.extend_from_slice(&9_000u64.to_le_bytes());
+        let unpacked = DynamicFees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(dynamic_fees, unpacked);
+
+        let accumulator = VolatilityAccumulator {
+            volatility: 1_234,
+            last_update_slot: 5_678,
+        };
+        let mut packed = [0u8; VolatilityAccumulator::LEN];
+        Pack::pack_into_slice(&accumulator, &mut packed[..]);
+        let unpacked = VolatilityAccumulator::unpack_from_slice(&packed).unwrap();
+        assert_eq!(accumulator, unpacked);
+    }
+
+    #[test]
+    fn dynamic_trade_fee() {
+        let fees = Fees {
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            ..Fees::default()
+        };
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 125,
+            max_volatility: 400,
+            decay_period: 100,
+        };
+        assert_eq!(dynamic_fees.validate(&fees), Ok(()));
+        assert_eq!(dynamic_fees.fees(&fees, 0).unwrap().trade_fee_numerator, 25);
+        assert_eq!(
+            dynamic_fees.fees(&fees, 100).unwrap().trade_fee_numerator,
+            50
+        );
+        assert_eq!(
+            dynamic_fees.fees(&fees, 400).unwrap().trade_fee_numerator,
+            125
+        );
+        assert_eq!(
+            dynamic_fees
+                .fees(&fees, u64::MAX)
+                .unwrap()
+                .trade_fee_numerator,
+            125
+        );
+
+        // disabled, the static fees apply
+        assert_eq!(DynamicFees::default().validate(&fees), Ok(()));
+        assert_eq!(DynamicFees::default().fees(&fees, 400).unwrap(), fees);
+
+        let invalid = DynamicFees {
+            max_trade_fee_numerator: 10,
+            ..dynamic_fees.clone()
+        };
+        assert_eq!(invalid.validate(&fees), Err(SwapError::InvalidFee));
+        let invalid = DynamicFees {
+            max_trade_fee_numerator: 10_000,
+            ..dynamic_fees.clone()
+        };
+        assert_eq!(invalid.validate(&fees), Err(SwapError::InvalidFee));
+        let invalid = DynamicFees {
+            max_volatility: 0,
+            ..dynamic_fees.clone()
+        };
+        assert_eq!(invalid.validate(&fees), Err(SwapError::InvalidFee));
+        let invalid = DynamicFees {
+            decay_period: 0,
+            ..dynamic_fees
+        };
+        assert_eq!(invalid.validate(&fees), Err(SwapError::InvalidFee));
+    }
+
+    #[test]
+    fn volatility_decay() {
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 100,
+            max_volatility: 1_000,
+            decay_period: 100,
+        };
+        let mut accumulator = VolatilityAccumulator::new(1_000);
+
+        // price moves from 1 to 1.05 then back to 1
+        let one = PreciseNumber::new(1).unwrap();
+        let higher = PreciseNumber::new(105)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(100).unwrap())
+            .unwrap();
+        accumulator.record_price_movement(&one, &higher);
+        assert_eq!(accumulator.volatility, 500);
+        accumulator.record_price_movement(&higher, &one);
+        assert_eq!(accumulator.volatility, 976);
+
+        accumulator.decay(&dynamic_fees, 1_000);
+        assert_eq!(accumulator.volatility, 976);
+        accumulator.decay(&dynamic_fees, 1_100);
+        assert_eq!(accumulator.volatility, 488);
+        accumulator.decay(&dynamic_fees, 1_150);
+        assert_eq!(accumulator.volatility, 366);
+        assert_eq!(accumulator.last_update_slot, 1_150);
+
+        // going back in time does nothing
+        accumulator.decay(&dynamic_fees, 1_000);
+        assert_eq!(accumulator.volatility, 366);
+        assert_eq!(accumulator.last_update_slot, 1_150);
+
+        accumulator.decay(&dynamic_fees, u64::MAX);
+        assert_eq!(accumulator.volatility, 0);
+
+        // movements saturate
+        accumulator.volatility = u64::MAX - 1;
+        accumulator.record_price_movement(&one, &higher);
+        assert_eq!(accumulator.volatility, u64::MAX);
+    }
 }
@@ -8,8 +8,8 @@ use {
                 TradingTokenResult,
             },
             constant_product::{
-                deposit_single_token_type, normalized_value, pool_tokens_to_trading_tokens, swap,
-                withdraw_single_token_type_exact_out,
+                deposit_single_token_type, normalized_value, pool_tokens_to_trading_tokens,
+                spot_price, swap, withdraw_single_token_type_exact_out,
             },
         },
         error::SwapError,
@@ -153,6 +153,19 @@ impl CurveCalculator for OffsetCurve {
             swap_token_b_amount.checked_add(token_b_offset)?,
         )
     }
+
+    /// The offset is added to the token B amount, as in all other calculations
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        let token_b_offset = self.token_b_offset as u128;
+        spot_price(
+            swap_token_a_amount,
+            swap_token_b_amount.checked_add(token_b_offset)?,
+        )
+    }
 }
 
 /// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
@@ -314,6 +327,17 @@ mod tests {
        }
     }
 
+    #[test]
+    fn spot_price_with_offset() {
+        let curve = OffsetCurve {
+            token_b_offset: 4_000,
+        };
+        let price = curve.spot_price(1_000, 1_000).unwrap();
+        assert_eq!(price, PreciseNumber::new(5).unwrap());
+        let price = curve.spot_price(1_000, 0).unwrap();
+        assert_eq!(price, PreciseNumber::new(4).unwrap());
+    }
+
     proptest! {
         #[test]
         fn deposit_token_conversion_a_to_b(
@@ -7,8 +7,9 @@ use {
         },
         error::SwapError,
     },
-    arrayref::{array_mut_ref, array_ref},
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
     solana_program::{
+        clock::UnixTimestamp,
         program_error::ProgramError,
         program_pack::{IsInitialized, Pack, Sealed},
     },
@@ -20,6 +21,16 @@ const N_COINS: u8 = 2;
 const N_COINS_SQUARED: u8 = 4;
 const ITERATIONS: u8 = 32;
 
+/// Minimum amplification coefficient reachable by a ramp
+pub const MIN_AMP: u64 = 1;
+/// Maximum amplification coefficient reachable by a ramp
+pub const MAX_AMP: u64 = 1_000_000;
+/// Maximum factor by which a single ramp may change the amplification
+/// coefficient, up or down
+pub const MAX_AMP_CHANGE: u64 = 10;
+/// Minimum duration of a ramp, in seconds
+pub const MIN_RAMP_DURATION: UnixTimestamp = 86_400;
+
 /// Returns self to the power of b
 fn checked_u8_power(a: &U256, b: u8) -> Option<U256> {
     let mut result = *a;
@@ -309,6 +320,32 @@ impl CurveCalculator for StableCurve {
         }
     }
 
+    /// The spot price is the ratio of the partial derivatives of the
+    /// invariant with respect to each token amount, which simplifies to:
+    ///
+    /// (4 * A * n * x * y + D**3 / x) / (4 * A * n * x * y + D**3 / y)
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        let leverage = self.amp.checked_mul(N_COINS as u64)?;
+        let d_val = compute_d(leverage, swap_token_a_amount, swap_token_b_amount)?;
+        let d_cubed = checked_u8_power(&U256::from(d_val), N_COINS.checked_add(1)?)?;
+        let leverage_term = checked_u8_mul(&U256::from(leverage), N_COINS_SQUARED)?
+            .checked_mul(swap_token_a_amount.into())?
+            .checked_mul(swap_token_b_amount.into())?;
+        let numerator =
+            leverage_term.checked_add(d_cubed.checked_div(swap_token_a_amount.into())?)?;
+        let denominator =
+            leverage_term.checked_add(d_cubed.checked_div(swap_token_b_amount.into())?)?;
+        Some(PreciseNumber {
+            value: numerator
+                .checked_mul(PreciseNumber::new(1)?.value)?
+                .checked_div(denominator)?,
+        })
+    }
+
     fn validate(&self) -> Result<(), SwapError> {
         // TODO are all amps valid?
         Ok(())
@@ -343,6 +380,95 @@ impl DynPack for StableCurve {
     }
 }
 
+/// Linear change of the amplification coefficient of a stable curve over
+/// time, so that the curve of a live pool never jumps.  All zeros if the
+/// swap has never ramped its curve.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AmpRamp {
+    /// Amplification coefficient at the start of the ramp
+    pub initial_amp: u64,
+    /// Amplification coefficient at the end of the ramp
+    pub target_amp: u64,
+    /// Unix timestamp of the start of the ramp
+    pub start_timestamp: UnixTimestamp,
+    /// Unix timestamp of the end of the ramp
+    pub stop_timestamp: UnixTimestamp,
+}
+
+impl AmpRamp {
+    /// Whether a ramp was ever started
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Amplification coefficient at the given time, interpolated linearly
+    /// between the start and the end of the ramp
+    pub fn amp(&self, timestamp: UnixTimestamp) -> u64 {
+        if timestamp >= self.stop_timestamp {
+            self.target_amp
+        } else if timestamp <= self.start_timestamp {
+            self.initial_amp
+        } else {
+            let elapsed = i128::from(timestamp) - i128::from(self.start_timestamp);
+            let duration = i128::from(self.stop_timestamp) - i128::from(self.start_timestamp);
+            let change = i128::from(self.target_amp) - i128::from(self.initial_amp);
+            // between the initial and target amps, so it fits
+            (i128::from(self.initial_amp) + change * elapsed / duration) as u64
+        }
+    }
+
+    /// Validate that the ramp is slow enough and reaches a valid amplification
+    /// coefficient
+    pub fn validate(&self) -> Result<(), SwapError> {
+        let initial_amp = u128::from(self.initial_amp);
+        let target_amp = u128::from(self.target_amp);
+        let max_amp_change = u128::from(MAX_AMP_CHANGE);
+        if self.target_amp < MIN_AMP
+            || self.target_amp > MAX_AMP
+            || target_amp > initial_amp * max_amp_change
+            || target_amp * max_amp_change < initial_amp
+            || self.stop_timestamp.saturating_sub(self.start_timestamp) < MIN_RAMP_DURATION
+        {
+            Err(SwapError::InvalidAmpRamp)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for AmpRamp {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for AmpRamp {}
+impl Pack for AmpRamp {
+    const LEN: usize = 32;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 32];
+        let (initial_amp, target_amp, start_timestamp, stop_timestamp) =
+            mut_array_refs![output, 8, 8, 8, 8];
+        *initial_amp = self.initial_amp.to_le_bytes();
+        *target_amp = self.target_amp.to_le_bytes();
+        *start_timestamp = self.start_timestamp.to_le_bytes();
+        *stop_timestamp = self.stop_timestamp.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<AmpRamp, ProgramError> {
+        let input = array_ref![input, 0, 32];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (initial_amp, target_amp, start_timestamp, stop_timestamp) =
+            array_refs![input, 8, 8, 8, 8];
+        Ok(Self {
+            initial_amp: u64::from_le_bytes(*initial_amp),
+            target_amp: u64::from_le_bytes(*target_amp),
+            start_timestamp: UnixTimestamp::from_le_bytes(*start_timestamp),
+            stop_timestamp: UnixTimestamp::from_le_bytes(*stop_timestamp),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +483,7 @@ mod tests {
     };
     use proptest::prelude::*;
     use sim::StableSwapModel;
+    use spl_math::precise_number::ONE;
 
     #[test]
     fn initial_pool_amount() {
@@ -395,6 +522,44 @@ mod tests {
         check_pool_token_rate(5, 501, 2, 10, 1, 101);
     }
 
+    #[test]
+    fn spot_price_of_pool() {
+        let curve = StableCurve { amp: 100 };
+        let one = PreciseNumber::new(1).unwrap();
+        assert_eq!(curve.spot_price(1_000, 1_000).unwrap(), one);
+
+        // token A is more abundant, so it is worth less than token B, but
+        // more than on a constant product curve
+        let swap_token_a_amount = 2_000_000_000;
+        let swap_token_b_amount = 500_000_000;
+        let price = curve
+            .spot_price(swap_token_a_amount, swap_token_b_amount)
+            .unwrap();
+        assert!(price.less_than(&one));
+        assert!(price.greater_than(
+            &PreciseNumber::new(1)
+                .unwrap()
+                .checked_div(&PreciseNumber::new(4).unwrap())
+                .unwrap()
+        ));
+
+        // a small trade gets roughly the spot price
+        let source_amount = 1_000;
+        let result = curve
+            .swap_without_fees(
+                source_amount,
+                swap_token_a_amount,
+                swap_token_b_amount,
+                TradeDirection::AtoB,
+            )
+            .unwrap();
+        let trade_price = PreciseNumber::new(result.destination_amount_swapped)
+            .unwrap()
+            .checked_div(&PreciseNumber::new(result.source_amount_swapped).unwrap())
+            .unwrap();
+        assert!(price.almost_eq(&trade_price, U256::from(ONE / 100)));
+    }
+
     proptest! {
         #[test]
         fn constant_product_swap_no_fee(
@@ -421,7 +586,7 @@ mod tests {
             );
 
             let result = result.unwrap();
-            let sim_result = model.sim_exchange(0, 1, source_amount);
+            let sim_result = model.sim_exchange(0, 1, source_amount).unwrap();
 
             let diff =
                 (sim_result as i128 - result.destination_amount_swapped as i128).abs();
@@ -458,6 +623,89 @@ mod tests {
         assert_eq!(curve, unpacked);
     }
 
+    #[test]
+    fn pack_amp_ramp() {
+        let ramp = AmpRamp {
+            initial_amp: 100,
+            target_amp: 1_000,
+            start_timestamp: 1_630_000_000,
+            stop_timestamp: 1_630_086_400,
+        };
+
+        let mut packed = [0u8; AmpRamp::LEN];
+        Pack::pack_into_slice(&ramp, &mut packed[..]);
+        let unpacked = AmpRamp::unpack(&packed).unwrap();
+        assert_eq!(ramp, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&ramp.initial_amp.to_le_bytes());
+        packed.extend_from_slice(&ramp.target_amp.to_le_bytes());
+        packed.extend_from_slice(&ramp.start_timestamp.to_le_bytes());
+        packed.extend_from_slice(&ramp.stop_timestamp.to_le_bytes());
+        let unpacked = AmpRamp::unpack(&packed).unwrap();
+        assert_eq!(ramp, unpacked);
+    }
+
+    #[test]
+    fn amp_ramp() {
+        let start_timestamp = 1_000;
+        let stop_timestamp = start_timestamp + MIN_RAMP_DURATION;
+        let up = AmpRamp {
+            initial_amp: 100,
+            target_amp: 1_000,
+            start_timestamp,
+            stop_timestamp,
+        };
+        up.validate().unwrap();
+        assert_eq!(up.amp(0), 100);
+        assert_eq!(up.amp(start_timestamp), 100);
+        assert_eq!(up.amp(start_timestamp + MIN_RAMP_DURATION / 4), 325);
+        assert_eq!(up.amp(start_timestamp + MIN_RAMP_DURATION / 2), 550);
+        assert_eq!(up.amp(stop_timestamp), 1_000);
+        assert_eq!(up.amp(UnixTimestamp::MAX), 1_000);
+
+        let down = AmpRamp {
+            initial_amp: 1_000,
+            target_amp: 100,
+            start_timestamp,
+            stop_timestamp,
+        };
+        down.validate().unwrap();
+        assert_eq!(down.amp(start_timestamp + MIN_RAMP_DURATION / 4), 775);
+        assert_eq!(down.amp(stop_timestamp), 100);
+
+        // too fast
+        let ramp = AmpRamp {
+            stop_timestamp: stop_timestamp - 1,
+            ..up.clone()
+        };
+        assert_eq!(ramp.validate(), Err(SwapError::InvalidAmpRamp));
+        // too far up or down
+        let ramp = AmpRamp {
+            target_amp: 1_001,
+            ..up.clone()
+        };
+        assert_eq!(ramp.validate(), Err(SwapError::InvalidAmpRamp));
+        let ramp = AmpRamp {
+            target_amp: 99,
+            ..down
+        };
+        assert_eq!(ramp.validate(), Err(SwapError::InvalidAmpRamp));
+        // out of range
+        let ramp = AmpRamp {
+            initial_amp: MAX_AMP,
+            target_amp: MAX_AMP + 1,
+            ..up.clone()
+        };
+        assert_eq!(ramp.validate(), Err(SwapError::InvalidAmpRamp));
+        let ramp = AmpRamp {
+            initial_amp: MIN_AMP,
+            target_amp: MIN_AMP - 1,
+            ..up
+        };
+        assert_eq!(ramp.validate(), Err(SwapError::InvalidAmpRamp));
+    }
+
     proptest! {
         #[test]
         fn curve_value_does_not_decrease_from_deposit(
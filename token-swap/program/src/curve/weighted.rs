@@ -0,0 +1,843 @@
+//! The Balancer-style weighted product invariant calculator.
+
+use {
+    crate::{
+        curve::{
+            calculator::{
+                map_zero_to_none, CurveCalculator, DynPack, RoundDirection, SwapWithoutFeesResult,
+                TradeDirection, TradingTokenResult,
+            },
+            constant_product::pool_tokens_to_trading_tokens,
+        },
+        error::SwapError,
+    },
+    arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs},
+    solana_program::{
+        program_error::ProgramError,
+        program_pack::{IsInitialized, Pack, Sealed},
+    },
+    spl_math::{precise_number::PreciseNumber, uint::U256},
+    std::convert::TryFrom,
+};
+
+/// Maximum ratio between the largest and the smallest weight of a pool, which
+/// keeps the exponents of the weighted math in a range where it is precise
+pub const MAX_WEIGHT_RATIO: u64 = 50;
+
+/// Fixed-point scale of the logarithms and exponentials, much finer than
+/// `PreciseNumber`, so that rounding errors stay far below one token for any
+/// u64 amount
+const SCALE: u128 = 1_000_000_000_000_000_000_000_000_000;
+/// ln(2), scaled
+const LN_2: u128 = 693_147_180_559_945_309_417_232_121;
+/// sqrt(2), scaled
+const SQRT_2: u128 = 1_414_213_562_373_095_048_801_688_724;
+/// Relative error bound of the scaled powers, as a divisor.  Results are
+/// rounded against the trader by this much, plus a few units of the scale.
+const ERROR_MARGIN: u128 = 100_000_000_000_000_000_000;
+const ERROR_UNITS: u128 = 10;
+/// Largest shift of a scaled exponential that fits in a U256
+const MAX_EXP_SHIFT: u32 = 160;
+/// Ratio between the scale used here and the one of `PreciseNumber`
+const PRECISE_NUMBER_RATIO: u128 = 1_000_000_000_000_000;
+
+/// WeightedCurve struct implementing CurveCalculator
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WeightedCurve {
+    /// Weight of token A in the invariant, relative to token B
+    pub token_a_weight: u64,
+    /// Weight of token B in the invariant, relative to token A
+    pub token_b_weight: u64,
+}
+
+/// Natural logarithm of a positive integer, scaled
+fn ln(x: u128) -> Option<U256> {
+    let scale = U256::from(SCALE);
+    // x = 2^k * m, with m between 1/sqrt(2) and sqrt(2)
+    let mut k = 127u32.checked_sub(x.checked_next_power_of_two()?.leading_zeros())?;
+    if x < 1u128 << k {
+        k = k.checked_sub(1)?;
+    }
+    let mut m = U256::from(x).checked_mul(scale)? >> k;
+    if m >= U256::from(SQRT_2) {
+        m >>= 1u32;
+        k = k.checked_add(1)?;
+    }
+
+    // ln(m) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...), where
+    // z = (m - 1) / (m + 1) is at most 0.18 in absolute value
+    let (numerator, negative) = if m >= scale {
+        (m.checked_sub(scale)?, false)
+    } else {
+        (scale.checked_sub(m)?, true)
+    };
+    let z = numerator
+        .checked_mul(scale)?
+        .checked_div(m.checked_add(scale)?)?;
+    let z_squared = z.checked_mul(z)?.checked_div(scale)?;
+    let mut term = z;
+    let mut sum = z;
+    let mut n = 1u32;
+    while !term.is_zero() {
+        term = term.checked_mul(z_squared)?.checked_div(scale)?;
+        n = n.checked_add(2)?;
+        sum = sum.checked_add(term.checked_div(U256::from(n))?)?;
+    }
+    let ln_m = sum.checked_mul(U256::from(2))?;
+    let ln_2_k = U256::from(LN_2).checked_mul(U256::from(k))?;
+    if negative {
+        ln_2_k.checked_sub(ln_m)
+    } else {
+        ln_2_k.checked_add(ln_m)
+    }
+}
+
+/// Split a scaled exponent y into k * ln(2) + f, with f between 0 and ln(2),
+/// and return k and e^f, scaled
+fn exp_parts(y: U256) -> Option<(U256, U256)> {
+    let scale = U256::from(SCALE);
+    let ln_2 = U256::from(LN_2);
+    let k = y.checked_div(ln_2)?;
+    let f = y.checked_sub(k.checked_mul(ln_2)?)?;
+    // e^f = 1 + f + f^2 / 2! + f^3 / 3! + ...
+    let mut term = scale;
+    let mut sum = scale;
+    let mut n = 0u32;
+    while !term.is_zero() {
+        n = n.checked_add(1)?;
+        term = term
+            .checked_mul(f)?
+            .checked_div(scale)?
+            .checked_div(U256::from(n))?;
+        sum = sum.checked_add(term)?;
+    }
+    Some((k, sum))
+}
+
+/// e^y for a scaled y, scaled
+fn exp(y: U256) -> Option<U256> {
+    let (k, exp_f) = exp_parts(y)?;
+    if k > U256::from(MAX_EXP_SHIFT) {
+        return None;
+    }
+    Some(exp_f << k)
+}
+
+/// e^-y for a scaled y, scaled
+fn exp_neg(y: U256) -> Option<U256> {
+    let (k, exp_f) = exp_parts(y)?;
+    let scale = U256::from(SCALE);
+    let exp_neg_f = scale.checked_mul(scale)?.checked_div(exp_f)?;
+    if k >= U256::from(256) {
+        return Some(U256::zero());
+    }
+    Some(exp_neg_f >> k)
+}
+
+/// (base_numerator / base_denominator) ^ (exponent_numerator /
+/// exponent_denominator), scaled
+fn pow_ratio(
+    base_numerator: u128,
+    base_denominator: u128,
+    exponent_numerator: u128,
+    exponent_denominator: u128,
+) -> Option<U256> {
+    let ln_numerator = ln(base_numerator)?;
+    let ln_denominator = ln(base_denominator)?;
+    let exponent = |ln_ratio: U256| {
+        ln_ratio
+            .checked_mul(U256::from(exponent_numerator))?
+            .checked_div(U256::from(exponent_denominator))
+    };
+    if ln_numerator >= ln_denominator {
+        exp(exponent(ln_numerator.checked_sub(ln_denominator)?)?)
+    } else {
+        exp_neg(exponent(ln_denominator.checked_sub(ln_numerator)?)?)
+    }
+}
+
+/// Bump a scaled power up by its error bound
+fn round_up(x: U256) -> Option<U256> {
+    x.checked_add(x.checked_div(U256::from(ERROR_MARGIN))?)?
+        .checked_add(U256::from(ERROR_UNITS))
+}
+
+/// Bring a scaled power down by its error bound
+fn round_down(x: U256) -> Option<U256> {
+    let error = x
+        .checked_div(U256::from(ERROR_MARGIN))?
+        .checked_add(U256::from(ERROR_UNITS))?;
+    Some(x.saturating_sub(error))
+}
+
+/// amount * ratio, for a scaled ratio
+fn mul_scaled(amount: u128, ratio: U256, round_direction: RoundDirection) -> Option<u128> {
+    let scale = U256::from(SCALE);
+    let product = U256::from(amount).checked_mul(ratio)?;
+    let product = match round_direction {
+        RoundDirection::Floor => product,
+        RoundDirection::Ceiling => product.checked_add(scale.checked_sub(U256::one())?)?,
+    };
+    u128::try_from(product.checked_div(scale)?).ok()
+}
+
+/// Check that all weights are positive, and within `MAX_WEIGHT_RATIO` of each
+/// other
+pub fn validate_weights(weights: &[u64]) -> Result<(), SwapError> {
+    let min_weight = weights.iter().min().ok_or(SwapError::InvalidCurve)?;
+    let max_weight = weights.iter().max().ok_or(SwapError::InvalidCurve)?;
+    if *min_weight == 0 || *max_weight / *min_weight > MAX_WEIGHT_RATIO {
+        return Err(SwapError::InvalidCurve);
+    }
+    if *max_weight / *min_weight == MAX_WEIGHT_RATIO && *max_weight % *min_weight != 0 {
+        return Err(SwapError::InvalidCurve);
+    }
+    Ok(())
+}
+
+/// The weighted product swap calculation, factored out of its class for reuse
+/// in pools of more than two tokens.
+///
+/// The amount out is given by the Balancer formula found at
+/// <https://balancer.finance/whitepaper/#out-given-in>, rounded against the
+/// trader.  Like the constant product curve, only the amount of source token
+/// needed for that amount out is taken.
+pub fn swap(
+    source_amount: u128,
+    swap_source_amount: u128,
+    swap_destination_amount: u128,
+    source_weight: u64,
+    destination_weight: u64,
+) -> Option<SwapWithoutFeesResult> {
+    let new_swap_source_amount = swap_source_amount.checked_add(source_amount)?;
+    let ratio = pow_ratio(
+        swap_source_amount,
+        new_swap_source_amount,
+        source_weight as u128,
+        destination_weight as u128,
+    )?;
+    let new_swap_destination_amount = mul_scaled(
+        swap_destination_amount,
+        round_up(ratio)?,
+        RoundDirection::Ceiling,
+    )?;
+    let destination_amount_swapped =
+        map_zero_to_none(swap_destination_amount.checked_sub(new_swap_destination_amount)?)?;
+
+    let ratio = pow_ratio(
+        swap_destination_amount,
+        new_swap_destination_amount,
+        destination_weight as u128,
+        source_weight as u128,
+    )?;
+    let new_swap_source_amount = std::cmp::min(
+        mul_scaled(
+            swap_source_amount,
+            round_up(ratio)?,
+            RoundDirection::Ceiling,
+        )?,
+        new_swap_source_amount,
+    );
+    let source_amount_swapped = new_swap_source_amount.checked_sub(swap_source_amount)?;
+
+    Some(SwapWithoutFeesResult {
+        source_amount_swapped,
+        destination_amount_swapped,
+    })
+}
+
+/// Get the amount of trading tokens of one type for the given amount of pool
+/// tokens, a simple ratio calculation like the constant product curve
+pub fn pool_tokens_to_trading_token(
+    pool_tokens: u128,
+    pool_token_supply: u128,
+    swap_token_amount: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let results = pool_tokens_to_trading_tokens(
+        pool_tokens,
+        pool_token_supply,
+        swap_token_amount,
+        0,
+        round_direction,
+    )?;
+    Some(results.token_a_amount)
+}
+
+/// Get the amount of pool tokens for the deposited amount of one token.
+///
+/// Uses the Balancer formula found at
+/// <https://balancer.finance/whitepaper/#single-asset-deposit>, where
+/// `token_weight / total_weight` is the normalized weight of the token.
+pub fn deposit_single_token_type(
+    source_amount: u128,
+    swap_token_amount: u128,
+    token_weight: u64,
+    total_weight: u128,
+    pool_supply: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let ratio = pow_ratio(
+        swap_token_amount.checked_add(source_amount)?,
+        swap_token_amount,
+        token_weight as u128,
+        total_weight,
+    )?;
+    let ratio = match round_direction {
+        RoundDirection::Floor => round_down(ratio)?,
+        RoundDirection::Ceiling => round_up(ratio)?,
+    };
+    let growth = ratio.saturating_sub(U256::from(SCALE));
+    mul_scaled(pool_supply, growth, round_direction)
+}
+
+/// Get the amount of pool tokens for the withdrawn amount of one token.
+///
+/// Uses the Balancer formula found at
+/// <https://balancer.finance/whitepaper/#single-asset-withdrawal>, where
+/// `token_weight / total_weight` is the normalized weight of the token.
+pub fn withdraw_single_token_type_exact_out(
+    source_amount: u128,
+    swap_token_amount: u128,
+    token_weight: u64,
+    total_weight: u128,
+    pool_supply: u128,
+    round_direction: RoundDirection,
+) -> Option<u128> {
+    let new_swap_token_amount = swap_token_amount.checked_sub(source_amount)?;
+    let scale = U256::from(SCALE);
+    // withdrawing everything costs all of the pool tokens
+    let ratio = if new_swap_token_amount == 0 {
+        U256::zero()
+    } else {
+        let ratio = pow_ratio(
+            new_swap_token_amount,
+            swap_token_amount,
+            token_weight as u128,
+            total_weight,
+        )?;
+        match round_direction {
+            RoundDirection::Floor => std::cmp::min(round_up(ratio)?, scale),
+            RoundDirection::Ceiling => round_down(ratio)?,
+        }
+    };
+    mul_scaled(pool_supply, scale.checked_sub(ratio)?, round_direction)
+}
+
+/// Calculates the total normalized value of the curve given the liquidity
+/// parameters.
+///
+/// The weighted product implementation gives the weighted geometric mean of
+/// the token amounts, ie. the invariant raised to the power of one over the
+/// total weight.
+pub fn normalized_value(amounts_and_weights: &[(u128, u64)]) -> Option<PreciseNumber> {
+    if amounts_and_weights.iter().any(|(amount, _)| *amount == 0) {
+        return PreciseNumber::new(0);
+    }
+    let mut ln_invariant = U256::zero();
+    let mut total_weight = U256::zero();
+    for (amount, weight) in amounts_and_weights {
+        ln_invariant = ln_invariant.checked_add(ln(*amount)?.checked_mul(U256::from(*weight))?)?;
+        total_weight = total_weight.checked_add(U256::from(*weight))?;
+    }
+    let value = exp(ln_invariant.checked_div(total_weight)?)?;
+    Some(PreciseNumber {
+        value: value.checked_div(U256::from(PRECISE_NUMBER_RATIO))?,
+    })
+}
+
+impl WeightedCurve {
+    fn weights(&self, trade_direction: TradeDirection) -> (u64, u64) {
+        match trade_direction {
+            TradeDirection::AtoB => (self.token_a_weight, self.token_b_weight),
+            TradeDirection::BtoA => (self.token_b_weight, self.token_a_weight),
+        }
+    }
+
+    fn total_weight(&self) -> u128 {
+        self.token_a_weight as u128 + self.token_b_weight as u128
+    }
+}
+
+impl CurveCalculator for WeightedCurve {
+    /// Weighted product swap ensures x ^ w_x * y ^ w_y = constant
+    fn swap_without_fees(
+        &self,
+        source_amount: u128,
+        swap_source_amount: u128,
+        swap_destination_amount: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<SwapWithoutFeesResult> {
+        let (source_weight, destination_weight) = self.weights(trade_direction);
+        swap(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            source_weight,
+            destination_weight,
+        )
+    }
+
+    /// The weighted product implementation is a simple ratio calculation for
+    /// how many trading tokens correspond to a certain number of pool tokens
+    fn pool_tokens_to_trading_tokens(
+        &self,
+        pool_tokens: u128,
+        pool_token_supply: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        round_direction: RoundDirection,
+    ) -> Option<TradingTokenResult> {
+        pool_tokens_to_trading_tokens(
+            pool_tokens,
+            pool_token_supply,
+            swap_token_a_amount,
+            swap_token_b_amount,
+            round_direction,
+        )
+    }
+
+    /// Get the amount of pool tokens for the deposited amount of token A or B.
+    fn deposit_single_token_type(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        let (token_weight, _) = self.weights(trade_direction);
+        let swap_token_amount = match trade_direction {
+            TradeDirection::AtoB => swap_token_a_amount,
+            TradeDirection::BtoA => swap_token_b_amount,
+        };
+        deposit_single_token_type(
+            source_amount,
+            swap_token_amount,
+            token_weight,
+            self.total_weight(),
+            pool_supply,
+            RoundDirection::Floor,
+        )
+    }
+
+    fn withdraw_single_token_type_exact_out(
+        &self,
+        source_amount: u128,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        pool_supply: u128,
+        trade_direction: TradeDirection,
+    ) -> Option<u128> {
+        let (token_weight, _) = self.weights(trade_direction);
+        let swap_token_amount = match trade_direction {
+            TradeDirection::AtoB => swap_token_a_amount,
+            TradeDirection::BtoA => swap_token_b_amount,
+        };
+        withdraw_single_token_type_exact_out(
+            source_amount,
+            swap_token_amount,
+            token_weight,
+            self.total_weight(),
+            pool_supply,
+            RoundDirection::Ceiling,
+        )
+    }
+
+    fn normalized_value(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        normalized_value(&[
+            (swap_token_a_amount, self.token_a_weight),
+            (swap_token_b_amount, self.token_b_weight),
+        ])
+    }
+
+    /// The spot price of a weighted pool is the ratio of the token amounts,
+    /// each divided by its weight
+    fn spot_price(
+        &self,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+    ) -> Option<PreciseNumber> {
+        let weighted_a_amount = PreciseNumber::new(swap_token_a_amount)?
+            .checked_mul(&PreciseNumber::new(self.token_b_weight as u128)?)?;
+        let weighted_b_amount = PreciseNumber::new(swap_token_b_amount)?
+            .checked_mul(&PreciseNumber::new(self.token_a_weight as u128)?)?;
+        weighted_b_amount.checked_div(&weighted_a_amount)
+    }
+
+    fn validate(&self) -> Result<(), SwapError> {
+        validate_weights(&[self.token_a_weight, self.token_b_weight])
+    }
+}
+
+/// IsInitialized is required to use `Pack::pack` and `Pack::unpack`
+impl IsInitialized for WeightedCurve {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+}
+impl Sealed for WeightedCurve {}
+impl Pack for WeightedCurve {
+    const LEN: usize = 16;
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        (self as &dyn DynPack).pack_into_slice(output);
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<WeightedCurve, ProgramError> {
+        let input = array_ref![input, 0, 16];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (token_a_weight, token_b_weight) = array_refs![input, 8, 8];
+        Ok(Self {
+            token_a_weight: u64::from_le_bytes(*token_a_weight),
+            token_b_weight: u64::from_le_bytes(*token_b_weight),
+        })
+    }
+}
+
+impl DynPack for WeightedCurve {
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 16];
+        let (token_a_weight, token_b_weight) = mut_array_refs![output, 8, 8];
+        *token_a_weight = self.token_a_weight.to_le_bytes();
+        *token_b_weight = self.token_b_weight.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::calculator::{
+        test::{
+            check_curve_value_from_swap, check_deposit_token_conversion,
+            check_pool_value_from_deposit, check_pool_value_from_withdraw,
+            check_withdraw_token_conversion, total_and_intermediate,
+            CONVERSION_BASIS_POINTS_GUARANTEE,
+        },
+        INITIAL_SWAP_POOL_AMOUNT,
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn initial_pool_amount() {
+        let calculator = WeightedCurve {
+            token_a_weight: 80,
+            token_b_weight: 20,
+        };
+        assert_eq!(calculator.new_pool_supply(), INITIAL_SWAP_POOL_AMOUNT);
+    }
+
+    #[test]
+    fn pack_curve() {
+        let token_a_weight = 4;
+        let token_b_weight = u64::MAX;
+        let curve = WeightedCurve {
+            token_a_weight,
+            token_b_weight,
+        };
+
+        let mut packed = [0u8; WeightedCurve::LEN];
+        Pack::pack_into_slice(&curve, &mut packed[..]);
+        let unpacked = WeightedCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&token_a_weight.to_le_bytes());
+        packed.extend_from_slice(&token_b_weight.to_le_bytes());
+        let unpacked = WeightedCurve::unpack(&packed).unwrap();
+        assert_eq!(curve, unpacked);
+    }
+
+    #[test]
+    fn validate_curve_weights() {
+        assert!(validate_weights(&[1, 1, 1]).is_ok());
+        assert!(validate_weights(&[1, 50]).is_ok());
+        assert!(validate_weights(&[2, 100, 3]).is_ok());
+        assert_eq!(validate_weights(&[]), Err(SwapError::InvalidCurve));
+        assert_eq!(validate_weights(&[0, 1]), Err(SwapError::InvalidCurve));
+        assert_eq!(validate_weights(&[1, 51]), Err(SwapError::InvalidCurve));
+        assert_eq!(validate_weights(&[2, 101]), Err(SwapError::InvalidCurve));
+    }
+
+    #[test]
+    fn logarithm_and_exponential() {
+        let scale = U256::from(SCALE);
+        assert_eq!(ln(1).unwrap(), U256::zero());
+        let ln_2 = ln(2).unwrap();
+        assert!(ln_2 <= U256::from(LN_2 + 10) && ln_2 >= U256::from(LN_2 - 10));
+        // ln(10^18) = 41.446531673892822312...
+        let ln_big = ln(1_000_000_000_000_000_000).unwrap();
+        let expected = U256::from(41_446_531_673_892_822_312_323_846_184u128);
+        assert!(ln_big <= expected + 1000 && ln_big >= expected - 1000);
+        // e = 2.718281828459045235360287471...
+        let e = exp(scale).unwrap();
+        let expected = U256::from(2_718_281_828_459_045_235_360_287_471u128);
+        assert!(e <= expected + 100 && e >= expected - 100);
+        assert_eq!(exp(U256::zero()).unwrap(), scale);
+        let e_inverse = exp_neg(scale).unwrap();
+        let expected = U256::from(367_879_441_171_442_321_595_523_770u128);
+        assert!(e_inverse <= expected + 100 && e_inverse >= expected - 100);
+    }
+
+    #[test]
+    fn swap_matches_constant_product_with_equal_weights() {
+        let curve = WeightedCurve {
+            token_a_weight: 10,
+            token_b_weight: 10,
+        };
+        let result = curve
+            .swap_without_fees(100, 1_000, 10_000, TradeDirection::AtoB)
+            .unwrap();
+        // 10,000 - 1,000 * 10,000 / 1,100 = 909.09
+        assert_eq!(result.destination_amount_swapped, 909);
+        // 1,000 * 10,000 / (10,000 - 909) = 1,099.99
+        assert_eq!(result.source_amount_swapped, 100);
+    }
+
+    #[test]
+    fn swap_weighted() {
+        let curve = WeightedCurve {
+            token_a_weight: 80,
+            token_b_weight: 20,
+        };
+        // 10,000 * (1 - (1,000 / 1,100) ^ 4) = 3,169.86
+        let result = curve
+            .swap_without_fees(100, 1_000, 10_000, TradeDirection::AtoB)
+            .unwrap();
+        assert_eq!(result.destination_amount_swapped, 3_169);
+        assert_eq!(result.source_amount_swapped, 100);
+        // 1,000 * (1 - (10,000 / 10,100) ^ 0.25) = 2.48
+        let result = curve
+            .swap_without_fees(100, 10_000, 1_000, TradeDirection::BtoA)
+            .unwrap();
+        assert_eq!(result.destination_amount_swapped, 2);
+        // (1,000 / 998) ^ 4 * 10,000 = 10,080.48
+        assert_eq!(result.source_amount_swapped, 81);
+    }
+
+    #[test]
+    fn swap_too_small() {
+        let curve = WeightedCurve {
+            token_a_weight: 1,
+            token_b_weight: 1,
+        };
+        assert!(curve
+            .swap_without_fees(10, 70_000_000_000, 4_000_000, TradeDirection::AtoB)
+            .is_none());
+    }
+
+    #[test]
+    fn normalized_value_of_pool() {
+        let value = normalized_value(&[(10_000, 1), (1_000_000, 1)]).unwrap();
+        assert_eq!(value.to_imprecise().unwrap(), 100_000);
+        let value = normalized_value(&[(1_000, 1), (1_000, 2), (1_000, 5)]).unwrap();
+        assert_eq!(value.to_imprecise().unwrap(), 1_000);
+        let value = normalized_value(&[(0, 1), (1_000, 1)]).unwrap();
+        assert_eq!(value.to_imprecise().unwrap(), 0);
+    }
+
+    #[test]
+    fn single_token_deposit_and_withdrawal() {
+        // 1,000,000 * ((1 + 210 / 1,000) ^ 0.5 - 1) = 100,000
+        let pool_tokens =
+            deposit_single_token_type(210, 1_000, 1, 2, 1_000_000, RoundDirection::Floor).unwrap();
+        assert_eq!(pool_tokens, 99_999);
+        // 1,000,000 * (1 - (1 - 190 / 1,000) ^ 0.5) = 100,000
+        let pool_tokens = withdraw_single_token_type_exact_out(
+            190,
+            1_000,
+            1,
+            2,
+            1_000_000,
+            RoundDirection::Ceiling,
+        )
+        .unwrap();
+        assert_eq!(pool_tokens, 100_001);
+        let pool_tokens = withdraw_single_token_type_exact_out(
+            1_000,
+            1_000,
+            1,
+            3,
+            1_000_000,
+            RoundDirection::Ceiling,
+        )
+        .unwrap();
+        assert_eq!(pool_tokens, 1_000_000);
+    }
+
+    prop_compose! {
+        pub fn weights()(token_a_weight in 1..=MAX_WEIGHT_RATIO)
+                        (token_b_weight in 1..=MAX_WEIGHT_RATIO, token_a_weight in Just(token_a_weight))
+                        -> WeightedCurve {
+            WeightedCurve { token_a_weight, token_b_weight }
+        }
+    }
+
+    #[test]
+    fn spot_price_with_weights() {
+        let curve = WeightedCurve {
+            token_a_weight: 80,
+            token_b_weight: 20,
+        };
+        // (1,000 / 20) / (1,000 / 80) = 4
+        let price = curve.spot_price(1_000, 1_000).unwrap();
+        assert_eq!(price, PreciseNumber::new(4).unwrap());
+        assert!(curve.spot_price(0, 1_000).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn deposit_token_conversion(
+            // in the pool token conversion calcs, we simulate trading half of
+            // source_token_amount, so this needs to be at least 2
+            source_token_amount in 2..u64::MAX,
+            swap_source_amount in 1..u64::MAX,
+            swap_destination_amount in 1..u64::MAX,
+            pool_supply in INITIAL_SWAP_POOL_AMOUNT..u64::MAX as u128,
+            curve in weights(),
+        ) {
+            // Make sure that the swap of half the source amount gives
+            // something, otherwise the calculation fails
+            prop_assume!(curve.swap_without_fees(
+                source_token_amount as u128 / 2,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::AtoB,
+            ).is_some());
+            check_deposit_token_conversion(
+                &curve,
+                source_token_amount as u128,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::AtoB,
+                pool_supply,
+                CONVERSION_BASIS_POINTS_GUARANTEE,
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn withdraw_token_conversion(
+            (pool_token_supply, pool_token_amount) in total_and_intermediate(),
+            swap_token_a_amount in 1..u64::MAX,
+            swap_token_b_amount in 1..u64::MAX,
+            curve in weights(),
+        ) {
+            let pool_token_amount = pool_token_amount as u128;
+            let pool_token_supply = pool_token_supply as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            let swap_token_b_amount = swap_token_b_amount as u128;
+            // Make sure we will get at least one trading token out for each
+            // side, otherwise the calculation fails
+            prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+            prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+            // The swap after the withdrawal must also give something, and
+            // leave enough of token B in the pool for the single-sided
+            // withdrawal to not be dominated by rounding, since a heavy
+            // token A drains token B much faster than a constant product
+            let withdrawn_a_amount = pool_token_amount * swap_token_a_amount / pool_token_supply;
+            let withdrawn_b_amount = pool_token_amount * swap_token_b_amount / pool_token_supply;
+            let results = curve.swap_without_fees(
+                withdrawn_a_amount,
+                swap_token_a_amount - withdrawn_a_amount,
+                swap_token_b_amount - withdrawn_b_amount,
+                TradeDirection::AtoB,
+            );
+            prop_assume!(results.is_some());
+            let remaining_b_amount = swap_token_b_amount
+                - withdrawn_b_amount
+                - results.unwrap().destination_amount_swapped;
+            prop_assume!(remaining_b_amount * 1_000 >= swap_token_b_amount);
+            check_withdraw_token_conversion(
+                &curve,
+                pool_token_amount,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+                TradeDirection::AtoB,
+                CONVERSION_BASIS_POINTS_GUARANTEE
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_swap(
+            source_token_amount in 1..u64::MAX,
+            swap_source_amount in 1..u64::MAX,
+            swap_destination_amount in 1..u64::MAX,
+            curve in weights(),
+        ) {
+            prop_assume!(curve.swap_without_fees(
+                source_token_amount as u128,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::AtoB,
+            ).is_some());
+            check_curve_value_from_swap(
+                &curve,
+                source_token_amount as u128,
+                swap_source_amount as u128,
+                swap_destination_amount as u128,
+                TradeDirection::AtoB
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_deposit(
+            pool_token_amount in 1..u64::MAX,
+            pool_token_supply in 1..u64::MAX,
+            swap_token_a_amount in 1..u64::MAX,
+            swap_token_b_amount in 1..u64::MAX,
+            curve in weights(),
+        ) {
+            let pool_token_amount = pool_token_amount as u128;
+            let pool_token_supply = pool_token_supply as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            let swap_token_b_amount = swap_token_b_amount as u128;
+            // Make sure we will get at least one trading token out for each
+            // side, otherwise the calculation fails
+            prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+            prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+            check_pool_value_from_deposit(
+                &curve,
+                pool_token_amount,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn curve_value_does_not_decrease_from_withdraw(
+            (pool_token_supply, pool_token_amount) in total_and_intermediate(),
+            swap_token_a_amount in 1..u64::MAX,
+            swap_token_b_amount in 1..u64::MAX,
+            curve in weights(),
+        ) {
+            let pool_token_amount = pool_token_amount as u128;
+            let pool_token_supply = pool_token_supply as u128;
+            let swap_token_a_amount = swap_token_a_amount as u128;
+            let swap_token_b_amount = swap_token_b_amount as u128;
+            // Make sure we will get at least one trading token out for each
+            // side, otherwise the calculation fails
+            prop_assume!(pool_token_amount * swap_token_a_amount / pool_token_supply >= 1);
+            prop_assume!(pool_token_amount * swap_token_b_amount / pool_token_supply >= 1);
+            check_pool_value_from_withdraw(
+                &curve,
+                pool_token_amount,
+                pool_token_supply,
+                swap_token_a_amount,
+                swap_token_b_amount,
+            );
+        }
+    }
+}
@@ -102,6 +102,20 @@ pub enum SwapError {
     /// The operation cannot be performed on the given curve
     #[error("The operation cannot be performed on the given curve")]
     UnsupportedCurveOperation,
+    /// The token index is out of range for the pool
+    #[error("The token index is out of range for the pool")]
+    InvalidTokenIndex,
+    /// The swap has no admin authority, or it did not sign
+    #[error("The swap has no admin authority, or it did not sign")]
+    InvalidAdmin,
+
+    // 30.
+    /// The swap is paused by its admin authority
+    #[error("The swap is paused by its admin authority")]
+    SwapPaused,
+    /// The amplification coefficient ramp is too fast or out of range
+    #[error("The amplification coefficient ramp is too fast or out of range")]
+    InvalidAmpRamp,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
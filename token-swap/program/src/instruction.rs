@@ -2,9 +2,13 @@
 
 #![allow(clippy::too_many_arguments)]
 
-use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::curve::{
+    base::SwapCurve,
+    fees::{DynamicFees, Fees},
+};
 use crate::error::SwapError;
 use solana_program::{
+    clock::UnixTimestamp,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     program_pack::Pack,
@@ -27,6 +31,9 @@ pub struct Initialize {
     /// swap curve info for pool, including CurveType and anything
     /// else that may be required
     pub swap_curve: SwapCurve,
+    /// optional dynamic trade fee, only packed if enabled, so that the
+    /// instruction data of swaps with static fees is unchanged
+    pub dynamic_fees: DynamicFees,
 }
 
 /// Swap instruction data
@@ -92,6 +99,122 @@ pub struct WithdrawSingleTokenTypeExactAmountOut {
     pub maximum_pool_token_amount: u64,
 }
 
+/// InitializeWeighted instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct InitializeWeighted {
+    /// nonce used to create valid program address
+    pub nonce: u8,
+    /// all swap fees
+    pub fees: Fees,
+    /// weight of each token, in the order of the token accounts
+    pub weights: Vec<u64>,
+    /// optional dynamic trade fee, only packed if enabled
+    pub dynamic_fees: DynamicFees,
+}
+
+/// WeightedSwap instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedSwap {
+    /// Index of the SOURCE token in the swap
+    pub source_index: u8,
+    /// Index of the DESTINATION token in the swap
+    pub destination_index: u8,
+    /// SOURCE amount to transfer, output to DESTINATION is based on the exchange rate
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to output, prevents excessive slippage
+    pub minimum_amount_out: u64,
+}
+
+/// WeightedDepositAllTokenTypes instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedDepositAllTokenTypes {
+    /// Pool token amount to transfer. Token amounts are set by the current
+    /// exchange rate and size of the pool
+    pub pool_token_amount: u64,
+    /// Maximum amount of each token to deposit, prevents excessive slippage
+    pub maximum_token_amounts: Vec<u64>,
+}
+
+/// WeightedWithdrawAllTokenTypes instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedWithdrawAllTokenTypes {
+    /// Amount of pool tokens to burn. User receives an output of each token
+    /// based on the percentage of the pool tokens that are returned.
+    pub pool_token_amount: u64,
+    /// Minimum amount of each token to receive, prevents excessive slippage
+    pub minimum_token_amounts: Vec<u64>,
+}
+
+/// WeightedDepositSingleTokenTypeExactAmountIn instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedDepositSingleTokenTypeExactAmountIn {
+    /// Index of the deposited token in the swap
+    pub token_index: u8,
+    /// Token amount to deposit
+    pub source_token_amount: u64,
+    /// Pool token amount to receive in exchange. The amount is set by
+    /// the current exchange rate and size of the pool
+    pub minimum_pool_token_amount: u64,
+}
+
+/// WeightedWithdrawSingleTokenTypeExactAmountOut instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedWithdrawSingleTokenTypeExactAmountOut {
+    /// Index of the withdrawn token in the swap
+    pub token_index: u8,
+    /// Amount of the token to receive
+    pub destination_token_amount: u64,
+    /// Maximum amount of pool tokens to burn. User receives an output of the
+    /// token based on the percentage of the pool tokens that are returned.
+    pub maximum_pool_token_amount: u64,
+}
+
+/// RoutedSwap instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutedSwap {
+    /// SOURCE amount to transfer into the first pool, output of each pool is
+    /// swapped through the next one
+    pub amount_in: u64,
+    /// Minimum amount of the final DESTINATION token to output, prevents
+    /// excessive slippage over the whole route
+    pub minimum_amount_out: u64,
+}
+
+/// SetFees instruction data
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub struct SetFees {
+    /// new swap fees
+    pub fees: Fees,
+    /// new dynamic trade fee, only packed if enabled, disabling the dynamic
+    /// fee if absent
+    pub dynamic_fees: DynamicFees,
+}
+
+/// RampAmp instruction data
+#[cfg_attr(feature = "fuzz", derive(Arbitrary))]
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RampAmp {
+    /// Amplification coefficient of the stable curve at the end of the ramp
+    pub target_amp: u64,
+    /// Unix timestamp of the end of the ramp, which starts immediately
+    pub stop_ramp_timestamp: UnixTimestamp,
+}
+
 /// Instructions supported by the token swap program.
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -108,11 +231,14 @@ pub enum SwapInstruction {
     ///   6. `[writable]` Pool Token Account to deposit the initial pool token
     ///   supply.  Must be empty, not owned by swap authority.
     ///   7. '[]` Token program id
+    ///   8. `[]` Admin authority (optional).  Allowed to change the swap
+    ///   with the admin instructions.  Requires an account of the latest size.
     Initialize(Initialize),
 
-    ///   Swap the tokens in the pool.
+    ///   Swap the tokens in the pool.  For weighted pools, any two of the
+    ///   tokens of the pool can be swapped.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
@@ -129,7 +255,7 @@ pub enum SwapInstruction {
     ///   token representing ownership in the pool. Inputs are converted to
     ///   the current ratio.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_a user transfer authority can transfer amount,
@@ -145,7 +271,7 @@ pub enum SwapInstruction {
     ///   pool tokens.  The pool tokens are burned in exchange for an equivalent
     ///   amount of token A and B.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -162,7 +288,7 @@ pub enum SwapInstruction {
     ///   representing ownership into the pool. Input token is converted as if
     ///   a swap and deposit all token types were performed.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority,
@@ -176,7 +302,7 @@ pub enum SwapInstruction {
     ///   Withdraw one token type from the pool at the current ratio given the
     ///   exact amount out expected.
     ///
-    ///   0. `[]` Token-swap
+    ///   0. `[writable]` Token-swap
     ///   1. `[]` swap authority
     ///   2. `[]` user transfer authority
     ///   3. `[writable]` Pool mint account, swap authority is the owner
@@ -187,6 +313,150 @@ pub enum SwapInstruction {
     ///   8. `[writable]` Fee account, to receive withdrawal fees
     ///   9. '[]` Token program id
     WithdrawSingleTokenTypeExactAmountOut(WithdrawSingleTokenTypeExactAmountOut),
+
+    ///   Initializes a new weighted swap of `N` tokens, between
+    ///   `WeightedSwapV1::MIN_TOKENS` and `WeightedSwapV1::MAX_TOKENS`
+    ///
+    ///   0. `[writable, signer]` New Token-swap to create.
+    ///   1. `[]` swap authority derived from `create_program_address(&[Token-swap account])`
+    ///   2. `[writable]` Pool Token Mint. Must be empty, owned by swap authority.
+    ///   3. `[]` Pool Token Account to deposit trading and withdraw fees.
+    ///   Must be empty, not owned by swap authority
+    ///   4. `[writable]` Pool Token Account to deposit the initial pool token
+    ///   supply.  Must be empty, not owned by swap authority.
+    ///   5. '[]` Token program id
+    ///   6..6+N. `[]` token Accounts. Must be non zero, owned by swap authority.
+    ///   6+N. `[]` Admin authority (optional).  Allowed to change the swap
+    ///   with the admin instructions.
+    InitializeWeighted(InitializeWeighted),
+
+    ///   Swap between two of the tokens in a weighted pool, given by index.
+    ///   Equivalent to a `Swap` between the same token accounts, which also
+    ///   supports weighted pools.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user transfer authority,
+    ///   4. `[writable]` Base Account to swap INTO.  Must be the token at the source index.
+    ///   5. `[writable]` Base Account to swap FROM.  Must be the token at the destination index.
+    ///   6. `[writable]` DESTINATION Account assigned to USER as the owner.
+    ///   7. `[writable]` Pool token mint, to generate trading fees
+    ///   8. `[writable]` Fee account, to receive trading fees
+    ///   9. '[]` Token program id
+    ///   10 `[optional, writable]` Host fee account to receive additional trading fees
+    WeightedSwap(WeightedSwap),
+
+    ///   Deposit all types of tokens into a weighted pool.  The output is a
+    ///   "pool" token representing ownership in the pool. Inputs are converted
+    ///   to the current ratio.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` Pool MINT account, swap authority is the owner.
+    ///   4. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   5. '[]` Token program id
+    ///   6..6+N. `[writable]` token accounts user transfer authority can transfer amount,
+    ///   6+N..6+2N. `[writable]` token Base Accounts to deposit into.
+    WeightedDepositAllTokenTypes(WeightedDepositAllTokenTypes),
+
+    ///   Withdraw all types of tokens from a weighted pool at the current
+    ///   ratio, given pool tokens.  The pool tokens are burned in exchange for
+    ///   an equivalent amount of each token.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` Pool mint account, swap authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
+    ///   5. `[writable]` Fee account, to receive withdrawal fees
+    ///   6. '[]` Token program id
+    ///   7..7+N. `[writable]` token Swap Accounts to withdraw FROM.
+    ///   7+N..7+2N. `[writable]` token user Accounts to credit.
+    WeightedWithdrawAllTokenTypes(WeightedWithdrawAllTokenTypes),
+
+    ///   Deposit one type of tokens into a weighted pool.  The output is a
+    ///   "pool" token representing ownership into the pool.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` SOURCE Account, amount is transferable by user transfer authority,
+    ///   4. `[writable]` Swap Account of the token at the given index to deposit INTO.
+    ///   5. `[writable]` Pool MINT account, swap authority is the owner.
+    ///   6. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   7. '[]` Token program id
+    WeightedDepositSingleTokenTypeExactAmountIn(WeightedDepositSingleTokenTypeExactAmountIn),
+
+    ///   Withdraw one token type from a weighted pool at the current ratio
+    ///   given the exact amount out expected.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[]` user transfer authority
+    ///   3. `[writable]` Pool mint account, swap authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by user transfer authority.
+    ///   5. `[writable]` Swap Account of the token at the given index to withdraw FROM.
+    ///   6. `[writable]` User Account to credit
+    ///   7. `[writable]` Fee account, to receive withdrawal fees
+    ///   8. '[]` Token program id
+    WeightedWithdrawSingleTokenTypeExactAmountOut(WeightedWithdrawSingleTokenTypeExactAmountOut),
+
+    ///   Swap tokens through an ordered list of pools, using the output of
+    ///   each pool as the input of the next one.  Only the output of the last
+    ///   pool is checked against the minimum amount.
+    ///
+    ///   0. `[]` user transfer authority
+    ///   1. `[writable]` SOURCE Account, amount is transferable by user transfer authority,
+    ///   2. '[]` Token program id
+    ///
+    ///   Then, for each pool in the route:
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` swap authority
+    ///   2. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the input token of the pool.
+    ///   3. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the output token of the pool.
+    ///   4. `[writable]` Pool token mint, to generate trading fees
+    ///   5. `[writable]` Fee account, to receive trading fees
+    ///   6. `[writable]` DESTINATION Account of the pool.  For all pools but the
+    ///      last, this is an intermediate account, amount is transferable by
+    ///      user transfer authority.  Any intermediate tokens not taken
+    ///      by the next pool due to rounding remain in this account.
+    RoutedSwap(RoutedSwap),
+
+    ///   Replace the fees of a swap, including its dynamic trade fee.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Admin authority
+    SetFees(SetFees),
+
+    ///   Replace the account receiving the trading and withdraw fees of a swap.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Admin authority
+    ///   2. `[]` New pool token account to deposit trading and withdraw fees.
+    ///   Not owned by swap authority.
+    SetPoolFeeAccount,
+
+    ///   Pause swaps and deposits.  Withdrawals are always allowed.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Admin authority
+    Pause,
+
+    ///   Resume swaps and deposits.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Admin authority
+    Unpause,
+
+    ///   Linearly change the amplification coefficient of a stable curve,
+    ///   from its current value to the target, until the stop time.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Admin authority
+    RampAmp(RampAmp),
 }
 
 impl SwapInstruction {
@@ -199,11 +469,21 @@ impl SwapInstruction {
                 if rest.len() >= Fees::LEN {
                     let (fees, rest) = rest.split_at(Fees::LEN);
                     let fees = Fees::unpack_unchecked(fees)?;
-                    let swap_curve = SwapCurve::unpack_unchecked(rest)?;
+                    let (swap_curve, dynamic_fees) =
+                        if rest.len() == SwapCurve::LEN + DynamicFees::LEN {
+                            let (swap_curve, dynamic_fees) = rest.split_at(SwapCurve::LEN);
+                            (
+                                SwapCurve::unpack_unchecked(swap_curve)?,
+                                DynamicFees::unpack_unchecked(dynamic_fees)?,
+                            )
+                        } else {
+                            (SwapCurve::unpack_unchecked(rest)?, DynamicFees::default())
+                        };
                     Self::Initialize(Initialize {
                         nonce,
                         fees,
                         swap_curve,
+                        dynamic_fees,
                     })
                 } else {
                     return Err(SwapError::InvalidInstruction.into());
@@ -253,6 +533,115 @@ impl SwapInstruction {
                     maximum_pool_token_amount,
                 })
             }
+            6 => {
+                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                if rest.len() >= Fees::LEN {
+                    let (fees, rest) = rest.split_at(Fees::LEN);
+                    let fees = Fees::unpack_unchecked(fees)?;
+                    let (weights, rest) = Self::unpack_u64_vec(rest)?;
+                    let dynamic_fees = if rest.is_empty() {
+                        DynamicFees::default()
+                    } else {
+                        DynamicFees::unpack_unchecked(rest)?
+                    };
+                    Self::InitializeWeighted(InitializeWeighted {
+                        nonce,
+                        fees,
+                        weights,
+                        dynamic_fees,
+                    })
+                } else {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+            }
+            7 => {
+                let (&source_index, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (&destination_index, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
+                Self::WeightedSwap(WeightedSwap {
+                    source_index,
+                    destination_index,
+                    amount_in,
+                    minimum_amount_out,
+                })
+            }
+            8 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_token_amounts, _rest) = Self::unpack_u64_vec(rest)?;
+                Self::WeightedDepositAllTokenTypes(WeightedDepositAllTokenTypes {
+                    pool_token_amount,
+                    maximum_token_amounts,
+                })
+            }
+            9 => {
+                let (pool_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_token_amounts, _rest) = Self::unpack_u64_vec(rest)?;
+                Self::WeightedWithdrawAllTokenTypes(WeightedWithdrawAllTokenTypes {
+                    pool_token_amount,
+                    minimum_token_amounts,
+                })
+            }
+            10 => {
+                let (&token_index, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (source_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (minimum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WeightedDepositSingleTokenTypeExactAmountIn(
+                    WeightedDepositSingleTokenTypeExactAmountIn {
+                        token_index,
+                        source_token_amount,
+                        minimum_pool_token_amount,
+                    },
+                )
+            }
+            11 => {
+                let (&token_index, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (destination_token_amount, rest) = Self::unpack_u64(rest)?;
+                let (maximum_pool_token_amount, _rest) = Self::unpack_u64(rest)?;
+                Self::WeightedWithdrawSingleTokenTypeExactAmountOut(
+                    WeightedWithdrawSingleTokenTypeExactAmountOut {
+                        token_index,
+                        destination_token_amount,
+                        maximum_pool_token_amount,
+                    },
+                )
+            }
+            12 => {
+                let (amount_in, rest) = Self::unpack_u64(rest)?;
+                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
+                Self::RoutedSwap(RoutedSwap {
+                    amount_in,
+                    minimum_amount_out,
+                })
+            }
+            13 => {
+                if rest.len() < Fees::LEN {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+                let (fees, rest) = rest.split_at(Fees::LEN);
+                let fees = Fees::unpack_unchecked(fees)?;
+                let dynamic_fees = if rest.is_empty() {
+                    DynamicFees::default()
+                } else {
+                    DynamicFees::unpack_unchecked(rest)?
+                };
+                Self::SetFees(SetFees { fees, dynamic_fees })
+            }
+            14 => Self::SetPoolFeeAccount,
+            15 => Self::Pause,
+            16 => Self::Unpause,
+            17 => {
+                let (target_amp, rest) = Self::unpack_u64(rest)?;
+                let (stop_ramp_timestamp, _rest) = Self::unpack_u64(rest)?;
+                Self::RampAmp(RampAmp {
+                    target_amp,
+                    stop_ramp_timestamp: stop_ramp_timestamp as UnixTimestamp,
+                })
+            }
             _ => return Err(SwapError::InvalidInstruction.into()),
         })
     }
@@ -271,6 +660,24 @@ impl SwapInstruction {
         }
     }
 
+    fn unpack_u64_vec(input: &[u8]) -> Result<(Vec<u64>, &[u8]), ProgramError> {
+        let (&len, mut rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        let mut values = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let (value, next) = Self::unpack_u64(rest)?;
+            values.push(value);
+            rest = next;
+        }
+        Ok((values, rest))
+    }
+
+    fn pack_u64_vec(values: &[u64], buf: &mut Vec<u8>) {
+        buf.push(values.len() as u8);
+        for value in values {
+            buf.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
     /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
@@ -279,6 +686,7 @@ impl SwapInstruction {
                 nonce,
                 fees,
                 swap_curve,
+                dynamic_fees,
             }) => {
                 buf.push(0);
                 buf.push(*nonce);
@@ -288,6 +696,11 @@ impl SwapInstruction {
                 let mut swap_curve_slice = [0u8; SwapCurve::LEN];
                 Pack::pack_into_slice(swap_curve, &mut swap_curve_slice[..]);
                 buf.extend_from_slice(&swap_curve_slice);
+                if dynamic_fees.is_enabled() {
+                    let mut dynamic_fees_slice = [0u8; DynamicFees::LEN];
+                    Pack::pack_into_slice(dynamic_fees, &mut dynamic_fees_slice[..]);
+                    buf.extend_from_slice(&dynamic_fees_slice);
+                }
             }
             Self::Swap(Swap {
                 amount_in,
@@ -335,6 +748,106 @@ impl SwapInstruction {
                 buf.extend_from_slice(&destination_token_amount.to_le_bytes());
                 buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
             }
+            Self::InitializeWeighted(InitializeWeighted {
+                nonce,
+                fees,
+                weights,
+                dynamic_fees,
+            }) => {
+                buf.push(6);
+                buf.push(*nonce);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                Self::pack_u64_vec(weights, &mut buf);
+                if dynamic_fees.is_enabled() {
+                    let mut dynamic_fees_slice = [0u8; DynamicFees::LEN];
+                    Pack::pack_into_slice(dynamic_fees, &mut dynamic_fees_slice[..]);
+                    buf.extend_from_slice(&dynamic_fees_slice);
+                }
+            }
+            Self::WeightedSwap(WeightedSwap {
+                source_index,
+                destination_index,
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                buf.push(7);
+                buf.push(*source_index);
+                buf.push(*destination_index);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+            }
+            Self::WeightedDepositAllTokenTypes(WeightedDepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_amounts,
+            }) => {
+                buf.push(8);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                Self::pack_u64_vec(maximum_token_amounts, &mut buf);
+            }
+            Self::WeightedWithdrawAllTokenTypes(WeightedWithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_amounts,
+            }) => {
+                buf.push(9);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                Self::pack_u64_vec(minimum_token_amounts, &mut buf);
+            }
+            Self::WeightedDepositSingleTokenTypeExactAmountIn(
+                WeightedDepositSingleTokenTypeExactAmountIn {
+                    token_index,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                buf.push(10);
+                buf.push(*token_index);
+                buf.extend_from_slice(&source_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+            }
+            Self::WeightedWithdrawSingleTokenTypeExactAmountOut(
+                WeightedWithdrawSingleTokenTypeExactAmountOut {
+                    token_index,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                buf.push(11);
+                buf.push(*token_index);
+                buf.extend_from_slice(&destination_token_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
+            }
+            Self::RoutedSwap(RoutedSwap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                buf.push(12);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+            }
+            Self::SetFees(SetFees { fees, dynamic_fees }) => {
+                buf.push(13);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                if dynamic_fees.is_enabled() {
+                    let mut dynamic_fees_slice = [0u8; DynamicFees::LEN];
+                    Pack::pack_into_slice(dynamic_fees, &mut dynamic_fees_slice[..]);
+                    buf.extend_from_slice(&dynamic_fees_slice);
+                }
+            }
+            Self::SetPoolFeeAccount => buf.push(14),
+            Self::Pause => buf.push(15),
+            Self::Unpause => buf.push(16),
+            Self::RampAmp(RampAmp {
+                target_amp,
+                stop_ramp_timestamp,
+            }) => {
+                buf.push(17);
+                buf.extend_from_slice(&target_amp.to_le_bytes());
+                buf.extend_from_slice(&stop_ramp_timestamp.to_le_bytes());
+            }
         }
         buf
     }
@@ -354,11 +867,45 @@ pub fn initialize(
     nonce: u8,
     fees: Fees,
     swap_curve: SwapCurve,
+) -> Result<Instruction, ProgramError> {
+    initialize_with_dynamic_fees(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_a_pubkey,
+        token_b_pubkey,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        nonce,
+        fees,
+        swap_curve,
+        DynamicFees::default(),
+    )
+}
+
+/// Creates an 'initialize' instruction for a swap with a dynamic trade fee.
+pub fn initialize_with_dynamic_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    nonce: u8,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    dynamic_fees: DynamicFees,
 ) -> Result<Instruction, ProgramError> {
     let init_data = SwapInstruction::Initialize(Initialize {
         nonce,
         fees,
         swap_curve,
+        dynamic_fees,
     });
     let data = init_data.pack();
 
@@ -380,6 +927,44 @@ pub fn initialize(
     })
 }
 
+/// Creates an 'initialize' instruction for a swap with an admin authority.
+pub fn initialize_with_admin(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+    nonce: u8,
+    fees: Fees,
+    swap_curve: SwapCurve,
+    dynamic_fees: DynamicFees,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = initialize_with_dynamic_fees(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_a_pubkey,
+        token_b_pubkey,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        nonce,
+        fees,
+        swap_curve,
+        dynamic_fees,
+    )?;
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*admin_authority_pubkey, false));
+    Ok(instruction)
+}
+
 /// Creates a 'deposit_all_token_types' instruction.
 pub fn deposit_all_token_types(
     program_id: &Pubkey,
@@ -398,7 +983,7 @@ pub fn deposit_all_token_types(
     let data = SwapInstruction::DepositAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*deposit_token_a_pubkey, false),
@@ -436,7 +1021,7 @@ pub fn withdraw_all_token_types(
     let data = SwapInstruction::WithdrawAllTokenTypes(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -473,7 +1058,7 @@ pub fn deposit_single_token_type_exact_amount_in(
     let data = SwapInstruction::DepositSingleTokenTypeExactAmountIn(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_token_pubkey, false),
@@ -509,7 +1094,7 @@ pub fn withdraw_single_token_type_exact_amount_out(
     let data = SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(instruction).pack();
 
     let accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*pool_mint_pubkey, false),
@@ -547,7 +1132,7 @@ pub fn swap(
     let data = SwapInstruction::Swap(instruction).pack();
 
     let mut accounts = vec![
-        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, false),
         AccountMeta::new_readonly(*authority_pubkey, false),
         AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
         AccountMeta::new(*source_pubkey, false),
@@ -569,11 +1154,465 @@ pub fn swap(
     })
 }
 
-/// Unpacks a reference from a bytes buffer.
-/// TODO actually pack / unpack instead of relying on normal memory layout.
-pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
-    if input.len() < size_of::<u8>() + size_of::<T>() {
-        return Err(ProgramError::InvalidAccountData);
+/// Creates an 'initialize_weighted' instruction.
+pub fn initialize_weighted(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_pubkeys: &[Pubkey],
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    nonce: u8,
+    fees: Fees,
+    weights: Vec<u64>,
+) -> Result<Instruction, ProgramError> {
+    initialize_weighted_with_dynamic_fees(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_pubkeys,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        nonce,
+        fees,
+        weights,
+        DynamicFees::default(),
+    )
+}
+
+/// Creates an 'initialize_weighted' instruction for a swap with a dynamic
+/// trade fee.
+pub fn initialize_weighted_with_dynamic_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_pubkeys: &[Pubkey],
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    nonce: u8,
+    fees: Fees,
+    weights: Vec<u64>,
+    dynamic_fees: DynamicFees,
+) -> Result<Instruction, ProgramError> {
+    let init_data = SwapInstruction::InitializeWeighted(InitializeWeighted {
+        nonce,
+        fees,
+        weights,
+        dynamic_fees,
+    });
+    let data = init_data.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*pool_pubkey, false),
+        AccountMeta::new_readonly(*fee_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    accounts.extend(
+        token_pubkeys
+            .iter()
+            .map(|pubkey| AccountMeta::new_readonly(*pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_weighted' instruction for a swap with an admin
+/// authority, and optionally a dynamic trade fee.
+pub fn initialize_weighted_with_admin(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    token_pubkeys: &[Pubkey],
+    pool_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+    nonce: u8,
+    fees: Fees,
+    weights: Vec<u64>,
+    dynamic_fees: DynamicFees,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction = initialize_weighted_with_dynamic_fees(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        token_pubkeys,
+        pool_pubkey,
+        fee_pubkey,
+        destination_pubkey,
+        nonce,
+        fees,
+        weights,
+        dynamic_fees,
+    )?;
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*admin_authority_pubkey, false));
+    Ok(instruction)
+}
+
+/// Creates a 'weighted_swap' instruction.
+pub fn weighted_swap(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+    host_fee_pubkey: Option<&Pubkey>,
+    instruction: WeightedSwap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WeightedSwap(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_fee_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    if let Some(host_fee_pubkey) = host_fee_pubkey {
+        accounts.push(AccountMeta::new(*host_fee_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'weighted_deposit_all_token_types' instruction.
+pub fn weighted_deposit_all_token_types(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    deposit_token_pubkeys: &[Pubkey],
+    swap_token_pubkeys: &[Pubkey],
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    instruction: WeightedDepositAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WeightedDepositAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    accounts.extend(
+        deposit_token_pubkeys
+            .iter()
+            .chain(swap_token_pubkeys.iter())
+            .map(|pubkey| AccountMeta::new(*pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'weighted_withdraw_all_token_types' instruction.
+pub fn weighted_withdraw_all_token_types(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_pubkeys: &[Pubkey],
+    destination_token_pubkeys: &[Pubkey],
+    instruction: WeightedWithdrawAllTokenTypes,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WeightedWithdrawAllTokenTypes(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    accounts.extend(
+        swap_token_pubkeys
+            .iter()
+            .chain(destination_token_pubkeys.iter())
+            .map(|pubkey| AccountMeta::new(*pubkey, false)),
+    );
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'weighted_deposit_single_token_type_exact_amount_in' instruction.
+pub fn weighted_deposit_single_token_type_exact_amount_in(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_token_pubkey: &Pubkey,
+    swap_token_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    instruction: WeightedDepositSingleTokenTypeExactAmountIn,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WeightedDepositSingleTokenTypeExactAmountIn(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_token_pubkey, false),
+        AccountMeta::new(*swap_token_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'weighted_withdraw_single_token_type_exact_amount_out' instruction.
+pub fn weighted_withdraw_single_token_type_exact_amount_out(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    fee_account_pubkey: &Pubkey,
+    pool_token_source_pubkey: &Pubkey,
+    swap_token_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    instruction: WeightedWithdrawSingleTokenTypeExactAmountOut,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WeightedWithdrawSingleTokenTypeExactAmountOut(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_token_source_pubkey, false),
+        AccountMeta::new(*swap_token_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*fee_account_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Accounts of one pool in a 'routed_swap' instruction
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteHop {
+    /// Token-swap
+    pub swap_pubkey: Pubkey,
+    /// Swap authority
+    pub authority_pubkey: Pubkey,
+    /// Base account of the pool to swap INTO
+    pub swap_source_pubkey: Pubkey,
+    /// Base account of the pool to swap FROM
+    pub swap_destination_pubkey: Pubkey,
+    /// Pool token mint, to generate trading fees
+    pub pool_mint_pubkey: Pubkey,
+    /// Fee account, to receive trading fees
+    pub pool_fee_pubkey: Pubkey,
+    /// Account receiving the output of the pool
+    pub destination_pubkey: Pubkey,
+}
+
+/// Creates a 'routed_swap' instruction.
+pub fn routed_swap(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    user_transfer_authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    route: &[RouteHop],
+    instruction: RoutedSwap,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::RoutedSwap(instruction).pack();
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*user_transfer_authority_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    for hop in route {
+        accounts.push(AccountMeta::new(hop.swap_pubkey, false));
+        accounts.push(AccountMeta::new_readonly(hop.authority_pubkey, false));
+        accounts.push(AccountMeta::new(hop.swap_source_pubkey, false));
+        accounts.push(AccountMeta::new(hop.swap_destination_pubkey, false));
+        accounts.push(AccountMeta::new(hop.pool_mint_pubkey, false));
+        accounts.push(AccountMeta::new(hop.pool_fee_pubkey, false));
+        accounts.push(AccountMeta::new(hop.destination_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_fees' instruction.
+pub fn set_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+    fees: Fees,
+    dynamic_fees: DynamicFees,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SetFees(SetFees { fees, dynamic_fees }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_pool_fee_account' instruction.
+pub fn set_pool_fee_account(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+    fee_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SetPoolFeeAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_authority_pubkey, true),
+        AccountMeta::new_readonly(*fee_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'pause' instruction.
+pub fn pause(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Pause.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'unpause' instruction.
+pub fn unpause(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Unpause.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'ramp_amp' instruction.
+pub fn ramp_amp(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_authority_pubkey: &Pubkey,
+    instruction: RampAmp,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::RampAmp(instruction).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Unpacks a reference from a bytes buffer.
+/// TODO actually pack / unpack instead of relying on normal memory layout.
+pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
+    if input.len() < size_of::<u8>() + size_of::<T>() {
+        return Err(ProgramError::InvalidAccountData);
     }
     #[allow(clippy::cast_ptr_alignment)]
     let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
@@ -616,8 +1655,9 @@ mod tests {
         };
         let check = SwapInstruction::Initialize(Initialize {
             nonce,
-            fees,
+            fees: fees.clone(),
             swap_curve,
+            dynamic_fees: DynamicFees::default(),
         });
         let packed = check.pack();
         let mut expect = vec![0u8, nonce];
@@ -635,6 +1675,30 @@ mod tests {
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let max_trade_fee_numerator: u64 = 3;
+        let max_volatility: u64 = 1_000;
+        let decay_period: u64 = 150;
+        let check = SwapInstruction::Initialize(Initialize {
+            nonce,
+            fees,
+            swap_curve: SwapCurve {
+                curve_type,
+                calculator: Box::new(StableCurve { amp }),
+            },
+            dynamic_fees: DynamicFees {
+                max_trade_fee_numerator,
+                max_volatility,
+                decay_period,
+            },
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&max_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&max_volatility.to_le_bytes());
+        expect.extend_from_slice(&decay_period.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
 
     #[test]
@@ -731,4 +1795,252 @@ mod tests {
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
     }
+    #[test]
+    fn pack_initialize_weighted() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 4,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 5,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 3,
+            host_fee_numerator: 5,
+            host_fee_denominator: 20,
+        };
+        let nonce: u8 = 254;
+        let weights = vec![50, 30, 20];
+        let check = SwapInstruction::InitializeWeighted(InitializeWeighted {
+            nonce,
+            fees: fees.clone(),
+            weights: weights.clone(),
+            dynamic_fees: DynamicFees::default(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![6u8, nonce];
+        let mut fees_slice = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+        expect.extend_from_slice(&fees_slice);
+        expect.push(3);
+        for weight in &weights {
+            expect.extend_from_slice(&weight.to_le_bytes());
+        }
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 3,
+            max_volatility: 1_000,
+            decay_period: 150,
+        };
+        let check = SwapInstruction::InitializeWeighted(InitializeWeighted {
+            nonce,
+            fees,
+            weights,
+            dynamic_fees: dynamic_fees.clone(),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&dynamic_fees.max_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&dynamic_fees.max_volatility.to_le_bytes());
+        expect.extend_from_slice(&dynamic_fees.decay_period.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_weighted_swap() {
+        let source_index: u8 = 2;
+        let destination_index: u8 = 0;
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::WeightedSwap(WeightedSwap {
+            source_index,
+            destination_index,
+            amount_in,
+            minimum_amount_out,
+        });
+        let packed = check.pack();
+        let mut expect = vec![7, source_index, destination_index];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_weighted_deposit() {
+        let pool_token_amount: u64 = 5;
+        let maximum_token_amounts = vec![10, 20, 30, 40];
+        let check = SwapInstruction::WeightedDepositAllTokenTypes(WeightedDepositAllTokenTypes {
+            pool_token_amount,
+            maximum_token_amounts: maximum_token_amounts.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![8];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.push(4);
+        for amount in maximum_token_amounts {
+            expect.extend_from_slice(&amount.to_le_bytes());
+        }
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        let err = SwapInstruction::unpack(&expect[..expect.len() - 1]).unwrap_err();
+        assert_eq!(err, SwapError::InvalidInstruction.into());
+    }
+
+    #[test]
+    fn pack_weighted_withdraw() {
+        let pool_token_amount: u64 = 1212438012089;
+        let minimum_token_amounts = vec![102198761982612, 2011239855213, 1];
+        let check = SwapInstruction::WeightedWithdrawAllTokenTypes(WeightedWithdrawAllTokenTypes {
+            pool_token_amount,
+            minimum_token_amounts: minimum_token_amounts.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![9];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.push(3);
+        for amount in minimum_token_amounts {
+            expect.extend_from_slice(&amount.to_le_bytes());
+        }
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_weighted_deposit_one_exact_in() {
+        let token_index: u8 = 1;
+        let source_token_amount: u64 = 10;
+        let minimum_pool_token_amount: u64 = 5;
+        let check = SwapInstruction::WeightedDepositSingleTokenTypeExactAmountIn(
+            WeightedDepositSingleTokenTypeExactAmountIn {
+                token_index,
+                source_token_amount,
+                minimum_pool_token_amount,
+            },
+        );
+        let packed = check.pack();
+        let mut expect = vec![10, token_index];
+        expect.extend_from_slice(&source_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_pool_token_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_weighted_withdraw_one_exact_out() {
+        let token_index: u8 = 7;
+        let destination_token_amount: u64 = 102198761982612;
+        let maximum_pool_token_amount: u64 = 1212438012089;
+        let check = SwapInstruction::WeightedWithdrawSingleTokenTypeExactAmountOut(
+            WeightedWithdrawSingleTokenTypeExactAmountOut {
+                token_index,
+                destination_token_amount,
+                maximum_pool_token_amount,
+            },
+        );
+        let packed = check.pack();
+        let mut expect = vec![11, token_index];
+        expect.extend_from_slice(&destination_token_amount.to_le_bytes());
+        expect.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_routed_swap() {
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::RoutedSwap(RoutedSwap {
+            amount_in,
+            minimum_amount_out,
+        });
+        let packed = check.pack();
+        let mut expect = vec![12];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_set_fees() {
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 4,
+            owner_trade_fee_numerator: 2,
+            owner_trade_fee_denominator: 5,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 3,
+            host_fee_numerator: 5,
+            host_fee_denominator: 20,
+        };
+        let check = SwapInstruction::SetFees(SetFees {
+            fees: fees.clone(),
+            dynamic_fees: DynamicFees::default(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![13];
+        let mut fees_slice = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+        expect.extend_from_slice(&fees_slice);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: 3,
+            max_volatility: 1_000,
+            decay_period: 150,
+        };
+        let check = SwapInstruction::SetFees(SetFees {
+            fees,
+            dynamic_fees: dynamic_fees.clone(),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&dynamic_fees.max_trade_fee_numerator.to_le_bytes());
+        expect.extend_from_slice(&dynamic_fees.max_volatility.to_le_bytes());
+        expect.extend_from_slice(&dynamic_fees.decay_period.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn pack_admin_without_data() {
+        for (check, tag) in &[
+            (SwapInstruction::SetPoolFeeAccount, 14),
+            (SwapInstruction::Pause, 15),
+            (SwapInstruction::Unpause, 16),
+        ] {
+            let packed = check.pack();
+            assert_eq!(packed, vec![*tag]);
+            let unpacked = SwapInstruction::unpack(&packed).unwrap();
+            assert_eq!(&unpacked, check);
+        }
+    }
+
+    #[test]
+    fn pack_ramp_amp() {
+        let target_amp: u64 = 100;
+        let stop_ramp_timestamp: UnixTimestamp = 1_630_086_400;
+        let check = SwapInstruction::RampAmp(RampAmp {
+            target_amp,
+            stop_ramp_timestamp,
+        });
+        let packed = check.pack();
+        let mut expect = vec![17];
+        expect.extend_from_slice(&target_amp.to_le_bytes());
+        expect.extend_from_slice(&stop_ramp_timestamp.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
 }
@@ -0,0 +1,206 @@
+//! Time-weighted average price oracle
+
+use crate::curve::calculator::CurveCalculator;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::UnixTimestamp,
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+};
+use spl_math::precise_number::PreciseNumber;
+use std::convert::TryFrom;
+
+/// Cumulative prices of the tokens in a swap, updated before every change to
+/// the pool balances.
+///
+/// Each cumulative price is the sum of the spot price of the token, as the
+/// inner value of a `PreciseNumber`, multiplied by the number of seconds that
+/// the price was in effect.  The sums are expected to wrap around on overflow,
+/// so only the difference between two observations is meaningful: divided by
+/// the time elapsed between them, it gives the average prices over that time.
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PriceAccumulator {
+    /// Unix timestamp of the last update
+    pub last_update_timestamp: UnixTimestamp,
+    /// Cumulative price of token A, denominated in token B
+    pub token_a_price_cumulative: u128,
+    /// Cumulative price of token B, denominated in token A
+    pub token_b_price_cumulative: u128,
+}
+
+impl PriceAccumulator {
+    /// Create a new accumulator, starting at the given time
+    pub fn new(timestamp: UnixTimestamp) -> Self {
+        Self {
+            last_update_timestamp: timestamp,
+            token_a_price_cumulative: 0,
+            token_b_price_cumulative: 0,
+        }
+    }
+
+    /// Accumulate the spot prices given by the pool balances for the time
+    /// elapsed since the last update.  The balances must be the ones in effect
+    /// since the last update, so this must be called before they change.
+    ///
+    /// If a spot price cannot be calculated, for example because one side of
+    /// the pool is empty, nothing is accumulated and the time of the last
+    /// update is kept, so that the elapsed time is accumulated with the next
+    /// price rather than averaged in as a price of zero.
+    pub fn update(
+        &mut self,
+        calculator: &dyn CurveCalculator,
+        swap_token_a_amount: u128,
+        swap_token_b_amount: u128,
+        timestamp: UnixTimestamp,
+    ) {
+        let elapsed = match timestamp.checked_sub(self.last_update_timestamp) {
+            Some(elapsed) if elapsed > 0 => elapsed as u128,
+            _ => return,
+        };
+        let (token_a_price, token_b_price) =
+            match spot_prices(calculator, swap_token_a_amount, swap_token_b_amount) {
+                Some(prices) => prices,
+                None => return,
+            };
+        self.token_a_price_cumulative = self
+            .token_a_price_cumulative
+            .wrapping_add(token_a_price.wrapping_mul(elapsed));
+        self.token_b_price_cumulative = self
+            .token_b_price_cumulative
+            .wrapping_add(token_b_price.wrapping_mul(elapsed));
+        self.last_update_timestamp = timestamp;
+    }
+}
+
+/// Spot prices of token A in token B and of token B in token A, as the inner
+/// values of `PreciseNumber`s
+fn spot_prices(
+    calculator: &dyn CurveCalculator,
+    swap_token_a_amount: u128,
+    swap_token_b_amount: u128,
+) -> Option<(u128, u128)> {
+    let token_a_price = calculator.spot_price(swap_token_a_amount, swap_token_b_amount)?;
+    let token_b_price = PreciseNumber::new(1)?.checked_div(&token_a_price)?;
+    Some((
+        u128::try_from(token_a_price.value).ok()?,
+        u128::try_from(token_b_price.value).ok()?,
+    ))
+}
+
+impl Sealed for PriceAccumulator {}
+impl Pack for PriceAccumulator {
+    const LEN: usize = 40;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 40];
+        let (last_update_timestamp, token_a_price_cumulative, token_b_price_cumulative) =
+            mut_array_refs![output, 8, 16, 16];
+        *last_update_timestamp = self.last_update_timestamp.to_le_bytes();
+        *token_a_price_cumulative = self.token_a_price_cumulative.to_le_bytes();
+        *token_b_price_cumulative = self.token_b_price_cumulative.to_le_bytes();
+    }
+
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 40];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (last_update_timestamp, token_a_price_cumulative, token_b_price_cumulative) =
+            array_refs![input, 8, 16, 16];
+        Ok(Self {
+            last_update_timestamp: UnixTimestamp::from_le_bytes(*last_update_timestamp),
+            token_a_price_cumulative: u128::from_le_bytes(*token_a_price_cumulative),
+            token_b_price_cumulative: u128::from_le_bytes(*token_b_price_cumulative),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{
+        constant_price::ConstantPriceCurve, constant_product::ConstantProductCurve,
+    };
+
+    #[test]
+    fn pack_price_accumulator() {
+        let accumulator = PriceAccumulator {
+            last_update_timestamp: 1_630_000_000,
+            token_a_price_cumulative: u128::MAX - 1,
+            token_b_price_cumulative: 12_345_678_901_234_567_890,
+        };
+        let mut packed = [0u8; PriceAccumulator::LEN];
+        Pack::pack_into_slice(&accumulator, &mut packed[..]);
+        let unpacked = PriceAccumulator::unpack_from_slice(&packed).unwrap();
+        assert_eq!(accumulator, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&accumulator.last_update_timestamp.to_le_bytes());
+        packed.extend_from_slice(&accumulator.token_a_price_cumulative.to_le_bytes());
+        packed.extend_from_slice(&accumulator.token_b_price_cumulative.to_le_bytes());
+        let unpacked = PriceAccumulator::unpack_from_slice(&packed).unwrap();
+        assert_eq!(accumulator, unpacked);
+    }
+
+    #[test]
+    fn accumulate_prices() {
+        let curve = ConstantProductCurve {};
+        let mut accumulator = PriceAccumulator::new(100);
+
+        // price of A is 4 B for 10 seconds
+        accumulator.update(&curve, 1_000, 4_000, 110);
+        // no time elapsed, nothing changes
+        let same_time = accumulator.clone();
+        accumulator.update(&curve, 4_000, 1_000, 110);
+        assert_eq!(accumulator, same_time);
+        // price of A is 1 B for 30 seconds
+        accumulator.update(&curve, 2_000, 2_000, 140);
+        assert_eq!(accumulator.last_update_timestamp, 140);
+
+        // 4 * 10 + 1 * 30 = 70
+        assert_eq!(
+            accumulator.token_a_price_cumulative,
+            precise_value(PreciseNumber::new(70).unwrap())
+        );
+        // 0.25 * 10 + 1 * 30 = 32.5
+        assert_eq!(
+            accumulator.token_b_price_cumulative,
+            precise_value(
+                PreciseNumber::new(65)
+                    .unwrap()
+                    .checked_div(&PreciseNumber::new(2).unwrap())
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn accumulate_prices_with_overflow() {
+        let curve = ConstantPriceCurve { token_b_price: 2 };
+        let mut accumulator = PriceAccumulator::new(0);
+        accumulator.token_a_price_cumulative = u128::MAX - 1_000;
+        accumulator.update(&curve, 1_000, 1_000, 1_000);
+        // 0.5 * 1,000 = 500, wrapped around
+        let accumulated = precise_value(PreciseNumber::new(500).unwrap());
+        assert_eq!(accumulator.token_a_price_cumulative, accumulated - 1_001);
+    }
+
+    #[test]
+    fn skip_empty_pool() {
+        let curve = ConstantProductCurve {};
+        let mut accumulator = PriceAccumulator::new(100);
+        accumulator.update(&curve, 0, 4_000, 110);
+        assert_eq!(accumulator, PriceAccumulator::new(100));
+
+        // the time the pool was empty is accumulated with the next price
+        accumulator.update(&curve, 1_000, 4_000, 120);
+        assert_eq!(accumulator.last_update_timestamp, 120);
+        assert_eq!(
+            accumulator.token_a_price_cumulative,
+            precise_value(PreciseNumber::new(80).unwrap())
+        );
+    }
+
+    fn precise_value(number: PreciseNumber) -> u128 {
+        u128::try_from(number.value).unwrap()
+    }
+}
@@ -3,20 +3,29 @@
 use crate::constraints::{SwapConstraints, SWAP_CONSTRAINTS};
 use crate::{
     curve::{
-        base::SwapCurve,
-        calculator::{RoundDirection, TradeDirection},
-        fees::Fees,
+        base::{CurveType, SwapCurve},
+        calculator::{RoundDirection, TradeDirection, INITIAL_SWAP_POOL_AMOUNT},
+        fees::{DynamicFees, Fees, VolatilityAccumulator},
+        stable::{AmpRamp, StableCurve},
+        weighted,
     },
     error::SwapError,
     instruction::{
-        DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, Swap,
-        SwapInstruction, WithdrawAllTokenTypes, WithdrawSingleTokenTypeExactAmountOut,
+        DepositAllTokenTypes, DepositSingleTokenTypeExactAmountIn, Initialize, InitializeWeighted,
+        RampAmp, RoutedSwap, SetFees, Swap, SwapInstruction, WeightedDepositAllTokenTypes,
+        WeightedDepositSingleTokenTypeExactAmountIn, WeightedSwap, WeightedWithdrawAllTokenTypes,
+        WeightedWithdrawSingleTokenTypeExactAmountOut, WithdrawAllTokenTypes,
+        WithdrawSingleTokenTypeExactAmountOut,
+    },
+    oracle::PriceAccumulator,
+    state::{
+        SwapState, SwapV1, SwapV2, SwapV3, SwapV4, SwapVersion, WeightedSwapV1, WeightedToken,
     },
-    state::{SwapState, SwapV1, SwapVersion},
 };
 use num_traits::FromPrimitive;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::UnixTimestamp,
     decode_error::DecodeError,
     entrypoint::ProgramResult,
     msg,
@@ -25,9 +34,13 @@ use solana_program::{
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
+    sysvar::{clock::Clock, Sysvar},
 };
 use std::convert::TryInto;
 
+/// Number of accounts provided for each pool in a routed swap
+const ROUTE_HOP_ACCOUNTS: usize = 7;
+
 /// Program state handler.
 pub struct Processor {}
 impl Processor {
@@ -167,6 +180,11 @@ impl Processor {
         if swap_account_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
+        // weighted swaps are deposited into and withdrawn from with their own
+        // instructions, taking every token
+        if token_swap.num_tokens() != 2 {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
         if *authority_info.key
             != Self::authority_id(program_id, swap_account_info.key, token_swap.nonce())?
         {
@@ -202,12 +220,107 @@ impl Processor {
         Ok(())
     }
 
+    /// Accumulates the spot prices of the swap since the last update, from
+    /// the token balances it recorded, then records the new balances of the
+    /// current instruction.  Swaps without price accumulators are left
+    /// untouched.
+    fn update_price_accumulator(
+        swap_info: &AccountInfo,
+        token_swap: &dyn SwapState,
+        swap_curve: &SwapCurve,
+        new_token_a_balance: u64,
+        new_token_b_balance: u64,
+    ) -> ProgramResult {
+        if let (Some(price_accumulator), Some((token_a_balance, token_b_balance))) =
+            (token_swap.price_accumulator(), token_swap.token_balances())
+        {
+            let mut price_accumulator = price_accumulator.clone();
+            price_accumulator.update(
+                swap_curve.calculator.as_ref(),
+                to_u128(token_a_balance)?,
+                to_u128(token_b_balance)?,
+                Clock::get()?.unix_timestamp,
+            );
+            SwapVersion::pack_price_accumulator(
+                &price_accumulator,
+                new_token_a_balance,
+                new_token_b_balance,
+                &mut swap_info.data.borrow_mut(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Accumulates the spot prices of every pair of a weighted swap since the
+    /// last update, from the token balances it recorded, then records the new
+    /// balances of the tokens changed by the current instruction, by index
+    fn update_weighted_price_accumulators(
+        swap_info: &AccountInfo,
+        new_balances: &[(usize, u64)],
+    ) -> ProgramResult {
+        let mut token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        token_swap.update_price_accumulators(new_balances, Clock::get()?.unix_timestamp);
+        SwapVersion::pack(
+            SwapVersion::WeightedSwapV1(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )
+    }
+
+    /// Returns the index of the swap's token held in the given liquidity
+    /// account
+    fn token_index(token_swap: &dyn SwapState, account: &Pubkey) -> Result<usize, ProgramError> {
+        (0..token_swap.num_tokens())
+            .find(|index| token_swap.token_account(*index) == Some(account))
+            .ok_or_else(|| SwapError::IncorrectSwapAccount.into())
+    }
+
+    /// Returns the curve of the swap at the current time if it differs from
+    /// the stored one, which only happens once the amplification coefficient
+    /// of a stable curve has been ramped
+    fn ramped_swap_curve(token_swap: &dyn SwapState) -> Result<Option<SwapCurve>, ProgramError> {
+        match token_swap.amp_ramp() {
+            Some(amp_ramp) if token_swap.swap_curve().curve_type == CurveType::Stable => {
+                Ok(Some(SwapCurve {
+                    curve_type: CurveType::Stable,
+                    calculator: Box::new(StableCurve {
+                        amp: amp_ramp.amp(Clock::get()?.unix_timestamp),
+                    }),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Calculates the fees for a trade through the swap, with the trade fee
+    /// scaled by the recent volatility if the swap has a dynamic fee, along
+    /// with its volatility accumulator decayed up to the current slot
+    fn trade_fees(
+        token_swap: &dyn SwapState,
+    ) -> Result<(Fees, Option<VolatilityAccumulator>), ProgramError> {
+        match (
+            token_swap.dynamic_fees(),
+            token_swap.volatility_accumulator(),
+        ) {
+            (Some(dynamic_fees), Some(volatility_accumulator)) => {
+                let mut volatility_accumulator = volatility_accumulator.clone();
+                volatility_accumulator.decay(dynamic_fees, Clock::get()?.slot);
+                let fees = dynamic_fees
+                    .fees(token_swap.fees(), volatility_accumulator.volatility)
+                    .ok_or(SwapError::FeeCalculationFailure)?;
+                Ok((fees, Some(volatility_accumulator)))
+            }
+            _ => Ok((token_swap.fees().clone(), None)),
+        }
+    }
+
     /// Processes an [Initialize](enum.Instruction.html).
+    #[allow(clippy::too_many_arguments)]
     pub fn process_initialize(
         program_id: &Pubkey,
         nonce: u8,
         fees: Fees,
         swap_curve: SwapCurve,
+        dynamic_fees: DynamicFees,
         accounts: &[AccountInfo],
         swap_constraints: &Option<SwapConstraints>,
     ) -> ProgramResult {
@@ -220,6 +333,7 @@ impl Processor {
         let fee_account_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
+        let admin_authority_info = next_account_info(account_info_iter).ok();
 
         let token_program_id = *token_program_info.key;
         if SwapVersion::is_initialized(&swap_info.data.borrow()) {
@@ -288,10 +402,25 @@ impl Processor {
                 return Err(SwapError::InvalidOwner.into());
             }
             swap_constraints.validate_curve(&swap_curve)?;
-            swap_constraints.validate_fees(&fees)?;
+            swap_constraints.validate_fees(&fees, &dynamic_fees)?;
+            if let Some(admin_authority_info) = admin_authority_info {
+                swap_constraints.validate_admin_authority(admin_authority_info.key)?;
+            }
         }
         fees.validate()?;
+        dynamic_fees.validate(&fees)?;
         swap_curve.calculator.validate()?;
+        // swap accounts sized for older versions cannot hold the volatility
+        // accumulator needed by a dynamic fee, or the admin authority
+        if dynamic_fees.is_enabled()
+            && swap_info.data_len() != SwapVersion::LATEST_LEN
+            && swap_info.data_len() != SwapVersion::V3_LEN
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if admin_authority_info.is_some() && swap_info.data_len() != SwapVersion::LATEST_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
 
         let initial_amount = swap_curve.calculator.new_pool_supply();
 
@@ -305,62 +434,123 @@ impl Processor {
             to_u64(initial_amount)?,
         )?;
 
-        let obj = SwapVersion::SwapV1(SwapV1 {
-            is_initialized: true,
-            nonce,
-            token_program_id,
-            token_a: *token_a_info.key,
-            token_b: *token_b_info.key,
-            pool_mint: *pool_mint_info.key,
-            token_a_mint: token_a.mint,
-            token_b_mint: token_b.mint,
-            pool_fee_account: *fee_account_info.key,
-            fees,
-            swap_curve,
-        });
+        // swap accounts sized for older versions are still initialized with
+        // the latest version they can hold
+        let obj = if swap_info.data_len() == SwapVersion::LATEST_LEN {
+            let clock = Clock::get()?;
+            SwapVersion::SwapV4(SwapV4 {
+                is_initialized: true,
+                nonce,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+                price_accumulator: PriceAccumulator::new(clock.unix_timestamp),
+                token_a_balance: token_a.amount,
+                token_b_balance: token_b.amount,
+                dynamic_fees,
+                volatility_accumulator: VolatilityAccumulator::new(clock.slot),
+                admin_authority: admin_authority_info
+                    .map(|admin_authority_info| *admin_authority_info.key)
+                    .unwrap_or_default(),
+                is_paused: false,
+                amp_ramp: AmpRamp::default(),
+            })
+        } else if swap_info.data_len() == SwapVersion::V3_LEN {
+            let clock = Clock::get()?;
+            SwapVersion::SwapV3(SwapV3 {
+                is_initialized: true,
+                nonce,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+                price_accumulator: PriceAccumulator::new(clock.unix_timestamp),
+                token_a_balance: token_a.amount,
+                token_b_balance: token_b.amount,
+                dynamic_fees,
+                volatility_accumulator: VolatilityAccumulator::new(clock.slot),
+            })
+        } else if swap_info.data_len() == SwapVersion::V2_LEN {
+            SwapVersion::SwapV2(SwapV2 {
+                is_initialized: true,
+                nonce,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+                price_accumulator: PriceAccumulator::new(Clock::get()?.unix_timestamp),
+                token_a_balance: token_a.amount,
+                token_b_balance: token_b.amount,
+            })
+        } else {
+            SwapVersion::SwapV1(SwapV1 {
+                is_initialized: true,
+                nonce,
+                token_program_id,
+                token_a: *token_a_info.key,
+                token_b: *token_b_info.key,
+                pool_mint: *pool_mint_info.key,
+                token_a_mint: token_a.mint,
+                token_b_mint: token_b.mint,
+                pool_fee_account: *fee_account_info.key,
+                fees,
+                swap_curve,
+            })
+        };
         SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
 
-    /// Processes an [Swap](enum.Instruction.html).
-    pub fn process_swap(
+    /// Swaps tokens through a single pool, returning the amount of
+    /// destination tokens transferred out of the pool
+    #[allow(clippy::too_many_arguments)]
+    fn swap_through_pool<'a>(
         program_id: &Pubkey,
         amount_in: u64,
         minimum_amount_out: u64,
-        accounts: &[AccountInfo],
-    ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let swap_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let user_transfer_authority_info = next_account_info(account_info_iter)?;
-        let source_info = next_account_info(account_info_iter)?;
-        let swap_source_info = next_account_info(account_info_iter)?;
-        let swap_destination_info = next_account_info(account_info_iter)?;
-        let destination_info = next_account_info(account_info_iter)?;
-        let pool_mint_info = next_account_info(account_info_iter)?;
-        let pool_fee_account_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-
+        swap_info: &AccountInfo<'a>,
+        authority_info: &AccountInfo<'a>,
+        user_transfer_authority_info: &AccountInfo<'a>,
+        source_info: &AccountInfo<'a>,
+        swap_source_info: &AccountInfo<'a>,
+        swap_destination_info: &AccountInfo<'a>,
+        destination_info: &AccountInfo<'a>,
+        pool_mint_info: &AccountInfo<'a>,
+        pool_fee_account_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        host_fee_account_info: Option<&AccountInfo<'a>>,
+    ) -> Result<u64, ProgramError> {
         if swap_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
-        let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        let token_swap = SwapVersion::unpack_version(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
 
         if *authority_info.key != Self::authority_id(program_id, swap_info.key, token_swap.nonce())?
         {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        if !(*swap_source_info.key == *token_swap.token_a_account()
-            || *swap_source_info.key == *token_swap.token_b_account())
-        {
-            return Err(SwapError::IncorrectSwapAccount.into());
-        }
-        if !(*swap_destination_info.key == *token_swap.token_a_account()
-            || *swap_destination_info.key == *token_swap.token_b_account())
-        {
-            return Err(SwapError::IncorrectSwapAccount.into());
-        }
-        if *swap_source_info.key == *swap_destination_info.key {
+        let source_index = Self::token_index(&token_swap, swap_source_info.key)?;
+        let destination_index = Self::token_index(&token_swap, swap_destination_info.key)?;
+        if source_index == destination_index {
             return Err(SwapError::InvalidInput.into());
         }
         if swap_source_info.key == source_info.key {
@@ -385,19 +575,31 @@ impl Processor {
             Self::unpack_token_account(swap_destination_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
 
-        let trade_direction = if *swap_source_info.key == *token_swap.token_a_account() {
-            TradeDirection::AtoB
-        } else {
-            TradeDirection::BtoA
+        let (fees, volatility_accumulator) = Self::trade_fees(&token_swap)?;
+        // weighted swaps trade between any two of their tokens, on a two-token
+        // curve with the source token as token A
+        let (pair_swap_curve, trade_direction) = match &token_swap {
+            SwapVersion::WeightedSwapV1(weighted_swap) => (
+                Some(weighted_swap.pair_swap_curve(source_index as u8, destination_index as u8)?),
+                TradeDirection::AtoB,
+            ),
+            _ if source_index == 0 => (Self::ramped_swap_curve(&token_swap)?, TradeDirection::AtoB),
+            _ => (Self::ramped_swap_curve(&token_swap)?, TradeDirection::BtoA),
+        };
+        let swap_curve = pair_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let (swap_token_a_amount_before, swap_token_b_amount_before) = match trade_direction {
+            TradeDirection::AtoB => (source_account.amount, dest_account.amount),
+            TradeDirection::BtoA => (dest_account.amount, source_account.amount),
         };
-        let result = token_swap
-            .swap_curve()
+        let result = swap_curve
             .swap(
                 to_u128(amount_in)?,
                 to_u128(source_account.amount)?,
                 to_u128(dest_account.amount)?,
                 trade_direction,
-                token_swap.fees(),
+                &fees,
             )
             .ok_or(SwapError::ZeroTradingTokens)?;
         if result.destination_amount_swapped < to_u128(minimum_amount_out)? {
@@ -415,6 +617,47 @@ impl Processor {
             ),
         };
 
+        match &token_swap {
+            SwapVersion::WeightedSwapV1(_) => {
+                Self::update_weighted_price_accumulators(
+                    swap_info,
+                    &[
+                        (source_index, to_u64(result.new_swap_source_amount)?),
+                        (
+                            destination_index,
+                            to_u64(result.new_swap_destination_amount)?,
+                        ),
+                    ],
+                )?;
+            }
+            _ => {
+                Self::update_price_accumulator(
+                    swap_info,
+                    &token_swap,
+                    swap_curve,
+                    to_u64(swap_token_a_amount)?,
+                    to_u64(swap_token_b_amount)?,
+                )?;
+            }
+        }
+
+        if let Some(mut volatility_accumulator) = volatility_accumulator {
+            let calculator = swap_curve.calculator.as_ref();
+            if let (Some(price_before), Some(price_after)) = (
+                calculator.spot_price(
+                    to_u128(swap_token_a_amount_before)?,
+                    to_u128(swap_token_b_amount_before)?,
+                ),
+                calculator.spot_price(swap_token_a_amount, swap_token_b_amount),
+            ) {
+                volatility_accumulator.record_price_movement(&price_before, &price_after);
+            }
+            SwapVersion::pack_volatility_accumulator(
+                &volatility_accumulator,
+                &mut swap_info.data.borrow_mut(),
+            )?;
+        }
+
         Self::token_transfer(
             swap_info.key,
             token_program_info.clone(),
@@ -425,21 +668,32 @@ impl Processor {
             to_u64(result.source_amount_swapped)?,
         )?;
 
-        let mut pool_token_amount = token_swap
-            .swap_curve()
-            .withdraw_single_token_type_exact_out(
+        // the owner fee is converted to pool tokens as a withdrawal of the
+        // source token, which for weighted swaps depends on every token
+        let mut pool_token_amount = match &token_swap {
+            SwapVersion::WeightedSwapV1(weighted_swap) => {
+                Self::weighted_withdraw_single_token_type_exact_out(
+                    weighted_swap,
+                    &fees,
+                    result.owner_fee,
+                    result.new_swap_source_amount,
+                    weighted_swap.token(source_index as u8)?.weight,
+                    to_u128(pool_mint.supply)?,
+                )
+            }
+            _ => swap_curve.withdraw_single_token_type_exact_out(
                 result.owner_fee,
                 swap_token_a_amount,
                 swap_token_b_amount,
                 to_u128(pool_mint.supply)?,
                 trade_direction,
-                token_swap.fees(),
-            )
-            .ok_or(SwapError::FeeCalculationFailure)?;
+                &fees,
+            ),
+        }
+        .ok_or(SwapError::FeeCalculationFailure)?;
 
         if pool_token_amount > 0 {
-            // Allow error to fall through
-            if let Ok(host_fee_account_info) = next_account_info(account_info_iter) {
+            if let Some(host_fee_account_info) = host_fee_account_info {
                 let host_fee_account = Self::unpack_token_account(
                     host_fee_account_info,
                     token_swap.token_program_id(),
@@ -477,6 +731,7 @@ impl Processor {
             )?;
         }
 
+        let amount_out = to_u64(result.destination_amount_swapped)?;
         Self::token_transfer(
             swap_info.key,
             token_program_info.clone(),
@@ -484,9 +739,106 @@ impl Processor {
             destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce(),
-            to_u64(result.destination_amount_swapped)?,
+            amount_out,
+        )?;
+
+        Ok(amount_out)
+    }
+
+    /// Processes an [Swap](enum.Instruction.html).
+    pub fn process_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        // Allow error to fall through
+        let host_fee_account_info = next_account_info(account_info_iter).ok();
+
+        Self::swap_through_pool(
+            program_id,
+            amount_in,
+            minimum_amount_out,
+            swap_info,
+            authority_info,
+            user_transfer_authority_info,
+            source_info,
+            swap_source_info,
+            swap_destination_info,
+            destination_info,
+            pool_mint_info,
+            pool_fee_account_info,
+            token_program_info,
+            host_fee_account_info,
         )?;
+        Ok(())
+    }
+
+    /// Processes a [RoutedSwap](enum.Instruction.html).
+    pub fn process_routed_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
+        let pool_accounts = account_info_iter.as_slice();
+        if pool_accounts.is_empty() || pool_accounts.len() % ROUTE_HOP_ACCOUNTS != 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let hops = pool_accounts.len() / ROUTE_HOP_ACCOUNTS;
+
+        let mut source_info = source_info;
+        let mut amount = amount_in;
+        for (index, hop_accounts) in pool_accounts.chunks(ROUTE_HOP_ACCOUNTS).enumerate() {
+            let hop_iter = &mut hop_accounts.iter();
+            let swap_info = next_account_info(hop_iter)?;
+            let authority_info = next_account_info(hop_iter)?;
+            let swap_source_info = next_account_info(hop_iter)?;
+            let swap_destination_info = next_account_info(hop_iter)?;
+            let pool_mint_info = next_account_info(hop_iter)?;
+            let pool_fee_account_info = next_account_info(hop_iter)?;
+            let destination_info = next_account_info(hop_iter)?;
+
+            // Only the output of the last pool is checked for slippage
+            let hop_minimum_amount_out = if index + 1 == hops {
+                minimum_amount_out
+            } else {
+                0
+            };
+            amount = Self::swap_through_pool(
+                program_id,
+                amount,
+                hop_minimum_amount_out,
+                swap_info,
+                authority_info,
+                user_transfer_authority_info,
+                source_info,
+                swap_source_info,
+                swap_destination_info,
+                destination_info,
+                pool_mint_info,
+                pool_fee_account_info,
+                token_program_info,
+                None,
+            )?;
+            source_info = destination_info;
+        }
         Ok(())
     }
 
@@ -511,7 +863,14 @@ impl Processor {
         let token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
-        let calculator = &token_swap.swap_curve().calculator;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
+        let ramped_swap_curve = Self::ramped_swap_curve(token_swap.as_ref())?;
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let calculator = &swap_curve.calculator;
         if !calculator.allows_deposits() {
             return Err(SwapError::UnsupportedCurveOperation.into());
         }
@@ -565,6 +924,22 @@ impl Processor {
 
         let pool_token_amount = to_u64(pool_token_amount)?;
 
+        let new_token_a_balance = token_a
+            .amount
+            .checked_add(token_a_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let new_token_b_balance = token_b
+            .amount
+            .checked_add(token_b_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_price_accumulator(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            new_token_a_balance,
+            new_token_b_balance,
+        )?;
+
         Self::token_transfer(
             swap_info.key,
             token_program_info.clone(),
@@ -636,7 +1011,11 @@ impl Processor {
         let token_b = Self::unpack_token_account(token_b_info, token_swap.token_program_id())?;
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
 
-        let calculator = &token_swap.swap_curve().calculator;
+        let ramped_swap_curve = Self::ramped_swap_curve(token_swap.as_ref())?;
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
+        let calculator = &swap_curve.calculator;
 
         let withdraw_fee: u128 = if *pool_fee_account_info.key == *source_info.key {
             // withdrawing from the fee account, don't assess withdraw fee
@@ -677,6 +1056,22 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let new_token_a_balance = token_a
+            .amount
+            .checked_sub(token_a_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let new_token_b_balance = token_b
+            .amount
+            .checked_sub(token_b_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_price_accumulator(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            new_token_a_balance,
+            new_token_b_balance,
+        )?;
+
         if withdraw_fee > 0 {
             Self::token_transfer(
                 swap_info.key,
@@ -742,6 +1137,9 @@ impl Processor {
         let token_program_info = next_account_info(account_info_iter)?;
 
         let token_swap = SwapVersion::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused() {
+            return Err(SwapError::SwapPaused.into());
+        }
         let source_account =
             Self::unpack_token_account(source_info, token_swap.token_program_id())?;
         let swap_token_a =
@@ -778,9 +1176,12 @@ impl Processor {
 
         let pool_mint = Self::unpack_mint(pool_mint_info, token_swap.token_program_id())?;
         let pool_mint_supply = to_u128(pool_mint.supply)?;
+        let ramped_swap_curve = Self::ramped_swap_curve(token_swap.as_ref())?;
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
         let pool_token_amount = if pool_mint_supply > 0 {
-            token_swap
-                .swap_curve()
+            swap_curve
                 .deposit_single_token_type(
                     to_u128(source_token_amount)?,
                     to_u128(swap_token_a.amount)?,
@@ -791,7 +1192,7 @@ impl Processor {
                 )
                 .ok_or(SwapError::ZeroTradingTokens)?
         } else {
-            token_swap.swap_curve().calculator.new_pool_supply()
+            swap_curve.calculator.new_pool_supply()
         };
 
         let pool_token_amount = to_u64(pool_token_amount)?;
@@ -802,6 +1203,24 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let (new_swap_token_a_balance, new_swap_token_b_balance) = match trade_direction {
+            TradeDirection::AtoB => (
+                swap_token_a.amount.checked_add(source_token_amount),
+                Some(swap_token_b.amount),
+            ),
+            TradeDirection::BtoA => (
+                Some(swap_token_a.amount),
+                swap_token_b.amount.checked_add(source_token_amount),
+            ),
+        };
+        Self::update_price_accumulator(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            new_swap_token_a_balance.ok_or(SwapError::CalculationFailure)?,
+            new_swap_token_b_balance.ok_or(SwapError::CalculationFailure)?,
+        )?;
+
         match trade_direction {
             TradeDirection::AtoB => {
                 Self::token_transfer(
@@ -896,9 +1315,12 @@ impl Processor {
         let pool_mint_supply = to_u128(pool_mint.supply)?;
         let swap_token_a_amount = to_u128(swap_token_a.amount)?;
         let swap_token_b_amount = to_u128(swap_token_b.amount)?;
+        let ramped_swap_curve = Self::ramped_swap_curve(token_swap.as_ref())?;
+        let swap_curve = ramped_swap_curve
+            .as_ref()
+            .unwrap_or_else(|| token_swap.swap_curve());
 
-        let burn_pool_token_amount = token_swap
-            .swap_curve()
+        let burn_pool_token_amount = swap_curve
             .withdraw_single_token_type_exact_out(
                 to_u128(destination_token_amount)?,
                 swap_token_a_amount,
@@ -929,6 +1351,24 @@ impl Processor {
             return Err(SwapError::ZeroTradingTokens.into());
         }
 
+        let (new_swap_token_a_balance, new_swap_token_b_balance) = match trade_direction {
+            TradeDirection::AtoB => (
+                swap_token_a.amount.checked_sub(destination_token_amount),
+                Some(swap_token_b.amount),
+            ),
+            TradeDirection::BtoA => (
+                Some(swap_token_a.amount),
+                swap_token_b.amount.checked_sub(destination_token_amount),
+            ),
+        };
+        Self::update_price_accumulator(
+            swap_info,
+            token_swap.as_ref(),
+            swap_curve,
+            new_swap_token_a_balance.ok_or(SwapError::CalculationFailure)?,
+            new_swap_token_b_balance.ok_or(SwapError::CalculationFailure)?,
+        )?;
+
         if withdraw_fee > 0 {
             Self::token_transfer(
                 swap_info.key,
@@ -978,1840 +1418,4389 @@ impl Processor {
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
+    fn check_weighted_accounts(
+        token_swap: &WeightedSwapV1,
+        program_id: &Pubkey,
+        swap_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        pool_mint_info: &AccountInfo,
+        token_program_info: &AccountInfo,
+        pool_fee_account_info: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        if swap_account_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if *authority_info.key
+            != Self::authority_id(program_id, swap_account_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectPoolMint.into());
+        }
+        if *token_program_info.key != token_swap.token_program_id {
+            return Err(SwapError::IncorrectTokenProgramId.into());
+        }
+        if let Some(pool_fee_account_info) = pool_fee_account_info {
+            if *pool_fee_account_info.key != token_swap.pool_fee_account {
+                return Err(SwapError::IncorrectFeeAccount.into());
+            }
+        }
+        Ok(())
     }
 
-    /// Processes an instruction given extra constraint
-    pub fn process_with_constraints(
+    /// Get the amount of pool tokens for the deposited amount of one token of
+    /// a weighted swap, charging the trading fee like `SwapCurve` does
+    fn weighted_deposit_single_token_type(
+        token_swap: &WeightedSwapV1,
+        fees: &Fees,
+        source_amount: u128,
+        swap_token_amount: u128,
+        token_weight: u64,
+        pool_supply: u128,
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let half_source_amount = std::cmp::max(1, source_amount.checked_div(2)?);
+        let trade_fee = fees.trading_fee(half_source_amount)?;
+        let source_amount = source_amount.checked_sub(trade_fee)?;
+        weighted::deposit_single_token_type(
+            source_amount,
+            swap_token_amount,
+            token_weight,
+            token_swap.total_weight(),
+            pool_supply,
+            RoundDirection::Floor,
+        )
+    }
+
+    /// Get the amount of pool tokens for the withdrawn amount of one token of
+    /// a weighted swap, charging the trading fee like `SwapCurve` does
+    fn weighted_withdraw_single_token_type_exact_out(
+        token_swap: &WeightedSwapV1,
+        fees: &Fees,
+        source_amount: u128,
+        swap_token_amount: u128,
+        token_weight: u64,
+        pool_supply: u128,
+    ) -> Option<u128> {
+        if source_amount == 0 {
+            return Some(0);
+        }
+        let half_source_amount = std::cmp::max(1, source_amount.checked_div(2)?);
+        let trade_fee = fees.trading_fee(half_source_amount)?;
+        let source_amount = source_amount.checked_sub(trade_fee)?;
+        weighted::withdraw_single_token_type_exact_out(
+            source_amount,
+            swap_token_amount,
+            token_weight,
+            token_swap.total_weight(),
+            pool_supply,
+            RoundDirection::Ceiling,
+        )
+    }
+
+    /// Processes an [InitializeWeighted](enum.Instruction.html).
+    pub fn process_initialize_weighted(
         program_id: &Pubkey,
+        nonce: u8,
+        fees: Fees,
+        weights: Vec<u64>,
+        dynamic_fees: DynamicFees,
         accounts: &[AccountInfo],
-        input: &[u8],
         swap_constraints: &Option<SwapConstraints>,
     ) -> ProgramResult {
-        let instruction = SwapInstruction::unpack(input)?;
-        match instruction {
-            SwapInstruction::Initialize(Initialize {
-                nonce,
-                fees,
-                swap_curve,
-            }) => {
-                msg!("Instruction: Init");
-                Self::process_initialize(
-                    program_id,
-                    nonce,
-                    fees,
-                    swap_curve,
-                    accounts,
-                    swap_constraints,
-                )
-            }
-            SwapInstruction::Swap(Swap {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                msg!("Instruction: Swap");
-                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
-            }
-            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
-                pool_token_amount,
-                maximum_token_a_amount,
-                maximum_token_b_amount,
-            }) => {
-                msg!("Instruction: DepositAllTokenTypes");
-                Self::process_deposit_all_token_types(
-                    program_id,
-                    pool_token_amount,
-                    maximum_token_a_amount,
-                    maximum_token_b_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                msg!("Instruction: WithdrawAllTokenTypes");
-                Self::process_withdraw_all_token_types(
-                    program_id,
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::DepositSingleTokenTypeExactAmountIn(
-                DepositSingleTokenTypeExactAmountIn {
-                    source_token_amount,
-                    minimum_pool_token_amount,
-                },
-            ) => {
-                msg!("Instruction: DepositSingleTokenTypeExactAmountIn");
-                Self::process_deposit_single_token_type_exact_amount_in(
-                    program_id,
-                    source_token_amount,
-                    minimum_pool_token_amount,
-                    accounts,
-                )
-            }
-            SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(
-                WithdrawSingleTokenTypeExactAmountOut {
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                },
-            ) => {
-                msg!("Instruction: WithdrawSingleTokenTypeExactAmountOut");
-                Self::process_withdraw_single_token_type_exact_amount_out(
-                    program_id,
-                    destination_token_amount,
-                    maximum_pool_token_amount,
-                    accounts,
-                )
-            }
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let fee_account_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        if weights.len() < WeightedSwapV1::MIN_TOKENS || weights.len() > WeightedSwapV1::MAX_TOKENS
+        {
+            return Err(SwapError::InvalidInstruction.into());
         }
-    }
-}
+        let token_infos = account_info_iter.take(weights.len()).collect::<Vec<_>>();
+        if token_infos.len() != weights.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let admin_authority_info = next_account_info(account_info_iter).ok();
 
-impl PrintProgramError for SwapError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
-    {
-        match self {
-            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
-            SwapError::InvalidProgramAddress => {
-                msg!("Error: Invalid program address generated from nonce and key")
-            }
-            SwapError::InvalidOwner => {
-                msg!("Error: The input account owner is not the program address")
-            }
-            SwapError::InvalidOutputOwner => {
-                msg!("Error: Output pool account owner cannot be the program address")
-            }
-            SwapError::ExpectedMint => msg!("Error: Deserialized account is not an SPL Token mint"),
-            SwapError::ExpectedAccount => {
-                msg!("Error: Deserialized account is not an SPL Token account")
-            }
-            SwapError::EmptySupply => msg!("Error: Input token account empty"),
-            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
-            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
-            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
-            SwapError::InvalidInput => msg!("Error: InvalidInput"),
-            SwapError::IncorrectSwapAccount => {
-                msg!("Error: Address of the provided swap token account is incorrect")
-            }
-            SwapError::IncorrectPoolMint => {
-                msg!("Error: Address of the provided pool token mint is incorrect")
-            }
-            SwapError::InvalidOutput => msg!("Error: InvalidOutput"),
-            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
-            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
-            SwapError::ExceededSlippage => {
-                msg!("Error: Swap instruction exceeds desired slippage limit")
-            }
-            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
-            SwapError::InvalidFreezeAuthority => {
-                msg!("Error: Pool token mint has a freeze authority")
-            }
-            SwapError::IncorrectFeeAccount => msg!("Error: Pool fee token account incorrect"),
-            SwapError::ZeroTradingTokens => {
-                msg!("Error: Given pool token amount results in zero trading tokens")
-            }
-            SwapError::FeeCalculationFailure => msg!(
-                "Error: The fee calculation failed due to overflow, underflow, or unexpected 0"
-            ),
-            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed."),
-            SwapError::InvalidFee => {
-                msg!("Error: The provided fee does not match the program owner's constraints")
+        let token_program_id = *token_program_info.key;
+        if SwapVersion::is_initialized(&swap_info.data.borrow()) {
+            return Err(SwapError::AlreadyInUse.into());
+        }
+        if *authority_info.key != Self::authority_id(program_id, swap_info.key, nonce)? {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let fee_account = Self::unpack_token_account(fee_account_info, &token_program_id)?;
+        let destination = Self::unpack_token_account(destination_info, &token_program_id)?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_program_id)?;
+        if *authority_info.key == destination.owner {
+            return Err(SwapError::InvalidOutputOwner.into());
+        }
+        if *authority_info.key == fee_account.owner {
+            return Err(SwapError::InvalidOutputOwner.into());
+        }
+        if COption::Some(*authority_info.key) != pool_mint.mint_authority {
+            return Err(SwapError::InvalidOwner.into());
+        }
+
+        let mut tokens: Vec<WeightedToken> = Vec::with_capacity(token_infos.len());
+        for (token_info, weight) in token_infos.iter().zip(weights.iter()) {
+            let token = Self::unpack_token_account(token_info, &token_program_id)?;
+            if *authority_info.key != token.owner {
+                return Err(SwapError::InvalidOwner.into());
             }
-            SwapError::IncorrectTokenProgramId => {
-                msg!("Error: The provided token program does not match the token program expected by the swap")
+            if tokens.iter().any(|other| other.mint == token.mint) {
+                return Err(SwapError::RepeatedMint.into());
             }
-            SwapError::UnsupportedCurveType => {
-                msg!("Error: The provided curve type is not supported by the program owner")
+            if token.amount == 0 {
+                return Err(SwapError::EmptySupply.into());
             }
-            SwapError::InvalidCurve => {
-                msg!("Error: The provided curve parameters are invalid")
+            if token.delegate.is_some() {
+                return Err(SwapError::InvalidDelegate.into());
             }
-            SwapError::UnsupportedCurveOperation => {
-                msg!("Error: The operation cannot be performed on the given curve")
+            if token.close_authority.is_some() {
+                return Err(SwapError::InvalidCloseAuthority.into());
             }
+            tokens.push(WeightedToken {
+                account: *token_info.key,
+                mint: token.mint,
+                weight: *weight,
+                balance: token.amount,
+            });
         }
-    }
-}
-
-fn to_u128(val: u64) -> Result<u128, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
-
-fn to_u64(val: u128) -> Result<u64, SwapError> {
-    val.try_into().map_err(|_| SwapError::ConversionFailure)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        curve::calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT},
-        curve::{
-            base::CurveType, constant_price::ConstantPriceCurve,
-            constant_product::ConstantProductCurve, offset::OffsetCurve,
-        },
-        instruction::{
-            deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize, swap,
-            withdraw_all_token_types, withdraw_single_token_type_exact_amount_out,
-        },
-    };
-    use solana_program::{instruction::Instruction, program_stubs, rent::Rent};
-    use solana_sdk::account::{create_account_for_test, create_is_signer_account_infos, Account};
-    use spl_token::{
-        error::TokenError,
-        instruction::{
-            approve, initialize_account, initialize_mint, mint_to, revoke, set_authority,
-            AuthorityType,
-        },
-    };
-
-    // Test program id for the swap program.
-    const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
-
-    struct TestSyscallStubs {}
-    impl program_stubs::SyscallStubs for TestSyscallStubs {
-        fn sol_invoke_signed(
-            &self,
-            instruction: &Instruction,
-            account_infos: &[AccountInfo],
-            signers_seeds: &[&[&[u8]]],
-        ) -> ProgramResult {
-            msg!("TestSyscallStubs::sol_invoke_signed()");
 
-            let mut new_account_infos = vec![];
+        if pool_mint.supply != 0 {
+            return Err(SwapError::InvalidSupply.into());
+        }
+        if pool_mint.freeze_authority.is_some() {
+            return Err(SwapError::InvalidFreezeAuthority.into());
+        }
+        if *pool_mint_info.key != fee_account.mint {
+            return Err(SwapError::IncorrectPoolMint.into());
+        }
 
-            // mimic check for token program in accounts
-            if !account_infos.iter().any(|x| *x.key == spl_token::id()) {
-                return Err(ProgramError::InvalidAccountData);
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if fee_account.owner != owner_key {
+                return Err(SwapError::InvalidOwner.into());
             }
-
-            for meta in instruction.accounts.iter() {
-                for account_info in account_infos.iter() {
-                    if meta.pubkey == *account_info.key {
-                        let mut new_account_info = account_info.clone();
-                        for seeds in signers_seeds.iter() {
-                            let signer =
-                                Pubkey::create_program_address(seeds, &SWAP_PROGRAM_ID).unwrap();
-                            if *account_info.key == signer {
-                                new_account_info.is_signer = true;
-                            }
-                        }
-                        new_account_infos.push(new_account_info);
-                    }
-                }
+            swap_constraints.validate_curve_type(CurveType::Weighted)?;
+            swap_constraints.validate_fees(&fees, &dynamic_fees)?;
+            if let Some(admin_authority_info) = admin_authority_info {
+                swap_constraints.validate_admin_authority(admin_authority_info.key)?;
             }
-
-            spl_token::processor::Processor::process(
-                &instruction.program_id,
-                &new_account_infos,
-                &instruction.data,
-            )
         }
-    }
+        fees.validate()?;
+        dynamic_fees.validate(&fees)?;
+        weighted::validate_weights(&weights)?;
 
-    fn test_syscall_stubs() {
-        use std::sync::Once;
-        static ONCE: Once = Once::new();
+        Self::token_mint_to(
+            swap_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            nonce,
+            to_u64(INITIAL_SWAP_POOL_AMOUNT)?,
+        )?;
 
-        ONCE.call_once(|| {
-            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+        let clock = Clock::get()?;
+        let obj = SwapVersion::WeightedSwapV1(WeightedSwapV1 {
+            is_initialized: true,
+            nonce,
+            token_program_id,
+            pool_mint: *pool_mint_info.key,
+            pool_fee_account: *fee_account_info.key,
+            fees,
+            dynamic_fees,
+            volatility_accumulator: VolatilityAccumulator::new(clock.slot),
+            admin_authority: admin_authority_info
+                .map(|admin_authority_info| *admin_authority_info.key)
+                .unwrap_or_default(),
+            is_paused: false,
+            price_accumulators: vec![PriceAccumulator::new(clock.unix_timestamp); tokens.len() - 1],
+            swap_curve: WeightedToken::swap_curve(&tokens[0], &tokens[1]),
+            tokens,
         });
+        SwapVersion::pack(obj, &mut swap_info.data.borrow_mut())?;
+        Ok(())
     }
 
-    struct SwapAccountInfo {
-        nonce: u8,
-        authority_key: Pubkey,
-        fees: Fees,
-        swap_curve: SwapCurve,
-        swap_key: Pubkey,
-        swap_account: Account,
-        pool_mint_key: Pubkey,
-        pool_mint_account: Account,
-        pool_fee_key: Pubkey,
-        pool_fee_account: Account,
-        pool_token_key: Pubkey,
-        pool_token_account: Account,
-        token_a_key: Pubkey,
-        token_a_account: Account,
-        token_a_mint_key: Pubkey,
-        token_a_mint_account: Account,
-        token_b_key: Pubkey,
-        token_b_account: Account,
-        token_b_mint_key: Pubkey,
-        token_b_mint_account: Account,
-    }
+    /// Processes a [WeightedSwap](enum.Instruction.html).
+    pub fn process_weighted_swap(
+        program_id: &Pubkey,
+        source_index: u8,
+        destination_index: u8,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        // Allow error to fall through
+        let host_fee_account_info = next_account_info(account_info_iter).ok();
 
-    impl SwapAccountInfo {
-        pub fn new(
-            user_key: &Pubkey,
-            fees: Fees,
-            swap_curve: SwapCurve,
-            token_a_amount: u64,
-            token_b_amount: u64,
-        ) -> Self {
-            let swap_key = Pubkey::new_unique();
-            let swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
-            let (authority_key, nonce) =
-                Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        // the indices only check the accounts, the swap itself is the same as
+        // a `Swap` between the two tokens
+        let token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        if *swap_source_info.key != token_swap.token(source_index)?.account {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *swap_destination_info.key != token_swap.token(destination_index)?.account {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
 
-            let (pool_mint_key, mut pool_mint_account) =
-                create_mint(&spl_token::id(), &authority_key, None);
-            let (pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &pool_mint_key,
-                &mut pool_mint_account,
-                &authority_key,
-                user_key,
-                0,
-            );
-            let (pool_fee_key, pool_fee_account) = mint_token(
-                &spl_token::id(),
-                &pool_mint_key,
-                &mut pool_mint_account,
-                &authority_key,
-                user_key,
-                0,
-            );
-            let (token_a_mint_key, mut token_a_mint_account) =
-                create_mint(&spl_token::id(), user_key, None);
-            let (token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &token_a_mint_key,
-                &mut token_a_mint_account,
-                user_key,
-                &authority_key,
-                token_a_amount,
-            );
-            let (token_b_mint_key, mut token_b_mint_account) =
-                create_mint(&spl_token::id(), user_key, None);
-            let (token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &token_b_mint_key,
-                &mut token_b_mint_account,
-                user_key,
-                &authority_key,
-                token_b_amount,
-            );
+        Self::swap_through_pool(
+            program_id,
+            amount_in,
+            minimum_amount_out,
+            swap_info,
+            authority_info,
+            user_transfer_authority_info,
+            source_info,
+            swap_source_info,
+            swap_destination_info,
+            destination_info,
+            pool_mint_info,
+            pool_fee_account_info,
+            token_program_info,
+            host_fee_account_info,
+        )?;
+        Ok(())
+    }
 
-            SwapAccountInfo {
-                nonce,
-                authority_key,
-                fees,
-                swap_curve,
-                swap_key,
-                swap_account,
-                pool_mint_key,
-                pool_mint_account,
-                pool_fee_key,
-                pool_fee_account,
-                pool_token_key,
-                pool_token_account,
-                token_a_key,
-                token_a_account,
-                token_a_mint_key,
-                token_a_mint_account,
-                token_b_key,
-                token_b_account,
-                token_b_mint_key,
-                token_b_mint_account,
-            }
-        }
+    /// Processes a [WeightedDepositAllTokenTypes](enum.Instruction.html).
+    pub fn process_weighted_deposit_all_token_types(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        maximum_token_amounts: &[u64],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let dest_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
-        pub fn initialize_swap(&mut self) -> ProgramResult {
-            do_process_instruction(
-                initialize(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    &self.pool_token_key,
-                    self.nonce,
-                    self.fees.clone(),
-                    self.swap_curve.clone(),
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut self.pool_fee_account,
-                    &mut self.pool_token_account,
-                    &mut Account::default(),
-                ],
-            )
+        let token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::SwapPaused.into());
         }
-
-        pub fn setup_token_accounts(
-            &mut self,
-            mint_owner: &Pubkey,
-            account_owner: &Pubkey,
-            a_amount: u64,
-            b_amount: u64,
-            pool_amount: u64,
-        ) -> (Pubkey, Account, Pubkey, Account, Pubkey, Account) {
-            let (token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &self.token_a_mint_key,
-                &mut self.token_a_mint_account,
-                mint_owner,
-                account_owner,
-                a_amount,
-            );
-            let (token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &self.token_b_mint_key,
-                &mut self.token_b_mint_account,
-                mint_owner,
-                account_owner,
-                b_amount,
-            );
-            let (pool_key, pool_account) = mint_token(
-                &spl_token::id(),
-                &self.pool_mint_key,
-                &mut self.pool_mint_account,
-                &self.authority_key,
-                account_owner,
-                pool_amount,
-            );
-            (
-                token_a_key,
-                token_a_account,
-                token_b_key,
-                token_b_account,
-                pool_key,
-                pool_account,
-            )
+        Self::check_weighted_accounts(
+            &token_swap,
+            program_id,
+            swap_info,
+            authority_info,
+            pool_mint_info,
+            token_program_info,
+            None,
+        )?;
+        let num_tokens = token_swap.tokens.len();
+        if maximum_token_amounts.len() != num_tokens {
+            return Err(SwapError::InvalidInstruction.into());
         }
-
-        fn get_token_account(&self, account_key: &Pubkey) -> &Account {
-            if *account_key == self.token_a_key {
-                return &self.token_a_account;
-            } else if *account_key == self.token_b_key {
-                return &self.token_b_account;
-            }
-            panic!("Could not find matching swap token account");
+        let token_infos = account_info_iter.as_slice();
+        if token_infos.len() != 2 * num_tokens {
+            return Err(ProgramError::NotEnoughAccountKeys);
         }
+        let (source_infos, token_infos) = token_infos.split_at(num_tokens);
 
-        fn set_token_account(&mut self, account_key: &Pubkey, account: Account) {
-            if *account_key == self.token_a_key {
-                self.token_a_account = account;
-                return;
-            } else if *account_key == self.token_b_key {
-                self.token_b_account = account;
-                return;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_swap.token_program_id)?;
+        let current_pool_mint_supply = to_u128(pool_mint.supply)?;
+        let (pool_token_amount, pool_mint_supply) = if current_pool_mint_supply > 0 {
+            (to_u128(pool_token_amount)?, current_pool_mint_supply)
+        } else {
+            (INITIAL_SWAP_POOL_AMOUNT, INITIAL_SWAP_POOL_AMOUNT)
+        };
+
+        let mut token_amounts = Vec::with_capacity(num_tokens);
+        let mut new_balances = Vec::with_capacity(num_tokens);
+        for (i, token_info) in token_infos.iter().enumerate() {
+            if *token_info.key != token_swap.tokens[i].account {
+                return Err(SwapError::IncorrectSwapAccount.into());
             }
-            panic!("Could not find matching swap token account");
+            if token_info.key == source_infos[i].key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            let token = Self::unpack_token_account(token_info, &token_swap.token_program_id)?;
+            let token_amount = weighted::pool_tokens_to_trading_token(
+                pool_token_amount,
+                pool_mint_supply,
+                to_u128(token.amount)?,
+                RoundDirection::Ceiling,
+            )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+            let token_amount = to_u64(token_amount)?;
+            if token_amount > maximum_token_amounts[i] {
+                return Err(SwapError::ExceededSlippage.into());
+            }
+            if token_amount == 0 {
+                return Err(SwapError::ZeroTradingTokens.into());
+            }
+            let new_balance = token
+                .amount
+                .checked_add(token_amount)
+                .ok_or(SwapError::CalculationFailure)?;
+            token_amounts.push(token_amount);
+            new_balances.push((i, new_balance));
         }
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn swap(
-            &mut self,
-            user_key: &Pubkey,
-            user_source_key: &Pubkey,
-            mut user_source_account: &mut Account,
-            swap_source_key: &Pubkey,
-            swap_destination_key: &Pubkey,
-            user_destination_key: &Pubkey,
-            mut user_destination_account: &mut Account,
-            amount_in: u64,
-            minimum_amount_out: u64,
-        ) -> ProgramResult {
-            let user_transfer_key = Pubkey::new_unique();
-            // approve moving from user source account
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    user_source_key,
-                    &user_transfer_key,
-                    user_key,
-                    &[],
-                    amount_in,
-                )
-                .unwrap(),
-                vec![
-                    &mut user_source_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let pool_token_amount = to_u64(pool_token_amount)?;
 
-            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
-            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+        Self::update_weighted_price_accumulators(swap_info, &new_balances)?;
 
-            // perform the swap
-            do_process_instruction(
-                swap(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_key,
-                    user_source_key,
-                    swap_source_key,
-                    swap_destination_key,
-                    user_destination_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    None,
-                    Swap {
-                        amount_in,
-                        minimum_amount_out,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut user_source_account,
-                    &mut swap_source_account,
-                    &mut swap_destination_account,
-                    &mut user_destination_account,
-                    &mut self.pool_mint_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
+        for ((source_info, token_info), token_amount) in source_infos
+            .iter()
+            .zip(token_infos.iter())
+            .zip(token_amounts)
+        {
+            Self::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                source_info.clone(),
+                token_info.clone(),
+                user_transfer_authority_info.clone(),
+                token_swap.nonce,
+                token_amount,
             )?;
+        }
+        Self::token_mint_to(
+            swap_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            dest_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            pool_token_amount,
+        )?;
 
-            self.set_token_account(swap_source_key, swap_source_account);
-            self.set_token_account(swap_destination_key, swap_destination_account);
+        Ok(())
+    }
 
-            Ok(())
+    /// Processes a [WeightedWithdrawAllTokenTypes](enum.Instruction.html).
+    pub fn process_weighted_withdraw_all_token_types(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_amounts: &[u64],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        Self::check_weighted_accounts(
+            &token_swap,
+            program_id,
+            swap_info,
+            authority_info,
+            pool_mint_info,
+            token_program_info,
+            Some(pool_fee_account_info),
+        )?;
+        let num_tokens = token_swap.tokens.len();
+        if minimum_token_amounts.len() != num_tokens {
+            return Err(SwapError::InvalidInstruction.into());
+        }
+        let token_infos = account_info_iter.as_slice();
+        if token_infos.len() != 2 * num_tokens {
+            return Err(ProgramError::NotEnoughAccountKeys);
         }
+        let (token_infos, dest_token_infos) = token_infos.split_at(num_tokens);
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn deposit_all_token_types(
-            &mut self,
-            depositor_key: &Pubkey,
-            depositor_token_a_key: &Pubkey,
-            mut depositor_token_a_account: &mut Account,
-            depositor_token_b_key: &Pubkey,
-            mut depositor_token_b_account: &mut Account,
-            depositor_pool_key: &Pubkey,
-            mut depositor_pool_account: &mut Account,
-            pool_token_amount: u64,
-            maximum_token_a_amount: u64,
-            maximum_token_b_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority = Pubkey::new_unique();
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    depositor_token_a_key,
-                    &user_transfer_authority,
-                    depositor_key,
-                    &[],
-                    maximum_token_a_amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut depositor_token_a_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_swap.token_program_id)?;
 
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    depositor_token_b_key,
-                    &user_transfer_authority,
-                    depositor_key,
-                    &[],
-                    maximum_token_b_amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut depositor_token_b_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        let withdraw_fee: u128 = if *pool_fee_account_info.key == *source_info.key {
+            // withdrawing from the fee account, don't assess withdraw fee
+            0
+        } else {
+            token_swap
+                .fees
+                .owner_withdraw_fee(to_u128(pool_token_amount)?)
+                .ok_or(SwapError::FeeCalculationFailure)?
+        };
+        let pool_token_amount = to_u128(pool_token_amount)?
+            .checked_sub(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
 
-            do_process_instruction(
-                deposit_all_token_types(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority,
-                    depositor_token_a_key,
-                    depositor_token_b_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    depositor_pool_key,
-                    DepositAllTokenTypes {
-                        pool_token_amount,
-                        maximum_token_a_amount,
-                        maximum_token_b_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut depositor_token_a_account,
-                    &mut depositor_token_b_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut depositor_pool_account,
-                    &mut Account::default(),
-                ],
+        let mut token_amounts = Vec::with_capacity(num_tokens);
+        let mut new_balances = Vec::with_capacity(num_tokens);
+        for (i, token_info) in token_infos.iter().enumerate() {
+            if *token_info.key != token_swap.tokens[i].account {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            if token_info.key == dest_token_infos[i].key {
+                return Err(SwapError::InvalidInput.into());
+            }
+            let token = Self::unpack_token_account(token_info, &token_swap.token_program_id)?;
+            let token_amount = weighted::pool_tokens_to_trading_token(
+                pool_token_amount,
+                to_u128(pool_mint.supply)?,
+                to_u128(token.amount)?,
+                RoundDirection::Floor,
             )
+            .ok_or(SwapError::ZeroTradingTokens)?;
+            let token_amount = std::cmp::min(token.amount, to_u64(token_amount)?);
+            if token_amount < minimum_token_amounts[i] {
+                return Err(SwapError::ExceededSlippage.into());
+            }
+            if token_amount == 0 && token.amount != 0 {
+                return Err(SwapError::ZeroTradingTokens.into());
+            }
+            let new_balance = token
+                .amount
+                .checked_sub(token_amount)
+                .ok_or(SwapError::CalculationFailure)?;
+            token_amounts.push(token_amount);
+            new_balances.push((i, new_balance));
         }
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn withdraw_all_token_types(
-            &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            mut pool_account: &mut Account,
-            token_a_key: &Pubkey,
-            mut token_a_account: &mut Account,
-            token_b_key: &Pubkey,
-            mut token_b_account: &mut Account,
-            pool_token_amount: u64,
-            minimum_token_a_amount: u64,
-            minimum_token_b_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            // approve user transfer authority to take out pool tokens
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    pool_key,
-                    &user_transfer_authority_key,
-                    user_key,
-                    &[],
-                    pool_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut pool_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
+        Self::update_weighted_price_accumulators(swap_info, &new_balances)?;
 
-            // withdraw token a and b correctly
-            do_process_instruction(
-                withdraw_all_token_types(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    token_a_key,
-                    token_b_key,
-                    WithdrawAllTokenTypes {
-                        pool_token_amount,
-                        minimum_token_a_amount,
-                        minimum_token_b_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    &mut pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut token_a_account,
-                    &mut token_b_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
-            )
-        }
-
-        #[allow(clippy::too_many_arguments)]
-        pub fn deposit_single_token_type_exact_amount_in(
-            &mut self,
-            depositor_key: &Pubkey,
-            deposit_account_key: &Pubkey,
-            mut deposit_token_account: &mut Account,
-            deposit_pool_key: &Pubkey,
-            mut deposit_pool_account: &mut Account,
-            source_token_amount: u64,
-            minimum_pool_token_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    deposit_account_key,
-                    &user_transfer_authority_key,
-                    depositor_key,
-                    &[],
-                    source_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut deposit_token_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-
-            do_process_instruction(
-                deposit_single_token_type_exact_amount_in(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    deposit_account_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    deposit_pool_key,
-                    DepositSingleTokenTypeExactAmountIn {
-                        source_token_amount,
-                        minimum_pool_token_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut deposit_token_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut deposit_pool_account,
-                    &mut Account::default(),
-                ],
-            )
+        if withdraw_fee > 0 {
+            Self::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                source_info.clone(),
+                pool_fee_account_info.clone(),
+                user_transfer_authority_info.clone(),
+                token_swap.nonce,
+                to_u64(withdraw_fee)?,
+            )?;
         }
+        Self::token_burn(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_swap.nonce,
+            to_u64(pool_token_amount)?,
+        )?;
 
-        #[allow(clippy::too_many_arguments)]
-        pub fn withdraw_single_token_type_exact_amount_out(
-            &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            mut pool_account: &mut Account,
-            destination_key: &Pubkey,
-            mut destination_account: &mut Account,
-            destination_token_amount: u64,
-            maximum_pool_token_amount: u64,
-        ) -> ProgramResult {
-            let user_transfer_authority_key = Pubkey::new_unique();
-            // approve user transfer authority to take out pool tokens
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    pool_key,
-                    &user_transfer_authority_key,
-                    user_key,
-                    &[],
-                    maximum_pool_token_amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut pool_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-
-            do_process_instruction(
-                withdraw_single_token_type_exact_amount_out(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_transfer_authority_key,
-                    &self.pool_mint_key,
-                    &self.pool_fee_key,
-                    pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    destination_key,
-                    WithdrawSingleTokenTypeExactAmountOut {
-                        destination_token_amount,
-                        maximum_pool_token_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    &mut pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut destination_account,
-                    &mut self.pool_fee_account,
-                    &mut Account::default(),
-                ],
-            )
+        for ((token_info, dest_token_info), token_amount) in token_infos
+            .iter()
+            .zip(dest_token_infos.iter())
+            .zip(token_amounts)
+        {
+            if token_amount > 0 {
+                Self::token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    token_info.clone(),
+                    dest_token_info.clone(),
+                    authority_info.clone(),
+                    token_swap.nonce,
+                    token_amount,
+                )?;
+            }
         }
+        Ok(())
     }
 
-    fn mint_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(spl_token::state::Mint::get_packed_len())
-    }
-
-    fn account_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(spl_token::state::Account::get_packed_len())
-    }
-
-    fn do_process_instruction_with_fee_constraints(
-        instruction: Instruction,
-        accounts: Vec<&mut Account>,
-        swap_constraints: &Option<SwapConstraints>,
+    /// Processes WeightedDepositSingleTokenTypeExactAmountIn
+    pub fn process_weighted_deposit_single_token_type_exact_amount_in(
+        program_id: &Pubkey,
+        token_index: u8,
+        source_token_amount: u64,
+        minimum_pool_token_amount: u64,
+        accounts: &[AccountInfo],
     ) -> ProgramResult {
-        test_syscall_stubs();
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_token_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
-        // approximate the logic in the actual runtime which runs the instruction
-        // and only updates accounts if the instruction is successful
-        let mut account_clones = accounts.iter().map(|x| (*x).clone()).collect::<Vec<_>>();
-        let mut meta = instruction
-            .accounts
-            .iter()
-            .zip(account_clones.iter_mut())
-            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
-            .collect::<Vec<_>>();
-        let mut account_infos = create_is_signer_account_infos(&mut meta);
-        let res = if instruction.program_id == SWAP_PROGRAM_ID {
-            Processor::process_with_constraints(
-                &instruction.program_id,
-                &account_infos,
-                &instruction.data,
-                swap_constraints,
+        let token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::SwapPaused.into());
+        }
+        Self::check_weighted_accounts(
+            &token_swap,
+            program_id,
+            swap_info,
+            authority_info,
+            pool_mint_info,
+            token_program_info,
+            None,
+        )?;
+        let token = token_swap.token(token_index)?;
+        if *swap_token_info.key != token.account {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if swap_token_info.key == source_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let swap_token = Self::unpack_token_account(swap_token_info, &token_swap.token_program_id)?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_swap.token_program_id)?;
+        let pool_mint_supply = to_u128(pool_mint.supply)?;
+        let pool_token_amount = if pool_mint_supply > 0 {
+            Self::weighted_deposit_single_token_type(
+                &token_swap,
+                &token_swap.fees,
+                to_u128(source_token_amount)?,
+                to_u128(swap_token.amount)?,
+                token.weight,
+                pool_mint_supply,
             )
+            .ok_or(SwapError::ZeroTradingTokens)?
         } else {
-            spl_token::processor::Processor::process(
-                &instruction.program_id,
-                &account_infos,
-                &instruction.data,
-            )
+            INITIAL_SWAP_POOL_AMOUNT
         };
 
-        if res.is_ok() {
-            let mut account_metas = instruction
-                .accounts
-                .iter()
-                .zip(accounts)
-                .map(|(account_meta, account)| (&account_meta.pubkey, account))
-                .collect::<Vec<_>>();
-            for account_info in account_infos.iter_mut() {
-                for account_meta in account_metas.iter_mut() {
-                    if account_info.key == account_meta.0 {
-                        let account = &mut account_meta.1;
-                        account.owner = *account_info.owner;
-                        account.lamports = **account_info.lamports.borrow();
-                        account.data = account_info.data.borrow().to_vec();
-                    }
-                }
-            }
+        let pool_token_amount = to_u64(pool_token_amount)?;
+        if pool_token_amount < minimum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
         }
-        res
-    }
 
-    fn do_process_instruction(
-        instruction: Instruction,
-        accounts: Vec<&mut Account>,
-    ) -> ProgramResult {
-        do_process_instruction_with_fee_constraints(instruction, accounts, &SWAP_CONSTRAINTS)
-    }
+        let new_balance = swap_token
+            .amount
+            .checked_add(source_token_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_weighted_price_accumulators(
+            swap_info,
+            &[(token_index as usize, new_balance)],
+        )?;
 
-    fn mint_token(
-        program_id: &Pubkey,
-        mint_key: &Pubkey,
-        mut mint_account: &mut Account,
-        mint_authority_key: &Pubkey,
-        account_owner_key: &Pubkey,
-        amount: u64,
-    ) -> (Pubkey, Account) {
-        let account_key = Pubkey::new_unique();
-        let mut account_account = Account::new(
-            account_minimum_balance(),
-            spl_token::state::Account::get_packed_len(),
-            program_id,
-        );
-        let mut mint_authority_account = Account::default();
-        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
-
-        do_process_instruction(
-            initialize_account(program_id, &account_key, mint_key, account_owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut mint_authority_account,
-                &mut rent_sysvar_account,
-            ],
-        )
-        .unwrap();
-
-        if amount > 0 {
-            do_process_instruction(
-                mint_to(
-                    program_id,
-                    mint_key,
-                    &account_key,
-                    mint_authority_key,
-                    &[],
-                    amount,
-                )
-                .unwrap(),
-                vec![
-                    &mut mint_account,
-                    &mut account_account,
-                    &mut mint_authority_account,
-                ],
-            )
-            .unwrap();
-        }
+        Self::token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            swap_token_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_swap.nonce,
+            source_token_amount,
+        )?;
+        Self::token_mint_to(
+            swap_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            pool_token_amount,
+        )?;
 
-        (account_key, account_account)
+        Ok(())
     }
 
-    fn create_mint(
+    /// Processes a [WeightedWithdrawSingleTokenTypeExactAmountOut](enum.Instruction.html).
+    pub fn process_weighted_withdraw_single_token_type_exact_amount_out(
         program_id: &Pubkey,
-        authority_key: &Pubkey,
-        freeze_authority: Option<&Pubkey>,
-    ) -> (Pubkey, Account) {
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account = Account::new(
-            mint_minimum_balance(),
-            spl_token::state::Mint::get_packed_len(),
-            program_id,
-        );
-        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
-
-        do_process_instruction(
-            initialize_mint(program_id, &mint_key, authority_key, freeze_authority, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar_account],
-        )
-        .unwrap();
+        token_index: u8,
+        destination_token_amount: u64,
+        maximum_pool_token_amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let user_transfer_authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_token_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let pool_fee_account_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
 
-        (mint_key, mint_account)
-    }
+        let token_swap = SwapVersion::unpack_weighted(&swap_info.data.borrow())?;
+        Self::check_weighted_accounts(
+            &token_swap,
+            program_id,
+            swap_info,
+            authority_info,
+            pool_mint_info,
+            token_program_info,
+            Some(pool_fee_account_info),
+        )?;
+        let token = token_swap.token(token_index)?;
+        if *swap_token_info.key != token.account {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if swap_token_info.key == destination_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
 
-    #[test]
-    fn test_token_program_id_error() {
-        test_syscall_stubs();
-        let swap_key = Pubkey::new_unique();
-        let mut mint = (Pubkey::new_unique(), Account::default());
-        let mut destination = (Pubkey::new_unique(), Account::default());
-        let token_program = (spl_token::id(), Account::default());
-        let (authority_key, nonce) =
-            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
-        let mut authority = (authority_key, Account::default());
-        let swap_bytes = swap_key.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
-        let signers = &[&authority_signature_seeds[..]];
-        let ix = mint_to(
-            &token_program.0,
-            &mint.0,
-            &destination.0,
-            &authority.0,
-            &[],
-            10,
+        let swap_token = Self::unpack_token_account(swap_token_info, &token_swap.token_program_id)?;
+        let pool_mint = Self::unpack_mint(pool_mint_info, &token_swap.token_program_id)?;
+        let burn_pool_token_amount = Self::weighted_withdraw_single_token_type_exact_out(
+            &token_swap,
+            &token_swap.fees,
+            to_u128(destination_token_amount)?,
+            to_u128(swap_token.amount)?,
+            token.weight,
+            to_u128(pool_mint.supply)?,
         )
-        .unwrap();
-        let mint = (&mut mint).into();
-        let destination = (&mut destination).into();
-        let authority = (&mut authority).into();
-
-        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
-        assert_eq!(err, ProgramError::InvalidAccountData);
-    }
+        .ok_or(SwapError::ZeroTradingTokens)?;
 
-    #[test]
-    fn test_initialize() {
-        let user_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 20;
-        let host_fee_denominator = 100;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+        let withdraw_fee: u128 = if *pool_fee_account_info.key == *source_info.key {
+            // withdrawing from the fee account, don't assess withdraw fee
+            0
+        } else {
+            token_swap
+                .fees
+                .owner_withdraw_fee(burn_pool_token_amount)
+                .ok_or(SwapError::FeeCalculationFailure)?
         };
+        let pool_token_amount = burn_pool_token_amount
+            .checked_add(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
 
-        let token_a_amount = 1000;
-        let token_b_amount = 2000;
-        let pool_token_amount = 10;
-        let curve_type = CurveType::ConstantProduct;
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Box::new(ConstantProductCurve {}),
-        };
+        if to_u64(pool_token_amount)? > maximum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        if pool_token_amount == 0 {
+            return Err(SwapError::ZeroTradingTokens.into());
+        }
 
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        let new_balance = swap_token
+            .amount
+            .checked_sub(destination_token_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::update_weighted_price_accumulators(
+            swap_info,
+            &[(token_index as usize, new_balance)],
+        )?;
 
-        // wrong nonce for authority_key
-        {
-            let old_nonce = accounts.nonce;
-            accounts.nonce = old_nonce - 1;
-            assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.initialize_swap()
-            );
-            accounts.nonce = old_nonce;
+        if withdraw_fee > 0 {
+            Self::token_transfer(
+                swap_info.key,
+                token_program_info.clone(),
+                source_info.clone(),
+                pool_fee_account_info.clone(),
+                user_transfer_authority_info.clone(),
+                token_swap.nonce,
+                to_u64(withdraw_fee)?,
+            )?;
         }
+        Self::token_burn(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            user_transfer_authority_info.clone(),
+            token_swap.nonce,
+            to_u64(burn_pool_token_amount)?,
+        )?;
+        Self::token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            swap_token_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            destination_token_amount,
+        )?;
 
-        // uninitialized token a account
-        {
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
-        }
+        Ok(())
+    }
 
-        // uninitialized token b account
-        {
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+    /// Unpacks a swap for an admin instruction, checking that it belongs to
+    /// the program and that its admin authority signed
+    fn unpack_admin_swap(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo,
+        admin_authority_info: &AccountInfo,
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> Result<SwapVersion, ProgramError> {
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
-
-        // uninitialized pool mint
+        let token_swap = SwapVersion::unpack_with_admin(&swap_info.data.borrow())?;
+        if !token_swap.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if token_swap.admin_authority() != Some(admin_authority_info.key)
+            || !admin_authority_info.is_signer
         {
-            let old_account = accounts.pool_mint_account;
-            accounts.pool_mint_account = Account::new(0, 0, &spl_token::id());
-            assert_eq!(
-                Err(SwapError::ExpectedMint.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_account;
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if let Some(swap_constraints) = swap_constraints {
+            swap_constraints.validate_admin_authority(admin_authority_info.key)?;
         }
+        Ok(token_swap)
+    }
 
-        // token A account owner is not swap authority
-        {
-            let (_token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &user_key,
-                0,
-            );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
-        }
+    /// Processes a [SetFees](enum.Instruction.html).
+    pub fn process_set_fees(
+        program_id: &Pubkey,
+        fees: Fees,
+        dynamic_fees: DynamicFees,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_authority_info = next_account_info(account_info_iter)?;
 
-        // token B account owner is not swap authority
-        {
-            let (_token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &user_key,
-                0,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+        let mut token_swap = Self::unpack_admin_swap(
+            program_id,
+            swap_info,
+            admin_authority_info,
+            swap_constraints,
+        )?;
+        if let Some(swap_constraints) = swap_constraints {
+            swap_constraints.validate_fees(&fees, &dynamic_fees)?;
         }
+        fees.validate()?;
+        dynamic_fees.validate(&fees)?;
 
-        // pool token account owner is swap authority
-        {
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.pool_token_account;
-            accounts.pool_token_account = pool_token_account;
-            assert_eq!(
-                Err(SwapError::InvalidOutputOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_token_account = old_account;
+        // the volatility accumulated so far still applies to the new fee
+        match &mut token_swap {
+            SwapVersion::SwapV4(token_swap) => {
+                token_swap.fees = fees;
+                token_swap.dynamic_fees = dynamic_fees;
+            }
+            SwapVersion::WeightedSwapV1(token_swap) => {
+                token_swap.fees = fees;
+                token_swap.dynamic_fees = dynamic_fees;
+            }
+            _ => return Err(SwapError::InvalidAdmin.into()),
         }
+        SwapVersion::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-        // pool fee account owner is swap authority
-        {
-            let (_pool_fee_key, pool_fee_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.pool_fee_account;
-            accounts.pool_fee_account = pool_fee_account;
-            assert_eq!(
-                Err(SwapError::InvalidOutputOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_fee_account = old_account;
-        }
+    /// Processes a [SetPoolFeeAccount](enum.Instruction.html).
+    pub fn process_set_pool_fee_account(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_authority_info = next_account_info(account_info_iter)?;
+        let fee_account_info = next_account_info(account_info_iter)?;
 
-        // pool mint authority is not swap authority
-        {
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &user_key, None);
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
-            assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_mint;
+        let mut token_swap = Self::unpack_admin_swap(
+            program_id,
+            swap_info,
+            admin_authority_info,
+            swap_constraints,
+        )?;
+        let fee_account =
+            Self::unpack_token_account(fee_account_info, token_swap.token_program_id())?;
+        if fee_account.mint != *token_swap.pool_mint() {
+            return Err(SwapError::IncorrectPoolMint.into());
         }
-
-        // pool mint token has freeze authority
-        {
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, Some(&user_key));
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
-            assert_eq!(
-                Err(SwapError::InvalidFreezeAuthority.into()),
-                accounts.initialize_swap()
-            );
-            accounts.pool_mint_account = old_mint;
+        if fee_account.owner == Self::authority_id(program_id, swap_info.key, token_swap.nonce())? {
+            return Err(SwapError::InvalidOutputOwner.into());
         }
-
-        // token A account owned by wrong program
-        {
-            let (_token_a_key, mut token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                token_a_amount,
-            );
-            token_a_account.owner = SWAP_PROGRAM_ID;
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+        if let Some(swap_constraints) = swap_constraints {
+            let owner_key = swap_constraints
+                .owner_key
+                .parse::<Pubkey>()
+                .map_err(|_| SwapError::InvalidOwner)?;
+            if fee_account.owner != owner_key {
+                return Err(SwapError::InvalidOwner.into());
+            }
         }
 
-        // token B account owned by wrong program
-        {
-            let (_token_b_key, mut token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                token_b_amount,
-            );
-            token_b_account.owner = SWAP_PROGRAM_ID;
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+        match &mut token_swap {
+            SwapVersion::SwapV4(token_swap) => {
+                token_swap.pool_fee_account = *fee_account_info.key;
+            }
+            SwapVersion::WeightedSwapV1(token_swap) => {
+                token_swap.pool_fee_account = *fee_account_info.key;
+            }
+            _ => return Err(SwapError::InvalidAdmin.into()),
         }
+        SwapVersion::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-        // empty token A account
-        {
-            let (_token_a_key, token_a_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
-            assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_a_account = old_account;
+    /// Processes a [Pause](enum.Instruction.html) or an
+    /// [Unpause](enum.Instruction.html).
+    pub fn process_set_paused(
+        program_id: &Pubkey,
+        is_paused: bool,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_authority_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = Self::unpack_admin_swap(
+            program_id,
+            swap_info,
+            admin_authority_info,
+            swap_constraints,
+        )?;
+        match &mut token_swap {
+            SwapVersion::SwapV4(token_swap) => token_swap.is_paused = is_paused,
+            SwapVersion::WeightedSwapV1(token_swap) => token_swap.is_paused = is_paused,
+            _ => return Err(SwapError::InvalidAdmin.into()),
         }
+        SwapVersion::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
 
-        // empty token B account
-        {
-            let (_token_b_key, token_b_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
-            assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
-            );
-            accounts.token_b_account = old_account;
+    /// Processes a [RampAmp](enum.Instruction.html).
+    pub fn process_ramp_amp(
+        program_id: &Pubkey,
+        target_amp: u64,
+        stop_ramp_timestamp: UnixTimestamp,
+        accounts: &[AccountInfo],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_authority_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = match Self::unpack_admin_swap(
+            program_id,
+            swap_info,
+            admin_authority_info,
+            swap_constraints,
+        )? {
+            SwapVersion::SwapV4(token_swap) => token_swap,
+            _ => return Err(SwapError::UnsupportedCurveOperation.into()),
+        };
+        if token_swap.swap_curve.curve_type != CurveType::Stable {
+            return Err(SwapError::UnsupportedCurveOperation.into());
+        }
+        if let Some(swap_constraints) = swap_constraints {
+            swap_constraints.validate_curve_type(CurveType::Stable)?;
         }
 
-        // invalid pool tokens
-        {
-            let old_mint = accounts.pool_mint_account;
-            let old_pool_account = accounts.pool_token_account;
+        // the new ramp starts from the current amplification coefficient, so
+        // the curve never jumps
+        let timestamp = Clock::get()?.unix_timestamp;
+        let initial_amp = if token_swap.amp_ramp.is_set() {
+            token_swap.amp_ramp.amp(timestamp)
+        } else {
+            let mut stable_curve = [0u8; StableCurve::LEN];
+            token_swap
+                .swap_curve
+                .calculator
+                .pack_into_slice(&mut stable_curve);
+            StableCurve::unpack_from_slice(&stable_curve)?.amp
+        };
+        let amp_ramp = AmpRamp {
+            initial_amp,
+            target_amp,
+            start_timestamp: timestamp,
+            stop_timestamp: stop_ramp_timestamp,
+        };
+        amp_ramp.validate()?;
 
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            accounts.pool_mint_account = pool_mint_account;
+        token_swap.swap_curve = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Box::new(StableCurve { amp: initial_amp }),
+        };
+        token_swap.amp_ramp = amp_ramp;
+        SwapVersion::pack(
+            SwapVersion::SwapV4(token_swap),
+            &mut swap_info.data.borrow_mut(),
+        )?;
+        Ok(())
+    }
 
-            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
-                0,
-            );
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        Self::process_with_constraints(program_id, accounts, input, &SWAP_CONSTRAINTS)
+    }
 
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &spl_token::id(),
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
+    /// Processes an instruction given extra constraint
+    pub fn process_with_constraints(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        input: &[u8],
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        let instruction = SwapInstruction::unpack(input)?;
+        match instruction {
+            SwapInstruction::Initialize(Initialize {
+                nonce,
+                fees,
+                swap_curve,
+                dynamic_fees,
+            }) => {
+                msg!("Instruction: Init");
+                Self::process_initialize(
+                    program_id,
+                    nonce,
+                    fees,
+                    swap_curve,
+                    dynamic_fees,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+            SwapInstruction::Swap(Swap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: Swap");
+                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::DepositAllTokenTypes(DepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_a_amount,
+                maximum_token_b_amount,
+            }) => {
+                msg!("Instruction: DepositAllTokenTypes");
+                Self::process_deposit_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    maximum_token_a_amount,
+                    maximum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawAllTokenTypes(WithdrawAllTokenTypes {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+            }) => {
+                msg!("Instruction: WithdrawAllTokenTypes");
+                Self::process_withdraw_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::DepositSingleTokenTypeExactAmountIn(
+                DepositSingleTokenTypeExactAmountIn {
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: DepositSingleTokenTypeExactAmountIn");
+                Self::process_deposit_single_token_type_exact_amount_in(
+                    program_id,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawSingleTokenTypeExactAmountOut(
+                WithdrawSingleTokenTypeExactAmountOut {
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: WithdrawSingleTokenTypeExactAmountOut");
+                Self::process_withdraw_single_token_type_exact_amount_out(
+                    program_id,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::InitializeWeighted(InitializeWeighted {
+                nonce,
+                fees,
+                weights,
+                dynamic_fees,
+            }) => {
+                msg!("Instruction: InitWeighted");
+                Self::process_initialize_weighted(
+                    program_id,
+                    nonce,
+                    fees,
+                    weights,
+                    dynamic_fees,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+            SwapInstruction::WeightedSwap(WeightedSwap {
+                source_index,
+                destination_index,
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: WeightedSwap");
+                Self::process_weighted_swap(
+                    program_id,
+                    source_index,
+                    destination_index,
+                    amount_in,
+                    minimum_amount_out,
+                    accounts,
+                )
+            }
+            SwapInstruction::WeightedDepositAllTokenTypes(WeightedDepositAllTokenTypes {
+                pool_token_amount,
+                maximum_token_amounts,
+            }) => {
+                msg!("Instruction: WeightedDepositAllTokenTypes");
+                Self::process_weighted_deposit_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    &maximum_token_amounts,
+                    accounts,
+                )
+            }
+            SwapInstruction::WeightedWithdrawAllTokenTypes(WeightedWithdrawAllTokenTypes {
                 pool_token_amount,
+                minimum_token_amounts,
+            }) => {
+                msg!("Instruction: WeightedWithdrawAllTokenTypes");
+                Self::process_weighted_withdraw_all_token_types(
+                    program_id,
+                    pool_token_amount,
+                    &minimum_token_amounts,
+                    accounts,
+                )
+            }
+            SwapInstruction::WeightedDepositSingleTokenTypeExactAmountIn(
+                WeightedDepositSingleTokenTypeExactAmountIn {
+                    token_index,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: WeightedDepositSingleTokenTypeExactAmountIn");
+                Self::process_weighted_deposit_single_token_type_exact_amount_in(
+                    program_id,
+                    token_index,
+                    source_token_amount,
+                    minimum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::WeightedWithdrawSingleTokenTypeExactAmountOut(
+                WeightedWithdrawSingleTokenTypeExactAmountOut {
+                    token_index,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                },
+            ) => {
+                msg!("Instruction: WeightedWithdrawSingleTokenTypeExactAmountOut");
+                Self::process_weighted_withdraw_single_token_type_exact_amount_out(
+                    program_id,
+                    token_index,
+                    destination_token_amount,
+                    maximum_pool_token_amount,
+                    accounts,
+                )
+            }
+            SwapInstruction::RoutedSwap(RoutedSwap {
+                amount_in,
+                minimum_amount_out,
+            }) => {
+                msg!("Instruction: RoutedSwap");
+                Self::process_routed_swap(program_id, amount_in, minimum_amount_out, accounts)
+            }
+            SwapInstruction::SetFees(SetFees { fees, dynamic_fees }) => {
+                msg!("Instruction: SetFees");
+                Self::process_set_fees(program_id, fees, dynamic_fees, accounts, swap_constraints)
+            }
+            SwapInstruction::SetPoolFeeAccount => {
+                msg!("Instruction: SetPoolFeeAccount");
+                Self::process_set_pool_fee_account(program_id, accounts, swap_constraints)
+            }
+            SwapInstruction::Pause => {
+                msg!("Instruction: Pause");
+                Self::process_set_paused(program_id, true, accounts, swap_constraints)
+            }
+            SwapInstruction::Unpause => {
+                msg!("Instruction: Unpause");
+                Self::process_set_paused(program_id, false, accounts, swap_constraints)
+            }
+            SwapInstruction::RampAmp(RampAmp {
+                target_amp,
+                stop_ramp_timestamp,
+            }) => {
+                msg!("Instruction: RampAmp");
+                Self::process_ramp_amp(
+                    program_id,
+                    target_amp,
+                    stop_ramp_timestamp,
+                    accounts,
+                    swap_constraints,
+                )
+            }
+        }
+    }
+}
+
+impl PrintProgramError for SwapError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
+            SwapError::InvalidProgramAddress => {
+                msg!("Error: Invalid program address generated from nonce and key")
+            }
+            SwapError::InvalidOwner => {
+                msg!("Error: The input account owner is not the program address")
+            }
+            SwapError::InvalidOutputOwner => {
+                msg!("Error: Output pool account owner cannot be the program address")
+            }
+            SwapError::ExpectedMint => msg!("Error: Deserialized account is not an SPL Token mint"),
+            SwapError::ExpectedAccount => {
+                msg!("Error: Deserialized account is not an SPL Token account")
+            }
+            SwapError::EmptySupply => msg!("Error: Input token account empty"),
+            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
+            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
+            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
+            SwapError::InvalidInput => msg!("Error: InvalidInput"),
+            SwapError::IncorrectSwapAccount => {
+                msg!("Error: Address of the provided swap token account is incorrect")
+            }
+            SwapError::IncorrectPoolMint => {
+                msg!("Error: Address of the provided pool token mint is incorrect")
+            }
+            SwapError::InvalidOutput => msg!("Error: InvalidOutput"),
+            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
+            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
+            SwapError::ExceededSlippage => {
+                msg!("Error: Swap instruction exceeds desired slippage limit")
+            }
+            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
+            SwapError::InvalidFreezeAuthority => {
+                msg!("Error: Pool token mint has a freeze authority")
+            }
+            SwapError::IncorrectFeeAccount => msg!("Error: Pool fee token account incorrect"),
+            SwapError::ZeroTradingTokens => {
+                msg!("Error: Given pool token amount results in zero trading tokens")
+            }
+            SwapError::FeeCalculationFailure => msg!(
+                "Error: The fee calculation failed due to overflow, underflow, or unexpected 0"
+            ),
+            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed."),
+            SwapError::InvalidFee => {
+                msg!("Error: The provided fee does not match the program owner's constraints")
+            }
+            SwapError::IncorrectTokenProgramId => {
+                msg!("Error: The provided token program does not match the token program expected by the swap")
+            }
+            SwapError::UnsupportedCurveType => {
+                msg!("Error: The provided curve type is not supported by the program owner")
+            }
+            SwapError::InvalidCurve => {
+                msg!("Error: The provided curve parameters are invalid")
+            }
+            SwapError::UnsupportedCurveOperation => {
+                msg!("Error: The operation cannot be performed on the given curve")
+            }
+            SwapError::InvalidTokenIndex => {
+                msg!("Error: The token index is out of range for the pool")
+            }
+            SwapError::InvalidAdmin => {
+                msg!("Error: The swap has no admin authority, or it did not sign")
+            }
+            SwapError::SwapPaused => msg!("Error: The swap is paused by its admin authority"),
+            SwapError::InvalidAmpRamp => {
+                msg!("Error: The amplification coefficient ramp is too fast or out of range")
+            }
+        }
+    }
+}
+
+fn to_u128(val: u64) -> Result<u128, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+fn to_u64(val: u128) -> Result<u64, SwapError> {
+    val.try_into().map_err(|_| SwapError::ConversionFailure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::calculator::{CurveCalculator, INITIAL_SWAP_POOL_AMOUNT},
+        curve::stable::MIN_RAMP_DURATION,
+        curve::{
+            base::CurveType, constant_price::ConstantPriceCurve,
+            constant_product::ConstantProductCurve, offset::OffsetCurve,
+        },
+        instruction::{
+            deposit_all_token_types, deposit_single_token_type_exact_amount_in, initialize,
+            initialize_weighted, initialize_weighted_with_admin, initialize_with_admin,
+            initialize_with_dynamic_fees, pause, ramp_amp, routed_swap, set_fees,
+            set_pool_fee_account, swap, unpause, weighted_deposit_all_token_types,
+            weighted_deposit_single_token_type_exact_amount_in, weighted_swap,
+            weighted_withdraw_all_token_types,
+            weighted_withdraw_single_token_type_exact_amount_out, withdraw_all_token_types,
+            withdraw_single_token_type_exact_amount_out, RouteHop,
+        },
+    };
+    use solana_program::{instruction::Instruction, program_stubs, rent::Rent};
+    use solana_sdk::account::{create_account_for_test, create_is_signer_account_infos, Account};
+    use spl_math::{precise_number::PreciseNumber, uint::U256};
+    use spl_token::{
+        error::TokenError,
+        instruction::{
+            approve, initialize_account, initialize_mint, mint_to, revoke, set_authority,
+            AuthorityType,
+        },
+    };
+
+    // Test program id for the swap program.
+    const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
+
+    struct TestSyscallStubs {}
+    impl program_stubs::SyscallStubs for TestSyscallStubs {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            msg!("TestSyscallStubs::sol_invoke_signed()");
+
+            let mut new_account_infos = vec![];
+
+            // mimic check for token program in accounts
+            if !account_infos.iter().any(|x| *x.key == spl_token::id()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            for meta in instruction.accounts.iter() {
+                for account_info in account_infos.iter() {
+                    if meta.pubkey == *account_info.key {
+                        let mut new_account_info = account_info.clone();
+                        for seeds in signers_seeds.iter() {
+                            let signer =
+                                Pubkey::create_program_address(seeds, &SWAP_PROGRAM_ID).unwrap();
+                            if *account_info.key == signer {
+                                new_account_info.is_signer = true;
+                            }
+                        }
+                        new_account_infos.push(new_account_info);
+                    }
+                }
+            }
+
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &new_account_infos,
+                &instruction.data,
+            )
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            #[allow(clippy::cast_ptr_alignment)]
+            unsafe {
+                *(var_addr as *mut Clock) = Clock {
+                    slot: TEST_SLOT.with(|slot| slot.get()),
+                    unix_timestamp: TEST_UNIX_TIMESTAMP.with(|timestamp| timestamp.get()),
+                    ..Clock::default()
+                };
+            }
+            solana_program::entrypoint::SUCCESS
+        }
+    }
+
+    thread_local! {
+        // Unix timestamp returned by the clock sysvar to the current test
+        static TEST_UNIX_TIMESTAMP: std::cell::Cell<i64> = std::cell::Cell::new(0);
+        // Slot returned by the clock sysvar to the current test
+        static TEST_SLOT: std::cell::Cell<u64> = std::cell::Cell::new(0);
+    }
+
+    fn set_test_unix_timestamp(unix_timestamp: i64) {
+        TEST_UNIX_TIMESTAMP.with(|timestamp| timestamp.set(unix_timestamp));
+    }
+
+    fn set_test_slot(slot: u64) {
+        TEST_SLOT.with(|test_slot| test_slot.set(slot));
+    }
+
+    fn test_syscall_stubs() {
+        use std::sync::Once;
+        static ONCE: Once = Once::new();
+
+        ONCE.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+        });
+    }
+
+    struct SwapAccountInfo {
+        nonce: u8,
+        authority_key: Pubkey,
+        fees: Fees,
+        swap_curve: SwapCurve,
+        dynamic_fees: DynamicFees,
+        swap_key: Pubkey,
+        swap_account: Account,
+        pool_mint_key: Pubkey,
+        pool_mint_account: Account,
+        pool_fee_key: Pubkey,
+        pool_fee_account: Account,
+        pool_token_key: Pubkey,
+        pool_token_account: Account,
+        token_a_key: Pubkey,
+        token_a_account: Account,
+        token_a_mint_key: Pubkey,
+        token_a_mint_account: Account,
+        token_b_key: Pubkey,
+        token_b_account: Account,
+        token_b_mint_key: Pubkey,
+        token_b_mint_account: Account,
+    }
+
+    impl SwapAccountInfo {
+        pub fn new(
+            user_key: &Pubkey,
+            fees: Fees,
+            swap_curve: SwapCurve,
+            token_a_amount: u64,
+            token_b_amount: u64,
+        ) -> Self {
+            let swap_key = Pubkey::new_unique();
+            let swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
+            let (authority_key, nonce) =
+                Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+
+            let (pool_mint_key, mut pool_mint_account) =
+                create_mint(&spl_token::id(), &authority_key, None);
+            let (pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
+            );
+            let (pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                user_key,
+                0,
+            );
+            let (token_a_mint_key, mut token_a_mint_account) =
+                create_mint(&spl_token::id(), user_key, None);
+            let (token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &token_a_mint_key,
+                &mut token_a_mint_account,
+                user_key,
+                &authority_key,
+                token_a_amount,
+            );
+            let (token_b_mint_key, mut token_b_mint_account) =
+                create_mint(&spl_token::id(), user_key, None);
+            let (token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &token_b_mint_key,
+                &mut token_b_mint_account,
+                user_key,
+                &authority_key,
+                token_b_amount,
+            );
+
+            SwapAccountInfo {
+                nonce,
+                authority_key,
+                fees,
+                swap_curve,
+                dynamic_fees: DynamicFees::default(),
+                swap_key,
+                swap_account,
+                pool_mint_key,
+                pool_mint_account,
+                pool_fee_key,
+                pool_fee_account,
+                pool_token_key,
+                pool_token_account,
+                token_a_key,
+                token_a_account,
+                token_a_mint_key,
+                token_a_mint_account,
+                token_b_key,
+                token_b_account,
+                token_b_mint_key,
+                token_b_mint_account,
+            }
+        }
+
+        pub fn initialize_swap(&mut self) -> ProgramResult {
+            do_process_instruction(
+                initialize_with_dynamic_fees(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    self.nonce,
+                    self.fees.clone(),
+                    self.swap_curve.clone(),
+                    self.dynamic_fees.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn initialize_swap_with_admin(
+            &mut self,
+            admin_authority_key: &Pubkey,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_with_admin(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    &self.pool_token_key,
+                    admin_authority_key,
+                    self.nonce,
+                    self.fees.clone(),
+                    self.swap_curve.clone(),
+                    self.dynamic_fees.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut self.pool_token_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn setup_token_accounts(
+            &mut self,
+            mint_owner: &Pubkey,
+            account_owner: &Pubkey,
+            a_amount: u64,
+            b_amount: u64,
+            pool_amount: u64,
+        ) -> (Pubkey, Account, Pubkey, Account, Pubkey, Account) {
+            let (token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &self.token_a_mint_key,
+                &mut self.token_a_mint_account,
+                mint_owner,
+                account_owner,
+                a_amount,
+            );
+            let (token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &self.token_b_mint_key,
+                &mut self.token_b_mint_account,
+                mint_owner,
+                account_owner,
+                b_amount,
+            );
+            let (pool_key, pool_account) = mint_token(
+                &spl_token::id(),
+                &self.pool_mint_key,
+                &mut self.pool_mint_account,
+                &self.authority_key,
+                account_owner,
+                pool_amount,
+            );
+            (
+                token_a_key,
+                token_a_account,
+                token_b_key,
+                token_b_account,
+                pool_key,
+                pool_account,
+            )
+        }
+
+        fn get_token_account(&self, account_key: &Pubkey) -> &Account {
+            if *account_key == self.token_a_key {
+                return &self.token_a_account;
+            } else if *account_key == self.token_b_key {
+                return &self.token_b_account;
+            }
+            panic!("Could not find matching swap token account");
+        }
+
+        fn set_token_account(&mut self, account_key: &Pubkey, account: Account) {
+            if *account_key == self.token_a_key {
+                self.token_a_account = account;
+                return;
+            } else if *account_key == self.token_b_key {
+                self.token_b_account = account;
+                return;
+            }
+            panic!("Could not find matching swap token account");
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            mut user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            let user_transfer_key = Pubkey::new_unique();
+            // approve moving from user source account
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    user_source_key,
+                    &user_transfer_key,
+                    user_key,
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            // perform the swap
+            do_process_instruction(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_key,
+                    user_source_key,
+                    swap_source_key,
+                    swap_destination_key,
+                    user_destination_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    None,
+                    Swap {
+                        amount_in,
+                        minimum_amount_out,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    &mut user_destination_account,
+                    &mut self.pool_mint_account,
+                    &mut self.pool_fee_account,
+                    &mut Account::default(),
+                ],
+            )?;
+
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_all_token_types(
+            &mut self,
+            depositor_key: &Pubkey,
+            depositor_token_a_key: &Pubkey,
+            mut depositor_token_a_account: &mut Account,
+            depositor_token_b_key: &Pubkey,
+            mut depositor_token_b_account: &mut Account,
+            depositor_pool_key: &Pubkey,
+            mut depositor_pool_account: &mut Account,
+            pool_token_amount: u64,
+            maximum_token_a_amount: u64,
+            maximum_token_b_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority = Pubkey::new_unique();
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    depositor_token_a_key,
+                    &user_transfer_authority,
+                    depositor_key,
+                    &[],
+                    maximum_token_a_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut depositor_token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    depositor_token_b_key,
+                    &user_transfer_authority,
+                    depositor_key,
+                    &[],
+                    maximum_token_b_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut depositor_token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority,
+                    depositor_token_a_key,
+                    depositor_token_b_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    depositor_pool_key,
+                    DepositAllTokenTypes {
+                        pool_token_amount,
+                        maximum_token_a_amount,
+                        maximum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut depositor_token_a_account,
+                    &mut depositor_token_b_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut depositor_pool_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_all_token_types(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            mut token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            mut token_b_account: &mut Account,
+            pool_token_amount: u64,
+            minimum_token_a_amount: u64,
+            minimum_token_b_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            // approve user transfer authority to take out pool tokens
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    pool_key,
+                    &user_transfer_authority_key,
+                    user_key,
+                    &[],
+                    pool_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            // withdraw token a and b correctly
+            do_process_instruction(
+                withdraw_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    token_a_key,
+                    token_b_key,
+                    WithdrawAllTokenTypes {
+                        pool_token_amount,
+                        minimum_token_a_amount,
+                        minimum_token_b_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    &mut pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut token_a_account,
+                    &mut token_b_account,
+                    &mut self.pool_fee_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_single_token_type_exact_amount_in(
+            &mut self,
+            depositor_key: &Pubkey,
+            deposit_account_key: &Pubkey,
+            mut deposit_token_account: &mut Account,
+            deposit_pool_key: &Pubkey,
+            mut deposit_pool_account: &mut Account,
+            source_token_amount: u64,
+            minimum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    deposit_account_key,
+                    &user_transfer_authority_key,
+                    depositor_key,
+                    &[],
+                    source_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut deposit_token_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_single_token_type_exact_amount_in(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    deposit_account_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                    deposit_pool_key,
+                    DepositSingleTokenTypeExactAmountIn {
+                        source_token_amount,
+                        minimum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut deposit_token_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut deposit_pool_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_single_token_type_exact_amount_out(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            destination_key: &Pubkey,
+            mut destination_account: &mut Account,
+            destination_token_amount: u64,
+            maximum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            let user_transfer_authority_key = Pubkey::new_unique();
+            // approve user transfer authority to take out pool tokens
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    pool_key,
+                    &user_transfer_authority_key,
+                    user_key,
+                    &[],
+                    maximum_pool_token_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                withdraw_single_token_type_exact_amount_out(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &self.swap_key,
+                    &self.authority_key,
+                    &user_transfer_authority_key,
+                    &self.pool_mint_key,
+                    &self.pool_fee_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_key,
+                    WithdrawSingleTokenTypeExactAmountOut {
+                        destination_token_amount,
+                        maximum_pool_token_amount,
+                    },
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    &mut pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut destination_account,
+                    &mut self.pool_fee_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+    }
+
+    fn mint_minimum_balance() -> u64 {
+        Rent::default().minimum_balance(spl_token::state::Mint::get_packed_len())
+    }
+
+    fn account_minimum_balance() -> u64 {
+        Rent::default().minimum_balance(spl_token::state::Account::get_packed_len())
+    }
+
+    fn do_process_instruction_with_fee_constraints(
+        instruction: Instruction,
+        accounts: Vec<&mut Account>,
+        swap_constraints: &Option<SwapConstraints>,
+    ) -> ProgramResult {
+        test_syscall_stubs();
+
+        // approximate the logic in the actual runtime which runs the instruction
+        // and only updates accounts if the instruction is successful
+        let mut account_clones = accounts.iter().map(|x| (*x).clone()).collect::<Vec<_>>();
+        let mut meta = instruction
+            .accounts
+            .iter()
+            .zip(account_clones.iter_mut())
+            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
+            .collect::<Vec<_>>();
+        let mut account_infos = create_is_signer_account_infos(&mut meta);
+        let res = if instruction.program_id == SWAP_PROGRAM_ID {
+            Processor::process_with_constraints(
+                &instruction.program_id,
+                &account_infos,
+                &instruction.data,
+                swap_constraints,
+            )
+        } else {
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &account_infos,
+                &instruction.data,
+            )
+        };
+
+        if res.is_ok() {
+            let mut account_metas = instruction
+                .accounts
+                .iter()
+                .zip(accounts)
+                .map(|(account_meta, account)| (&account_meta.pubkey, account))
+                .collect::<Vec<_>>();
+            for account_info in account_infos.iter_mut() {
+                for account_meta in account_metas.iter_mut() {
+                    if account_info.key == account_meta.0 {
+                        let account = &mut account_meta.1;
+                        account.owner = *account_info.owner;
+                        account.lamports = **account_info.lamports.borrow();
+                        account.data = account_info.data.borrow().to_vec();
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn do_process_instruction(
+        instruction: Instruction,
+        accounts: Vec<&mut Account>,
+    ) -> ProgramResult {
+        do_process_instruction_with_fee_constraints(instruction, accounts, &SWAP_CONSTRAINTS)
+    }
+
+    fn mint_token(
+        program_id: &Pubkey,
+        mint_key: &Pubkey,
+        mut mint_account: &mut Account,
+        mint_authority_key: &Pubkey,
+        account_owner_key: &Pubkey,
+        amount: u64,
+    ) -> (Pubkey, Account) {
+        let account_key = Pubkey::new_unique();
+        let mut account_account = Account::new(
+            account_minimum_balance(),
+            spl_token::state::Account::get_packed_len(),
+            program_id,
+        );
+        let mut mint_authority_account = Account::default();
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+
+        do_process_instruction(
+            initialize_account(program_id, &account_key, mint_key, account_owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut mint_authority_account,
+                &mut rent_sysvar_account,
+            ],
+        )
+        .unwrap();
+
+        if amount > 0 {
+            do_process_instruction(
+                mint_to(
+                    program_id,
+                    mint_key,
+                    &account_key,
+                    mint_authority_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut mint_authority_account,
+                ],
+            )
+            .unwrap();
+        }
+
+        (account_key, account_account)
+    }
+
+    fn create_mint(
+        program_id: &Pubkey,
+        authority_key: &Pubkey,
+        freeze_authority: Option<&Pubkey>,
+    ) -> (Pubkey, Account) {
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = Account::new(
+            mint_minimum_balance(),
+            spl_token::state::Mint::get_packed_len(),
+            program_id,
+        );
+        let mut rent_sysvar_account = create_account_for_test(&Rent::free());
+
+        do_process_instruction(
+            initialize_mint(program_id, &mint_key, authority_key, freeze_authority, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar_account],
+        )
+        .unwrap();
+
+        (mint_key, mint_account)
+    }
+
+    #[test]
+    fn test_token_program_id_error() {
+        test_syscall_stubs();
+        let swap_key = Pubkey::new_unique();
+        let mut mint = (Pubkey::new_unique(), Account::default());
+        let mut destination = (Pubkey::new_unique(), Account::default());
+        let token_program = (spl_token::id(), Account::default());
+        let (authority_key, nonce) =
+            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+        let mut authority = (authority_key, Account::default());
+        let swap_bytes = swap_key.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = mint_to(
+            &token_program.0,
+            &mint.0,
+            &destination.0,
+            &authority.0,
+            &[],
+            10,
+        )
+        .unwrap();
+        let mint = (&mut mint).into();
+        let destination = (&mut destination).into();
+        let authority = (&mut authority).into();
+
+        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_initialize() {
+        let user_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let pool_token_amount = 10;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Box::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // wrong nonce for authority_key
+        {
+            let old_nonce = accounts.nonce;
+            accounts.nonce = old_nonce - 1;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.initialize_swap()
+            );
+            accounts.nonce = old_nonce;
+        }
+
+        // uninitialized token a account
+        {
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // uninitialized token b account
+        {
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // uninitialized pool mint
+        {
+            let old_account = accounts.pool_mint_account;
+            accounts.pool_mint_account = Account::new(0, 0, &spl_token::id());
+            assert_eq!(
+                Err(SwapError::ExpectedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_account;
+        }
+
+        // token A account owner is not swap authority
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B account owner is not swap authority
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // pool token account owner is swap authority
+        {
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.pool_token_account;
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_token_account = old_account;
+        }
+
+        // pool fee account owner is swap authority
+        {
+            let (_pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.pool_fee_account;
+            accounts.pool_fee_account = pool_fee_account;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_fee_account = old_account;
+        }
+
+        // pool mint authority is not swap authority
+        {
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &user_key, None);
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // pool mint token has freeze authority
+        {
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, Some(&user_key));
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidFreezeAuthority.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // token A account owned by wrong program
+        {
+            let (_token_a_key, mut token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_a_amount,
+            );
+            token_a_account.owner = SWAP_PROGRAM_ID;
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B account owned by wrong program
+        {
+            let (_token_b_key, mut token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                token_b_amount,
+            );
+            token_b_account.owner = SWAP_PROGRAM_ID;
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // empty token A account
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // empty token B account
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // invalid pool tokens
+        {
+            let old_mint = accounts.pool_mint_account;
+            let old_pool_account = accounts.pool_token_account;
+
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            accounts.pool_mint_account = pool_mint_account;
+
+            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &spl_token::id(),
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                pool_token_amount,
+            );
+
+            // non-empty pool token account
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            // pool tokens already in circulation
+            accounts.pool_token_account = empty_pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.pool_mint_account = old_mint;
+            accounts.pool_token_account = old_pool_account;
+        }
+
+        // pool fee account has wrong mint
+        {
+            let (_pool_fee_key, pool_fee_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.pool_fee_account;
+            accounts.pool_fee_account = pool_fee_account;
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_fee_account = old_account;
+        }
+
+        // token A account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token A account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_a_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &spl_token::id(),
+                    &accounts.token_b_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // wrong token program id
+        {
+            let wrong_program_id = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        accounts.nonce,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // create swap with same token A and B
+        {
+            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
+                &spl_token::id(),
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                10,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_a_repeat_account;
+            assert_eq!(
+                Err(SwapError::RepeatedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // create valid swap
+        accounts.initialize_swap().unwrap();
+
+        // create invalid flat swap
+        {
+            let token_b_price = 0;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Box::new(ConstantPriceCurve { token_b_price }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
+            );
+        }
+
+        // create valid flat swap
+        {
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let token_b_price = 10_000;
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantPrice,
+                calculator: Box::new(ConstantPriceCurve { token_b_price }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            accounts.initialize_swap().unwrap();
+        }
+
+        // create invalid offset swap
+        {
+            let token_b_offset = 0;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::Offset,
+                calculator: Box::new(OffsetCurve { token_b_offset }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            assert_eq!(
+                Err(SwapError::InvalidCurve.into()),
+                accounts.initialize_swap()
+            );
+        }
+
+        // create valid offset swap
+        {
+            let token_b_offset = 10;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::Offset,
+                calculator: Box::new(OffsetCurve { token_b_offset }),
+            };
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            accounts.initialize_swap().unwrap();
+        }
+
+        // wrong owner key in constraint
+        {
+            let new_key = Pubkey::new_unique();
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let curve = ConstantProductCurve {};
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(curve),
+            };
+            let owner_key = &new_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                dynamic_fees: None,
+            });
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                do_process_instruction_with_fee_constraints(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        accounts.nonce,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // wrong fee in constraint
+        {
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let curve = ConstantProductCurve {};
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(curve),
+            };
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                dynamic_fees: None,
+            });
+            let mut bad_fees = fees.clone();
+            bad_fees.trade_fee_numerator = trade_fee_numerator - 1;
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                bad_fees,
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidFee.into()),
+                do_process_instruction_with_fee_constraints(
+                    initialize(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &accounts.pool_token_key,
+                        accounts.nonce,
+                        accounts.fees.clone(),
+                        accounts.swap_curve.clone(),
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut accounts.pool_token_account,
+                        &mut Account::default(),
+                    ],
+                    &constraints,
+                )
+            );
+        }
+
+        // create valid swap with constraints
+        {
+            let trade_fee_numerator = 25;
+            let trade_fee_denominator = 10000;
+            let owner_trade_fee_numerator = 5;
+            let owner_trade_fee_denominator = 10000;
+            let host_fee_numerator = 20;
+            let host_fee_denominator = 100;
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let curve = ConstantProductCurve {};
+            let swap_curve = SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(curve),
+            };
+            let owner_key = &user_key.to_string();
+            let valid_curve_types = &[CurveType::ConstantProduct];
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types,
+                fees: &fees,
+                dynamic_fees: None,
+            });
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                fees.clone(),
+                swap_curve,
+                token_a_amount,
+                token_b_amount,
+            );
+            do_process_instruction_with_fee_constraints(
+                initialize(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    &accounts.pool_token_key,
+                    accounts.nonce,
+                    accounts.fees,
+                    accounts.swap_curve.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.pool_token_account,
+                    &mut Account::default(),
+                ],
+                &constraints,
+            )
+            .unwrap();
+        }
+
+        // create again
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_swap()
+            );
+        }
+        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_state.is_initialized());
+        assert_eq!(swap_state.nonce(), accounts.nonce);
+        assert_eq!(
+            swap_state.swap_curve().curve_type,
+            accounts.swap_curve.curve_type
+        );
+        assert_eq!(*swap_state.token_a_account(), accounts.token_a_key);
+        assert_eq!(*swap_state.token_b_account(), accounts.token_b_key);
+        assert_eq!(*swap_state.pool_mint(), accounts.pool_mint_key);
+        assert_eq!(*swap_state.token_a_mint(), accounts.token_a_mint_key);
+        assert_eq!(*swap_state.token_b_mint(), accounts.token_b_mint_key);
+        assert_eq!(*swap_state.pool_fee_account(), accounts.pool_fee_key);
+        let token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, token_b_amount);
+        let pool_account =
+            spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        assert_eq!(pool_mint.supply, pool_account.amount);
+    }
+
+    #[test]
+    fn test_deposit() {
+        let user_key = Pubkey::new_unique();
+        let depositor_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 9000;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Box::new(ConstantProductCurve {}),
+        };
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // depositing 10% of the current pool amount in token A and B means
+        // that our pool tokens will be worth 1 / 10 of the current pool amount
+        let pool_amount = INITIAL_SWAP_POOL_AMOUNT / 10;
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong nonce for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough token A
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a / 2,
+                deposit_b,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // not enough token B
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a,
+                deposit_b / 2,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (
+                wrong_token_key,
+                mut wrong_token_account,
+                _token_b_key,
+                mut _token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &wrong_token_key,
+                    &mut wrong_token_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let user_transfer_authority_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    deposit_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        DepositAllTokenTypes {
+                            pool_token_amount: pool_amount.try_into().unwrap(),
+                            maximum_token_a_amount: deposit_a,
+                            maximum_token_b_amount: deposit_b,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let wrong_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    deposit_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        DepositAllTokenTypes {
+                            pool_token_amount: pool_amount.try_into().unwrap(),
+                            maximum_token_a_amount: deposit_a,
+                            maximum_token_b_amount: deposit_b,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // deposit 1 pool token fails beacuse it equates to 0 swap tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1,
+                    deposit_a,
+                    deposit_b,
+                )
+            );
+        }
+
+        // slippage exceeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            // maximum A amount in too low
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a / 10,
+                    deposit_b,
+                )
+            );
+            // maximum B amount in too low
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b / 10,
+                )
             );
+        }
 
-            // non-empty pool token account
-            accounts.pool_token_account = pool_token_account;
+        // invalid input: can't use swap pool tokens as source
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            let authority_key = accounts.authority_key;
             assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_all_token_types(
+                    &authority_key,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
             );
+        }
 
-            // pool tokens already in circulation
-            accounts.pool_token_account = empty_pool_token_account;
+        // correctly deposit
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit_all_token_types(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount.try_into().unwrap(),
+                    deposit_a,
+                    deposit_b,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 0);
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            let swap_pool_account =
+                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
             assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
+                pool_mint.supply,
+                pool_account.amount + swap_pool_account.amount
             );
+        }
+    }
 
-            accounts.pool_mint_account = old_mint;
-            accounts.pool_token_account = old_pool_account;
+    #[test]
+    fn test_withdraw() {
+        let user_key = Pubkey::new_unique();
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 2;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 10;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 5;
+        let host_fee_numerator = 7;
+        let host_fee_denominator = 100;
+
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let curve_type = CurveType::ConstantProduct;
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator: Box::new(ConstantProductCurve {}),
+        };
+
+        let withdrawer_key = Pubkey::new_unique();
+        let initial_a = token_a_amount / 10;
+        let initial_b = token_b_amount / 10;
+        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
+        let withdraw_amount = initial_pool / 4;
+        let minimum_token_a_amount = initial_a / 40;
+        let minimum_token_b_amount = initial_b / 40;
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
         }
 
-        // pool fee account has wrong mint
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
         {
-            let (_pool_fee_key, pool_fee_account) = mint_token(
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
+            );
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong nonce for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
                 &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
-                &user_key,
-                0,
             );
-            let old_account = accounts.pool_fee_account;
-            accounts.pool_fee_account = pool_fee_account;
+            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
             );
-            accounts.pool_fee_account = old_account;
+            accounts.authority_key = old_authority;
         }
 
-        // token A account is delegated
+        // not enough pool tokens
         {
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_a_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                to_u64(withdraw_amount).unwrap() / 2u64,
             );
-
-            do_process_instruction(
-                revoke(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    &accounts.authority_key,
-                    &[],
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount / 2,
+                    minimum_token_b_amount / 2,
                 )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
+            );
         }
 
-        // token B account is delegated
+        // wrong token a / b accounts
         {
-            do_process_instruction(
-                approve(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_b_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
             );
-
-            do_process_instruction(
-                revoke(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    &accounts.authority_key,
-                    &[],
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
                 )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
-            )
-            .unwrap();
+            );
         }
 
-        // token A account has close authority
+        // wrong pool token account
         {
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
             );
-
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_a_key,
-                    None,
-                    AuthorityType::CloseAccount,
-                    &user_key,
-                    &[],
+            let (
+                wrong_token_a_key,
+                mut wrong_token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                withdraw_amount.try_into().unwrap(),
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &wrong_token_a_key,
+                    &mut wrong_token_a_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
                 )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
+            );
         }
 
-        // token B account has close authority
+        // wrong pool fee account
         {
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
-            )
-            .unwrap();
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let old_pool_fee_account = accounts.pool_fee_account;
+            let old_pool_fee_key = accounts.pool_fee_key;
+            accounts.pool_fee_account = wrong_pool_account;
+            accounts.pool_fee_key = wrong_pool_key;
             assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectFeeAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                ),
             );
+            accounts.pool_fee_account = old_pool_fee_account;
+            accounts.pool_fee_key = old_pool_fee_key;
+        }
 
-            do_process_instruction(
-                set_authority(
-                    &spl_token::id(),
-                    &accounts.token_b_key,
-                    None,
-                    AuthorityType::CloseAccount,
-                    &user_key,
-                    &[],
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                0,
+                0,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let user_transfer_authority_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        WithdrawAllTokenTypes {
+                            pool_token_amount: withdraw_amount.try_into().unwrap(),
+                            minimum_token_a_amount,
+                            minimum_token_b_amount,
+                        }
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                    ],
                 )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
-            )
-            .unwrap();
+            );
         }
 
         // wrong token program id
         {
-            let wrong_program_id = Pubkey::new_unique();
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount.try_into().unwrap(),
+            );
+            let wrong_key = Pubkey::new_unique();
             assert_eq!(
                 Err(SwapError::IncorrectTokenProgramId.into()),
                 do_process_instruction(
-                    initialize(
+                    withdraw_all_token_types(
                         &SWAP_PROGRAM_ID,
-                        &wrong_program_id,
+                        &wrong_key,
                         &accounts.swap_key,
                         &accounts.authority_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
+                        &accounts.authority_key,
                         &accounts.pool_mint_key,
                         &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.nonce,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        WithdrawAllTokenTypes {
+                            pool_token_amount: withdraw_amount.try_into().unwrap(),
+                            minimum_token_a_amount,
+                            minimum_token_b_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
                         &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
                         &mut Account::default(),
                     ],
                 )
             );
         }
 
-        // create swap with same token A and B
+        // wrong swap token accounts
         {
-            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
-                &spl_token::id(),
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                &accounts.authority_key,
-                10,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
             );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_a_repeat_account;
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
             assert_eq!(
-                Err(SwapError::RepeatedMint.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
             );
-            accounts.token_b_account = old_account;
-        }
 
-        // create valid swap
-        accounts.initialize_swap().unwrap();
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
 
-        // create invalid flat swap
-        {
-            let token_b_price = 0;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantPrice,
-                calculator: Box::new(ConstantPriceCurve { token_b_price }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
             assert_eq!(
-                Err(SwapError::InvalidCurve.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
             );
-        }
 
-        // create valid flat swap
-        {
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let token_b_price = 10_000;
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantPrice,
-                calculator: Box::new(ConstantPriceCurve { token_b_price }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            accounts.initialize_swap().unwrap();
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
         }
 
-        // create invalid offset swap
+        // wrong mint
         {
-            let token_b_offset = 0;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::Offset,
-                calculator: Box::new(OffsetCurve { token_b_offset }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
             assert_eq!(
-                Err(SwapError::InvalidCurve.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
             );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
         }
 
-        // create valid offset swap
+        // withdrawing 1 pool token fails because it equates to 0 output tokens
         {
-            let token_b_offset = 10;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::Offset,
-                calculator: Box::new(OffsetCurve { token_b_offset }),
-            };
-            let mut accounts =
-                SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-            accounts.initialize_swap().unwrap();
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            assert_eq!(
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1,
+                    0,
+                    0,
+                )
+            );
         }
 
-        // wrong owner key in constraint
+        // slippage exceeded
         {
-            let new_key = Pubkey::new_unique();
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Box::new(curve),
-            };
-            let owner_key = &new_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut accounts = SwapAccountInfo::new(
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                fees.clone(),
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
             );
+            // minimum A amount out too high
             assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                do_process_instruction_with_fee_constraints(
-                    initialize(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.nonce,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
-                        &mut Account::default(),
-                    ],
-                    &constraints,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount * 10,
+                    minimum_token_b_amount,
+                )
+            );
+            // minimum B amount out too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount * 10,
                 )
             );
         }
 
-        // wrong fee in constraint
+        // invalid input: can't use swap pool tokens as destination
         {
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Box::new(curve),
-            };
-            let owner_key = &user_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut bad_fees = fees.clone();
-            bad_fees.trade_fee_numerator = trade_fee_numerator - 1;
-            let mut accounts = SwapAccountInfo::new(
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                bad_fees,
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                )
             );
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
             assert_eq!(
-                Err(SwapError::InvalidFee.into()),
-                do_process_instruction_with_fee_constraints(
-                    initialize(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &accounts.pool_token_key,
-                        accounts.nonce,
-                        accounts.fees.clone(),
-                        accounts.swap_curve.clone(),
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut accounts.pool_token_account,
-                        &mut Account::default(),
-                    ],
-                    &constraints,
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
                 )
             );
         }
 
-        // create valid swap with constraints
+        // correct withdrawal
         {
-            let trade_fee_numerator = 25;
-            let trade_fee_denominator = 10000;
-            let owner_trade_fee_numerator = 5;
-            let owner_trade_fee_denominator = 10000;
-            let host_fee_numerator = 20;
-            let host_fee_denominator = 100;
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let curve = ConstantProductCurve {};
-            let swap_curve = SwapCurve {
-                curve_type: CurveType::ConstantProduct,
-                calculator: Box::new(curve),
-            };
-            let owner_key = &user_key.to_string();
-            let valid_curve_types = &[CurveType::ConstantProduct];
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types,
-                fees: &fees,
-            });
-            let mut accounts = SwapAccountInfo::new(
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                fees.clone(),
-                swap_curve,
-                token_a_amount,
-                token_b_amount,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
             );
-            do_process_instruction_with_fee_constraints(
-                initialize(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &accounts.swap_key,
-                    &accounts.authority_key,
-                    &accounts.token_a_key,
-                    &accounts.token_b_key,
-                    &accounts.pool_mint_key,
-                    &accounts.pool_fee_key,
-                    &accounts.pool_token_key,
-                    accounts.nonce,
-                    accounts.fees,
-                    accounts.swap_curve.clone(),
+
+            accounts
+                .withdraw_all_token_types(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount.try_into().unwrap(),
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
                 )
-                .unwrap(),
-                vec![
-                    &mut accounts.swap_account,
-                    &mut Account::default(),
-                    &mut accounts.token_a_account,
-                    &mut accounts.token_b_account,
-                    &mut accounts.pool_mint_account,
-                    &mut accounts.pool_fee_account,
-                    &mut accounts.pool_token_account,
-                    &mut Account::default(),
-                ],
-                &constraints,
-            )
-            .unwrap();
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+            let withdraw_fee = accounts.fees.owner_withdraw_fee(withdraw_amount).unwrap();
+            let results = accounts
+                .swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    withdraw_amount - withdraw_fee,
+                    pool_mint.supply.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    RoundDirection::Floor,
+                )
+                .unwrap();
+            assert_eq!(
+                swap_token_a.amount,
+                token_a_amount - to_u64(results.token_a_amount).unwrap()
+            );
+            assert_eq!(
+                swap_token_b.amount,
+                token_b_amount - to_u64(results.token_b_amount).unwrap()
+            );
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(
+                token_a.amount,
+                initial_a + to_u64(results.token_a_amount).unwrap()
+            );
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(
+                token_b.amount,
+                initial_b + to_u64(results.token_b_amount).unwrap()
+            );
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            assert_eq!(
+                pool_account.amount,
+                to_u64(initial_pool - withdraw_amount).unwrap()
+            );
+            let fee_account =
+                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+            assert_eq!(
+                fee_account.amount,
+                TryInto::<u64>::try_into(withdraw_fee).unwrap()
+            );
         }
 
-        // create again
+        // correct withdrawal from fee account
         {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+            let pool_fee_key = accounts.pool_fee_key;
+            let mut pool_fee_account = accounts.pool_fee_account.clone();
+            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
+            let pool_fee_amount = fee_account.amount;
+
+            accounts
+                .withdraw_all_token_types(
+                    &user_key,
+                    &pool_fee_key,
+                    &mut pool_fee_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    pool_fee_amount,
+                    0,
+                    0,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+            let results = accounts
+                .swap_curve
+                .calculator
+                .pool_tokens_to_trading_tokens(
+                    pool_fee_amount.try_into().unwrap(),
+                    pool_mint.supply.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    RoundDirection::Floor,
+                )
+                .unwrap();
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
             assert_eq!(
-                Err(SwapError::AlreadyInUse.into()),
-                accounts.initialize_swap()
+                token_a.amount,
+                TryInto::<u64>::try_into(results.token_a_amount).unwrap()
             );
-        }
-        let swap_state = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
-        assert!(swap_state.is_initialized());
-        assert_eq!(swap_state.nonce(), accounts.nonce);
-        assert_eq!(
-            swap_state.swap_curve().curve_type,
-            accounts.swap_curve.curve_type
-        );
-        assert_eq!(*swap_state.token_a_account(), accounts.token_a_key);
-        assert_eq!(*swap_state.token_b_account(), accounts.token_b_key);
-        assert_eq!(*swap_state.pool_mint(), accounts.pool_mint_key);
-        assert_eq!(*swap_state.token_a_mint(), accounts.token_a_mint_key);
-        assert_eq!(*swap_state.token_b_mint(), accounts.token_b_mint_key);
-        assert_eq!(*swap_state.pool_fee_account(), accounts.pool_fee_key);
-        let token_a = spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, token_b_amount);
-        let pool_account =
-            spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        assert_eq!(pool_mint.supply, pool_account.amount);
+            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+            assert_eq!(
+                token_b.amount,
+                TryInto::<u64>::try_into(results.token_b_amount).unwrap()
+            );
+        }
     }
 
     #[test]
-    fn test_deposit() {
+    fn test_deposit_one_exact_in() {
         let user_key = Pubkey::new_unique();
         let depositor_key = Pubkey::new_unique();
         let trade_fee_numerator = 1;
@@ -2845,35 +5834,30 @@ mod tests {
         let mut accounts =
             SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-        // depositing 10% of the current pool amount in token A and B means
-        // that our pool tokens will be worth 1 / 10 of the current pool amount
-        let pool_amount = INITIAL_SWAP_POOL_AMOUNT / 10;
         let deposit_a = token_a_amount / 10;
         let deposit_b = token_b_amount / 10;
+        let pool_amount = to_u64(INITIAL_SWAP_POOL_AMOUNT / 100).unwrap();
 
         // swap not initialized
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
             assert_eq!(
                 Err(ProgramError::UninitializedAccount),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
         }
@@ -2885,8 +5869,8 @@ mod tests {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -2896,17 +5880,14 @@ mod tests {
             accounts.swap_account = wrong_swap_account;
             assert_eq!(
                 Err(ProgramError::IncorrectProgramId),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
             accounts.swap_account = old_swap_account;
@@ -2917,8 +5898,8 @@ mod tests {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -2930,23 +5911,20 @@ mod tests {
             accounts.authority_key = bad_authority_key;
             assert_eq!(
                 Err(SwapError::InvalidProgramAddress.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
             accounts.authority_key = old_authority;
         }
 
-        // not enough token A
+        // not enough token A / B
         {
             let (
                 token_a_key,
@@ -2959,82 +5937,31 @@ mod tests {
                 &user_key,
                 &depositor_key,
                 deposit_a / 2,
-                deposit_b,
-                0,
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
-                    deposit_b,
-                )
-            );
-        }
-
-        // not enough token B
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &depositor_key,
-                deposit_a,
                 deposit_b / 2,
                 0,
             );
             assert_eq!(
                 Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    0,
                 )
             );
-        }
-
-        // wrong swap token accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit_all_token_types(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_b_key,
                     &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
                     deposit_b,
+                    0,
                 )
             );
         }
@@ -3049,27 +5976,16 @@ mod tests {
                 _pool_key,
                 mut _pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (
-                wrong_token_key,
-                mut wrong_token_account,
-                _token_b_key,
-                mut _token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
             assert_eq!(
                 Err(TokenError::MintMismatch.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    &wrong_token_key,
-                    &mut wrong_token_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
         }
@@ -3079,8 +5995,8 @@ mod tests {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -3088,22 +6004,20 @@ mod tests {
             assert_eq!(
                 Err(TokenError::OwnerMismatch.into()),
                 do_process_instruction(
-                    deposit_all_token_types(
+                    deposit_single_token_type_exact_amount_in(
                         &SWAP_PROGRAM_ID,
                         &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
                         &user_transfer_authority_key,
                         &token_a_key,
-                        &token_b_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
                         &accounts.pool_mint_key,
                         &pool_key,
-                        DepositAllTokenTypes {
-                            pool_token_amount: pool_amount.try_into().unwrap(),
-                            maximum_token_a_amount: deposit_a,
-                            maximum_token_b_amount: deposit_b,
+                        DepositSingleTokenTypeExactAmountIn {
+                            source_token_amount: deposit_a,
+                            minimum_pool_token_amount: pool_amount,
                         },
                     )
                     .unwrap(),
@@ -3112,7 +6026,6 @@ mod tests {
                         &mut Account::default(),
                         &mut Account::default(),
                         &mut token_a_account,
-                        &mut token_b_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
@@ -3128,8 +6041,8 @@ mod tests {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -3137,22 +6050,20 @@ mod tests {
             assert_eq!(
                 Err(SwapError::IncorrectTokenProgramId.into()),
                 do_process_instruction(
-                    deposit_all_token_types(
+                    deposit_single_token_type_exact_amount_in(
                         &SWAP_PROGRAM_ID,
                         &wrong_key,
                         &accounts.swap_key,
                         &accounts.authority_key,
                         &accounts.authority_key,
                         &token_a_key,
-                        &token_b_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
                         &accounts.pool_mint_key,
                         &pool_key,
-                        DepositAllTokenTypes {
-                            pool_token_amount: pool_amount.try_into().unwrap(),
-                            maximum_token_a_amount: deposit_a,
-                            maximum_token_b_amount: deposit_b,
+                        DepositSingleTokenTypeExactAmountIn {
+                            source_token_amount: deposit_a,
+                            minimum_pool_token_amount: pool_amount,
                         },
                     )
                     .unwrap(),
@@ -3161,7 +6072,6 @@ mod tests {
                         &mut Account::default(),
                         &mut Account::default(),
                         &mut token_a_account,
-                        &mut token_b_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
@@ -3178,7 +6088,7 @@ mod tests {
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
-                mut token_b_account,
+                token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -3192,17 +6102,14 @@ mod tests {
             // wrong swap token a account
             assert_eq!(
                 Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
 
@@ -3213,22 +6120,19 @@ mod tests {
             let old_b_account = accounts.token_b_account;
 
             accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
+            accounts.token_b_account = token_b_account;
 
             // wrong swap token b account
             assert_eq!(
                 Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
 
@@ -3241,8 +6145,8 @@ mod tests {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
@@ -3255,17 +6159,14 @@ mod tests {
 
             assert_eq!(
                 Err(SwapError::IncorrectPoolMint.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a,
-                    deposit_b,
+                    pool_amount,
                 )
             );
 
@@ -3273,33 +6174,6 @@ mod tests {
             accounts.pool_mint_account = old_pool_account;
         }
 
-        // deposit 1 pool token fails beacuse it equates to 0 swap tokens
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.deposit_all_token_types(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    1,
-                    deposit_a,
-                    deposit_b,
-                )
-            );
-        }
-
         // slippage exceeded
         {
             let (
@@ -3310,36 +6184,30 @@ mod tests {
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // maximum A amount in too low
+            // minimum pool amount too high
             assert_eq!(
                 Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
                     deposit_a / 10,
-                    deposit_b,
+                    pool_amount,
                 )
             );
-            // maximum B amount in too low
+            // minimum pool amount too high
             assert_eq!(
                 Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
                     deposit_b / 10,
+                    pool_amount,
                 )
             );
         }
@@ -3361,17 +6229,26 @@ mod tests {
             let authority_key = accounts.authority_key;
             assert_eq!(
                 Err(SwapError::InvalidInput.into()),
-                accounts.deposit_all_token_types(
+                accounts.deposit_single_token_type_exact_amount_in(
                     &authority_key,
                     &swap_token_a_key,
                     &mut swap_token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+            );
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_single_token_type_exact_amount_in(
+                    &authority_key,
                     &swap_token_b_key,
                     &mut swap_token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
                     deposit_b,
+                    pool_amount,
                 )
             );
         }
@@ -3387,30 +6264,42 @@ mod tests {
                 mut pool_account,
             ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
             accounts
-                .deposit_all_token_types(
+                .deposit_single_token_type_exact_amount_in(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    pool_amount,
+                )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+
+            accounts
+                .deposit_single_token_type_exact_amount_in(
                     &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
                     &pool_key,
                     &mut pool_account,
-                    pool_amount.try_into().unwrap(),
-                    deposit_a,
                     deposit_b,
+                    pool_amount,
                 )
                 .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
             let swap_token_b =
                 spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
             assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 0);
+
             let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
             assert_eq!(token_b.amount, 0);
+
             let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
             let swap_pool_account =
                 spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
@@ -3424,7 +6313,7 @@ mod tests {
     }
 
     #[test]
-    fn test_withdraw() {
+    fn test_withdraw_one_exact_out() {
         let user_key = Pubkey::new_unique();
         let trade_fee_numerator = 1;
         let trade_fee_denominator = 2;
@@ -3446,8 +6335,8 @@ mod tests {
             host_fee_denominator,
         };
 
-        let token_a_amount = 1000;
-        let token_b_amount = 2000;
+        let token_a_amount = 100_000;
+        let token_b_amount = 200_000;
         let curve_type = CurveType::ConstantProduct;
         let swap_curve = SwapCurve {
             curve_type,
@@ -3458,463 +6347,104 @@ mod tests {
         let initial_a = token_a_amount / 10;
         let initial_b = token_b_amount / 10;
         let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
-        let withdraw_amount = initial_pool / 4;
-        let minimum_token_a_amount = initial_a / 40;
-        let minimum_token_b_amount = initial_b / 40;
-
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-        }
-
-        accounts.initialize_swap().unwrap();
-
-        // wrong owner for swap account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
-            assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-            accounts.swap_account = old_swap_account;
-        }
-
-        // wrong nonce for authority_key
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
-            );
-            accounts.authority_key = bad_authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-            accounts.authority_key = old_authority;
-        }
-
-        // not enough pool tokens
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                to_u64(withdraw_amount).unwrap() / 2u64,
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount / 2,
-                    minimum_token_b_amount / 2,
-                )
-            );
-        }
-
-        // wrong token a / b accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-        }
-
-        // wrong pool token account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let (
-                wrong_token_a_key,
-                mut wrong_token_a_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                withdraw_amount.try_into().unwrap(),
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &wrong_token_a_key,
-                    &mut wrong_token_a_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-            );
-        }
-
-        // wrong pool fee account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
+        let maximum_pool_token_amount = to_u64(initial_pool / 4).unwrap();
+        let destination_a_amount = initial_a / 40;
+        let destination_b_amount = initial_b / 40;
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+
+        // swap not initialized
+        {
             let (
-                _token_a_key,
-                _token_a_account,
+                token_a_key,
+                mut token_a_account,
                 _token_b_key,
                 _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let old_pool_fee_account = accounts.pool_fee_account;
-            let old_pool_fee_key = accounts.pool_fee_key;
-            accounts.pool_fee_account = wrong_pool_account;
-            accounts.pool_fee_key = wrong_pool_key;
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.withdraw_all_token_types(
+                Err(ProgramError::UninitializedAccount),
+                accounts.withdraw_single_token_type_exact_amount_out(
                     &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                ),
-            );
-            accounts.pool_fee_account = old_pool_fee_account;
-            accounts.pool_fee_key = old_pool_fee_key;
-        }
-
-        // no approval
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                0,
-                0,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let user_transfer_authority_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    withdraw_all_token_types(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &token_b_key,
-                        WithdrawAllTokenTypes {
-                            pool_token_amount: withdraw_amount.try_into().unwrap(),
-                            minimum_token_a_amount,
-                            minimum_token_b_amount,
-                        }
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
         }
 
-        // wrong token program id
+        accounts.initialize_swap().unwrap();
+
+        // wrong owner for swap account
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount.try_into().unwrap(),
-            );
-            let wrong_key = Pubkey::new_unique();
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    withdraw_all_token_types(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &token_b_key,
-                        WithdrawAllTokenTypes {
-                            pool_token_amount: withdraw_amount.try_into().unwrap(),
-                            minimum_token_a_amount,
-                            minimum_token_b_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
+                Err(ProgramError::IncorrectProgramId),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
+            accounts.swap_account = old_swap_account;
         }
 
-        // wrong swap token accounts
+        // wrong nonce for authority_key
         {
             let (
-                token_a_key,
-                mut token_a_account,
+                _token_a_key,
+                _token_a_account,
                 token_b_key,
                 mut token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
-
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
-
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
             );
-
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
-
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
-
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
-
-            // wrong swap token b account
+            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_all_token_types(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
                     &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
-
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
+            accounts.authority_key = old_authority;
         }
 
-        // wrong mint
+        // not enough pool tokens
         {
             let (
-                token_a_key,
-                mut token_a_account,
+                _token_a_key,
+                _token_a_account,
                 token_b_key,
                 mut token_b_account,
                 pool_key,
@@ -3924,75 +6454,73 @@ mod tests {
                 &withdrawer_key,
                 initial_a,
                 initial_b,
-                initial_pool.try_into().unwrap(),
+                maximum_pool_token_amount / 1000,
             );
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
-
             assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.withdraw_all_token_types(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
                     &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
-            );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
+            );
         }
 
-        // withdrawing 1 pool token fails because it equates to 0 output tokens
+        // wrong pool token account
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
+                _pool_key,
+                _pool_account,
             ) = accounts.setup_token_accounts(
                 &user_key,
                 &withdrawer_key,
-                initial_a,
+                maximum_pool_token_amount,
                 initial_b,
-                initial_pool.try_into().unwrap(),
+                maximum_pool_token_amount,
             );
             assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.withdraw_all_token_types(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
                     &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    1,
-                    0,
-                    0,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
         }
 
-        // slippage exceeded
+        // wrong pool fee account
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+            let (
+                _token_a_key,
+                _token_a_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(
@@ -4000,101 +6528,89 @@ mod tests {
                 &withdrawer_key,
                 initial_a,
                 initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            // minimum A amount out too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount * 10,
-                    minimum_token_b_amount,
-                )
+                maximum_pool_token_amount,
             );
-            // minimum B amount out too high
+            let old_pool_fee_account = accounts.pool_fee_account;
+            let old_pool_fee_key = accounts.pool_fee_key;
+            accounts.pool_fee_account = wrong_pool_account;
+            accounts.pool_fee_key = wrong_pool_key;
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_all_token_types(
+                Err(SwapError::IncorrectFeeAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
                     &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount * 10,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
+            accounts.pool_fee_account = old_pool_fee_account;
+            accounts.pool_fee_key = old_pool_fee_key;
         }
 
-        // invalid input: can't use swap pool tokens as destination
+        // no approval
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(
                 &user_key,
                 &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
+                0,
+                0,
+                maximum_pool_token_amount,
             );
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            let user_transfer_authority_key = Pubkey::new_unique();
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw_single_token_type_exact_amount_out(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        WithdrawSingleTokenTypeExactAmountOut {
+                            destination_token_amount: destination_a_amount,
+                            maximum_pool_token_amount,
+                        }
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                    ],
                 )
             );
         }
 
-        // correct withdrawal
+        // wrong token program id
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(
@@ -4102,174 +6618,111 @@ mod tests {
                 &withdrawer_key,
                 initial_a,
                 initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            accounts
-                .withdraw_all_token_types(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount.try_into().unwrap(),
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                )
-                .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            let withdraw_fee = accounts.fees.owner_withdraw_fee(withdraw_amount).unwrap();
-            let results = accounts
-                .swap_curve
-                .calculator
-                .pool_tokens_to_trading_tokens(
-                    withdraw_amount - withdraw_fee,
-                    pool_mint.supply.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    RoundDirection::Floor,
-                )
-                .unwrap();
-            assert_eq!(
-                swap_token_a.amount,
-                token_a_amount - to_u64(results.token_a_amount).unwrap()
-            );
-            assert_eq!(
-                swap_token_b.amount,
-                token_b_amount - to_u64(results.token_b_amount).unwrap()
-            );
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(
-                token_a.amount,
-                initial_a + to_u64(results.token_a_amount).unwrap()
-            );
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(
-                token_b.amount,
-                initial_b + to_u64(results.token_b_amount).unwrap()
-            );
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            assert_eq!(
-                pool_account.amount,
-                to_u64(initial_pool - withdraw_amount).unwrap()
+                maximum_pool_token_amount,
             );
-            let fee_account =
-                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+            let wrong_key = Pubkey::new_unique();
             assert_eq!(
-                fee_account.amount,
-                TryInto::<u64>::try_into(withdraw_fee).unwrap()
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                do_process_instruction(
+                    withdraw_single_token_type_exact_amount_out(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        WithdrawSingleTokenTypeExactAmountOut {
+                            destination_token_amount: destination_a_amount,
+                            maximum_pool_token_amount,
+                        }
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                    ],
+                )
             );
         }
 
-        // correct withdrawal from fee account
+        // wrong swap token accounts
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
 
-            let pool_fee_key = accounts.pool_fee_key;
-            let mut pool_fee_account = accounts.pool_fee_account.clone();
-            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
-            let pool_fee_amount = fee_account.amount;
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
 
-            accounts
-                .withdraw_all_token_types(
-                    &user_key,
-                    &pool_fee_key,
-                    &mut pool_fee_account,
+            accounts.token_a_key = token_a_key;
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    pool_fee_amount,
-                    0,
-                    0,
-                )
-                .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            let results = accounts
-                .swap_curve
-                .calculator
-                .pool_tokens_to_trading_tokens(
-                    pool_fee_amount.try_into().unwrap(),
-                    pool_mint.supply.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    RoundDirection::Floor,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
-                .unwrap();
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(
-                token_a.amount,
-                TryInto::<u64>::try_into(results.token_a_amount).unwrap()
-            );
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(
-                token_b.amount,
-                TryInto::<u64>::try_into(results.token_b_amount).unwrap()
             );
-        }
-    }
 
-    #[test]
-    fn test_deposit_one_exact_in() {
-        let user_key = Pubkey::new_unique();
-        let depositor_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 20;
-        let host_fee_denominator = 100;
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
 
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
 
-        let token_a_amount = 1000;
-        let token_b_amount = 9000;
-        let curve_type = CurveType::ConstantProduct;
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Box::new(ConstantProductCurve {}),
-        };
+            accounts.token_b_key = token_b_key;
+            accounts.token_b_account = token_b_account.clone();
 
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
+                )
+            );
 
-        let deposit_a = token_a_amount / 10;
-        let deposit_b = token_b_amount / 10;
-        let pool_amount = to_u64(INITIAL_SWAP_POOL_AMOUNT / 100).unwrap();
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
 
-        // swap not initialized
+        // wrong mint
         {
             let (
                 token_a_key,
@@ -4278,483 +6731,676 @@ mod tests {
                 _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
+            );
+            let (pool_mint_key, pool_mint_account) =
+                create_mint(&spl_token::id(), &accounts.authority_key, None);
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
+                Err(SwapError::IncorrectPoolMint.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    deposit_a,
-                    pool_amount,
+                    &token_a_key,
+                    &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
             );
-        }
 
-        accounts.initialize_swap().unwrap();
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
 
-        // wrong owner for swap account
+        // slippage exceeded
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
+                token_b_key,
+                mut token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
+            );
+
+            // maximum pool token amount too low
             assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
                     &token_a_key,
                     &mut token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount / 1000,
+                )
+            );
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    deposit_a,
-                    pool_amount,
+                    &token_b_key,
+                    &mut token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount / 1000,
                 )
             );
-            accounts.swap_account = old_swap_account;
         }
 
-        // wrong nonce for authority_key
+        // invalid input: can't use swap pool tokens as destination
         {
             let (
-                token_a_key,
-                mut token_a_account,
+                _token_a_key,
+                _token_a_account,
                 _token_b_key,
                 _token_b_account,
                 pool_key,
                 mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                maximum_pool_token_amount,
             );
-            accounts.authority_key = bad_authority_key;
+            let swap_token_a_key = accounts.token_a_key;
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    deposit_a,
-                    pool_amount,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
+                )
+            );
+            let swap_token_b_key = accounts.token_b_key;
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    destination_b_amount,
+                    maximum_pool_token_amount,
                 )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // not enough token A / B
+        // correct withdrawal
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                token_b_key,
-                mut token_b_account,
+                _token_b_key,
+                _token_b_account,
                 pool_key,
                 mut pool_account,
             ) = accounts.setup_token_accounts(
                 &user_key,
-                &depositor_key,
-                deposit_a / 2,
-                deposit_b / 2,
-                0,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool.try_into().unwrap(),
             );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    0,
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+            let swap_token_b =
+                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+            let pool_mint =
+                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+
+            let pool_token_amount = accounts
+                .swap_curve
+                .withdraw_single_token_type_exact_out(
+                    destination_a_amount.try_into().unwrap(),
+                    swap_token_a.amount.try_into().unwrap(),
+                    swap_token_b.amount.try_into().unwrap(),
+                    pool_mint.supply.try_into().unwrap(),
+                    TradeDirection::AtoB,
+                    &accounts.fees,
                 )
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
+                .unwrap();
+            let withdraw_fee = accounts.fees.owner_withdraw_fee(pool_token_amount).unwrap();
+
+            accounts
+                .withdraw_single_token_type_exact_amount_out(
+                    &withdrawer_key,
                     &pool_key,
                     &mut pool_account,
-                    deposit_b,
-                    0,
-                )
-            );
-        }
-
-        // wrong pool token account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    deposit_a,
-                    pool_amount,
+                    destination_a_amount,
+                    maximum_pool_token_amount,
                 )
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            assert_eq!(swap_token_a.amount, token_a_amount - destination_a_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a + destination_a_amount);
+
+            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+            assert_eq!(
+                pool_account.amount,
+                to_u64(initial_pool - pool_token_amount - withdraw_fee).unwrap()
             );
+            let fee_account =
+                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+            assert_eq!(fee_account.amount, to_u64(withdraw_fee).unwrap());
         }
 
-        // no approval
+        // correct withdrawal from fee account
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 _token_b_key,
                 _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let user_transfer_authority_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    deposit_single_token_type_exact_amount_in(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositSingleTokenTypeExactAmountIn {
-                            source_token_amount: deposit_a,
-                            minimum_pool_token_amount: pool_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut Account::default(),
-                    ],
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+
+            let fee_a_amount = 2;
+            let pool_fee_key = accounts.pool_fee_key;
+            let mut pool_fee_account = accounts.pool_fee_account.clone();
+            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
+            let pool_fee_amount = fee_account.amount;
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            let token_a_amount = swap_token_a.amount;
+            accounts
+                .withdraw_single_token_type_exact_amount_out(
+                    &user_key,
+                    &pool_fee_key,
+                    &mut pool_fee_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    fee_a_amount,
+                    pool_fee_amount,
                 )
-            );
+                .unwrap();
+
+            let swap_token_a =
+                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+
+            assert_eq!(swap_token_a.amount, token_a_amount - fee_a_amount);
+            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a + fee_a_amount);
         }
+    }
+
+    fn check_valid_swap_curve(
+        fees: Fees,
+        curve_type: CurveType,
+        calculator: Box<dyn CurveCalculator>,
+        token_a_amount: u64,
+        token_b_amount: u64,
+    ) {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        accounts.initialize_swap().unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+        // swap one way
+        let a_to_b_amount = initial_a / 10;
+        let minimum_token_b_amount = 0;
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let initial_supply = pool_mint.supply;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
+            .unwrap();
+
+        let results = swap_curve
+            .swap(
+                a_to_b_amount.try_into().unwrap(),
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        let token_a_amount = swap_token_a.amount;
+        assert_eq!(
+            token_a_amount,
+            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
+        );
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, initial_a - a_to_b_amount);
+
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        let token_b_amount = swap_token_b.amount;
+        assert_eq!(
+            token_b_amount,
+            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
+        );
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + to_u64(results.destination_amount_swapped).unwrap()
+        );
 
-        // wrong token program id
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let wrong_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
-                do_process_instruction(
-                    deposit_single_token_type_exact_amount_in(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        DepositSingleTokenTypeExactAmountIn {
-                            source_token_amount: deposit_a,
-                            minimum_pool_token_amount: pool_amount,
-                        },
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut Account::default(),
-                    ],
-                )
-            );
-        }
+        let first_fee = swap_curve
+            .withdraw_single_token_type_exact_out(
+                results.owner_fee,
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                initial_supply.try_into().unwrap(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        assert_eq!(
+            fee_account.amount,
+            TryInto::<u64>::try_into(first_fee).unwrap()
+        );
 
-        // wrong swap token accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+        let first_swap_amount = results.destination_amount_swapped;
 
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+        // swap the other way
+        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
+        let initial_supply = pool_mint.supply;
 
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
+        let b_to_a_amount = initial_b / 10;
+        let minimum_a_amount = 0;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                b_to_a_amount,
+                minimum_a_amount,
+            )
+            .unwrap();
 
-            // wrong swap token a account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
+        let results = swap_curve
+            .swap(
+                b_to_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                token_a_amount.try_into().unwrap(),
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        let token_a_amount = swap_token_a.amount;
+        assert_eq!(
+            token_a_amount,
+            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
+        );
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(
+            token_a.amount,
+            initial_a - a_to_b_amount + to_u64(results.destination_amount_swapped).unwrap()
+        );
 
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        let token_b_amount = swap_token_b.amount;
+        assert_eq!(
+            token_b_amount,
+            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
+        );
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + to_u64(first_swap_amount).unwrap()
+                - to_u64(results.source_amount_swapped).unwrap()
+        );
 
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account;
+        let second_fee = swap_curve
+            .withdraw_single_token_type_exact_out(
+                results.owner_fee,
+                token_a_amount.try_into().unwrap(),
+                token_b_amount.try_into().unwrap(),
+                initial_supply.try_into().unwrap(),
+                TradeDirection::BtoA,
+                &fees,
+            )
+            .unwrap();
+        let fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        assert_eq!(fee_account.amount, to_u64(first_fee + second_fee).unwrap());
+    }
 
-            // wrong swap token b account
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
+    #[test]
+    fn test_valid_swap_curves_all_fees() {
+        // All fees
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 100;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 10_000_000_000;
+        let token_b_amount = 50_000_000_000;
+
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantProduct,
+            Box::new(ConstantProductCurve {}),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_price = 1;
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantPrice,
+            Box::new(ConstantPriceCurve { token_b_price }),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_offset = 10_000_000_000;
+        check_valid_swap_curve(
+            fees,
+            CurveType::Offset,
+            Box::new(OffsetCurve { token_b_offset }),
+            token_a_amount,
+            token_b_amount,
+        );
+    }
+
+    #[test]
+    fn test_valid_swap_curves_trade_fee_only() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 0;
+        let owner_trade_fee_denominator = 0;
+        let owner_withdraw_fee_numerator = 0;
+        let owner_withdraw_fee_denominator = 0;
+        let host_fee_numerator = 0;
+        let host_fee_denominator = 0;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let token_a_amount = 10_000_000_000;
+        let token_b_amount = 50_000_000_000;
+
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantProduct,
+            Box::new(ConstantProductCurve {}),
+            token_a_amount,
+            token_b_amount,
+        );
+        let token_b_price = 10_000;
+        check_valid_swap_curve(
+            fees.clone(),
+            CurveType::ConstantPrice,
+            Box::new(ConstantPriceCurve { token_b_price }),
+            token_a_amount,
+            token_b_amount / token_b_price,
+        );
+        let token_b_offset = 1;
+        check_valid_swap_curve(
+            fees,
+            CurveType::Offset,
+            Box::new(OffsetCurve { token_b_offset }),
+            token_a_amount,
+            token_b_amount,
+        );
+    }
 
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
-        }
+    #[test]
+    fn test_valid_swap_with_fee_constraints() {
+        let owner_key = Pubkey::new_unique();
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 5_000_000;
 
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
-        }
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
-        // slippage exceeded
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // minimum pool amount too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a / 10,
-                    pool_amount,
-                )
-            );
-            // minimum pool amount too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b / 10,
-                    pool_amount,
-                )
-            );
-        }
+        let curve = ConstantProductCurve {};
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(curve),
+        };
 
-        // invalid input: can't use swap pool tokens as source
-        {
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            let authority_key = accounts.authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &authority_key,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-            );
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.deposit_single_token_type_exact_amount_in(
-                    &authority_key,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b,
-                    pool_amount,
-                )
-            );
-        }
+        let owner_key_str = &owner_key.to_string();
+        let valid_curve_types = &[CurveType::ConstantProduct];
+        let constraints = Some(SwapConstraints {
+            owner_key: owner_key_str,
+            valid_curve_types,
+            fees: &fees,
+            dynamic_fees: None,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &owner_key,
+            fees.clone(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
 
-        // correctly deposit
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            accounts
-                .deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    pool_amount,
-                )
-                .unwrap();
+        // initialize swap
+        do_process_instruction_with_fee_constraints(
+            initialize(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &accounts.token_a_key,
+                &accounts.token_b_key,
+                &accounts.pool_mint_key,
+                &accounts.pool_fee_key,
+                &accounts.pool_token_key,
+                accounts.nonce,
+                accounts.fees.clone(),
+                accounts.swap_curve.clone(),
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.pool_token_account,
+                &mut Account::default(),
+            ],
+            &constraints,
+        )
+        .unwrap();
 
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+        let authority_key = accounts.authority_key;
 
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 0);
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &owner_key,
+            &authority_key,
+            token_a_amount,
+            token_b_amount,
+            0,
+        );
 
-            accounts
-                .deposit_single_token_type_exact_amount_in(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_b,
-                    pool_amount,
-                )
-                .unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+        let amount_in = token_a_amount / 2;
+        let minimum_amount_out = 0;
 
-            let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 0);
+        // perform the swap
+        do_process_instruction_with_fee_constraints(
+            swap(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                &token_a_key,
+                &accounts.token_a_key,
+                &accounts.token_b_key,
+                &token_b_key,
+                &accounts.pool_mint_key,
+                &accounts.pool_fee_key,
+                Some(&pool_key),
+                Swap {
+                    amount_in,
+                    minimum_amount_out,
+                },
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut token_a_account,
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut Account::default(),
+                &mut pool_account,
+            ],
+            &constraints,
+        )
+        .unwrap();
 
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            let swap_pool_account =
-                spl_token::state::Account::unpack(&accounts.pool_token_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-            assert_eq!(
-                pool_mint.supply,
-                pool_account.amount + swap_pool_account.amount
-            );
-        }
+        // check that fees were taken in the host fee account
+        let host_fee_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
+        let owner_fee_account =
+            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
+        let total_fee = owner_fee_account.amount * host_fee_denominator
+            / (host_fee_denominator - host_fee_numerator);
+        assert_eq!(
+            total_fee,
+            host_fee_account.amount + owner_fee_account.amount
+        );
     }
 
     #[test]
-    fn test_withdraw_one_exact_out() {
+    fn test_invalid_swap() {
         let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
         let trade_fee_numerator = 1;
-        let trade_fee_denominator = 2;
+        let trade_fee_denominator = 4;
         let owner_trade_fee_numerator = 1;
         let owner_trade_fee_denominator = 10;
         let owner_withdraw_fee_numerator = 1;
         let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 7;
+        let host_fee_numerator = 9;
         let host_fee_denominator = 100;
-
         let fees = Fees {
             trade_fee_numerator,
             trade_fee_denominator,
@@ -4766,142 +7412,52 @@ mod tests {
             host_fee_denominator,
         };
 
-        let token_a_amount = 100_000;
-        let token_b_amount = 200_000;
+        let token_a_amount = 1000;
+        let token_b_amount = 5000;
         let curve_type = CurveType::ConstantProduct;
         let swap_curve = SwapCurve {
             curve_type,
             calculator: Box::new(ConstantProductCurve {}),
         };
-
-        let withdrawer_key = Pubkey::new_unique();
-        let initial_a = token_a_amount / 10;
-        let initial_b = token_b_amount / 10;
-        let initial_pool = swap_curve.calculator.new_pool_supply() / 10;
-        let maximum_pool_token_amount = to_u64(initial_pool / 4).unwrap();
-        let destination_a_amount = initial_a / 40;
-        let destination_b_amount = initial_b / 40;
-
         let mut accounts =
             SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
-            );
-        }
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let minimum_token_b_amount = initial_b / 2;
 
-        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
 
-        // wrong owner for swap account
+        // swap not initialized
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
-            assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
-                )
-            );
-            accounts.swap_account = old_swap_account;
-        }
-
-        // wrong nonce for authority_key
-        {
-            let (
-                _token_a_key,
-                _token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
-            );
-            accounts.authority_key = bad_authority_key;
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(ProgramError::UninitializedAccount),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // not enough pool tokens
-        {
-            let (
-                _token_a_key,
-                _token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount / 1000,
-            );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
-                )
-            );
-        }
+        accounts.initialize_swap().unwrap();
 
-        // wrong pool token account
+        // wrong swap account program id
         {
             let (
                 token_a_key,
@@ -4910,265 +7466,247 @@ mod tests {
                 mut token_b_account,
                 _pool_key,
                 _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                maximum_pool_token_amount,
-                initial_b,
-                maximum_pool_token_amount,
-            );
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let old_swap_account = accounts.swap_account;
+            let mut wrong_swap_account = old_swap_account.clone();
+            wrong_swap_account.owner = spl_token::id();
+            accounts.swap_account = wrong_swap_account;
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
+                Err(ProgramError::IncorrectProgramId),
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
-        }
-
-        // wrong pool fee account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-            let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
+            accounts.swap_account = old_swap_account;
+        }
+
+        // wrong nonce
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &spl_token::id(),
             );
-            let old_pool_fee_account = accounts.pool_fee_account;
-            let old_pool_fee_key = accounts.pool_fee_key;
-            accounts.pool_fee_account = wrong_pool_account;
-            accounts.pool_fee_key = wrong_pool_key;
+            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
-            accounts.pool_fee_account = old_pool_fee_account;
-            accounts.pool_fee_key = old_pool_fee_key;
+            accounts.authority_key = old_authority;
         }
 
-        // no approval
+        // wrong token program id
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                0,
-                0,
-                maximum_pool_token_amount,
-            );
-            let user_transfer_authority_key = Pubkey::new_unique();
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let wrong_program_id = Pubkey::new_unique();
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
+                Err(SwapError::IncorrectTokenProgramId.into()),
                 do_process_instruction(
-                    withdraw_single_token_type_exact_amount_out(
+                    swap(
                         &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
+                        &wrong_program_id,
                         &accounts.swap_key,
                         &accounts.authority_key,
-                        &user_transfer_authority_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        &pool_key,
+                        &accounts.authority_key,
+                        &token_a_key,
                         &accounts.token_a_key,
                         &accounts.token_b_key,
-                        &token_a_key,
-                        WithdrawSingleTokenTypeExactAmountOut {
-                            destination_token_amount: destination_a_amount,
-                            maximum_pool_token_amount,
-                        }
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
+                        &mut token_a_account,
                         &mut accounts.token_a_account,
                         &mut accounts.token_b_account,
-                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
                         &mut accounts.pool_fee_account,
                         &mut Account::default(),
                     ],
-                )
+                ),
             );
         }
 
-        // wrong token program id
+        // not enough token a to swap
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a * 2,
+                    minimum_token_b_amount * 2,
+                )
             );
-            let wrong_key = Pubkey::new_unique();
+        }
+
+        // wrong swap token A / B accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let user_transfer_key = Pubkey::new_unique();
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
+                Err(SwapError::IncorrectSwapAccount.into()),
                 do_process_instruction(
-                    withdraw_single_token_type_exact_amount_out(
+                    swap(
                         &SWAP_PROGRAM_ID,
-                        &wrong_key,
+                        &spl_token::id(),
                         &accounts.swap_key,
                         &accounts.authority_key,
-                        &accounts.authority_key,
+                        &user_transfer_key,
+                        &token_a_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &token_b_key,
                         &accounts.pool_mint_key,
                         &accounts.pool_fee_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        WithdrawSingleTokenTypeExactAmountOut {
-                            destination_token_amount: destination_a_amount,
-                            maximum_pool_token_amount,
-                        }
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
+                        &mut token_a_account.clone(),
                         &mut token_a_account,
+                        &mut token_b_account.clone(),
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
                         &mut accounts.pool_fee_account,
                         &mut Account::default(),
                     ],
-                )
+                ),
             );
         }
 
-        // wrong swap token accounts
+        // wrong user token A / B accounts
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
-
-            accounts.token_a_key = token_a_key;
-            accounts.token_a_account = token_a_account.clone();
-
-            // wrong swap token a account
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(TokenError::MintMismatch.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_a_key,
                     &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
+        }
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
-
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
-
-            accounts.token_b_key = token_b_key;
-            accounts.token_b_account = token_b_account.clone();
-
-            // wrong swap token b account
+        // swap from a to a
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account.clone(),
+                    &swap_token_a_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
-            );
-
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
+            );
         }
 
-        // wrong mint
+        // incorrect mint provided
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             let (pool_mint_key, pool_mint_account) =
                 create_mint(&spl_token::id(), &accounts.authority_key, None);
             let old_pool_key = accounts.pool_mint_key;
@@ -5178,14 +7716,16 @@ mod tests {
 
             assert_eq!(
                 Err(SwapError::IncorrectPoolMint.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
 
@@ -5193,232 +7733,365 @@ mod tests {
             accounts.pool_mint_account = old_pool_account;
         }
 
-        // slippage exceeded
+        // incorrect fee account provided
         {
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
-            );
-
-            // maximum pool token amount too low
+                wrong_pool_key,
+                wrong_pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let old_pool_fee_account = accounts.pool_fee_account;
+            let old_pool_fee_key = accounts.pool_fee_key;
+            accounts.pool_fee_account = wrong_pool_account;
+            accounts.pool_fee_key = wrong_pool_key;
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::IncorrectFeeAccount.into()),
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount / 1000,
-                )
-            );
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount / 1000,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
             );
+            accounts.pool_fee_account = old_pool_fee_account;
+            accounts.pool_fee_key = old_pool_fee_key;
         }
 
-        // invalid input: can't use swap pool tokens as destination
+        // no approval
         {
             let (
-                _token_a_key,
-                _token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                maximum_pool_token_amount,
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let user_transfer_key = Pubkey::new_unique();
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &user_transfer_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        None,
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: minimum_token_b_amount,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                    ],
+                ),
             );
-            let swap_token_a_key = accounts.token_a_key;
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+        }
+
+        // output token value 0
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::ZeroTradingTokens.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_b_key,
                     &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
+                    &token_a_key,
+                    &mut token_a_account,
+                    1,
+                    1,
                 )
             );
-            let swap_token_b_key = accounts.token_b_key;
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+        }
+
+        // slippage exceeded: minimum out amount too high
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
                     &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    destination_b_amount,
-                    maximum_pool_token_amount,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount * 2,
                 )
             );
         }
 
-        // correct withdrawal
+        // invalid input: can't use swap pool as user source / dest
         {
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool.try_into().unwrap(),
-            );
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-            let swap_token_b =
-                spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-            let pool_mint =
-                spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-
-            let pool_token_amount = accounts
-                .swap_curve
-                .withdraw_single_token_type_exact_out(
-                    destination_a_amount.try_into().unwrap(),
-                    swap_token_a.amount.try_into().unwrap(),
-                    swap_token_b.amount.try_into().unwrap(),
-                    pool_mint.supply.try_into().unwrap(),
-                    TradeDirection::AtoB,
-                    &accounts.fees,
-                )
-                .unwrap();
-            let withdraw_fee = accounts.fees.owner_withdraw_fee(pool_token_amount).unwrap();
-
-            accounts
-                .withdraw_single_token_type_exact_amount_out(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
+            let authority_key = accounts.authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &authority_key,
+                    &swap_token_a_key,
+                    &mut swap_token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
+                )
+            );
+            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
-                    destination_a_amount,
-                    maximum_pool_token_amount,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &swap_token_b_key,
+                    &mut swap_token_b_account,
+                    initial_a,
+                    minimum_token_b_amount,
                 )
-                .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-
-            assert_eq!(swap_token_a.amount, token_a_amount - destination_a_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, initial_a + destination_a_amount);
-
-            let pool_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-            assert_eq!(
-                pool_account.amount,
-                to_u64(initial_pool - pool_token_amount - withdraw_fee).unwrap()
             );
-            let fee_account =
-                spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-            assert_eq!(fee_account.amount, to_u64(withdraw_fee).unwrap());
         }
 
-        // correct withdrawal from fee account
+        // still correct: constraint specified, no host fee account
         {
+            let authority_key = accounts.authority_key;
             let (
                 token_a_key,
                 mut token_a_account,
-                _token_b_key,
-                _token_b_account,
+                token_b_key,
+                mut token_b_account,
                 _pool_key,
                 _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-
-            let fee_a_amount = 2;
-            let pool_fee_key = accounts.pool_fee_key;
-            let mut pool_fee_account = accounts.pool_fee_account.clone();
-            let fee_account = spl_token::state::Account::unpack(&pool_fee_account.data).unwrap();
-            let pool_fee_amount = fee_account.amount;
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-
-            let token_a_amount = swap_token_a.amount;
-            accounts
-                .withdraw_single_token_type_exact_amount_out(
-                    &user_key,
-                    &pool_fee_key,
-                    &mut pool_fee_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let owner_key = &swapper_key.to_string();
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types: &[],
+                fees: &fees,
+                dynamic_fees: None,
+            });
+            do_process_instruction_with_fee_constraints(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.authority_key,
                     &token_a_key,
-                    &mut token_a_account,
-                    fee_a_amount,
-                    pool_fee_amount,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    None,
+                    Swap {
+                        amount_in: initial_a,
+                        minimum_amount_out: minimum_token_b_amount,
+                    },
                 )
-                .unwrap();
-
-            let swap_token_a =
-                spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut token_a_account,
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut Account::default(),
+                ],
+                &constraints,
+            )
+            .unwrap();
+        }
 
-            assert_eq!(swap_token_a.amount, token_a_amount - fee_a_amount);
-            let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, initial_a + fee_a_amount);
+        // invalid mint for host fee account
+        {
+            let authority_key = accounts.authority_key;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let (
+                bad_token_a_key,
+                mut bad_token_a_account,
+                _token_b_key,
+                mut _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
+            let owner_key = &swapper_key.to_string();
+            let fees = Fees {
+                trade_fee_numerator,
+                trade_fee_denominator,
+                owner_trade_fee_numerator,
+                owner_trade_fee_denominator,
+                owner_withdraw_fee_numerator,
+                owner_withdraw_fee_denominator,
+                host_fee_numerator,
+                host_fee_denominator,
+            };
+            let constraints = Some(SwapConstraints {
+                owner_key,
+                valid_curve_types: &[],
+                fees: &fees,
+                dynamic_fees: None,
+            });
+            assert_eq!(
+                Err(SwapError::IncorrectPoolMint.into()),
+                do_process_instruction_with_fee_constraints(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.pool_mint_key,
+                        &accounts.pool_fee_key,
+                        Some(&bad_token_a_key),
+                        Swap {
+                            amount_in: initial_a,
+                            minimum_amount_out: 0,
+                        },
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut accounts.pool_fee_account,
+                        &mut Account::default(),
+                        &mut bad_token_a_account,
+                    ],
+                    &constraints,
+                ),
+            );
         }
     }
 
-    fn check_valid_swap_curve(
-        fees: Fees,
-        curve_type: CurveType,
-        calculator: Box<dyn CurveCalculator>,
-        token_a_amount: u64,
-        token_b_amount: u64,
-    ) {
-        let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
+    #[test]
+    fn test_overdraw_offset_curve() {
+        let trade_fee_numerator = 1;
+        let trade_fee_denominator = 10;
+        let owner_trade_fee_numerator = 1;
+        let owner_trade_fee_denominator = 30;
+        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_denominator = 30;
+        let host_fee_numerator = 10;
+        let host_fee_denominator = 100;
+
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 0;
+        let fees = Fees {
+            trade_fee_numerator,
+            trade_fee_denominator,
+            owner_trade_fee_numerator,
+            owner_trade_fee_denominator,
+            owner_withdraw_fee_numerator,
+            owner_withdraw_fee_denominator,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
 
+        let token_b_offset = 2_000_000;
         let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
+            curve_type: CurveType::Offset,
+            calculator: Box::new(OffsetCurve { token_b_offset }),
         };
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-        let mut accounts = SwapAccountInfo::new(
-            &user_key,
-            fees.clone(),
-            swap_curve.clone(),
-            token_a_amount,
-            token_b_amount,
-        );
-        let initial_a = token_a_amount / 5;
-        let initial_b = token_b_amount / 5;
         accounts.initialize_swap().unwrap();
 
         let swap_token_a_key = accounts.token_a_key;
         let swap_token_b_key = accounts.token_b_key;
+        let initial_a = 500_000;
+        let initial_b = 1_000;
 
         let (
             token_a_key,
@@ -5428,13 +8101,14 @@ mod tests {
             _pool_key,
             _pool_account,
         ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-        // swap one way
-        let a_to_b_amount = initial_a / 10;
+
+        // swap a to b way, fails, there's no liquidity
+        let a_to_b_amount = initial_a;
         let minimum_token_b_amount = 0;
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        let initial_supply = pool_mint.supply;
-        accounts
-            .swap(
+
+        assert_eq!(
+            Err(SwapError::ZeroTradingTokens.into()),
+            accounts.swap(
                 &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
@@ -5445,66 +8119,11 @@ mod tests {
                 a_to_b_amount,
                 minimum_token_b_amount,
             )
-            .unwrap();
-
-        let results = swap_curve
-            .swap(
-                a_to_b_amount.try_into().unwrap(),
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                TradeDirection::AtoB,
-                &fees,
-            )
-            .unwrap();
-
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        let token_a_amount = swap_token_a.amount;
-        assert_eq!(
-            token_a_amount,
-            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
-        );
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, initial_a - a_to_b_amount);
-
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        let token_b_amount = swap_token_b.amount;
-        assert_eq!(
-            token_b_amount,
-            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
-        );
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(
-            token_b.amount,
-            initial_b + to_u64(results.destination_amount_swapped).unwrap()
-        );
-
-        let first_fee = swap_curve
-            .withdraw_single_token_type_exact_out(
-                results.owner_fee,
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                initial_supply.try_into().unwrap(),
-                TradeDirection::AtoB,
-                &fees,
-            )
-            .unwrap();
-        let fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        assert_eq!(
-            fee_account.amount,
-            TryInto::<u64>::try_into(first_fee).unwrap()
         );
 
-        let first_swap_amount = results.destination_amount_swapped;
-
-        // swap the other way
-        let pool_mint = spl_token::state::Mint::unpack(&accounts.pool_mint_account.data).unwrap();
-        let initial_supply = pool_mint.supply;
-
-        let b_to_a_amount = initial_b / 10;
-        let minimum_a_amount = 0;
+        // swap b to a, succeeds at offset price
+        let b_to_a_amount = initial_b;
+        let minimum_token_a_amount = 0;
         accounts
             .swap(
                 &swapper_key,
@@ -5515,73 +8134,86 @@ mod tests {
                 &token_a_key,
                 &mut token_a_account,
                 b_to_a_amount,
-                minimum_a_amount,
+                minimum_token_a_amount,
             )
             .unwrap();
 
-        let results = swap_curve
+        // try a to b again, succeeds due to new liquidity
+        accounts
             .swap(
-                b_to_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                token_a_amount.try_into().unwrap(),
-                TradeDirection::BtoA,
-                &fees,
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
             )
             .unwrap();
 
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        let token_a_amount = swap_token_a.amount;
-        assert_eq!(
-            token_a_amount,
-            TryInto::<u64>::try_into(results.new_swap_destination_amount).unwrap()
-        );
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(
-            token_a.amount,
-            initial_a - a_to_b_amount + to_u64(results.destination_amount_swapped).unwrap()
-        );
-
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        let token_b_amount = swap_token_b.amount;
-        assert_eq!(
-            token_b_amount,
-            TryInto::<u64>::try_into(results.new_swap_source_amount).unwrap()
-        );
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        // try a to b again, fails due to no more liquidity
         assert_eq!(
-            token_b.amount,
-            initial_b + to_u64(first_swap_amount).unwrap()
-                - to_u64(results.source_amount_swapped).unwrap()
+            Err(SwapError::ZeroTradingTokens.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_token_b_amount,
+            )
         );
 
-        let second_fee = swap_curve
-            .withdraw_single_token_type_exact_out(
-                results.owner_fee,
-                token_a_amount.try_into().unwrap(),
-                token_b_amount.try_into().unwrap(),
-                initial_supply.try_into().unwrap(),
-                TradeDirection::BtoA,
-                &fees,
-            )
-            .unwrap();
-        let fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        assert_eq!(fee_account.amount, to_u64(first_fee + second_fee).unwrap());
+        // Try to deposit, fails because deposits are not allowed for offset
+        // curve swaps
+        {
+            let initial_a = 100;
+            let initial_b = 100;
+            let pool_amount = 100;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::UnsupportedCurveOperation.into()),
+                accounts.deposit_all_token_types(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    pool_amount,
+                    initial_a,
+                    initial_b,
+                )
+            );
+        }
     }
 
     #[test]
-    fn test_valid_swap_curves_all_fees() {
-        // All fees
+    fn test_withdraw_all_offset_curve() {
         let trade_fee_numerator = 1;
         let trade_fee_denominator = 10;
         let owner_trade_fee_numerator = 1;
         let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_numerator = 0;
         let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 20;
+        let host_fee_numerator = 10;
         let host_fee_denominator = 100;
+
+        let token_a_amount = 1_000_000_000;
+        let token_b_amount = 10;
         let fees = Fees {
             trade_fee_numerator,
             trade_fee_denominator,
@@ -5593,99 +8225,80 @@ mod tests {
             host_fee_denominator,
         };
 
-        let token_a_amount = 10_000_000_000;
-        let token_b_amount = 50_000_000_000;
+        let token_b_offset = 2_000_000;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Offset,
+            calculator: Box::new(OffsetCurve { token_b_offset }),
+        };
+        let total_pool = swap_curve.calculator.new_pool_supply();
+        let user_key = Pubkey::new_unique();
+        let withdrawer_key = Pubkey::new_unique();
 
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantProduct,
-            Box::new(ConstantProductCurve {}),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_price = 1;
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantPrice,
-            Box::new(ConstantPriceCurve { token_b_price }),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_offset = 10_000_000_000;
-        check_valid_swap_curve(
-            fees,
-            CurveType::Offset,
-            Box::new(OffsetCurve { token_b_offset }),
-            token_a_amount,
-            token_b_amount,
-        );
-    }
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
 
-    #[test]
-    fn test_valid_swap_curves_trade_fee_only() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 0;
-        let owner_trade_fee_denominator = 0;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 0;
-        let host_fee_numerator = 0;
-        let host_fee_denominator = 0;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
+        accounts.initialize_swap().unwrap();
 
-        let token_a_amount = 10_000_000_000;
-        let token_b_amount = 50_000_000_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+        let pool_key = accounts.pool_token_key;
+        let mut pool_account = accounts.pool_token_account.clone();
+
+        // WithdrawAllTokenTypes takes all tokens for A and B.
+        // The curve's calculation for token B will say to transfer
+        // `token_b_offset + token_b_amount`, but only `token_b_amount` will be
+        // moved.
+        accounts
+            .withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
 
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantProduct,
-            Box::new(ConstantProductCurve {}),
-            token_a_amount,
-            token_b_amount,
-        );
-        let token_b_price = 10_000;
-        check_valid_swap_curve(
-            fees.clone(),
-            CurveType::ConstantPrice,
-            Box::new(ConstantPriceCurve { token_b_price }),
-            token_a_amount,
-            token_b_amount / token_b_price,
-        );
-        let token_b_offset = 1;
-        check_valid_swap_curve(
-            fees,
-            CurveType::Offset,
-            Box::new(OffsetCurve { token_b_offset }),
-            token_a_amount,
-            token_b_amount,
-        );
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, token_b_amount);
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, 0);
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(swap_token_b.amount, 0);
     }
 
     #[test]
-    fn test_valid_swap_with_fee_constraints() {
-        let owner_key = Pubkey::new_unique();
-
+    fn test_withdraw_all_constant_price_curve() {
         let trade_fee_numerator = 1;
         let trade_fee_denominator = 10;
         let owner_trade_fee_numerator = 1;
         let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
+        let owner_withdraw_fee_numerator = 0;
         let owner_withdraw_fee_denominator = 30;
         let host_fee_numerator = 10;
         let host_fee_denominator = 100;
 
-        let token_a_amount = 1_000_000;
-        let token_b_amount = 5_000_000;
-
+        // initialize "unbalanced", so that withdrawing all will have some issues
+        // A: 1_000_000_000
+        // B: 2_000_000_000 (1_000 * 2_000_000)
+        let swap_token_a_amount = 1_000_000_000;
+        let swap_token_b_amount = 1_000;
+        let token_b_price = 2_000_000;
         let fees = Fees {
             trade_fee_numerator,
             trade_fee_denominator,
@@ -5697,60 +8310,85 @@ mod tests {
             host_fee_denominator,
         };
 
-        let curve = ConstantProductCurve {};
         let swap_curve = SwapCurve {
-            curve_type: CurveType::ConstantProduct,
-            calculator: Box::new(curve),
+            curve_type: CurveType::ConstantPrice,
+            calculator: Box::new(ConstantPriceCurve { token_b_price }),
         };
+        let total_pool = swap_curve.calculator.new_pool_supply();
+        let user_key = Pubkey::new_unique();
+        let withdrawer_key = Pubkey::new_unique();
 
-        let owner_key_str = &owner_key.to_string();
-        let valid_curve_types = &[CurveType::ConstantProduct];
-        let constraints = Some(SwapConstraints {
-            owner_key: owner_key_str,
-            valid_curve_types,
-            fees: &fees,
-        });
         let mut accounts = SwapAccountInfo::new(
-            &owner_key,
-            fees.clone(),
+            &user_key,
+            fees,
             swap_curve,
-            token_a_amount,
-            token_b_amount,
+            swap_token_a_amount,
+            swap_token_b_amount,
         );
 
-        // initialize swap
-        do_process_instruction_with_fee_constraints(
-            initialize(
-                &SWAP_PROGRAM_ID,
-                &spl_token::id(),
-                &accounts.swap_key,
-                &accounts.authority_key,
-                &accounts.token_a_key,
-                &accounts.token_b_key,
-                &accounts.pool_mint_key,
-                &accounts.pool_fee_key,
-                &accounts.pool_token_key,
-                accounts.nonce,
-                accounts.fees.clone(),
-                accounts.swap_curve.clone(),
+        accounts.initialize_swap().unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+
+        let pool_key = accounts.pool_token_key;
+        let mut pool_account = accounts.pool_token_account.clone();
+
+        // WithdrawAllTokenTypes will not take all token A and B, since their
+        // ratio is unbalanced.  It will try to take 1_500_000_000 worth of
+        // each token, which means 1_500_000_000 token A, and 750 token B.
+        // With no slippage, this will leave 250 token B in the pool.
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                swap_token_a_amount,
+                swap_token_b_amount,
             )
-            .unwrap(),
-            vec![
-                &mut accounts.swap_account,
-                &mut Account::default(),
-                &mut accounts.token_a_account,
-                &mut accounts.token_b_account,
-                &mut accounts.pool_mint_account,
-                &mut accounts.pool_fee_account,
-                &mut accounts.pool_token_account,
-                &mut Account::default(),
-            ],
-            &constraints,
-        )
-        .unwrap();
+        );
 
-        let authority_key = accounts.authority_key;
+        accounts
+            .withdraw_all_token_types(
+                &user_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                total_pool.try_into().unwrap(),
+                0,
+                0,
+            )
+            .unwrap();
+
+        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, swap_token_a_amount);
+        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, 750);
+        let swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, 0);
+        let swap_token_b =
+            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
+        assert_eq!(swap_token_b.amount, 250);
 
+        // deposit now, not enough to cover the tokens already in there
+        let token_b_amount = 10;
+        let token_a_amount = token_b_amount * token_b_price;
         let (
             token_a_key,
             mut token_a_account,
@@ -5759,784 +8397,1025 @@ mod tests {
             pool_key,
             mut pool_account,
         ) = accounts.setup_token_accounts(
-            &owner_key,
-            &authority_key,
+            &user_key,
+            &withdrawer_key,
             token_a_amount,
             token_b_amount,
             0,
         );
 
-        let amount_in = token_a_amount / 2;
-        let minimum_amount_out = 0;
-
-        // perform the swap
-        do_process_instruction_with_fee_constraints(
-            swap(
-                &SWAP_PROGRAM_ID,
-                &spl_token::id(),
-                &accounts.swap_key,
-                &accounts.authority_key,
-                &accounts.authority_key,
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.deposit_all_token_types(
+                &withdrawer_key,
                 &token_a_key,
-                &accounts.token_a_key,
-                &accounts.token_b_key,
-                &token_b_key,
-                &accounts.pool_mint_key,
-                &accounts.pool_fee_key,
-                Some(&pool_key),
-                Swap {
-                    amount_in,
-                    minimum_amount_out,
-                },
-            )
-            .unwrap(),
-            vec![
-                &mut accounts.swap_account,
-                &mut Account::default(),
-                &mut Account::default(),
                 &mut token_a_account,
-                &mut accounts.token_a_account,
-                &mut accounts.token_b_account,
+                &token_b_key,
                 &mut token_b_account,
-                &mut accounts.pool_mint_account,
-                &mut accounts.pool_fee_account,
-                &mut Account::default(),
+                &pool_key,
                 &mut pool_account,
-            ],
-            &constraints,
-        )
-        .unwrap();
+                1, // doesn't matter
+                token_a_amount,
+                token_b_amount,
+            )
+        );
 
-        // check that fees were taken in the host fee account
-        let host_fee_account = spl_token::state::Account::unpack(&pool_account.data).unwrap();
-        let owner_fee_account =
-            spl_token::state::Account::unpack(&accounts.pool_fee_account.data).unwrap();
-        let total_fee = owner_fee_account.amount * host_fee_denominator
-            / (host_fee_denominator - host_fee_numerator);
-        assert_eq!(
-            total_fee,
-            host_fee_account.amount + owner_fee_account.amount
+        // deposit enough tokens, success!
+        let token_b_amount = 125;
+        let token_a_amount = token_b_amount * token_b_price;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &withdrawer_key,
+            token_a_amount,
+            token_b_amount,
+            0,
         );
+
+        accounts
+            .deposit_all_token_types(
+                &withdrawer_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                1, // doesn't matter
+                token_a_amount,
+                token_b_amount,
+            )
+            .unwrap();
+    }
+
+    fn token_amount(account: &Account) -> u64 {
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
     }
 
     #[test]
-    fn test_invalid_swap() {
-        let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 4;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 10;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 5;
-        let host_fee_numerator = 9;
-        let host_fee_denominator = 100;
+    fn test_weighted_pool() {
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
-
-        let token_a_amount = 1000;
-        let token_b_amount = 5000;
-        let curve_type = CurveType::ConstantProduct;
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator: Box::new(ConstantProductCurve {}),
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 5,
+            owner_trade_fee_denominator: 10_000,
+            owner_withdraw_fee_numerator: 1,
+            owner_withdraw_fee_denominator: 100,
+            host_fee_numerator: 20,
+            host_fee_denominator: 100,
         };
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        let initial_a = token_a_amount / 5;
-        let initial_b = token_b_amount / 5;
-        let minimum_token_b_amount = initial_b / 2;
-
-        let swap_token_a_key = accounts.token_a_key;
-        let swap_token_b_key = accounts.token_b_key;
+        let weights = vec![50, 30, 20];
+        let swap_amounts = [1_000_000u64, 2_000_000, 3_000_000];
+        let user_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let mut authority_account = Account::default();
+        let mut token_program_account = Account::default();
+        let mut user_transfer_authority_account = Account::default();
 
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
+        let swap_key = Pubkey::new_unique();
+        let mut swap_account = Account::new(0, SwapVersion::WEIGHTED_LEN, &SWAP_PROGRAM_ID);
+        let (authority_key, nonce) =
+            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+        let (pool_mint_key, mut pool_mint_account) =
+            create_mint(&spl_token::id(), &authority_key, None);
+        let (pool_token_key, mut pool_token_account) = mint_token(
+            &spl_token::id(),
+            &pool_mint_key,
+            &mut pool_mint_account,
+            &authority_key,
+            &user_key,
+            0,
+        );
+        let (pool_fee_key, mut pool_fee_account) = mint_token(
+            &spl_token::id(),
+            &pool_mint_key,
+            &mut pool_mint_account,
+            &authority_key,
+            &user_key,
+            0,
+        );
+        let mut mint_keys = vec![];
+        let mut mint_accounts = vec![];
+        let mut swap_token_keys = vec![];
+        let mut swap_token_accounts = vec![];
+        let mut user_token_keys = vec![];
+        let mut user_token_accounts = vec![];
+        for amount in swap_amounts.iter() {
+            let (mint_key, mut mint_account) = create_mint(&spl_token::id(), &user_key, None);
+            let (token_key, token_account) = mint_token(
+                &spl_token::id(),
+                &mint_key,
+                &mut mint_account,
+                &user_key,
+                &authority_key,
+                *amount,
             );
-        }
-
-        accounts.initialize_swap().unwrap();
-
-        // wrong swap account program id
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_swap_account = accounts.swap_account;
-            let mut wrong_swap_account = old_swap_account.clone();
-            wrong_swap_account.owner = spl_token::id();
-            accounts.swap_account = wrong_swap_account;
-            assert_eq!(
-                Err(ProgramError::IncorrectProgramId),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
+            let (user_token_key, user_token_account) = mint_token(
+                &spl_token::id(),
+                &mint_key,
+                &mut mint_account,
+                &user_key,
+                &user_key,
+                1_000_000,
             );
-            accounts.swap_account = old_swap_account;
+            mint_keys.push(mint_key);
+            mint_accounts.push(mint_account);
+            swap_token_keys.push(token_key);
+            swap_token_accounts.push(token_account);
+            user_token_keys.push(user_token_key);
+            user_token_accounts.push(user_token_account);
         }
 
-        // wrong nonce
+        // two tokens are not enough
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &spl_token::id(),
-            );
-            accounts.authority_key = bad_authority_key;
+            let mut accounts = vec![
+                &mut swap_account,
+                &mut authority_account,
+                &mut pool_mint_account,
+                &mut pool_fee_account,
+                &mut pool_token_account,
+                &mut token_program_account,
+            ];
+            accounts.extend(swap_token_accounts.iter_mut().take(2));
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+                Err(SwapError::InvalidInstruction.into()),
+                do_process_instruction(
+                    initialize_weighted(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &swap_key,
+                        &authority_key,
+                        &swap_token_keys[..2],
+                        &pool_mint_key,
+                        &pool_fee_key,
+                        &pool_token_key,
+                        nonce,
+                        fees.clone(),
+                        weights[..2].to_vec(),
+                    )
+                    .unwrap(),
+                    accounts,
                 )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // wrong token program id
+        // weights too far apart
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let wrong_program_id = Pubkey::new_unique();
+            let mut accounts = vec![
+                &mut swap_account,
+                &mut authority_account,
+                &mut pool_mint_account,
+                &mut pool_fee_account,
+                &mut pool_token_account,
+                &mut token_program_account,
+            ];
+            accounts.extend(swap_token_accounts.iter_mut());
             assert_eq!(
-                Err(SwapError::IncorrectTokenProgramId.into()),
+                Err(SwapError::InvalidCurve.into()),
                 do_process_instruction(
-                    swap(
+                    initialize_weighted(
                         &SWAP_PROGRAM_ID,
-                        &wrong_program_id,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
-                        },
+                        &spl_token::id(),
+                        &swap_key,
+                        &authority_key,
+                        &swap_token_keys,
+                        &pool_mint_key,
+                        &pool_fee_key,
+                        &pool_token_key,
+                        nonce,
+                        fees.clone(),
+                        vec![1, 51, 10],
                     )
                     .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
-                        &mut Account::default(),
-                    ],
-                ),
+                    accounts,
+                )
             );
         }
 
-        // not enough token a to swap
+        // correct initialization
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a * 2,
-                    minimum_token_b_amount * 2,
+            let mut admin_account = Account::default();
+            let mut accounts = vec![
+                &mut swap_account,
+                &mut authority_account,
+                &mut pool_mint_account,
+                &mut pool_fee_account,
+                &mut pool_token_account,
+                &mut token_program_account,
+            ];
+            accounts.extend(swap_token_accounts.iter_mut());
+            accounts.push(&mut admin_account);
+            do_process_instruction(
+                initialize_weighted_with_admin(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &swap_key,
+                    &authority_key,
+                    &swap_token_keys,
+                    &pool_mint_key,
+                    &pool_fee_key,
+                    &pool_token_key,
+                    &admin_key,
+                    nonce,
+                    fees.clone(),
+                    weights.clone(),
+                    DynamicFees::default(),
                 )
-            );
+                .unwrap(),
+                accounts,
+            )
+            .unwrap();
         }
+        let token_swap = SwapVersion::unpack_weighted(&swap_account.data).unwrap();
+        assert!(token_swap.is_initialized);
+        assert_eq!(token_swap.nonce, nonce);
+        assert_eq!(token_swap.pool_mint, pool_mint_key);
+        assert_eq!(token_swap.fees, fees);
+        assert_eq!(token_swap.admin_authority, admin_key);
+        assert_eq!(token_swap.weights(), weights);
+        for (i, token) in token_swap.tokens.iter().enumerate() {
+            assert_eq!(token.account, swap_token_keys[i]);
+            assert_eq!(token.mint, mint_keys[i]);
+            assert_eq!(token.balance, swap_amounts[i]);
+        }
+        assert_eq!(token_swap.price_accumulators.len(), 2);
+        assert_eq!(
+            token_amount(&pool_token_account),
+            INITIAL_SWAP_POOL_AMOUNT as u64
+        );
 
-        // wrong swap token A / B accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let user_transfer_key = Pubkey::new_unique();
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_key,
-                        &token_a_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
+        // two-token deposits cannot be used on a weighted swap
+        {
+            let (swap_token_a_account, swap_token_b_account) = swap_token_accounts.split_at_mut(1);
+            let (user_token_a_account, user_token_b_account) = user_token_accounts.split_at_mut(1);
+            assert_eq!(
+                Err(SwapError::UnsupportedCurveOperation.into()),
+                do_process_instruction(
+                    deposit_all_token_types(
+                        &SWAP_PROGRAM_ID,
+                        &spl_token::id(),
+                        &swap_key,
+                        &authority_key,
+                        &user_key,
+                        &user_token_keys[0],
+                        &user_token_keys[1],
+                        &swap_token_keys[0],
+                        &swap_token_keys[1],
+                        &pool_mint_key,
+                        &pool_token_key,
+                        DepositAllTokenTypes {
+                            pool_token_amount: 1_000,
+                            maximum_token_a_amount: u64::MAX,
+                            maximum_token_b_amount: u64::MAX,
                         },
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
+                        &mut swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut token_a_account.clone(),
-                        &mut token_a_account,
-                        &mut token_b_account.clone(),
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
+                        &mut user_token_a_account[0],
+                        &mut user_token_b_account[0],
+                        &mut swap_token_a_account[0],
+                        &mut swap_token_b_account[0],
+                        &mut pool_mint_account,
+                        &mut pool_token_account,
                         &mut Account::default(),
                     ],
-                ),
+                )
             );
         }
 
-        // wrong user token A / B accounts
+        // swap from token 2 to token 0
+        let amount_in = 100_000;
+        let expected = token_swap
+            .pair_swap_curve(2, 0)
+            .unwrap()
+            .swap(
+                amount_in as u128,
+                swap_amounts[2] as u128,
+                swap_amounts[0] as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    initial_a,
-                    minimum_token_b_amount,
+            let (swap_destination_account, swap_source_account) =
+                swap_token_accounts.split_at_mut(2);
+            let (user_destination_account, user_source_account) =
+                user_token_accounts.split_at_mut(2);
+            let swap_instruction = |destination_index, minimum_amount_out| {
+                weighted_swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &swap_key,
+                    &authority_key,
+                    &user_key,
+                    &user_token_keys[2],
+                    &swap_token_keys[2],
+                    &swap_token_keys[0],
+                    &user_token_keys[0],
+                    &pool_mint_key,
+                    &pool_fee_key,
+                    None,
+                    WeightedSwap {
+                        source_index: 2,
+                        destination_index,
+                        amount_in,
+                        minimum_amount_out,
+                    },
                 )
-            );
+                .unwrap()
+            };
+            let minimum_amount_out = expected.destination_amount_swapped as u64;
+            for (destination_index, minimum_amount_out, expected_result) in &[
+                (3, 0, Err(SwapError::InvalidTokenIndex.into())),
+                (1, 0, Err(SwapError::IncorrectSwapAccount.into())),
+                (
+                    0,
+                    minimum_amount_out + 1,
+                    Err(SwapError::ExceededSlippage.into()),
+                ),
+                (0, minimum_amount_out, Ok(())),
+            ] {
+                assert_eq!(
+                    *expected_result,
+                    do_process_instruction(
+                        swap_instruction(*destination_index, *minimum_amount_out),
+                        vec![
+                            &mut swap_account,
+                            &mut Account::default(),
+                            &mut Account::default(),
+                            &mut user_source_account[0],
+                            &mut swap_source_account[0],
+                            &mut swap_destination_account[0],
+                            &mut user_destination_account[0],
+                            &mut pool_mint_account,
+                            &mut pool_fee_account,
+                            &mut Account::default(),
+                        ],
+                    )
+                );
+            }
         }
-
-        // swap from a to a
+        assert_eq!(
+            token_amount(&swap_token_accounts[2]),
+            expected.new_swap_source_amount as u64
+        );
+        assert_eq!(
+            token_amount(&swap_token_accounts[0]),
+            expected.new_swap_destination_amount as u64
+        );
+        assert_eq!(
+            token_amount(&user_token_accounts[0]),
+            1_000_000 + expected.destination_amount_swapped as u64
+        );
+        assert!(token_amount(&pool_fee_account) > 0);
+
+        // a plain swap trades between any two tokens of the pool, from token 0
+        // to token 1 here
+        let token_swap = SwapVersion::unpack_weighted(&swap_account.data).unwrap();
+        let expected = token_swap
+            .pair_swap_curve(0, 1)
+            .unwrap()
+            .swap(
+                amount_in as u128,
+                token_amount(&swap_token_accounts[0]) as u128,
+                token_amount(&swap_token_accounts[1]) as u128,
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account.clone(),
-                    &swap_token_a_key,
-                    &swap_token_a_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    initial_a,
-                    minimum_token_b_amount,
+            let (swap_source_account, swap_destination_account) =
+                swap_token_accounts.split_at_mut(1);
+            let (user_source_account, user_destination_account) =
+                user_token_accounts.split_at_mut(1);
+            do_process_instruction(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &swap_key,
+                    &authority_key,
+                    &user_key,
+                    &user_token_keys[0],
+                    &swap_token_keys[0],
+                    &swap_token_keys[1],
+                    &user_token_keys[1],
+                    &pool_mint_key,
+                    &pool_fee_key,
+                    None,
+                    Swap {
+                        amount_in,
+                        minimum_amount_out: expected.destination_amount_swapped as u64,
+                    },
                 )
-            );
+                .unwrap(),
+                vec![
+                    &mut swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut user_source_account[0],
+                    &mut swap_source_account[0],
+                    &mut swap_destination_account[0],
+                    &mut user_destination_account[0],
+                    &mut pool_mint_account,
+                    &mut pool_fee_account,
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
         }
+        assert_eq!(
+            token_amount(&swap_token_accounts[0]),
+            expected.new_swap_source_amount as u64
+        );
+        assert_eq!(
+            token_amount(&swap_token_accounts[1]),
+            expected.new_swap_destination_amount as u64
+        );
 
-        // incorrect mint provided
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let (pool_mint_key, pool_mint_account) =
-                create_mint(&spl_token::id(), &accounts.authority_key, None);
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        // the balances recorded for the price accumulators follow the swaps
+        let token_swap = SwapVersion::unpack_weighted(&swap_account.data).unwrap();
+        for (token, account) in token_swap.tokens.iter().zip(swap_token_accounts.iter()) {
+            assert_eq!(token.balance, token_amount(account));
+        }
 
-            assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+        // deposit all tokens for 1% of the pool tokens
+        let pool_token_amount = INITIAL_SWAP_POOL_AMOUNT as u64 / 100;
+        let swap_amounts_before = swap_token_accounts
+            .iter()
+            .map(token_amount)
+            .collect::<Vec<_>>();
+        {
+            let mut accounts = vec![
+                &mut swap_account,
+                &mut authority_account,
+                &mut user_transfer_authority_account,
+                &mut pool_mint_account,
+                &mut pool_token_account,
+                &mut token_program_account,
+            ];
+            accounts.extend(user_token_accounts.iter_mut());
+            accounts.extend(swap_token_accounts.iter_mut());
+            do_process_instruction(
+                weighted_deposit_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &swap_key,
+                    &authority_key,
+                    &user_key,
+                    &user_token_keys,
+                    &swap_token_keys,
+                    &pool_mint_key,
+                    &pool_token_key,
+                    WeightedDepositAllTokenTypes {
+                        pool_token_amount,
+                        maximum_token_amounts: vec![u64::MAX; 3],
+                    },
                 )
-            );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
+                .unwrap(),
+                accounts,
+            )
+            .unwrap();
+        }
+        for (account, amount_before) in swap_token_accounts.iter().zip(swap_amounts_before) {
+            let deposited = token_amount(account) - amount_before;
+            assert!(deposited >= amount_before / 100);
+            assert!(deposited <= amount_before / 100 + 1);
         }
+        let pool_tokens = token_amount(&pool_token_account);
+        assert_eq!(
+            pool_tokens,
+            INITIAL_SWAP_POOL_AMOUNT as u64 + pool_token_amount
+        );
 
-        // incorrect fee account provided
+        // withdraw them again, paying the withdraw fee
+        let swap_amounts_before = swap_token_accounts
+            .iter()
+            .map(token_amount)
+            .collect::<Vec<_>>();
+        let user_amounts_before = user_token_accounts
+            .iter()
+            .map(token_amount)
+            .collect::<Vec<_>>();
+        let pool_fees_before = token_amount(&pool_fee_account);
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                wrong_pool_key,
-                wrong_pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_pool_fee_account = accounts.pool_fee_account;
-            let old_pool_fee_key = accounts.pool_fee_key;
-            accounts.pool_fee_account = wrong_pool_account;
-            accounts.pool_fee_key = wrong_pool_key;
-            assert_eq!(
-                Err(SwapError::IncorrectFeeAccount.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
+            let mut accounts = vec![
+                &mut swap_account,
+                &mut authority_account,
+                &mut user_transfer_authority_account,
+                &mut pool_mint_account,
+                &mut pool_token_account,
+                &mut pool_fee_account,
+                &mut token_program_account,
+            ];
+            accounts.extend(swap_token_accounts.iter_mut());
+            accounts.extend(user_token_accounts.iter_mut());
+            do_process_instruction(
+                weighted_withdraw_all_token_types(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &swap_key,
+                    &authority_key,
+                    &user_key,
+                    &pool_mint_key,
+                    &pool_fee_key,
+                    &pool_token_key,
+                    &swap_token_keys,
+                    &user_token_keys,
+                    WeightedWithdrawAllTokenTypes {
+                        pool_token_amount,
+                        minimum_token_amounts: vec![1; 3],
+                    },
                 )
+                .unwrap(),
+                accounts,
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            token_amount(&pool_fee_account),
+            pool_fees_before + pool_token_amount / 100
+        );
+        for i in 0..3 {
+            let withdrawn = swap_amounts_before[i] - token_amount(&swap_token_accounts[i]);
+            assert!(withdrawn > 0);
+            assert_eq!(
+                token_amount(&user_token_accounts[i]),
+                user_amounts_before[i] + withdrawn
             );
-            accounts.pool_fee_account = old_pool_fee_account;
-            accounts.pool_fee_key = old_pool_fee_key;
         }
 
-        // no approval
+        // deposit and withdraw a single token
+        let pool_tokens_before = token_amount(&pool_token_account);
+        let deposit_amount = 10_000;
+        do_process_instruction(
+            weighted_deposit_single_token_type_exact_amount_in(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &swap_key,
+                &authority_key,
+                &user_key,
+                &user_token_keys[1],
+                &swap_token_keys[1],
+                &pool_mint_key,
+                &pool_token_key,
+                WeightedDepositSingleTokenTypeExactAmountIn {
+                    token_index: 1,
+                    source_token_amount: deposit_amount,
+                    minimum_pool_token_amount: 1,
+                },
+            )
+            .unwrap(),
+            vec![
+                &mut swap_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut user_token_accounts[1],
+                &mut swap_token_accounts[1],
+                &mut pool_mint_account,
+                &mut pool_token_account,
+                &mut Account::default(),
+            ],
+        )
+        .unwrap();
+        let deposited_pool_tokens = token_amount(&pool_token_account) - pool_tokens_before;
+        assert!(deposited_pool_tokens > 0);
+
+        let user_amount_before = token_amount(&user_token_accounts[1]);
+        let withdraw_amount = deposit_amount / 2;
+        do_process_instruction(
+            weighted_withdraw_single_token_type_exact_amount_out(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &swap_key,
+                &authority_key,
+                &user_key,
+                &pool_mint_key,
+                &pool_fee_key,
+                &pool_token_key,
+                &swap_token_keys[1],
+                &user_token_keys[1],
+                WeightedWithdrawSingleTokenTypeExactAmountOut {
+                    token_index: 1,
+                    destination_token_amount: withdraw_amount,
+                    maximum_pool_token_amount: deposited_pool_tokens,
+                },
+            )
+            .unwrap(),
+            vec![
+                &mut swap_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                &mut pool_mint_account,
+                &mut pool_token_account,
+                &mut swap_token_accounts[1],
+                &mut user_token_accounts[1],
+                &mut pool_fee_account,
+                &mut Account::default(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            token_amount(&user_token_accounts[1]),
+            user_amount_before + withdraw_amount
+        );
+        let withdrawn_pool_tokens =
+            pool_tokens_before + deposited_pool_tokens - token_amount(&pool_token_account);
+        assert!(withdrawn_pool_tokens < deposited_pool_tokens);
+        assert!(withdrawn_pool_tokens > deposited_pool_tokens / 2);
+        let token_swap = SwapVersion::unpack_weighted(&swap_account.data).unwrap();
+        for (token, account) in token_swap.tokens.iter().zip(swap_token_accounts.iter()) {
+            assert_eq!(token.balance, token_amount(account));
+        }
+
+        // the admin can change the fees and pause the swap
+        let new_fees = Fees {
+            trade_fee_numerator: 50,
+            ..fees
+        };
+        do_process_instruction(
+            set_fees(
+                &SWAP_PROGRAM_ID,
+                &swap_key,
+                &admin_key,
+                new_fees.clone(),
+                DynamicFees::default(),
+            )
+            .unwrap(),
+            vec![&mut swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        let token_swap = SwapVersion::unpack_weighted(&swap_account.data).unwrap();
+        assert_eq!(token_swap.fees, new_fees);
+        do_process_instruction(
+            pause(&SWAP_PROGRAM_ID, &swap_key, &admin_key).unwrap(),
+            vec![&mut swap_account, &mut Account::default()],
+        )
+        .unwrap();
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let user_transfer_key = Pubkey::new_unique();
+            let (swap_source_account, swap_destination_account) =
+                swap_token_accounts.split_at_mut(1);
+            let (user_source_account, user_destination_account) =
+                user_token_accounts.split_at_mut(1);
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
+                Err(SwapError::SwapPaused.into()),
                 do_process_instruction(
-                    swap(
+                    weighted_swap(
                         &SWAP_PROGRAM_ID,
                         &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &user_transfer_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
+                        &swap_key,
+                        &authority_key,
+                        &user_key,
+                        &user_token_keys[0],
+                        &swap_token_keys[0],
+                        &swap_token_keys[1],
+                        &user_token_keys[1],
+                        &pool_mint_key,
+                        &pool_fee_key,
                         None,
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: minimum_token_b_amount,
+                        WeightedSwap {
+                            source_index: 0,
+                            destination_index: 1,
+                            amount_in,
+                            minimum_amount_out: 0,
                         },
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
+                        &mut swap_account,
                         &mut Account::default(),
                         &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
+                        &mut user_source_account[0],
+                        &mut swap_source_account[0],
+                        &mut swap_destination_account[0],
+                        &mut user_destination_account[0],
+                        &mut pool_mint_account,
+                        &mut pool_fee_account,
                         &mut Account::default(),
                     ],
-                ),
-            );
-        }
-
-        // output token value 0
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::ZeroTradingTokens.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &swap_token_b_key,
-                    &swap_token_a_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    1,
-                    1,
                 )
             );
         }
+        do_process_instruction(
+            unpause(&SWAP_PROGRAM_ID, &swap_key, &admin_key).unwrap(),
+            vec![&mut swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        assert!(
+            !SwapVersion::unpack_weighted(&swap_account.data)
+                .unwrap()
+                .is_paused
+        );
+    }
 
-        // slippage exceeded: minimum out amount too high
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount * 2,
-                )
-            );
-        }
+    #[test]
+    fn test_routed_swap() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 200,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(ConstantProductCurve {}),
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 5_000_000;
+        let token_c_amount = 2_000_000;
 
-        // invalid input: can't use swap pool as user source / dest
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let mut swap_token_a_account = accounts.get_token_account(&swap_token_a_key).clone();
-            let authority_key = accounts.authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &authority_key,
-                    &swap_token_a_key,
-                    &mut swap_token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
-            );
-            let mut swap_token_b_account = accounts.get_token_account(&swap_token_b_key).clone();
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &swap_token_b_key,
-                    &mut swap_token_b_account,
-                    initial_a,
-                    minimum_token_b_amount,
-                )
-            );
-        }
+        // first pool trades A for B, second pool trades B for C
+        let mut first = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        first.initialize_swap().unwrap();
+        let mut second = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_b_amount,
+            token_c_amount,
+        );
+        let (second_token_a_key, second_token_a_account) = mint_token(
+            &spl_token::id(),
+            &first.token_b_mint_key,
+            &mut first.token_b_mint_account,
+            &user_key,
+            &second.authority_key,
+            token_b_amount,
+        );
+        second.token_a_key = second_token_a_key;
+        second.token_a_account = second_token_a_account;
+        second.token_a_mint_key = first.token_b_mint_key;
+        second.token_a_mint_account = first.token_b_mint_account.clone();
+        second.initialize_swap().unwrap();
+
+        let amount_in = 10_000;
+        let (source_key, mut source_account, intermediate_key, mut intermediate_account, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            second.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+
+        let user_transfer_key = Pubkey::new_unique();
+        do_process_instruction(
+            approve(
+                &spl_token::id(),
+                &source_key,
+                &user_transfer_key,
+                &swapper_key,
+                &[],
+                amount_in,
+            )
+            .unwrap(),
+            vec![
+                &mut source_account,
+                &mut Account::default(),
+                &mut Account::default(),
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            approve(
+                &spl_token::id(),
+                &intermediate_key,
+                &user_transfer_key,
+                &swapper_key,
+                &[],
+                u64::MAX,
+            )
+            .unwrap(),
+            vec![
+                &mut intermediate_account,
+                &mut Account::default(),
+                &mut Account::default(),
+            ],
+        )
+        .unwrap();
 
-        // still correct: constraint specified, no host fee account
+        let first_result = swap_curve
+            .swap(
+                amount_in.into(),
+                token_a_amount.into(),
+                token_b_amount.into(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let second_result = swap_curve
+            .swap(
+                first_result.destination_amount_swapped,
+                token_b_amount.into(),
+                token_c_amount.into(),
+                TradeDirection::AtoB,
+                &fees,
+            )
+            .unwrap();
+        let expected_amount_out = to_u64(second_result.destination_amount_swapped).unwrap();
+
+        let route = [
+            RouteHop {
+                swap_pubkey: first.swap_key,
+                authority_pubkey: first.authority_key,
+                swap_source_pubkey: first.token_a_key,
+                swap_destination_pubkey: first.token_b_key,
+                pool_mint_pubkey: first.pool_mint_key,
+                pool_fee_pubkey: first.pool_fee_key,
+                destination_pubkey: intermediate_key,
+            },
+            RouteHop {
+                swap_pubkey: second.swap_key,
+                authority_pubkey: second.authority_key,
+                swap_source_pubkey: second.token_a_key,
+                swap_destination_pubkey: second.token_b_key,
+                pool_mint_pubkey: second.pool_mint_key,
+                pool_fee_pubkey: second.pool_fee_key,
+                destination_pubkey: destination_key,
+            },
+        ];
+
+        // not a whole number of pools
         {
-            let authority_key = accounts.authority_key;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let owner_key = &swapper_key.to_string();
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types: &[],
-                fees: &fees,
-            });
-            do_process_instruction_with_fee_constraints(
-                swap(
-                    &SWAP_PROGRAM_ID,
-                    &spl_token::id(),
-                    &accounts.swap_key,
-                    &accounts.authority_key,
-                    &accounts.authority_key,
-                    &token_a_key,
-                    &accounts.token_a_key,
-                    &accounts.token_b_key,
-                    &token_b_key,
-                    &accounts.pool_mint_key,
-                    &accounts.pool_fee_key,
-                    None,
-                    Swap {
-                        amount_in: initial_a,
-                        minimum_amount_out: minimum_token_b_amount,
-                    },
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.swap_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                    &mut token_a_account,
-                    &mut accounts.token_a_account,
-                    &mut accounts.token_b_account,
-                    &mut token_b_account,
-                    &mut accounts.pool_mint_account,
-                    &mut accounts.pool_fee_account,
-                    &mut Account::default(),
-                ],
-                &constraints,
+            let mut instruction = routed_swap(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &user_transfer_key,
+                &source_key,
+                &route,
+                RoutedSwap {
+                    amount_in,
+                    minimum_amount_out: 0,
+                },
             )
             .unwrap();
+            instruction.accounts.pop();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                do_process_instruction(
+                    instruction,
+                    vec![
+                        &mut Account::default(),
+                        &mut source_account,
+                        &mut Account::default(),
+                        &mut first.swap_account,
+                        &mut Account::default(),
+                        &mut first.token_a_account,
+                        &mut first.token_b_account,
+                        &mut first.pool_mint_account,
+                        &mut first.pool_fee_account,
+                        &mut intermediate_account,
+                        &mut second.swap_account,
+                        &mut Account::default(),
+                        &mut second.token_a_account,
+                        &mut second.token_b_account,
+                        &mut second.pool_mint_account,
+                        &mut second.pool_fee_account,
+                    ],
+                )
+            );
         }
 
-        // invalid mint for host fee account
-        {
-            let authority_key = accounts.authority_key;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let (
-                bad_token_a_key,
-                mut bad_token_a_account,
-                _token_b_key,
-                mut _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &authority_key, initial_a, initial_b, 0);
-            let owner_key = &swapper_key.to_string();
-            let fees = Fees {
-                trade_fee_numerator,
-                trade_fee_denominator,
-                owner_trade_fee_numerator,
-                owner_trade_fee_denominator,
-                owner_withdraw_fee_numerator,
-                owner_withdraw_fee_denominator,
-                host_fee_numerator,
-                host_fee_denominator,
-            };
-            let constraints = Some(SwapConstraints {
-                owner_key,
-                valid_curve_types: &[],
-                fees: &fees,
-            });
+        for (minimum_amount_out, expected_result) in &[
+            (
+                expected_amount_out + 1,
+                Err(SwapError::ExceededSlippage.into()),
+            ),
+            (expected_amount_out, Ok(())),
+        ] {
             assert_eq!(
-                Err(SwapError::IncorrectPoolMint.into()),
-                do_process_instruction_with_fee_constraints(
-                    swap(
+                *expected_result,
+                do_process_instruction(
+                    routed_swap(
                         &SWAP_PROGRAM_ID,
                         &spl_token::id(),
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.pool_mint_key,
-                        &accounts.pool_fee_key,
-                        Some(&bad_token_a_key),
-                        Swap {
-                            amount_in: initial_a,
-                            minimum_amount_out: 0,
+                        &user_transfer_key,
+                        &source_key,
+                        &route,
+                        RoutedSwap {
+                            amount_in,
+                            minimum_amount_out: *minimum_amount_out,
                         },
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
                         &mut Account::default(),
+                        &mut source_account,
                         &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.pool_mint_account,
-                        &mut accounts.pool_fee_account,
+                        &mut first.swap_account,
                         &mut Account::default(),
-                        &mut bad_token_a_account,
+                        &mut first.token_a_account,
+                        &mut first.token_b_account,
+                        &mut first.pool_mint_account,
+                        &mut first.pool_fee_account,
+                        &mut intermediate_account,
+                        &mut second.swap_account,
+                        &mut Account::default(),
+                        &mut second.token_a_account,
+                        &mut second.token_b_account,
+                        &mut second.pool_mint_account,
+                        &mut second.pool_fee_account,
+                        &mut destination_account,
                     ],
-                    &constraints,
-                ),
+                )
             );
         }
+
+        assert_eq!(token_amount(&source_account), 0);
+        // the curve may leave dust of the intermediate token with the user
+        assert_eq!(
+            token_amount(&intermediate_account),
+            to_u64(first_result.destination_amount_swapped - second_result.source_amount_swapped)
+                .unwrap()
+        );
+        assert_eq!(token_amount(&destination_account), expected_amount_out);
+        assert_eq!(
+            token_amount(&first.token_a_account),
+            to_u64(first_result.new_swap_source_amount).unwrap()
+        );
+        assert_eq!(
+            token_amount(&second.token_a_account),
+            to_u64(second_result.new_swap_source_amount).unwrap()
+        );
+        assert_eq!(
+            token_amount(&second.token_b_account),
+            to_u64(second_result.new_swap_destination_amount).unwrap()
+        );
+        assert!(token_amount(&first.pool_fee_account) > 0);
+        assert!(token_amount(&second.pool_fee_account) > 0);
     }
 
     #[test]
-    fn test_overdraw_offset_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 1;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        let token_a_amount = 1_000_000_000;
-        let token_b_amount = 0;
+    fn test_price_accumulator() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
         let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 0,
+            owner_trade_fee_denominator: 0,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
         };
-
-        let token_b_offset = 2_000_000;
         let swap_curve = SwapCurve {
-            curve_type: CurveType::Offset,
-            calculator: Box::new(OffsetCurve { token_b_offset }),
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(ConstantProductCurve {}),
         };
-        let user_key = Pubkey::new_unique();
-        let swapper_key = Pubkey::new_unique();
-
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 4_000_000;
 
+        set_test_unix_timestamp(1_000);
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
         accounts.initialize_swap().unwrap();
+        let initial = PriceAccumulator::new(1_000);
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .price_accumulator(),
+            Some(&initial)
+        );
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .token_balances(),
+            Some((token_a_amount, token_b_amount))
+        );
 
         let swap_token_a_key = accounts.token_a_key;
         let swap_token_b_key = accounts.token_b_key;
-        let initial_a = 500_000;
-        let initial_b = 1_000;
-
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-
-        // swap a to b way, fails, there's no liquidity
-        let a_to_b_amount = initial_a;
-        let minimum_token_b_amount = 0;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
 
-        assert_eq!(
-            Err(SwapError::ZeroTradingTokens.into()),
-            accounts.swap(
+        // price of A is 4 B for the first 10 seconds
+        set_test_unix_timestamp(1_010);
+        accounts
+            .swap(
                 &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
@@ -6544,29 +9423,33 @@ mod tests {
                 &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
+                50_000,
+                0,
             )
+            .unwrap();
+        let mut expected = initial.clone();
+        expected.update(
+            swap_curve.calculator.as_ref(),
+            token_a_amount.into(),
+            token_b_amount.into(),
+            1_010,
         );
-
-        // swap b to a, succeeds at offset price
-        let b_to_a_amount = initial_b;
-        let minimum_token_a_amount = 0;
-        accounts
-            .swap(
-                &swapper_key,
-                &token_b_key,
-                &mut token_b_account,
-                &swap_token_b_key,
-                &swap_token_a_key,
-                &token_a_key,
-                &mut token_a_account,
-                b_to_a_amount,
-                minimum_token_a_amount,
-            )
+        let after_first_swap = SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .price_accumulator()
+            .cloned()
             .unwrap();
+        assert_eq!(after_first_swap, expected);
+        // 4 * 10 seconds
+        assert_eq!(
+            U256::from(after_first_swap.token_a_price_cumulative),
+            PreciseNumber::new(40).unwrap().value
+        );
 
-        // try a to b again, succeeds due to new liquidity
+        // the price after the first swap is in effect for the next 30 seconds
+        set_test_unix_timestamp(1_040);
+        let swap_token_a_amount = token_amount(&accounts.token_a_account);
+        let swap_token_b_amount = token_amount(&accounts.token_b_account);
         accounts
             .swap(
                 &swapper_key,
@@ -6576,309 +9459,827 @@ mod tests {
                 &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
+                50_000,
+                0,
             )
             .unwrap();
-
-        // try a to b again, fails due to no more liquidity
-        assert_eq!(
-            Err(SwapError::ZeroTradingTokens.into()),
-            accounts.swap(
-                &swapper_key,
-                &token_a_key,
-                &mut token_a_account,
-                &swap_token_a_key,
-                &swap_token_b_key,
-                &token_b_key,
-                &mut token_b_account,
-                a_to_b_amount,
-                minimum_token_b_amount,
-            )
+        expected.update(
+            swap_curve.calculator.as_ref(),
+            swap_token_a_amount.into(),
+            swap_token_b_amount.into(),
+            1_040,
         );
-
-        // Try to deposit, fails because deposits are not allowed for offset
-        // curve swaps
-        {
-            let initial_a = 100;
-            let initial_b = 100;
-            let pool_amount = 100;
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(SwapError::UnsupportedCurveOperation.into()),
-                accounts.deposit_all_token_types(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    pool_amount,
-                    initial_a,
-                    initial_b,
-                )
-            );
-        }
-    }
-
-    #[test]
-    fn test_withdraw_all_offset_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        let token_a_amount = 1_000_000_000;
-        let token_b_amount = 10;
-        let fees = Fees {
-            trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
-        };
-
-        let token_b_offset = 2_000_000;
-        let swap_curve = SwapCurve {
-            curve_type: CurveType::Offset,
-            calculator: Box::new(OffsetCurve { token_b_offset }),
+        let after_second_swap = SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .price_accumulator()
+            .cloned()
+            .unwrap();
+        assert_eq!(after_second_swap, expected);
+        let second_price = swap_curve
+            .calculator
+            .spot_price(swap_token_a_amount.into(), swap_token_b_amount.into())
+            .unwrap();
+        let accumulated = PreciseNumber {
+            value: U256::from(
+                after_second_swap.token_a_price_cumulative
+                    - after_first_swap.token_a_price_cumulative,
+            ),
         };
-        let total_pool = swap_curve.calculator.new_pool_supply();
-        let user_key = Pubkey::new_unique();
-        let withdrawer_key = Pubkey::new_unique();
-
-        let mut accounts =
-            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
-
-        accounts.initialize_swap().unwrap();
-
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+        let thirty_seconds = PreciseNumber::new(30).unwrap();
+        assert!(accumulated.almost_eq(
+            &second_price.checked_mul(&thirty_seconds).unwrap(),
+            U256::from(30)
+        ));
+
+        // tokens sent straight to the pool don't move the accumulated price,
+        // which comes from the balances recorded by the last instruction
+        let swap_token_a_amount = token_amount(&accounts.token_a_account);
+        let swap_token_b_amount = token_amount(&accounts.token_b_account);
+        let mut swap_token_a =
+            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
+        swap_token_a.amount += 1_000_000;
+        spl_token::state::Account::pack(swap_token_a, &mut accounts.token_a_account.data).unwrap();
 
+        // deposits are accumulated too
+        set_test_unix_timestamp(1_100);
         let pool_key = accounts.pool_token_key;
         let mut pool_account = accounts.pool_token_account.clone();
-
-        // WithdrawAllTokenTypes takes all tokens for A and B.
-        // The curve's calculation for token B will say to transfer
-        // `token_b_offset + token_b_amount`, but only `token_b_amount` will be
-        // moved.
+        let (deposit_a_key, mut deposit_a_account, deposit_b_key, mut deposit_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &user_key, 1_000_000, 4_000_000, 0);
         accounts
-            .withdraw_all_token_types(
+            .deposit_all_token_types(
                 &user_key,
+                &deposit_a_key,
+                &mut deposit_a_account,
+                &deposit_b_key,
+                &mut deposit_b_account,
                 &pool_key,
                 &mut pool_account,
+                1_000,
+                1_000_000,
+                4_000_000,
+            )
+            .unwrap();
+        let after_deposit = SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .price_accumulator()
+            .cloned()
+            .unwrap();
+        expected.update(
+            swap_curve.calculator.as_ref(),
+            swap_token_a_amount.into(),
+            swap_token_b_amount.into(),
+            1_100,
+        );
+        assert_eq!(after_deposit, expected);
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .token_balances(),
+            Some((
+                token_amount(&accounts.token_a_account),
+                token_amount(&accounts.token_b_account)
+            ))
+        );
+
+        // swaps in accounts of the original size have no accumulator
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, fees, swap_curve, token_a_amount, token_b_amount);
+        accounts.swap_account = Account::new(0, SwapVersion::V1_LEN, &SWAP_PROGRAM_ID);
+        accounts.initialize_swap().unwrap();
+        assert_eq!(accounts.swap_account.data[0], 1);
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .price_accumulator(),
+            None
+        );
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        accounts
+            .swap(
+                &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                total_pool.try_into().unwrap(),
-                0,
+                50_000,
                 0,
             )
             .unwrap();
-
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, token_b_amount);
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(swap_token_a.amount, 0);
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(swap_token_b.amount, 0);
     }
 
     #[test]
-    fn test_withdraw_all_constant_price_curve() {
-        let trade_fee_numerator = 1;
-        let trade_fee_denominator = 10;
-        let owner_trade_fee_numerator = 1;
-        let owner_trade_fee_denominator = 30;
-        let owner_withdraw_fee_numerator = 0;
-        let owner_withdraw_fee_denominator = 30;
-        let host_fee_numerator = 10;
-        let host_fee_denominator = 100;
-
-        // initialize "unbalanced", so that withdrawing all will have some issues
-        // A: 1_000_000_000
-        // B: 2_000_000_000 (1_000 * 2_000_000)
-        let swap_token_a_amount = 1_000_000_000;
-        let swap_token_b_amount = 1_000;
-        let token_b_price = 2_000_000;
+    fn test_dynamic_fees() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let trade_fee_numerator = 10;
+        let max_trade_fee_numerator = 100;
         let fees = Fees {
             trade_fee_numerator,
-            trade_fee_denominator,
-            owner_trade_fee_numerator,
-            owner_trade_fee_denominator,
-            owner_withdraw_fee_numerator,
-            owner_withdraw_fee_denominator,
-            host_fee_numerator,
-            host_fee_denominator,
+            trade_fee_denominator: 10_000,
+            owner_trade_fee_numerator: 0,
+            owner_trade_fee_denominator: 0,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let dynamic_fees = DynamicFees {
+            max_trade_fee_numerator,
+            max_volatility: 1_000,
+            decay_period: 100,
         };
-
         let swap_curve = SwapCurve {
-            curve_type: CurveType::ConstantPrice,
-            calculator: Box::new(ConstantPriceCurve { token_b_price }),
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(ConstantProductCurve {}),
         };
-        let total_pool = swap_curve.calculator.new_pool_supply();
-        let user_key = Pubkey::new_unique();
-        let withdrawer_key = Pubkey::new_unique();
+        let token_a_amount = 10_000_000;
+        let token_b_amount = 10_000_000;
 
+        // the ceiling must be above the floor
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            fees,
-            swap_curve,
-            swap_token_a_amount,
-            swap_token_b_amount,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.dynamic_fees = DynamicFees {
+            max_trade_fee_numerator: trade_fee_numerator - 1,
+            ..dynamic_fees.clone()
+        };
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            accounts.initialize_swap()
         );
 
+        // older versions cannot hold the volatility
+        accounts.dynamic_fees = dynamic_fees.clone();
+        accounts.swap_account = Account::new(0, SwapVersion::V2_LEN, &SWAP_PROGRAM_ID);
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            accounts.initialize_swap()
+        );
+
+        set_test_slot(1_000);
+        accounts.swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
         accounts.initialize_swap().unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(token_swap.dynamic_fees(), Some(&dynamic_fees));
+        assert_eq!(
+            token_swap.volatility_accumulator(),
+            Some(&VolatilityAccumulator::new(1_000))
+        );
 
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            _pool_key,
-            _pool_account,
-        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 2_000_000, 0, 0);
+        let mut swap_a_to_b = |accounts: &mut SwapAccountInfo, amount_in: u64| {
+            let before = token_amount(&token_b_account);
+            accounts
+                .swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    amount_in,
+                    0,
+                )
+                .unwrap();
+            token_amount(&token_b_account) - before
+        };
+        let expected_amount_out = |accounts: &SwapAccountInfo, amount_in: u64, fees: &Fees| {
+            accounts
+                .swap_curve
+                .swap(
+                    amount_in.into(),
+                    token_amount(&accounts.token_a_account).into(),
+                    token_amount(&accounts.token_b_account).into(),
+                    TradeDirection::AtoB,
+                    fees,
+                )
+                .unwrap()
+                .destination_amount_swapped as u64
+        };
+
+        // a calm pool charges the floor, and a large trade moves the price
+        let expected = expected_amount_out(&accounts, 1_000_000, &fees);
+        assert_eq!(swap_a_to_b(&mut accounts, 1_000_000), expected);
+        let volatility = SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .volatility_accumulator()
+            .cloned()
+            .unwrap();
+        assert_eq!(volatility.last_update_slot, 1_000);
+        // the price of A drops from 1 B to about 0.83 B
+        assert!(volatility.volatility > 1_000);
+
+        // the next trade in the same slot pays the ceiling
+        let max_fees = Fees {
+            trade_fee_numerator: max_trade_fee_numerator,
+            ..fees.clone()
+        };
+        let expected = expected_amount_out(&accounts, 10_000, &max_fees);
+        assert!(expected < expected_amount_out(&accounts, 10_000, &fees));
+        assert_eq!(swap_a_to_b(&mut accounts, 10_000), expected);
+
+        // the volatility decays over the slots, and the fee with it
+        set_test_slot(1_000 + 100 * 8);
+        let mut decayed = SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .volatility_accumulator()
+            .cloned()
+            .unwrap();
+        decayed.decay(&dynamic_fees, 1_000 + 100 * 8);
+        assert!(decayed.volatility < dynamic_fees.max_volatility);
+        let decayed_fees = dynamic_fees.fees(&fees, decayed.volatility).unwrap();
+        assert!(decayed_fees.trade_fee_numerator < max_trade_fee_numerator);
+        let expected = expected_amount_out(&accounts, 10_000, &decayed_fees);
+        assert_eq!(swap_a_to_b(&mut accounts, 10_000), expected);
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .volatility_accumulator()
+                .unwrap()
+                .last_update_slot,
+            1_000 + 100 * 8
+        );
+
+        // dynamic fees are not allowed by constraints without bounds
+        let owner_key = &user_key.to_string();
+        let valid_curve_types = &[CurveType::ConstantProduct];
+        let constraints = Some(SwapConstraints {
+            owner_key,
+            valid_curve_types,
+            fees: &fees,
+            dynamic_fees: None,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.dynamic_fees = dynamic_fees.clone();
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            do_process_instruction_with_fee_constraints(
+                initialize_with_dynamic_fees(
+                    &SWAP_PROGRAM_ID,
+                    &spl_token::id(),
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &accounts.token_a_key,
+                    &accounts.token_b_key,
+                    &accounts.pool_mint_key,
+                    &accounts.pool_fee_key,
+                    &accounts.pool_token_key,
+                    accounts.nonce,
+                    accounts.fees.clone(),
+                    accounts.swap_curve.clone(),
+                    accounts.dynamic_fees.clone(),
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.pool_token_account,
+                    &mut Account::default(),
+                ],
+                &constraints,
+            )
+        );
+    }
+
+    #[test]
+    fn test_admin_instructions() {
+        let user_key = Pubkey::new_unique();
+        let swapper_key = Pubkey::new_unique();
+        let admin_key = Pubkey::new_unique();
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            owner_trade_fee_numerator: 1,
+            owner_trade_fee_denominator: 100,
+            owner_withdraw_fee_numerator: 0,
+            owner_withdraw_fee_denominator: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+        };
+        let amp = 100;
+        let swap_curve = SwapCurve {
+            curve_type: CurveType::Stable,
+            calculator: Box::new(StableCurve { amp }),
+        };
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+
+        // the admin authority needs an account of the latest size
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve.clone(),
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.swap_account = Account::new(0, SwapVersion::V3_LEN, &SWAP_PROGRAM_ID);
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            accounts.initialize_swap_with_admin(&admin_key)
+        );
+
+        set_test_unix_timestamp(1_000);
+        accounts.swap_account = Account::new(0, SwapVersion::LATEST_LEN, &SWAP_PROGRAM_ID);
+        accounts.initialize_swap_with_admin(&admin_key).unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(token_swap.admin_authority(), Some(&admin_key));
+        assert!(!token_swap.is_paused());
+        assert_eq!(token_swap.amp_ramp(), None);
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+
+        // only the admin authority can change the swap, and must sign
+        let new_fees = Fees {
+            trade_fee_numerator: 2,
+            ..fees.clone()
+        };
+        let wrong_admin_key = Pubkey::new_unique();
+        assert_eq!(
+            Err(SwapError::InvalidAdmin.into()),
+            do_process_instruction(
+                set_fees(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &wrong_admin_key,
+                    new_fees.clone(),
+                    DynamicFees::default(),
+                )
+                .unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+        let mut instruction = set_fees(
+            &SWAP_PROGRAM_ID,
+            &accounts.swap_key,
+            &admin_key,
+            new_fees.clone(),
+            DynamicFees::default(),
+        )
+        .unwrap();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            Err(SwapError::InvalidAdmin.into()),
+            do_process_instruction(
+                instruction,
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+
+        // fees are validated as on initialization
+        let invalid_fees = Fees {
+            trade_fee_numerator: 101,
+            ..fees.clone()
+        };
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            do_process_instruction(
+                set_fees(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &admin_key,
+                    invalid_fees,
+                    DynamicFees::default(),
+                )
+                .unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+        do_process_instruction(
+            set_fees(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &admin_key,
+                new_fees.clone(),
+                DynamicFees::default(),
+            )
+            .unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(*token_swap.fees(), new_fees);
 
+        // pausing stops swaps and deposits, but not withdrawals
+        do_process_instruction(
+            pause(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key).unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        assert!(SwapVersion::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .is_paused());
+        assert_eq!(
+            Err(SwapError::SwapPaused.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+            )
+        );
         let pool_key = accounts.pool_token_key;
         let mut pool_account = accounts.pool_token_account.clone();
-
-        // WithdrawAllTokenTypes will not take all token A and B, since their
-        // ratio is unbalanced.  It will try to take 1_500_000_000 worth of
-        // each token, which means 1_500_000_000 token A, and 750 token B.
-        // With no slippage, this will leave 250 token B in the pool.
+        let (deposit_a_key, mut deposit_a_account, deposit_b_key, mut deposit_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &user_key, 10_000, 10_000, 0);
         assert_eq!(
-            Err(SwapError::ExceededSlippage.into()),
-            accounts.withdraw_all_token_types(
+            Err(SwapError::SwapPaused.into()),
+            accounts.deposit_all_token_types(
                 &user_key,
+                &deposit_a_key,
+                &mut deposit_a_account,
+                &deposit_b_key,
+                &mut deposit_b_account,
                 &pool_key,
                 &mut pool_account,
-                &token_a_key,
-                &mut token_a_account,
-                &token_b_key,
-                &mut token_b_account,
-                total_pool.try_into().unwrap(),
-                swap_token_a_amount,
-                swap_token_b_amount,
+                1_000,
+                10_000,
+                10_000,
             )
         );
-
         accounts
             .withdraw_all_token_types(
                 &user_key,
                 &pool_key,
                 &mut pool_account,
+                &deposit_a_key,
+                &mut deposit_a_account,
+                &deposit_b_key,
+                &mut deposit_b_account,
+                1_000,
+                0,
+                0,
+            )
+            .unwrap();
+        do_process_instruction(
+            unpause(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key).unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        let expected = accounts
+            .swap_curve
+            .swap(
+                10_000,
+                token_amount(&accounts.token_a_account).into(),
+                token_amount(&accounts.token_b_account).into(),
+                TradeDirection::AtoB,
+                &new_fees,
+            )
+            .unwrap();
+        let before = token_amount(&token_b_account);
+        accounts
+            .swap(
+                &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                total_pool.try_into().unwrap(),
-                0,
+                10_000,
                 0,
             )
             .unwrap();
+        assert_eq!(
+            u128::from(token_amount(&token_b_account) - before),
+            expected.destination_amount_swapped
+        );
 
-        let token_a = spl_token::state::Account::unpack(&token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, swap_token_a_amount);
-        let token_b = spl_token::state::Account::unpack(&token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, 750);
-        let swap_token_a =
-            spl_token::state::Account::unpack(&accounts.token_a_account.data).unwrap();
-        assert_eq!(swap_token_a.amount, 0);
-        let swap_token_b =
-            spl_token::state::Account::unpack(&accounts.token_b_account.data).unwrap();
-        assert_eq!(swap_token_b.amount, 250);
-
-        // deposit now, not enough to cover the tokens already in there
-        let token_b_amount = 10;
-        let token_a_amount = token_b_amount * token_b_price;
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            pool_key,
-            mut pool_account,
-        ) = accounts.setup_token_accounts(
-            &user_key,
-            &withdrawer_key,
-            token_a_amount,
-            token_b_amount,
+        // the fee account must be a pool token account not owned by the swap
+        let (bad_fee_key, mut bad_fee_account) = mint_token(
+            &spl_token::id(),
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &accounts.authority_key,
+            &accounts.authority_key,
             0,
         );
-
         assert_eq!(
-            Err(SwapError::ExceededSlippage.into()),
-            accounts.deposit_all_token_types(
-                &withdrawer_key,
+            Err(SwapError::InvalidOutputOwner.into()),
+            do_process_instruction(
+                set_pool_fee_account(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &admin_key,
+                    &bad_fee_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut bad_fee_account,
+                ],
+            )
+        );
+        let (new_fee_key, mut new_fee_account) = mint_token(
+            &spl_token::id(),
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &accounts.authority_key,
+            &admin_key,
+            0,
+        );
+        do_process_instruction(
+            set_pool_fee_account(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &admin_key,
+                &new_fee_key,
+            )
+            .unwrap(),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut new_fee_account,
+            ],
+        )
+        .unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(*token_swap.pool_fee_account(), new_fee_key);
+        // trading fees now go to the new account only
+        assert_eq!(
+            Err(SwapError::IncorrectFeeAccount.into()),
+            accounts.swap(
+                &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                &pool_key,
-                &mut pool_account,
-                1, // doesn't matter
-                token_a_amount,
-                token_b_amount,
+                10_000,
+                0,
             )
         );
+        accounts.pool_fee_key = new_fee_key;
+        accounts.pool_fee_account = new_fee_account;
 
-        // deposit enough tokens, success!
-        let token_b_amount = 125;
-        let token_a_amount = token_b_amount * token_b_price;
-        let (
-            token_a_key,
-            mut token_a_account,
-            token_b_key,
-            mut token_b_account,
-            pool_key,
-            mut pool_account,
-        ) = accounts.setup_token_accounts(
-            &user_key,
-            &withdrawer_key,
-            token_a_amount,
-            token_b_amount,
-            0,
+        // ramps must be slow enough
+        let too_fast = RampAmp {
+            target_amp: 1_000,
+            stop_ramp_timestamp: 1_000 + MIN_RAMP_DURATION - 1,
+        };
+        assert_eq!(
+            Err(SwapError::InvalidAmpRamp.into()),
+            do_process_instruction(
+                ramp_amp(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key, too_fast).unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+        let ramp = RampAmp {
+            target_amp: 1_000,
+            stop_ramp_timestamp: 1_000 + MIN_RAMP_DURATION,
+        };
+        do_process_instruction(
+            ramp_amp(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key, ramp).unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            token_swap.amp_ramp(),
+            Some(&AmpRamp {
+                initial_amp: amp,
+                target_amp: 1_000,
+                start_timestamp: 1_000,
+                stop_timestamp: 1_000 + MIN_RAMP_DURATION,
+            })
         );
 
+        // halfway through the ramp, swaps use the interpolated amp
+        set_test_unix_timestamp(1_000 + MIN_RAMP_DURATION / 2);
+        let swap_amount_out = |accounts: &SwapAccountInfo, amp: u64| {
+            SwapCurve {
+                curve_type: CurveType::Stable,
+                calculator: Box::new(StableCurve { amp }),
+            }
+            .swap(
+                10_000,
+                token_amount(&accounts.token_a_account).into(),
+                token_amount(&accounts.token_b_account).into(),
+                TradeDirection::AtoB,
+                &new_fees,
+            )
+            .unwrap()
+            .destination_amount_swapped
+        };
+        let expected = swap_amount_out(&accounts, 550);
+        assert!(expected > swap_amount_out(&accounts, amp));
+        let before = token_amount(&token_b_account);
         accounts
-            .deposit_all_token_types(
-                &withdrawer_key,
+            .swap(
+                &swapper_key,
                 &token_a_key,
                 &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
                 &token_b_key,
                 &mut token_b_account,
-                &pool_key,
-                &mut pool_account,
-                1, // doesn't matter
-                token_a_amount,
-                token_b_amount,
+                10_000,
+                0,
             )
             .unwrap();
+        assert_eq!(
+            u128::from(token_amount(&token_b_account) - before),
+            expected
+        );
+
+        // a new ramp starts from the current amp
+        set_test_unix_timestamp(1_000 + MIN_RAMP_DURATION * 2);
+        let ramp = RampAmp {
+            target_amp: 100,
+            stop_ramp_timestamp: 1_000 + MIN_RAMP_DURATION * 3,
+        };
+        do_process_instruction(
+            ramp_amp(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key, ramp).unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+        )
+        .unwrap();
+        let token_swap = SwapVersion::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(token_swap.amp_ramp().unwrap().initial_amp, 1_000);
+        assert_eq!(
+            *token_swap.swap_curve(),
+            SwapCurve {
+                curve_type: CurveType::Stable,
+                calculator: Box::new(StableCurve { amp: 1_000 }),
+            }
+        );
+
+        // only stable curves can be ramped
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(ConstantProductCurve {}),
+            },
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.initialize_swap_with_admin(&admin_key).unwrap();
+        let ramp = RampAmp {
+            target_amp: 1_000,
+            stop_ramp_timestamp: 1_000 + MIN_RAMP_DURATION * 3,
+        };
+        assert_eq!(
+            Err(SwapError::UnsupportedCurveOperation.into()),
+            do_process_instruction(
+                ramp_amp(&SWAP_PROGRAM_ID, &accounts.swap_key, &admin_key, ramp).unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+
+        // swaps without an admin authority cannot be changed
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            swap_curve,
+            token_a_amount,
+            token_b_amount,
+        );
+        accounts.initialize_swap().unwrap();
+        assert_eq!(
+            SwapVersion::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .admin_authority(),
+            None
+        );
+        assert_eq!(
+            Err(SwapError::InvalidAdmin.into()),
+            do_process_instruction(
+                pause(&SWAP_PROGRAM_ID, &accounts.swap_key, &Pubkey::default()).unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+            )
+        );
+
+        // under constraints, only the program owner may be the admin authority
+        let owner_key = &user_key.to_string();
+        let valid_curve_types = &[CurveType::ConstantProduct];
+        let constraints = Some(SwapConstraints {
+            owner_key,
+            valid_curve_types,
+            fees: &fees,
+            dynamic_fees: None,
+        });
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            fees.clone(),
+            SwapCurve {
+                curve_type: CurveType::ConstantProduct,
+                calculator: Box::new(ConstantProductCurve {}),
+            },
+            token_a_amount,
+            token_b_amount,
+        );
+        let initialize_instruction = |accounts: &SwapAccountInfo, admin_key: &Pubkey| {
+            initialize_with_admin(
+                &SWAP_PROGRAM_ID,
+                &spl_token::id(),
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &accounts.token_a_key,
+                &accounts.token_b_key,
+                &accounts.pool_mint_key,
+                &accounts.pool_fee_key,
+                &accounts.pool_token_key,
+                admin_key,
+                accounts.nonce,
+                accounts.fees.clone(),
+                accounts.swap_curve.clone(),
+                DynamicFees::default(),
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            Err(SwapError::InvalidAdmin.into()),
+            do_process_instruction_with_fee_constraints(
+                initialize_instruction(&accounts, &admin_key),
+                vec![
+                    &mut accounts.swap_account,
+                    &mut Account::default(),
+                    &mut accounts.token_a_account,
+                    &mut accounts.token_b_account,
+                    &mut accounts.pool_mint_account,
+                    &mut accounts.pool_fee_account,
+                    &mut accounts.pool_token_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+                &constraints,
+            )
+        );
+        do_process_instruction_with_fee_constraints(
+            initialize_instruction(&accounts, &user_key),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut accounts.token_a_account,
+                &mut accounts.token_b_account,
+                &mut accounts.pool_mint_account,
+                &mut accounts.pool_fee_account,
+                &mut accounts.pool_token_account,
+                &mut Account::default(),
+                &mut Account::default(),
+            ],
+            &constraints,
+        )
+        .unwrap();
+        // and fees stay within the constraints
+        let lower_fees = Fees {
+            trade_fee_numerator: 0,
+            ..fees.clone()
+        };
+        assert_eq!(
+            Err(SwapError::InvalidFee.into()),
+            do_process_instruction_with_fee_constraints(
+                set_fees(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &user_key,
+                    lower_fees,
+                    DynamicFees::default(),
+                )
+                .unwrap(),
+                vec![&mut accounts.swap_account, &mut Account::default()],
+                &constraints,
+            )
+        );
+        do_process_instruction_with_fee_constraints(
+            pause(&SWAP_PROGRAM_ID, &accounts.swap_key, &user_key).unwrap(),
+            vec![&mut accounts.swap_account, &mut Account::default()],
+            &constraints,
+        )
+        .unwrap();
     }
 }
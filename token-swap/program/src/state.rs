@@ -1,9 +1,19 @@
 //! State transition types
 
-use crate::curve::{base::SwapCurve, fees::Fees};
+use crate::{
+    curve::{
+        base::{CurveType, SwapCurve},
+        fees::{DynamicFees, Fees, VolatilityAccumulator},
+        stable::AmpRamp,
+        weighted::WeightedCurve,
+    },
+    error::SwapError,
+    oracle::PriceAccumulator,
+};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use enum_dispatch::enum_dispatch;
 use solana_program::{
+    clock::UnixTimestamp,
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
@@ -37,13 +47,69 @@ pub trait SwapState {
     fn fees(&self) -> &Fees;
     /// Curve associated with swap
     fn swap_curve(&self) -> &SwapCurve;
+
+    /// Cumulative prices of the swap, if tracked by this version
+    fn price_accumulator(&self) -> Option<&PriceAccumulator>;
+    /// Balances of tokens A and B as of the last instruction that changed
+    /// them, from which prices are accumulated, if recorded by this version
+    fn token_balances(&self) -> Option<(u64, u64)>;
+
+    /// Dynamic trade fee configuration, if supported by this version and
+    /// enabled
+    fn dynamic_fees(&self) -> Option<&DynamicFees>;
+    /// Recent volatility of the swap, if tracked by this version
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator>;
+
+    /// Authority allowed to change the swap, if any
+    fn admin_authority(&self) -> Option<&Pubkey>;
+    /// Whether swaps and deposits are paused by the admin authority
+    fn is_paused(&self) -> bool;
+    /// Ramp of the amplification coefficient of a stable curve, if the swap
+    /// has ever ramped it
+    fn amp_ramp(&self) -> Option<&AmpRamp>;
+
+    /// Number of tokens held by the swap, two for all but weighted swaps
+    fn num_tokens(&self) -> usize {
+        2
+    }
+    /// Address of the liquidity account of the token at the given index,
+    /// tokens A and B being the first two
+    fn token_account(&self, index: usize) -> Option<&Pubkey> {
+        match index {
+            0 => Some(self.token_a_account()),
+            1 => Some(self.token_b_account()),
+            _ => None,
+        }
+    }
+    /// Address of the mint of the token at the given index
+    fn token_mint(&self, index: usize) -> Option<&Pubkey> {
+        match index {
+            0 => Some(self.token_a_mint()),
+            1 => Some(self.token_b_mint()),
+            _ => None,
+        }
+    }
 }
 
 /// All versions of SwapState
 #[enum_dispatch(SwapState)]
+#[derive(Debug)]
 pub enum SwapVersion {
-    /// Latest version, used for all new swaps
+    /// Original version, still used for new swaps in accounts too small for
+    /// the latest version
     SwapV1,
+    /// Version with price accumulators, still used for new swaps in
+    /// accounts too small for the latest version
+    SwapV2,
+    /// Version with price accumulators and an optional dynamic trade fee,
+    /// still used for new swaps in accounts too small for the latest version
+    SwapV3,
+    /// Latest version, used for all new swaps, with an optional admin
+    /// authority
+    SwapV4,
+    /// Weighted swap of more than two tokens, seen as a swap of its first two
+    /// tokens by users of `SwapState` that only know about tokens A and B
+    WeightedSwapV1,
 }
 
 /// SwapVersion does not implement program_pack::Pack because there are size
@@ -51,7 +117,15 @@ pub enum SwapVersion {
 /// special implementations are provided here
 impl SwapVersion {
     /// Size of the latest version of the SwapState
-    pub const LATEST_LEN: usize = 1 + SwapV1::LEN; // add one for the version enum
+    pub const LATEST_LEN: usize = 1 + SwapV4::LEN; // add one for the version enum
+    /// Size of the original version of the SwapState
+    pub const V1_LEN: usize = 1 + SwapV1::LEN;
+    /// Size of the version of the SwapState with price accumulators
+    pub const V2_LEN: usize = 1 + SwapV2::LEN;
+    /// Size of the version of the SwapState with a dynamic trade fee
+    pub const V3_LEN: usize = 1 + SwapV3::LEN;
+    /// Size of a weighted swap of any number of tokens
+    pub const WEIGHTED_LEN: usize = 1 + WeightedSwapV1::LEN;
 
     /// Pack a swap into a byte array, based on its version
     pub fn pack(src: Self, dst: &mut [u8]) -> Result<(), ProgramError> {
@@ -60,17 +134,111 @@ impl SwapVersion {
                 dst[0] = 1;
                 SwapV1::pack(swap_info, &mut dst[1..])
             }
+            // version 2 is taken by weighted swaps
+            Self::SwapV2(swap_info) => {
+                dst[0] = 3;
+                SwapV2::pack(swap_info, &mut dst[1..])
+            }
+            Self::SwapV3(swap_info) => {
+                dst[0] = 4;
+                SwapV3::pack(swap_info, &mut dst[1..])
+            }
+            Self::SwapV4(swap_info) => {
+                dst[0] = 5;
+                SwapV4::pack(swap_info, &mut dst[1..])
+            }
+            Self::WeightedSwapV1(swap_info) => {
+                dst[0] = 2;
+                WeightedSwapV1::pack(swap_info, &mut dst[1..])
+            }
+        }
+    }
+
+    /// Pack an updated price accumulator into a swap account, with the token
+    /// balances to accumulate from next time, without touching the rest of the
+    /// swap
+    pub fn pack_price_accumulator(
+        accumulator: &PriceAccumulator,
+        token_a_balance: u64,
+        token_b_balance: u64,
+        dst: &mut [u8],
+    ) -> Result<(), ProgramError> {
+        match dst.first() {
+            Some(3) | Some(4) | Some(5) if dst.len() >= Self::V2_LEN => {
+                let dst = array_mut_ref![dst, 1 + SwapV1::LEN, PriceAccumulator::LEN + 16];
+                let (packed_accumulator, packed_token_a_balance, packed_token_b_balance) =
+                    mut_array_refs![dst, PriceAccumulator::LEN, 8, 8];
+                accumulator.pack_into_slice(packed_accumulator);
+                *packed_token_a_balance = token_a_balance.to_le_bytes();
+                *packed_token_b_balance = token_b_balance.to_le_bytes();
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Pack an updated volatility accumulator into a swap account, without
+    /// touching the rest of the swap
+    pub fn pack_volatility_accumulator(
+        accumulator: &VolatilityAccumulator,
+        dst: &mut [u8],
+    ) -> Result<(), ProgramError> {
+        match dst.first() {
+            Some(4) | Some(5) if dst.len() >= Self::V3_LEN => {
+                accumulator.pack_into_slice(
+                    &mut dst[Self::V3_LEN - VolatilityAccumulator::LEN..Self::V3_LEN],
+                );
+                Ok(())
+            }
+            Some(2) if dst.len() >= Self::WEIGHTED_LEN => {
+                let offset = 1 + WeightedSwapV1::VOLATILITY_ACCUMULATOR_OFFSET;
+                accumulator.pack_into_slice(&mut dst[offset..offset + VolatilityAccumulator::LEN]);
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidAccountData),
         }
     }
 
     /// Unpack the swap account based on its version, returning the result as a
     /// SwapState trait object
     pub fn unpack(input: &[u8]) -> Result<Box<dyn SwapState>, ProgramError> {
+        Ok(Box::new(Self::unpack_version(input)?))
+    }
+
+    /// Unpack the swap account based on its version, for the program to
+    /// handle the versions that need it separately
+    pub fn unpack_version(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, rest) = input
+            .split_first()
+            .ok_or(ProgramError::InvalidAccountData)?;
+        match version {
+            1 => Ok(Self::SwapV1(SwapV1::unpack(rest)?)),
+            2 => Ok(Self::WeightedSwapV1(WeightedSwapV1::unpack(rest)?)),
+            3 => Ok(Self::SwapV2(SwapV2::unpack(rest)?)),
+            4 => Ok(Self::SwapV3(SwapV3::unpack(rest)?)),
+            5 => Ok(Self::SwapV4(SwapV4::unpack(rest)?)),
+            _ => Err(ProgramError::UninitializedAccount),
+        }
+    }
+
+    /// Unpack a swap account of a version with an admin authority, the latest
+    /// two-token version or a weighted swap, to be changed by an admin
+    /// instruction
+    pub fn unpack_with_admin(input: &[u8]) -> Result<Self, ProgramError> {
+        match input.first() {
+            Some(1) | Some(3) | Some(4) => Err(SwapError::InvalidAdmin.into()),
+            _ => Self::unpack_version(input),
+        }
+    }
+
+    /// Unpack a weighted swap account
+    pub fn unpack_weighted(input: &[u8]) -> Result<WeightedSwapV1, ProgramError> {
         let (&version, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidAccountData)?;
         match version {
-            1 => Ok(Box::new(SwapV1::unpack(rest)?)),
+            1 | 3 | 4 | 5 => Err(SwapError::UnsupportedCurveOperation.into()),
+            2 => WeightedSwapV1::unpack(rest),
             _ => Err(ProgramError::UninitializedAccount),
         }
     }
@@ -170,6 +338,34 @@ impl SwapState for SwapV1 {
     fn swap_curve(&self) -> &SwapCurve {
         &self.swap_curve
     }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        None
+    }
+
+    fn token_balances(&self) -> Option<(u64, u64)> {
+        None
+    }
+
+    fn dynamic_fees(&self) -> Option<&DynamicFees> {
+        None
+    }
+
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator> {
+        None
+    }
+
+    fn admin_authority(&self) -> Option<&Pubkey> {
+        None
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn amp_ramp(&self) -> Option<&AmpRamp> {
+        None
+    }
 }
 
 impl Sealed for SwapV1 {}
@@ -247,130 +443,1840 @@ impl Pack for SwapV1 {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::curve::stable::StableCurve;
+/// Program states, with cumulative prices for time-weighted average prices.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV2 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Nonce used in program address.
+    /// The program address is created deterministically with the nonce,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub nonce: u8,
 
-    use std::convert::TryInto;
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
 
-    const TEST_FEES: Fees = Fees {
-        trade_fee_numerator: 1,
-        trade_fee_denominator: 4,
-        owner_trade_fee_numerator: 3,
-        owner_trade_fee_denominator: 10,
-        owner_withdraw_fee_numerator: 2,
-        owner_withdraw_fee_denominator: 7,
-        host_fee_numerator: 5,
-        host_fee_denominator: 20,
-    };
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
 
-    const TEST_NONCE: u8 = 255;
-    const TEST_TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1u8; 32]);
-    const TEST_TOKEN_A: Pubkey = Pubkey::new_from_array([2u8; 32]);
-    const TEST_TOKEN_B: Pubkey = Pubkey::new_from_array([3u8; 32]);
-    const TEST_POOL_MINT: Pubkey = Pubkey::new_from_array([4u8; 32]);
-    const TEST_TOKEN_A_MINT: Pubkey = Pubkey::new_from_array([5u8; 32]);
-    const TEST_TOKEN_B_MINT: Pubkey = Pubkey::new_from_array([6u8; 32]);
-    const TEST_POOL_FEE_ACCOUNT: Pubkey = Pubkey::new_from_array([7u8; 32]);
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
 
-    const TEST_CURVE_TYPE: u8 = 2;
-    const TEST_AMP: u64 = 1;
-    const TEST_CURVE: StableCurve = StableCurve { amp: TEST_AMP };
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
 
-    #[test]
-    fn swap_version_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Box::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapVersion::SwapV1(SwapV1 {
-            is_initialized: true,
-            nonce: TEST_NONCE,
-            token_program_id: TEST_TOKEN_PROGRAM_ID,
-            token_a: TEST_TOKEN_A,
-            token_b: TEST_TOKEN_B,
-            pool_mint: TEST_POOL_MINT,
-            token_a_mint: TEST_TOKEN_A_MINT,
-            token_b_mint: TEST_TOKEN_B_MINT,
-            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
-            fees: TEST_FEES,
-            swap_curve: swap_curve.clone(),
-        });
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
 
-        let mut packed = [0u8; SwapVersion::LATEST_LEN];
-        SwapVersion::pack(swap_info, &mut packed).unwrap();
-        let unpacked = SwapVersion::unpack(&packed).unwrap();
+    /// All fee information
+    pub fees: Fees,
 
-        assert!(unpacked.is_initialized());
-        assert_eq!(unpacked.nonce(), TEST_NONCE);
-        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
-        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
-        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
-        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
-        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
-        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
-        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
-        assert_eq!(*unpacked.fees(), TEST_FEES);
-        assert_eq!(*unpacked.swap_curve(), swap_curve);
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Cumulative prices of token A and B, updated from the recorded balances
+    /// before every change to the pool balances
+    pub price_accumulator: PriceAccumulator,
+    /// Balance of the token A liquidity account as of the last instruction
+    /// that changed it.  Prices are accumulated from the recorded balances,
+    /// so that tokens sent straight to the liquidity accounts don't move them.
+    pub token_a_balance: u64,
+    /// Balance of the token B liquidity account as of the last instruction
+    /// that changed it
+    pub token_b_balance: u64,
+}
+
+impl SwapState for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
     }
 
-    #[test]
-    fn swap_v1_pack() {
-        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
-        let calculator = Box::new(TEST_CURVE);
-        let swap_curve = SwapCurve {
-            curve_type,
-            calculator,
-        };
-        let swap_info = SwapV1 {
-            is_initialized: true,
-            nonce: TEST_NONCE,
-            token_program_id: TEST_TOKEN_PROGRAM_ID,
-            token_a: TEST_TOKEN_A,
-            token_b: TEST_TOKEN_B,
-            pool_mint: TEST_POOL_MINT,
-            token_a_mint: TEST_TOKEN_A_MINT,
-            token_b_mint: TEST_TOKEN_B_MINT,
-            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
-            fees: TEST_FEES,
-            swap_curve,
-        };
+    fn nonce(&self) -> u8 {
+        self.nonce
+    }
 
-        let mut packed = [0u8; SwapV1::LEN];
-        SwapV1::pack_into_slice(&swap_info, &mut packed);
-        let unpacked = SwapV1::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
 
-        let mut packed = vec![1u8, TEST_NONCE];
-        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
-        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
-        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
-        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
-        packed.push(TEST_CURVE_TYPE);
-        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
-        packed.extend_from_slice(&[0u8; 24]);
-        let unpacked = SwapV1::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
 
-        let packed = [0u8; SwapV1::LEN];
-        let swap_info: SwapV1 = Default::default();
-        let unpack_unchecked = SwapV1::unpack_unchecked(&packed).unwrap();
-        assert_eq!(unpack_unchecked, swap_info);
-        let err = SwapV1::unpack(&packed).unwrap_err();
-        assert_eq!(err, ProgramError::UninitializedAccount);
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        Some(&self.price_accumulator)
+    }
+
+    fn token_balances(&self) -> Option<(u64, u64)> {
+        Some((self.token_a_balance, self.token_b_balance))
+    }
+
+    fn dynamic_fees(&self) -> Option<&DynamicFees> {
+        None
+    }
+
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator> {
+        None
+    }
+
+    fn admin_authority(&self) -> Option<&Pubkey> {
+        None
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn amp_ramp(&self) -> Option<&AmpRamp> {
+        None
+    }
+}
+
+impl Sealed for SwapV2 {}
+impl IsInitialized for SwapV2 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV2 {
+    const LEN: usize = 379;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 379];
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        self.price_accumulator
+            .pack_into_slice(&mut price_accumulator[..]);
+        *token_a_balance = self.token_a_balance.to_le_bytes();
+        *token_b_balance = self.token_b_balance.to_le_bytes();
+    }
+
+    /// Unpacks a byte buffer into a [SwapV2](struct.SwapV2.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 379];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            price_accumulator: PriceAccumulator::unpack_from_slice(price_accumulator)?,
+            token_a_balance: u64::from_le_bytes(*token_a_balance),
+            token_b_balance: u64::from_le_bytes(*token_b_balance),
+        })
+    }
+}
+
+/// Program states, with cumulative prices for time-weighted average prices and
+/// an optional dynamic trade fee.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV3 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Nonce used in program address.
+    /// The program address is created deterministically with the nonce,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub nonce: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Cumulative prices of token A and B, updated from the recorded balances
+    /// before every change to the pool balances
+    pub price_accumulator: PriceAccumulator,
+    /// Balance of the token A liquidity account as of the last instruction
+    /// that changed it.  Prices are accumulated from the recorded balances,
+    /// so that tokens sent straight to the liquidity accounts don't move them.
+    pub token_a_balance: u64,
+    /// Balance of the token B liquidity account as of the last instruction
+    /// that changed it
+    pub token_b_balance: u64,
+
+    /// Dynamic trade fee configuration, all zeros if disabled
+    pub dynamic_fees: DynamicFees,
+
+    /// Recent volatility of the price, updated on every trade
+    pub volatility_accumulator: VolatilityAccumulator,
+}
+
+impl SwapState for SwapV3 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn nonce(&self) -> u8 {
+        self.nonce
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        Some(&self.price_accumulator)
+    }
+
+    fn token_balances(&self) -> Option<(u64, u64)> {
+        Some((self.token_a_balance, self.token_b_balance))
+    }
+
+    fn dynamic_fees(&self) -> Option<&DynamicFees> {
+        if self.dynamic_fees.is_enabled() {
+            Some(&self.dynamic_fees)
+        } else {
+            None
+        }
+    }
+
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator> {
+        Some(&self.volatility_accumulator)
+    }
+
+    fn admin_authority(&self) -> Option<&Pubkey> {
+        None
+    }
+
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    fn amp_ramp(&self) -> Option<&AmpRamp> {
+        None
+    }
+}
+
+impl Sealed for SwapV3 {}
+impl IsInitialized for SwapV3 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV3 {
+    const LEN: usize = 419;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 419];
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+            dynamic_fees,
+            volatility_accumulator,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8, 24, 16];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        self.price_accumulator
+            .pack_into_slice(&mut price_accumulator[..]);
+        *token_a_balance = self.token_a_balance.to_le_bytes();
+        *token_b_balance = self.token_b_balance.to_le_bytes();
+        self.dynamic_fees.pack_into_slice(&mut dynamic_fees[..]);
+        self.volatility_accumulator
+            .pack_into_slice(&mut volatility_accumulator[..]);
+    }
+
+    /// Unpacks a byte buffer into a [SwapV3](struct.SwapV3.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 419];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+            dynamic_fees,
+            volatility_accumulator,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8, 24, 16];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            price_accumulator: PriceAccumulator::unpack_from_slice(price_accumulator)?,
+            token_a_balance: u64::from_le_bytes(*token_a_balance),
+            token_b_balance: u64::from_le_bytes(*token_b_balance),
+            dynamic_fees: DynamicFees::unpack_from_slice(dynamic_fees)?,
+            volatility_accumulator: VolatilityAccumulator::unpack_from_slice(
+                volatility_accumulator,
+            )?,
+        })
+    }
+}
+
+/// Program states, with cumulative prices for time-weighted average prices, an
+/// optional dynamic trade fee, and an optional admin authority.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct SwapV4 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Nonce used in program address.
+    /// The program address is created deterministically with the nonce,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token A account, token B account, and pool
+    /// token mint.
+    pub nonce: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Token A
+    pub token_a: Pubkey,
+    /// Token B
+    pub token_b: Pubkey,
+
+    /// Pool tokens are issued when A or B tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original A or B token.
+    pub pool_mint: Pubkey,
+
+    /// Mint information for token A
+    pub token_a_mint: Pubkey,
+    /// Mint information for token B
+    pub token_b_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Swap curve parameters, to be unpacked and used by the SwapCurve, which
+    /// calculates swaps, deposits, and withdrawals
+    pub swap_curve: SwapCurve,
+
+    /// Cumulative prices of token A and B, updated from the recorded balances
+    /// before every change to the pool balances
+    pub price_accumulator: PriceAccumulator,
+    /// Balance of the token A liquidity account as of the last instruction
+    /// that changed it.  Prices are accumulated from the recorded balances,
+    /// so that tokens sent straight to the liquidity accounts don't move them.
+    pub token_a_balance: u64,
+    /// Balance of the token B liquidity account as of the last instruction
+    /// that changed it
+    pub token_b_balance: u64,
+
+    /// Dynamic trade fee configuration, all zeros if disabled
+    pub dynamic_fees: DynamicFees,
+
+    /// Recent volatility of the price, updated on every trade
+    pub volatility_accumulator: VolatilityAccumulator,
+
+    /// Authority allowed to change the fees, pool fee account and curve of
+    /// the swap, and to pause it, all zeros if none
+    pub admin_authority: Pubkey,
+
+    /// Whether swaps and deposits are paused by the admin authority.
+    /// Withdrawals are always allowed.
+    pub is_paused: bool,
+
+    /// Ramp of the amplification coefficient of a stable curve, all zeros if
+    /// never ramped
+    pub amp_ramp: AmpRamp,
+}
+
+impl SwapState for SwapV4 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn nonce(&self) -> u8 {
+        self.nonce
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.token_a
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.token_b
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.token_a_mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.token_b_mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        Some(&self.price_accumulator)
+    }
+
+    fn token_balances(&self) -> Option<(u64, u64)> {
+        Some((self.token_a_balance, self.token_b_balance))
+    }
+
+    fn dynamic_fees(&self) -> Option<&DynamicFees> {
+        if self.dynamic_fees.is_enabled() {
+            Some(&self.dynamic_fees)
+        } else {
+            None
+        }
+    }
+
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator> {
+        Some(&self.volatility_accumulator)
+    }
+
+    fn admin_authority(&self) -> Option<&Pubkey> {
+        if self.admin_authority == Pubkey::default() {
+            None
+        } else {
+            Some(&self.admin_authority)
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    fn amp_ramp(&self) -> Option<&AmpRamp> {
+        if self.amp_ramp.is_set() {
+            Some(&self.amp_ramp)
+        } else {
+            None
+        }
+    }
+}
+
+impl Sealed for SwapV4 {}
+impl IsInitialized for SwapV4 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapV4 {
+    const LEN: usize = 484;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 484];
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+            dynamic_fees,
+            volatility_accumulator,
+            admin_authority,
+            is_paused,
+            amp_ramp,
+        ) = mut_array_refs![
+            output, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8, 24, 16, 32, 1, 32
+        ];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        token_a.copy_from_slice(self.token_a.as_ref());
+        token_b.copy_from_slice(self.token_b.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        token_a_mint.copy_from_slice(self.token_a_mint.as_ref());
+        token_b_mint.copy_from_slice(self.token_b_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.swap_curve.pack_into_slice(&mut swap_curve[..]);
+        self.price_accumulator
+            .pack_into_slice(&mut price_accumulator[..]);
+        *token_a_balance = self.token_a_balance.to_le_bytes();
+        *token_b_balance = self.token_b_balance.to_le_bytes();
+        self.dynamic_fees.pack_into_slice(&mut dynamic_fees[..]);
+        self.volatility_accumulator
+            .pack_into_slice(&mut volatility_accumulator[..]);
+        admin_authority.copy_from_slice(self.admin_authority.as_ref());
+        is_paused[0] = self.is_paused as u8;
+        self.amp_ramp.pack_into_slice(&mut amp_ramp[..]);
+    }
+
+    /// Unpacks a byte buffer into a [SwapV4](struct.SwapV4.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 484];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            pool_fee_account,
+            fees,
+            swap_curve,
+            price_accumulator,
+            token_a_balance,
+            token_b_balance,
+            dynamic_fees,
+            volatility_accumulator,
+            admin_authority,
+            is_paused,
+            amp_ramp,
+        ) = array_refs![
+            input, 1, 1, 32, 32, 32, 32, 32, 32, 32, 64, 33, 40, 8, 8, 24, 16, 32, 1, 32
+        ];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            token_a: Pubkey::new_from_array(*token_a),
+            token_b: Pubkey::new_from_array(*token_b),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            token_a_mint: Pubkey::new_from_array(*token_a_mint),
+            token_b_mint: Pubkey::new_from_array(*token_b_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            swap_curve: SwapCurve::unpack_from_slice(swap_curve)?,
+            price_accumulator: PriceAccumulator::unpack_from_slice(price_accumulator)?,
+            token_a_balance: u64::from_le_bytes(*token_a_balance),
+            token_b_balance: u64::from_le_bytes(*token_b_balance),
+            dynamic_fees: DynamicFees::unpack_from_slice(dynamic_fees)?,
+            volatility_accumulator: VolatilityAccumulator::unpack_from_slice(
+                volatility_accumulator,
+            )?,
+            admin_authority: Pubkey::new_from_array(*admin_authority),
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            amp_ramp: AmpRamp::unpack_from_slice(amp_ramp)?,
+        })
+    }
+}
+
+/// Token held by a weighted swap
+#[repr(C)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WeightedToken {
+    /// Liquidity account of the token
+    pub account: Pubkey,
+    /// Mint information for the token
+    pub mint: Pubkey,
+    /// Weight of the token in the invariant, relative to the other tokens
+    pub weight: u64,
+    /// Balance of the liquidity account as of the last instruction that
+    /// changed it.  Swaps only pass the accounts of the two tokens traded, so
+    /// the prices involving the other tokens are accumulated from these.
+    pub balance: u64,
+}
+
+impl WeightedToken {
+    /// Two-token curve for swapping from the `source` token, as token A, to
+    /// the `destination` token
+    pub fn swap_curve(source: &Self, destination: &Self) -> SwapCurve {
+        SwapCurve {
+            curve_type: CurveType::Weighted,
+            calculator: Box::new(WeightedCurve {
+                token_a_weight: source.weight,
+                token_b_weight: destination.weight,
+            }),
+        }
+    }
+}
+
+/// Token A or B of a weighted swap without tokens, which is never initialized
+static EMPTY_WEIGHTED_TOKEN: WeightedToken = WeightedToken {
+    account: Pubkey::new_from_array([0u8; 32]),
+    mint: Pubkey::new_from_array([0u8; 32]),
+    weight: 0,
+    balance: 0,
+};
+
+/// Weighted swap state, holding between `MIN_TOKENS` and `MAX_TOKENS` tokens.
+///
+/// Tokens A and B of the swap, as seen through `SwapState`, are its first two
+/// tokens.
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct WeightedSwapV1 {
+    /// Initialized state.
+    pub is_initialized: bool,
+    /// Nonce used in program address.
+    /// The program address is created deterministically with the nonce,
+    /// swap program id, and swap account pubkey.  This program address has
+    /// authority over the swap's token accounts and pool token mint.
+    pub nonce: u8,
+
+    /// Program ID of the tokens being exchanged.
+    pub token_program_id: Pubkey,
+
+    /// Pool tokens are issued when tokens are deposited.
+    /// Pool tokens can be withdrawn back to the original tokens.
+    pub pool_mint: Pubkey,
+
+    /// Pool token account to receive trading and / or withdrawal fees
+    pub pool_fee_account: Pubkey,
+
+    /// All fee information
+    pub fees: Fees,
+
+    /// Dynamic trade fee configuration, all zeros if disabled
+    pub dynamic_fees: DynamicFees,
+
+    /// Recent volatility of the prices, updated on every trade with the
+    /// price of the pair traded
+    pub volatility_accumulator: VolatilityAccumulator,
+
+    /// Authority allowed to change the fees and pool fee account of the swap,
+    /// and to pause it, all zeros if none
+    pub admin_authority: Pubkey,
+
+    /// Whether swaps and deposits are paused by the admin authority.
+    /// Withdrawals are always allowed.
+    pub is_paused: bool,
+
+    /// Tokens of the swap, in the order given at initialization
+    pub tokens: Vec<WeightedToken>,
+
+    /// Cumulative prices of the first token paired with each other token, in
+    /// order, the first token being token A of each pair.  Updated from the
+    /// recorded token balances before every change to the pool balances.
+    pub price_accumulators: Vec<PriceAccumulator>,
+
+    /// Curve between tokens A and B, derived from the weights of the first two
+    /// tokens rather than stored
+    pub swap_curve: SwapCurve,
+}
+
+impl WeightedSwapV1 {
+    /// Minimum number of tokens in a weighted swap, smaller swaps should use
+    /// the two-token `SwapV1`
+    pub const MIN_TOKENS: usize = 3;
+    /// Maximum number of tokens in a weighted swap
+    pub const MAX_TOKENS: usize = 8;
+
+    /// Offset of the volatility accumulator in the packed swap, updated on
+    /// its own after every trade
+    const VOLATILITY_ACCUMULATOR_OFFSET: usize = 186;
+
+    /// Get the token at the given index
+    pub fn token(&self, index: u8) -> Result<&WeightedToken, SwapError> {
+        self.tokens
+            .get(index as usize)
+            .ok_or(SwapError::InvalidTokenIndex)
+    }
+
+    /// Sum of the weights of all tokens
+    pub fn total_weight(&self) -> u128 {
+        self.tokens.iter().map(|token| token.weight as u128).sum()
+    }
+
+    /// Weights of all tokens
+    pub fn weights(&self) -> Vec<u64> {
+        self.tokens.iter().map(|token| token.weight).collect()
+    }
+
+    /// Two-token curve for swapping between the tokens at the given indices,
+    /// with the source token as token A
+    pub fn pair_swap_curve(
+        &self,
+        source_index: u8,
+        destination_index: u8,
+    ) -> Result<SwapCurve, SwapError> {
+        Ok(WeightedToken::swap_curve(
+            self.token(source_index)?,
+            self.token(destination_index)?,
+        ))
+    }
+
+    /// Accumulate the spot prices of every pair since the last update, from
+    /// the recorded token balances, then record the balances of the tokens
+    /// changed by the current instruction, given by index
+    pub fn update_price_accumulators(
+        &mut self,
+        new_balances: &[(usize, u64)],
+        timestamp: UnixTimestamp,
+    ) {
+        if let Some((first_token, other_tokens)) = self.tokens.split_first() {
+            for (price_accumulator, token) in
+                self.price_accumulators.iter_mut().zip(other_tokens.iter())
+            {
+                let calculator = WeightedCurve {
+                    token_a_weight: first_token.weight,
+                    token_b_weight: token.weight,
+                };
+                price_accumulator.update(
+                    &calculator,
+                    first_token.balance as u128,
+                    token.balance as u128,
+                    timestamp,
+                );
+            }
+        }
+        for &(index, balance) in new_balances {
+            if let Some(token) = self.tokens.get_mut(index) {
+                token.balance = balance;
+            }
+        }
+    }
+}
+
+impl SwapState for WeightedSwapV1 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+
+    fn nonce(&self) -> u8 {
+        self.nonce
+    }
+
+    fn token_program_id(&self) -> &Pubkey {
+        &self.token_program_id
+    }
+
+    fn token_a_account(&self) -> &Pubkey {
+        &self.tokens.first().unwrap_or(&EMPTY_WEIGHTED_TOKEN).account
+    }
+
+    fn token_b_account(&self) -> &Pubkey {
+        &self.tokens.get(1).unwrap_or(&EMPTY_WEIGHTED_TOKEN).account
+    }
+
+    fn pool_mint(&self) -> &Pubkey {
+        &self.pool_mint
+    }
+
+    fn token_a_mint(&self) -> &Pubkey {
+        &self.tokens.first().unwrap_or(&EMPTY_WEIGHTED_TOKEN).mint
+    }
+
+    fn token_b_mint(&self) -> &Pubkey {
+        &self.tokens.get(1).unwrap_or(&EMPTY_WEIGHTED_TOKEN).mint
+    }
+
+    fn pool_fee_account(&self) -> &Pubkey {
+        &self.pool_fee_account
+    }
+
+    fn fees(&self) -> &Fees {
+        &self.fees
+    }
+
+    fn swap_curve(&self) -> &SwapCurve {
+        &self.swap_curve
+    }
+
+    fn price_accumulator(&self) -> Option<&PriceAccumulator> {
+        self.price_accumulators.first()
+    }
+
+    fn token_balances(&self) -> Option<(u64, u64)> {
+        match self.tokens.as_slice() {
+            [token_a, token_b, ..] => Some((token_a.balance, token_b.balance)),
+            _ => None,
+        }
+    }
+
+    fn dynamic_fees(&self) -> Option<&DynamicFees> {
+        if self.dynamic_fees.is_enabled() {
+            Some(&self.dynamic_fees)
+        } else {
+            None
+        }
+    }
+
+    fn volatility_accumulator(&self) -> Option<&VolatilityAccumulator> {
+        Some(&self.volatility_accumulator)
+    }
+
+    fn admin_authority(&self) -> Option<&Pubkey> {
+        if self.admin_authority == Pubkey::default() {
+            None
+        } else {
+            Some(&self.admin_authority)
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    fn amp_ramp(&self) -> Option<&AmpRamp> {
+        None
+    }
+
+    fn num_tokens(&self) -> usize {
+        self.tokens.len()
+    }
+
+    fn token_account(&self, index: usize) -> Option<&Pubkey> {
+        self.tokens.get(index).map(|token| &token.account)
+    }
+
+    fn token_mint(&self, index: usize) -> Option<&Pubkey> {
+        self.tokens.get(index).map(|token| &token.mint)
+    }
+}
+
+impl Sealed for WeightedSwapV1 {}
+impl IsInitialized for WeightedSwapV1 {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for WeightedSwapV1 {
+    const LEN: usize = 1156;
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 1156];
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            pool_mint,
+            pool_fee_account,
+            fees,
+            dynamic_fees,
+            volatility_accumulator,
+            admin_authority,
+            is_paused,
+            num_tokens,
+            tokens,
+            price_accumulators,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 32, 64, 24, 16, 32, 1, 1, 640, 280];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        token_program_id.copy_from_slice(self.token_program_id.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        pool_fee_account.copy_from_slice(self.pool_fee_account.as_ref());
+        self.fees.pack_into_slice(&mut fees[..]);
+        self.dynamic_fees.pack_into_slice(&mut dynamic_fees[..]);
+        self.volatility_accumulator
+            .pack_into_slice(&mut volatility_accumulator[..]);
+        admin_authority.copy_from_slice(self.admin_authority.as_ref());
+        is_paused[0] = self.is_paused as u8;
+        num_tokens[0] = self.tokens.len() as u8;
+        // unused token and price accumulator slots are zeroed
+        let empty_token = WeightedToken::default();
+        for (i, dst) in tokens.chunks_exact_mut(80).enumerate() {
+            let token = self.tokens.get(i).unwrap_or(&empty_token);
+            let dst = array_mut_ref![dst, 0, 80];
+            let (account, mint, weight, balance) = mut_array_refs![dst, 32, 32, 8, 8];
+            account.copy_from_slice(token.account.as_ref());
+            mint.copy_from_slice(token.mint.as_ref());
+            *weight = token.weight.to_le_bytes();
+            *balance = token.balance.to_le_bytes();
+        }
+        let empty_price_accumulator = PriceAccumulator::default();
+        for (i, dst) in price_accumulators
+            .chunks_exact_mut(PriceAccumulator::LEN)
+            .enumerate()
+        {
+            self.price_accumulators
+                .get(i)
+                .unwrap_or(&empty_price_accumulator)
+                .pack_into_slice(dst);
+        }
+    }
+
+    /// Unpacks a byte buffer into a [WeightedSwapV1](struct.WeightedSwapV1.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 1156];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            token_program_id,
+            pool_mint,
+            pool_fee_account,
+            fees,
+            dynamic_fees,
+            volatility_accumulator,
+            admin_authority,
+            is_paused,
+            num_tokens,
+            tokens,
+            price_accumulators,
+        ) = array_refs![input, 1, 1, 32, 32, 32, 64, 24, 16, 32, 1, 1, 640, 280];
+        let num_tokens = num_tokens[0] as usize;
+        if num_tokens > Self::MAX_TOKENS {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let tokens = tokens
+            .chunks_exact(80)
+            .take(num_tokens)
+            .map(|src| {
+                let src = array_ref![src, 0, 80];
+                #[allow(clippy::ptr_offset_with_cast)]
+                let (account, mint, weight, balance) = array_refs![src, 32, 32, 8, 8];
+                WeightedToken {
+                    account: Pubkey::new_from_array(*account),
+                    mint: Pubkey::new_from_array(*mint),
+                    weight: u64::from_le_bytes(*weight),
+                    balance: u64::from_le_bytes(*balance),
+                }
+            })
+            .collect::<Vec<_>>();
+        let price_accumulators = price_accumulators
+            .chunks_exact(PriceAccumulator::LEN)
+            .take(num_tokens.saturating_sub(1))
+            .map(PriceAccumulator::unpack_from_slice)
+            .collect::<Result<Vec<_>, _>>()?;
+        // uninitialized swaps have no tokens, so no curve between the first two
+        let swap_curve = match tokens.get(0..2) {
+            Some([token_a, token_b]) => WeightedToken::swap_curve(token_a, token_b),
+            _ => SwapCurve::default(),
+        };
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            token_program_id: Pubkey::new_from_array(*token_program_id),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            pool_fee_account: Pubkey::new_from_array(*pool_fee_account),
+            fees: Fees::unpack_from_slice(fees)?,
+            dynamic_fees: DynamicFees::unpack_from_slice(dynamic_fees)?,
+            volatility_accumulator: VolatilityAccumulator::unpack_from_slice(
+                volatility_accumulator,
+            )?,
+            admin_authority: Pubkey::new_from_array(*admin_authority),
+            is_paused: match is_paused {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            tokens,
+            price_accumulators,
+            swap_curve,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::stable::StableCurve;
+    use spl_math::precise_number::PreciseNumber;
+
+    use std::convert::TryInto;
+
+    const TEST_FEES: Fees = Fees {
+        trade_fee_numerator: 1,
+        trade_fee_denominator: 4,
+        owner_trade_fee_numerator: 3,
+        owner_trade_fee_denominator: 10,
+        owner_withdraw_fee_numerator: 2,
+        owner_withdraw_fee_denominator: 7,
+        host_fee_numerator: 5,
+        host_fee_denominator: 20,
+    };
+
+    const TEST_NONCE: u8 = 255;
+    const TEST_TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1u8; 32]);
+    const TEST_TOKEN_A: Pubkey = Pubkey::new_from_array([2u8; 32]);
+    const TEST_TOKEN_B: Pubkey = Pubkey::new_from_array([3u8; 32]);
+    const TEST_POOL_MINT: Pubkey = Pubkey::new_from_array([4u8; 32]);
+    const TEST_TOKEN_A_MINT: Pubkey = Pubkey::new_from_array([5u8; 32]);
+    const TEST_TOKEN_B_MINT: Pubkey = Pubkey::new_from_array([6u8; 32]);
+    const TEST_POOL_FEE_ACCOUNT: Pubkey = Pubkey::new_from_array([7u8; 32]);
+
+    const TEST_CURVE_TYPE: u8 = 2;
+    const TEST_AMP: u64 = 1;
+    const TEST_CURVE: StableCurve = StableCurve { amp: TEST_AMP };
+
+    const TEST_ADMIN_AUTHORITY: Pubkey = Pubkey::new_from_array([8u8; 32]);
+    const TEST_AMP_RAMP: AmpRamp = AmpRamp {
+        initial_amp: TEST_AMP,
+        target_amp: 10,
+        start_timestamp: 1_630_000_000,
+        stop_timestamp: 1_630_086_400,
+    };
+
+    const TEST_DYNAMIC_FEES: DynamicFees = DynamicFees {
+        max_trade_fee_numerator: 3,
+        max_volatility: 1_000,
+        decay_period: 100,
+    };
+
+    #[test]
+    fn swap_version_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Box::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapVersion::SwapV1(SwapV1 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+        });
+
+        let mut packed = [0u8; SwapVersion::V1_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+
+        assert!(unpacked.is_initialized());
+        assert_eq!(unpacked.nonce(), TEST_NONCE);
+        assert_eq!(*unpacked.token_program_id(), TEST_TOKEN_PROGRAM_ID);
+        assert_eq!(*unpacked.token_a_account(), TEST_TOKEN_A);
+        assert_eq!(*unpacked.token_b_account(), TEST_TOKEN_B);
+        assert_eq!(*unpacked.pool_mint(), TEST_POOL_MINT);
+        assert_eq!(*unpacked.token_a_mint(), TEST_TOKEN_A_MINT);
+        assert_eq!(*unpacked.token_b_mint(), TEST_TOKEN_B_MINT);
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+        assert_eq!(*unpacked.fees(), TEST_FEES);
+        assert_eq!(*unpacked.swap_curve(), swap_curve);
+    }
+
+    #[test]
+    fn swap_v1_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Box::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let swap_info = SwapV1 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+        };
+
+        let mut packed = [0u8; SwapV1::LEN];
+        SwapV1::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = vec![1u8, TEST_NONCE];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_A_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_TOKEN_B_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.owner_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&TEST_FEES.host_fee_denominator.to_le_bytes());
+        packed.push(TEST_CURVE_TYPE);
+        packed.extend_from_slice(&TEST_AMP.to_le_bytes());
+        packed.extend_from_slice(&[0u8; 24]);
+        let unpacked = SwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapV1::LEN];
+        let swap_info: SwapV1 = Default::default();
+        let unpack_unchecked = SwapV1::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV1::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v2_pack() {
+        let curve_type = TEST_CURVE_TYPE.try_into().unwrap();
+        let calculator = Box::new(TEST_CURVE);
+        let swap_curve = SwapCurve {
+            curve_type,
+            calculator,
+        };
+        let price_accumulator = PriceAccumulator {
+            last_update_timestamp: 1_630_000_000,
+            token_a_price_cumulative: 123_456_789,
+            token_b_price_cumulative: 987_654_321,
+        };
+        let swap_info = SwapV2 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            price_accumulator: price_accumulator.clone(),
+            token_a_balance: 1_000,
+            token_b_balance: 2_000,
+        };
+
+        let mut packed = [0u8; SwapV2::LEN];
+        SwapV2::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV2::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        // the first part of the layout is the same as SwapV1
+        let swap_info_v1 = SwapV1::unpack(&packed[..SwapV1::LEN]).unwrap();
+        assert_eq!(*swap_info_v1.swap_curve(), swap_curve);
+        let mut packed_tail = vec![0u8; PriceAccumulator::LEN];
+        price_accumulator.pack_into_slice(&mut packed_tail);
+        packed_tail.extend_from_slice(&1_000u64.to_le_bytes());
+        packed_tail.extend_from_slice(&2_000u64.to_le_bytes());
+        assert_eq!(&packed[SwapV1::LEN..], &packed_tail[..]);
+
+        let packed = [0u8; SwapV2::LEN];
+        let swap_info: SwapV2 = Default::default();
+        let unpack_unchecked = SwapV2::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV2::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v2_version_pack() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        };
+        let swap_info = SwapVersion::SwapV2(SwapV2 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+            price_accumulator: PriceAccumulator::new(100),
+            token_a_balance: 10,
+            token_b_balance: 20,
+        });
+
+        let mut packed = [0u8; SwapVersion::V2_LEN];
+        SwapVersion::pack(swap_info, &mut packed).unwrap();
+        assert_eq!(packed[0], 3);
+        assert!(SwapVersion::is_initialized(&packed));
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.nonce(), TEST_NONCE);
+        assert_eq!(
+            unpacked.price_accumulator(),
+            Some(&PriceAccumulator::new(100))
+        );
+        assert_eq!(unpacked.token_balances(), Some((10, 20)));
+        assert_eq!(
+            SwapVersion::unpack_weighted(&packed).unwrap_err(),
+            SwapError::UnsupportedCurveOperation.into()
+        );
+
+        let updated = PriceAccumulator {
+            last_update_timestamp: 200,
+            token_a_price_cumulative: 1,
+            token_b_price_cumulative: 2,
+        };
+        SwapVersion::pack_price_accumulator(&updated, 30, 40, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.price_accumulator(), Some(&updated));
+        assert_eq!(unpacked.token_balances(), Some((30, 40)));
+        assert_eq!(*unpacked.pool_fee_account(), TEST_POOL_FEE_ACCOUNT);
+
+        // the original version has no accumulator to update
+        let mut packed = [0u8; SwapVersion::V2_LEN];
+        packed[0] = 1;
+        assert_eq!(
+            SwapVersion::pack_price_accumulator(&updated, 30, 40, &mut packed).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    #[test]
+    fn swap_v3_pack() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        };
+        let price_accumulator = PriceAccumulator {
+            last_update_timestamp: 1_630_000_000,
+            token_a_price_cumulative: 123_456_789,
+            token_b_price_cumulative: 987_654_321,
+        };
+        let volatility_accumulator = VolatilityAccumulator {
+            volatility: 1_234,
+            last_update_slot: 5_678,
+        };
+        let swap_info = SwapV3 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            price_accumulator: price_accumulator.clone(),
+            token_a_balance: 1_000,
+            token_b_balance: 2_000,
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility_accumulator: volatility_accumulator.clone(),
+        };
+
+        let mut packed = [0u8; SwapV3::LEN];
+        SwapV3::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV3::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        // the first part of the layout is the same as SwapV2
+        let swap_info_v2 = SwapV2::unpack(&packed[..SwapV2::LEN]).unwrap();
+        assert_eq!(*swap_info_v2.swap_curve(), swap_curve);
+        assert_eq!(swap_info_v2.price_accumulator, price_accumulator);
+        let mut packed_tail = vec![];
+        packed_tail.extend_from_slice(&TEST_DYNAMIC_FEES.max_trade_fee_numerator.to_le_bytes());
+        packed_tail.extend_from_slice(&TEST_DYNAMIC_FEES.max_volatility.to_le_bytes());
+        packed_tail.extend_from_slice(&TEST_DYNAMIC_FEES.decay_period.to_le_bytes());
+        packed_tail.extend_from_slice(&volatility_accumulator.volatility.to_le_bytes());
+        packed_tail.extend_from_slice(&volatility_accumulator.last_update_slot.to_le_bytes());
+        assert_eq!(&packed[SwapV2::LEN..], &packed_tail[..]);
+
+        let packed = [0u8; SwapV3::LEN];
+        let swap_info: SwapV3 = Default::default();
+        let unpack_unchecked = SwapV3::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV3::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v3_version_pack() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        };
+        let swap_info = SwapV3 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+            price_accumulator: PriceAccumulator::new(100),
+            token_a_balance: 10,
+            token_b_balance: 20,
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility_accumulator: VolatilityAccumulator::new(10),
+        };
+
+        let mut packed = [0u8; SwapVersion::V3_LEN];
+        SwapVersion::pack(SwapVersion::SwapV3(swap_info), &mut packed).unwrap();
+        assert_eq!(packed[0], 4);
+        assert!(SwapVersion::is_initialized(&packed));
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.nonce(), TEST_NONCE);
+        assert_eq!(unpacked.dynamic_fees(), Some(&TEST_DYNAMIC_FEES));
+        assert_eq!(
+            unpacked.volatility_accumulator(),
+            Some(&VolatilityAccumulator::new(10))
+        );
+        assert_eq!(
+            SwapVersion::unpack_weighted(&packed).unwrap_err(),
+            SwapError::UnsupportedCurveOperation.into()
+        );
+
+        // accumulators are updated in place
+        let updated_prices = PriceAccumulator {
+            last_update_timestamp: 200,
+            token_a_price_cumulative: 1,
+            token_b_price_cumulative: 2,
+        };
+        SwapVersion::pack_price_accumulator(&updated_prices, 30, 40, &mut packed).unwrap();
+        let updated_volatility = VolatilityAccumulator {
+            volatility: 42,
+            last_update_slot: 20,
+        };
+        SwapVersion::pack_volatility_accumulator(&updated_volatility, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.price_accumulator(), Some(&updated_prices));
+        assert_eq!(unpacked.token_balances(), Some((30, 40)));
+        assert_eq!(unpacked.dynamic_fees(), Some(&TEST_DYNAMIC_FEES));
+        assert_eq!(unpacked.volatility_accumulator(), Some(&updated_volatility));
+
+        // disabled dynamic fees are not reported
+        let swap_info = SwapV3 {
+            dynamic_fees: DynamicFees::default(),
+            ..SwapV3::unpack(&packed[1..]).unwrap()
+        };
+        SwapVersion::pack(SwapVersion::SwapV3(swap_info), &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.dynamic_fees(), None);
+        assert_eq!(unpacked.volatility_accumulator(), Some(&updated_volatility));
+
+        // older versions have no volatility to update
+        let mut packed = [0u8; SwapVersion::V3_LEN];
+        packed[0] = 3;
+        assert_eq!(
+            SwapVersion::pack_volatility_accumulator(&updated_volatility, &mut packed).unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+    }
+
+    #[test]
+    fn swap_v4_pack() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        };
+        let swap_info = SwapV4 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve: swap_curve.clone(),
+            price_accumulator: PriceAccumulator::new(1_630_000_000),
+            token_a_balance: 1_000,
+            token_b_balance: 2_000,
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility_accumulator: VolatilityAccumulator::new(5_678),
+            admin_authority: TEST_ADMIN_AUTHORITY,
+            is_paused: true,
+            amp_ramp: TEST_AMP_RAMP,
+        };
+
+        let mut packed = [0u8; SwapV4::LEN];
+        SwapV4::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = SwapV4::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        // the first part of the layout is the same as SwapV3
+        let swap_info_v3 = SwapV3::unpack(&packed[..SwapV3::LEN]).unwrap();
+        assert_eq!(*swap_info_v3.swap_curve(), swap_curve);
+        assert_eq!(swap_info_v3.dynamic_fees, TEST_DYNAMIC_FEES);
+        let mut packed_tail = vec![];
+        packed_tail.extend_from_slice(&TEST_ADMIN_AUTHORITY.to_bytes());
+        packed_tail.push(1);
+        packed_tail.extend_from_slice(&TEST_AMP_RAMP.initial_amp.to_le_bytes());
+        packed_tail.extend_from_slice(&TEST_AMP_RAMP.target_amp.to_le_bytes());
+        packed_tail.extend_from_slice(&TEST_AMP_RAMP.start_timestamp.to_le_bytes());
+        packed_tail.extend_from_slice(&TEST_AMP_RAMP.stop_timestamp.to_le_bytes());
+        assert_eq!(&packed[SwapV3::LEN..], &packed_tail[..]);
+
+        let packed = [0u8; SwapV4::LEN];
+        let swap_info: SwapV4 = Default::default();
+        let unpack_unchecked = SwapV4::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapV4::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn swap_v4_version_pack() {
+        let swap_curve = SwapCurve {
+            curve_type: TEST_CURVE_TYPE.try_into().unwrap(),
+            calculator: Box::new(TEST_CURVE),
+        };
+        let swap_info = SwapV4 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            token_a: TEST_TOKEN_A,
+            token_b: TEST_TOKEN_B,
+            pool_mint: TEST_POOL_MINT,
+            token_a_mint: TEST_TOKEN_A_MINT,
+            token_b_mint: TEST_TOKEN_B_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            swap_curve,
+            price_accumulator: PriceAccumulator::new(100),
+            token_a_balance: 10,
+            token_b_balance: 20,
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility_accumulator: VolatilityAccumulator::new(10),
+            admin_authority: TEST_ADMIN_AUTHORITY,
+            is_paused: false,
+            amp_ramp: TEST_AMP_RAMP,
+        };
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        SwapVersion::pack(SwapVersion::SwapV4(swap_info), &mut packed).unwrap();
+        assert_eq!(packed[0], 5);
+        assert!(SwapVersion::is_initialized(&packed));
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.nonce(), TEST_NONCE);
+        assert_eq!(unpacked.dynamic_fees(), Some(&TEST_DYNAMIC_FEES));
+        assert_eq!(unpacked.admin_authority(), Some(&TEST_ADMIN_AUTHORITY));
+        assert!(!unpacked.is_paused());
+        assert_eq!(unpacked.amp_ramp(), Some(&TEST_AMP_RAMP));
+        assert_eq!(
+            SwapVersion::unpack_weighted(&packed).unwrap_err(),
+            SwapError::UnsupportedCurveOperation.into()
+        );
+
+        // accumulators are updated in place
+        let updated_volatility = VolatilityAccumulator {
+            volatility: 42,
+            last_update_slot: 20,
+        };
+        SwapVersion::pack_volatility_accumulator(&updated_volatility, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.volatility_accumulator(), Some(&updated_volatility));
+        assert_eq!(unpacked.admin_authority(), Some(&TEST_ADMIN_AUTHORITY));
+
+        // admin changes go through the concrete version
+        let mut swap_info = match SwapVersion::unpack_with_admin(&packed).unwrap() {
+            SwapVersion::SwapV4(swap_info) => swap_info,
+            swap_info => panic!("unexpected version {:?}", swap_info),
+        };
+        assert_eq!(swap_info.volatility_accumulator, updated_volatility);
+        swap_info.is_paused = true;
+        swap_info.admin_authority = Pubkey::default();
+        swap_info.amp_ramp = AmpRamp::default();
+        SwapVersion::pack(SwapVersion::SwapV4(swap_info), &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert!(unpacked.is_paused());
+        assert_eq!(unpacked.admin_authority(), None);
+        assert_eq!(unpacked.amp_ramp(), None);
+
+        // older versions have no admin authority
+        let mut packed = [0u8; SwapVersion::V3_LEN];
+        packed[0] = 4;
+        assert_eq!(
+            SwapVersion::unpack_with_admin(&packed).unwrap_err(),
+            SwapError::InvalidAdmin.into()
+        );
+    }
+
+    #[test]
+    fn weighted_swap_pack() {
+        let tokens = (0..3u8)
+            .map(|i| WeightedToken {
+                account: Pubkey::new_from_array([10 + i; 32]),
+                mint: Pubkey::new_from_array([20 + i; 32]),
+                weight: 10 * (i as u64 + 1),
+                balance: 1_000 * (i as u64 + 1),
+            })
+            .collect::<Vec<_>>();
+        let price_accumulators = vec![PriceAccumulator::new(100), PriceAccumulator::new(200)];
+        let swap_info = WeightedSwapV1 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            pool_mint: TEST_POOL_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            dynamic_fees: TEST_DYNAMIC_FEES,
+            volatility_accumulator: VolatilityAccumulator::new(10),
+            admin_authority: TEST_ADMIN_AUTHORITY,
+            is_paused: true,
+            tokens: tokens.clone(),
+            price_accumulators: price_accumulators.clone(),
+            swap_curve: WeightedToken::swap_curve(&tokens[0], &tokens[1]),
+        };
+
+        let mut packed = [0u8; WeightedSwapV1::LEN];
+        WeightedSwapV1::pack_into_slice(&swap_info, &mut packed);
+        let unpacked = WeightedSwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+        assert_eq!(unpacked.total_weight(), 60);
+        assert_eq!(unpacked.token(2).unwrap(), &tokens[2]);
+        assert_eq!(unpacked.token(3), Err(SwapError::InvalidTokenIndex));
+
+        let mut packed = vec![1u8, TEST_NONCE];
+        packed.extend_from_slice(&TEST_TOKEN_PROGRAM_ID.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_MINT.to_bytes());
+        packed.extend_from_slice(&TEST_POOL_FEE_ACCOUNT.to_bytes());
+        let mut fees = [0u8; Fees::LEN];
+        TEST_FEES.pack_into_slice(&mut fees);
+        packed.extend_from_slice(&fees);
+        let mut dynamic_fees = [0u8; DynamicFees::LEN];
+        TEST_DYNAMIC_FEES.pack_into_slice(&mut dynamic_fees);
+        packed.extend_from_slice(&dynamic_fees);
+        let mut volatility_accumulator = [0u8; VolatilityAccumulator::LEN];
+        VolatilityAccumulator::new(10).pack_into_slice(&mut volatility_accumulator);
+        packed.extend_from_slice(&volatility_accumulator);
+        packed.extend_from_slice(&TEST_ADMIN_AUTHORITY.to_bytes());
+        packed.push(1);
+        packed.push(3);
+        for token in tokens.iter() {
+            packed.extend_from_slice(&token.account.to_bytes());
+            packed.extend_from_slice(&token.mint.to_bytes());
+            packed.extend_from_slice(&token.weight.to_le_bytes());
+            packed.extend_from_slice(&token.balance.to_le_bytes());
+        }
+        packed.extend_from_slice(&[0u8; 5 * 80]);
+        for price_accumulator in price_accumulators.iter() {
+            let mut packed_accumulator = [0u8; PriceAccumulator::LEN];
+            price_accumulator.pack_into_slice(&mut packed_accumulator);
+            packed.extend_from_slice(&packed_accumulator);
+        }
+        packed.extend_from_slice(&[0u8; 5 * PriceAccumulator::LEN]);
+        let unpacked = WeightedSwapV1::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        packed[WeightedSwapV1::LEN - 8 * 80 - 7 * PriceAccumulator::LEN - 1] = 9;
+        let err = WeightedSwapV1::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn weighted_swap_version_pack() {
+        let tokens = (0..3u8)
+            .map(|i| WeightedToken {
+                account: Pubkey::new_from_array([10 + i; 32]),
+                mint: Pubkey::new_from_array([20 + i; 32]),
+                weight: 10 * (i as u64 + 1),
+                balance: 1_000,
+            })
+            .collect::<Vec<_>>();
+        let swap_info = WeightedSwapV1 {
+            is_initialized: true,
+            nonce: TEST_NONCE,
+            token_program_id: TEST_TOKEN_PROGRAM_ID,
+            pool_mint: TEST_POOL_MINT,
+            pool_fee_account: TEST_POOL_FEE_ACCOUNT,
+            fees: TEST_FEES,
+            dynamic_fees: DynamicFees::default(),
+            volatility_accumulator: VolatilityAccumulator::new(10),
+            admin_authority: TEST_ADMIN_AUTHORITY,
+            is_paused: false,
+            tokens: tokens.clone(),
+            price_accumulators: vec![PriceAccumulator::new(100); 2],
+            swap_curve: WeightedToken::swap_curve(&tokens[0], &tokens[1]),
+        };
+        let mut packed = [0u8; SwapVersion::WEIGHTED_LEN];
+        assert!(!SwapVersion::is_initialized(&packed));
+        SwapVersion::pack(SwapVersion::WeightedSwapV1(swap_info), &mut packed).unwrap();
+        assert_eq!(packed[0], 2);
+        assert!(SwapVersion::is_initialized(&packed));
+        let unpacked = SwapVersion::unpack_weighted(&packed).unwrap();
+        assert_eq!(unpacked.tokens, tokens);
+
+        // the first two tokens are tokens A and B of the swap
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.num_tokens(), 3);
+        assert_eq!(*unpacked.token_a_account(), tokens[0].account);
+        assert_eq!(*unpacked.token_b_mint(), tokens[1].mint);
+        assert_eq!(unpacked.token_account(2), Some(&tokens[2].account));
+        assert_eq!(unpacked.token_mint(3), None);
+        assert_eq!(
+            *unpacked.swap_curve(),
+            WeightedToken::swap_curve(&tokens[0], &tokens[1])
+        );
+        assert_eq!(
+            unpacked.price_accumulator(),
+            Some(&PriceAccumulator::new(100))
+        );
+        assert_eq!(unpacked.token_balances(), Some((1_000, 1_000)));
+        assert_eq!(unpacked.dynamic_fees(), None);
+        assert_eq!(unpacked.admin_authority(), Some(&TEST_ADMIN_AUTHORITY));
+        assert_eq!(unpacked.amp_ramp(), None);
+
+        // accumulators are updated in place
+        let updated_volatility = VolatilityAccumulator {
+            volatility: 42,
+            last_update_slot: 20,
+        };
+        SwapVersion::pack_volatility_accumulator(&updated_volatility, &mut packed).unwrap();
+        let unpacked = SwapVersion::unpack(&packed).unwrap();
+        assert_eq!(unpacked.volatility_accumulator(), Some(&updated_volatility));
+        assert_eq!(unpacked.admin_authority(), Some(&TEST_ADMIN_AUTHORITY));
+        assert_eq!(
+            SwapVersion::pack_price_accumulator(&PriceAccumulator::new(200), 0, 0, &mut packed)
+                .unwrap_err(),
+            ProgramError::InvalidAccountData
+        );
+
+        // weighted swaps have an admin authority
+        let swap_info = match SwapVersion::unpack_with_admin(&packed).unwrap() {
+            SwapVersion::WeightedSwapV1(swap_info) => swap_info,
+            swap_info => panic!("unexpected version {:?}", swap_info),
+        };
+        assert_eq!(swap_info.volatility_accumulator, updated_volatility);
+
+        let mut packed = [0u8; SwapVersion::LATEST_LEN];
+        packed[0] = 1;
+        assert_eq!(
+            SwapVersion::unpack_weighted(&packed).unwrap_err(),
+            SwapError::UnsupportedCurveOperation.into()
+        );
+    }
+
+    #[test]
+    fn weighted_swap_price_accumulators() {
+        let tokens = [(1, 1_000), (1, 4_000), (2, 4_000)]
+            .iter()
+            .map(|&(weight, balance)| WeightedToken {
+                weight,
+                balance,
+                ..WeightedToken::default()
+            })
+            .collect::<Vec<_>>();
+        let mut swap_info = WeightedSwapV1 {
+            is_initialized: true,
+            tokens,
+            price_accumulators: vec![PriceAccumulator::new(100); 2],
+            ..WeightedSwapV1::default()
+        };
+
+        // the price of the first token is 4 of the second and 2 of the third
+        // for 10 seconds, then 1 of the second until the next update
+        swap_info.update_price_accumulators(&[(1, 1_000)], 110);
+        assert_eq!(swap_info.tokens[1].balance, 1_000);
+        assert_eq!(swap_info.tokens[2].balance, 4_000);
+        swap_info.update_price_accumulators(&[], 120);
+
+        let one = PreciseNumber::new(1).unwrap().value.as_u128();
+        let first_pair = &swap_info.price_accumulators[0];
+        assert_eq!(first_pair.last_update_timestamp, 120);
+        assert_eq!(first_pair.token_a_price_cumulative, 50 * one);
+        let second_pair = &swap_info.price_accumulators[1];
+        assert_eq!(second_pair.last_update_timestamp, 120);
+        assert_eq!(second_pair.token_a_price_cumulative, 40 * one);
     }
 }
@@ -0,0 +1,701 @@
+//! Token distribution from a CSV file, resumable through a local ledger
+
+use crate::{check_fee_payer_balance, config::Config, output::*, CommandResult, Error};
+use serde::{Deserialize, Serialize};
+use solana_clap_utils::keypair::CliSignerInfo;
+use solana_sdk::{
+    hash::Hash,
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{self, Transaction},
+};
+use spl_associated_token_account::*;
+use spl_token::{extension::StateWithExtensions, instruction::transfer_checked, state::Mint};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    str::FromStr,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum DistributionStatus {
+    /// Not yet sent
+    Pending,
+    /// Sent in a transaction that may or may not have landed
+    Sent {
+        signature: String,
+        blockhash: String,
+    },
+    /// Sent in a transaction that is known to have landed
+    Confirmed { signature: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Distribution {
+    recipient: String,
+    amount: u64,
+    status: DistributionStatus,
+}
+
+/// Progress of a distribution, saved after every state change so that an
+/// interrupted run can resume without paying any recipient twice
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Ledger {
+    mint: String,
+    sender: String,
+    distributions: Vec<Distribution>,
+}
+
+impl Ledger {
+    fn new(mint: &Pubkey, sender: &Pubkey, rows: &[(Pubkey, u64)]) -> Self {
+        Self {
+            mint: mint.to_string(),
+            sender: sender.to_string(),
+            distributions: rows
+                .iter()
+                .map(|(recipient, amount)| Distribution {
+                    recipient: recipient.to_string(),
+                    amount: *amount,
+                    status: DistributionStatus::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    fn load(path: &Path) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read ledger {}: {}", path.display(), err))?;
+        serde_json::from_str(&contents)
+            .map_err(|err| format!("Invalid ledger {}: {}", path.display(), err).into())
+    }
+
+    // Write to a temporary file first, so that a crash can never leave a
+    // truncated ledger behind
+    fn save(&self, path: &Path) -> Result<(), Error> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// Indices of the distributions still to be sent
+    fn pending(&self) -> Vec<usize> {
+        self.distributions
+            .iter()
+            .enumerate()
+            .filter(|(_, distribution)| distribution.status == DistributionStatus::Pending)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn matches(&self, mint: &Pubkey, sender: &Pubkey, rows: &[(Pubkey, u64)]) -> bool {
+        self.mint == mint.to_string()
+            && self.sender == sender.to_string()
+            && self.distributions.len() == rows.len()
+            && self
+                .distributions
+                .iter()
+                .zip(rows)
+                .all(|(distribution, (recipient, amount))| {
+                    distribution.recipient == recipient.to_string()
+                        && distribution.amount == *amount
+                })
+    }
+}
+
+/// Parse `recipient,amount` rows, with amounts in tokens.  An optional header
+/// row, blank lines and lines starting with `#` are skipped.  A recipient
+/// listed on several rows receives each of their amounts.
+fn read_distribution_csv(path: &str, decimals: u8) -> Result<Vec<(Pubkey, u64)>, Error> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+    let mut rows = vec![];
+    let mut header_allowed = true;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if fields.len() != 2 {
+            return Err(format!("{}:{}: expected `recipient,amount`", path, i + 1).into());
+        }
+        if header_allowed {
+            header_allowed = false;
+            if fields[0].eq_ignore_ascii_case("recipient") {
+                continue;
+            }
+        }
+        let recipient = Pubkey::from_str(fields[0])
+            .map_err(|_| format!("{}:{}: invalid recipient {}", path, i + 1, fields[0]))?;
+        let amount = fields[1]
+            .parse::<f64>()
+            .ok()
+            .filter(|ui_amount| ui_amount.is_finite() && *ui_amount > 0.)
+            .map(|ui_amount| spl_token::ui_amount_to_amount(ui_amount, decimals))
+            .filter(|amount| *amount > 0)
+            .ok_or_else(|| format!("{}:{}: invalid amount {}", path, i + 1, fields[1]))?;
+        rows.push((recipient, amount));
+    }
+    if rows.is_empty() {
+        return Err(format!("{} contains no distributions", path).into());
+    }
+    Ok(rows)
+}
+
+fn checked_total<I: Iterator<Item = u64>>(mut amounts: I) -> Result<u64, Error> {
+    amounts
+        .try_fold(0u64, |total, amount| total.checked_add(amount))
+        .ok_or_else(|| "Error: Total distribution amount overflows".into())
+}
+
+/// Status of a transaction sent by a previous run, and whether its blockhash
+/// has expired
+type SentStatus = (Option<transaction::Result<()>>, bool);
+
+// Look up whether the blockhash has expired before the signature status, so
+// that a transaction not found afterwards can no longer land
+fn get_sent_status(
+    config: &Config,
+    signature: &Signature,
+    blockhash: &Hash,
+) -> Result<SentStatus, Error> {
+    let expired = config
+        .rpc_client
+        .get_fee_calculator_for_blockhash(blockhash)?
+        .is_none();
+    let status = config
+        .rpc_client
+        .get_signature_status_with_commitment_and_history(
+            signature,
+            config.rpc_client.commitment(),
+            true,
+        )?;
+    Ok((status, expired))
+}
+
+// Settle distributions left in flight by a previous run.  A transaction that
+// has not landed by the time its blockhash expires never will, so it is safe
+// to send those distributions again.
+fn resolve_sent<F>(ledger: &mut Ledger, mut get_status: F) -> Result<(), Error>
+where
+    F: FnMut(&Signature, &Hash) -> Result<SentStatus, Error>,
+{
+    for distribution in ledger.distributions.iter_mut() {
+        if let DistributionStatus::Sent {
+            signature,
+            blockhash,
+        } = &distribution.status
+        {
+            let signature = Signature::from_str(signature)?;
+            let blockhash = Hash::from_str(blockhash)?;
+            let (status, expired) = get_status(&signature, &blockhash)?;
+            distribution.status = match status {
+                Some(Ok(())) => DistributionStatus::Confirmed {
+                    signature: signature.to_string(),
+                },
+                Some(Err(_)) => DistributionStatus::Pending,
+                None if expired => DistributionStatus::Pending,
+                None => {
+                    return Err(format!(
+                        "Transaction {} may still land, try again in a minute",
+                        signature
+                    )
+                    .into())
+                }
+            };
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn command_distribute(
+    config: &Config,
+    signer_info: &CliSignerInfo,
+    token: Pubkey,
+    csv_path: &str,
+    ledger_path: &str,
+    sender: Option<Pubkey>,
+    sender_owner: Pubkey,
+    batch_size: usize,
+) -> CommandResult {
+    let mint = config.rpc_client.get_account(&token)?;
+    let decimals = StateWithExtensions::<Mint>::unpack(&mint.data)
+        .map_err(|_| format!("Invalid mint account {:?}", token))?
+        .base
+        .decimals;
+    let sender = sender.unwrap_or_else(|| get_associated_token_address(&sender_owner, &token));
+    let rows = read_distribution_csv(csv_path, decimals)?;
+
+    let ledger_path = Path::new(ledger_path);
+    let mut ledger = if ledger_path.exists() {
+        let ledger = Ledger::load(ledger_path)?;
+        if !ledger.matches(&token, &sender, &rows) {
+            return Err(format!(
+                "Ledger {} belongs to a different distribution",
+                ledger_path.display()
+            )
+            .into());
+        }
+        println_display(
+            config,
+            format!("Resuming distribution from {}", ledger_path.display()),
+        );
+        ledger
+    } else {
+        Ledger::new(&token, &sender, &rows)
+    };
+    let total_amount = checked_total(
+        ledger
+            .distributions
+            .iter()
+            .map(|distribution| distribution.amount),
+    )?;
+    resolve_sent(&mut ledger, |signature, blockhash| {
+        get_sent_status(config, signature, blockhash)
+    })?;
+    ledger.save(ledger_path)?;
+
+    let pending = ledger.pending();
+    let pending_amount = checked_total(pending.iter().map(|i| ledger.distributions[*i].amount))?;
+    println_display(
+        config,
+        format!(
+            "Distribute {} tokens to {} recipients\n  Sender: {}",
+            spl_token::amount_to_ui_amount(pending_amount, decimals),
+            pending.len(),
+            sender
+        ),
+    );
+
+    if !pending.is_empty() {
+        let sender_balance = config
+            .rpc_client
+            .get_token_account_balance(&sender)?
+            .amount
+            .parse::<u64>()?;
+        if pending_amount > sender_balance {
+            return Err(format!(
+                "Error: Sender has insufficient funds, current balance is {}",
+                spl_token::amount_to_ui_amount(sender_balance, decimals)
+            )
+            .into());
+        }
+    }
+
+    let account_len = spl_token::extension::get_account_len_for_mint(&mint.data)?;
+    let minimum_balance_for_rent_exemption = config
+        .rpc_client
+        .get_minimum_balance_for_rent_exemption(account_len)?;
+    let mut created = HashSet::new();
+    let mut distributed_this_run = 0;
+    for chunk in pending.chunks(batch_size) {
+        let mut instructions = vec![];
+        let mut lamports_needed = 0;
+        for i in chunk {
+            let distribution = &ledger.distributions[*i];
+            let recipient = Pubkey::from_str(&distribution.recipient)?;
+            let recipient_token_account = get_associated_token_address(&recipient, &token);
+            if !created.contains(&recipient_token_account)
+                && config
+                    .rpc_client
+                    .get_account_with_commitment(
+                        &recipient_token_account,
+                        config.rpc_client.commitment(),
+                    )?
+                    .value
+                    .is_none()
+            {
+                instructions.push(create_associated_token_account(
+                    &config.fee_payer,
+                    &recipient,
+                    &token,
+                ));
+                lamports_needed += minimum_balance_for_rent_exemption;
+                created.insert(recipient_token_account);
+            }
+            instructions.push(transfer_checked(
+                &spl_token::id(),
+                &sender,
+                &token,
+                &recipient_token_account,
+                &sender_owner,
+                &config.multisigner_pubkeys,
+                distribution.amount,
+                decimals,
+            )?);
+        }
+
+        let message = Message::new(&instructions, Some(&config.fee_payer));
+        let (recent_blockhash, fee_calculator) = config
+            .blockhash_query
+            .get_blockhash_and_fee_calculator(&config.rpc_client, config.rpc_client.commitment())?;
+        check_fee_payer_balance(
+            config,
+            lamports_needed + fee_calculator.calculate_fee(&message),
+        )?;
+        let signers = signer_info.signers_for_message(&message);
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.try_sign(&signers, recent_blockhash)?;
+        let signature = transaction.signatures[0];
+
+        // Record the signature before sending, so that a crash while waiting
+        // for confirmation can be resolved on the next run
+        for i in chunk {
+            ledger.distributions[*i].status = DistributionStatus::Sent {
+                signature: signature.to_string(),
+                blockhash: recent_blockhash.to_string(),
+            };
+        }
+        ledger.save(ledger_path)?;
+
+        config
+            .rpc_client
+            .send_and_confirm_transaction_with_spinner(&transaction)
+            .map_err(|err| {
+                format!(
+                    "Error: Transaction {} failed: {}\n\
+                     Run the same command again to resume the distribution",
+                    signature, err
+                )
+            })?;
+        for i in chunk {
+            ledger.distributions[*i].status = DistributionStatus::Confirmed {
+                signature: signature.to_string(),
+            };
+        }
+        ledger.save(ledger_path)?;
+        distributed_this_run += chunk.len();
+        println_display(config, format!("Signature: {}", signature));
+    }
+
+    let is_confirmed = |distribution: &&Distribution| {
+        matches!(distribution.status, DistributionStatus::Confirmed { .. })
+    };
+    let distributed_amount = checked_total(
+        ledger
+            .distributions
+            .iter()
+            .filter(is_confirmed)
+            .map(|distribution| distribution.amount),
+    )?;
+    let undistributed = ledger
+        .distributions
+        .iter()
+        .filter(|distribution| !is_confirmed(distribution))
+        .map(|distribution| CliUndistributed {
+            recipient: distribution.recipient.clone(),
+            amount: spl_token::amount_to_ui_amount(distribution.amount, decimals),
+        })
+        .collect::<Vec<_>>();
+    let sender_balance = config.rpc_client.get_token_account_balance(&sender)?;
+    let report = CliDistributionReport {
+        token: token.to_string(),
+        sender: sender.to_string(),
+        sender_balance: sender_balance.real_number_string_trimmed(),
+        ledger: ledger_path.display().to_string(),
+        recipients: ledger.distributions.len(),
+        total_amount: spl_token::amount_to_ui_amount(total_amount, decimals),
+        distributed_amount: spl_token::amount_to_ui_amount(distributed_amount, decimals),
+        distributed_previously: ledger.distributions.len()
+            - undistributed.len()
+            - distributed_this_run,
+        distributed_this_run,
+        undistributed,
+    };
+    println!("{}", config.output_format.formatted_string(&report));
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "spl-token-distribute-{}-{}",
+            std::process::id(),
+            name
+        ))
+    }
+
+    fn write_csv(name: &str, contents: &str) -> String {
+        let path = temp_path(name);
+        fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn new_signature() -> Signature {
+        Keypair::new().sign_message(b"distribute")
+    }
+
+    fn sent(signature: &Signature, blockhash: &Hash) -> DistributionStatus {
+        DistributionStatus::Sent {
+            signature: signature.to_string(),
+            blockhash: blockhash.to_string(),
+        }
+    }
+
+    fn confirmed(signature: &Signature) -> DistributionStatus {
+        DistributionStatus::Confirmed {
+            signature: signature.to_string(),
+        }
+    }
+
+    fn failed() -> transaction::Result<()> {
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1),
+        ))
+    }
+
+    #[test]
+    fn read_csv() {
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let path = write_csv(
+            "read_csv",
+            &format!(
+                "Recipient,Amount\n\n# comment\n{}, 1.5\n{},0.01\n{},2\n",
+                alice, bob, alice
+            ),
+        );
+        assert_eq!(
+            read_distribution_csv(&path, 2).unwrap(),
+            vec![(alice, 150), (bob, 1), (alice, 200)]
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_csv_invalid_rows() {
+        let recipient = Pubkey::new_unique();
+        for (name, contents) in [
+            ("empty", "".to_string()),
+            ("header_only", "recipient,amount\n".to_string()),
+            ("too_few_fields", format!("{}\n", recipient)),
+            ("too_many_fields", format!("{},1,2\n", recipient)),
+            ("invalid_recipient", "not-a-pubkey,1\n".to_string()),
+            (
+                "late_header",
+                format!("{},1\nrecipient,amount\n", recipient),
+            ),
+            ("zero_amount", format!("{},0\n", recipient)),
+            ("negative_amount", format!("{},-1\n", recipient)),
+            ("nan_amount", format!("{},NaN\n", recipient)),
+            ("infinite_amount", format!("{},inf\n", recipient)),
+            ("invalid_amount", format!("{},one\n", recipient)),
+            ("below_precision", format!("{},0.001\n", recipient)),
+        ]
+        .iter()
+        {
+            let path = write_csv(name, contents);
+            assert!(
+                read_distribution_csv(&path, 2).is_err(),
+                "{} should be rejected",
+                name
+            );
+            fs::remove_file(path).unwrap();
+        }
+        assert!(read_distribution_csv(&temp_path("missing").to_string_lossy(), 2).is_err());
+    }
+
+    #[test]
+    fn total_amount() {
+        assert_eq!(checked_total(vec![].into_iter()).unwrap(), 0);
+        assert_eq!(checked_total(vec![1, 2, 3].into_iter()).unwrap(), 6);
+        assert_eq!(
+            checked_total(vec![u64::MAX - 1, 1].into_iter()).unwrap(),
+            u64::MAX
+        );
+        assert!(checked_total(vec![u64::MAX, 1].into_iter()).is_err());
+        assert!(checked_total(vec![u64::MAX - 1, 1, 1].into_iter()).is_err());
+    }
+
+    #[test]
+    fn ledger_matches_distribution() {
+        let mint = Pubkey::new_unique();
+        let sender = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let rows = vec![(alice, 10), (bob, 20)];
+        let mut ledger = Ledger::new(&mint, &sender, &rows);
+        assert!(ledger.matches(&mint, &sender, &rows));
+
+        // progress does not affect the match
+        ledger.distributions[0].status = confirmed(&new_signature());
+        assert!(ledger.matches(&mint, &sender, &rows));
+
+        assert!(!ledger.matches(&Pubkey::new_unique(), &sender, &rows));
+        assert!(!ledger.matches(&mint, &Pubkey::new_unique(), &rows));
+        assert!(!ledger.matches(&mint, &sender, &[(bob, 20), (alice, 10)]));
+        assert!(!ledger.matches(&mint, &sender, &[(alice, 10), (bob, 21)]));
+        assert!(!ledger.matches(&mint, &sender, &[(alice, 10)]));
+        assert!(!ledger.matches(&mint, &sender, &[(alice, 10), (bob, 20), (bob, 20)]));
+    }
+
+    #[test]
+    fn ledger_save_and_load() {
+        let rows = vec![
+            (Pubkey::new_unique(), 10),
+            (Pubkey::new_unique(), 20),
+            (Pubkey::new_unique(), 30),
+        ];
+        let mut ledger = Ledger::new(&Pubkey::new_unique(), &Pubkey::new_unique(), &rows);
+        let signature = new_signature();
+        ledger.distributions[0].status = confirmed(&signature);
+        ledger.distributions[1].status = sent(&signature, &Hash::new_unique());
+
+        let path = temp_path("ledger.json");
+        ledger.save(&path).unwrap();
+        let mut unsaved_path = path.as_os_str().to_owned();
+        unsaved_path.push(".tmp");
+        assert!(!Path::new(&unsaved_path).exists());
+
+        let loaded = Ledger::load(&path).unwrap();
+        assert_eq!(loaded.mint, ledger.mint);
+        assert_eq!(loaded.sender, ledger.sender);
+        for (loaded, saved) in loaded.distributions.iter().zip(&ledger.distributions) {
+            assert_eq!(loaded.recipient, saved.recipient);
+            assert_eq!(loaded.amount, saved.amount);
+            assert_eq!(loaded.status, saved.status);
+        }
+        assert_eq!(loaded.pending(), vec![2]);
+
+        fs::write(&path, "{").unwrap();
+        assert!(Ledger::load(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn resolve_sent_distributions() {
+        let rows = vec![
+            (Pubkey::new_unique(), 1),
+            (Pubkey::new_unique(), 2),
+            (Pubkey::new_unique(), 3),
+            (Pubkey::new_unique(), 4),
+            (Pubkey::new_unique(), 5),
+        ];
+        let mut ledger = Ledger::new(&Pubkey::new_unique(), &Pubkey::new_unique(), &rows);
+        let previous = new_signature();
+        let landed = new_signature();
+        let failed_signature = new_signature();
+        let dropped = new_signature();
+        let blockhash = Hash::new_unique();
+        ledger.distributions[1].status = confirmed(&previous);
+        ledger.distributions[2].status = sent(&landed, &blockhash);
+        ledger.distributions[3].status = sent(&failed_signature, &blockhash);
+        ledger.distributions[4].status = sent(&dropped, &blockhash);
+
+        let mut looked_up = vec![];
+        resolve_sent(&mut ledger, |signature, _| {
+            looked_up.push(*signature);
+            Ok(if *signature == landed {
+                (Some(Ok(())), false)
+            } else if *signature == failed_signature {
+                (Some(failed()), false)
+            } else {
+                (None, true)
+            })
+        })
+        .unwrap();
+
+        // only distributions in flight are looked up
+        assert_eq!(looked_up, vec![landed, failed_signature, dropped]);
+        assert_eq!(ledger.distributions[0].status, DistributionStatus::Pending);
+        assert_eq!(ledger.distributions[1].status, confirmed(&previous));
+        assert_eq!(ledger.distributions[2].status, confirmed(&landed));
+        assert_eq!(ledger.distributions[3].status, DistributionStatus::Pending);
+        assert_eq!(ledger.distributions[4].status, DistributionStatus::Pending);
+        assert_eq!(ledger.pending(), vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn resolve_sent_before_expiry() {
+        let rows = vec![(Pubkey::new_unique(), 1)];
+        let mut ledger = Ledger::new(&Pubkey::new_unique(), &Pubkey::new_unique(), &rows);
+        let signature = new_signature();
+        ledger.distributions[0].status = sent(&signature, &Hash::new_unique());
+
+        // the transaction may still land, so it must not be sent again
+        assert!(resolve_sent(&mut ledger, |_, _| Ok((None, false))).is_err());
+        assert!(ledger.pending().is_empty());
+
+        // lookup errors are passed on
+        assert!(resolve_sent(&mut ledger, |_, _| Err("rpc error".into())).is_err());
+        assert!(ledger.pending().is_empty());
+
+        // once the blockhash has expired, the distribution is sent again
+        resolve_sent(&mut ledger, |_, _| Ok((None, true))).unwrap();
+        assert_eq!(ledger.pending(), vec![0]);
+    }
+
+    #[test]
+    fn resume_after_crash() {
+        let mint = Pubkey::new_unique();
+        let sender = Pubkey::new_unique();
+        let rows = vec![
+            (Pubkey::new_unique(), 1),
+            (Pubkey::new_unique(), 2),
+            (Pubkey::new_unique(), 3),
+            (Pubkey::new_unique(), 4),
+        ];
+        let first_batch = new_signature();
+        let second_batch = new_signature();
+        let path = temp_path("resume.json");
+
+        // crashed with the first batch confirmed, the second batch sent and
+        // the rest still pending
+        let mut ledger = Ledger::new(&mint, &sender, &rows);
+        ledger.distributions[0].status = confirmed(&first_batch);
+        ledger.distributions[1].status = sent(&second_batch, &Hash::new_unique());
+        ledger.distributions[2].status = sent(&second_batch, &Hash::new_unique());
+        ledger.save(&path).unwrap();
+
+        // the second batch landed before the crash
+        let mut ledger = Ledger::load(&path).unwrap();
+        assert!(ledger.matches(&mint, &sender, &rows));
+        resolve_sent(&mut ledger, |signature, _| {
+            assert_eq!(*signature, second_batch);
+            Ok((Some(Ok(())), true))
+        })
+        .unwrap();
+        assert_eq!(ledger.pending(), vec![3]);
+        assert_eq!(ledger.distributions[1].status, confirmed(&second_batch));
+        assert_eq!(ledger.distributions[2].status, confirmed(&second_batch));
+
+        // the second batch never landed and its blockhash expired
+        let mut ledger = Ledger::load(&path).unwrap();
+        resolve_sent(&mut ledger, |_, _| Ok((None, true))).unwrap();
+        assert_eq!(ledger.pending(), vec![1, 2, 3]);
+        assert_eq!(ledger.distributions[0].status, confirmed(&first_batch));
+
+        // a fully confirmed ledger has nothing left to send
+        let mut ledger = Ledger::load(&path).unwrap();
+        for distribution in ledger.distributions.iter_mut() {
+            distribution.status = confirmed(&first_batch);
+        }
+        ledger.save(&path).unwrap();
+        let mut ledger = Ledger::load(&path).unwrap();
+        resolve_sent(&mut ledger, |_, _| panic!("nothing is in flight")).unwrap();
+        assert!(ledger.pending().is_empty());
+
+        fs::remove_file(path).unwrap();
+    }
+}
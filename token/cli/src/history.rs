@@ -0,0 +1,818 @@
+//! Transaction history and running balance of token accounts
+
+use crate::{config::Config, output::*, CommandResult, Error};
+use solana_account_decoder::parse_token::real_number_string_trimmed;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_sdk::{
+    clock::{Slot, UnixTimestamp},
+    message::Message,
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{
+    UiInstruction, UiTransactionEncoding, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
+use spl_token::instruction::TokenInstruction;
+use std::str::FromStr;
+
+// Maximum number of signatures returned by a single RPC request
+const SIGNATURES_PAGE_SIZE: usize = 1_000;
+
+fn format_amount(amount: u64, decimals: u8) -> String {
+    real_number_string_trimmed(amount, decimals)
+}
+
+fn format_change(change: i128, decimals: u8) -> String {
+    let amount = format_amount(change.unsigned_abs() as u64, decimals);
+    if change < 0 {
+        format!("-{}", amount)
+    } else {
+        format!("+{}", amount)
+    }
+}
+
+/// Describe a token instruction, from the point of view of `address`.  Returns
+/// `None` for instructions that do not involve `address`.
+fn describe_instruction(
+    instruction: &TokenInstruction,
+    accounts: &[Pubkey],
+    address: &Pubkey,
+    decimals: u8,
+) -> Option<String> {
+    if let TokenInstruction::Batch { instructions } = instruction {
+        let mut descriptions = vec![];
+        let mut accounts = accounts;
+        for (num_accounts, instruction) in instructions {
+            let (instruction_accounts, rest) =
+                accounts.split_at((*num_accounts as usize).min(accounts.len()));
+            accounts = rest;
+            descriptions.extend(describe_instruction(
+                instruction,
+                instruction_accounts,
+                address,
+                decimals,
+            ));
+        }
+        return if descriptions.is_empty() {
+            None
+        } else {
+            Some(descriptions.join("; "))
+        };
+    }
+
+    if !accounts.contains(address) {
+        return None;
+    }
+    let description = match instruction {
+        TokenInstruction::Transfer { amount } => {
+            transfer_description(accounts.get(0), accounts.get(1), *amount, address, decimals)
+        }
+        TokenInstruction::TransferChecked { amount, .. } => {
+            transfer_description(accounts.get(0), accounts.get(2), *amount, address, decimals)
+        }
+        TokenInstruction::MintTo { amount } | TokenInstruction::MintToChecked { amount, .. } => {
+            format!("mint {}", format_amount(*amount, decimals))
+        }
+        TokenInstruction::Burn { amount } | TokenInstruction::BurnChecked { amount, .. } => {
+            format!("burn {}", format_amount(*amount, decimals))
+        }
+        TokenInstruction::Approve { amount } | TokenInstruction::ApproveChecked { amount, .. } => {
+            format!("approve {}", format_amount(*amount, decimals))
+        }
+        TokenInstruction::InitializeAccount
+        | TokenInstruction::InitializeAccount2 { .. }
+        | TokenInstruction::InitializeAccount3 { .. } => "initialize account".to_string(),
+        TokenInstruction::CloseAccount => "close account".to_string(),
+        TokenInstruction::FreezeAccount => "freeze".to_string(),
+        TokenInstruction::ThawAccount => "thaw".to_string(),
+        TokenInstruction::Revoke => "revoke".to_string(),
+        TokenInstruction::SyncNative => "sync native".to_string(),
+        instruction => format!("{:?}", instruction),
+    };
+    Some(description)
+}
+
+fn transfer_description(
+    source: Option<&Pubkey>,
+    destination: Option<&Pubkey>,
+    amount: u64,
+    address: &Pubkey,
+    decimals: u8,
+) -> String {
+    let amount = format_amount(amount, decimals);
+    match (source, destination) {
+        (Some(source), Some(destination)) if source == address => {
+            format!("transfer {} to {}", amount, destination)
+        }
+        (Some(source), _) => format!("transfer {} from {}", amount, source),
+        _ => format!("transfer {}", amount),
+    }
+}
+
+fn decode_instruction(
+    program_id: &Pubkey,
+    accounts: &[Pubkey],
+    data: &[u8],
+    address: &Pubkey,
+    decimals: u8,
+) -> Option<String> {
+    if *program_id != spl_token::id() {
+        return None;
+    }
+    match TokenInstruction::unpack(data) {
+        Ok(instruction) => describe_instruction(&instruction, accounts, address, decimals),
+        Err(_) if accounts.contains(address) => Some("unknown instruction".to_string()),
+        Err(_) => None,
+    }
+}
+
+fn token_balance<'a>(
+    balances: &'a Option<Vec<UiTransactionTokenBalance>>,
+    account_index: usize,
+) -> Option<&'a UiTransactionTokenBalance> {
+    balances
+        .as_ref()?
+        .iter()
+        .find(|balance| balance.account_index as usize == account_index)
+}
+
+/// Build the history entry of a transaction from the point of view of the
+/// token account `address`, from the transaction's message and status meta.
+/// Returns `None` if the transaction does not record a balance of the account.
+fn history_entry(
+    address: &Pubkey,
+    signature: &Signature,
+    slot: Slot,
+    block_time: Option<UnixTimestamp>,
+    message: &Message,
+    meta: &UiTransactionStatusMeta,
+) -> Result<Option<CliHistoryEntry>, Error> {
+    let account_keys = &message.account_keys;
+    let account_index = match account_keys.iter().position(|key| key == address) {
+        Some(account_index) => account_index,
+        None => return Ok(None),
+    };
+
+    // Balances of accounts that were created or closed by the transaction are
+    // only recorded on one side
+    let pre_balance = token_balance(&meta.pre_token_balances, account_index);
+    let post_balance = token_balance(&meta.post_token_balances, account_index);
+    let decimals = match pre_balance.or(post_balance) {
+        Some(balance) => balance.ui_token_amount.decimals,
+        None => return Ok(None),
+    };
+    let pre_amount = pre_balance
+        .map(|balance| balance.ui_token_amount.amount.parse::<u64>())
+        .transpose()?
+        .unwrap_or(0);
+    let post_amount = post_balance
+        .map(|balance| balance.ui_token_amount.amount.parse::<u64>())
+        .transpose()?
+        .unwrap_or(0);
+
+    let mut instructions = vec![];
+    for (i, instruction) in message.instructions.iter().enumerate() {
+        let accounts = instruction
+            .accounts
+            .iter()
+            .map(|index| account_keys[*index as usize])
+            .collect::<Vec<_>>();
+        instructions.extend(decode_instruction(
+            &account_keys[instruction.program_id_index as usize],
+            &accounts,
+            &instruction.data,
+            address,
+            decimals,
+        ));
+        // Include token instructions invoked by other programs, such as swaps
+        let inner_instructions = meta
+            .inner_instructions
+            .iter()
+            .flatten()
+            .filter(|inner_instructions| inner_instructions.index as usize == i)
+            .flat_map(|inner_instructions| inner_instructions.instructions.iter());
+        for inner_instruction in inner_instructions {
+            if let UiInstruction::Compiled(inner_instruction) = inner_instruction {
+                let accounts = inner_instruction
+                    .accounts
+                    .iter()
+                    .map(|index| account_keys[*index as usize])
+                    .collect::<Vec<_>>();
+                instructions.extend(decode_instruction(
+                    &account_keys[inner_instruction.program_id_index as usize],
+                    &accounts,
+                    &bs58::decode(&inner_instruction.data).into_vec()?,
+                    address,
+                    decimals,
+                ));
+            }
+        }
+    }
+
+    Ok(Some(CliHistoryEntry {
+        slot,
+        block_time,
+        signature: signature.to_string(),
+        instructions,
+        change: format_change(post_amount as i128 - pre_amount as i128, decimals),
+        balance: format_amount(post_amount, decimals),
+    }))
+}
+
+fn fetch_history_entry(
+    config: &Config,
+    address: &Pubkey,
+    signature: &Signature,
+) -> Result<Option<CliHistoryEntry>, Error> {
+    let confirmed_transaction = config
+        .rpc_client
+        .get_transaction(signature, UiTransactionEncoding::Base64)?;
+    let meta = match confirmed_transaction.transaction.meta {
+        Some(meta) => meta,
+        None => return Ok(None),
+    };
+    let transaction = confirmed_transaction
+        .transaction
+        .transaction
+        .decode()
+        .ok_or_else(|| format!("Unable to decode transaction {}", signature))?;
+    history_entry(
+        address,
+        signature,
+        confirmed_transaction.slot,
+        confirmed_transaction.block_time,
+        &transaction.message,
+        &meta,
+    )
+}
+
+/// Balance after the newest of `entries`, oldest first, at or before
+/// `at_slot`.  Without any, the account did not hold any tokens yet.
+fn balance_at_slot(entries: &[CliHistoryEntry], at_slot: Slot) -> String {
+    entries
+        .iter()
+        .rev()
+        .find(|entry| entry.slot <= at_slot)
+        .map(|entry| entry.balance.clone())
+        .unwrap_or_else(|| "0".to_string())
+}
+
+pub(crate) fn command_history(
+    config: &Config,
+    address: Pubkey,
+    limit: Option<usize>,
+    at_slot: Option<Slot>,
+    csv: bool,
+) -> CommandResult {
+    // Page through signatures, newest first, skipping failed transactions,
+    // which cannot have changed the balance
+    let mut signatures = vec![];
+    let mut before = None;
+    'pages: loop {
+        let page = config.rpc_client.get_signatures_for_address_with_config(
+            &address,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURES_PAGE_SIZE),
+                commitment: Some(config.rpc_client.commitment()),
+            },
+        )?;
+        let last_page = page.len() < SIGNATURES_PAGE_SIZE;
+        before = page
+            .last()
+            .map(|status| Signature::from_str(&status.signature))
+            .transpose()?;
+        for status in page {
+            if limit.map_or(false, |limit| signatures.len() >= limit) {
+                break 'pages;
+            }
+            if status.err.is_none() && at_slot.map_or(true, |at_slot| status.slot <= at_slot) {
+                signatures.push(Signature::from_str(&status.signature)?);
+            }
+        }
+        if last_page {
+            break;
+        }
+    }
+
+    let mut entries = vec![];
+    for signature in signatures.iter().rev() {
+        entries.extend(fetch_history_entry(config, &address, signature)?);
+    }
+    let balance_at_slot = at_slot.map(|at_slot| balance_at_slot(&entries, at_slot));
+
+    let history = CliTokenHistory {
+        address: address.to_string(),
+        entries,
+        at_slot,
+        balance_at_slot,
+    };
+    if csv {
+        print!("{}", history.to_csv());
+    } else {
+        println!("{}", config.output_format.formatted_string(&history));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        program_option::COption,
+    };
+    use spl_token::{
+        instruction::{transfer, AuthorityType},
+        state::AccountState,
+    };
+
+    const DECIMALS: u8 = 2;
+
+    fn describe(
+        instruction: &TokenInstruction,
+        accounts: &[Pubkey],
+        address: &Pubkey,
+    ) -> Option<String> {
+        decode_instruction(
+            &spl_token::id(),
+            accounts,
+            &instruction.pack(),
+            address,
+            DECIMALS,
+        )
+    }
+
+    fn token_balances(mint: &Pubkey, balances: &[(usize, u64)]) -> serde_json::Value {
+        balances
+            .iter()
+            .map(|(account_index, amount)| {
+                json!({
+                    "accountIndex": account_index,
+                    "mint": mint.to_string(),
+                    "uiTokenAmount": {
+                        "uiAmount": *amount as f64 / 100.,
+                        "decimals": DECIMALS,
+                        "amount": amount.to_string(),
+                        "uiAmountString": format_amount(*amount, DECIMALS),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn status_meta(
+        mint: &Pubkey,
+        pre_balances: &[(usize, u64)],
+        post_balances: &[(usize, u64)],
+        inner_instructions: serde_json::Value,
+    ) -> UiTransactionStatusMeta {
+        serde_json::from_value(json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 5000,
+            "preBalances": [],
+            "postBalances": [],
+            "innerInstructions": inner_instructions,
+            "preTokenBalances": token_balances(mint, pre_balances),
+            "postTokenBalances": token_balances(mint, post_balances),
+        }))
+        .unwrap()
+    }
+
+    fn index_of(message: &Message, key: &Pubkey) -> usize {
+        message
+            .account_keys
+            .iter()
+            .position(|account_key| account_key == key)
+            .unwrap()
+    }
+
+    #[test]
+    fn format_changes() {
+        assert_eq!(format_change(150, DECIMALS), "+1.5");
+        assert_eq!(format_change(-150, DECIMALS), "-1.5");
+        assert_eq!(format_change(0, DECIMALS), "+0");
+        assert_eq!(
+            format_change(-(u64::MAX as i128), 0),
+            format!("-{}", u64::MAX)
+        );
+    }
+
+    #[test]
+    fn describe_each_instruction() {
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let accounts = [address, mint, authority];
+
+        // (instruction, accounts, description)
+        let described = vec![
+            (
+                TokenInstruction::Transfer { amount: 150 },
+                vec![address, other, authority],
+                format!("transfer 1.5 to {}", other),
+            ),
+            (
+                TokenInstruction::Transfer { amount: 150 },
+                vec![other, address, authority],
+                format!("transfer 1.5 from {}", other),
+            ),
+            (
+                TokenInstruction::TransferChecked {
+                    amount: 150,
+                    decimals: DECIMALS,
+                },
+                vec![address, mint, other, authority],
+                format!("transfer 1.5 to {}", other),
+            ),
+            (
+                TokenInstruction::TransferChecked {
+                    amount: 150,
+                    decimals: DECIMALS,
+                },
+                vec![other, mint, address, authority],
+                format!("transfer 1.5 from {}", other),
+            ),
+            (
+                TokenInstruction::MintTo { amount: 150 },
+                vec![mint, address, authority],
+                "mint 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::MintToChecked {
+                    amount: 150,
+                    decimals: DECIMALS,
+                },
+                vec![mint, address, authority],
+                "mint 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::Burn { amount: 150 },
+                accounts.to_vec(),
+                "burn 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::BurnChecked {
+                    amount: 150,
+                    decimals: DECIMALS,
+                },
+                accounts.to_vec(),
+                "burn 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::Approve { amount: 150 },
+                vec![address, other, authority],
+                "approve 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::ApproveChecked {
+                    amount: 150,
+                    decimals: DECIMALS,
+                },
+                vec![address, mint, other, authority],
+                "approve 1.5".to_string(),
+            ),
+            (
+                TokenInstruction::InitializeAccount,
+                accounts.to_vec(),
+                "initialize account".to_string(),
+            ),
+            (
+                TokenInstruction::InitializeAccount2 { owner: authority },
+                accounts.to_vec(),
+                "initialize account".to_string(),
+            ),
+            (
+                TokenInstruction::InitializeAccount3 { owner: authority },
+                accounts.to_vec(),
+                "initialize account".to_string(),
+            ),
+            (
+                TokenInstruction::CloseAccount,
+                accounts.to_vec(),
+                "close account".to_string(),
+            ),
+            (
+                TokenInstruction::FreezeAccount,
+                accounts.to_vec(),
+                "freeze".to_string(),
+            ),
+            (
+                TokenInstruction::ThawAccount,
+                accounts.to_vec(),
+                "thaw".to_string(),
+            ),
+            (
+                TokenInstruction::Revoke,
+                accounts.to_vec(),
+                "revoke".to_string(),
+            ),
+            (
+                TokenInstruction::SyncNative,
+                accounts.to_vec(),
+                "sync native".to_string(),
+            ),
+        ];
+        for (instruction, accounts, description) in described {
+            assert_eq!(
+                describe(&instruction, &accounts, &address),
+                Some(description),
+                "{:?}",
+                instruction
+            );
+            // instructions not involving the account are left out
+            let others = accounts
+                .iter()
+                .map(|account| if *account == address { other } else { *account })
+                .collect::<Vec<_>>();
+            assert_eq!(describe(&instruction, &others, &address), None);
+        }
+
+        // instructions without a summary are shown in full
+        let shown_in_full = vec![
+            TokenInstruction::InitializeMint {
+                decimals: DECIMALS,
+                mint_authority: authority,
+                freeze_authority: COption::Some(other),
+            },
+            TokenInstruction::InitializeMint2 {
+                decimals: DECIMALS,
+                mint_authority: authority,
+                freeze_authority: COption::None,
+            },
+            TokenInstruction::InitializeMultisig { m: 2 },
+            TokenInstruction::InitializeMultisig2 { m: 2 },
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::AccountOwner,
+                new_authority: COption::Some(other),
+            },
+            TokenInstruction::InitializeTransferFeeConfig {
+                withdraw_withheld_authority: COption::Some(authority),
+                transfer_fee_basis_points: 10,
+                maximum_fee: 1_000,
+            },
+            TokenInstruction::WithdrawWithheldTokensFromAccounts {
+                num_token_accounts: 1,
+            },
+            TokenInstruction::EnableRequiredTransferMemos,
+            TokenInstruction::DisableRequiredTransferMemos,
+            TokenInstruction::InitializeNonTransferableMint,
+            TokenInstruction::InitializeInterestBearingMint {
+                rate_authority: COption::Some(authority),
+                rate: -100,
+            },
+            TokenInstruction::UpdateInterestBearingMintRate { rate: 100 },
+            TokenInstruction::AmountToUiAmount { amount: 150 },
+            TokenInstruction::UiAmountToAmount { ui_amount: 1.5 },
+            TokenInstruction::InitializePermanentDelegate { delegate: other },
+            TokenInstruction::InitializeDefaultAccountState {
+                state: AccountState::Frozen,
+            },
+            TokenInstruction::UpdateDefaultAccountState {
+                state: AccountState::Initialized,
+            },
+            TokenInstruction::InitializeMintCloseAuthority {
+                close_authority: COption::Some(authority),
+            },
+        ];
+        for instruction in shown_in_full {
+            assert_eq!(
+                describe(&instruction, &accounts, &address),
+                Some(format!("{:?}", instruction))
+            );
+            assert_eq!(describe(&instruction, &[mint, authority], &address), None);
+        }
+    }
+
+    #[test]
+    fn describe_batch() {
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let batch = TokenInstruction::Batch {
+            instructions: vec![
+                (3, TokenInstruction::Transfer { amount: 100 }),
+                (3, TokenInstruction::Transfer { amount: 200 }),
+                (2, TokenInstruction::SyncNative),
+            ],
+        };
+
+        // each sub-instruction is described from its own accounts
+        let accounts = [address, other, owner, other, address, owner, address, owner];
+        assert_eq!(
+            describe(&batch, &accounts, &address),
+            Some(format!(
+                "transfer 1 to {}; transfer 2 from {}; sync native",
+                other, other
+            ))
+        );
+
+        // sub-instructions not involving the account are left out
+        let accounts = [other, owner, owner, other, address, owner, other, owner];
+        assert_eq!(
+            describe(&batch, &accounts, &address),
+            Some(format!("transfer 2 from {}", other))
+        );
+        let accounts = [other, owner, owner, owner, other, owner, other, owner];
+        assert_eq!(describe(&batch, &accounts, &address), None);
+
+        // missing accounts are tolerated
+        assert_eq!(
+            describe(&batch, &[address, other, owner, other], &address),
+            Some(format!("transfer 1 to {}", other))
+        );
+    }
+
+    #[test]
+    fn decode_instructions() {
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let transfer = TokenInstruction::Transfer { amount: 100 }.pack();
+
+        // only token program instructions are decoded
+        assert_eq!(
+            decode_instruction(
+                &Pubkey::new_unique(),
+                &[address, other],
+                &transfer,
+                &address,
+                DECIMALS
+            ),
+            None
+        );
+        assert_eq!(
+            decode_instruction(
+                &spl_token::id(),
+                &[address, other],
+                &[255],
+                &address,
+                DECIMALS
+            ),
+            Some("unknown instruction".to_string())
+        );
+        assert_eq!(
+            decode_instruction(&spl_token::id(), &[other], &[255], &address, DECIMALS),
+            None
+        );
+    }
+
+    #[test]
+    fn token_balances_by_account_index() {
+        let mint = Pubkey::new_unique();
+        let meta = status_meta(&mint, &[(1, 100), (3, 300)], &[], json!(null));
+        assert_eq!(
+            token_balance(&meta.pre_token_balances, 3)
+                .unwrap()
+                .ui_token_amount
+                .amount,
+            "300"
+        );
+        assert!(token_balance(&meta.pre_token_balances, 2).is_none());
+        assert!(token_balance(&meta.post_token_balances, 1).is_none());
+        assert!(token_balance(&None, 1).is_none());
+    }
+
+    #[test]
+    fn entry_from_transaction() {
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let swap_program_id = Pubkey::new_unique();
+        let signature = Signature::default();
+
+        // a transfer out of the account, then a swap paying into it
+        let message = Message::new(
+            &[
+                transfer(&spl_token::id(), &address, &other, &owner, &[], 300).unwrap(),
+                Instruction::new_with_bytes(
+                    swap_program_id,
+                    &[],
+                    vec![
+                        AccountMeta::new(other, false),
+                        AccountMeta::new(address, false),
+                        AccountMeta::new_readonly(owner, true),
+                        AccountMeta::new_readonly(spl_token::id(), false),
+                    ],
+                ),
+            ],
+            Some(&payer),
+        );
+        let inner_transfer = json!({
+            "programIdIndex": index_of(&message, &spl_token::id()),
+            "accounts": [
+                index_of(&message, &other),
+                index_of(&message, &address),
+                index_of(&message, &owner),
+            ],
+            "data": bs58::encode(TokenInstruction::Transfer { amount: 50 }.pack()).into_string(),
+        });
+        let address_index = index_of(&message, &address);
+        let other_index = index_of(&message, &other);
+        let meta = status_meta(
+            &mint,
+            &[(address_index, 1_000), (other_index, 0)],
+            &[(address_index, 750), (other_index, 250)],
+            json!([{ "index": 1, "instructions": [inner_transfer] }]),
+        );
+
+        let entry = history_entry(&address, &signature, 42, Some(7), &message, &meta)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.slot, 42);
+        assert_eq!(entry.block_time, Some(7));
+        assert_eq!(entry.signature, signature.to_string());
+        assert_eq!(
+            entry.instructions,
+            vec![
+                format!("transfer 3 to {}", other),
+                format!("transfer 0.5 from {}", other),
+            ]
+        );
+        assert_eq!(entry.change, "-2.5");
+        assert_eq!(entry.balance, "7.5");
+
+        // an account created by the transaction only has a post balance
+        let meta = status_meta(&mint, &[], &[(address_index, 250)], json!(null));
+        let entry = history_entry(&address, &signature, 42, None, &message, &meta)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.change, "+2.5");
+        assert_eq!(entry.balance, "2.5");
+
+        // and a closed one only a pre balance
+        let meta = status_meta(&mint, &[(address_index, 250)], &[], json!(null));
+        let entry = history_entry(&address, &signature, 42, None, &message, &meta)
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.change, "-2.5");
+        assert_eq!(entry.balance, "0");
+
+        // transactions without a balance of the account are skipped
+        let meta = status_meta(
+            &mint,
+            &[(other_index, 0)],
+            &[(other_index, 250)],
+            json!(null),
+        );
+        assert!(
+            history_entry(&address, &signature, 42, None, &message, &meta)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            history_entry(&Pubkey::new_unique(), &signature, 42, None, &message, &meta)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn balance_at_slots() {
+        let address = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let message = Message::new(
+            &[transfer(&spl_token::id(), &other, &address, &owner, &[], 100).unwrap()],
+            Some(&owner),
+        );
+        let address_index = index_of(&message, &address);
+
+        // oldest first, each transaction receiving one token
+        let entries = [(10, 0, 100), (20, 100, 200), (30, 200, 300)]
+            .iter()
+            .map(|(slot, pre_amount, post_amount)| {
+                let meta = status_meta(
+                    &mint,
+                    &[(address_index, *pre_amount)],
+                    &[(address_index, *post_amount)],
+                    json!(null),
+                );
+                history_entry(
+                    &address,
+                    &Signature::default(),
+                    *slot,
+                    None,
+                    &message,
+                    &meta,
+                )
+                .unwrap()
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // the balance after the last transaction at or before the slot
+        assert_eq!(balance_at_slot(&entries, 5), "0");
+        assert_eq!(balance_at_slot(&entries, 10), "1");
+        assert_eq!(balance_at_slot(&entries, 25), "2");
+        assert_eq!(balance_at_slot(&entries, 30), "3");
+        assert_eq!(balance_at_slot(&entries, 100), "3");
+        assert_eq!(balance_at_slot(&[], 100), "0");
+    }
+}
@@ -8,12 +8,14 @@ use solana_account_decoder::{
 };
 use solana_clap_utils::{
     fee_payer::fee_payer_arg,
-    input_parsers::{pubkey_of_signer, pubkeys_of_multiple_signers, value_of},
+    input_parsers::{pubkey_of, pubkey_of_signer, pubkeys_of_multiple_signers, value_of},
     input_validators::{
         is_amount, is_amount_or_all, is_parsable, is_url_or_moniker, is_valid_pubkey,
         is_valid_signer, normalize_to_url_if_moniker,
     },
-    keypair::{signer_from_path, CliSignerInfo},
+    keypair::{
+        signer_from_path, signer_from_path_with_config, CliSignerInfo, SignerFromPathConfig,
+    },
     nonce::*,
     offline::{self, *},
     ArgConstant,
@@ -24,29 +26,48 @@ use solana_client::{
 };
 use solana_remote_wallet::remote_wallet::RemoteWalletManager;
 use solana_sdk::{
+    account::from_account,
+    clock::{Clock, Slot},
     commitment_config::CommitmentConfig,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
     message::Message,
     native_token::*,
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    system_instruction, system_program,
+    system_instruction, system_program, sysvar,
     transaction::Transaction,
 };
 use spl_associated_token_account::*;
 use spl_token::{
     self,
+    extension::{
+        default_account_state::DefaultAccountState,
+        interest_bearing_mint::{BasisPoints, InterestBearingConfig},
+        mint_close_authority::MintCloseAuthority,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::TransferFeeConfig,
+        ExtensionType, StateWithExtensions,
+    },
     instruction::*,
     native_mint,
-    state::{Account, Mint, Multisig},
+    state::{Account, AccountState, GenericTokenAccount, Mint, Multisig},
 };
 use std::{collections::HashMap, process::exit, str::FromStr, sync::Arc};
 
 mod config;
 use config::Config;
 
+mod distribute;
+use distribute::command_distribute;
+
+mod history;
+use history::command_history;
+
+mod proposal;
+use proposal::{command_multisig_sign, command_multisig_submit, save_proposal};
+
 mod output;
 use output::*;
 
@@ -122,6 +143,14 @@ pub fn mint_address_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help(MINT_ADDRESS_ARG.help)
 }
 
+fn parse_account_state(state: &str) -> AccountState {
+    match state {
+        "initialized" => AccountState::Initialized,
+        "frozen" => AccountState::Frozen,
+        _ => unreachable!(),
+    }
+}
+
 fn is_mint_decimals(string: String) -> Result<(), String> {
     is_parsable::<u8>(string)
 }
@@ -182,6 +211,15 @@ fn is_multisig_minimum_signers(string: String) -> Result<(), String> {
     }
 }
 
+fn is_batch_size(string: String) -> Result<(), String> {
+    let v = usize::from_str(&string).map_err(|e| e.to_string())?;
+    if v == 0 {
+        Err("must be at least 1".to_string())
+    } else {
+        Ok(())
+    }
+}
+
 type Error = Box<dyn std::error::Error>;
 type CommandResult = Result<Option<(u64, Vec<Vec<Instruction>>)>, Error>;
 
@@ -246,12 +284,14 @@ pub fn signers_of(
     matches: &ArgMatches<'_>,
     name: &str,
     wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+    config: &SignerFromPathConfig,
 ) -> Result<Option<SignersOf>, Box<dyn std::error::Error>> {
     if let Some(values) = matches.values_of(name) {
         let mut results = Vec::new();
         for (i, value) in values.enumerate() {
             let name = format!("{}-{}", name, i + 1);
-            let signer = signer_from_path(matches, value, &name, wallet_manager)?;
+            let signer =
+                signer_from_path_with_config(matches, value, &name, wallet_manager, config)?;
             let signer_pubkey = signer.pubkey();
             results.push((signer, signer_pubkey));
         }
@@ -267,35 +307,92 @@ fn command_create_token(
     token: Pubkey,
     authority: Pubkey,
     enable_freeze: bool,
+    non_transferable: bool,
+    interest_rate: Option<BasisPoints>,
+    permanent_delegate: Option<Pubkey>,
+    default_account_state: Option<AccountState>,
+    enable_close: bool,
     memo: Option<String>,
 ) -> CommandResult {
     println_display(config, format!("Creating token {}", token));
 
+    if default_account_state == Some(AccountState::Frozen) && !enable_freeze {
+        return Err("Frozen-by-default tokens require `--enable-freeze`".into());
+    }
+
+    let mut extension_types = vec![];
+    if non_transferable {
+        extension_types.push(ExtensionType::NonTransferable);
+    }
+    if interest_rate.is_some() {
+        extension_types.push(ExtensionType::InterestBearingConfig);
+    }
+    if permanent_delegate.is_some() {
+        extension_types.push(ExtensionType::PermanentDelegate);
+    }
+    if default_account_state.is_some() {
+        extension_types.push(ExtensionType::DefaultAccountState);
+    }
+    if enable_close {
+        extension_types.push(ExtensionType::MintCloseAuthority);
+    }
+    let mint_len = ExtensionType::get_account_len::<Mint>(&extension_types);
+
     let minimum_balance_for_rent_exemption = if !config.sign_only {
         config
             .rpc_client
-            .get_minimum_balance_for_rent_exemption(Mint::LEN)?
+            .get_minimum_balance_for_rent_exemption(mint_len)?
     } else {
         0
     };
     let freeze_authority_pubkey = if enable_freeze { Some(authority) } else { None };
 
-    let mut instructions = vec![
-        system_instruction::create_account(
-            &config.fee_payer,
+    let mut instructions = vec![system_instruction::create_account(
+        &config.fee_payer,
+        &token,
+        minimum_balance_for_rent_exemption,
+        mint_len as u64,
+        &spl_token::id(),
+    )];
+    if non_transferable {
+        instructions.push(initialize_non_transferable_mint(&spl_token::id(), &token)?);
+    }
+    if let Some(rate) = interest_rate {
+        instructions.push(initialize_interest_bearing_mint(
+            &spl_token::id(),
             &token,
-            minimum_balance_for_rent_exemption,
-            Mint::LEN as u64,
+            Some(&authority),
+            rate,
+        )?);
+    }
+    if let Some(delegate) = permanent_delegate {
+        instructions.push(initialize_permanent_delegate(
             &spl_token::id(),
-        ),
-        initialize_mint(
+            &token,
+            &delegate,
+        )?);
+    }
+    if let Some(state) = default_account_state {
+        instructions.push(initialize_default_account_state(
             &spl_token::id(),
             &token,
-            &authority,
-            freeze_authority_pubkey.as_ref(),
-            decimals,
-        )?,
-    ];
+            state,
+        )?);
+    }
+    if enable_close {
+        instructions.push(initialize_mint_close_authority(
+            &spl_token::id(),
+            &token,
+            Some(&authority),
+        )?);
+    }
+    instructions.push(initialize_mint(
+        &spl_token::id(),
+        &token,
+        &authority,
+        freeze_authority_pubkey.as_ref(),
+        decimals,
+    )?);
     if let Some(text) = memo {
         instructions.push(spl_memo::build_memo(text.as_bytes(), &[&config.fee_payer]));
     }
@@ -311,12 +408,16 @@ fn command_create_account(
     owner: Pubkey,
     maybe_account: Option<Pubkey>,
 ) -> CommandResult {
-    let minimum_balance_for_rent_exemption = if !config.sign_only {
-        config
-            .rpc_client
-            .get_minimum_balance_for_rent_exemption(Account::LEN)?
+    let (minimum_balance_for_rent_exemption, account_len) = if !config.sign_only {
+        let account_len = get_account_len_for_mint(config, &token)?;
+        (
+            config
+                .rpc_client
+                .get_minimum_balance_for_rent_exemption(account_len)?,
+            account_len,
+        )
     } else {
-        0
+        (0, Account::LEN)
     };
 
     let (account, system_account_ok, instructions) = if let Some(account) = maybe_account {
@@ -329,7 +430,7 @@ fn command_create_account(
                     &config.fee_payer,
                     &account,
                     minimum_balance_for_rent_exemption,
-                    Account::LEN as u64,
+                    account_len as u64,
                     &spl_token::id(),
                 ),
                 initialize_account(&spl_token::id(), &account, &token, &owner)?,
@@ -425,19 +526,41 @@ fn command_authorize(
         AuthorityType::FreezeAccount => "freeze authority",
         AuthorityType::AccountOwner => "owner",
         AuthorityType::CloseAccount => "close authority",
+        AuthorityType::WithheldWithdraw => "withheld withdraw authority",
+        AuthorityType::InterestRate => "interest rate authority",
+        AuthorityType::PermanentDelegate => "permanent delegate",
+        AuthorityType::CloseMint => "mint close authority",
     };
     let previous_authority = if !config.sign_only {
         let target_account = config.rpc_client.get_account(&account)?;
-        if let Ok(mint) = Mint::unpack(&target_account.data) {
+        if let Ok(mint) = StateWithExtensions::<Mint>::unpack(&target_account.data) {
             match authority_type {
                 AuthorityType::AccountOwner | AuthorityType::CloseAccount => Err(format!(
                     "Authority type `{}` not supported for SPL Token mints",
                     auth_str
                 )),
-                AuthorityType::MintTokens => Ok(mint.mint_authority),
-                AuthorityType::FreezeAccount => Ok(mint.freeze_authority),
+                AuthorityType::MintTokens => Ok(mint.base.mint_authority),
+                AuthorityType::FreezeAccount => Ok(mint.base.freeze_authority),
+                AuthorityType::WithheldWithdraw => mint
+                    .get_extension::<TransferFeeConfig>()
+                    .map(|config| config.withdraw_withheld_authority)
+                    .map_err(|_| format!("Mint {} does not have a transfer fee", account)),
+                AuthorityType::InterestRate => mint
+                    .get_extension::<InterestBearingConfig>()
+                    .map(|config| config.rate_authority)
+                    .map_err(|_| format!("Mint {} does not bear interest", account)),
+                AuthorityType::PermanentDelegate => mint
+                    .get_extension::<PermanentDelegate>()
+                    .map(|permanent_delegate| permanent_delegate.delegate)
+                    .map_err(|_| format!("Mint {} does not have a permanent delegate", account)),
+                AuthorityType::CloseMint => mint
+                    .get_extension::<MintCloseAuthority>()
+                    .map(|mint_close_authority| mint_close_authority.close_authority)
+                    .map_err(|_| format!("Mint {} does not have a close authority", account)),
             }
-        } else if let Ok(token_account) = Account::unpack(&target_account.data) {
+        } else if let Ok(token_account) =
+            StateWithExtensions::<Account>::unpack(&target_account.data).map(|a| a.base)
+        {
             let check_associated_token_account = || -> Result<(), Error> {
                 let maybe_associated_token_account =
                     get_associated_token_address(&token_account.owner, &token_account.mint);
@@ -456,7 +579,12 @@ fn command_authorize(
             };
 
             match authority_type {
-                AuthorityType::MintTokens | AuthorityType::FreezeAccount => Err(format!(
+                AuthorityType::MintTokens
+                | AuthorityType::FreezeAccount
+                | AuthorityType::WithheldWithdraw
+                | AuthorityType::InterestRate
+                | AuthorityType::PermanentDelegate
+                | AuthorityType::CloseMint => Err(format!(
                     "Authority type `{}` not supported for SPL Token accounts",
                     auth_str
                 )),
@@ -536,12 +664,18 @@ fn resolve_mint_info(
 
 fn validate_mint(config: &Config, token: Pubkey) -> Result<(), Error> {
     let mint = config.rpc_client.get_account(&token);
-    if mint.is_err() || Mint::unpack(&mint.unwrap().data).is_err() {
+    if mint.is_err() || StateWithExtensions::<Mint>::unpack(&mint.unwrap().data).is_err() {
         return Err(format!("Invalid mint account {:?}", token).into());
     }
     Ok(())
 }
 
+fn get_account_len_for_mint(config: &Config, token: &Pubkey) -> Result<usize, Error> {
+    let mint = config.rpc_client.get_account(token)?;
+    spl_token::extension::get_account_len_for_mint(&mint.data)
+        .map_err(|_| format!("Invalid mint account {:?}", token).into())
+}
+
 #[allow(clippy::too_many_arguments)]
 fn command_transfer(
     config: &Config,
@@ -554,6 +688,7 @@ fn command_transfer(
     fund_recipient: bool,
     mint_decimals: Option<u8>,
     recipient_is_ata_owner: bool,
+    memo: Option<String>,
 ) -> CommandResult {
     let sender = if let Some(sender) = sender {
         sender
@@ -613,7 +748,9 @@ fn command_transfer(
             .rpc_client
             .get_account_with_commitment(&recipient, config.rpc_client.commitment())?
             .value
-            .map(|account| account.owner == spl_token::id() && account.data.len() == Account::LEN);
+            .map(|account| {
+                account.owner == spl_token::id() && Account::valid_account_data(&account.data)
+            });
 
         if recipient_account_info.is_none() && !allow_unfunded_recipient {
             return Err("Error: The recipient address is not funded. \
@@ -665,9 +802,10 @@ fn command_transfer(
         if needs_funding {
             if fund_recipient {
                 if !config.sign_only {
+                    let account_len = get_account_len_for_mint(config, &mint_pubkey)?;
                     minimum_balance_for_rent_exemption += config
                         .rpc_client
-                        .get_minimum_balance_for_rent_exemption(Account::LEN)?;
+                        .get_minimum_balance_for_rent_exemption(account_len)?;
                     println_display(
                         config,
                         format!(
@@ -692,7 +830,7 @@ fn command_transfer(
         }
     }
 
-    instructions.push(transfer_checked(
+    let mut transfer_instruction = transfer_checked(
         &spl_token::id(),
         &sender,
         &mint_pubkey,
@@ -701,7 +839,16 @@ fn command_transfer(
         &config.multisigner_pubkeys,
         transfer_balance,
         decimals,
-    )?);
+    )?;
+    if let Some(text) = memo {
+        // The memo must immediately precede the transfer, and the recipient may
+        // check for it through the instructions sysvar
+        instructions.push(spl_memo::build_memo(text.as_bytes(), &[&config.fee_payer]));
+        transfer_instruction
+            .accounts
+            .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+    instructions.push(transfer_instruction);
     Ok(Some((
         minimum_balance_for_rent_exemption,
         vec![instructions],
@@ -790,6 +937,39 @@ fn command_freeze(
     Ok(Some((0, vec![instructions])))
 }
 
+fn command_update_default_account_state(
+    config: &Config,
+    token: Pubkey,
+    state: AccountState,
+    freeze_authority: Pubkey,
+) -> CommandResult {
+    if !config.sign_only {
+        let mint_account = config.rpc_client.get_account(&token)?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+            .map_err(|_| format!("Invalid mint account {:?}", token))?;
+        if mint.get_extension::<DefaultAccountState>().is_err() {
+            return Err(format!("Mint {} does not have a default account state", token).into());
+        }
+    }
+
+    println_display(
+        config,
+        format!(
+            "Updating default account state: {:?}\n  Token: {}",
+            state, token
+        ),
+    );
+
+    let instructions = vec![update_default_account_state(
+        &spl_token::id(),
+        &token,
+        &freeze_authority,
+        &config.multisigner_pubkeys,
+        state,
+    )?];
+    Ok(Some((0, vec![instructions])))
+}
+
 fn command_thaw(
     config: &Config,
     account: Pubkey,
@@ -1020,12 +1200,76 @@ fn command_close(
     Ok(Some((0, vec![instructions])))
 }
 
+fn command_close_mint(
+    config: &Config,
+    token: Pubkey,
+    close_authority: Pubkey,
+    recipient: Pubkey,
+) -> CommandResult {
+    if !config.sign_only {
+        let mint_account = config.rpc_client.get_account(&token)?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+            .map_err(|_| format!("Invalid mint account {:?}", token))?;
+        if mint.get_extension::<MintCloseAuthority>().is_err() {
+            return Err(format!("Mint {} does not have a close authority", token).into());
+        }
+        if mint.base.supply > 0 {
+            return Err(format!(
+                "Mint {} still has a supply of {}; burn all tokens in order to close it.",
+                token,
+                spl_token::amount_to_ui_amount(mint.base.supply, mint.base.decimals)
+            )
+            .into());
+        }
+    }
+
+    println_display(config, format!("Closing mint {}", token));
+
+    let instructions = vec![close_account(
+        &spl_token::id(),
+        &token,
+        &recipient,
+        &close_authority,
+        &config.multisigner_pubkeys,
+    )?];
+    Ok(Some((0, vec![instructions])))
+}
+
+fn interest_adjusted_ui_amount(
+    config: &Config,
+    token: &Pubkey,
+    amount: &str,
+) -> Result<Option<f64>, Error> {
+    let mint_account = config.rpc_client.get_account(token)?;
+    let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+        .map_err(|_| format!("Invalid mint account {:?}", token))?;
+    if let Ok(interest_bearing_config) = mint.get_extension::<InterestBearingConfig>() {
+        let amount = amount.parse::<u64>()?;
+        let clock_account = config.rpc_client.get_account(&sysvar::clock::id())?;
+        let clock = from_account::<Clock, _>(&clock_account).ok_or("Invalid clock sysvar")?;
+        Ok(interest_bearing_config.amount_to_ui_amount(
+            amount,
+            mint.base.decimals,
+            clock.unix_timestamp,
+        ))
+    } else {
+        Ok(None)
+    }
+}
+
 fn command_balance(config: &Config, address: Pubkey) -> CommandResult {
     let balance = config
         .rpc_client
         .get_token_account_balance(&address)
         .map_err(|_| format!("Could not find token account {}", address))?;
-    let cli_token_amount = CliTokenAmount { amount: balance };
+    let account = config.rpc_client.get_account(&address)?;
+    let mint = Account::unpack_account_mint(&account.data)
+        .ok_or_else(|| format!("Could not find token account {}", address))?;
+    let interest_adjusted_ui_amount = interest_adjusted_ui_amount(config, &mint, &balance.amount)?;
+    let cli_token_amount = CliTokenAmount {
+        amount: balance,
+        interest_adjusted_ui_amount,
+    };
     println!(
         "{}",
         config.output_format.formatted_string(&cli_token_amount)
@@ -1036,7 +1280,12 @@ fn command_balance(config: &Config, address: Pubkey) -> CommandResult {
 
 fn command_supply(config: &Config, address: Pubkey) -> CommandResult {
     let supply = config.rpc_client.get_token_supply(&address)?;
-    let cli_token_amount = CliTokenAmount { amount: supply };
+    let interest_adjusted_ui_amount =
+        interest_adjusted_ui_amount(config, &address, &supply.amount)?;
+    let cli_token_amount = CliTokenAmount {
+        amount: supply,
+        interest_adjusted_ui_amount,
+    };
     println!(
         "{}",
         config.output_format.formatted_string(&cli_token_amount)
@@ -1105,10 +1354,13 @@ fn command_account_info(config: &Config, address: Pubkey) -> CommandResult {
     let mint = Pubkey::from_str(&account.mint).unwrap();
     let owner = Pubkey::from_str(&account.owner).unwrap();
     let is_associated = get_associated_token_address(&owner, &mint) == address;
+    let interest_adjusted_ui_amount =
+        interest_adjusted_ui_amount(config, &mint, &account.token_amount.amount)?;
     let cli_token_account = CliTokenAccount {
         address: address.to_string(),
         is_associated,
         account,
+        interest_adjusted_ui_amount,
     };
     println!(
         "{}",
@@ -1309,7 +1561,7 @@ impl offline::ArgsConfig for SignOnlyNeedsDelegateAddress {
 fn main() {
     let default_decimals = &format!("{}", native_mint::DECIMALS);
     let mut no_wait = false;
-    let app_matches = App::new(crate_name!())
+    let app = App::new(crate_name!())
         .about(crate_description!())
         .version(crate_version!())
         .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -1401,6 +1653,56 @@ fn main() {
                             "Enable the mint authority to freeze associated token accounts."
                         ),
                 )
+                .arg(
+                    Arg::with_name("non_transferable")
+                        .long("non-transferable")
+                        .takes_value(false)
+                        .help(
+                            "Permanently prevent tokens of the mint from being transferred or delegated. \
+                             Tokens may still be minted, burned, and their accounts closed."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("interest_rate")
+                        .long("interest-rate")
+                        .value_name("RATE_BPS")
+                        .validator(is_parsable::<BasisPoints>)
+                        .takes_value(true)
+                        .help(
+                            "Make the token bear interest at the given annual rate, in basis points. \
+                             The mint authority may later update the rate."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("permanent_delegate")
+                        .long("permanent-delegate")
+                        .value_name("ADDRESS")
+                        .validator(is_valid_pubkey)
+                        .takes_value(true)
+                        .help(
+                            "Give the token a permanent delegate, which may transfer or burn \
+                             tokens from any account of the mint without the owner's approval."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("default_account_state")
+                        .long("default-account-state")
+                        .value_name("STATE")
+                        .takes_value(true)
+                        .possible_values(&["initialized", "frozen"])
+                        .help(
+                            "Specify the state of new accounts of the token. \
+                             Frozen accounts must be thawed by the freeze authority before use."
+                        ),
+                )
+                .arg(
+                    Arg::with_name("enable_close")
+                        .long("enable-close")
+                        .takes_value(false)
+                        .help(
+                            "Enable the mint authority to close the mint once its supply is zero."
+                        ),
+                )
                 .arg(
                     Arg::with_name("memo")
                         .long("memo")
@@ -1498,11 +1800,21 @@ fn main() {
                     Arg::with_name("authority_type")
                         .value_name("AUTHORITY_TYPE")
                         .takes_value(true)
-                        .possible_values(&["mint", "freeze", "owner", "close"])
+                        .possible_values(&[
+                            "mint",
+                            "freeze",
+                            "owner",
+                            "close",
+                            "withheld-withdraw",
+                            "interest-rate",
+                            "permanent-delegate",
+                            "close-mint",
+                        ])
                         .index(2)
                         .required(true)
                         .help("The new authority type. \
-                            Token mints support `mint` and `freeze` authorities;\
+                            Token mints support `mint`, `freeze`, `withheld-withdraw`, `interest-rate`, \
+                            `permanent-delegate` and `close-mint` authorities;\
                             Token accounts support `owner` and `close` authorities."),
                 )
                 .arg(
@@ -1622,11 +1934,78 @@ fn main() {
                         .requires("sign_only")
                         .help("In sign-only mode, specifies that the recipient is the owner of the associated token account rather than an actual token account"),
                 )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("with-memo")
+                        .value_name("MEMO")
+                        .takes_value(true)
+                        .help("Add a memo to the transfer, as required by recipients that only accept transfers with memos"),
+                )
                 .arg(multisig_signer_arg())
                 .arg(mint_decimals_arg())
                 .nonce_args(true)
                 .offline_args_config(&SignOnlyNeedsMintDecimals{}),
         )
+        .subcommand(
+            SubCommand::with_name("distribute")
+                .about("Transfer tokens to many recipients listed in a CSV file")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("Token to distribute"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .value_name("CSV_FILE")
+                        .takes_value(true)
+                        .index(2)
+                        .required(true)
+                        .help("CSV file of `recipient,amount` rows, with recipient wallet \
+                               addresses and amounts in tokens. Tokens are sent to the \
+                               recipients' associated token accounts, which are created \
+                               as needed"),
+                )
+                .arg(
+                    Arg::with_name("ledger")
+                        .long("ledger")
+                        .value_name("LEDGER_FILE")
+                        .takes_value(true)
+                        .help("File recording the progress of the distribution. \
+                               Rerunning the command with the same ledger resumes \
+                               an interrupted distribution without paying anyone twice \
+                               [default: CSV_FILE.ledger.json]"),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .validator(is_valid_pubkey)
+                        .value_name("SENDER_TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .long("from")
+                        .help("Specify the sending token account \
+                            [default: owner's associated token account]")
+                )
+                .arg(owner_keypair_arg_with_value_name("SENDER_TOKEN_OWNER_KEYPAIR")
+                        .help(
+                            "Specify the owner of the sending token account. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("batch_size")
+                        .long("batch-size")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .validator(is_batch_size)
+                        .default_value("5")
+                        .help("Maximum number of transfers per transaction"),
+                )
+                .arg(multisig_signer_arg()),
+        )
         .subcommand(
             SubCommand::with_name("burn")
                 .about("Burn tokens from an account")
@@ -1737,6 +2116,43 @@ fn main() {
                 .nonce_args(true)
                 .offline_args_config(&SignOnlyNeedsMintAddress{}),
         )
+        .subcommand(
+            SubCommand::with_name("update-default-account-state")
+                .about("Update the state of new accounts of a token")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_MINT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The token to update"),
+                )
+                .arg(
+                    Arg::with_name("state")
+                        .value_name("STATE")
+                        .takes_value(true)
+                        .possible_values(&["initialized", "frozen"])
+                        .index(2)
+                        .required(true)
+                        .help("The new state of new accounts of the token"),
+                )
+                .arg(
+                    Arg::with_name("freeze_authority")
+                        .long("freeze-authority")
+                        .value_name("KEYPAIR")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .help(
+                            "Specify the token's freeze authority keypair. \
+                             This may be a keypair file or the ASK keyword. \
+                             Defaults to the client keypair."
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
         .subcommand(
             SubCommand::with_name("thaw")
                 .about("Thaw a token account")
@@ -1936,6 +2352,42 @@ fn main() {
                 .nonce_args(true)
                 .offline_args(),
         )
+        .subcommand(
+            SubCommand::with_name("close-mint")
+                .about("Close a token mint with zero supply")
+                .arg(
+                    Arg::with_name("token")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_MINT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The token to close"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .validator(is_valid_pubkey)
+                        .value_name("REFUND_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .help("The address of the account to receive remaining SOL [default: client keypair]"),
+                )
+                .arg(
+                    Arg::with_name("close_authority")
+                        .long("close-authority")
+                        .value_name("KEYPAIR")
+                        .validator(is_valid_signer)
+                        .takes_value(true)
+                        .help(
+                            "Specify the token's close authority. \
+                            This may be a keypair file, the ASK keyword. \
+                            Defaults to the client keypair.",
+                        ),
+                )
+                .arg(multisig_signer_arg())
+                .nonce_args(true)
+                .offline_args(),
+        )
         .subcommand(
             SubCommand::with_name("balance")
                 .about("Get token account balance")
@@ -2038,6 +2490,44 @@ fn main() {
                         .help("Specify the token account to query"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Show the transaction history and running balance of a token account")
+                .arg(
+                    Arg::with_name("address")
+                        .validator(is_valid_pubkey)
+                        .value_name("TOKEN_ACCOUNT_ADDRESS")
+                        .takes_value(true)
+                        .index(1)
+                        .required(true)
+                        .help("The address of the token account"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .validator(is_parsable::<usize>)
+                        .help("Only show the most recent NUMBER transactions \
+                               [default: all transactions]"),
+                )
+                .arg(
+                    Arg::with_name("at_slot")
+                        .long("at-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .validator(is_parsable::<Slot>)
+                        .help("Only show transactions up to SLOT, and the balance \
+                               of the account at that slot"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .takes_value(false)
+                        .conflicts_with("output_format")
+                        .help("Print the history as CSV"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("multisig-info")
                 .about("Query details about and SPL Token multisig account by address")
@@ -2077,7 +2567,113 @@ fn main() {
                         .help("Specify the specific token account address to sync"),
                 ),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("multisig")
+                .about("Collect the signatures of a multisig's signers across machines")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("propose")
+                        .about("Save the transaction of another command to a proposal file, \
+                                instead of sending it")
+                        .setting(AppSettings::TrailingVarArg)
+                        .arg(
+                            Arg::with_name("multisig")
+                                .validator(is_valid_pubkey)
+                                .value_name("MULTISIG_ADDRESS")
+                                .takes_value(true)
+                                .index(1)
+                                .required(true)
+                                .help("The multisig authorizing the transaction"),
+                        )
+                        .arg(
+                            Arg::with_name("proposal_file")
+                                .value_name("PROPOSAL_FILE")
+                                .takes_value(true)
+                                .index(2)
+                                .required(true)
+                                .help("File to save the proposal to"),
+                        )
+                        .arg(
+                            Arg::with_name("command")
+                                .value_name("COMMAND")
+                                .takes_value(true)
+                                .index(3)
+                                .multiple(true)
+                                .required(true)
+                                .help("The command to propose, with all of its arguments. \
+                                       It must use a durable nonce, with `--nonce`, and list \
+                                       exactly M of the multisig signers, those that will \
+                                       sign, with `--multisig-signer`"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("sign")
+                        .about("Add a signature to a proposal. Does not require network access")
+                        .arg(
+                            Arg::with_name("proposal_file")
+                                .value_name("PROPOSAL_FILE")
+                                .takes_value(true)
+                                .index(1)
+                                .required(true)
+                                .help("The proposal to sign"),
+                        )
+                        .arg(
+                            Arg::with_name("signer")
+                                .long("signer")
+                                .value_name("SIGNER_KEYPAIR")
+                                .validator(is_valid_signer)
+                                .takes_value(true)
+                                .help("Specify the signer. \
+                                       This may be a keypair file or the ASK keyword. \
+                                       Defaults to the client keypair."),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("submit")
+                        .about("Send a fully signed proposal")
+                        .arg(
+                            Arg::with_name("proposal_file")
+                                .value_name("PROPOSAL_FILE")
+                                .takes_value(true)
+                                .index(1)
+                                .required(true)
+                                .help("The proposal to send"),
+                        ),
+                ),
+        );
+    let app_matches = app.clone().get_matches();
+
+    // `multisig propose` runs the command it wraps as usual, but saves the
+    // resulting transaction to the proposal file instead of sending it
+    let proposal = match app_matches.subcommand() {
+        ("multisig", Some(multisig_matches)) => match multisig_matches.subcommand() {
+            ("propose", Some(propose_matches)) => Some((
+                pubkey_of(propose_matches, "multisig").unwrap(),
+                propose_matches
+                    .value_of("proposal_file")
+                    .unwrap()
+                    .to_string(),
+                propose_matches
+                    .values_of("command")
+                    .unwrap()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<_>>(),
+            )),
+            _ => None,
+        },
+        _ => None,
+    };
+    let (app_matches, proposal) = if let Some((multisig, proposal_file, command)) = proposal {
+        let command_matches =
+            app.get_matches_from(std::iter::once(crate_name!().to_string()).chain(command));
+        if let ("multisig", _) = command_matches.subcommand() {
+            eprintln!("error: `multisig` commands cannot be proposed");
+            exit(1);
+        }
+        (command_matches, Some((multisig, proposal_file)))
+    } else {
+        (app_matches, None)
+    };
 
     let mut wallet_manager = None;
     let mut bulk_signers: Vec<Box<dyn Signer>> = Vec::new();
@@ -2162,11 +2758,19 @@ fn main() {
         let blockhash_query = BlockhashQuery::new_from_matches(matches);
         let sign_only = matches.is_present(SIGN_ONLY_ARG.name);
 
-        let multisig_signers = signers_of(matches, MULTISIG_SIGNER_ARG.name, &mut wallet_manager)
-            .unwrap_or_else(|e| {
-                eprintln!("error: {}", e);
-                exit(1);
-            });
+        // Multisig signers of a proposal sign later, on their own machines
+        let multisig_signers = signers_of(
+            matches,
+            MULTISIG_SIGNER_ARG.name,
+            &mut wallet_manager,
+            &SignerFromPathConfig {
+                allow_null_signer: proposal.is_some(),
+            },
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            exit(1);
+        });
         if let Some(mut multisig_signers) = multisig_signers {
             multisig_signers.sort_by(|(_, lp), (_, rp)| lp.cmp(rp));
             let (signers, pubkeys): (Vec<_>, Vec<_>) = multisig_signers.into_iter().unzip();
@@ -2208,6 +2812,13 @@ fn main() {
                 token,
                 mint_authority,
                 arg_matches.is_present("enable_freeze"),
+                arg_matches.is_present("non_transferable"),
+                value_t!(arg_matches, "interest_rate", BasisPoints).ok(),
+                pubkey_of_signer(arg_matches, "permanent_delegate", &mut wallet_manager).unwrap(),
+                arg_matches
+                    .value_of("default_account_state")
+                    .map(parse_account_state),
+                arg_matches.is_present("enable_close"),
                 memo,
             )
         }
@@ -2260,6 +2871,10 @@ fn main() {
                 "freeze" => AuthorityType::FreezeAccount,
                 "owner" => AuthorityType::AccountOwner,
                 "close" => AuthorityType::CloseAccount,
+                "withheld-withdraw" => AuthorityType::WithheldWithdraw,
+                "interest-rate" => AuthorityType::InterestRate,
+                "permanent-delegate" => AuthorityType::PermanentDelegate,
+                "close-mint" => AuthorityType::CloseMint,
                 _ => unreachable!(),
             };
 
@@ -2302,6 +2917,7 @@ fn main() {
                 || matches.is_present("allow_unfunded_recipient");
             no_wait = matches.is_present("no_wait");
             let recipient_is_ata_owner = matches.is_present("recipient_is_ata_owner");
+            let memo = value_t!(arg_matches, "memo", String).ok();
 
             command_transfer(
                 &config,
@@ -2314,6 +2930,39 @@ fn main() {
                 fund_recipient,
                 mint_decimals,
                 recipient_is_ata_owner,
+                memo,
+            )
+        }
+        ("distribute", Some(arg_matches)) => {
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let csv_path = arg_matches.value_of("csv").unwrap();
+            let ledger_path = arg_matches
+                .value_of("ledger")
+                .map(|path| path.to_string())
+                .unwrap_or_else(|| format!("{}.ledger.json", csv_path));
+            let sender = pubkey_of_signer(arg_matches, "from", &mut wallet_manager).unwrap();
+
+            let (owner_signer, owner) =
+                config.signer_or_default(arg_matches, "owner", &mut wallet_manager);
+            bulk_signers.push(owner_signer);
+
+            let batch_size = value_t_or_exit!(arg_matches, "batch_size", usize);
+            // Transactions are sent as the distribution progresses, so the
+            // command needs the signers itself
+            let signer_info = CliSignerInfo {
+                signers: std::mem::take(&mut bulk_signers),
+            };
+            command_distribute(
+                &config,
+                &signer_info,
+                token,
+                csv_path,
+                &ledger_path,
+                sender,
+                owner,
+                batch_size,
             )
         }
         ("burn", Some(arg_matches)) => {
@@ -2367,6 +3016,17 @@ fn main() {
                 pubkey_of_signer(arg_matches, MINT_ADDRESS_ARG.name, &mut wallet_manager).unwrap();
             command_freeze(&config, account, mint_address, freeze_authority)
         }
+        ("update-default-account-state", Some(arg_matches)) => {
+            let (freeze_authority_signer, freeze_authority) =
+                config.signer_or_default(arg_matches, "freeze_authority", &mut wallet_manager);
+            bulk_signers.push(freeze_authority_signer);
+
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let state = parse_account_state(arg_matches.value_of("state").unwrap());
+            command_update_default_account_state(&config, token, state, freeze_authority)
+        }
         ("thaw", Some(arg_matches)) => {
             let (freeze_authority_signer, freeze_authority) =
                 config.signer_or_default(arg_matches, "freeze_authority", &mut wallet_manager);
@@ -2455,6 +3115,17 @@ fn main() {
             let recipient = config.pubkey_or_default(arg_matches, "recipient", &mut wallet_manager);
             command_close(&config, address, close_authority, recipient)
         }
+        ("close-mint", Some(arg_matches)) => {
+            let (close_authority_signer, close_authority) =
+                config.signer_or_default(arg_matches, "close_authority", &mut wallet_manager);
+            bulk_signers.push(close_authority_signer);
+
+            let token = pubkey_of_signer(arg_matches, "token", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let recipient = config.pubkey_or_default(arg_matches, "recipient", &mut wallet_manager);
+            command_close_mint(&config, token, close_authority, recipient)
+        }
         ("balance", Some(arg_matches)) => {
             let address = config.associated_token_address_or_override(
                 arg_matches,
@@ -2487,6 +3158,20 @@ fn main() {
             );
             command_account_info(&config, address)
         }
+        ("history", Some(arg_matches)) => {
+            let address = pubkey_of_signer(arg_matches, "address", &mut wallet_manager)
+                .unwrap()
+                .unwrap();
+            let limit = value_of::<usize>(arg_matches, "limit");
+            let at_slot = value_of::<Slot>(arg_matches, "at_slot");
+            command_history(
+                &config,
+                address,
+                limit,
+                at_slot,
+                arg_matches.is_present("csv"),
+            )
+        }
         ("multisig-info", Some(arg_matches)) => {
             let address = pubkey_of_signer(arg_matches, "address", &mut wallet_manager)
                 .unwrap()
@@ -2509,6 +3194,19 @@ fn main() {
 
             command_gc(&config, owner_address)
         }
+        ("multisig", Some(arg_matches)) => match arg_matches.subcommand() {
+            ("sign", Some(arg_matches)) => {
+                let proposal_file = arg_matches.value_of("proposal_file").unwrap();
+                let (signer, _) =
+                    config.signer_or_default(arg_matches, "signer", &mut wallet_manager);
+                command_multisig_sign(&config, proposal_file, signer)
+            }
+            ("submit", Some(arg_matches)) => {
+                let proposal_file = arg_matches.value_of("proposal_file").unwrap();
+                command_multisig_submit(&config, proposal_file)
+            }
+            _ => unreachable!(),
+        },
         ("sync-native", Some(arg_matches)) => {
             let address = config.associated_token_address_for_token_or_override(
                 arg_matches,
@@ -2527,6 +3225,9 @@ fn main() {
             let signer_info = CliSignerInfo {
                 signers: bulk_signers,
             };
+            if proposal.is_some() && instruction_batches.len() != 1 {
+                return Err("Only commands sending a single transaction can be proposed".into());
+            }
 
             for instructions in instruction_batches {
                 let message = if let Some(nonce_account) = config.nonce_account.as_ref() {
@@ -2560,7 +3261,10 @@ fn main() {
                 let signers = signer_info.signers_for_message(&message);
                 let mut transaction = Transaction::new_unsigned(message);
 
-                if config.sign_only {
+                if let Some((multisig, proposal_file)) = &proposal {
+                    transaction.try_partial_sign(&signers, recent_blockhash)?;
+                    save_proposal(&config, proposal_file, *multisig, transaction)?;
+                } else if config.sign_only {
                     transaction.try_partial_sign(&signers, recent_blockhash)?;
                     println!("{}", return_signers(&transaction, &config.output_format)?);
                 } else {
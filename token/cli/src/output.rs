@@ -21,12 +21,21 @@ pub(crate) fn println_display(config: &Config, message: String) {
 pub(crate) struct CliTokenAmount {
     #[serde(flatten)]
     pub(crate) amount: UiTokenAmount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) interest_adjusted_ui_amount: Option<f64>,
 }
 
 impl QuietDisplay for CliTokenAmount {}
 impl VerboseDisplay for CliTokenAmount {
     fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
         writeln!(w, "ui amount: {}", self.amount.real_number_string_trimmed())?;
+        if let Some(interest_adjusted_ui_amount) = self.interest_adjusted_ui_amount {
+            writeln!(
+                w,
+                "interest-adjusted ui amount: {}",
+                interest_adjusted_ui_amount
+            )?;
+        }
         writeln!(w, "decimals: {}", self.amount.decimals)?;
         writeln!(w, "amount: {}", self.amount.amount)
     }
@@ -34,7 +43,11 @@ impl VerboseDisplay for CliTokenAmount {
 
 impl fmt::Display for CliTokenAmount {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "{}", self.amount.real_number_string_trimmed())
+        if let Some(interest_adjusted_ui_amount) = self.interest_adjusted_ui_amount {
+            writeln!(f, "{}", interest_adjusted_ui_amount)
+        } else {
+            writeln!(f, "{}", self.amount.real_number_string_trimmed())
+        }
     }
 }
 
@@ -87,6 +100,88 @@ impl fmt::Display for CliMultisig {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliProposalSigner {
+    pub(crate) pubkey: String,
+    pub(crate) is_multisig_signer: bool,
+    pub(crate) signed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliProposalInstruction {
+    pub(crate) program_id: String,
+    pub(crate) accounts: Vec<String>,
+    pub(crate) decoded: String,
+}
+
+pub(crate) struct CliProposalInstructions(pub(crate) Vec<CliProposalInstruction>);
+
+impl fmt::Display for CliProposalInstructions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln_name_value(f, "Instructions:", " ")?;
+        for (i, instruction) in self.0.iter().enumerate() {
+            writeln!(f, "  {}. {}", i, instruction.decoded)?;
+            writeln!(f, "     Program: {}", instruction.program_id)?;
+            for (j, account) in instruction.accounts.iter().enumerate() {
+                writeln!(f, "     Account {}: {}", j, account)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliMultisigProposal {
+    pub(crate) multisig: String,
+    pub(crate) m: u8,
+    pub(crate) signers: Vec<CliProposalSigner>,
+    pub(crate) instructions: Vec<CliProposalInstruction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) signature: Option<String>,
+}
+
+impl QuietDisplay for CliMultisigProposal {}
+impl VerboseDisplay for CliMultisigProposal {}
+
+impl fmt::Display for CliMultisigProposal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let multisig_signatures = self
+            .signers
+            .iter()
+            .filter(|signer| signer.is_multisig_signer && signer.signed)
+            .count();
+        writeln!(f)?;
+        writeln_name_value(f, "Multisig:", &self.multisig)?;
+        writeln_name_value(
+            f,
+            "Multisig signatures:",
+            &format!("{} of {} required", multisig_signatures, self.m),
+        )?;
+        writeln_name_value(f, "Signers:", " ")?;
+        for signer in &self.signers {
+            writeln!(
+                f,
+                "  {:<44}  {}{}",
+                signer.pubkey,
+                if signer.signed { "signed" } else { "missing" },
+                if signer.is_multisig_signer {
+                    ""
+                } else {
+                    " (not a multisig signer)"
+                },
+            )?;
+        }
+        if let Some(signature) = &self.signature {
+            writeln!(f)?;
+            writeln_name_value(f, "Signature:", signature)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct CliTokenAccount {
@@ -94,6 +189,8 @@ pub(crate) struct CliTokenAccount {
     pub(crate) is_associated: bool,
     #[serde(flatten)]
     pub(crate) account: UiTokenAccount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) interest_adjusted_ui_amount: Option<f64>,
 }
 
 impl QuietDisplay for CliTokenAccount {}
@@ -112,6 +209,13 @@ impl fmt::Display for CliTokenAccount {
             "Balance:",
             &self.account.token_amount.real_number_string_trimmed(),
         )?;
+        if let Some(interest_adjusted_ui_amount) = self.interest_adjusted_ui_amount {
+            writeln_name_value(
+                f,
+                "Balance with interest:",
+                &interest_adjusted_ui_amount.to_string(),
+            )?;
+        }
         let mint = format!(
             "{}{}",
             self.account.mint,
@@ -314,6 +418,170 @@ impl fmt::Display for CliTokenAccounts {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliUndistributed {
+    pub(crate) recipient: String,
+    pub(crate) amount: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliDistributionReport {
+    pub(crate) token: String,
+    pub(crate) sender: String,
+    pub(crate) sender_balance: String,
+    pub(crate) ledger: String,
+    pub(crate) recipients: usize,
+    pub(crate) total_amount: f64,
+    pub(crate) distributed_amount: f64,
+    pub(crate) distributed_previously: usize,
+    pub(crate) distributed_this_run: usize,
+    pub(crate) undistributed: Vec<CliUndistributed>,
+}
+
+impl QuietDisplay for CliDistributionReport {}
+impl VerboseDisplay for CliDistributionReport {}
+
+impl fmt::Display for CliDistributionReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln_name_value(f, "Token:", &self.token)?;
+        writeln_name_value(f, "Sender:", &self.sender)?;
+        writeln_name_value(f, "Sender balance:", &self.sender_balance)?;
+        writeln_name_value(f, "Ledger:", &self.ledger)?;
+        writeln_name_value(f, "Recipients:", &self.recipients.to_string())?;
+        writeln_name_value(f, "Total amount:", &self.total_amount.to_string())?;
+        writeln_name_value(
+            f,
+            "Distributed amount:",
+            &self.distributed_amount.to_string(),
+        )?;
+        writeln_name_value(
+            f,
+            "Distributed previously:",
+            &self.distributed_previously.to_string(),
+        )?;
+        writeln_name_value(
+            f,
+            "Distributed this run:",
+            &self.distributed_this_run.to_string(),
+        )?;
+        if !self.undistributed.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "{} Undistributed:", WARNING)?;
+            writeln!(f, "{:<44}  Amount", "Recipient")?;
+            for undistributed in &self.undistributed {
+                writeln!(
+                    f,
+                    "{:<44}  {}",
+                    undistributed.recipient, undistributed.amount
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliHistoryEntry {
+    pub(crate) slot: u64,
+    pub(crate) block_time: Option<i64>,
+    pub(crate) signature: String,
+    pub(crate) instructions: Vec<String>,
+    pub(crate) change: String,
+    pub(crate) balance: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CliTokenHistory {
+    pub(crate) address: String,
+    pub(crate) entries: Vec<CliHistoryEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) at_slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) balance_at_slot: Option<String>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl CliTokenHistory {
+    pub(crate) fn to_csv(&self) -> String {
+        let mut csv = "slot,block_time,signature,change,balance,instructions\n".to_string();
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                entry.slot,
+                entry
+                    .block_time
+                    .map(|block_time| block_time.to_string())
+                    .unwrap_or_default(),
+                entry.signature,
+                entry.change,
+                entry.balance,
+                csv_field(&entry.instructions.join("; ")),
+            ));
+        }
+        csv
+    }
+}
+
+impl QuietDisplay for CliTokenHistory {}
+impl VerboseDisplay for CliTokenHistory {
+    fn write_str(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        writeln!(w)?;
+        for entry in &self.entries {
+            writeln_name_value(w, "Slot:", &entry.slot.to_string())?;
+            if let Some(block_time) = entry.block_time {
+                writeln_name_value(w, "Block time:", &block_time.to_string())?;
+            }
+            writeln_name_value(w, "Signature:", &entry.signature)?;
+            writeln_name_value(w, "Change:", &entry.change)?;
+            writeln_name_value(w, "Balance:", &entry.balance)?;
+            for instruction in &entry.instructions {
+                writeln!(w, "  {}", instruction)?;
+            }
+            writeln!(w)?;
+        }
+        if let (Some(at_slot), Some(balance_at_slot)) = (self.at_slot, &self.balance_at_slot) {
+            writeln_name_value(w, &format!("Balance at slot {}:", at_slot), balance_at_slot)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CliTokenHistory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<12}  {:<88}  {:>20}  {:>20}",
+            "Slot", "Signature", "Change", "Balance"
+        )?;
+        writeln!(f, "{}", "-".repeat(146))?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:<12}  {:<88}  {:>20}  {:>20}",
+                entry.slot, entry.signature, entry.change, entry.balance
+            )?;
+        }
+        if let (Some(at_slot), Some(balance_at_slot)) = (self.at_slot, &self.balance_at_slot) {
+            writeln!(f)?;
+            writeln_name_value(f, &format!("Balance at slot {}:", at_slot), balance_at_slot)?;
+        }
+        Ok(())
+    }
+}
+
 fn flattened<S: Serializer>(
     vec: &[Vec<CliTokenAccount>],
     serializer: S,
@@ -0,0 +1,258 @@
+//! Multisig transaction proposals, collecting signatures across machines
+
+use crate::{config::Config, get_multisig, output::*, CommandResult, Error};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    program_pack::Pack, pubkey::Pubkey, sanitize::Sanitize, signature::Signer,
+    system_instruction::SystemInstruction, system_program, transaction::Transaction,
+};
+use spl_token::{
+    instruction::{TokenInstruction, MAX_SIGNERS},
+    state::Multisig,
+};
+use std::{fs, str::FromStr};
+
+/// Contents of a proposal file
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProposalFile {
+    /// Address of the multisig authority
+    multisig: String,
+    /// Multisig account data when the proposal was made, base64-encoded, so
+    /// that signers can validate the proposal offline
+    multisig_data: String,
+    /// Partially signed transaction, bincode- and base64-encoded
+    transaction: String,
+}
+
+struct Proposal {
+    multisig_address: Pubkey,
+    multisig: Multisig,
+    transaction: Transaction,
+}
+
+impl Proposal {
+    fn load(path: &str) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read proposal {}: {}", path, err))?;
+        let file: ProposalFile = serde_json::from_str(&contents)
+            .map_err(|err| format!("Invalid proposal {}: {}", path, err))?;
+        let proposal = Self {
+            multisig_address: Pubkey::from_str(&file.multisig)?,
+            multisig: Multisig::unpack(&base64::decode(&file.multisig_data)?)?,
+            transaction: bincode::deserialize(&base64::decode(&file.transaction)?)?,
+        };
+        proposal.validate()?;
+        Ok(proposal)
+    }
+
+    fn save(&self, path: &str) -> Result<(), Error> {
+        let mut multisig_data = vec![0; Multisig::LEN];
+        Multisig::pack(self.multisig, &mut multisig_data)?;
+        let file = ProposalFile {
+            multisig: self.multisig_address.to_string(),
+            multisig_data: base64::encode(multisig_data),
+            transaction: base64::encode(bincode::serialize(&self.transaction)?),
+        };
+        fs::write(path, serde_json::to_string_pretty(&file)?)
+            .map_err(|err| format!("Unable to write proposal {}: {}", path, err).into())
+    }
+
+    fn is_multisig_signer(&self, pubkey: &Pubkey) -> bool {
+        self.multisig.signers[..self.multisig.n as usize].contains(pubkey)
+    }
+
+    fn required_signers(&self) -> &[Pubkey] {
+        let message = &self.transaction.message;
+        &message.account_keys[..message.header.num_required_signatures as usize]
+    }
+
+    fn instructions(&self) -> Vec<CliProposalInstruction> {
+        let message = &self.transaction.message;
+        message
+            .instructions
+            .iter()
+            .map(|instruction| {
+                let program_id = message.account_keys[instruction.program_id_index as usize];
+                CliProposalInstruction {
+                    program_id: program_id.to_string(),
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|i| message.account_keys[*i as usize].to_string())
+                        .collect(),
+                    decoded: decode_instruction(&program_id, &instruction.data),
+                }
+            })
+            .collect()
+    }
+
+    // The multisig must be well-formed, and the transaction must be authorized
+    // by it and require the signatures of exactly `m` of its signers, so that
+    // it can be sent once those `m` have signed
+    fn validate(&self) -> Result<(), Error> {
+        let Multisig { m, n, .. } = self.multisig;
+        if n as usize > MAX_SIGNERS || m == 0 || m > n {
+            return Err(format!(
+                "Multisig {} is invalid, requiring {} of {} signers",
+                self.multisig_address, m, n
+            )
+            .into());
+        }
+        self.transaction
+            .sanitize()
+            .map_err(|err| format!("Invalid proposal transaction: {}", err))?;
+        let message = &self.transaction.message;
+        if !message.account_keys.contains(&self.multisig_address) {
+            return Err(format!(
+                "Transaction does not involve multisig {}",
+                self.multisig_address
+            )
+            .into());
+        }
+        let multisig_signers = self
+            .required_signers()
+            .iter()
+            .filter(|pubkey| self.is_multisig_signer(pubkey))
+            .count();
+        if multisig_signers != m as usize {
+            return Err(format!(
+                "Transaction must require exactly {} of the multisig's signers, but {} are present",
+                m, multisig_signers
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> CliMultisigProposal {
+        let signed = self.transaction.verify_with_results();
+        let signers = self
+            .required_signers()
+            .iter()
+            .zip(signed)
+            .map(|(pubkey, signed)| CliProposalSigner {
+                pubkey: pubkey.to_string(),
+                is_multisig_signer: self.is_multisig_signer(pubkey),
+                signed,
+            })
+            .collect();
+        CliMultisigProposal {
+            multisig: self.multisig_address.to_string(),
+            m: self.multisig.m,
+            signers,
+            instructions: self.instructions(),
+            signature: None,
+        }
+    }
+}
+
+fn decode_instruction(program_id: &Pubkey, data: &[u8]) -> String {
+    let decoded = if *program_id == spl_token::id() {
+        TokenInstruction::unpack(data)
+            .ok()
+            .map(|instruction| format!("{:?}", instruction))
+    } else if *program_id == system_program::id() {
+        bincode::deserialize::<SystemInstruction>(data)
+            .ok()
+            .map(|instruction| format!("{:?}", instruction))
+    } else if *program_id == spl_memo::id() || *program_id == spl_memo::v1::id() {
+        std::str::from_utf8(data)
+            .ok()
+            .map(|memo| format!("Memo {:?}", memo))
+    } else {
+        None
+    };
+    decoded.unwrap_or_else(|| format!("Unknown instruction, data {}", base64::encode(data)))
+}
+
+/// Save `transaction`, authorized by `multisig_address`, as a proposal for
+/// the multisig signers to sign
+pub(crate) fn save_proposal(
+    config: &Config,
+    path: &str,
+    multisig_address: Pubkey,
+    transaction: Transaction,
+) -> Result<(), Error> {
+    if config.nonce_account.is_none() {
+        return Err(
+            "Proposals must use a durable nonce, so that they do not expire while \
+             signatures are collected. Add `--nonce`"
+                .into(),
+        );
+    }
+    let proposal = Proposal {
+        multisig_address,
+        multisig: get_multisig(config, &multisig_address)?,
+        transaction,
+    };
+    proposal.validate()?;
+    proposal.save(path)?;
+    println_display(config, format!("Proposal saved to {}", path));
+    println!(
+        "{}",
+        config.output_format.formatted_string(&proposal.status())
+    );
+    Ok(())
+}
+
+pub(crate) fn command_multisig_sign(
+    config: &Config,
+    path: &str,
+    signer: Box<dyn Signer>,
+) -> CommandResult {
+    let mut proposal = Proposal::load(path)?;
+    let pubkey = signer.pubkey();
+    if !proposal.required_signers().contains(&pubkey) {
+        return Err(format!("{} is not a signer of this proposal", pubkey).into());
+    }
+    // Show what is being signed before signing it
+    println_display(
+        config,
+        format!(
+            "Signing proposal as {}\n{}",
+            pubkey,
+            CliProposalInstructions(proposal.instructions())
+        ),
+    );
+    let recent_blockhash = proposal.transaction.message.recent_blockhash;
+    proposal
+        .transaction
+        .try_partial_sign(&vec![signer], recent_blockhash)?;
+    proposal.save(path)?;
+    println!(
+        "{}",
+        config.output_format.formatted_string(&proposal.status())
+    );
+    Ok(None)
+}
+
+pub(crate) fn command_multisig_submit(config: &Config, path: &str) -> CommandResult {
+    let proposal = Proposal::load(path)?;
+    let multisig = get_multisig(config, &proposal.multisig_address)?;
+    if multisig != proposal.multisig {
+        return Err(format!(
+            "Multisig {} does not match the proposal",
+            proposal.multisig_address
+        )
+        .into());
+    }
+
+    let mut status = proposal.status();
+    let missing = status
+        .signers
+        .iter()
+        .filter(|signer| !signer.signed)
+        .map(|signer| signer.pubkey.as_str())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(format!("Proposal is missing signatures from {}", missing.join(", ")).into());
+    }
+
+    let signature = config
+        .rpc_client
+        .send_and_confirm_transaction_with_spinner(&proposal.transaction)?;
+    status.signature = Some(signature.to_string());
+    println!("{}", config.output_format.formatted_string(&status));
+    Ok(None)
+}
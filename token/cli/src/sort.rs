@@ -52,6 +52,7 @@ pub(crate) fn sort_and_parse_token_accounts(
                             address,
                             account: ui_token_account,
                             is_associated,
+                            interest_adjusted_ui_amount: None,
                         };
                         let entry = mint_accounts.entry(mint);
                         match entry {
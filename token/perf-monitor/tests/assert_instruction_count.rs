@@ -136,10 +136,29 @@ fn assert_instruction_count() {
     ];
     let transfer_count = run_program(&program_id, &parameter_accounts[..], &instruction_data);
 
+    // Transfer back from destination to source, in two batched transfers
+    let instruction = TokenInstruction::Batch {
+        instructions: vec![
+            (3, TokenInstruction::Transfer { amount: 50 }),
+            (3, TokenInstruction::Transfer { amount: 50 }),
+        ],
+    };
+    let instruction_data = instruction.pack();
+    let parameter_accounts = vec![
+        KeyedAccount::new(&destination_key, false, &destination_account),
+        KeyedAccount::new(&source_key, false, &source_account),
+        KeyedAccount::new(&owner_key, true, &owner_account),
+        KeyedAccount::new(&destination_key, false, &destination_account),
+        KeyedAccount::new(&source_key, false, &source_account),
+        KeyedAccount::new(&owner_key, true, &owner_account),
+    ];
+    let batch_transfer_count = run_program(&program_id, &parameter_accounts[..], &instruction_data);
+
     const BASELINE_NEW_MINT_COUNT: u64 = 4000; // last known 3802
     const BASELINE_INITIALIZE_ACCOUNT_COUNT: u64 = 6500; // last known 6445
     const BASELINE_MINTTO_COUNT: u64 = 6500; // last known 6194
     const BASELINE_TRANSFER_COUNT: u64 = 8000; // last known 7609
+    const BASELINE_BATCH_TRANSFER_COUNT: u64 = 14000;
 
     println!("BPF instructions executed");
     println!(
@@ -158,8 +177,16 @@ fn assert_instruction_count() {
         "  Transfer         : {:?} ({:?})",
         transfer_count, BASELINE_TRANSFER_COUNT,
     );
+    println!(
+        "  Batch (2x Transfer): {:?} ({:?})",
+        batch_transfer_count, BASELINE_BATCH_TRANSFER_COUNT,
+    );
 
     assert!(initialize_account_count <= BASELINE_INITIALIZE_ACCOUNT_COUNT);
     assert!(initialize_mint_count <= BASELINE_NEW_MINT_COUNT);
     assert!(transfer_count <= BASELINE_TRANSFER_COUNT);
+    assert!(batch_transfer_count <= BASELINE_BATCH_TRANSFER_COUNT);
+    // batching saves the per-instruction overhead of the second transfer
+    assert!(batch_transfer_count < 2 * BASELINE_TRANSFER_COUNT);
+    assert!(batch_transfer_count < 2 * transfer_count);
 }
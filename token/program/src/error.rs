@@ -74,6 +74,29 @@ pub enum TokenError {
     /// Instruction does not support non-native tokens
     #[error("Instruction does not support non-native tokens")]
     NonNativeNotSupported,
+
+    // 20
+    /// Transfer fee exceeds maximum of 10,000 basis points
+    #[error("Transfer fee exceeds maximum of 10,000 basis points")]
+    TransferFeeExceedsMaximum,
+    /// Mint required for this account to transfer tokens, use `TransferChecked`
+    #[error("Mint required for this account to transfer tokens, use `TransferChecked`")]
+    MintRequiredForTransfer,
+    /// Account has withheld transfer fees, which must be withdrawn before closing
+    #[error("Account has withheld transfer fees, which must be withdrawn before closing")]
+    AccountHasWithheldTransferFees,
+    /// No authority exists to perform the desired operation
+    #[error("No authority exists to perform the desired operation")]
+    NoAuthorityExists,
+    /// No memo in previous instruction; required for recipient to receive a transfer
+    #[error("No memo in previous instruction; required for recipient to receive a transfer")]
+    NoMemo,
+    /// Transfer is disabled for this mint
+    #[error("Transfer is disabled for this mint")]
+    NonTransferable,
+    /// Mint has a nonzero supply and cannot be closed
+    #[error("Mint has a nonzero supply and cannot be closed")]
+    MintHasSupply,
 }
 impl From<TokenError> for ProgramError {
     fn from(e: TokenError) -> Self {
@@ -0,0 +1,60 @@
+//! Default account state extension
+
+use crate::{
+    extension::{Extension, ExtensionType},
+    state::AccountState,
+};
+use num_enum::TryFromPrimitive;
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+};
+
+/// Default account state extension data for mints.
+///
+/// Accounts of the mint start out in this state, so a mint that defaults to
+/// `Frozen` requires the freeze authority to thaw each new account.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DefaultAccountState {
+    /// Default state of new accounts of the mint
+    pub state: AccountState,
+}
+impl Sealed for DefaultAccountState {}
+impl Pack for DefaultAccountState {
+    const LEN: usize = 1;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let state =
+            AccountState::try_from_primitive(src[0]).or(Err(ProgramError::InvalidAccountData))?;
+        Ok(DefaultAccountState { state })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.state as u8;
+    }
+}
+impl Extension for DefaultAccountState {
+    const TYPE: ExtensionType = ExtensionType::DefaultAccountState;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = DefaultAccountState {
+            state: AccountState::Frozen,
+        };
+        let mut packed = vec![0; DefaultAccountState::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        assert_eq!(packed, vec![2]);
+        assert_eq!(
+            DefaultAccountState::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+        assert_eq!(
+            DefaultAccountState::unpack_from_slice(&[3]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Interest-bearing mint extension
+
+use crate::{
+    extension::{Extension, ExtensionType},
+    state::{pack_coption_key, unpack_coption_key},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Annual interest rate, expressed as basis points
+pub type BasisPoints = i16;
+const ONE_IN_BASIS_POINTS: f64 = 10_000.;
+const SECONDS_PER_YEAR: f64 = 60. * 60. * 24. * 365.24;
+
+/// Interest-bearing extension data for mints
+///
+/// Tokens accrue interest continuously at the current rate.  Raw amounts are
+/// never changed, the interest is only reflected in the UI amount, computed
+/// from the raw amount and the current time.  To carry the interest accrued
+/// so far across rate changes, the time-weighted average of all previous rates
+/// is stored along with the current rate.  That average is truncated to whole
+/// basis points, so a rate change may move UI amounts slightly.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InterestBearingConfig {
+    /// Authority that can set the interest rate
+    pub rate_authority: COption<Pubkey>,
+    /// Timestamp of initialization, from which to base interest calculations
+    pub initialization_timestamp: i64,
+    /// Average rate from initialization until the last time it was updated
+    pub pre_update_average_rate: BasisPoints,
+    /// Timestamp of the last update, used to calculate the total amount accrued
+    pub last_update_timestamp: i64,
+    /// Current rate, since the last update
+    pub current_rate: BasisPoints,
+}
+impl InterestBearingConfig {
+    /// Create a new config, accruing interest at `rate` from `unix_timestamp`
+    pub fn new(rate_authority: COption<Pubkey>, rate: BasisPoints, unix_timestamp: i64) -> Self {
+        Self {
+            rate_authority,
+            initialization_timestamp: unix_timestamp,
+            pre_update_average_rate: rate,
+            last_update_timestamp: unix_timestamp,
+            current_rate: rate,
+        }
+    }
+
+    fn pre_update_exp(&self) -> Option<f64> {
+        let numerator = (self.pre_update_average_rate as i128).checked_mul(
+            self.last_update_timestamp
+                .checked_sub(self.initialization_timestamp)? as i128,
+        )? as f64;
+        let exponent = numerator / SECONDS_PER_YEAR / ONE_IN_BASIS_POINTS;
+        Some(exponent.exp())
+    }
+
+    fn post_update_exp(&self, unix_timestamp: i64) -> Option<f64> {
+        let numerator = (self.current_rate as i128)
+            .checked_mul(unix_timestamp.checked_sub(self.last_update_timestamp)? as i128)?
+            as f64;
+        let exponent = numerator / SECONDS_PER_YEAR / ONE_IN_BASIS_POINTS;
+        Some(exponent.exp())
+    }
+
+    fn total_scale(&self, decimals: u8, unix_timestamp: i64) -> Option<f64> {
+        Some(
+            self.pre_update_exp()? * self.post_update_exp(unix_timestamp)?
+                / 10_f64.powi(decimals as i32),
+        )
+    }
+
+    /// Convert a raw amount to its UI representation, including the interest
+    /// accrued up to `unix_timestamp`
+    pub fn amount_to_ui_amount(
+        &self,
+        amount: u64,
+        decimals: u8,
+        unix_timestamp: i64,
+    ) -> Option<f64> {
+        let scale = self.total_scale(decimals, unix_timestamp)?;
+        Some(amount as f64 * scale)
+    }
+
+    /// Convert a UI amount, including the interest accrued up to
+    /// `unix_timestamp`, back to the raw amount
+    pub fn ui_amount_to_amount(
+        &self,
+        ui_amount: f64,
+        decimals: u8,
+        unix_timestamp: i64,
+    ) -> Option<u64> {
+        let scale = self.total_scale(decimals, unix_timestamp)?;
+        let amount = (ui_amount / scale).round();
+        if amount.is_finite() && amount >= 0. && amount <= u64::MAX as f64 {
+            Some(amount as u64)
+        } else {
+            None
+        }
+    }
+
+    /// Time-weighted average of all rates from initialization until
+    /// `unix_timestamp`, used as the new `pre_update_average_rate` when the rate
+    /// changes.  The average is truncated toward zero to whole basis points,
+    /// so interest computed from it can drift from the interest accrued at the
+    /// previous rates by up to one basis point over the elapsed time.
+    pub fn time_weighted_average_rate(&self, unix_timestamp: i64) -> Option<BasisPoints> {
+        let total_timespan = unix_timestamp.checked_sub(self.initialization_timestamp)? as i128;
+        if total_timespan == 0 {
+            return Some(self.current_rate);
+        }
+        let pre_update_timespan = self
+            .last_update_timestamp
+            .checked_sub(self.initialization_timestamp)? as i128;
+        let post_update_timespan = unix_timestamp.checked_sub(self.last_update_timestamp)? as i128;
+        let pre_update_sum =
+            (self.pre_update_average_rate as i128).checked_mul(pre_update_timespan)?;
+        let post_update_sum = (self.current_rate as i128).checked_mul(post_update_timespan)?;
+        let average = pre_update_sum
+            .checked_add(post_update_sum)?
+            .checked_div(total_timespan)?;
+        if average < BasisPoints::MIN as i128 || average > BasisPoints::MAX as i128 {
+            None
+        } else {
+            Some(average as BasisPoints)
+        }
+    }
+
+    /// Change the rate from `unix_timestamp` onwards, keeping all interest
+    /// accrued so far
+    pub fn update_rate(&mut self, rate: BasisPoints, unix_timestamp: i64) -> Option<()> {
+        self.pre_update_average_rate = self.time_weighted_average_rate(unix_timestamp)?;
+        self.last_update_timestamp = unix_timestamp;
+        self.current_rate = rate;
+        Some(())
+    }
+}
+impl Sealed for InterestBearingConfig {}
+impl Pack for InterestBearingConfig {
+    const LEN: usize = 56;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 56];
+        let (
+            rate_authority,
+            initialization_timestamp,
+            pre_update_average_rate,
+            last_update_timestamp,
+            current_rate,
+        ) = array_refs![src, 36, 8, 2, 8, 2];
+        Ok(InterestBearingConfig {
+            rate_authority: unpack_coption_key(rate_authority)?,
+            initialization_timestamp: i64::from_le_bytes(*initialization_timestamp),
+            pre_update_average_rate: BasisPoints::from_le_bytes(*pre_update_average_rate),
+            last_update_timestamp: i64::from_le_bytes(*last_update_timestamp),
+            current_rate: BasisPoints::from_le_bytes(*current_rate),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 56];
+        let (
+            rate_authority_dst,
+            initialization_timestamp_dst,
+            pre_update_average_rate_dst,
+            last_update_timestamp_dst,
+            current_rate_dst,
+        ) = mut_array_refs![dst, 36, 8, 2, 8, 2];
+        pack_coption_key(&self.rate_authority, rate_authority_dst);
+        *initialization_timestamp_dst = self.initialization_timestamp.to_le_bytes();
+        *pre_update_average_rate_dst = self.pre_update_average_rate.to_le_bytes();
+        *last_update_timestamp_dst = self.last_update_timestamp.to_le_bytes();
+        *current_rate_dst = self.current_rate.to_le_bytes();
+    }
+}
+impl Extension for InterestBearingConfig {
+    const TYPE: ExtensionType = ExtensionType::InterestBearingConfig;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ONE_YEAR: i64 = SECONDS_PER_YEAR as i64;
+
+    #[test]
+    fn test_amount_to_ui_amount() {
+        // 10% annual rate, compounded continuously
+        let config = InterestBearingConfig::new(COption::None, 1_000, 0);
+        assert_eq!(config.amount_to_ui_amount(1_000, 2, 0), Some(10.));
+        let ui_amount = config.amount_to_ui_amount(1_000, 2, ONE_YEAR).unwrap();
+        assert!((ui_amount - 10. * 0.1_f64.exp()).abs() < 1e-9);
+        assert_eq!(
+            config.ui_amount_to_amount(ui_amount, 2, ONE_YEAR),
+            Some(1_000)
+        );
+
+        // negative rates shrink the UI amount
+        let config = InterestBearingConfig::new(COption::None, -1_000, 0);
+        let ui_amount = config.amount_to_ui_amount(1_000, 2, ONE_YEAR).unwrap();
+        assert!((ui_amount - 10. * (-0.1_f64).exp()).abs() < 1e-9);
+
+        // timestamps before initialization discount the amount
+        let config = InterestBearingConfig::new(COption::None, 1_000, ONE_YEAR);
+        let ui_amount = config.amount_to_ui_amount(1_000, 2, 0).unwrap();
+        assert!((ui_amount - 10. * (-0.1_f64).exp()).abs() < 1e-9);
+
+        // out of range
+        let config = InterestBearingConfig::new(COption::None, BasisPoints::MAX, 1);
+        assert_eq!(config.ui_amount_to_amount(-1., 2, 1), None);
+        assert_eq!(config.ui_amount_to_amount(f64::MAX, 0, 1), None);
+        assert_eq!(config.amount_to_ui_amount(1, 0, i64::MIN), None);
+    }
+
+    #[test]
+    fn test_update_rate() {
+        let mut config = InterestBearingConfig::new(COption::None, 1_000, 0);
+        config.update_rate(-1_000, ONE_YEAR).unwrap();
+        assert_eq!(config.pre_update_average_rate, 1_000);
+        assert_eq!(config.current_rate, -1_000);
+        // interest accrued before the update is kept
+        let ui_amount = config.amount_to_ui_amount(1_000, 2, ONE_YEAR).unwrap();
+        assert!((ui_amount - 10. * 0.1_f64.exp()).abs() < 1e-9);
+        // and undone by the same time at the opposite rate
+        let ui_amount = config.amount_to_ui_amount(1_000, 2, 2 * ONE_YEAR).unwrap();
+        assert!((ui_amount - 10.).abs() < 1e-9);
+
+        assert_eq!(config.time_weighted_average_rate(2 * ONE_YEAR), Some(0));
+        config.update_rate(500, 2 * ONE_YEAR).unwrap();
+        assert_eq!(config.pre_update_average_rate, 0);
+        assert_eq!(config.last_update_timestamp, 2 * ONE_YEAR);
+
+        // the average is truncated to whole basis points: 500 for one year
+        // and 0 for two averages to 166.67
+        assert_eq!(config.time_weighted_average_rate(3 * ONE_YEAR), Some(166));
+        // toward zero for negative rates too
+        let mut config = InterestBearingConfig::new(COption::None, -1, 0);
+        config.update_rate(0, ONE_YEAR).unwrap();
+        assert_eq!(config.time_weighted_average_rate(3 * ONE_YEAR), Some(0));
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = InterestBearingConfig {
+            rate_authority: COption::Some(Pubkey::new(&[1; 32])),
+            initialization_timestamp: 2,
+            pre_update_average_rate: -3,
+            last_update_timestamp: 4,
+            current_rate: 5,
+        };
+        let mut packed = vec![0; InterestBearingConfig::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        let mut expect = vec![1, 0, 0, 0];
+        expect.extend_from_slice(&[1; 32]);
+        expect.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend_from_slice(&[253, 255]);
+        expect.extend_from_slice(&[4, 0, 0, 0, 0, 0, 0, 0]);
+        expect.extend_from_slice(&[5, 0]);
+        assert_eq!(packed, expect);
+        assert_eq!(
+            InterestBearingConfig::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Memo transfer extension
+
+use crate::{
+    error::TokenError,
+    extension::{Extension, ExtensionType},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+/// Memo transfer extension data for accounts.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoTransfer {
+    /// Require transfers into this account to be preceded by a memo
+    pub require_incoming_transfer_memos: bool,
+}
+impl Sealed for MemoTransfer {}
+impl Pack for MemoTransfer {
+    const LEN: usize = 1;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let require_incoming_transfer_memos = match src {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(MemoTransfer {
+            require_incoming_transfer_memos,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.require_incoming_transfer_memos as u8;
+    }
+}
+impl Extension for MemoTransfer {
+    const TYPE: ExtensionType = ExtensionType::MemoTransfer;
+}
+
+/// Checks that the instruction preceding the one currently executing in the
+/// transaction is an spl-memo instruction, using the provided instructions
+/// sysvar account
+pub fn check_previous_instruction_is_memo(
+    instructions_sysvar_info: Option<&AccountInfo>,
+) -> ProgramResult {
+    let instructions_sysvar_info = instructions_sysvar_info.ok_or(TokenError::NoMemo)?;
+    let current_index = load_current_index_checked(instructions_sysvar_info)?;
+    let previous_index = current_index.checked_sub(1).ok_or(TokenError::NoMemo)?;
+    let previous_instruction =
+        load_instruction_at_checked(previous_index as usize, instructions_sysvar_info)?;
+    if previous_instruction.program_id == spl_memo::id()
+        || previous_instruction.program_id == spl_memo::v1::id()
+    {
+        Ok(())
+    } else {
+        Err(TokenError::NoMemo.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = MemoTransfer {
+            require_incoming_transfer_memos: true,
+        };
+        let mut packed = vec![0; MemoTransfer::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        assert_eq!(packed, vec![1]);
+        assert_eq!(MemoTransfer::unpack_from_slice(&packed).unwrap(), check);
+        assert_eq!(
+            MemoTransfer::unpack_from_slice(&[2]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
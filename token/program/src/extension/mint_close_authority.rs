@@ -0,0 +1,59 @@
+//! Mint close authority extension
+
+use crate::{
+    extension::{Extension, ExtensionType},
+    state::{pack_coption_key, unpack_coption_key},
+};
+use arrayref::{array_mut_ref, array_ref};
+use solana_program::{
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Close authority extension data for mints.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MintCloseAuthority {
+    /// Optional authority to close the mint once its supply is zero
+    pub close_authority: COption<Pubkey>,
+}
+impl Sealed for MintCloseAuthority {}
+impl Pack for MintCloseAuthority {
+    const LEN: usize = 36;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 36];
+        Ok(MintCloseAuthority {
+            close_authority: unpack_coption_key(src)?,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 36];
+        pack_coption_key(&self.close_authority, dst);
+    }
+}
+impl Extension for MintCloseAuthority {
+    const TYPE: ExtensionType = ExtensionType::MintCloseAuthority;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = MintCloseAuthority {
+            close_authority: COption::Some(Pubkey::new(&[1; 32])),
+        };
+        let mut packed = vec![0; MintCloseAuthority::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        let mut expect = vec![1, 0, 0, 0];
+        expect.extend_from_slice(&[1; 32]);
+        assert_eq!(packed, expect);
+        assert_eq!(
+            MintCloseAuthority::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+    }
+}
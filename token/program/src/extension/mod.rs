@@ -0,0 +1,676 @@
+//! Extensions available to token mints and accounts
+//!
+//! An account with extensions starts with the same layout as a legacy `Mint`
+//! or `Account`.  Mints are padded with zeroes up to `Account::LEN`, so that
+//! the byte at `ACCOUNT_TYPE_INDEX` always holds the `AccountType`, followed
+//! by a type-length-value list of extensions.
+
+use crate::state::{Account, Mint, Multisig};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use solana_program::{
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+};
+use std::convert::{TryFrom, TryInto};
+
+pub mod default_account_state;
+pub mod interest_bearing_mint;
+pub mod memo_transfer;
+pub mod mint_close_authority;
+pub mod non_transferable;
+pub mod permanent_delegate;
+pub mod transfer_fee;
+
+/// Index of the `AccountType` byte in any account with extensions
+pub const ACCOUNT_TYPE_INDEX: usize = Account::LEN;
+
+const TLV_START_INDEX: usize = ACCOUNT_TYPE_INDEX + 1;
+const TYPE_LEN: usize = 2;
+const LENGTH_LEN: usize = 2;
+const TLV_HEADER_LEN: usize = TYPE_LEN + LENGTH_LEN;
+
+/// Account type discriminator, stored after the base state once an account
+/// has room for extensions.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive)]
+pub enum AccountType {
+    /// Marker for 0 data
+    Uninitialized,
+    /// Mint account with additional extensions
+    Mint,
+    /// Token holding account with additional extensions
+    Account,
+}
+impl Default for AccountType {
+    fn default() -> Self {
+        AccountType::Uninitialized
+    }
+}
+
+/// Extensions that can be applied to mints or accounts.  Mint extensions must
+/// only be applied to mint accounts, and account extensions must only be
+/// applied to token holding accounts.
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive, IntoPrimitive)]
+pub enum ExtensionType {
+    /// Used as padding if the account size would otherwise be 355, same as a
+    /// multisig
+    Uninitialized,
+    /// Includes transfer fee rate info and accompanying authorities to withdraw
+    /// withheld fees
+    TransferFeeConfig,
+    /// Includes withheld transfer fees
+    TransferFeeAmount,
+    /// Includes an optional requirement for memos on incoming transfers
+    MemoTransfer,
+    /// Indicates that the tokens of the mint may not be transferred
+    NonTransferable,
+    /// Indicates that the account belongs to a non-transferable mint
+    NonTransferableAccount,
+    /// Includes the interest rate and accompanying authority, used to show
+    /// interest-adjusted UI amounts
+    InterestBearingConfig,
+    /// Includes an optional permanent delegate for all accounts of the mint
+    PermanentDelegate,
+    /// Includes the default state of new accounts of the mint
+    DefaultAccountState,
+    /// Includes an optional authority to close the mint
+    MintCloseAuthority,
+}
+impl ExtensionType {
+    /// Get the data length of the type associated with the enum
+    pub fn get_type_len(&self) -> usize {
+        match self {
+            ExtensionType::Uninitialized => 0,
+            ExtensionType::TransferFeeConfig => transfer_fee::TransferFeeConfig::LEN,
+            ExtensionType::TransferFeeAmount => transfer_fee::TransferFeeAmount::LEN,
+            ExtensionType::MemoTransfer => memo_transfer::MemoTransfer::LEN,
+            ExtensionType::NonTransferable => non_transferable::NonTransferable::LEN,
+            ExtensionType::NonTransferableAccount => non_transferable::NonTransferableAccount::LEN,
+            ExtensionType::InterestBearingConfig => {
+                interest_bearing_mint::InterestBearingConfig::LEN
+            }
+            ExtensionType::PermanentDelegate => permanent_delegate::PermanentDelegate::LEN,
+            ExtensionType::DefaultAccountState => default_account_state::DefaultAccountState::LEN,
+            ExtensionType::MintCloseAuthority => mint_close_authority::MintCloseAuthority::LEN,
+        }
+    }
+
+    /// Get the associated account type
+    pub fn get_account_type(&self) -> AccountType {
+        match self {
+            ExtensionType::Uninitialized => AccountType::Uninitialized,
+            ExtensionType::TransferFeeConfig
+            | ExtensionType::NonTransferable
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::PermanentDelegate
+            | ExtensionType::DefaultAccountState
+            | ExtensionType::MintCloseAuthority => AccountType::Mint,
+            ExtensionType::TransferFeeAmount
+            | ExtensionType::MemoTransfer
+            | ExtensionType::NonTransferableAccount => AccountType::Account,
+        }
+    }
+
+    /// Get the required account data length for the given base state and
+    /// extension types
+    pub fn get_account_len<S: BaseState>(extension_types: &[ExtensionType]) -> usize {
+        if extension_types.is_empty() {
+            S::LEN
+        } else {
+            let extension_len = extension_types
+                .iter()
+                .map(|e| TLV_HEADER_LEN + e.get_type_len())
+                .sum::<usize>();
+            let account_len = TLV_START_INDEX + extension_len;
+            if account_len == Multisig::LEN {
+                account_len + TYPE_LEN
+            } else {
+                account_len
+            }
+        }
+    }
+
+    /// Get the account extensions required by the given mint extensions
+    pub fn get_required_init_account_extensions(
+        mint_extension_types: &[ExtensionType],
+    ) -> Vec<ExtensionType> {
+        let mut account_extension_types = vec![];
+        for extension_type in mint_extension_types {
+            match extension_type {
+                ExtensionType::TransferFeeConfig => {
+                    account_extension_types.push(ExtensionType::TransferFeeAmount);
+                }
+                ExtensionType::NonTransferable => {
+                    account_extension_types.push(ExtensionType::NonTransferableAccount);
+                }
+                _ => {}
+            }
+        }
+        account_extension_types
+    }
+}
+
+/// Base state of a mint or account, which may be followed by extensions
+pub trait BaseState: Pack + IsInitialized {
+    /// Associated account type, for use in the `AccountType` byte
+    const ACCOUNT_TYPE: AccountType;
+}
+impl BaseState for Account {
+    const ACCOUNT_TYPE: AccountType = AccountType::Account;
+}
+impl BaseState for Mint {
+    const ACCOUNT_TYPE: AccountType = AccountType::Mint;
+}
+
+/// Trait to be implemented by all extension states, specifying which extension
+/// and account type they are associated with
+pub trait Extension: Pack {
+    /// Associated extension type enum, checked at the start of TLV entries
+    const TYPE: ExtensionType;
+}
+
+/// Read-only view of a base state and its extensions
+#[derive(Debug, PartialEq)]
+pub struct StateWithExtensions<'data, S: BaseState> {
+    /// Unpacked base data
+    pub base: S,
+    tlv_data: &'data [u8],
+}
+impl<'data, S: BaseState> StateWithExtensions<'data, S> {
+    /// Unpack an initialized base state, followed by any extensions.  Accepts
+    /// legacy accounts of exactly `S::LEN` bytes.
+    pub fn unpack(input: &'data [u8]) -> Result<Self, ProgramError> {
+        check_account_len::<S>(input.len())?;
+        let (base_data, rest) = input.split_at(S::LEN);
+        let base = S::unpack(base_data)?;
+        let tlv_data = if rest.is_empty() {
+            rest
+        } else {
+            let (account_type, tlv_data) = split_account_type::<S>(rest)?;
+            if account_type != S::ACCOUNT_TYPE {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            tlv_data
+        };
+        Ok(Self { base, tlv_data })
+    }
+
+    /// Unpack a copy of the given extension
+    pub fn get_extension<V: Extension>(&self) -> Result<V, ProgramError> {
+        get_extension::<S, V>(self.tlv_data)
+    }
+
+    /// List the types of all extensions present on the account
+    pub fn get_extension_types(&self) -> Result<Vec<ExtensionType>, ProgramError> {
+        get_extension_types(self.tlv_data)
+    }
+}
+
+/// Mutable view of a base state and its extensions
+#[derive(Debug, PartialEq)]
+pub struct StateWithExtensionsMut<'data, S: BaseState> {
+    /// Unpacked base data
+    pub base: S,
+    base_data: &'data mut [u8],
+    account_type: &'data mut [u8],
+    tlv_data: &'data mut [u8],
+}
+impl<'data, S: BaseState> StateWithExtensionsMut<'data, S> {
+    /// Unpack an initialized base state, followed by any extensions
+    pub fn unpack(input: &'data mut [u8]) -> Result<Self, ProgramError> {
+        let state = Self::unpack_unchecked(input)?;
+        if !state.base.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        if !state.account_type.is_empty() && state.account_type[0] != S::ACCOUNT_TYPE as u8 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(state)
+    }
+
+    /// Unpack a base state and its extensions without checking that the base
+    /// is initialized, for use while initializing the account
+    pub fn unpack_unchecked(input: &'data mut [u8]) -> Result<Self, ProgramError> {
+        check_account_len::<S>(input.len())?;
+        let (base_data, rest) = input.split_at_mut(S::LEN);
+        let base = S::unpack_unchecked(base_data)?;
+        let (account_type, tlv_data) = if rest.is_empty() {
+            rest.split_at_mut(0)
+        } else {
+            let (account_type, _) = split_account_type::<S>(rest)?;
+            if account_type != AccountType::Uninitialized && account_type != S::ACCOUNT_TYPE {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let account_type_index = ACCOUNT_TYPE_INDEX - S::LEN;
+            let (_padding, rest) = rest.split_at_mut(account_type_index);
+            rest.split_at_mut(1)
+        };
+        Ok(Self {
+            base,
+            base_data,
+            account_type,
+            tlv_data,
+        })
+    }
+
+    /// Pack the base state into the start of the account
+    pub fn pack_base(&mut self) {
+        S::pack_into_slice(&self.base, self.base_data);
+    }
+
+    /// Write the account type byte, if the account has room for extensions
+    pub fn init_account_type(&mut self) {
+        if !self.account_type.is_empty() {
+            self.account_type[0] = S::ACCOUNT_TYPE as u8;
+        }
+    }
+
+    /// Unpack a copy of the given extension
+    pub fn get_extension<V: Extension>(&self) -> Result<V, ProgramError> {
+        get_extension::<S, V>(self.tlv_data)
+    }
+
+    /// List the types of all extensions present on the account
+    pub fn get_extension_types(&self) -> Result<Vec<ExtensionType>, ProgramError> {
+        get_extension_types(self.tlv_data)
+    }
+
+    /// Pack an extension that is already present on the account
+    pub fn pack_extension<V: Extension>(&mut self, extension: &V) -> ProgramResult {
+        let (value_start, value_end) = get_extension_indices::<V>(self.tlv_data, false)?;
+        extension.pack_into_slice(&mut self.tlv_data[value_start..value_end]);
+        Ok(())
+    }
+
+    /// Pack an extension into the first free slot of the account, or over its
+    /// current value if already present
+    pub fn init_extension<V: Extension>(&mut self, extension: &V) -> ProgramResult {
+        if V::TYPE.get_account_type() != S::ACCOUNT_TYPE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (value_start, value_end) = get_extension_indices::<V>(self.tlv_data, true)?;
+        let type_start = value_start - TLV_HEADER_LEN;
+        let length_start = type_start + TYPE_LEN;
+        self.tlv_data[type_start..length_start].copy_from_slice(&u16::from(V::TYPE).to_le_bytes());
+        self.tlv_data[length_start..value_start].copy_from_slice(&(V::LEN as u16).to_le_bytes());
+        extension.pack_into_slice(&mut self.tlv_data[value_start..value_end]);
+        Ok(())
+    }
+}
+
+/// Get the account type of an account that may have extensions, based on its
+/// length and, if extended, its account type byte
+pub fn get_account_type(input: &[u8]) -> AccountType {
+    match input.len() {
+        Account::LEN => AccountType::Account,
+        Mint::LEN => AccountType::Mint,
+        Multisig::LEN => AccountType::Uninitialized,
+        len if len > ACCOUNT_TYPE_INDEX => {
+            AccountType::try_from(input[ACCOUNT_TYPE_INDEX]).unwrap_or(AccountType::Uninitialized)
+        }
+        _ => AccountType::Uninitialized,
+    }
+}
+
+/// Get the length of a token account for the given mint, including any
+/// extensions that the mint requires on its accounts
+pub fn get_account_len_for_mint(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+    let account_extension_types =
+        ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?);
+    Ok(ExtensionType::get_account_len::<Account>(
+        &account_extension_types,
+    ))
+}
+
+/// Pack the base state into the start of an account that may have extensions,
+/// leaving the extension data untouched
+pub fn pack_base<S: BaseState>(base: S, dst: &mut [u8]) -> ProgramResult {
+    check_account_len::<S>(dst.len())?;
+    base.pack_into_slice(&mut dst[..S::LEN]);
+    Ok(())
+}
+
+fn check_account_len<S: BaseState>(account_len: usize) -> ProgramResult {
+    if account_len == S::LEN || (account_len > ACCOUNT_TYPE_INDEX && account_len != Multisig::LEN) {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountData)
+    }
+}
+
+fn split_account_type<S: BaseState>(rest: &[u8]) -> Result<(AccountType, &[u8]), ProgramError> {
+    let account_type_index = ACCOUNT_TYPE_INDEX - S::LEN;
+    let (padding, rest) = rest.split_at(account_type_index);
+    if padding.iter().any(|&byte| byte != 0) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (&account_type, tlv_data) = rest.split_first().ok_or(ProgramError::InvalidAccountData)?;
+    let account_type =
+        AccountType::try_from(account_type).map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok((account_type, tlv_data))
+}
+
+fn read_tlv_header(tlv_data: &[u8], type_start: usize) -> Option<(u16, usize)> {
+    let header = tlv_data.get(type_start..type_start + TLV_HEADER_LEN)?;
+    let extension_type = u16::from_le_bytes(header[..TYPE_LEN].try_into().ok()?);
+    let length = u16::from_le_bytes(header[TYPE_LEN..].try_into().ok()?);
+    Some((extension_type, length as usize))
+}
+
+fn get_extension_indices<V: Extension>(
+    tlv_data: &[u8],
+    init: bool,
+) -> Result<(usize, usize), ProgramError> {
+    let mut type_start = 0;
+    while let Some((extension_type, length)) = read_tlv_header(tlv_data, type_start) {
+        let value_start = type_start + TLV_HEADER_LEN;
+        if extension_type == u16::from(ExtensionType::Uninitialized) {
+            if init {
+                let value_end = value_start + V::LEN;
+                if value_end > tlv_data.len() {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+                return Ok((value_start, value_end));
+            }
+            break;
+        }
+        let value_end = value_start + length;
+        if value_end > tlv_data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if extension_type == u16::from(V::TYPE) {
+            if length != V::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            return Ok((value_start, value_end));
+        }
+        type_start = value_end;
+    }
+    Err(ProgramError::InvalidAccountData)
+}
+
+fn get_extension<S: BaseState, V: Extension>(tlv_data: &[u8]) -> Result<V, ProgramError> {
+    if V::TYPE.get_account_type() != S::ACCOUNT_TYPE {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (value_start, value_end) = get_extension_indices::<V>(tlv_data, false)?;
+    V::unpack_from_slice(&tlv_data[value_start..value_end])
+}
+
+fn get_extension_types(tlv_data: &[u8]) -> Result<Vec<ExtensionType>, ProgramError> {
+    let mut extension_types = vec![];
+    let mut type_start = 0;
+    while let Some((extension_type, length)) = read_tlv_header(tlv_data, type_start) {
+        let extension_type = ExtensionType::try_from(extension_type)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if extension_type == ExtensionType::Uninitialized {
+            break;
+        }
+        extension_types.push(extension_type);
+        type_start += TLV_HEADER_LEN + length;
+        if type_start > tlv_data.len() {
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+    Ok(extension_types)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        extension::transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        state::{AccountState, GenericTokenAccount},
+    };
+    use solana_program::{program_option::COption, pubkey::Pubkey};
+
+    fn test_mint() -> Mint {
+        Mint {
+            mint_authority: COption::Some(Pubkey::new(&[1; 32])),
+            supply: 42,
+            decimals: 7,
+            is_initialized: true,
+            freeze_authority: COption::Some(Pubkey::new(&[2; 32])),
+        }
+    }
+
+    fn test_account() -> Account {
+        Account {
+            mint: Pubkey::new(&[1; 32]),
+            owner: Pubkey::new(&[2; 32]),
+            amount: 3,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+    }
+
+    #[test]
+    fn test_get_account_len() {
+        assert_eq!(ExtensionType::get_account_len::<Mint>(&[]), Mint::LEN);
+        assert_eq!(ExtensionType::get_account_len::<Account>(&[]), Account::LEN);
+        assert_eq!(
+            ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]),
+            Account::LEN + 1 + TLV_HEADER_LEN + TransferFeeConfig::LEN
+        );
+        assert_eq!(
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount]),
+            Account::LEN + 1 + TLV_HEADER_LEN + TransferFeeAmount::LEN
+        );
+    }
+
+    #[test]
+    fn test_legacy_accounts() {
+        let mut buffer = vec![0; Mint::LEN];
+        test_mint().pack_into_slice(&mut buffer);
+        assert_eq!(get_account_type(&buffer), AccountType::Mint);
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(state.base, test_mint());
+        assert_eq!(state.get_extension_types().unwrap(), vec![]);
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>(),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        let mut buffer = vec![0; Account::LEN];
+        test_account().pack_into_slice(&mut buffer);
+        assert_eq!(get_account_type(&buffer), AccountType::Account);
+        let mut state = StateWithExtensionsMut::<Account>::unpack(&mut buffer).unwrap();
+        assert_eq!(state.base, test_account());
+        assert_eq!(
+            state.init_extension(&TransferFeeAmount::default()),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // wrong base size
+        assert_eq!(
+            StateWithExtensions::<Account>::unpack(&[0; Mint::LEN]),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            StateWithExtensions::<Mint>::unpack(&[0; Multisig::LEN]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_generic_token_account() {
+        let mut buffer = vec![0; Account::LEN];
+        assert!(!Account::valid_account_data(&buffer));
+        test_account().pack_into_slice(&mut buffer);
+        assert!(Account::valid_account_data(&buffer));
+        assert_eq!(
+            Account::unpack_account_owner(&buffer),
+            Some(test_account().owner)
+        );
+        assert_eq!(
+            Account::unpack_account_mint(&buffer),
+            Some(test_account().mint)
+        );
+
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount]);
+        let mut buffer = vec![0; account_len];
+        let mut state = StateWithExtensionsMut::<Account>::unpack_unchecked(&mut buffer).unwrap();
+        state.base = test_account();
+        state.pack_base();
+        // no account type yet
+        assert!(!Account::valid_account_data(&buffer));
+        let mut state = StateWithExtensionsMut::<Account>::unpack_unchecked(&mut buffer).unwrap();
+        state.init_account_type();
+        assert!(Account::valid_account_data(&buffer));
+        assert_eq!(
+            Account::unpack_account_owner(&buffer),
+            Some(test_account().owner)
+        );
+
+        // mints and multisigs are never token accounts
+        let mut buffer = vec![0; Multisig::LEN];
+        test_account().pack_into_slice(&mut buffer);
+        buffer[ACCOUNT_TYPE_INDEX] = AccountType::Account as u8;
+        assert!(!Account::valid_account_data(&buffer));
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let mut buffer = vec![0; mint_len];
+        test_account().pack_into_slice(&mut buffer);
+        buffer[ACCOUNT_TYPE_INDEX] = AccountType::Mint as u8;
+        assert!(!Account::valid_account_data(&buffer));
+        assert_eq!(Account::unpack_account_owner(&buffer), None);
+    }
+
+    #[test]
+    fn test_get_account_len_for_mint() {
+        let mut buffer = vec![0; Mint::LEN];
+        test_mint().pack_into_slice(&mut buffer);
+        assert_eq!(get_account_len_for_mint(&buffer).unwrap(), Account::LEN);
+
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let mut buffer = vec![0; mint_len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut buffer).unwrap();
+        state.init_extension(&TransferFeeConfig::default()).unwrap();
+        state.base = test_mint();
+        state.pack_base();
+        state.init_account_type();
+        assert_eq!(
+            get_account_len_for_mint(&buffer).unwrap(),
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount])
+        );
+
+        assert_eq!(
+            get_account_len_for_mint(&[0; Account::LEN]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_mint_with_extension() {
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let mut buffer = vec![0; mint_len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut buffer).unwrap();
+        let config = TransferFeeConfig {
+            withdraw_withheld_authority: COption::Some(Pubkey::new(&[3; 32])),
+            transfer_fee_basis_points: 10,
+            maximum_fee: 100,
+        };
+        state.init_extension(&config).unwrap();
+        // account extensions can't go on a mint
+        assert_eq!(
+            state.init_extension(&TransferFeeAmount::default()),
+            Err(ProgramError::InvalidAccountData)
+        );
+        state.base = test_mint();
+        state.pack_base();
+        state.init_account_type();
+
+        assert_eq!(get_account_type(&buffer), AccountType::Mint);
+        assert_eq!(buffer[ACCOUNT_TYPE_INDEX], AccountType::Mint as u8);
+        // padding between the mint and the account type stays zeroed
+        assert!(buffer[Mint::LEN..ACCOUNT_TYPE_INDEX]
+            .iter()
+            .all(|&b| b == 0));
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(state.base, test_mint());
+        assert_eq!(
+            state.get_extension_types().unwrap(),
+            vec![ExtensionType::TransferFeeConfig]
+        );
+        assert_eq!(state.get_extension::<TransferFeeConfig>().unwrap(), config);
+
+        // mints can't be read as accounts
+        assert_eq!(
+            StateWithExtensions::<Account>::unpack(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // update in place
+        let mut state = StateWithExtensionsMut::<Mint>::unpack(&mut buffer).unwrap();
+        let new_config = TransferFeeConfig {
+            withdraw_withheld_authority: COption::None,
+            ..config
+        };
+        state.pack_extension(&new_config).unwrap();
+        let state = StateWithExtensions::<Mint>::unpack(&buffer).unwrap();
+        assert_eq!(
+            state.get_extension::<TransferFeeConfig>().unwrap(),
+            new_config
+        );
+
+        // non-zero padding is rejected
+        buffer[Mint::LEN] = 1;
+        assert_eq!(
+            StateWithExtensions::<Mint>::unpack(&buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_malformed_tlv() {
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount]);
+        let mut buffer = vec![0; account_len];
+        let mut state = StateWithExtensionsMut::<Account>::unpack_unchecked(&mut buffer).unwrap();
+        state.base = test_account();
+        state.pack_base();
+        state.init_account_type();
+        state
+            .init_extension(&TransferFeeAmount { withheld_amount: 1 })
+            .unwrap();
+
+        // length overruns the account
+        let length_start = ACCOUNT_TYPE_INDEX + 1 + TYPE_LEN;
+        let mut bad_buffer = buffer.clone();
+        bad_buffer[length_start..length_start + LENGTH_LEN]
+            .copy_from_slice(&(TransferFeeAmount::LEN as u16 + 1).to_le_bytes());
+        let state = StateWithExtensions::<Account>::unpack(&bad_buffer).unwrap();
+        assert_eq!(
+            state.get_extension::<TransferFeeAmount>(),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            state.get_extension_types(),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // unknown extension type
+        let mut bad_buffer = buffer.clone();
+        bad_buffer[ACCOUNT_TYPE_INDEX + 1] = 0xff;
+        let state = StateWithExtensions::<Account>::unpack(&bad_buffer).unwrap();
+        assert_eq!(
+            state.get_extension_types(),
+            Err(ProgramError::InvalidAccountData)
+        );
+
+        // unknown account type
+        let mut bad_buffer = buffer.clone();
+        bad_buffer[ACCOUNT_TYPE_INDEX] = 0xff;
+        assert_eq!(get_account_type(&bad_buffer), AccountType::Uninitialized);
+        assert_eq!(
+            StateWithExtensions::<Account>::unpack(&bad_buffer),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Non-transferable extension
+
+use crate::extension::{Extension, ExtensionType};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+};
+
+/// Indicates that the tokens of a mint may not be transferred or delegated,
+/// only minted, burned and closed
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NonTransferable;
+impl Sealed for NonTransferable {}
+impl Pack for NonTransferable {
+    const LEN: usize = 0;
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(NonTransferable)
+    }
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+}
+impl Extension for NonTransferable {
+    const TYPE: ExtensionType = ExtensionType::NonTransferable;
+}
+
+/// Marks a token account as belonging to a non-transferable mint, so that
+/// transfers can be rejected without the mint account
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NonTransferableAccount;
+impl Sealed for NonTransferableAccount {}
+impl Pack for NonTransferableAccount {
+    const LEN: usize = 0;
+    fn unpack_from_slice(_src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(NonTransferableAccount)
+    }
+    fn pack_into_slice(&self, _dst: &mut [u8]) {}
+}
+impl Extension for NonTransferableAccount {
+    const TYPE: ExtensionType = ExtensionType::NonTransferableAccount;
+}
@@ -0,0 +1,62 @@
+//! Permanent delegate extension
+
+use crate::{
+    extension::{Extension, ExtensionType},
+    state::{pack_coption_key, unpack_coption_key},
+};
+use arrayref::{array_mut_ref, array_ref};
+use solana_program::{
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Permanent delegate extension data for mints.
+///
+/// The permanent delegate may transfer or burn any amount of tokens from any
+/// account of the mint, without approval from the account owner.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PermanentDelegate {
+    /// Optional permanent delegate for all accounts of the mint
+    pub delegate: COption<Pubkey>,
+}
+impl Sealed for PermanentDelegate {}
+impl Pack for PermanentDelegate {
+    const LEN: usize = 36;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 36];
+        Ok(PermanentDelegate {
+            delegate: unpack_coption_key(src)?,
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 36];
+        pack_coption_key(&self.delegate, dst);
+    }
+}
+impl Extension for PermanentDelegate {
+    const TYPE: ExtensionType = ExtensionType::PermanentDelegate;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = PermanentDelegate {
+            delegate: COption::Some(Pubkey::new(&[1; 32])),
+        };
+        let mut packed = vec![0; PermanentDelegate::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        let mut expect = vec![1, 0, 0, 0];
+        expect.extend_from_slice(&[1; 32]);
+        assert_eq!(packed, expect);
+        assert_eq!(
+            PermanentDelegate::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Transfer fee extension
+
+use crate::{
+    extension::{Extension, ExtensionType},
+    state::{pack_coption_key, unpack_coption_key},
+};
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::convert::TryInto;
+
+/// Maximum possible fee in basis points is 100%, aka 10_000 basis points
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+const ONE_IN_BASIS_POINTS: u128 = MAX_FEE_BASIS_POINTS as u128;
+
+/// Transfer fee extension data for mints.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFeeConfig {
+    /// Optional authority that may withdraw withheld fees from token accounts.
+    /// If no authority is present then withheld fees can never be withdrawn.
+    pub withdraw_withheld_authority: COption<Pubkey>,
+    /// Amount of transfer collected as fees, expressed as basis points of the
+    /// transfer amount, ie. increments of 0.01%
+    pub transfer_fee_basis_points: u16,
+    /// Maximum fee assessed on transfers, expressed as an amount of tokens
+    pub maximum_fee: u64,
+}
+impl TransferFeeConfig {
+    /// Calculate the fee that would be withheld on a transfer of `amount`
+    /// tokens, rounding up and capped at `maximum_fee`
+    pub fn calculate_fee(&self, amount: u64) -> Option<u64> {
+        let transfer_fee_basis_points = self.transfer_fee_basis_points as u128;
+        if transfer_fee_basis_points == 0 || amount == 0 {
+            Some(0)
+        } else {
+            let numerator = (amount as u128).checked_mul(transfer_fee_basis_points)?;
+            let raw_fee = numerator
+                .checked_add(ONE_IN_BASIS_POINTS)?
+                .checked_sub(1)?
+                .checked_div(ONE_IN_BASIS_POINTS)?;
+            let fee = raw_fee.min(self.maximum_fee as u128);
+            fee.try_into().ok()
+        }
+    }
+}
+impl Sealed for TransferFeeConfig {}
+impl Pack for TransferFeeConfig {
+    const LEN: usize = 46;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 46];
+        let (withdraw_withheld_authority, transfer_fee_basis_points, maximum_fee) =
+            array_refs![src, 36, 2, 8];
+        Ok(TransferFeeConfig {
+            withdraw_withheld_authority: unpack_coption_key(withdraw_withheld_authority)?,
+            transfer_fee_basis_points: u16::from_le_bytes(*transfer_fee_basis_points),
+            maximum_fee: u64::from_le_bytes(*maximum_fee),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 46];
+        let (withdraw_withheld_authority_dst, transfer_fee_basis_points_dst, maximum_fee_dst) =
+            mut_array_refs![dst, 36, 2, 8];
+        pack_coption_key(
+            &self.withdraw_withheld_authority,
+            withdraw_withheld_authority_dst,
+        );
+        *transfer_fee_basis_points_dst = self.transfer_fee_basis_points.to_le_bytes();
+        *maximum_fee_dst = self.maximum_fee.to_le_bytes();
+    }
+}
+impl Extension for TransferFeeConfig {
+    const TYPE: ExtensionType = ExtensionType::TransferFeeConfig;
+}
+
+/// Transfer fee extension data for accounts.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFeeAmount {
+    /// Amount withheld during transfers, to be harvested by the mint's
+    /// withdraw withheld authority
+    pub withheld_amount: u64,
+}
+impl Sealed for TransferFeeAmount {}
+impl Pack for TransferFeeAmount {
+    const LEN: usize = 8;
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = array_ref![src, 0, 8];
+        Ok(TransferFeeAmount {
+            withheld_amount: u64::from_le_bytes(*src),
+        })
+    }
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, 8];
+        *dst = self.withheld_amount.to_le_bytes();
+    }
+}
+impl Extension for TransferFeeAmount {
+    const TYPE: ExtensionType = ExtensionType::TransferFeeAmount;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_calculate_fee() {
+        let config = TransferFeeConfig {
+            withdraw_withheld_authority: COption::None,
+            transfer_fee_basis_points: 100,
+            maximum_fee: 5_000,
+        };
+        assert_eq!(config.calculate_fee(0), Some(0));
+        // rounds up
+        assert_eq!(config.calculate_fee(1), Some(1));
+        assert_eq!(config.calculate_fee(100), Some(1));
+        assert_eq!(config.calculate_fee(101), Some(2));
+        assert_eq!(config.calculate_fee(10_000), Some(100));
+        // capped at the maximum fee
+        assert_eq!(config.calculate_fee(1_000_000), Some(5_000));
+        assert_eq!(config.calculate_fee(u64::MAX), Some(5_000));
+
+        let config = TransferFeeConfig {
+            transfer_fee_basis_points: MAX_FEE_BASIS_POINTS,
+            maximum_fee: u64::MAX,
+            ..config
+        };
+        assert_eq!(config.calculate_fee(u64::MAX), Some(u64::MAX));
+
+        let config = TransferFeeConfig {
+            transfer_fee_basis_points: 0,
+            ..config
+        };
+        assert_eq!(config.calculate_fee(u64::MAX), Some(0));
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let check = TransferFeeConfig {
+            withdraw_withheld_authority: COption::Some(Pubkey::new(&[1; 32])),
+            transfer_fee_basis_points: 2,
+            maximum_fee: 3,
+        };
+        let mut packed = vec![0; TransferFeeConfig::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        let mut expect = vec![1, 0, 0, 0];
+        expect.extend_from_slice(&[1; 32]);
+        expect.extend_from_slice(&[2, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(packed, expect);
+        assert_eq!(
+            TransferFeeConfig::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+
+        let check = TransferFeeAmount { withheld_amount: 4 };
+        let mut packed = vec![0; TransferFeeAmount::get_packed_len()];
+        check.pack_into_slice(&mut packed);
+        assert_eq!(packed, vec![4, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            TransferFeeAmount::unpack_from_slice(&packed).unwrap(),
+            check
+        );
+    }
+}
@@ -1,6 +1,10 @@
 //! Instruction types
 
-use crate::{check_program_account, error::TokenError};
+use crate::{
+    check_program_account, error::TokenError, extension::interest_bearing_mint::BasisPoints,
+    state::AccountState,
+};
+use num_enum::TryFromPrimitive;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
@@ -102,6 +106,12 @@ pub enum TokenInstruction {
     ///   1. `[writable]` The destination account.
     ///   2. `[]` The source account's multisignature owner/delegate.
     ///   3. ..3+M `[signer]` M signer accounts.
+    ///
+    /// If the destination account requires transfer memos, the instructions
+    /// sysvar must also be provided after the signer accounts.
+    ///
+    /// The mint's permanent delegate cannot sign this instruction, since the
+    /// mint is not provided, and must use `TransferChecked` instead.
     Transfer {
         /// The amount of tokens to transfer.
         amount: u64,
@@ -190,6 +200,9 @@ pub enum TokenInstruction {
     ///   1. `[writable]` The token mint.
     ///   2. `[]` The account's multisignature owner/delegate.
     ///   3. ..3+M `[signer]` M signer accounts.
+    ///
+    /// If the mint has a permanent delegate, it may sign in place of the
+    /// account's owner/delegate.
     Burn {
         /// The amount of tokens to burn.
         amount: u64,
@@ -197,6 +210,9 @@ pub enum TokenInstruction {
     /// Close an account by transferring all its SOL to the destination account.
     /// Non-native accounts may only be closed if its token amount is zero.
     ///
+    /// Mints with a close authority may also be closed once their supply is
+    /// zero, signed by the close authority in place of the owner.
+    ///
     /// Accounts expected by this instruction:
     ///
     ///   * Single owner
@@ -265,6 +281,12 @@ pub enum TokenInstruction {
     ///   2. `[writable]` The destination account.
     ///   3. `[]` The source account's multisignature owner/delegate.
     ///   4. ..4+M `[signer]` M signer accounts.
+    ///
+    /// If the destination account requires transfer memos, the instructions
+    /// sysvar must also be provided after the signer accounts.
+    ///
+    /// If the mint has a permanent delegate, it may sign in place of the
+    /// source account's owner/delegate.
     TransferChecked {
         /// The amount of tokens to transfer.
         amount: u64,
@@ -343,6 +365,9 @@ pub enum TokenInstruction {
     ///   1. `[writable]` The token mint.
     ///   2. `[]` The account's multisignature owner/delegate.
     ///   3. ..3+M `[signer]` M signer accounts.
+    ///
+    /// If the mint has a permanent delegate, it may sign in place of the
+    /// account's owner/delegate.
     BurnChecked {
         /// The amount of tokens to burn.
         amount: u64,
@@ -409,6 +434,228 @@ pub enum TokenInstruction {
         /// The freeze authority/multisignature of the mint.
         freeze_authority: COption<Pubkey>,
     },
+    /// Initialize the transfer fee on a new mint.  Every `TransferChecked`
+    /// between accounts of the mint withholds the fee in the destination
+    /// account, where it may only be withdrawn by the withdraw withheld
+    /// authority.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `TransferFeeConfig` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeTransferFeeConfig {
+        /// Withdraw instructions must be signed by this key
+        withdraw_withheld_authority: COption<Pubkey>,
+        /// Amount of transfer collected as fees, expressed as basis points of
+        /// the transfer amount
+        transfer_fee_basis_points: u16,
+        /// Maximum fee assessed on transfers
+        maximum_fee: u64,
+    },
+    /// Transfer all withheld tokens from the given token accounts to the
+    /// destination account.  Signed by the mint's withdraw withheld authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[]` The token mint.
+    ///   1. `[writable]` The fee receiver account.
+    ///   2. `[signer]` The mint's `withdraw_withheld_authority`.
+    ///   3. ..3+N `[writable]` The source accounts to withdraw from.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[]` The token mint.
+    ///   1. `[writable]` The fee receiver account.
+    ///   2. `[]` The mint's multisig `withdraw_withheld_authority`.
+    ///   3. ..3+M `[signer]` M signer accounts.
+    ///   3+M+1. ..3+M+N `[writable]` The source accounts to withdraw from.
+    WithdrawWithheldTokensFromAccounts {
+        /// Number of token accounts harvested
+        num_token_accounts: u8,
+    },
+    /// Require memos for transfers into this account.  A transfer into the
+    /// account then fails unless the preceding instruction in the transaction
+    /// is an spl-memo instruction, and such transfers cannot be made within a
+    /// `Batch`.  The account must be sized to hold the `MemoTransfer`
+    /// extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    EnableRequiredTransferMemos,
+    /// Stop requiring memos for transfers into this account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[signer]` The account's owner.
+    ///
+    ///   * Multisignature owner
+    ///   0. `[writable]` The account to update.
+    ///   1. `[]` The account's multisignature owner.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    DisableRequiredTransferMemos,
+    /// Make a new mint non-transferable.  Tokens of the mint can then only be
+    /// minted, burned, and closed, and any `Transfer`, `TransferChecked`,
+    /// `Approve` or `ApproveChecked` on its accounts fails.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `NonTransferable` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeNonTransferableMint,
+    /// Initialize the interest-bearing configuration of a new mint.  Tokens
+    /// of the mint accrue interest continuously at `rate`, starting from the
+    /// current `Clock` timestamp.  Raw amounts never change, the interest is
+    /// only reflected in UI amounts, see `AmountToUiAmount`.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `InterestBearingConfig` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeInterestBearingMint {
+        /// The authority that can update the rate
+        rate_authority: COption<Pubkey>,
+        /// The initial annual interest rate, in basis points
+        rate: BasisPoints,
+    },
+    /// Update the interest rate of an interest-bearing mint.  Interest
+    /// accrued at the previous rates is kept, as their time-weighted average
+    /// truncated to whole basis points.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint's rate authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[]` The mint's multisignature rate authority.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    UpdateInterestBearingMintRate {
+        /// The new annual interest rate, in basis points
+        rate: BasisPoints,
+    },
+    /// Convert a raw amount to its UI representation, including any interest
+    /// accrued up to the current `Clock` timestamp.  The result is written to
+    /// the program log.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The mint.
+    AmountToUiAmount {
+        /// The raw amount of tokens to convert
+        amount: u64,
+    },
+    /// Convert a UI amount, including any interest accrued up to the current
+    /// `Clock` timestamp, back to the raw amount.  The result is written to
+    /// the program log.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[]` The mint.
+    UiAmountToAmount {
+        /// The UI amount of tokens to convert
+        ui_amount: f64,
+    },
+    /// Give a new mint a permanent delegate, which may transfer or burn any
+    /// amount of tokens from any account of the mint without the owner's
+    /// approval.  Transfers by the permanent delegate must use
+    /// `TransferChecked`, since the mint must be provided.  The delegate may
+    /// be changed or removed later using `SetAuthority`.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `PermanentDelegate` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializePermanentDelegate {
+        /// The permanent delegate for all accounts of the mint
+        delegate: Pubkey,
+    },
+    /// Set the state that new accounts of a new mint start out in.  With a
+    /// default state of `Frozen`, every new account must be thawed by the
+    /// freeze authority before use, and `InitializeMint` fails unless a freeze
+    /// authority is provided.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `DefaultAccountState` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeDefaultAccountState {
+        /// The default state of new accounts
+        state: AccountState,
+    },
+    /// Update the state that new accounts of the mint start out in.  Existing
+    /// accounts are not affected.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Single authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[signer]` The mint's freeze authority.
+    ///
+    ///   * Multisignature authority
+    ///   0. `[writable]` The mint.
+    ///   1. `[]` The mint's multisignature freeze authority.
+    ///   2. ..2+M `[signer]` M signer accounts.
+    UpdateDefaultAccountState {
+        /// The new default state of new accounts
+        state: AccountState,
+    },
+    /// Give a new mint a close authority, which may close the mint with
+    /// `CloseAccount` once its supply is zero.  The close authority may be
+    /// changed or removed later using `SetAuthority`.
+    ///
+    /// Fails if the mint has already been initialized, so must be called
+    /// before `InitializeMint`.  The mint account must be sized to hold the
+    /// `MintCloseAuthority` extension.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. `[writable]` The mint to initialize.
+    InitializeMintCloseAuthority {
+        /// The authority that can close the mint
+        close_authority: COption<Pubkey>,
+    },
+    /// Process a sequence of instructions within a single instruction, saving
+    /// the program id and account headers that separate instructions repeat.
+    /// Each sub-instruction consumes the given number of accounts, in order,
+    /// from the accounts of the batch, and the batch fails if any of them
+    /// fails.  Batches may not be nested, and may not contain transfers into
+    /// accounts that require transfer memos, since a single memo preceding the
+    /// batch cannot be attributed to any one of its transfers.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   0. ..N The accounts of each sub-instruction, in order.
+    Batch {
+        /// The sub-instructions, each with its number of accounts
+        instructions: Vec<(u8, TokenInstruction)>,
+    },
 }
 impl TokenInstruction {
     /// Unpacks a byte buffer into a [TokenInstruction](enum.TokenInstruction.html).
@@ -529,6 +776,106 @@ impl TokenInstruction {
                     decimals,
                 }
             }
+            21 => {
+                let (withdraw_withheld_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let transfer_fee_basis_points = rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u16::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                let maximum_fee = rest
+                    .get(2..10)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::InitializeTransferFeeConfig {
+                    withdraw_withheld_authority,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                }
+            }
+            22 => {
+                let &num_token_accounts = rest.get(0).ok_or(InvalidInstruction)?;
+                Self::WithdrawWithheldTokensFromAccounts { num_token_accounts }
+            }
+            23 => Self::EnableRequiredTransferMemos,
+            24 => Self::DisableRequiredTransferMemos,
+            25 => Self::InitializeNonTransferableMint,
+            26 => {
+                let (rate_authority, rest) = Self::unpack_pubkey_option(rest)?;
+                let rate = rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(BasisPoints::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::InitializeInterestBearingMint {
+                    rate_authority,
+                    rate,
+                }
+            }
+            27 => {
+                let rate = rest
+                    .get(..2)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(BasisPoints::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::UpdateInterestBearingMintRate { rate }
+            }
+            28 => {
+                let amount = rest
+                    .get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(u64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::AmountToUiAmount { amount }
+            }
+            29 => {
+                let ui_amount = rest
+                    .get(..8)
+                    .and_then(|slice| slice.try_into().ok())
+                    .map(f64::from_le_bytes)
+                    .ok_or(InvalidInstruction)?;
+                Self::UiAmountToAmount { ui_amount }
+            }
+            30 => {
+                let (delegate, _rest) = Self::unpack_pubkey(rest)?;
+                Self::InitializePermanentDelegate { delegate }
+            }
+            31 => {
+                let state = Self::unpack_account_state(rest)?;
+                Self::InitializeDefaultAccountState { state }
+            }
+            32 => {
+                let state = Self::unpack_account_state(rest)?;
+                Self::UpdateDefaultAccountState { state }
+            }
+            33 => {
+                let (close_authority, _rest) = Self::unpack_pubkey_option(rest)?;
+                Self::InitializeMintCloseAuthority { close_authority }
+            }
+            34 => {
+                let mut instructions = vec![];
+                let mut rest = rest;
+                while let Some((&num_accounts, remaining)) = rest.split_first() {
+                    let (&data_len, remaining) =
+                        remaining.split_first().ok_or(InvalidInstruction)?;
+                    let (data, remaining) = if remaining.len() >= data_len as usize {
+                        remaining.split_at(data_len as usize)
+                    } else {
+                        return Err(InvalidInstruction.into());
+                    };
+                    let instruction = Self::unpack(data)?;
+                    if let Self::Batch { .. } = instruction {
+                        return Err(InvalidInstruction.into());
+                    }
+                    instructions.push((num_accounts, instruction));
+                    rest = remaining;
+                }
+                if instructions.is_empty() {
+                    return Err(InvalidInstruction.into());
+                }
+                Self::Batch { instructions }
+            }
             _ => return Err(TokenError::InvalidInstruction.into()),
         })
     }
@@ -625,6 +972,68 @@ impl TokenInstruction {
                 buf.extend_from_slice(mint_authority.as_ref());
                 Self::pack_pubkey_option(freeze_authority, &mut buf);
             }
+            &Self::InitializeTransferFeeConfig {
+                ref withdraw_withheld_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                buf.push(21);
+                Self::pack_pubkey_option(withdraw_withheld_authority, &mut buf);
+                buf.extend_from_slice(&transfer_fee_basis_points.to_le_bytes());
+                buf.extend_from_slice(&maximum_fee.to_le_bytes());
+            }
+            &Self::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+                buf.push(22);
+                buf.push(num_token_accounts);
+            }
+            Self::EnableRequiredTransferMemos => buf.push(23),
+            Self::DisableRequiredTransferMemos => buf.push(24),
+            Self::InitializeNonTransferableMint => buf.push(25),
+            &Self::InitializeInterestBearingMint {
+                ref rate_authority,
+                rate,
+            } => {
+                buf.push(26);
+                Self::pack_pubkey_option(rate_authority, &mut buf);
+                buf.extend_from_slice(&rate.to_le_bytes());
+            }
+            &Self::UpdateInterestBearingMintRate { rate } => {
+                buf.push(27);
+                buf.extend_from_slice(&rate.to_le_bytes());
+            }
+            &Self::AmountToUiAmount { amount } => {
+                buf.push(28);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            &Self::UiAmountToAmount { ui_amount } => {
+                buf.push(29);
+                buf.extend_from_slice(&ui_amount.to_le_bytes());
+            }
+            Self::InitializePermanentDelegate { delegate } => {
+                buf.push(30);
+                buf.extend_from_slice(delegate.as_ref());
+            }
+            &Self::InitializeDefaultAccountState { state } => {
+                buf.push(31);
+                buf.push(state as u8);
+            }
+            &Self::UpdateDefaultAccountState { state } => {
+                buf.push(32);
+                buf.push(state as u8);
+            }
+            Self::InitializeMintCloseAuthority { close_authority } => {
+                buf.push(33);
+                Self::pack_pubkey_option(close_authority, &mut buf);
+            }
+            Self::Batch { instructions } => {
+                buf.push(34);
+                for (num_accounts, instruction) in instructions {
+                    let data = instruction.pack();
+                    buf.push(*num_accounts);
+                    buf.push(data.len() as u8);
+                    buf.extend_from_slice(&data);
+                }
+            }
         };
         buf
     }
@@ -651,6 +1060,13 @@ impl TokenInstruction {
         }
     }
 
+    fn unpack_account_state(input: &[u8]) -> Result<AccountState, ProgramError> {
+        input
+            .first()
+            .and_then(|&state| AccountState::try_from_primitive(state).ok())
+            .ok_or_else(|| TokenError::InvalidInstruction.into())
+    }
+
     fn pack_pubkey_option(value: &COption<Pubkey>, buf: &mut Vec<u8>) {
         match *value {
             COption::Some(ref key) => {
@@ -674,6 +1090,14 @@ pub enum AuthorityType {
     AccountOwner,
     /// Authority to close a token account
     CloseAccount,
+    /// Authority to withdraw withheld transfer fees from token accounts
+    WithheldWithdraw,
+    /// Authority to update the interest rate of an interest-bearing mint
+    InterestRate,
+    /// Authority to transfer or burn tokens from any account of the mint
+    PermanentDelegate,
+    /// Authority to close a mint with zero supply
+    CloseMint,
 }
 
 impl AuthorityType {
@@ -683,6 +1107,10 @@ impl AuthorityType {
             AuthorityType::FreezeAccount => 1,
             AuthorityType::AccountOwner => 2,
             AuthorityType::CloseAccount => 3,
+            AuthorityType::WithheldWithdraw => 4,
+            AuthorityType::InterestRate => 5,
+            AuthorityType::PermanentDelegate => 6,
+            AuthorityType::CloseMint => 7,
         }
     }
 
@@ -692,6 +1120,10 @@ impl AuthorityType {
             1 => Ok(AuthorityType::FreezeAccount),
             2 => Ok(AuthorityType::AccountOwner),
             3 => Ok(AuthorityType::CloseAccount),
+            4 => Ok(AuthorityType::WithheldWithdraw),
+            5 => Ok(AuthorityType::InterestRate),
+            6 => Ok(AuthorityType::PermanentDelegate),
+            7 => Ok(AuthorityType::CloseMint),
             _ => Err(TokenError::InvalidInstruction.into()),
         }
     }
@@ -1298,6 +1730,336 @@ pub fn sync_native(
     })
 }
 
+/// Creates an `InitializeTransferFeeConfig` instruction.
+pub fn initialize_transfer_fee_config(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    withdraw_withheld_authority_pubkey: Option<&Pubkey>,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let withdraw_withheld_authority = withdraw_withheld_authority_pubkey.cloned().into();
+    let data = TokenInstruction::InitializeTransferFeeConfig {
+        withdraw_withheld_authority,
+        transfer_fee_basis_points,
+        maximum_fee,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `WithdrawWithheldTokensFromAccounts` instruction.
+pub fn withdraw_withheld_tokens_from_accounts(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    source_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let num_token_accounts = source_pubkeys
+        .len()
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let data = TokenInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts }.pack();
+
+    let mut accounts = Vec::with_capacity(3 + signer_pubkeys.len() + source_pubkeys.len());
+    accounts.push(AccountMeta::new_readonly(*mint_pubkey, false));
+    accounts.push(AccountMeta::new(*destination_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+    for source_pubkey in source_pubkeys.iter() {
+        accounts.push(AccountMeta::new(**source_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `EnableRequiredTransferMemos` instruction.
+pub fn enable_required_transfer_memos(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::EnableRequiredTransferMemos.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a `DisableRequiredTransferMemos` instruction.
+pub fn disable_required_transfer_memos(
+    token_program_id: &Pubkey,
+    account_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::DisableRequiredTransferMemos.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*account_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *owner_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeNonTransferableMint` instruction.
+pub fn initialize_non_transferable_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeNonTransferableMint.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates an `InitializeInterestBearingMint` instruction.
+pub fn initialize_interest_bearing_mint(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority_pubkey: Option<&Pubkey>,
+    rate: BasisPoints,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let rate_authority = rate_authority_pubkey.cloned().into();
+    let data = TokenInstruction::InitializeInterestBearingMint {
+        rate_authority,
+        rate,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates an `UpdateInterestBearingMintRate` instruction.
+pub fn update_interest_bearing_mint_rate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    rate_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    rate: BasisPoints,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::UpdateInterestBearingMintRate { rate }.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *rate_authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `AmountToUiAmount` instruction.
+pub fn amount_to_ui_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::AmountToUiAmount { amount }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new_readonly(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `UiAmountToAmount` instruction.
+pub fn ui_amount_to_amount(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    ui_amount: f64,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::UiAmountToAmount { ui_amount }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new_readonly(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates an `InitializePermanentDelegate` instruction.
+pub fn initialize_permanent_delegate(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    delegate_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializePermanentDelegate {
+        delegate: *delegate_pubkey,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates an `InitializeDefaultAccountState` instruction.
+pub fn initialize_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    state: AccountState,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::InitializeDefaultAccountState { state }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates an `UpdateDefaultAccountState` instruction.
+pub fn update_default_account_state(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    freeze_authority_pubkey: &Pubkey,
+    signer_pubkeys: &[&Pubkey],
+    state: AccountState,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let data = TokenInstruction::UpdateDefaultAccountState { state }.pack();
+
+    let mut accounts = Vec::with_capacity(2 + signer_pubkeys.len());
+    accounts.push(AccountMeta::new(*mint_pubkey, false));
+    accounts.push(AccountMeta::new_readonly(
+        *freeze_authority_pubkey,
+        signer_pubkeys.is_empty(),
+    ));
+    for signer_pubkey in signer_pubkeys.iter() {
+        accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an `InitializeMintCloseAuthority` instruction.
+pub fn initialize_mint_close_authority(
+    token_program_id: &Pubkey,
+    mint_pubkey: &Pubkey,
+    close_authority_pubkey: Option<&Pubkey>,
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+    let close_authority = close_authority_pubkey.cloned().into();
+    let data = TokenInstruction::InitializeMintCloseAuthority { close_authority }.pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts: vec![AccountMeta::new(*mint_pubkey, false)],
+        data,
+    })
+}
+
+/// Creates a `Batch` instruction, which processes the given token
+/// instructions in order.
+pub fn batch(
+    token_program_id: &Pubkey,
+    instructions: &[Instruction],
+) -> Result<Instruction, ProgramError> {
+    check_program_account(token_program_id)?;
+
+    let mut batched_instructions = Vec::with_capacity(instructions.len());
+    let mut accounts = vec![];
+    for instruction in instructions {
+        if instruction.program_id != *token_program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let num_accounts = instruction
+            .accounts
+            .len()
+            .try_into()
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        let token_instruction = TokenInstruction::unpack(&instruction.data)?;
+        if let TokenInstruction::Batch { .. } = token_instruction {
+            return Err(TokenError::InvalidInstruction.into());
+        }
+        batched_instructions.push((num_accounts, token_instruction));
+        accounts.extend_from_slice(&instruction.accounts);
+    }
+    let data = TokenInstruction::Batch {
+        instructions: batched_instructions,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *token_program_id,
+        accounts,
+        data,
+    })
+}
+
 /// Utility function that checks index is between MIN_SIGNERS and MAX_SIGNERS
 pub fn is_valid_signer_index(index: usize) -> bool {
     (MIN_SIGNERS..=MAX_SIGNERS).contains(&index)
@@ -1518,5 +2280,160 @@ mod test {
         assert_eq!(packed, expect);
         let unpacked = TokenInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeTransferFeeConfig {
+            withdraw_withheld_authority: COption::Some(Pubkey::new(&[4u8; 32])),
+            transfer_fee_basis_points: 5,
+            maximum_fee: 6,
+        };
+        let packed = check.pack();
+        let mut expect = vec![21u8, 1];
+        expect.extend_from_slice(&[4u8; 32]);
+        expect.extend_from_slice(&[5, 0, 6, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::WithdrawWithheldTokensFromAccounts {
+            num_token_accounts: 3,
+        };
+        let packed = check.pack();
+        let expect = vec![22u8, 3];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::EnableRequiredTransferMemos;
+        let packed = check.pack();
+        let expect = vec![23u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::DisableRequiredTransferMemos;
+        let packed = check.pack();
+        let expect = vec![24u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeNonTransferableMint;
+        let packed = check.pack();
+        let expect = vec![25u8];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeInterestBearingMint {
+            rate_authority: COption::Some(Pubkey::new(&[6u8; 32])),
+            rate: -2,
+        };
+        let packed = check.pack();
+        let mut expect = vec![26u8, 1];
+        expect.extend_from_slice(&[6u8; 32]);
+        expect.extend_from_slice(&[254, 255]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::UpdateInterestBearingMintRate { rate: 3 };
+        let packed = check.pack();
+        let expect = vec![27u8, 3, 0];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::AmountToUiAmount { amount: 4 };
+        let packed = check.pack();
+        let expect = vec![28u8, 4, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::UiAmountToAmount { ui_amount: 0.5 };
+        let packed = check.pack();
+        let mut expect = vec![29u8];
+        expect.extend_from_slice(&0.5f64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializePermanentDelegate {
+            delegate: Pubkey::new(&[7u8; 32]),
+        };
+        let packed = check.pack();
+        let mut expect = vec![30u8];
+        expect.extend_from_slice(&[7u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::InitializeDefaultAccountState {
+            state: AccountState::Frozen,
+        };
+        let packed = check.pack();
+        let expect = vec![31u8, 2];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::UpdateDefaultAccountState {
+            state: AccountState::Initialized,
+        };
+        let packed = check.pack();
+        let expect = vec![32u8, 1];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        assert_eq!(
+            TokenInstruction::unpack(&[32u8, 3]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+
+        let check = TokenInstruction::InitializeMintCloseAuthority {
+            close_authority: COption::Some(Pubkey::new(&[8u8; 32])),
+        };
+        let packed = check.pack();
+        let mut expect = vec![33u8, 1];
+        expect.extend_from_slice(&[8u8; 32]);
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = TokenInstruction::Batch {
+            instructions: vec![
+                (3, TokenInstruction::Transfer { amount: 1 }),
+                (
+                    4,
+                    TokenInstruction::TransferChecked {
+                        amount: 2,
+                        decimals: 3,
+                    },
+                ),
+            ],
+        };
+        let packed = check.pack();
+        let expect = vec![
+            34u8, 3, 9, 3, 1, 0, 0, 0, 0, 0, 0, 0, 4, 10, 12, 2, 0, 0, 0, 0, 0, 0, 0, 3,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = TokenInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // empty, truncated and nested batches are rejected
+        assert_eq!(
+            TokenInstruction::unpack(&[34u8]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&expect[..expect.len() - 1]),
+            Err(TokenError::InvalidInstruction.into())
+        );
+        let mut nested = vec![34u8, 3, expect.len() as u8];
+        nested.extend_from_slice(&expect);
+        assert_eq!(
+            TokenInstruction::unpack(&nested),
+            Err(TokenError::InvalidInstruction.into())
+        );
     }
 }
@@ -2,6 +2,18 @@
 
 use crate::{
     error::TokenError,
+    extension::{
+        default_account_state::DefaultAccountState,
+        get_account_type,
+        interest_bearing_mint::{BasisPoints, InterestBearingConfig},
+        memo_transfer::{check_previous_instruction_is_memo, MemoTransfer},
+        mint_close_authority::MintCloseAuthority,
+        non_transferable::{NonTransferable, NonTransferableAccount},
+        pack_base,
+        permanent_delegate::PermanentDelegate,
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig, MAX_FEE_BASIS_POINTS},
+        AccountType, ExtensionType, StateWithExtensions, StateWithExtensionsMut,
+    },
     instruction::{is_valid_signer_index, AuthorityType, TokenInstruction, MAX_SIGNERS},
     state::{Account, AccountState, Mint, Multisig},
 };
@@ -15,7 +27,7 @@ use solana_program::{
     program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{clock::Clock, instructions, rent::Rent, Sysvar},
 };
 
 /// Program state handler.
@@ -37,8 +49,9 @@ impl Processor {
             Rent::get()?
         };
 
-        let mut mint = Mint::unpack_unchecked(&mint_info.data.borrow())?;
-        if mint.is_initialized {
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
             return Err(TokenError::AlreadyInUse.into());
         }
 
@@ -46,12 +59,18 @@ impl Processor {
             return Err(TokenError::NotRentExempt.into());
         }
 
-        mint.mint_authority = COption::Some(mint_authority);
-        mint.decimals = decimals;
-        mint.is_initialized = true;
-        mint.freeze_authority = freeze_authority;
+        if let Ok(default_account_state) = mint.get_extension::<DefaultAccountState>() {
+            if default_account_state.state == AccountState::Frozen && freeze_authority.is_none() {
+                return Err(TokenError::MintCannotFreeze.into());
+            }
+        }
 
-        Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
+        mint.base.mint_authority = COption::Some(mint_authority);
+        mint.base.decimals = decimals;
+        mint.base.is_initialized = true;
+        mint.base.freeze_authority = freeze_authority;
+        mint.pack_base();
+        mint.init_account_type();
 
         Ok(())
     }
@@ -96,8 +115,9 @@ impl Processor {
             Rent::get()?
         };
 
-        let mut account = Account::unpack_unchecked(&new_account_info.data.borrow())?;
-        if account.is_initialized() {
+        let mut account_data = new_account_info.data.borrow_mut();
+        let mut account = StateWithExtensionsMut::<Account>::unpack_unchecked(&mut account_data)?;
+        if account.base.is_initialized() {
             return Err(TokenError::AlreadyInUse.into());
         }
 
@@ -105,29 +125,52 @@ impl Processor {
             return Err(TokenError::NotRentExempt.into());
         }
 
-        if *mint_info.key != crate::native_mint::id() {
-            let _ = Mint::unpack(&mint_info.data.borrow_mut())
+        let (required_extensions, starting_state) = if *mint_info.key != crate::native_mint::id() {
+            let mint_data = mint_info.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)
                 .map_err(|_| Into::<ProgramError>::into(TokenError::InvalidMint))?;
-        }
+            let starting_state = mint
+                .get_extension::<DefaultAccountState>()
+                .map(|default_account_state| default_account_state.state)
+                .unwrap_or(AccountState::Initialized);
+            (
+                ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?),
+                starting_state,
+            )
+        } else {
+            (vec![], AccountState::Initialized)
+        };
 
-        account.mint = *mint_info.key;
-        account.owner = *owner;
-        account.delegate = COption::None;
-        account.delegated_amount = 0;
-        account.state = AccountState::Initialized;
+        account.base.mint = *mint_info.key;
+        account.base.owner = *owner;
+        account.base.delegate = COption::None;
+        account.base.delegated_amount = 0;
+        account.base.state = starting_state;
         if *mint_info.key == crate::native_mint::id() {
             let rent_exempt_reserve = rent.minimum_balance(new_account_info_data_len);
-            account.is_native = COption::Some(rent_exempt_reserve);
-            account.amount = new_account_info
+            account.base.is_native = COption::Some(rent_exempt_reserve);
+            account.base.amount = new_account_info
                 .lamports()
                 .checked_sub(rent_exempt_reserve)
                 .ok_or(TokenError::Overflow)?;
         } else {
-            account.is_native = COption::None;
-            account.amount = 0;
+            account.base.is_native = COption::None;
+            account.base.amount = 0;
         };
 
-        Account::pack(account, &mut new_account_info.data.borrow_mut())?;
+        account.pack_base();
+        account.init_account_type();
+        for extension_type in required_extensions {
+            match extension_type {
+                ExtensionType::TransferFeeAmount => {
+                    account.init_extension(&TransferFeeAmount::default())?
+                }
+                ExtensionType::NonTransferableAccount => {
+                    account.init_extension(&NonTransferableAccount)?
+                }
+                _ => return Err(ProgramError::InvalidAccountData),
+            }
+        }
 
         Ok(())
     }
@@ -219,8 +262,28 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
-        let mut dest_account = Account::unpack(&dest_account_info.data.borrow())?;
+        let (mut source_account, source_has_transfer_fee) = {
+            let source_account_data = source_account_info.data.borrow();
+            let source_account = StateWithExtensions::<Account>::unpack(&source_account_data)?;
+            if source_account
+                .get_extension::<NonTransferableAccount>()
+                .is_ok()
+            {
+                return Err(TokenError::NonTransferable.into());
+            }
+            let source_has_transfer_fee =
+                source_account.get_extension::<TransferFeeAmount>().is_ok();
+            (source_account.base, source_has_transfer_fee)
+        };
+        let (dest_account, dest_requires_memo) = {
+            let dest_account_data = dest_account_info.data.borrow();
+            let dest_account = StateWithExtensions::<Account>::unpack(&dest_account_data)?;
+            let dest_requires_memo = dest_account
+                .get_extension::<MemoTransfer>()
+                .map(|memo_transfer| memo_transfer.require_incoming_transfer_memos)
+                .unwrap_or(false);
+            (dest_account.base, dest_requires_memo)
+        };
 
         if source_account.is_frozen() || dest_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
@@ -232,20 +295,53 @@ impl Processor {
             return Err(TokenError::MintMismatch.into());
         }
 
-        if let Some((mint_info, expected_decimals)) = expected_mint_info {
+        let (fee, permanent_delegate) = if let Some((mint_info, expected_decimals)) =
+            expected_mint_info
+        {
             if source_account.mint != *mint_info.key {
                 return Err(TokenError::MintMismatch.into());
             }
 
-            let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
-            if expected_decimals != mint.decimals {
+            let mint_data = mint_info.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            if expected_decimals != mint.base.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
             }
+
+            let fee = if let Ok(transfer_fee_config) = mint.get_extension::<TransferFeeConfig>() {
+                transfer_fee_config
+                    .calculate_fee(amount)
+                    .ok_or(TokenError::Overflow)?
+            } else {
+                0
+            };
+            (fee, Self::get_permanent_delegate(&mint))
+        } else if source_has_transfer_fee {
+            return Err(TokenError::MintRequiredForTransfer.into());
+        } else {
+            (0, COption::None)
+        };
+
+        if dest_requires_memo {
+            check_previous_instruction_is_memo(
+                account_info_iter
+                    .as_slice()
+                    .iter()
+                    .find(|account_info| instructions::check_id(account_info.key)),
+            )?;
         }
 
         let self_transfer = source_account_info.key == dest_account_info.key;
 
         match source_account.delegate {
+            _ if permanent_delegate == COption::Some(*authority_info.key) => {
+                Self::validate_owner(
+                    program_id,
+                    authority_info.key,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
             COption::Some(ref delegate) if authority_info.key == delegate => {
                 Self::validate_owner(
                     program_id,
@@ -266,12 +362,17 @@ impl Processor {
                     }
                 }
             }
-            _ => Self::validate_owner(
-                program_id,
-                &source_account.owner,
-                authority_info,
-                account_info_iter.as_slice(),
-            )?,
+            _ => {
+                if expected_mint_info.is_none() && authority_info.key != &source_account.owner {
+                    msg!("A mint's permanent delegate must transfer with TransferChecked");
+                }
+                Self::validate_owner(
+                    program_id,
+                    &source_account.owner,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?
+            }
         };
 
         // This check MUST occur just before the amounts are manipulated
@@ -284,10 +385,25 @@ impl Processor {
             .amount
             .checked_sub(amount)
             .ok_or(TokenError::Overflow)?;
-        dest_account.amount = dest_account
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+
+        let mut dest_account_data = dest_account_info.data.borrow_mut();
+        let mut dest_account = StateWithExtensionsMut::<Account>::unpack(&mut dest_account_data)?;
+        let credited_amount = amount.checked_sub(fee).ok_or(TokenError::Overflow)?;
+        dest_account.base.amount = dest_account
+            .base
             .amount
-            .checked_add(amount)
+            .checked_add(credited_amount)
             .ok_or(TokenError::Overflow)?;
+        dest_account.pack_base();
+        if fee > 0 {
+            let mut transfer_fee_amount = dest_account.get_extension::<TransferFeeAmount>()?;
+            transfer_fee_amount.withheld_amount = transfer_fee_amount
+                .withheld_amount
+                .checked_add(fee)
+                .ok_or(TokenError::Overflow)?;
+            dest_account.pack_extension(&transfer_fee_amount)?;
+        }
 
         if source_account.is_native() {
             let source_starting_lamports = source_account_info.lamports();
@@ -301,9 +417,6 @@ impl Processor {
                 .ok_or(TokenError::Overflow)?;
         }
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
-        Account::pack(dest_account, &mut dest_account_info.data.borrow_mut())?;
-
         Ok(())
     }
 
@@ -326,7 +439,17 @@ impl Processor {
         let delegate_info = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account = {
+            let source_account_data = source_account_info.data.borrow();
+            let source_account = StateWithExtensions::<Account>::unpack(&source_account_data)?;
+            if source_account
+                .get_extension::<NonTransferableAccount>()
+                .is_ok()
+            {
+                return Err(TokenError::NonTransferable.into());
+            }
+            source_account.base
+        };
 
         if source_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
@@ -337,7 +460,7 @@ impl Processor {
                 return Err(TokenError::MintMismatch.into());
             }
 
-            let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_info.data.borrow())?.base;
             if expected_decimals != mint.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
             }
@@ -353,7 +476,7 @@ impl Processor {
         source_account.delegate = COption::Some(*delegate_info.key);
         source_account.delegated_amount = amount;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -363,7 +486,8 @@ impl Processor {
         let account_info_iter = &mut accounts.iter();
         let source_account_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account =
+            StateWithExtensions::<Account>::unpack(&source_account_info.data.borrow())?.base;
 
         let owner_info = next_account_info(account_info_iter)?;
 
@@ -381,7 +505,7 @@ impl Processor {
         source_account.delegate = COption::None;
         source_account.delegated_amount = 0;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -397,8 +521,10 @@ impl Processor {
         let account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        if account_info.data_len() == Account::get_packed_len() {
-            let mut account = Account::unpack(&account_info.data.borrow())?;
+        let account_type = get_account_type(&account_info.data.borrow());
+        if account_type == AccountType::Account {
+            let mut account =
+                StateWithExtensions::<Account>::unpack(&account_info.data.borrow())?.base;
 
             if account.is_frozen() {
                 return Err(TokenError::AccountFrozen.into());
@@ -440,9 +566,9 @@ impl Processor {
                     return Err(TokenError::AuthorityTypeNotSupported.into());
                 }
             }
-            Account::pack(account, &mut account_info.data.borrow_mut())?;
-        } else if account_info.data_len() == Mint::get_packed_len() {
-            let mut mint = Mint::unpack(&account_info.data.borrow())?;
+            pack_base(account, &mut account_info.data.borrow_mut())?;
+        } else if account_type == AccountType::Mint {
+            let mut mint = StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())?.base;
             match authority_type {
                 AuthorityType::MintTokens => {
                     // Once a mint's supply is fixed, it cannot be undone by setting a new
@@ -472,11 +598,94 @@ impl Processor {
                     )?;
                     mint.freeze_authority = new_authority;
                 }
+                AuthorityType::WithheldWithdraw => {
+                    let mut transfer_fee_config =
+                        StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())?
+                            .get_extension::<TransferFeeConfig>()
+                            .map_err(|_| {
+                                Into::<ProgramError>::into(TokenError::AuthorityTypeNotSupported)
+                            })?;
+                    let withdraw_withheld_authority = transfer_fee_config
+                        .withdraw_withheld_authority
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &withdraw_withheld_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    transfer_fee_config.withdraw_withheld_authority = new_authority;
+                    StateWithExtensionsMut::<Mint>::unpack(&mut account_info.data.borrow_mut())?
+                        .pack_extension(&transfer_fee_config)?;
+                }
+                AuthorityType::InterestRate => {
+                    let mut interest_bearing_config =
+                        StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())?
+                            .get_extension::<InterestBearingConfig>()
+                            .map_err(|_| {
+                                Into::<ProgramError>::into(TokenError::AuthorityTypeNotSupported)
+                            })?;
+                    let rate_authority =
+                        interest_bearing_config
+                            .rate_authority
+                            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &rate_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    interest_bearing_config.rate_authority = new_authority;
+                    StateWithExtensionsMut::<Mint>::unpack(&mut account_info.data.borrow_mut())?
+                        .pack_extension(&interest_bearing_config)?;
+                }
+                AuthorityType::PermanentDelegate => {
+                    let mut permanent_delegate =
+                        StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())?
+                            .get_extension::<PermanentDelegate>()
+                            .map_err(|_| {
+                                Into::<ProgramError>::into(TokenError::AuthorityTypeNotSupported)
+                            })?;
+                    // Once removed, a permanent delegate cannot be re-enabled
+                    let delegate = permanent_delegate
+                        .delegate
+                        .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &delegate,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    permanent_delegate.delegate = new_authority;
+                    StateWithExtensionsMut::<Mint>::unpack(&mut account_info.data.borrow_mut())?
+                        .pack_extension(&permanent_delegate)?;
+                }
+                AuthorityType::CloseMint => {
+                    let mut mint_close_authority =
+                        StateWithExtensions::<Mint>::unpack(&account_info.data.borrow())?
+                            .get_extension::<MintCloseAuthority>()
+                            .map_err(|_| {
+                                Into::<ProgramError>::into(TokenError::AuthorityTypeNotSupported)
+                            })?;
+                    let close_authority =
+                        mint_close_authority
+                            .close_authority
+                            .ok_or(Into::<ProgramError>::into(TokenError::NoAuthorityExists))?;
+                    Self::validate_owner(
+                        program_id,
+                        &close_authority,
+                        authority_info,
+                        account_info_iter.as_slice(),
+                    )?;
+                    mint_close_authority.close_authority = new_authority;
+                    StateWithExtensionsMut::<Mint>::unpack(&mut account_info.data.borrow_mut())?
+                        .pack_extension(&mint_close_authority)?;
+                }
                 _ => {
                     return Err(TokenError::AuthorityTypeNotSupported.into());
                 }
             }
-            Mint::pack(mint, &mut account_info.data.borrow_mut())?;
+            pack_base(mint, &mut account_info.data.borrow_mut())?;
         } else {
             return Err(ProgramError::InvalidArgument);
         }
@@ -496,7 +705,8 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let owner_info = next_account_info(account_info_iter)?;
 
-        let mut dest_account = Account::unpack(&dest_account_info.data.borrow())?;
+        let mut dest_account =
+            StateWithExtensions::<Account>::unpack(&dest_account_info.data.borrow())?.base;
         if dest_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
         }
@@ -508,7 +718,7 @@ impl Processor {
             return Err(TokenError::MintMismatch.into());
         }
 
-        let mut mint = Mint::unpack(&mint_info.data.borrow())?;
+        let mut mint = StateWithExtensions::<Mint>::unpack(&mint_info.data.borrow())?.base;
         if let Some(expected_decimals) = expected_decimals {
             if expected_decimals != mint.decimals {
                 return Err(TokenError::MintDecimalsMismatch.into());
@@ -535,8 +745,8 @@ impl Processor {
             .checked_add(amount)
             .ok_or(TokenError::Overflow)?;
 
-        Account::pack(dest_account, &mut dest_account_info.data.borrow_mut())?;
-        Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
+        pack_base(dest_account, &mut dest_account_info.data.borrow_mut())?;
+        pack_base(mint, &mut mint_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -554,8 +764,13 @@ impl Processor {
         let mint_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
-        let mut mint = Mint::unpack(&mint_info.data.borrow())?;
+        let mut source_account =
+            StateWithExtensions::<Account>::unpack(&source_account_info.data.borrow())?.base;
+        let (mut mint, permanent_delegate) = {
+            let mint_data = mint_info.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            (mint.base, Self::get_permanent_delegate(&mint))
+        };
 
         if source_account.is_frozen() {
             return Err(TokenError::AccountFrozen.into());
@@ -577,6 +792,14 @@ impl Processor {
         }
 
         match source_account.delegate {
+            _ if permanent_delegate == COption::Some(*authority_info.key) => {
+                Self::validate_owner(
+                    program_id,
+                    authority_info.key,
+                    authority_info,
+                    account_info_iter.as_slice(),
+                )?;
+            }
             COption::Some(ref delegate) if authority_info.key == delegate => {
                 Self::validate_owner(
                     program_id,
@@ -613,8 +836,8 @@ impl Processor {
             .checked_sub(amount)
             .ok_or(TokenError::Overflow)?;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
-        Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+        pack_base(mint, &mut mint_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -626,10 +849,31 @@ impl Processor {
         let dest_account_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        if get_account_type(&source_account_info.data.borrow()) == AccountType::Mint {
+            return Self::close_mint(
+                program_id,
+                source_account_info,
+                dest_account_info,
+                authority_info,
+                account_info_iter.as_slice(),
+            );
+        }
+
+        let (mut source_account, withheld_amount) = {
+            let source_account_data = source_account_info.data.borrow();
+            let source_account = StateWithExtensions::<Account>::unpack(&source_account_data)?;
+            let withheld_amount = source_account
+                .get_extension::<TransferFeeAmount>()
+                .map(|transfer_fee_amount| transfer_fee_amount.withheld_amount)
+                .unwrap_or(0);
+            (source_account.base, withheld_amount)
+        };
         if !source_account.is_native() && source_account.amount != 0 {
             return Err(TokenError::NonNativeHasBalance.into());
         }
+        if withheld_amount != 0 {
+            return Err(TokenError::AccountHasWithheldTransferFees.into());
+        }
 
         let authority = source_account
             .close_authority
@@ -649,7 +893,46 @@ impl Processor {
         **source_account_info.lamports.borrow_mut() = 0;
         source_account.amount = 0;
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
+
+        Ok(())
+    }
+
+    fn close_mint(
+        program_id: &Pubkey,
+        mint_info: &AccountInfo,
+        dest_account_info: &AccountInfo,
+        authority_info: &AccountInfo,
+        signers: &[AccountInfo],
+    ) -> ProgramResult {
+        if mint_info.key == dest_account_info.key {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let (supply, close_authority) = {
+            let mint_data = mint_info.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            let mint_close_authority = mint
+                .get_extension::<MintCloseAuthority>()
+                .map_err(|_| Into::<ProgramError>::into(TokenError::AuthorityTypeNotSupported))?;
+            (mint.base.supply, mint_close_authority.close_authority)
+        };
+        if supply != 0 {
+            return Err(TokenError::MintHasSupply.into());
+        }
+        let close_authority = close_authority.ok_or(TokenError::NoAuthorityExists)?;
+        Self::validate_owner(program_id, &close_authority, authority_info, signers)?;
+
+        let dest_starting_lamports = dest_account_info.lamports();
+        **dest_account_info.lamports.borrow_mut() = dest_starting_lamports
+            .checked_add(mint_info.lamports())
+            .ok_or(TokenError::Overflow)?;
+
+        **mint_info.lamports.borrow_mut() = 0;
+        // Wipe the mint so that it cannot be revived within the transaction
+        for byte in mint_info.data.borrow_mut().iter_mut() {
+            *byte = 0;
+        }
 
         Ok(())
     }
@@ -666,7 +949,8 @@ impl Processor {
         let mint_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
 
-        let mut source_account = Account::unpack(&source_account_info.data.borrow())?;
+        let mut source_account =
+            StateWithExtensions::<Account>::unpack(&source_account_info.data.borrow())?.base;
         if freeze && source_account.is_frozen() || !freeze && !source_account.is_frozen() {
             return Err(TokenError::InvalidState.into());
         }
@@ -677,7 +961,7 @@ impl Processor {
             return Err(TokenError::MintMismatch.into());
         }
 
-        let mint = Mint::unpack(&mint_info.data.borrow_mut())?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_info.data.borrow())?.base;
         match mint.freeze_authority {
             COption::Some(authority) => Self::validate_owner(
                 program_id,
@@ -694,7 +978,7 @@ impl Processor {
             AccountState::Initialized
         };
 
-        Account::pack(source_account, &mut source_account_info.data.borrow_mut())?;
+        pack_base(source_account, &mut source_account_info.data.borrow_mut())?;
 
         Ok(())
     }
@@ -707,7 +991,8 @@ impl Processor {
         if native_account_info.owner != program_id {
             return Err(ProgramError::IncorrectProgramId);
         }
-        let mut native_account = Account::unpack(&native_account_info.data.borrow())?;
+        let mut native_account =
+            StateWithExtensions::<Account>::unpack(&native_account_info.data.borrow())?.base;
 
         if let COption::Some(rent_exempt_reserve) = native_account.is_native {
             let new_amount = native_account_info
@@ -722,899 +1007,2989 @@ impl Processor {
             return Err(TokenError::NonNativeNotSupported.into());
         }
 
-        Account::pack(native_account, &mut native_account_info.data.borrow_mut())?;
+        pack_base(native_account, &mut native_account_info.data.borrow_mut())?;
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        let instruction = TokenInstruction::unpack(input)?;
+    /// Processes an [InitializeTransferFeeConfig](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_transfer_fee_config(
+        accounts: &[AccountInfo],
+        withdraw_withheld_authority: COption<Pubkey>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
 
-        match instruction {
-            TokenInstruction::InitializeMint {
-                decimals,
-                mint_authority,
-                freeze_authority,
-            } => {
-                msg!("Instruction: InitializeMint");
-                Self::process_initialize_mint(accounts, decimals, mint_authority, freeze_authority)
-            }
-            TokenInstruction::InitializeMint2 {
-                decimals,
-                mint_authority,
-                freeze_authority,
-            } => {
-                msg!("Instruction: InitializeMint2");
-                Self::process_initialize_mint2(accounts, decimals, mint_authority, freeze_authority)
-            }
-            TokenInstruction::InitializeAccount => {
-                msg!("Instruction: InitializeAccount");
-                Self::process_initialize_account(accounts)
-            }
-            TokenInstruction::InitializeAccount2 { owner } => {
-                msg!("Instruction: InitializeAccount2");
-                Self::process_initialize_account2(accounts, owner)
-            }
-            TokenInstruction::InitializeAccount3 { owner } => {
-                msg!("Instruction: InitializeAccount3");
-                Self::process_initialize_account3(accounts, owner)
-            }
-            TokenInstruction::InitializeMultisig { m } => {
-                msg!("Instruction: InitializeMultisig");
-                Self::process_initialize_multisig(accounts, m)
-            }
-            TokenInstruction::InitializeMultisig2 { m } => {
-                msg!("Instruction: InitializeMultisig2");
-                Self::process_initialize_multisig2(accounts, m)
-            }
-            TokenInstruction::Transfer { amount } => {
-                msg!("Instruction: Transfer");
-                Self::process_transfer(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Approve { amount } => {
-                msg!("Instruction: Approve");
-                Self::process_approve(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Revoke => {
-                msg!("Instruction: Revoke");
-                Self::process_revoke(program_id, accounts)
-            }
-            TokenInstruction::SetAuthority {
-                authority_type,
-                new_authority,
-            } => {
-                msg!("Instruction: SetAuthority");
-                Self::process_set_authority(program_id, accounts, authority_type, new_authority)
-            }
-            TokenInstruction::MintTo { amount } => {
-                msg!("Instruction: MintTo");
-                Self::process_mint_to(program_id, accounts, amount, None)
-            }
-            TokenInstruction::Burn { amount } => {
-                msg!("Instruction: Burn");
-                Self::process_burn(program_id, accounts, amount, None)
-            }
-            TokenInstruction::CloseAccount => {
-                msg!("Instruction: CloseAccount");
-                Self::process_close_account(program_id, accounts)
-            }
-            TokenInstruction::FreezeAccount => {
-                msg!("Instruction: FreezeAccount");
-                Self::process_toggle_freeze_account(program_id, accounts, true)
-            }
-            TokenInstruction::ThawAccount => {
-                msg!("Instruction: ThawAccount");
-                Self::process_toggle_freeze_account(program_id, accounts, false)
-            }
-            TokenInstruction::TransferChecked { amount, decimals } => {
-                msg!("Instruction: TransferChecked");
-                Self::process_transfer(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::ApproveChecked { amount, decimals } => {
-                msg!("Instruction: ApproveChecked");
-                Self::process_approve(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::MintToChecked { amount, decimals } => {
-                msg!("Instruction: MintToChecked");
-                Self::process_mint_to(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::BurnChecked { amount, decimals } => {
-                msg!("Instruction: BurnChecked");
-                Self::process_burn(program_id, accounts, amount, Some(decimals))
-            }
-            TokenInstruction::SyncNative => {
-                msg!("Instruction: SyncNative");
-                Self::process_sync_native(program_id, accounts)
-            }
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
         }
-    }
 
-    /// Validates owner(s) are present
-    pub fn validate_owner(
-        program_id: &Pubkey,
-        expected_owner: &Pubkey,
-        owner_account_info: &AccountInfo,
-        signers: &[AccountInfo],
-    ) -> ProgramResult {
-        if expected_owner != owner_account_info.key {
-            return Err(TokenError::OwnerMismatch.into());
+        if transfer_fee_basis_points > MAX_FEE_BASIS_POINTS {
+            return Err(TokenError::TransferFeeExceedsMaximum.into());
         }
-        if program_id == owner_account_info.owner
-            && owner_account_info.data_len() == Multisig::get_packed_len()
-        {
-            let multisig = Multisig::unpack(&owner_account_info.data.borrow())?;
-            let mut num_signers = 0;
-            let mut matched = [false; MAX_SIGNERS];
-            for signer in signers.iter() {
-                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
-                    if key == signer.key && !matched[position] {
-                        if !signer.is_signer {
-                            return Err(ProgramError::MissingRequiredSignature);
-                        }
-                        matched[position] = true;
-                        num_signers += 1;
-                    }
-                }
-            }
-            if num_signers < multisig.m {
-                return Err(ProgramError::MissingRequiredSignature);
-            }
-            return Ok(());
-        } else if !owner_account_info.is_signer {
-            return Err(ProgramError::MissingRequiredSignature);
+
+        mint.init_account_type();
+        mint.init_extension(&TransferFeeConfig {
+            withdraw_withheld_authority,
+            transfer_fee_basis_points,
+            maximum_fee,
+        })?;
+
+        Ok(())
+    }
+
+    /// Processes an [InitializeNonTransferableMint](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_non_transferable_mint(accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
         }
+
+        mint.init_account_type();
+        mint.init_extension(&NonTransferable)?;
+
         Ok(())
     }
-}
 
-impl PrintProgramError for TokenError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
-    {
-        match self {
-            TokenError::NotRentExempt => msg!("Error: Lamport balance below rent-exempt threshold"),
-            TokenError::InsufficientFunds => msg!("Error: insufficient funds"),
-            TokenError::InvalidMint => msg!("Error: Invalid Mint"),
-            TokenError::MintMismatch => msg!("Error: Account not associated with this Mint"),
-            TokenError::OwnerMismatch => msg!("Error: owner does not match"),
-            TokenError::FixedSupply => msg!("Error: the total supply of this token is fixed"),
-            TokenError::AlreadyInUse => msg!("Error: account or token already in use"),
-            TokenError::InvalidNumberOfProvidedSigners => {
-                msg!("Error: Invalid number of provided signers")
-            }
-            TokenError::InvalidNumberOfRequiredSigners => {
-                msg!("Error: Invalid number of required signers")
-            }
-            TokenError::UninitializedState => msg!("Error: State is uninitialized"),
-            TokenError::NativeNotSupported => {
-                msg!("Error: Instruction does not support native tokens")
-            }
-            TokenError::NonNativeHasBalance => {
-                msg!("Error: Non-native account can only be closed if its balance is zero")
-            }
-            TokenError::InvalidInstruction => msg!("Error: Invalid instruction"),
-            TokenError::InvalidState => msg!("Error: Invalid account state for operation"),
-            TokenError::Overflow => msg!("Error: Operation overflowed"),
-            TokenError::AuthorityTypeNotSupported => {
-                msg!("Error: Account does not support specified authority type")
-            }
-            TokenError::MintCannotFreeze => msg!("Error: This token mint cannot freeze accounts"),
-            TokenError::AccountFrozen => msg!("Error: Account is frozen"),
-            TokenError::MintDecimalsMismatch => {
-                msg!("Error: decimals different from the Mint decimals")
+    /// Processes a [WithdrawWithheldTokensFromAccounts](enum.TokenInstruction.html) instruction.
+    pub fn process_withdraw_withheld_tokens_from_accounts(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        num_token_accounts: u8,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let dest_account_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let remaining_account_infos = account_info_iter.as_slice();
+        let num_signers = remaining_account_infos
+            .len()
+            .checked_sub(num_token_accounts as usize)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let (signer_infos, source_account_infos) = remaining_account_infos.split_at(num_signers);
+
+        let transfer_fee_config = StateWithExtensions::<Mint>::unpack(&mint_info.data.borrow())?
+            .get_extension::<TransferFeeConfig>()?;
+        let withdraw_withheld_authority = transfer_fee_config
+            .withdraw_withheld_authority
+            .ok_or(TokenError::NoAuthorityExists)?;
+        Self::validate_owner(
+            program_id,
+            &withdraw_withheld_authority,
+            authority_info,
+            signer_infos,
+        )?;
+
+        let dest_account =
+            StateWithExtensions::<Account>::unpack(&dest_account_info.data.borrow())?.base;
+        if dest_account.mint != *mint_info.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        if dest_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+
+        let mut withdrawn_amount = 0u64;
+        for source_account_info in source_account_infos {
+            if source_account_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
             }
-            TokenError::NonNativeNotSupported => {
-                msg!("Error: Instruction does not support non-native tokens")
+            let mut source_account_data = source_account_info.data.borrow_mut();
+            let mut source_account =
+                StateWithExtensionsMut::<Account>::unpack(&mut source_account_data)?;
+            if source_account.base.mint != *mint_info.key {
+                return Err(TokenError::MintMismatch.into());
             }
+            let mut transfer_fee_amount = source_account.get_extension::<TransferFeeAmount>()?;
+            withdrawn_amount = withdrawn_amount
+                .checked_add(transfer_fee_amount.withheld_amount)
+                .ok_or(TokenError::Overflow)?;
+            transfer_fee_amount.withheld_amount = 0;
+            source_account.pack_extension(&transfer_fee_amount)?;
         }
+
+        let mut dest_account_data = dest_account_info.data.borrow_mut();
+        let mut dest_account = StateWithExtensionsMut::<Account>::unpack(&mut dest_account_data)?;
+        dest_account.base.amount = dest_account
+            .base
+            .amount
+            .checked_add(withdrawn_amount)
+            .ok_or(TokenError::Overflow)?;
+        dest_account.pack_base();
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::instruction::*;
-    use solana_program::{
-        account_info::IntoAccountInfo, clock::Epoch, instruction::Instruction, program_error,
-        sysvar::rent,
-    };
-    use solana_sdk::account::{
-        create_account_for_test, create_is_signer_account_infos, Account as SolanaAccount,
-    };
+    /// Processes an [EnableRequiredTransferMemos](enum.TokenInstruction.html) or a
+    /// [DisableRequiredTransferMemos](enum.TokenInstruction.html) instruction.
+    pub fn process_toggle_required_transfer_memos(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enable: bool,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_account_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
 
-    struct SyscallStubs {}
-    impl solana_sdk::program_stubs::SyscallStubs for SyscallStubs {
-        fn sol_log(&self, _message: &str) {}
+        let token_account =
+            StateWithExtensions::<Account>::unpack(&token_account_info.data.borrow())?.base;
+        Self::validate_owner(
+            program_id,
+            &token_account.owner,
+            owner_info,
+            account_info_iter.as_slice(),
+        )?;
 
-        fn sol_invoke_signed(
-            &self,
-            _instruction: &Instruction,
-            _account_infos: &[AccountInfo],
-            _signers_seeds: &[&[&[u8]]],
-        ) -> ProgramResult {
-            Err(ProgramError::Custom(42)) // Not supported
-        }
+        let mut token_account_data = token_account_info.data.borrow_mut();
+        let mut token_account = StateWithExtensionsMut::<Account>::unpack(&mut token_account_data)?;
+        token_account.init_extension(&MemoTransfer {
+            require_incoming_transfer_memos: enable,
+        })?;
 
-        fn sol_get_clock_sysvar(&self, _var_addr: *mut u8) -> u64 {
-            program_error::UNSUPPORTED_SYSVAR
-        }
+        Ok(())
+    }
 
-        fn sol_get_epoch_schedule_sysvar(&self, _var_addr: *mut u8) -> u64 {
-            program_error::UNSUPPORTED_SYSVAR
-        }
+    /// Processes an [InitializeInterestBearingMint](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_interest_bearing_mint(
+        accounts: &[AccountInfo],
+        rate_authority: COption<Pubkey>,
+        rate: BasisPoints,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
 
-        #[allow(deprecated)]
-        fn sol_get_fees_sysvar(&self, _var_addr: *mut u8) -> u64 {
-            program_error::UNSUPPORTED_SYSVAR
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
         }
 
-        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
-            unsafe {
-                *(var_addr as *mut _ as *mut Rent) = Rent::default();
-            }
-            solana_program::entrypoint::SUCCESS
-        }
+        let unix_timestamp = Clock::get()?.unix_timestamp;
+        mint.init_account_type();
+        mint.init_extension(&InterestBearingConfig::new(
+            rate_authority,
+            rate,
+            unix_timestamp,
+        ))?;
+
+        Ok(())
     }
 
-    fn do_process_instruction(
-        instruction: Instruction,
-        accounts: Vec<&mut SolanaAccount>,
+    /// Processes an [UpdateInterestBearingMintRate](enum.TokenInstruction.html) instruction.
+    pub fn process_update_interest_bearing_mint_rate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        rate: BasisPoints,
     ) -> ProgramResult {
-        {
-            use std::sync::Once;
-            static ONCE: Once = Once::new();
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
 
-            ONCE.call_once(|| {
-                solana_sdk::program_stubs::set_syscall_stubs(Box::new(SyscallStubs {}));
-            });
-        }
+        let mut interest_bearing_config =
+            StateWithExtensions::<Mint>::unpack(&mint_info.data.borrow())?
+                .get_extension::<InterestBearingConfig>()?;
+        let rate_authority = interest_bearing_config
+            .rate_authority
+            .ok_or(TokenError::NoAuthorityExists)?;
+        Self::validate_owner(
+            program_id,
+            &rate_authority,
+            authority_info,
+            account_info_iter.as_slice(),
+        )?;
 
-        let mut meta = instruction
-            .accounts
-            .iter()
-            .zip(accounts)
-            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
-            .collect::<Vec<_>>();
+        let unix_timestamp = Clock::get()?.unix_timestamp;
+        interest_bearing_config
+            .update_rate(rate, unix_timestamp)
+            .ok_or(TokenError::Overflow)?;
+        StateWithExtensionsMut::<Mint>::unpack(&mut mint_info.data.borrow_mut())?
+            .pack_extension(&interest_bearing_config)?;
 
-        let account_infos = create_is_signer_account_infos(&mut meta);
-        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+        Ok(())
     }
 
-    fn do_process_instruction_dups(
-        instruction: Instruction,
-        account_infos: Vec<AccountInfo>,
-    ) -> ProgramResult {
-        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+    /// Processes an [AmountToUiAmount](enum.TokenInstruction.html) instruction.
+    pub fn process_amount_to_ui_amount(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mint_data = mint_info.data.borrow();
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let ui_amount =
+            if let Ok(interest_bearing_config) = mint.get_extension::<InterestBearingConfig>() {
+                let unix_timestamp = Clock::get()?.unix_timestamp;
+                interest_bearing_config
+                    .amount_to_ui_amount(amount, mint.base.decimals, unix_timestamp)
+                    .ok_or(TokenError::Overflow)?
+            } else {
+                crate::amount_to_ui_amount(amount, mint.base.decimals)
+            };
+        msg!("UI amount: {}", ui_amount);
+
+        Ok(())
     }
 
-    fn return_token_error_as_program_error() -> ProgramError {
-        TokenError::MintMismatch.into()
+    /// Processes a [UiAmountToAmount](enum.TokenInstruction.html) instruction.
+    pub fn process_ui_amount_to_amount(accounts: &[AccountInfo], ui_amount: f64) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mint_data = mint_info.data.borrow();
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let amount =
+            if let Ok(interest_bearing_config) = mint.get_extension::<InterestBearingConfig>() {
+                let unix_timestamp = Clock::get()?.unix_timestamp;
+                interest_bearing_config
+                    .ui_amount_to_amount(ui_amount, mint.base.decimals, unix_timestamp)
+                    .ok_or(TokenError::Overflow)?
+            } else {
+                crate::ui_amount_to_amount(ui_amount, mint.base.decimals)
+            };
+        msg!("Amount: {}", amount);
+
+        Ok(())
     }
 
-    fn rent_sysvar() -> SolanaAccount {
-        create_account_for_test(&Rent::default())
+    /// Processes an [InitializePermanentDelegate](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_permanent_delegate(
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        mint.init_account_type();
+        mint.init_extension(&PermanentDelegate {
+            delegate: COption::Some(delegate),
+        })?;
+
+        Ok(())
     }
 
-    fn mint_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(Mint::get_packed_len())
+    /// Processes an [InitializeDefaultAccountState](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_default_account_state(
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        if state == AccountState::Uninitialized {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        mint.init_account_type();
+        mint.init_extension(&DefaultAccountState { state })?;
+
+        Ok(())
     }
 
-    fn account_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(Account::get_packed_len())
+    /// Processes an [UpdateDefaultAccountState](enum.TokenInstruction.html) instruction.
+    pub fn process_update_default_account_state(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        state: AccountState,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+        let freeze_authority_info = next_account_info(account_info_iter)?;
+
+        if state == AccountState::Uninitialized {
+            return Err(TokenError::InvalidState.into());
+        }
+
+        let (freeze_authority, mut default_account_state) = {
+            let mint_data = mint_info.data.borrow();
+            let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+            (
+                mint.base.freeze_authority,
+                mint.get_extension::<DefaultAccountState>()?,
+            )
+        };
+        let freeze_authority = freeze_authority.ok_or(TokenError::MintCannotFreeze)?;
+        Self::validate_owner(
+            program_id,
+            &freeze_authority,
+            freeze_authority_info,
+            account_info_iter.as_slice(),
+        )?;
+
+        default_account_state.state = state;
+        StateWithExtensionsMut::<Mint>::unpack(&mut mint_info.data.borrow_mut())?
+            .pack_extension(&default_account_state)?;
+
+        Ok(())
     }
 
-    fn multisig_minimum_balance() -> u64 {
-        Rent::default().minimum_balance(Multisig::get_packed_len())
+    /// Processes an [InitializeMintCloseAuthority](enum.TokenInstruction.html) instruction.
+    pub fn process_initialize_mint_close_authority(
+        accounts: &[AccountInfo],
+        close_authority: COption<Pubkey>,
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let mint_info = next_account_info(account_info_iter)?;
+
+        let mut mint_data = mint_info.data.borrow_mut();
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_unchecked(&mut mint_data)?;
+        if mint.base.is_initialized {
+            return Err(TokenError::AlreadyInUse.into());
+        }
+
+        mint.init_account_type();
+        mint.init_extension(&MintCloseAuthority { close_authority })?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_print_error() {
-        let error = return_token_error_as_program_error();
-        error.print::<TokenError>();
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        let instruction = TokenInstruction::unpack(input)?;
+        Self::process_instruction(program_id, accounts, instruction)
     }
 
-    #[test]
-    #[should_panic(expected = "Custom(3)")]
-    fn test_error_unwrap() {
-        Err::<(), ProgramError>(return_token_error_as_program_error()).unwrap();
+    /// Processes a [Batch](enum.TokenInstruction.html) instruction.
+    pub fn process_batch(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instructions: Vec<(u8, TokenInstruction)>,
+    ) -> ProgramResult {
+        let mut remaining_accounts = accounts;
+        for (num_accounts, instruction) in instructions {
+            if remaining_accounts.len() < num_accounts as usize {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+            let (instruction_accounts, rest) = remaining_accounts.split_at(num_accounts as usize);
+            let dest_account_info = match instruction {
+                TokenInstruction::Transfer { .. } => instruction_accounts.get(1),
+                TokenInstruction::TransferChecked { .. } => instruction_accounts.get(2),
+                _ => None,
+            };
+            if let Some(dest_account_info) = dest_account_info {
+                if Self::requires_transfer_memo(dest_account_info) {
+                    msg!("Transfers requiring memos cannot be batched");
+                    return Err(TokenError::NoMemo.into());
+                }
+            }
+            Self::process_instruction(program_id, instruction_accounts, instruction)?;
+            remaining_accounts = rest;
+        }
+        Ok(())
     }
 
-    #[test]
-    fn test_unique_account_sizes() {
-        assert_ne!(Mint::get_packed_len(), 0);
-        assert_ne!(Mint::get_packed_len(), Account::get_packed_len());
-        assert_ne!(Mint::get_packed_len(), Multisig::get_packed_len());
-        assert_ne!(Account::get_packed_len(), 0);
-        assert_ne!(Account::get_packed_len(), Multisig::get_packed_len());
-        assert_ne!(Multisig::get_packed_len(), 0);
+    fn requires_transfer_memo(account_info: &AccountInfo) -> bool {
+        let account_data = account_info.data.borrow();
+        StateWithExtensions::<Account>::unpack(&account_data)
+            .and_then(|account| account.get_extension::<MemoTransfer>())
+            .map(|memo_transfer| memo_transfer.require_incoming_transfer_memos)
+            .unwrap_or(false)
     }
 
-    #[test]
-    fn test_pack_unpack() {
-        // Mint
-        let check = Mint {
-            mint_authority: COption::Some(Pubkey::new(&[1; 32])),
-            supply: 42,
-            decimals: 7,
-            is_initialized: true,
-            freeze_authority: COption::Some(Pubkey::new(&[2; 32])),
+    fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction: TokenInstruction,
+    ) -> ProgramResult {
+        match instruction {
+            TokenInstruction::InitializeMint {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                msg!("Instruction: InitializeMint");
+                Self::process_initialize_mint(accounts, decimals, mint_authority, freeze_authority)
+            }
+            TokenInstruction::InitializeMint2 {
+                decimals,
+                mint_authority,
+                freeze_authority,
+            } => {
+                msg!("Instruction: InitializeMint2");
+                Self::process_initialize_mint2(accounts, decimals, mint_authority, freeze_authority)
+            }
+            TokenInstruction::InitializeAccount => {
+                msg!("Instruction: InitializeAccount");
+                Self::process_initialize_account(accounts)
+            }
+            TokenInstruction::InitializeAccount2 { owner } => {
+                msg!("Instruction: InitializeAccount2");
+                Self::process_initialize_account2(accounts, owner)
+            }
+            TokenInstruction::InitializeAccount3 { owner } => {
+                msg!("Instruction: InitializeAccount3");
+                Self::process_initialize_account3(accounts, owner)
+            }
+            TokenInstruction::InitializeMultisig { m } => {
+                msg!("Instruction: InitializeMultisig");
+                Self::process_initialize_multisig(accounts, m)
+            }
+            TokenInstruction::InitializeMultisig2 { m } => {
+                msg!("Instruction: InitializeMultisig2");
+                Self::process_initialize_multisig2(accounts, m)
+            }
+            TokenInstruction::Transfer { amount } => {
+                msg!("Instruction: Transfer");
+                Self::process_transfer(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Approve { amount } => {
+                msg!("Instruction: Approve");
+                Self::process_approve(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Revoke => {
+                msg!("Instruction: Revoke");
+                Self::process_revoke(program_id, accounts)
+            }
+            TokenInstruction::SetAuthority {
+                authority_type,
+                new_authority,
+            } => {
+                msg!("Instruction: SetAuthority");
+                Self::process_set_authority(program_id, accounts, authority_type, new_authority)
+            }
+            TokenInstruction::MintTo { amount } => {
+                msg!("Instruction: MintTo");
+                Self::process_mint_to(program_id, accounts, amount, None)
+            }
+            TokenInstruction::Burn { amount } => {
+                msg!("Instruction: Burn");
+                Self::process_burn(program_id, accounts, amount, None)
+            }
+            TokenInstruction::CloseAccount => {
+                msg!("Instruction: CloseAccount");
+                Self::process_close_account(program_id, accounts)
+            }
+            TokenInstruction::FreezeAccount => {
+                msg!("Instruction: FreezeAccount");
+                Self::process_toggle_freeze_account(program_id, accounts, true)
+            }
+            TokenInstruction::ThawAccount => {
+                msg!("Instruction: ThawAccount");
+                Self::process_toggle_freeze_account(program_id, accounts, false)
+            }
+            TokenInstruction::TransferChecked { amount, decimals } => {
+                msg!("Instruction: TransferChecked");
+                Self::process_transfer(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::ApproveChecked { amount, decimals } => {
+                msg!("Instruction: ApproveChecked");
+                Self::process_approve(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::MintToChecked { amount, decimals } => {
+                msg!("Instruction: MintToChecked");
+                Self::process_mint_to(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::BurnChecked { amount, decimals } => {
+                msg!("Instruction: BurnChecked");
+                Self::process_burn(program_id, accounts, amount, Some(decimals))
+            }
+            TokenInstruction::SyncNative => {
+                msg!("Instruction: SyncNative");
+                Self::process_sync_native(program_id, accounts)
+            }
+            TokenInstruction::InitializeTransferFeeConfig {
+                withdraw_withheld_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => {
+                msg!("Instruction: InitializeTransferFeeConfig");
+                Self::process_initialize_transfer_fee_config(
+                    accounts,
+                    withdraw_withheld_authority,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                )
+            }
+            TokenInstruction::WithdrawWithheldTokensFromAccounts { num_token_accounts } => {
+                msg!("Instruction: WithdrawWithheldTokensFromAccounts");
+                Self::process_withdraw_withheld_tokens_from_accounts(
+                    program_id,
+                    accounts,
+                    num_token_accounts,
+                )
+            }
+            TokenInstruction::EnableRequiredTransferMemos => {
+                msg!("Instruction: EnableRequiredTransferMemos");
+                Self::process_toggle_required_transfer_memos(program_id, accounts, true)
+            }
+            TokenInstruction::DisableRequiredTransferMemos => {
+                msg!("Instruction: DisableRequiredTransferMemos");
+                Self::process_toggle_required_transfer_memos(program_id, accounts, false)
+            }
+            TokenInstruction::InitializeNonTransferableMint => {
+                msg!("Instruction: InitializeNonTransferableMint");
+                Self::process_initialize_non_transferable_mint(accounts)
+            }
+            TokenInstruction::InitializeInterestBearingMint {
+                rate_authority,
+                rate,
+            } => {
+                msg!("Instruction: InitializeInterestBearingMint");
+                Self::process_initialize_interest_bearing_mint(accounts, rate_authority, rate)
+            }
+            TokenInstruction::UpdateInterestBearingMintRate { rate } => {
+                msg!("Instruction: UpdateInterestBearingMintRate");
+                Self::process_update_interest_bearing_mint_rate(program_id, accounts, rate)
+            }
+            TokenInstruction::AmountToUiAmount { amount } => {
+                msg!("Instruction: AmountToUiAmount");
+                Self::process_amount_to_ui_amount(accounts, amount)
+            }
+            TokenInstruction::UiAmountToAmount { ui_amount } => {
+                msg!("Instruction: UiAmountToAmount");
+                Self::process_ui_amount_to_amount(accounts, ui_amount)
+            }
+            TokenInstruction::InitializePermanentDelegate { delegate } => {
+                msg!("Instruction: InitializePermanentDelegate");
+                Self::process_initialize_permanent_delegate(accounts, delegate)
+            }
+            TokenInstruction::InitializeDefaultAccountState { state } => {
+                msg!("Instruction: InitializeDefaultAccountState");
+                Self::process_initialize_default_account_state(accounts, state)
+            }
+            TokenInstruction::UpdateDefaultAccountState { state } => {
+                msg!("Instruction: UpdateDefaultAccountState");
+                Self::process_update_default_account_state(program_id, accounts, state)
+            }
+            TokenInstruction::InitializeMintCloseAuthority { close_authority } => {
+                msg!("Instruction: InitializeMintCloseAuthority");
+                Self::process_initialize_mint_close_authority(accounts, close_authority)
+            }
+            TokenInstruction::Batch { instructions } => {
+                msg!("Instruction: Batch");
+                Self::process_batch(program_id, accounts, instructions)
+            }
+        }
+    }
+
+    /// Validates owner(s) are present
+    pub fn validate_owner(
+        program_id: &Pubkey,
+        expected_owner: &Pubkey,
+        owner_account_info: &AccountInfo,
+        signers: &[AccountInfo],
+    ) -> ProgramResult {
+        if expected_owner != owner_account_info.key {
+            return Err(TokenError::OwnerMismatch.into());
+        }
+        if program_id == owner_account_info.owner
+            && owner_account_info.data_len() == Multisig::get_packed_len()
+        {
+            let multisig = Multisig::unpack(&owner_account_info.data.borrow())?;
+            let mut num_signers = 0;
+            let mut matched = [false; MAX_SIGNERS];
+            for signer in signers.iter() {
+                for (position, key) in multisig.signers[0..multisig.n as usize].iter().enumerate() {
+                    if key == signer.key && !matched[position] {
+                        if !signer.is_signer {
+                            return Err(ProgramError::MissingRequiredSignature);
+                        }
+                        matched[position] = true;
+                        num_signers += 1;
+                    }
+                }
+            }
+            if num_signers < multisig.m {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            return Ok(());
+        } else if !owner_account_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(())
+    }
+
+    fn get_permanent_delegate(mint: &StateWithExtensions<Mint>) -> COption<Pubkey> {
+        mint.get_extension::<PermanentDelegate>()
+            .map(|permanent_delegate| permanent_delegate.delegate)
+            .unwrap_or(COption::None)
+    }
+}
+
+impl PrintProgramError for TokenError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            TokenError::NotRentExempt => msg!("Error: Lamport balance below rent-exempt threshold"),
+            TokenError::InsufficientFunds => msg!("Error: insufficient funds"),
+            TokenError::InvalidMint => msg!("Error: Invalid Mint"),
+            TokenError::MintMismatch => msg!("Error: Account not associated with this Mint"),
+            TokenError::OwnerMismatch => msg!("Error: owner does not match"),
+            TokenError::FixedSupply => msg!("Error: the total supply of this token is fixed"),
+            TokenError::AlreadyInUse => msg!("Error: account or token already in use"),
+            TokenError::InvalidNumberOfProvidedSigners => {
+                msg!("Error: Invalid number of provided signers")
+            }
+            TokenError::InvalidNumberOfRequiredSigners => {
+                msg!("Error: Invalid number of required signers")
+            }
+            TokenError::UninitializedState => msg!("Error: State is uninitialized"),
+            TokenError::NativeNotSupported => {
+                msg!("Error: Instruction does not support native tokens")
+            }
+            TokenError::NonNativeHasBalance => {
+                msg!("Error: Non-native account can only be closed if its balance is zero")
+            }
+            TokenError::InvalidInstruction => msg!("Error: Invalid instruction"),
+            TokenError::InvalidState => msg!("Error: Invalid account state for operation"),
+            TokenError::Overflow => msg!("Error: Operation overflowed"),
+            TokenError::AuthorityTypeNotSupported => {
+                msg!("Error: Account does not support specified authority type")
+            }
+            TokenError::MintCannotFreeze => msg!("Error: This token mint cannot freeze accounts"),
+            TokenError::AccountFrozen => msg!("Error: Account is frozen"),
+            TokenError::MintDecimalsMismatch => {
+                msg!("Error: decimals different from the Mint decimals")
+            }
+            TokenError::NonNativeNotSupported => {
+                msg!("Error: Instruction does not support non-native tokens")
+            }
+            TokenError::TransferFeeExceedsMaximum => {
+                msg!("Error: Transfer fee exceeds maximum of 10,000 basis points")
+            }
+            TokenError::MintRequiredForTransfer => {
+                msg!("Error: Mint required for this account to transfer tokens, use `TransferChecked`")
+            }
+            TokenError::AccountHasWithheldTransferFees => {
+                msg!("Error: Account has withheld transfer fees, which must be withdrawn before closing")
+            }
+            TokenError::NoAuthorityExists => {
+                msg!("Error: No authority exists to perform the desired operation")
+            }
+            TokenError::NoMemo => {
+                msg!("Error: No memo in previous instruction; required for recipient to receive a transfer")
+            }
+            TokenError::NonTransferable => msg!("Error: Transfer is disabled for this mint"),
+            TokenError::MintHasSupply => {
+                msg!("Error: Mint has a nonzero supply and cannot be closed")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::*;
+    use solana_program::{
+        account_info::IntoAccountInfo,
+        clock::Epoch,
+        instruction::{AccountMeta, Instruction},
+        program_error,
+        sysvar::rent,
+    };
+    use solana_sdk::account::{
+        create_account_for_test, create_is_signer_account_infos, Account as SolanaAccount,
+    };
+
+    struct SyscallStubs {}
+    impl solana_sdk::program_stubs::SyscallStubs for SyscallStubs {
+        fn sol_log(&self, _message: &str) {}
+
+        fn sol_invoke_signed(
+            &self,
+            _instruction: &Instruction,
+            _account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            Err(ProgramError::Custom(42)) // Not supported
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut _ as *mut Clock) = Clock::default();
+            }
+            solana_program::entrypoint::SUCCESS
+        }
+
+        fn sol_get_epoch_schedule_sysvar(&self, _var_addr: *mut u8) -> u64 {
+            program_error::UNSUPPORTED_SYSVAR
+        }
+
+        #[allow(deprecated)]
+        fn sol_get_fees_sysvar(&self, _var_addr: *mut u8) -> u64 {
+            program_error::UNSUPPORTED_SYSVAR
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut _ as *mut Rent) = Rent::default();
+            }
+            solana_program::entrypoint::SUCCESS
+        }
+    }
+
+    fn do_process_instruction(
+        instruction: Instruction,
+        accounts: Vec<&mut SolanaAccount>,
+    ) -> ProgramResult {
+        {
+            use std::sync::Once;
+            static ONCE: Once = Once::new();
+
+            ONCE.call_once(|| {
+                solana_sdk::program_stubs::set_syscall_stubs(Box::new(SyscallStubs {}));
+            });
+        }
+
+        let mut meta = instruction
+            .accounts
+            .iter()
+            .zip(accounts)
+            .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
+            .collect::<Vec<_>>();
+
+        let account_infos = create_is_signer_account_infos(&mut meta);
+        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+    }
+
+    fn do_process_instruction_dups(
+        instruction: Instruction,
+        account_infos: Vec<AccountInfo>,
+    ) -> ProgramResult {
+        Processor::process(&instruction.program_id, &account_infos, &instruction.data)
+    }
+
+    fn return_token_error_as_program_error() -> ProgramError {
+        TokenError::MintMismatch.into()
+    }
+
+    fn rent_sysvar() -> SolanaAccount {
+        create_account_for_test(&Rent::default())
+    }
+
+    fn mint_minimum_balance() -> u64 {
+        Rent::default().minimum_balance(Mint::get_packed_len())
+    }
+
+    fn account_minimum_balance() -> u64 {
+        Rent::default().minimum_balance(Account::get_packed_len())
+    }
+
+    fn multisig_minimum_balance() -> u64 {
+        Rent::default().minimum_balance(Multisig::get_packed_len())
+    }
+
+    fn instructions_sysvar(instructions: &[Instruction], current_index: u16) -> SolanaAccount {
+        let mut data = (instructions.len() as u16).to_le_bytes().to_vec();
+        let mut serialized_instructions = vec![];
+        for instruction in instructions {
+            let offset = 2 + 2 * instructions.len() + serialized_instructions.len();
+            data.extend_from_slice(&(offset as u16).to_le_bytes());
+            serialized_instructions
+                .extend_from_slice(&(instruction.accounts.len() as u16).to_le_bytes());
+            for account_meta in &instruction.accounts {
+                serialized_instructions
+                    .push(account_meta.is_signer as u8 | (account_meta.is_writable as u8) << 1);
+                serialized_instructions.extend_from_slice(account_meta.pubkey.as_ref());
+            }
+            serialized_instructions.extend_from_slice(instruction.program_id.as_ref());
+            serialized_instructions
+                .extend_from_slice(&(instruction.data.len() as u16).to_le_bytes());
+            serialized_instructions.extend_from_slice(&instruction.data);
+        }
+        data.extend_from_slice(&serialized_instructions);
+        data.extend_from_slice(&current_index.to_le_bytes());
+        let mut account = SolanaAccount::new(0, data.len(), &solana_program::sysvar::id());
+        account.data = data;
+        account
+    }
+
+    #[test]
+    fn test_print_error() {
+        let error = return_token_error_as_program_error();
+        error.print::<TokenError>();
+    }
+
+    #[test]
+    #[should_panic(expected = "Custom(3)")]
+    fn test_error_unwrap() {
+        Err::<(), ProgramError>(return_token_error_as_program_error()).unwrap();
+    }
+
+    #[test]
+    fn test_unique_account_sizes() {
+        assert_ne!(Mint::get_packed_len(), 0);
+        assert_ne!(Mint::get_packed_len(), Account::get_packed_len());
+        assert_ne!(Mint::get_packed_len(), Multisig::get_packed_len());
+        assert_ne!(Account::get_packed_len(), 0);
+        assert_ne!(Account::get_packed_len(), Multisig::get_packed_len());
+        assert_ne!(Multisig::get_packed_len(), 0);
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        // Mint
+        let check = Mint {
+            mint_authority: COption::Some(Pubkey::new(&[1; 32])),
+            supply: 42,
+            decimals: 7,
+            is_initialized: true,
+            freeze_authority: COption::Some(Pubkey::new(&[2; 32])),
         };
         let mut packed = vec![0; Mint::get_packed_len() + 1];
         assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Mint::pack(check, &mut packed)
+            Err(ProgramError::InvalidAccountData),
+            Mint::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Mint::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Mint::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Mint::get_packed_len()];
+        Mint::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 1, 1, 1, 1, 42, 0, 0, 0, 0, 0, 0, 0, 7, 1, 1, 0, 0, 0, 2, 2, 2, 2, 2, 2, 2, 2,
+            2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Mint::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        // Account
+        let check = Account {
+            mint: Pubkey::new(&[1; 32]),
+            owner: Pubkey::new(&[2; 32]),
+            amount: 3,
+            delegate: COption::Some(Pubkey::new(&[4; 32])),
+            state: AccountState::Frozen,
+            is_native: COption::Some(5),
+            delegated_amount: 6,
+            close_authority: COption::Some(Pubkey::new(&[7; 32])),
+        };
+        let mut packed = vec![0; Account::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Account::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Account::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Account::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Account::get_packed_len()];
+        Account::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+            1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+            2, 2, 2, 2, 2, 2, 3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
+            4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 2, 1, 0, 0, 0, 5, 0, 0,
+            0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Account::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+
+        // Multisig
+        let check = Multisig {
+            m: 1,
+            n: 2,
+            is_initialized: true,
+            signers: [Pubkey::new(&[3; 32]); MAX_SIGNERS],
+        };
+        let mut packed = vec![0; Multisig::get_packed_len() + 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Multisig::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Multisig::get_packed_len() - 1];
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            Multisig::pack(check, &mut packed)
+        );
+        let mut packed = vec![0; Multisig::get_packed_len()];
+        Multisig::pack(check, &mut packed).unwrap();
+        let expect = vec![
+            1, 2, 1, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+            3, 3, 3, 3, 3, 3, 3,
+        ];
+        assert_eq!(packed, expect);
+        let unpacked = Multisig::unpack(&packed).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_initialize_mint() {
+        let program_id = crate::id();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(42, Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // mint is not rent exempt
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar]
+            )
+        );
+
+        mint_account.lamports = mint_minimum_balance();
+
+        // create new mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create twice
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2,).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar]
+            )
+        );
+
+        // create another mint that can freeze
+        do_process_instruction(
+            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
+        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
+    }
+
+    #[test]
+    fn test_initialize_mint2() {
+        let program_id = crate::id();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(42, Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+
+        // mint is not rent exempt
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_mint2(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account]
+            )
+        );
+
+        mint_account.lamports = mint_minimum_balance();
+
+        // create new mint
+        do_process_instruction(
+            initialize_mint2(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+
+        // create twice
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_mint2(&program_id, &mint_key, &owner_key, None, 2,).unwrap(),
+                vec![&mut mint_account]
+            )
+        );
+
+        // create another mint that can freeze
+        do_process_instruction(
+            initialize_mint2(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
+            vec![&mut mint2_account],
+        )
+        .unwrap();
+        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
+        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
+    }
+
+    #[test]
+    fn test_initialize_mint_account() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(42, Account::get_packed_len(), &program_id);
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // account is not rent exempt
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+
+        account_account.lamports = account_minimum_balance();
+
+        // mint is not valid (not initialized)
+        assert_eq!(
+            Err(TokenError::InvalidMint.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create twice
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_transfer_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_info: AccountInfo = (&account3_key, false, &mut account3_account).into();
+        let account4_key = Pubkey::new_unique();
+        let mut account4_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account4_info: AccountInfo = (&account4_key, true, &mut account4_account).into();
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_account = SolanaAccount::new(
+            multisig_minimum_balance(),
+            Multisig::get_packed_len(),
+            &program_id,
+        );
+        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                account2_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-owner transfer
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-owner TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate transfer
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.amount = 1000;
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // test destination-owner transfer
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account3_key, &mint_key, &account2_key).unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        account1_info.is_signer = false;
+        account2_info.is_signer = true;
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account3_key,
+                &account2_key,
+                &account2_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                account2_info.clone(),
+                account2_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // destination-owner TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account3_key,
+                &mint_key,
+                &account2_key,
+                &account2_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account2_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // test source-multisig signer
+        do_process_instruction_dups(
+            initialize_multisig(&program_id, &multisig_key, &[&account4_key], 1).unwrap(),
+            vec![
+                multisig_info.clone(),
+                rent_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account4_key, &mint_key, &multisig_key).unwrap(),
+            vec![
+                account4_info.clone(),
+                mint_info.clone(),
+                multisig_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account4_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account4_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+
+        // source-multisig-signer transfer
+        do_process_instruction_dups(
+            transfer(
+                &program_id,
+                &account4_key,
+                &account2_key,
+                &multisig_key,
+                &[&account4_key],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account4_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-multisig-signer TransferChecked
+        do_process_instruction_dups(
+            transfer_checked(
+                &program_id,
+                &account4_key,
+                &mint_key,
+                &account2_key,
+                &multisig_key,
+                &[&account4_key],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account4_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account4_info.clone(),
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_transfer() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // missing signer
+        let mut instruction = transfer(
+            &program_id,
+            &account_key,
+            &account2_key,
+            &owner_key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // mismatch mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &mismatch_key,
+                    &owner_key,
+                    &[],
+                    1000
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mismatch_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner2_key,
+                    &[],
+                    1000
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // transfer
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                1000,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 1).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // transfer half back
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account2_key,
+                &account_key,
+                &owner_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                &mut account2_account,
+                &mut account_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account2_key,
+                    &mint_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    1,
+                    10 // <-- incorrect decimals
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // incorrect mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                transfer_checked(
+                    &program_id,
+                    &account2_key,
+                    &account3_key, // <-- incorrect mint
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    1,
+                    2
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account3_account, // <-- incorrect mint
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+        // transfer rest with explicit decimals
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut account_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(&program_id, &account2_key, &account_key, &owner_key, &[], 1).unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // transfer via delegate
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds approved via delegate
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
+
+        // transfer rest
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                900,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // approve delegate
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+
+        // insufficient funds in source account via delegate
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut delegate_account,
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_transfer() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        let account_info = (&account_key, false, &mut account_account).into_account_info();
+        let account3_info = (&account3_key, false, &mut account3_account).into_account_info();
+        let delegate_info = (&delegate_key, true, &mut delegate_account).into_account_info();
+        let owner_info = (&owner_key, true, &mut owner_account).into_account_info();
+        let owner2_info = (&owner2_key, true, &mut owner2_account).into_account_info();
+        let mint_info = (&mint_key, false, &mut mint_account).into_account_info();
+
+        // transfer
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+
+        // transfer checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+
+        // missing signer
+        let mut owner_no_sign_info = owner_info.clone();
+        let mut instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_no_sign_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        instruction.accounts[2].is_signer = false;
+        owner_no_sign_info.is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_no_sign_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing signer checked
+        let mut instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_no_sign_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        instruction.accounts[3].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_no_sign_info,
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing owner
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner2_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner2_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // missing owner checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner2_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner2_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // insufficient funds
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1001,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // insufficient funds checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1001,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // incorrect decimals
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1,
+            10, // <-- incorrect decimals
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // incorrect mint
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            account3_info.key, // <-- incorrect mint
+            account_info.key,
+            owner_info.key,
+            &[],
+            1,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account3_info.clone(), // <-- incorrect mint
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // approve delegate
+        let instruction = approve(
+            &program_id,
+            account_info.key,
+            delegate_info.key,
+            owner_info.key,
+            &[],
+            100,
+        )
+        .unwrap();
+        Processor::process(
+            &instruction.program_id,
+            &[
+                account_info.clone(),
+                delegate_info.clone(),
+                owner_info.clone(),
+            ],
+            &instruction.data,
+        )
+        .unwrap();
+
+        // delegate transfer
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            100,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+        assert_eq!(account.delegated_amount, 100);
+
+        // delegate transfer checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            100,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+        assert_eq!(account.delegated_amount, 100);
+
+        // delegate insufficient funds
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            101,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
+        );
+
+        // delegate insufficient funds checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            delegate_info.key,
+            &[],
+            101,
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    delegate_info.clone(),
+                ],
+                &instruction.data,
+            )
         );
-        let mut packed = vec![0; Mint::get_packed_len() - 1];
+
+        // owner transfer with delegate assigned
+        let instruction = transfer(
+            &program_id,
+            account_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+        )
+        .unwrap();
         assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Mint::pack(check, &mut packed)
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
         );
-        let mut packed = vec![0; Mint::get_packed_len()];
-        Mint::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-            1, 1, 1, 1, 1, 1, 1, 42, 0, 0, 0, 0, 0, 0, 0, 7, 1, 1, 0, 0, 0, 2, 2, 2, 2, 2, 2, 2, 2,
-            2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Mint::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
 
-        // Account
-        let check = Account {
-            mint: Pubkey::new(&[1; 32]),
-            owner: Pubkey::new(&[2; 32]),
-            amount: 3,
-            delegate: COption::Some(Pubkey::new(&[4; 32])),
-            state: AccountState::Frozen,
-            is_native: COption::Some(5),
-            delegated_amount: 6,
-            close_authority: COption::Some(Pubkey::new(&[7; 32])),
-        };
-        let mut packed = vec![0; Account::get_packed_len() + 1];
+        // owner transfer with delegate assigned checked
+        let instruction = transfer_checked(
+            &program_id,
+            account_info.key,
+            mint_info.key,
+            account_info.key,
+            owner_info.key,
+            &[],
+            1000,
+            2,
+        )
+        .unwrap();
         assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Account::pack(check, &mut packed)
+            Ok(()),
+            Processor::process(
+                &instruction.program_id,
+                &[
+                    account_info.clone(),
+                    mint_info.clone(),
+                    account_info.clone(),
+                    owner_info.clone(),
+                ],
+                &instruction.data,
+            )
         );
-        let mut packed = vec![0; Account::get_packed_len() - 1];
-        assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Account::pack(check, &mut packed)
+        // no balance change...
+        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(account.amount, 1000);
+    }
+
+    #[test]
+    fn test_mintable_token_with_zero_supply() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-        let mut packed = vec![0; Account::get_packed_len()];
-        Account::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
-            1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
-            2, 2, 2, 2, 2, 2, 3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4,
-            4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 2, 1, 0, 0, 0, 5, 0, 0,
-            0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
-            7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Account::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
 
-        // Multisig
-        let check = Multisig {
-            m: 1,
-            n: 2,
-            is_initialized: true,
-            signers: [Pubkey::new(&[3; 32]); MAX_SIGNERS],
-        };
-        let mut packed = vec![0; Multisig::get_packed_len() + 1];
+        // create mint-able token with zero supply
+        let decimals = 2;
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, decimals).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
         assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Multisig::pack(check, &mut packed)
+            mint,
+            Mint {
+                mint_authority: COption::Some(owner_key),
+                supply: 0,
+                decimals,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            }
         );
-        let mut packed = vec![0; Multisig::get_packed_len() - 1];
+
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+
+        // mint to
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to 2, with incorrect decimals
         assert_eq!(
-            Err(ProgramError::InvalidAccountData),
-            Multisig::pack(check, &mut packed)
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                mint_to_checked(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    42,
+                    decimals + 1
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
+            )
         );
-        let mut packed = vec![0; Multisig::get_packed_len()];
-        Multisig::pack(check, &mut packed).unwrap();
-        let expect = vec![
-            1, 2, 1, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
-            3, 3, 3, 3, 3, 3, 3,
-        ];
-        assert_eq!(packed, expect);
-        let unpacked = Multisig::unpack(&packed).unwrap();
-        assert_eq!(unpacked, check);
+
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to 2
+        do_process_instruction(
+            mint_to_checked(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                42,
+                decimals,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        let _ = Mint::unpack(&mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 84);
     }
 
     #[test]
-    fn test_initialize_mint() {
+    fn test_approve_dups() {
         let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_info: AccountInfo = (&account3_key, true, &mut account3_account).into();
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_account = SolanaAccount::new(
+            multisig_minimum_balance(),
+            Multisig::get_packed_len(),
+            &program_id,
+        );
+        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
         let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
         let mint_key = Pubkey::new_unique();
-        let mut mint_account = SolanaAccount::new(42, Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
+        let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // mint is not rent exempt
-        assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
-            do_process_instruction(
-                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-                vec![&mut mint_account, &mut rent_sysvar]
-            )
-        );
-
-        mint_account.lamports = mint_minimum_balance();
-
-        // create new mint
-        do_process_instruction(
+        // create mint
+        do_process_instruction_dups(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
-        // create twice
-        assert_eq!(
-            Err(TokenError::AlreadyInUse.into()),
-            do_process_instruction(
-                initialize_mint(&program_id, &mint_key, &owner_key, None, 2,).unwrap(),
-                vec![&mut mint_account, &mut rent_sysvar]
-            )
-        );
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        // create another mint that can freeze
-        do_process_instruction(
-            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
+        // create another account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                account2_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
+            ],
         )
         .unwrap();
-        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
-        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
-    }
 
-    #[test]
-    fn test_initialize_mint2() {
-        let program_id = crate::id();
-        let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account = SolanaAccount::new(42, Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
 
-        // mint is not rent exempt
-        assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
-            do_process_instruction(
-                initialize_mint2(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-                vec![&mut mint_account]
+        // source-owner approve
+        do_process_instruction_dups(
+            approve(
+                &program_id,
+                &account1_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
             )
-        );
-
-        mint_account.lamports = mint_minimum_balance();
-
-        // create new mint
-        do_process_instruction(
-            initialize_mint2(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account],
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
         )
         .unwrap();
 
-        // create twice
-        assert_eq!(
-            Err(TokenError::AlreadyInUse.into()),
-            do_process_instruction(
-                initialize_mint2(&program_id, &mint_key, &owner_key, None, 2,).unwrap(),
-                vec![&mut mint_account]
+        // source-owner approve_checked
+        do_process_instruction_dups(
+            approve_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account2_key,
+                &account1_key,
+                &[],
+                500,
+                2,
             )
-        );
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        // create another mint that can freeze
-        do_process_instruction(
-            initialize_mint2(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
-            vec![&mut mint2_account],
+        // source-owner revoke
+        do_process_instruction_dups(
+            revoke(&program_id, &account1_key, &account1_key, &[]).unwrap(),
+            vec![account1_info.clone(), account1_info.clone()],
         )
         .unwrap();
-        let mint = Mint::unpack_unchecked(&mint2_account.data).unwrap();
-        assert_eq!(mint.freeze_authority, COption::Some(owner_key));
-    }
 
-    #[test]
-    fn test_initialize_mint_account() {
-        let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(42, Account::get_packed_len(), &program_id);
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut rent_sysvar = rent_sysvar();
+        // test source-multisig signer
+        do_process_instruction_dups(
+            initialize_multisig(&program_id, &multisig_key, &[&account3_key], 1).unwrap(),
+            vec![
+                multisig_info.clone(),
+                rent_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        // account is not rent exempt
-        assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
-            do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
-                ],
-            )
-        );
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account3_key, &mint_key, &multisig_key).unwrap(),
+            vec![
+                account3_info.clone(),
+                mint_info.clone(),
+                multisig_info.clone(),
+                rent_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        account_account.lamports = account_minimum_balance();
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
 
-        // mint is not valid (not initialized)
-        assert_eq!(
-            Err(TokenError::InvalidMint.into()),
-            do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
-                ],
+        // source-multisig-signer approve
+        do_process_instruction_dups(
+            approve(
+                &program_id,
+                &account3_key,
+                &account2_key,
+                &multisig_key,
+                &[&account3_key],
+                500,
             )
-        );
-
-        // create mint
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            .unwrap(),
+            vec![
+                account3_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
+            ],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+        // source-multisig-signer approve_checked
+        do_process_instruction_dups(
+            approve_checked(
+                &program_id,
+                &account3_key,
+                &mint_key,
+                &account2_key,
+                &multisig_key,
+                &[&account3_key],
+                500,
+                2,
+            )
+            .unwrap(),
             vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
+                account3_info.clone(),
+                mint_info.clone(),
+                account2_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
             ],
         )
         .unwrap();
 
-        // create twice
-        assert_eq!(
-            Err(TokenError::AlreadyInUse.into()),
-            do_process_instruction(
-                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut owner_account,
-                    &mut rent_sysvar
-                ],
-            )
-        );
+        // source-owner multisig-signer
+        do_process_instruction_dups(
+            revoke(&program_id, &account3_key, &multisig_key, &[&account3_key]).unwrap(),
+            vec![
+                account3_info.clone(),
+                multisig_info.clone(),
+                account3_info.clone(),
+            ],
+        )
+        .unwrap();
     }
 
     #[test]
-    fn test_transfer_dups() {
+    fn test_approve() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let mut account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let account2_key = Pubkey::new_unique();
         let mut account2_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let mut account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_info: AccountInfo = (&account3_key, false, &mut account3_account).into();
-        let account4_key = Pubkey::new_unique();
-        let mut account4_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account4_info: AccountInfo = (&account4_key, true, &mut account4_account).into();
-        let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = SolanaAccount::new(
-            multisig_minimum_balance(),
-            Multisig::get_packed_len(),
-            &program_id,
-        );
-        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
-        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
         // create mint
-        do_process_instruction_dups(
+        do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
         // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
         // create another account
-        do_process_instruction_dups(
+        do_process_instruction(
             initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account2_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
         // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // source-owner transfer
-        do_process_instruction_dups(
-            transfer(
-                &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-            )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
-            ],
+        // missing signer
+        let mut instruction = approve(
+            &program_id,
+            &account_key,
+            &delegate_key,
+            &owner_key,
+            &[],
+            100,
         )
         .unwrap();
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
 
-        // source-owner TransferChecked
-        do_process_instruction_dups(
-            transfer_checked(
+        // no owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                approve(
+                    &program_id,
+                    &account_key,
+                    &delegate_key,
+                    &owner2_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner2_account,
+                ],
+            )
+        );
+
+        // approve delegate
+        do_process_instruction(
+            approve(
                 &program_id,
-                &account1_key,
-                &mint_key,
-                &account2_key,
-                &account1_key,
+                &account_key,
+                &delegate_key,
+                &owner_key,
                 &[],
-                500,
-                2,
+                100,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
 
-        // source-delegate transfer
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.amount = 1000;
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        // approve delegate 2, with incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    0 // <-- incorrect decimals
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
+            )
+        );
 
-        do_process_instruction_dups(
-            transfer(
-                &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
+        // approve delegate 2, with incorrect mint
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &account2_key, // <-- bad mint
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100,
+                    0
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account, // <-- bad mint
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
             )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
+        );
 
-        // source-delegate TransferChecked
-        do_process_instruction_dups(
-            transfer_checked(
+        // approve delegate 2
+        do_process_instruction(
+            approve_checked(
                 &program_id,
-                &account1_key,
+                &account_key,
                 &mint_key,
-                &account2_key,
-                &account1_key,
+                &delegate_key,
+                &owner_key,
                 &[],
-                500,
+                100,
                 2,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
 
-        // test destination-owner transfer
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account3_key, &mint_key, &account2_key).unwrap(),
-            vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                rent_info.clone(),
-            ],
+        // revoke delegate
+        do_process_instruction(
+            revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut owner_account],
         )
         .unwrap();
+    }
+
+    #[test]
+    fn test_set_authority_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
+
+        // create mint
         do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+            initialize_mint(&program_id, &mint_key, &mint_key, Some(&mint_key), 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
-        account1_info.is_signer = false;
-        account2_info.is_signer = true;
+        // create account
         do_process_instruction_dups(
-            transfer(
-                &program_id,
-                &account3_key,
-                &account2_key,
-                &account2_key,
-                &[],
-                500,
-            )
-            .unwrap(),
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
             vec![
-                account3_info.clone(),
-                account2_info.clone(),
-                account2_info.clone(),
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
             ],
         )
         .unwrap();
 
-        // destination-owner TransferChecked
+        // set mint_authority when currently self
         do_process_instruction_dups(
-            transfer_checked(
+            set_authority(
                 &program_id,
-                &account3_key,
                 &mint_key,
-                &account2_key,
-                &account2_key,
+                Some(&owner_key),
+                AuthorityType::MintTokens,
+                &mint_key,
                 &[],
-                500,
-                2,
             )
             .unwrap(),
-            vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account2_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // test source-multisig signer
-        do_process_instruction_dups(
-            initialize_multisig(&program_id, &multisig_key, &[&account4_key], 1).unwrap(),
-            vec![
-                multisig_info.clone(),
-                rent_info.clone(),
-                account4_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account4_key, &mint_key, &multisig_key).unwrap(),
-            vec![
-                account4_info.clone(),
-                mint_info.clone(),
-                multisig_info.clone(),
-                rent_info.clone(),
-            ],
+            vec![mint_info.clone(), mint_info.clone()],
         )
         .unwrap();
 
+        // set freeze_authority when currently self
         do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account4_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account4_info.clone(), owner_info.clone()],
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&owner_key),
+                AuthorityType::FreezeAccount,
+                &mint_key,
+                &[],
+            )
+            .unwrap(),
+            vec![mint_info.clone(), mint_info.clone()],
         )
         .unwrap();
 
-        // source-multisig-signer transfer
+        // set account owner when currently self
         do_process_instruction_dups(
-            transfer(
+            set_authority(
                 &program_id,
-                &account4_key,
-                &account2_key,
-                &multisig_key,
-                &[&account4_key],
-                500,
+                &account1_key,
+                Some(&owner_key),
+                AuthorityType::AccountOwner,
+                &account1_key,
+                &[],
             )
             .unwrap(),
-            vec![
-                account4_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account4_info.clone(),
-            ],
+            vec![account1_info.clone(), account1_info.clone()],
         )
         .unwrap();
 
-        // source-multisig-signer TransferChecked
+        // set close_authority when currently self
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.close_authority = COption::Some(account1_key);
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+
         do_process_instruction_dups(
-            transfer_checked(
+            set_authority(
                 &program_id,
-                &account4_key,
-                &mint_key,
-                &account2_key,
-                &multisig_key,
-                &[&account4_key],
-                500,
-                2,
+                &account1_key,
+                Some(&owner_key),
+                AuthorityType::CloseAccount,
+                &account1_key,
+                &[],
             )
             .unwrap(),
-            vec![
-                account4_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account4_info.clone(),
-            ],
+            vec![account1_info.clone(), account1_info.clone()],
         )
         .unwrap();
     }
 
     #[test]
-    fn test_transfer() {
+    fn test_set_authority() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
@@ -1628,66 +4003,56 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = SolanaAccount::default();
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = SolanaAccount::default();
+        let owner3_key = Pubkey::new_unique();
+        let mut owner3_account = SolanaAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        // create new mint with owner
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
+        // create mint with owner and freeze_authority
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
+        // invalid account
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    Some(&owner2_key),
+                    AuthorityType::AccountOwner,
+                    &owner_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut owner_account],
+            )
+        );
 
-        // create another account
+        // create account
         do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                &mut account3_account,
+                &mut account_account,
                 &mut mint_account,
                 &mut owner_account,
                 &mut rent_sysvar,
@@ -1695,1190 +4060,1088 @@ mod tests {
         )
         .unwrap();
 
-        // create mismatch account
+        // create another account
         do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account2_key, &mint2_key, &owner_key).unwrap(),
             vec![
-                &mut mismatch_account,
-                &mut mint_account,
+                &mut account2_account,
+                &mut mint2_account,
                 &mut owner_account,
                 &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
-
-        // mint to account
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
+            ],
         )
         .unwrap();
 
-        // missing signer
-        let mut instruction = transfer(
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                set_authority(
+                    &program_id,
+                    &account_key,
+                    Some(&owner_key),
+                    AuthorityType::AccountOwner,
+                    &owner2_key,
+                    &[]
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut owner2_account],
+            )
+        );
+
+        // owner did not sign
+        let mut instruction = set_authority(
             &program_id,
             &account_key,
-            &account2_key,
+            Some(&owner2_key),
+            AuthorityType::AccountOwner,
             &owner_key,
             &[],
-            1000,
         )
         .unwrap();
-        instruction.accounts[2].is_signer = false;
+        instruction.accounts[1].is_signer = false;
         assert_eq!(
             Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(
-                instruction,
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner_account,
-                ],
-            )
+            do_process_instruction(instruction, vec![&mut account_account, &mut owner_account,],)
         );
 
-        // mismatch mint
+        // wrong authority type
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(TokenError::AuthorityTypeNotSupported.into()),
             do_process_instruction(
-                transfer(
+                set_authority(
                     &program_id,
                     &account_key,
-                    &mismatch_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
                     &owner_key,
                     &[],
-                    1000
                 )
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mismatch_account,
-                    &mut owner_account,
-                ],
+                vec![&mut account_account, &mut owner_account],
             )
         );
 
-        // missing owner
+        // account owner may not be set to None
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::InvalidInstruction.into()),
             do_process_instruction(
-                transfer(
+                set_authority(
                     &program_id,
                     &account_key,
-                    &account2_key,
-                    &owner2_key,
+                    None,
+                    AuthorityType::AccountOwner,
+                    &owner_key,
                     &[],
-                    1000
                 )
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner2_account,
-                ],
+                vec![&mut account_account, &mut owner_account],
             )
         );
 
-        // transfer
+        // set delegate
         do_process_instruction(
-            transfer(
+            approve(
                 &program_id,
                 &account_key,
-                &account2_key,
+                &owner2_key,
                 &owner_key,
                 &[],
-                1000,
+                u64::MAX,
             )
             .unwrap(),
             vec![
                 &mut account_account,
-                &mut account2_account,
+                &mut owner2_account,
                 &mut owner_account,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::Some(owner2_key));
+        assert_eq!(account.delegated_amount, u64::MAX);
 
-        // insufficient funds
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            do_process_instruction(
-                transfer(&program_id, &account_key, &account2_key, &owner_key, &[], 1).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut owner_account,
-                ],
+        // set owner
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner3_key),
+                AuthorityType::AccountOwner,
+                &owner_key,
+                &[],
             )
-        );
+            .unwrap(),
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
 
-        // transfer half back
+        // check delegate cleared
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.delegate, COption::None);
+        assert_eq!(account.delegated_amount, 0);
+
+        // set owner without existing delegate
         do_process_instruction(
-            transfer(
+            set_authority(
                 &program_id,
-                &account2_key,
                 &account_key,
-                &owner_key,
+                Some(&owner2_key),
+                AuthorityType::AccountOwner,
+                &owner3_key,
                 &[],
-                500,
             )
             .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut account_account,
-                &mut owner_account,
-            ],
+            vec![&mut account_account, &mut owner3_account],
         )
         .unwrap();
 
-        // incorrect decimals
+        // set close_authority
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::CloseAccount,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        // close_authority may be set to None
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                None,
+                AuthorityType::CloseAccount,
+                &owner2_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut owner2_account],
+        )
+        .unwrap();
+
+        // wrong owner
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                transfer_checked(
+                set_authority(
                     &program_id,
-                    &account2_key,
                     &mint_key,
-                    &account_key,
-                    &owner_key,
-                    &[],
-                    1,
-                    10 // <-- incorrect decimals
+                    Some(&owner3_key),
+                    AuthorityType::MintTokens,
+                    &owner2_key,
+                    &[]
                 )
                 .unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut mint_account,
-                    &mut account_account,
-                    &mut owner_account,
-                ],
+                vec![&mut mint_account, &mut owner2_account],
             )
         );
 
-        // incorrect mint
+        // owner did not sign
+        let mut instruction = set_authority(
+            &program_id,
+            &mint_key,
+            Some(&owner2_key),
+            AuthorityType::MintTokens,
+            &owner_key,
+            &[],
+        )
+        .unwrap();
+        instruction.accounts[1].is_signer = false;
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(instruction, vec![&mut mint_account, &mut owner_account],)
+        );
+
+        // cannot freeze
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
             do_process_instruction(
-                transfer_checked(
+                set_authority(
                     &program_id,
-                    &account2_key,
-                    &account3_key, // <-- incorrect mint
-                    &account_key,
+                    &mint_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
                     &owner_key,
                     &[],
-                    1,
-                    2
                 )
                 .unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut account3_account, // <-- incorrect mint
-                    &mut account_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-        // transfer rest with explicit decimals
-        do_process_instruction(
-            transfer_checked(
-                &program_id,
-                &account2_key,
-                &mint_key,
-                &account_key,
-                &owner_key,
-                &[],
-                500,
-                2,
-            )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut account_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-
-        // insufficient funds
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            do_process_instruction(
-                transfer(&program_id, &account2_key, &account_key, &owner_key, &[], 1).unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut account_account,
-                    &mut owner_account,
-                ],
+                vec![&mut mint_account, &mut owner_account],
             )
         );
 
-        // approve delegate
+        // set owner
         do_process_instruction(
-            approve(
+            set_authority(
                 &program_id,
-                &account_key,
-                &delegate_key,
+                &mint_key,
+                Some(&owner2_key),
+                AuthorityType::MintTokens,
                 &owner_key,
                 &[],
-                100,
             )
             .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
+            vec![&mut mint_account, &mut owner_account],
         )
         .unwrap();
 
-        // transfer via delegate
+        // set owner to None
         do_process_instruction(
-            transfer(
+            set_authority(
                 &program_id,
-                &account_key,
-                &account2_key,
-                &delegate_key,
+                &mint_key,
+                None,
+                AuthorityType::MintTokens,
+                &owner2_key,
                 &[],
-                100,
             )
             .unwrap(),
-            vec![
-                &mut account_account,
-                &mut account2_account,
-                &mut delegate_account,
-            ],
+            vec![&mut mint_account, &mut owner2_account],
         )
         .unwrap();
 
-        // insufficient funds approved via delegate
+        // test unsetting mint_authority is one-way operation
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::FixedSupply.into()),
             do_process_instruction(
-                transfer(
+                set_authority(
                     &program_id,
-                    &account_key,
-                    &account2_key,
-                    &delegate_key,
-                    &[],
-                    100
+                    &mint2_key,
+                    Some(&owner2_key),
+                    AuthorityType::MintTokens,
+                    &owner_key,
+                    &[]
                 )
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut delegate_account,
-                ],
+                vec![&mut mint_account, &mut owner_account],
             )
         );
 
-        // transfer rest
+        // set freeze_authority
         do_process_instruction(
-            transfer(
+            set_authority(
                 &program_id,
-                &account_key,
-                &account2_key,
+                &mint2_key,
+                Some(&owner2_key),
+                AuthorityType::FreezeAccount,
                 &owner_key,
                 &[],
-                900,
             )
             .unwrap(),
-            vec![
-                &mut account_account,
-                &mut account2_account,
-                &mut owner_account,
-            ],
+            vec![&mut mint2_account, &mut owner_account],
         )
         .unwrap();
 
-        // approve delegate
+        // test unsetting freeze_authority is one-way operation
         do_process_instruction(
-            approve(
+            set_authority(
                 &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
+                &mint2_key,
+                None,
+                AuthorityType::FreezeAccount,
+                &owner2_key,
                 &[],
-                100,
             )
             .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
+            vec![&mut mint2_account, &mut owner2_account],
         )
         .unwrap();
 
-        // insufficient funds in source account via delegate
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::MintCannotFreeze.into()),
             do_process_instruction(
-                transfer(
+                set_authority(
                     &program_id,
-                    &account_key,
-                    &account2_key,
-                    &delegate_key,
+                    &mint2_key,
+                    Some(&owner2_key),
+                    AuthorityType::FreezeAccount,
+                    &owner_key,
                     &[],
-                    100
                 )
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account,
-                    &mut delegate_account,
-                ],
+                vec![&mut mint2_account, &mut owner2_account],
             )
         );
     }
 
     #[test]
-    fn test_self_transfer() {
+    fn test_mint_to_dups() {
         let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = SolanaAccount::default();
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
         // create mint
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &mint_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
         // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &owner_key).unwrap(),
             vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
+                account1_info.clone(),
+                mint_info.clone(),
+                owner_info.clone(),
+                rent_info.clone(),
             ],
         )
         .unwrap();
 
-        // mint to account
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let account_info = (&account_key, false, &mut account_account).into_account_info();
-        let account3_info = (&account3_key, false, &mut account3_account).into_account_info();
-        let delegate_info = (&delegate_key, true, &mut delegate_account).into_account_info();
-        let owner_info = (&owner_key, true, &mut owner_account).into_account_info();
-        let owner2_info = (&owner2_key, true, &mut owner2_account).into_account_info();
-        let mint_info = (&mint_key, false, &mut mint_account).into_account_info();
-
-        // transfer
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-
-        // transfer checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-            2,
-        )
-        .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-
-        // missing signer
-        let mut owner_no_sign_info = owner_info.clone();
-        let mut instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_no_sign_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
-        instruction.accounts[2].is_signer = false;
-        owner_no_sign_info.is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_no_sign_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-
-        // missing signer checked
-        let mut instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_no_sign_info.key,
-            &[],
-            1000,
-            2,
+        // mint_to when mint_authority is self
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &mint_key, &[], 42).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
         )
         .unwrap();
-        instruction.accounts[3].is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_no_sign_info,
-                ],
-                &instruction.data,
-            )
-        );
 
-        // missing owner
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner2_info.key,
-            &[],
-            1000,
+        // mint_to_checked when mint_authority is self
+        do_process_instruction_dups(
+            mint_to_checked(&program_id, &mint_key, &account1_key, &mint_key, &[], 42, 2).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner2_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // missing owner checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner2_info.key,
-            &[],
-            1000,
-            2,
+        // mint_to when mint_authority is account owner
+        let mut mint = Mint::unpack_unchecked(&mint_info.data.borrow()).unwrap();
+        mint.mint_authority = COption::Some(account1_key);
+        Mint::pack(mint, &mut mint_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                42,
+            )
+            .unwrap(),
+            vec![
+                mint_info.clone(),
+                account1_info.clone(),
+                account1_info.clone(),
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner2_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // insufficient funds
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1001,
+        // mint_to_checked when mint_authority is account owner
+        do_process_instruction_dups(
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                42,
+            )
+            .unwrap(),
+            vec![
+                mint_info.clone(),
+                account1_info.clone(),
+                account1_info.clone(),
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
+    }
 
-        // insufficient funds checked
-        let instruction = transfer_checked(
+    #[test]
+    fn test_mint_to() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
             &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1001,
-            2,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint2_key = Pubkey::new_unique();
+        let uninitialized_key = Pubkey::new_unique();
+        let mut uninitialized_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut rent_sysvar = rent_sysvar();
+
+        // create new mint with owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // incorrect decimals
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1,
-            10, // <-- incorrect decimals
+        // create account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // incorrect mint
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            account3_info.key, // <-- incorrect mint
-            account_info.key,
-            owner_info.key,
-            &[],
-            1,
-            2,
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account3_info.clone(), // <-- incorrect mint
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
 
-        // approve delegate
-        let instruction = approve(
-            &program_id,
-            account_info.key,
-            delegate_info.key,
-            owner_info.key,
-            &[],
-            100,
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        Processor::process(
-            &instruction.program_id,
-            &[
-                account_info.clone(),
-                delegate_info.clone(),
-                owner_info.clone(),
+
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
-            &instruction.data,
         )
         .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
 
-        // delegate transfer
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            100,
+        // mint to
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
-                ],
-                &instruction.data,
-            )
-        );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-        assert_eq!(account.delegated_amount, 100);
 
-        // delegate transfer checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            100,
-            2,
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 42);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // mint to another account to test supply accumulation
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut mint_account, &mut account2_account, &mut owner_account],
         )
         .unwrap();
+
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 84);
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 42);
+
+        // missing signer
+        let mut instruction =
+            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap();
+        instruction.accounts[2].is_signer = false;
         assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
-                ],
-                &instruction.data,
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![&mut mint_account, &mut account2_account, &mut owner_account],
             )
         );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
-        assert_eq!(account.delegated_amount, 100);
 
-        // delegate insufficient funds
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            101,
-        )
-        .unwrap();
+        // mismatch account
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
-                ],
-                &instruction.data,
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
             )
         );
 
-        // delegate insufficient funds checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            delegate_info.key,
-            &[],
-            101,
-            2,
-        )
-        .unwrap();
+        // missing owner
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    delegate_info.clone(),
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account2_key, &owner2_key, &[], 42).unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut owner2_account,
                 ],
-                &instruction.data,
             )
         );
 
-        // owner transfer with delegate assigned
-        let instruction = transfer(
-            &program_id,
-            account_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-        )
-        .unwrap();
+        // uninitialized destination account
         assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &uninitialized_key,
+                    &owner_key,
+                    &[],
+                    42
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut uninitialized_account,
+                    &mut owner_account,
                 ],
-                &instruction.data,
             )
         );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
 
-        // owner transfer with delegate assigned checked
-        let instruction = transfer_checked(
-            &program_id,
-            account_info.key,
-            mint_info.key,
-            account_info.key,
-            owner_info.key,
-            &[],
-            1000,
-            2,
-        )
-        .unwrap();
-        assert_eq!(
-            Ok(()),
-            Processor::process(
-                &instruction.program_id,
-                &[
-                    account_info.clone(),
-                    mint_info.clone(),
-                    account_info.clone(),
-                    owner_info.clone(),
-                ],
-                &instruction.data,
+        // unset mint_authority and test minting fails
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                None,
+                AuthorityType::MintTokens,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::FixedSupply.into()),
+            do_process_instruction(
+                mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mint_account, &mut account2_account, &mut owner_account],
             )
         );
-        // no balance change...
-        let account = Account::unpack_unchecked(&account_info.try_borrow_data().unwrap()).unwrap();
-        assert_eq!(account.amount, 1000);
     }
 
     #[test]
-    fn test_mintable_token_with_zero_supply() {
+    fn test_burn_dups() {
         let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint-able token with zero supply
-        let decimals = 2;
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, decimals).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(
-            mint,
-            Mint {
-                mint_authority: COption::Some(owner_key),
-                supply: 0,
-                decimals,
-                is_initialized: true,
-                freeze_authority: COption::None,
-            }
-        );
 
         // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
             vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
             ],
         )
         .unwrap();
 
-        // mint to
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        // mint to account
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
         )
         .unwrap();
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
 
-        // mint to 2, with incorrect decimals
-        assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
-            do_process_instruction(
-                mint_to_checked(
-                    &program_id,
-                    &mint_key,
-                    &account_key,
-                    &owner_key,
-                    &[],
-                    42,
-                    decimals + 1
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account],
+        // source-owner burn
+        do_process_instruction_dups(
+            burn(
+                &program_id,
+                &mint_key,
+                &account1_key,
+                &account1_key,
+                &[],
+                500,
             )
-        );
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
+        // source-owner burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
 
-        // mint to 2
-        do_process_instruction(
-            mint_to_checked(
+        // mint-owner burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.owner = mint_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint-owner burn_checked
+        do_process_instruction_dups(
+            burn_checked(
                 &program_id,
+                &account1_key,
+                &mint_key,
                 &mint_key,
-                &account_key,
-                &owner_key,
                 &[],
-                42,
-                decimals,
+                500,
+                2,
             )
             .unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // source-delegate burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // source-delegate burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &account1_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+            ],
+        )
+        .unwrap();
+
+        // mint-delegate burn
+        do_process_instruction_dups(
+            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
+            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
+        )
+        .unwrap();
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.delegated_amount = 1000;
+        account.delegate = COption::Some(mint_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
+        )
+        .unwrap();
+
+        // mint-delegate burn_checked
+        do_process_instruction_dups(
+            burn_checked(
+                &program_id,
+                &account1_key,
+                &mint_key,
+                &mint_key,
+                &[],
+                500,
+                2,
+            )
+            .unwrap(),
+            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
         )
         .unwrap();
-        let _ = Mint::unpack(&mint_account.data).unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 84);
     }
 
     #[test]
-    fn test_approve_dups() {
+    fn test_burn() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let account2_key = Pubkey::new_unique();
         let mut account2_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
         let account3_key = Pubkey::new_unique();
         let mut account3_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_info: AccountInfo = (&account3_key, true, &mut account3_account).into();
-        let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = SolanaAccount::new(
-            multisig_minimum_balance(),
-            Multisig::get_packed_len(),
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
+        let mismatch_key = Pubkey::new_unique();
+        let mut mismatch_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
             &program_id,
         );
-        let multisig_info: AccountInfo = (&multisig_key, true, &mut multisig_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
-        let rent_key = rent::id();
+        let mint2_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
+        // create new mint
+        do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
         // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
         // create another account
-        do_process_instruction_dups(
+        do_process_instruction(
             initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account2_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-
-        // source-owner approve
-        do_process_instruction_dups(
-            approve(
-                &program_id,
-                &account1_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-            )
-            .unwrap(),
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account3_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // source-owner approve_checked
-        do_process_instruction_dups(
-            approve_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &account2_key,
-                &account1_key,
-                &[],
-                500,
-                2,
-            )
-            .unwrap(),
+        // create mismatch account
+        do_process_instruction(
+            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut mismatch_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // source-owner revoke
-        do_process_instruction_dups(
-            revoke(&program_id, &account1_key, &account1_key, &[]).unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
+        // mint to account
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // test source-multisig signer
-        do_process_instruction_dups(
-            initialize_multisig(&program_id, &multisig_key, &[&account3_key], 1).unwrap(),
-            vec![
-                multisig_info.clone(),
-                rent_info.clone(),
-                account3_info.clone(),
-            ],
+        // mint to mismatch account and change mint key
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
         )
         .unwrap();
+        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
+        account.mint = mint2_key;
+        Account::pack(account, &mut mismatch_account.data).unwrap();
 
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account3_key, &mint_key, &multisig_key).unwrap(),
-            vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                multisig_info.clone(),
-                rent_info.clone(),
-            ],
+        // missing signer
+        let mut instruction =
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 42).unwrap();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account
+                ],
+            )
+        );
+
+        // missing owner
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                burn(&program_id, &account_key, &mint_key, &owner2_key, &[], 42).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+            )
+        );
+
+        // mint mismatch
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            do_process_instruction(
+                burn(&program_id, &mismatch_key, &mint_key, &owner_key, &[], 42).unwrap(),
+                vec![&mut mismatch_account, &mut mint_account, &mut owner_account],
+            )
+        );
+
+        // burn
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 21).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
 
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account3_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account3_info.clone(), owner_info.clone()],
+        // burn_checked, with incorrect decimals
+        assert_eq!(
+            Err(TokenError::MintDecimalsMismatch.into()),
+            do_process_instruction(
+                burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 3).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
+            )
+        );
+
+        // burn_checked
+        do_process_instruction(
+            burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 2).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
 
-        // source-multisig-signer approve
-        do_process_instruction_dups(
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 2000 - 42);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 1000 - 42);
+
+        // insufficient funds
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    100_000_000
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
+            )
+        );
+
+        // approve delegate
+        do_process_instruction(
             approve(
                 &program_id,
-                &account3_key,
-                &account2_key,
-                &multisig_key,
-                &[&account3_key],
-                500,
+                &account_key,
+                &delegate_key,
+                &owner_key,
+                &[],
+                84,
             )
             .unwrap(),
             vec![
-                account3_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
+                &mut account_account,
+                &mut delegate_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
 
-        // source-multisig-signer approve_checked
-        do_process_instruction_dups(
-            approve_checked(
-                &program_id,
-                &account3_key,
-                &mint_key,
-                &account2_key,
-                &multisig_key,
-                &[&account3_key],
-                500,
-                2,
+        // not a delegate of source account
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction(
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    100_000_000
+                )
+                .unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
-            .unwrap(),
-            vec![
-                account3_info.clone(),
-                mint_info.clone(),
-                account2_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
-            ],
-        )
-        .unwrap();
+        );
 
-        // source-owner multisig-signer
-        do_process_instruction_dups(
-            revoke(&program_id, &account3_key, &multisig_key, &[&account3_key]).unwrap(),
+        // burn via delegate
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 84).unwrap(),
             vec![
-                account3_info.clone(),
-                multisig_info.clone(),
-                account3_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
             ],
         )
         .unwrap();
+
+        // match
+        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        assert_eq!(mint.supply, 2000 - 42 - 84);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 1000 - 42 - 84);
+
+        // insufficient funds approved via delegate
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                burn(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account
+                ],
+            )
+        );
     }
 
     #[test]
-    fn test_approve() {
+    fn test_multisig() {
         let program_id = crate::id();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
+        let mut account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
@@ -2889,783 +5152,887 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = SolanaAccount::default();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_account = SolanaAccount::new(42, Multisig::get_packed_len(), &program_id);
+        let multisig_delegate_key = Pubkey::new_unique();
+        let mut multisig_delegate_account = SolanaAccount::new(
+            multisig_minimum_balance(),
+            Multisig::get_packed_len(),
+            &program_id,
+        );
+        let signer_keys = vec![Pubkey::new_unique(); MAX_SIGNERS];
+        let signer_key_refs: Vec<&Pubkey> = signer_keys.iter().collect();
+        let mut signer_accounts = vec![SolanaAccount::new(0, 0, &program_id); MAX_SIGNERS];
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        // multisig is not rent exempt
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        assert_eq!(
+            Err(TokenError::NotRentExempt.into()),
+            do_process_instruction(
+                initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+                vec![
+                    &mut multisig_account,
+                    &mut rent_sysvar,
+                    &mut account_info_iter.next().unwrap(),
+                ],
+            )
+        );
+
+        multisig_account.lamports = multisig_minimum_balance();
+        let mut multisig_account2 = multisig_account.clone();
+
+        // single signer
+        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+            vec![
+                &mut multisig_account,
+                &mut rent_sysvar,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // single signer using `initialize_multisig2`
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            initialize_multisig2(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
+            vec![
+                &mut multisig_account2,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // multiple signer
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            initialize_multisig(
+                &program_id,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                MAX_SIGNERS as u8,
+            )
+            .unwrap(),
+            vec![
+                &mut multisig_delegate_account,
+                &mut rent_sysvar,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        // create new mint with multisig owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &multisig_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
+        // create account with multisig owner
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &multisig_key).unwrap(),
             vec![
-                &mut account_account,
+                &mut account,
                 &mut mint_account,
-                &mut owner_account,
+                &mut multisig_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // create another account
+        // create another account with multisig owner
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &multisig_delegate_key,
+            )
+            .unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
-                &mut owner_account,
+                &mut multisig_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
 
         // mint to account
+        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
+            mint_to(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                1000,
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
 
-        // missing signer
-        let mut instruction = approve(
-            &program_id,
-            &account_key,
-            &delegate_key,
-            &owner_key,
-            &[],
-            100,
+        // approve
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            approve(
+                &program_id,
+                &account_key,
+                &multisig_delegate_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut multisig_delegate_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
-        instruction.accounts[2].is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(
-                instruction,
-                vec![
-                    &mut account_account,
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
+
+        // transfer
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
             )
-        );
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut account2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
 
-        // no owner
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                approve(
-                    &program_id,
-                    &account_key,
-                    &delegate_key,
-                    &owner2_key,
-                    &[],
-                    100
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut delegate_account,
-                    &mut owner2_account,
-                ],
+        // transfer via delegate
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                42,
             )
-        );
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut account2_account,
+                &mut multisig_delegate_account,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+            ],
+        )
+        .unwrap();
 
-        // approve delegate
+        // mint to
+        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            approve(
+            mint_to(
                 &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                100,
+                &mint_key,
+                &account2_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
             )
             .unwrap(),
             vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // approve delegate 2, with incorrect decimals
-        assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
-            do_process_instruction(
-                approve_checked(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &delegate_key,
-                    &owner_key,
-                    &[],
-                    100,
-                    0 // <-- incorrect decimals
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // approve delegate 2, with incorrect mint
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            do_process_instruction(
-                approve_checked(
-                    &program_id,
-                    &account_key,
-                    &account2_key, // <-- bad mint
-                    &delegate_key,
-                    &owner_key,
-                    &[],
-                    100,
-                    0
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account2_account, // <-- bad mint
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // approve delegate 2
+        // burn
+        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            approve_checked(
+            burn(
                 &program_id,
                 &account_key,
                 &mint_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                100,
-                2,
+                &multisig_key,
+                &[&signer_keys[0]],
+                42,
             )
             .unwrap(),
             vec![
-                &mut account_account,
+                &mut account,
                 &mut mint_account,
-                &mut delegate_account,
-                &mut owner_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
             ],
         )
         .unwrap();
 
-        // revoke delegate
+        // burn via delegate
+        let account_info_iter = &mut signer_accounts.iter_mut();
         do_process_instruction(
-            revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut owner_account],
+            burn(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &multisig_delegate_key,
+                &signer_key_refs,
+                42,
+            )
+            .unwrap(),
+            vec![
+                &mut account,
+                &mut mint_account,
+                &mut multisig_delegate_account,
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
-    }
 
-    #[test]
-    fn test_set_authority_dups() {
-        let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
+        // freeze account
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
+        let mint2_key = Pubkey::new_unique();
+        let mut mint2_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
-        let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
-
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &mint_key, Some(&mint_key), 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+        do_process_instruction(
+            initialize_mint(
+                &program_id,
+                &mint2_key,
+                &multisig_key,
+                Some(&multisig_key),
+                2,
+            )
+            .unwrap(),
+            vec![&mut mint2_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        do_process_instruction(
+            initialize_account(&program_id, &account3_key, &mint2_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account3_account,
+                &mut mint2_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // set mint_authority when currently self
-        do_process_instruction_dups(
-            set_authority(
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::MintTokens,
-                &mint_key,
-                &[],
+                &mint2_key,
+                &account3_key,
+                &multisig_key,
+                &[&signer_keys[0]],
+                1000,
             )
             .unwrap(),
-            vec![mint_info.clone(), mint_info.clone()],
+            vec![
+                &mut mint2_account,
+                &mut account3_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
-
-        // set freeze_authority when currently self
-        do_process_instruction_dups(
-            set_authority(
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
+            freeze_account(
                 &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::FreezeAccount,
-                &mint_key,
-                &[],
+                &account3_key,
+                &mint2_key,
+                &multisig_key,
+                &[&signer_keys[0]],
             )
             .unwrap(),
-            vec![mint_info.clone(), mint_info.clone()],
+            vec![
+                &mut account3_account,
+                &mut mint2_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
 
-        // set account owner when currently self
-        do_process_instruction_dups(
+        // do SetAuthority on mint
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
             set_authority(
                 &program_id,
-                &account1_key,
+                &mint_key,
                 Some(&owner_key),
-                AuthorityType::AccountOwner,
-                &account1_key,
-                &[],
+                AuthorityType::MintTokens,
+                &multisig_key,
+                &[&signer_keys[0]],
             )
             .unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
+            vec![
+                &mut mint_account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
 
-        // set close_authority when currently self
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.close_authority = COption::Some(account1_key);
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-
-        do_process_instruction_dups(
+        // do SetAuthority on account
+        let account_info_iter = &mut signer_accounts.iter_mut();
+        do_process_instruction(
             set_authority(
                 &program_id,
-                &account1_key,
+                &account_key,
                 Some(&owner_key),
-                AuthorityType::CloseAccount,
-                &account1_key,
-                &[],
+                AuthorityType::AccountOwner,
+                &multisig_key,
+                &[&signer_keys[0]],
             )
             .unwrap(),
-            vec![account1_info.clone(), account1_info.clone()],
+            vec![
+                &mut account,
+                &mut multisig_account,
+                &mut account_info_iter.next().unwrap(),
+            ],
         )
         .unwrap();
     }
 
     #[test]
-    fn test_set_authority() {
+    fn test_validate_owner() {
         let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let owner_key = Pubkey::new_unique();
+        let mut signer_keys = [Pubkey::default(); MAX_SIGNERS];
+        for signer_key in signer_keys.iter_mut().take(MAX_SIGNERS) {
+            *signer_key = Pubkey::new_unique();
+        }
+        let mut signer_lamports = 0;
+        let mut signer_data = vec![];
+        let mut signers = vec![
+            AccountInfo::new(
+                &owner_key,
+                true,
+                false,
+                &mut signer_lamports,
+                &mut signer_data,
+                &program_id,
+                false,
+                Epoch::default(),
+            );
+            MAX_SIGNERS + 1
+        ];
+        for (signer, key) in signers.iter_mut().zip(&signer_keys) {
+            signer.key = key;
+        }
+        let mut lamports = 0;
+        let mut data = vec![0; Multisig::get_packed_len()];
+        let mut multisig = Multisig::unpack_unchecked(&data).unwrap();
+        multisig.m = MAX_SIGNERS as u8;
+        multisig.n = MAX_SIGNERS as u8;
+        multisig.signers = signer_keys;
+        multisig.is_initialized = true;
+        Multisig::pack(multisig, &mut data).unwrap();
+        let owner_account_info = AccountInfo::new(
+            &owner_key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
             &program_id,
+            false,
+            Epoch::default(),
         );
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
-        let owner3_key = Pubkey::new_unique();
-        let mut owner3_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
+        // full 11 of 11
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
 
-        // create mint with owner and freeze_authority
-        do_process_instruction(
-            initialize_mint(&program_id, &mint2_key, &owner_key, Some(&owner_key), 2).unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
-        )
-        .unwrap();
+        // 1 of 11
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 1;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
 
-        // invalid account
+        // 2:1
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 1;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
         assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner2_key),
-                    AuthorityType::AccountOwner,
-                    &owner_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut owner_account],
-            )
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
         );
 
-        // create account
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint2_key, &owner_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint2_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
+        // 0:11
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 0;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
 
-        // missing owner
+        // 2:11 but 0 provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner_key),
-                    AuthorityType::AccountOwner,
-                    &owner2_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut owner2_account],
-            )
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &[])
+        );
+        // 2:11 but 1 provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[0..1])
         );
 
-        // owner did not sign
-        let mut instruction = set_authority(
-            &program_id,
-            &account_key,
-            Some(&owner2_key),
-            AuthorityType::AccountOwner,
-            &owner_key,
-            &[],
-        )
-        .unwrap();
-        instruction.accounts[1].is_signer = false;
+        // 2:11, 2 from middle provided
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 2;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[5..7])
+            .unwrap();
+
+        // 11:11, one is not a signer
+        {
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 11;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+        }
+        signers[5].is_signer = false;
         assert_eq!(
             Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(instruction, vec![&mut account_account, &mut owner_account,],)
+            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
         );
+        signers[5].is_signer = true;
+
+        // 11:11, single signer signs multiple times
+        {
+            let mut signer_lamports = 0;
+            let mut signer_data = vec![];
+            let signers = vec![
+                AccountInfo::new(
+                    &signer_keys[5],
+                    true,
+                    false,
+                    &mut signer_lamports,
+                    &mut signer_data,
+                    &program_id,
+                    false,
+                    Epoch::default(),
+                );
+                MAX_SIGNERS + 1
+            ];
+            let mut multisig =
+                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
+            multisig.m = 11;
+            multisig.n = 11;
+            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
+            assert_eq!(
+                Err(ProgramError::MissingRequiredSignature),
+                Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
+            );
+        }
+    }
 
-        // wrong authority type
-        assert_eq!(
-            Err(TokenError::AuthorityTypeNotSupported.into()),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut owner_account],
-            )
+    #[test]
+    fn test_close_account_dups() {
+        let program_id = crate::id();
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-
-        // account owner may not be set to None
-        assert_eq!(
-            Err(TokenError::InvalidInstruction.into()),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &account_key,
-                    None,
-                    AuthorityType::AccountOwner,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut owner_account],
-            )
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
+        let account2_info: AccountInfo = (&account2_key, true, &mut account2_account).into();
+        let owner_key = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let rent_key = rent::id();
+        let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // set delegate
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &owner2_key,
-                &owner_key,
-                &[],
-                u64::MAX,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut owner2_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.delegate, COption::Some(owner2_key));
-        assert_eq!(account.delegated_amount, u64::MAX);
-
-        // set owner
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner3_key),
-                AuthorityType::AccountOwner,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut owner_account],
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
-        // check delegate cleared
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.delegate, COption::None);
-        assert_eq!(account.delegated_amount, 0);
-
-        // set owner without existing delegate
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::AccountOwner,
-                &owner3_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut owner3_account],
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+            vec![
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
+            ],
         )
         .unwrap();
 
-        // set close_authority
-        do_process_instruction(
-            set_authority(
+        // source-owner close
+        do_process_instruction_dups(
+            close_account(
                 &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::CloseAccount,
-                &owner2_key,
+                &account1_key,
+                &account2_key,
+                &account1_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut account_account, &mut owner2_account],
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
         )
         .unwrap();
 
-        // close_authority may be set to None
-        do_process_instruction(
-            set_authority(
+        // source-close-authority close
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.close_authority = COption::Some(account1_key);
+        account.owner = owner_key;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            close_account(
                 &program_id,
-                &account_key,
-                None,
-                AuthorityType::CloseAccount,
-                &owner2_key,
+                &account1_key,
+                &account2_key,
+                &account1_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut account_account, &mut owner2_account],
+            vec![
+                account1_info.clone(),
+                account2_info.clone(),
+                account1_info.clone(),
+            ],
         )
         .unwrap();
+    }
 
-        // wrong owner
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint_key,
-                    Some(&owner3_key),
-                    AuthorityType::MintTokens,
-                    &owner2_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut owner2_account],
-            )
+    #[test]
+    fn test_close_account() {
+        let program_id = crate::id();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
         );
-
-        // owner did not sign
-        let mut instruction = set_authority(
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance() + 42,
+            Account::get_packed_len(),
             &program_id,
-            &mint_key,
-            Some(&owner2_key),
-            AuthorityType::MintTokens,
-            &owner_key,
-            &[],
-        )
-        .unwrap();
-        instruction.accounts[1].is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            do_process_instruction(instruction, vec![&mut mint_account, &mut owner_account],)
         );
+        let account3_key = Pubkey::new_unique();
+        let mut account3_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mut rent_sysvar = rent_sysvar();
 
-        // cannot freeze
+        // uninitialized
         assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
+            Err(ProgramError::UninitializedAccount),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut owner_account],
+                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner2_account,
+                ],
             )
         );
 
-        // set owner
+        // initialize and mint to non-native account
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint_key,
-                Some(&owner2_key),
-                AuthorityType::MintTokens,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut mint_account, &mut owner_account],
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 42);
 
-        // set owner to None
+        // initialize native account
         do_process_instruction(
-            set_authority(
+            initialize_account(
                 &program_id,
-                &mint_key,
-                None,
-                AuthorityType::MintTokens,
-                &owner2_key,
-                &[],
+                &account2_key,
+                &crate::native_mint::id(),
+                &owner_key,
             )
             .unwrap(),
-            vec![&mut mint_account, &mut owner2_account],
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 42);
 
-        // test unsetting mint_authority is one-way operation
+        // close non-native account with balance
         assert_eq!(
-            Err(TokenError::FixedSupply.into()),
+            Err(TokenError::NonNativeHasBalance.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint2_key,
-                    Some(&owner2_key),
-                    AuthorityType::MintTokens,
-                    &owner_key,
-                    &[]
-                )
-                .unwrap(),
-                vec![&mut mint_account, &mut owner_account],
+                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
             )
         );
+        assert_eq!(account_account.lamports, account_minimum_balance());
 
-        // set freeze_authority
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint2_key,
-                Some(&owner2_key),
-                AuthorityType::FreezeAccount,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut mint2_account, &mut owner_account],
-        )
-        .unwrap();
-
-        // test unsetting freeze_authority is one-way operation
+        // empty account
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint2_key,
-                None,
-                AuthorityType::FreezeAccount,
-                &owner2_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut mint2_account, &mut owner2_account],
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 42).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
 
+        // wrong owner
         assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                set_authority(
-                    &program_id,
-                    &mint2_key,
-                    Some(&owner2_key),
-                    AuthorityType::FreezeAccount,
-                    &owner_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut mint2_account, &mut owner2_account],
+                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner2_account,
+                ],
             )
         );
-    }
 
-    #[test]
-    fn test_mint_to_dups() {
-        let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
+        // close account
+        do_process_instruction(
+            close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account3_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
+
+        // fund and initialize new non-native account to test close authority
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = SolanaAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
-        let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
-
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &mint_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
-        )
-        .unwrap();
-
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &owner_key).unwrap(),
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                owner_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        account_account.lamports = 2;
 
-        // mint_to when mint_authority is self
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &mint_key, &[], 42).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
-
-        // mint_to_checked when mint_authority is self
-        do_process_instruction_dups(
-            mint_to_checked(&program_id, &mint_key, &account1_key, &mint_key, &[], 42, 2).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
-
-        // mint_to when mint_authority is account owner
-        let mut mint = Mint::unpack_unchecked(&mint_info.data.borrow()).unwrap();
-        mint.mint_authority = COption::Some(account1_key);
-        Mint::pack(mint, &mut mint_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            mint_to(
+        do_process_instruction(
+            set_authority(
                 &program_id,
-                &mint_key,
-                &account1_key,
-                &account1_key,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::CloseAccount,
+                &owner_key,
                 &[],
-                42,
             )
             .unwrap(),
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // account owner cannot authorize close if close_authority is set
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account3_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // close non-native account with close_authority
+        do_process_instruction(
+            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
             vec![
-                mint_info.clone(),
-                account1_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut account3_account,
+                &mut owner2_account,
             ],
         )
         .unwrap();
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance() + 2);
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 0);
 
-        // mint_to_checked when mint_authority is account owner
-        do_process_instruction_dups(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account1_key,
-                &account1_key,
-                &[],
-                42,
-            )
-            .unwrap(),
+        // close native account
+        do_process_instruction(
+            close_account(&program_id, &account2_key, &account3_key, &owner_key, &[]).unwrap(),
             vec![
-                mint_info.clone(),
-                account1_info.clone(),
-                account1_info.clone(),
+                &mut account2_account,
+                &mut account3_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account.amount, 0);
+        assert_eq!(
+            account3_account.lamports,
+            3 * account_minimum_balance() + 2 + 42
+        );
     }
 
     #[test]
-    fn test_mint_to() {
+    fn test_native_token() {
         let program_id = crate::id();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
+            account_minimum_balance() + 40,
             Account::get_packed_len(),
             &program_id,
         );
@@ -3676,43 +6043,23 @@ mod tests {
             &program_id,
         );
         let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
+        let mut account3_account = SolanaAccount::new(account_minimum_balance(), 0, &program_id);
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
         let owner2_key = Pubkey::new_unique();
         let mut owner2_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
-        let uninitialized_key = Pubkey::new_unique();
-        let mut uninitialized_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
+        let owner3_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
-
-        // create account
+        // initialize native account
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(
+                &program_id,
+                &account_key,
+                &crate::native_mint::id(),
+                &owner_key,
+            )
+            .unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
@@ -3721,10 +6068,19 @@ mod tests {
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 40);
 
-        // create another account
+        // initialize native account
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            initialize_account(
+                &program_id,
+                &account2_key,
+                &crate::native_mint::id(),
+                &owner_key,
+            )
+            .unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
@@ -3733,338 +6089,337 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create mismatch account
-        do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut mismatch_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
-
-        // mint to
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 42);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
-
-        // mint to another account to test supply accumulation
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut mint_account, &mut account2_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 84);
         let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert_eq!(account.amount, 42);
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
 
-        // missing signer
-        let mut instruction =
-            mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap();
-        instruction.accounts[2].is_signer = false;
+        // mint_to unsupported
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
+            Err(TokenError::NativeNotSupported.into()),
             do_process_instruction(
-                instruction,
-                vec![&mut mint_account, &mut account2_account, &mut owner_account],
+                mint_to(
+                    &program_id,
+                    &crate::native_mint::id(),
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    42
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
             )
         );
 
-        // mismatch account
-        assert_eq!(
-            Err(TokenError::MintMismatch.into()),
-            do_process_instruction(
-                mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
-            )
-        );
+        // burn unsupported
+        let bogus_mint_key = Pubkey::new_unique();
+        let mut bogus_mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        do_process_instruction(
+            initialize_mint(&program_id, &bogus_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut bogus_mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
 
-        // missing owner
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::NativeNotSupported.into()),
             do_process_instruction(
-                mint_to(&program_id, &mint_key, &account2_key, &owner2_key, &[], 42).unwrap(),
+                burn(
+                    &program_id,
+                    &account_key,
+                    &bogus_mint_key,
+                    &owner_key,
+                    &[],
+                    42
+                )
+                .unwrap(),
                 vec![
-                    &mut mint_account,
-                    &mut account2_account,
-                    &mut owner2_account,
+                    &mut account_account,
+                    &mut bogus_mint_account,
+                    &mut owner_account
                 ],
             )
         );
 
-        // uninitialized destination account
+        // ensure can't transfer below rent-exempt reserve
         assert_eq!(
-            Err(ProgramError::UninitializedAccount),
+            Err(TokenError::InsufficientFunds.into()),
             do_process_instruction(
-                mint_to(
+                transfer(
                     &program_id,
-                    &mint_key,
-                    &uninitialized_key,
+                    &account_key,
+                    &account2_key,
                     &owner_key,
                     &[],
-                    42
+                    50,
                 )
                 .unwrap(),
                 vec![
-                    &mut mint_account,
-                    &mut uninitialized_account,
+                    &mut account_account,
+                    &mut account2_account,
                     &mut owner_account,
                 ],
             )
         );
 
-        // unset mint_authority and test minting fails
+        // transfer between native accounts
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &account2_key,
+                &owner_key,
+                &[],
+                40,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(account_account.lamports, account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
+        assert_eq!(account2_account.lamports, account_minimum_balance() + 40);
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 40);
+
+        // set close authority
         do_process_instruction(
             set_authority(
                 &program_id,
-                &mint_key,
-                None,
-                AuthorityType::MintTokens,
+                &account_key,
+                Some(&owner3_key),
+                AuthorityType::CloseAccount,
                 &owner_key,
                 &[],
             )
             .unwrap(),
-            vec![&mut mint_account, &mut owner_account],
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.close_authority, COption::Some(owner3_key));
+
+        // set new account owner
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &account_key,
+                Some(&owner2_key),
+                AuthorityType::AccountOwner,
+                &owner_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // close authority cleared
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.close_authority, COption::None);
+
+        // close native account
+        do_process_instruction(
+            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+            vec![
+                &mut account_account,
+                &mut account3_account,
+                &mut owner2_account,
+            ],
         )
         .unwrap();
-        assert_eq!(
-            Err(TokenError::FixedSupply.into()),
-            do_process_instruction(
-                mint_to(&program_id, &mint_key, &account2_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mint_account, &mut account2_account, &mut owner_account],
-            )
-        );
+        assert_eq!(account_account.lamports, 0);
+        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, 0);
     }
 
     #[test]
-    fn test_burn_dups() {
+    fn test_overflow() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mint_owner_key = Pubkey::new_unique();
+        let mut mint_owner_account = SolanaAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+        // create new mint with owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &mint_owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        // create an account
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // mint to account
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-
-        // source-owner burn
-        do_process_instruction_dups(
-            burn(
-                &program_id,
-                &mint_key,
-                &account1_key,
-                &account1_key,
-                &[],
-                500,
-            )
-            .unwrap(),
+        // create another account
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner2_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // source-owner burn_checked
-        do_process_instruction_dups(
-            burn_checked(
+        // mint the max to an account
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &account1_key,
                 &mint_key,
-                &account1_key,
+                &account_key,
+                &mint_owner_key,
                 &[],
-                500,
-                2,
+                u64::MAX,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut mint_account,
+                &mut account_account,
+                &mut mint_owner_account,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u64::MAX);
 
-        // mint-owner burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.owner = mint_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
-
-        // mint-owner burn_checked
-        do_process_instruction_dups(
-            burn_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &mint_key,
-                &[],
-                500,
-                2,
+        // attempt to mint one more to account
+        assert_eq!(
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &mint_owner_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut mint_owner_account,
+                ],
             )
-            .unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
+        );
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u64::MAX);
 
-        // source-delegate burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &account1_key,
-                &[],
-                500,
+        // atttempt to mint one more to the other account
+        assert_eq!(
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                mint_to(
+                    &program_id,
+                    &mint_key,
+                    &account2_key,
+                    &mint_owner_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut mint_owner_account,
+                ],
             )
-            .unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-            ],
+        );
+
+        // burn some of the supply
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u64::MAX - 100);
 
-        // source-delegate burn_checked
-        do_process_instruction_dups(
-            burn_checked(
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &account1_key,
                 &mint_key,
-                &account1_key,
+                &account_key,
+                &mint_owner_key,
                 &[],
-                500,
-                2,
+                100,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
+                &mut mint_account,
+                &mut account_account,
+                &mut mint_owner_account,
             ],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, u64::MAX);
 
-        // mint-delegate burn
-        do_process_instruction_dups(
-            mint_to(&program_id, &mint_key, &account1_key, &owner_key, &[], 1000).unwrap(),
-            vec![mint_info.clone(), account1_info.clone(), owner_info.clone()],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.delegated_amount = 1000;
-        account.delegate = COption::Some(mint_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            burn(&program_id, &account1_key, &mint_key, &mint_key, &[], 500).unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
-
-        // mint-delegate burn_checked
-        do_process_instruction_dups(
-            burn_checked(
-                &program_id,
-                &account1_key,
-                &mint_key,
-                &mint_key,
-                &[],
-                500,
-                2,
+        // manipulate account balance to attempt overflow transfer
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.amount = 1;
+        Account::pack(account, &mut account2_account.data).unwrap();
+
+        assert_eq!(
+            Err(TokenError::Overflow.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account2_key,
+                    &account_key,
+                    &owner2_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut account_account,
+                    &mut owner2_account,
+                ],
             )
-            .unwrap(),
-            vec![account1_info.clone(), mint_info.clone(), mint_info.clone()],
-        )
-        .unwrap();
+        );
     }
 
     #[test]
-    fn test_burn() {
+    fn test_frozen() {
         let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
@@ -4078,31 +6433,14 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = SolanaAccount::default();
-        let mismatch_key = Pubkey::new_unique();
-        let mut mismatch_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint2_key = Pubkey::new_unique();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint
+        // create new mint and fund first account
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
@@ -4133,866 +6471,856 @@ mod tests {
         )
         .unwrap();
 
-        // create another account
-        do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create mismatch account
-        do_process_instruction(
-            initialize_account(&program_id, &mismatch_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut mismatch_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // mint to account
+        // fund first account
         do_process_instruction(
             mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // mint to mismatch account and change mint key
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &mismatch_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut mismatch_account, &mut owner_account],
-        )
-        .unwrap();
-        let mut account = Account::unpack_unchecked(&mismatch_account.data).unwrap();
-        account.mint = mint2_key;
-        Account::pack(account, &mut mismatch_account.data).unwrap();
-
-        // missing signer
-        let mut instruction =
-            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 42).unwrap();
-        instruction.accounts[1].is_signer = false;
+        // no transfer if either account is frozen
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account2_account.data).unwrap();
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                instruction,
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    500,
+                )
+                .unwrap(),
                 vec![
                     &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
             )
         );
 
-        // missing owner
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.state = AccountState::Initialized;
+        Account::pack(account, &mut account_account.data).unwrap();
+        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account2_account.data).unwrap();
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn(&program_id, &account_key, &mint_key, &owner2_key, &[], 42).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    500,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
             )
         );
 
-        // mint mismatch
+        // no approve if account is frozen
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account_account.data).unwrap();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
         assert_eq!(
-            Err(TokenError::MintMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn(&program_id, &mismatch_key, &mint_key, &owner_key, &[], 42).unwrap(),
-                vec![&mut mismatch_account, &mut mint_account, &mut owner_account],
+                approve(
+                    &program_id,
+                    &account_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    100
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
             )
         );
 
-        // burn
-        do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 21).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
-        )
-        .unwrap();
-
-        // burn_checked, with incorrect decimals
+        // no revoke if account is frozen
+        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
+        account.delegate = COption::Some(delegate_key);
+        account.delegated_amount = 100;
+        Account::pack(account, &mut account_account.data).unwrap();
         assert_eq!(
-            Err(TokenError::MintDecimalsMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 3).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut owner_account],
             )
         );
 
-        // burn_checked
-        do_process_instruction(
-            burn_checked(&program_id, &account_key, &mint_key, &owner_key, &[], 21, 2).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
-        )
-        .unwrap();
-
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 2000 - 42);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 1000 - 42);
-
-        // insufficient funds
+        // no set authority if account is frozen
+        let new_owner_key = Pubkey::new_unique();
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn(
+                set_authority(
                     &program_id,
                     &account_key,
-                    &mint_key,
+                    Some(&new_owner_key),
+                    AuthorityType::AccountOwner,
                     &owner_key,
-                    &[],
-                    100_000_000
+                    &[]
                 )
                 .unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                vec![&mut account_account, &mut owner_account,],
             )
         );
 
-        // approve delegate
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &delegate_key,
-                &owner_key,
-                &[],
-                84,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut delegate_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-
-        // not a delegate of source account
+        // no mint_to if destination account is frozen
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &owner_key,
-                    &[],
-                    100_000_000
-                )
-                .unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account,],
             )
         );
 
-        // burn via delegate
-        do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 84).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut delegate_account,
-            ],
-        )
-        .unwrap();
-
-        // match
-        let mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        assert_eq!(mint.supply, 2000 - 42 - 84);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 1000 - 42 - 84);
-
-        // insufficient funds approved via delegate
+        // no burn if account is frozen
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                burn(
-                    &program_id,
-                    &account_key,
-                    &mint_key,
-                    &delegate_key,
-                    &[],
-                    100
-                )
-                .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut mint_account,
-                    &mut delegate_account
-                ],
+                burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
         );
     }
 
     #[test]
-    fn test_multisig() {
+    fn test_freeze_thaw_dups() {
         let program_id = crate::id();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let account_key = Pubkey::new_unique();
-        let mut account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = SolanaAccount::new(
+        let account1_key = Pubkey::new_unique();
+        let mut account1_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
+        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let owner_key = Pubkey::new_unique();
-        let mut owner_account = SolanaAccount::default();
-        let multisig_key = Pubkey::new_unique();
-        let mut multisig_account = SolanaAccount::new(42, Multisig::get_packed_len(), &program_id);
-        let multisig_delegate_key = Pubkey::new_unique();
-        let mut multisig_delegate_account = SolanaAccount::new(
-            multisig_minimum_balance(),
-            Multisig::get_packed_len(),
-            &program_id,
-        );
-        let signer_keys = vec![Pubkey::new_unique(); MAX_SIGNERS];
-        let signer_key_refs: Vec<&Pubkey> = signer_keys.iter().collect();
-        let mut signer_accounts = vec![SolanaAccount::new(0, 0, &program_id); MAX_SIGNERS];
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
+        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // multisig is not rent exempt
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        assert_eq!(
-            Err(TokenError::NotRentExempt.into()),
-            do_process_instruction(
-                initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
-                vec![
-                    &mut multisig_account,
-                    &mut rent_sysvar,
-                    &mut account_info_iter.next().unwrap(),
-                ],
-            )
-        );
-
-        multisig_account.lamports = multisig_minimum_balance();
-        let mut multisig_account2 = multisig_account.clone();
-
-        // single signer
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            initialize_multisig(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
-            vec![
-                &mut multisig_account,
-                &mut rent_sysvar,
-                &mut account_info_iter.next().unwrap(),
-            ],
-        )
-        .unwrap();
-
-        // single signer using `initialize_multisig2`
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            initialize_multisig2(&program_id, &multisig_key, &[&signer_keys[0]], 1).unwrap(),
-            vec![
-                &mut multisig_account2,
-                &mut account_info_iter.next().unwrap(),
-            ],
-        )
-        .unwrap();
-
-        // multiple signer
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            initialize_multisig(
-                &program_id,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                MAX_SIGNERS as u8,
-            )
-            .unwrap(),
-            vec![
-                &mut multisig_delegate_account,
-                &mut rent_sysvar,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-            ],
-        )
-        .unwrap();
-
-        // create new mint with multisig owner
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &multisig_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
-
-        // create account with multisig owner
-        do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &multisig_key).unwrap(),
-            vec![
-                &mut account,
-                &mut mint_account,
-                &mut multisig_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-
-        // create another account with multisig owner
-        do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &mint_key,
-                &multisig_delegate_key,
-            )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut multisig_account,
-                &mut rent_sysvar,
-            ],
+        // create mint
+        do_process_instruction_dups(
+            initialize_mint(&program_id, &mint_key, &owner_key, Some(&account1_key), 2).unwrap(),
+            vec![mint_info.clone(), rent_info.clone()],
         )
         .unwrap();
 
-        // mint to account
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                1000,
-            )
-            .unwrap(),
+        // create account
+        do_process_instruction_dups(
+            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
             vec![
-                &mut mint_account,
-                &mut account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
+                rent_info.clone(),
             ],
         )
         .unwrap();
 
-        // approve
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            approve(
-                &program_id,
-                &account_key,
-                &multisig_delegate_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                100,
-            )
-            .unwrap(),
+        // freeze where mint freeze_authority is account
+        do_process_instruction_dups(
+            freeze_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
             vec![
-                &mut account,
-                &mut multisig_delegate_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
 
-        // transfer
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
-            )
-            .unwrap(),
+        // thaw where mint freeze_authority is account
+        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
+        account.state = AccountState::Frozen;
+        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
+        do_process_instruction_dups(
+            thaw_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
             vec![
-                &mut account,
-                &mut account2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                account1_info.clone(),
+                mint_info.clone(),
+                account1_info.clone(),
             ],
         )
         .unwrap();
-
-        // transfer via delegate
-        let account_info_iter = &mut signer_accounts.iter_mut();
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                42,
-            )
-            .unwrap(),
-            vec![
-                &mut account,
-                &mut account2_account,
-                &mut multisig_delegate_account,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-            ],
+    }
+
+    #[test]
+    fn test_freeze_account() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let account_owner_key = Pubkey::new_unique();
+        let mut account_owner_account = SolanaAccount::default();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let owner2_key = Pubkey::new_unique();
+        let mut owner2_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create new mint with owner different from account owner
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // mint to
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        // create account
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
-            )
-            .unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &account_owner_key).unwrap(),
             vec![
+                &mut account_account,
                 &mut mint_account,
-                &mut account2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut account_owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // burn
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        // mint to account
         do_process_instruction(
-            burn(
-                &program_id,
-                &account_key,
-                &mint_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                42,
-            )
-            .unwrap(),
-            vec![
-                &mut account,
-                &mut mint_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // burn via delegate
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        // mint cannot freeze
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
+            do_process_instruction(
+                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
+            )
+        );
+
+        // missing freeze_authority
+        let mut mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
+        mint.freeze_authority = COption::Some(owner_key);
+        Mint::pack(mint, &mut mint_account.data).unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                freeze_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+            )
+        );
+
+        // check explicit thaw
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+            )
+        );
+
+        // freeze
         do_process_instruction(
-            burn(
-                &program_id,
-                &account_key,
-                &mint_key,
-                &multisig_delegate_key,
-                &signer_key_refs,
-                42,
+            freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Frozen);
+
+        // check explicit freeze
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner_account],
             )
-            .unwrap(),
-            vec![
-                &mut account,
-                &mut mint_account,
-                &mut multisig_delegate_account,
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-                &mut account_info_iter.next().unwrap(),
-            ],
+        );
+
+        // check thaw authority
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
+                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+            )
+        );
+
+        // thaw
+        do_process_instruction(
+            thaw_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Initialized);
+    }
 
-        // freeze account
-        let account3_key = Pubkey::new_unique();
+    #[test]
+    fn test_initialize_account2_and_3() {
+        let program_id = crate::id();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
         let mut account3_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let mint2_key = Pubkey::new_unique();
-        let mut mint2_account =
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mut rent_sysvar = rent_sysvar();
+
+        // create mint
         do_process_instruction(
-            initialize_mint(
-                &program_id,
-                &mint2_key,
-                &multisig_key,
-                Some(&multisig_key),
-                2,
-            )
-            .unwrap(),
-            vec![&mut mint2_account, &mut rent_sysvar],
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+
         do_process_instruction(
-            initialize_account(&program_id, &account3_key, &mint2_key, &owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                &mut account3_account,
-                &mut mint2_account,
+                &mut account_account,
+                &mut mint_account,
                 &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-        let account_info_iter = &mut signer_accounts.iter_mut();
+
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint2_key,
-                &account3_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-                1000,
-            )
-            .unwrap(),
-            vec![
-                &mut mint2_account,
-                &mut account3_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            initialize_account2(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![&mut account2_account, &mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-        let account_info_iter = &mut signer_accounts.iter_mut();
+
+        assert_eq!(account_account, account2_account);
+
         do_process_instruction(
-            freeze_account(
-                &program_id,
-                &account3_key,
-                &mint2_key,
-                &multisig_key,
-                &[&signer_keys[0]],
-            )
-            .unwrap(),
-            vec![
-                &mut account3_account,
-                &mut mint2_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
-            ],
+            initialize_account3(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            vec![&mut account3_account, &mut mint_account],
+        )
+        .unwrap();
+
+        assert_eq!(account_account, account3_account);
+    }
+
+    #[test]
+    fn test_sync_native() {
+        let program_id = crate::id();
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let native_account_key = Pubkey::new_unique();
+        let lamports = 40;
+        let mut native_account = SolanaAccount::new(
+            account_minimum_balance() + lamports,
+            Account::get_packed_len(),
+            &program_id,
+        );
+        let non_native_account_key = Pubkey::new_unique();
+        let mut non_native_account = SolanaAccount::new(
+            account_minimum_balance() + 50,
+            Account::get_packed_len(),
+            &program_id,
+        );
+
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let mut rent_sysvar = rent_sysvar();
+
+        // initialize non-native mint
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // do SetAuthority on mint
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        // initialize non-native account
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &mint_key,
-                Some(&owner_key),
-                AuthorityType::MintTokens,
-                &multisig_key,
-                &[&signer_keys[0]],
-            )
-            .unwrap(),
+            initialize_account(&program_id, &non_native_account_key, &mint_key, &owner_key)
+                .unwrap(),
             vec![
+                &mut non_native_account,
                 &mut mint_account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
 
-        // do SetAuthority on account
-        let account_info_iter = &mut signer_accounts.iter_mut();
+        let account = Account::unpack_unchecked(&non_native_account.data).unwrap();
+        assert!(!account.is_native());
+        assert_eq!(account.amount, 0);
+
+        // fail sync non-native
+        assert_eq!(
+            Err(TokenError::NonNativeNotSupported.into()),
+            do_process_instruction(
+                sync_native(&program_id, &non_native_account_key,).unwrap(),
+                vec![&mut non_native_account],
+            )
+        );
+
+        // fail sync uninitialized
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            do_process_instruction(
+                sync_native(&program_id, &native_account_key,).unwrap(),
+                vec![&mut native_account],
+            )
+        );
+
+        // wrap native account
         do_process_instruction(
-            set_authority(
+            initialize_account(
                 &program_id,
-                &account_key,
-                Some(&owner_key),
-                AuthorityType::AccountOwner,
-                &multisig_key,
-                &[&signer_keys[0]],
+                &native_account_key,
+                &crate::native_mint::id(),
+                &owner_key,
             )
             .unwrap(),
             vec![
-                &mut account,
-                &mut multisig_account,
-                &mut account_info_iter.next().unwrap(),
+                &mut native_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
-    }
-
-    #[test]
-    fn test_validate_owner() {
-        let program_id = crate::id();
-        let owner_key = Pubkey::new_unique();
-        let mut signer_keys = [Pubkey::default(); MAX_SIGNERS];
-        for signer_key in signer_keys.iter_mut().take(MAX_SIGNERS) {
-            *signer_key = Pubkey::new_unique();
-        }
-        let mut signer_lamports = 0;
-        let mut signer_data = vec![];
-        let mut signers = vec![
-            AccountInfo::new(
-                &owner_key,
-                true,
-                false,
-                &mut signer_lamports,
-                &mut signer_data,
-                &program_id,
-                false,
-                Epoch::default(),
-            );
-            MAX_SIGNERS + 1
-        ];
-        for (signer, key) in signers.iter_mut().zip(&signer_keys) {
-            signer.key = key;
-        }
-        let mut lamports = 0;
-        let mut data = vec![0; Multisig::get_packed_len()];
-        let mut multisig = Multisig::unpack_unchecked(&data).unwrap();
-        multisig.m = MAX_SIGNERS as u8;
-        multisig.n = MAX_SIGNERS as u8;
-        multisig.signers = signer_keys;
-        multisig.is_initialized = true;
-        Multisig::pack(multisig, &mut data).unwrap();
-        let owner_account_info = AccountInfo::new(
-            &owner_key,
-            false,
-            false,
-            &mut lamports,
-            &mut data,
-            &program_id,
-            false,
-            Epoch::default(),
-        );
-
-        // full 11 of 11
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
-
-        // 1 of 11
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 1;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert!(account.is_native());
+        assert_eq!(account.amount, lamports);
 
-        // 2:1
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 1;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
-        );
+        // sync, no change
+        do_process_instruction(
+            sync_native(&program_id, &native_account_key).unwrap(),
+            vec![&mut native_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert_eq!(account.amount, lamports);
 
-        // 0:11
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 0;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers).unwrap();
+        // transfer sol
+        let new_lamports = lamports + 50;
+        native_account.lamports = account_minimum_balance() + new_lamports;
 
-        // 2:11 but 0 provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &[])
-        );
-        // 2:11 but 1 provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[0..1])
-        );
+        // success sync
+        do_process_instruction(
+            sync_native(&program_id, &native_account_key).unwrap(),
+            vec![&mut native_account],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&native_account.data).unwrap();
+        assert_eq!(account.amount, new_lamports);
 
-        // 2:11, 2 from middle provided
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 2;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers[5..7])
-            .unwrap();
+        // reduce sol
+        native_account.lamports -= 1;
 
-        // 11:11, one is not a signer
-        {
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 11;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-        }
-        signers[5].is_signer = false;
+        // fail sync
         assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
-        );
-        signers[5].is_signer = true;
-
-        // 11:11, single signer signs multiple times
-        {
-            let mut signer_lamports = 0;
-            let mut signer_data = vec![];
-            let signers = vec![
-                AccountInfo::new(
-                    &signer_keys[5],
-                    true,
-                    false,
-                    &mut signer_lamports,
-                    &mut signer_data,
-                    &program_id,
-                    false,
-                    Epoch::default(),
-                );
-                MAX_SIGNERS + 1
-            ];
-            let mut multisig =
-                Multisig::unpack_unchecked(&owner_account_info.data.borrow()).unwrap();
-            multisig.m = 11;
-            multisig.n = 11;
-            Multisig::pack(multisig, &mut owner_account_info.data.borrow_mut()).unwrap();
-            assert_eq!(
-                Err(ProgramError::MissingRequiredSignature),
-                Processor::validate_owner(&program_id, &owner_key, &owner_account_info, &signers)
-            );
-        }
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                sync_native(&program_id, &native_account_key,).unwrap(),
+                vec![&mut native_account],
+            )
+        );
     }
 
     #[test]
-    fn test_close_account_dups() {
+    fn test_transfer_fee() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]);
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::TransferFeeAmount]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
+            &program_id,
+        );
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
         let account2_key = Pubkey::new_unique();
         let mut account2_account = SolanaAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let legacy_account_key = Pubkey::new_unique();
+        let mut legacy_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_info: AccountInfo = (&account2_key, true, &mut account2_account).into();
         let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
-        let rent_key = rent::id();
+        let mut owner_account = SolanaAccount::default();
+        let withdraw_authority_key = Pubkey::new_unique();
+        let mut withdraw_authority_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // create mint
-        do_process_instruction_dups(
+        // fee above 100% fails
+        assert_eq!(
+            Err(TokenError::TransferFeeExceedsMaximum.into()),
+            do_process_instruction(
+                initialize_transfer_fee_config(
+                    &program_id,
+                    &mint_key,
+                    Some(&withdraw_authority_key),
+                    MAX_FEE_BASIS_POINTS + 1,
+                    u64::MAX,
+                )
+                .unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+
+        // 1% fee, capped at 50 tokens
+        do_process_instruction(
+            initialize_transfer_fee_config(
+                &program_id,
+                &mint_key,
+                Some(&withdraw_authority_key),
+                100,
+                50,
+            )
+            .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
+        // fee can't be configured once the mint is initialized
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_transfer_fee_config(&program_id, &mint_key, None, 0, 0).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+
+        // legacy-sized account has no room for withheld fees
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            do_process_instruction(
+                initialize_account(&program_id, &legacy_account_key, &mint_key, &owner_key)
+                    .unwrap(),
+                vec![
+                    &mut legacy_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar,
+                ],
+            )
+        );
+
+        // create accounts
+        do_process_instruction(
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
             ],
         )
         .unwrap();
+        let account = StateWithExtensions::<Account>::unpack(&account_account.data).unwrap();
+        assert_eq!(
+            account.get_extension::<TransferFeeAmount>().unwrap(),
+            TransferFeeAmount::default()
+        );
 
-        // source-owner close
-        do_process_instruction_dups(
-            close_account(
+        do_process_instruction(
+            mint_to(
                 &program_id,
-                &account1_key,
+                &mint_key,
+                &account_key,
+                &owner_key,
+                &[],
+                10_000,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // plain transfer must provide the mint
+        assert_eq!(
+            Err(TokenError::MintRequiredForTransfer.into()),
+            do_process_instruction(
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    1_000
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // fee is withheld on the destination
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
                 &account2_key,
-                &account1_key,
+                &owner_key,
                 &[],
+                1_000,
+                2,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut owner_account,
             ],
         )
         .unwrap();
+        let account = StateWithExtensions::<Account>::unpack(&account_account.data).unwrap();
+        assert_eq!(account.base.amount, 9_000);
+        let account2 = StateWithExtensions::<Account>::unpack(&account2_account.data).unwrap();
+        assert_eq!(account2.base.amount, 990);
+        assert_eq!(
+            account2
+                .get_extension::<TransferFeeAmount>()
+                .unwrap()
+                .withheld_amount,
+            10
+        );
 
-        // source-close-authority close
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.close_authority = COption::Some(account1_key);
-        account.owner = owner_key;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            close_account(
+        // fee is capped at the maximum
+        do_process_instruction(
+            transfer_checked(
                 &program_id,
-                &account1_key,
+                &account_key,
+                &mint_key,
                 &account2_key,
-                &account1_key,
+                &owner_key,
                 &[],
+                9_000,
+                2,
             )
             .unwrap(),
             vec![
-                account1_info.clone(),
-                account2_info.clone(),
-                account1_info.clone(),
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut owner_account,
+            ],
+        )
+        .unwrap();
+        let account2 = StateWithExtensions::<Account>::unpack(&account2_account.data).unwrap();
+        assert_eq!(account2.base.amount, 9_940);
+        assert_eq!(
+            account2
+                .get_extension::<TransferFeeAmount>()
+                .unwrap()
+                .withheld_amount,
+            60
+        );
+
+        // withheld fees block closing the account
+        do_process_instruction(
+            burn_checked(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &owner_key,
+                &[],
+                9_940,
+                2,
+            )
+            .unwrap(),
+            vec![&mut account2_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::AccountHasWithheldTransferFees.into()),
+            do_process_instruction(
+                close_account(&program_id, &account2_key, &owner_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut account2_account,
+                    &mut owner_account.clone(),
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // wrong withdraw authority
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                withdraw_withheld_tokens_from_accounts(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &owner_key,
+                    &[],
+                    &[&account2_key],
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut owner_account,
+                    &mut account2_account,
+                ],
+            )
+        );
+
+        // harvest withheld fees
+        do_process_instruction(
+            withdraw_withheld_tokens_from_accounts(
+                &program_id,
+                &mint_key,
+                &account_key,
+                &withdraw_authority_key,
+                &[],
+                &[&account2_key],
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut account_account,
+                &mut withdraw_authority_account,
+                &mut account2_account,
+            ],
+        )
+        .unwrap();
+        let account = StateWithExtensions::<Account>::unpack(&account_account.data).unwrap();
+        assert_eq!(account.base.amount, 60);
+        let account2 = StateWithExtensions::<Account>::unpack(&account2_account.data).unwrap();
+        assert_eq!(
+            account2
+                .get_extension::<TransferFeeAmount>()
+                .unwrap()
+                .withheld_amount,
+            0
+        );
+
+        // now the account can be closed
+        do_process_instruction(
+            close_account(&program_id, &account2_key, &owner_key, &owner_key, &[]).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut owner_account.clone(),
+                &mut owner_account,
             ],
         )
         .unwrap();
+
+        // remove the withdraw authority
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                None,
+                AuthorityType::WithheldWithdraw,
+                &withdraw_authority_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut withdraw_authority_account],
+        )
+        .unwrap();
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(
+            mint.get_extension::<TransferFeeConfig>()
+                .unwrap()
+                .withdraw_withheld_authority,
+            COption::None
+        );
+        assert_eq!(
+            Err(TokenError::NoAuthorityExists.into()),
+            do_process_instruction(
+                withdraw_withheld_tokens_from_accounts(
+                    &program_id,
+                    &mint_key,
+                    &account_key,
+                    &withdraw_authority_key,
+                    &[],
+                    &[],
+                )
+                .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut account_account,
+                    &mut withdraw_authority_account,
+                ],
+            )
+        );
     }
 
     #[test]
-    fn test_close_account() {
+    fn test_memo_transfer() {
         let program_id = crate::id();
+        let account_len = ExtensionType::get_account_len::<Account>(&[ExtensionType::MemoTransfer]);
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
@@ -5002,16 +7330,10 @@ mod tests {
             Account::get_packed_len(),
             &program_id,
         );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = SolanaAccount::new(
-            account_minimum_balance() + 42,
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let memo_account_key = Pubkey::new_unique();
+        let mut memo_account_account = SolanaAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let owner_key = Pubkey::new_unique();
@@ -5020,20 +7342,6 @@ mod tests {
         let mut owner2_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // uninitialized
-        assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account3_account,
-                    &mut owner2_account,
-                ],
-            )
-        );
-
-        // initialize and mint to non-native account
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
@@ -5050,210 +7358,311 @@ mod tests {
         )
         .unwrap();
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 42).unwrap(),
+            initialize_account(&program_id, &memo_account_key, &mint_key, &owner2_key).unwrap(),
             vec![
+                &mut memo_account_account,
                 &mut mint_account,
-                &mut account_account,
-                &mut owner_account,
+                &mut owner2_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 42);
-
-        // initialize native account
         do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &crate::native_mint::id(),
-                &owner_key,
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // legacy-sized account has no room for the extension
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            do_process_instruction(
+                enable_required_transfer_memos(&program_id, &account_key, &owner_key, &[]).unwrap(),
+                vec![&mut account_account, &mut owner_account],
             )
-            .unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+        );
+
+        // only the owner may toggle
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                enable_required_transfer_memos(&program_id, &memo_account_key, &owner_key, &[])
+                    .unwrap(),
+                vec![&mut memo_account_account, &mut owner_account],
+            )
+        );
+        let mut instruction =
+            enable_required_transfer_memos(&program_id, &memo_account_key, &owner2_key, &[])
+                .unwrap();
+        instruction.accounts[1].is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            do_process_instruction(
+                instruction,
+                vec![&mut memo_account_account, &mut owner2_account],
+            )
+        );
+
+        do_process_instruction(
+            enable_required_transfer_memos(&program_id, &memo_account_key, &owner2_key, &[])
+                .unwrap(),
+            vec![&mut memo_account_account, &mut owner2_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 42);
+        let account = StateWithExtensions::<Account>::unpack(&memo_account_account.data).unwrap();
+        assert_eq!(
+            account.get_extension::<MemoTransfer>().unwrap(),
+            MemoTransfer {
+                require_incoming_transfer_memos: true
+            }
+        );
 
-        // close non-native account with balance
+        // missing instructions sysvar
         assert_eq!(
-            Err(TokenError::NonNativeHasBalance.into()),
+            Err(TokenError::NoMemo.into()),
             do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &memo_account_key,
+                    &owner_key,
+                    &[],
+                    10
+                )
+                .unwrap(),
                 vec![
                     &mut account_account,
-                    &mut account3_account,
+                    &mut memo_account_account,
                     &mut owner_account,
                 ],
             )
         );
-        assert_eq!(account_account.lamports, account_minimum_balance());
 
-        // empty account
-        do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 42).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
-        )
-        .unwrap();
+        let transfer_with_sysvar = || {
+            let mut instruction = transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &memo_account_key,
+                &owner_key,
+                &[],
+                10,
+                2,
+            )
+            .unwrap();
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(instructions::id(), false));
+            instruction
+        };
+        let memo = spl_memo::build_memo(b"hello", &[]);
+        let not_memo = sync_native(&program_id, &account_key).unwrap();
 
-        // wrong owner
+        // transfer is the first instruction
+        let mut sysvar_account = instructions_sysvar(&[transfer_with_sysvar(), memo.clone()], 0);
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::NoMemo.into()),
             do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+                transfer_with_sysvar(),
                 vec![
                     &mut account_account,
-                    &mut account3_account,
-                    &mut owner2_account,
+                    &mut mint_account,
+                    &mut memo_account_account,
+                    &mut owner_account,
+                    &mut sysvar_account,
                 ],
             )
         );
 
-        // close account
-        do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
-            vec![
-                &mut account_account,
-                &mut account3_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 0);
+        // previous instruction is not a memo
+        let mut sysvar_account = instructions_sysvar(&[not_memo, transfer_with_sysvar()], 1);
+        assert_eq!(
+            Err(TokenError::NoMemo.into()),
+            do_process_instruction(
+                transfer_with_sysvar(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut memo_account_account,
+                    &mut owner_account,
+                    &mut sysvar_account,
+                ],
+            )
+        );
 
-        // fund and initialize new non-native account to test close authority
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
+        // a memo preceding a batch does not cover its transfers
+        let batch_instruction = batch(&program_id, &[transfer_with_sysvar()]).unwrap();
+        let mut sysvar_account = instructions_sysvar(&[memo.clone(), batch_instruction.clone()], 1);
+        assert_eq!(
+            Err(TokenError::NoMemo.into()),
+            do_process_instruction(
+                batch_instruction,
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut memo_account_account,
+                    &mut owner_account,
+                    &mut sysvar_account,
+                ],
+            )
         );
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
+
+        // sysvar account provided in place of the instructions sysvar
+        let mut instruction = transfer_with_sysvar();
+        instruction.accounts[4].pubkey = rent::id();
+        let mut sysvar_account = instructions_sysvar(&[memo.clone(), instruction.clone()], 1);
+        assert_eq!(
+            Err(TokenError::NoMemo.into()),
+            do_process_instruction(
+                instruction,
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut memo_account_account,
+                    &mut owner_account,
+                    &mut sysvar_account,
+                ],
+            )
         );
+
+        // memo precedes the transfer
+        let mut sysvar_account = instructions_sysvar(&[memo, transfer_with_sysvar()], 1);
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+            transfer_with_sysvar(),
             vec![
                 &mut account_account,
                 &mut mint_account,
+                &mut memo_account_account,
                 &mut owner_account,
-                &mut rent_sysvar,
+                &mut sysvar_account,
             ],
         )
         .unwrap();
-        account_account.lamports = 2;
+        let account = Account::unpack_from_slice(&memo_account_account.data).unwrap();
+        assert_eq!(account.amount, 10);
 
+        // transfers out of the account don't need a memo
         do_process_instruction(
-            set_authority(
+            transfer(
                 &program_id,
+                &memo_account_key,
                 &account_key,
-                Some(&owner2_key),
-                AuthorityType::CloseAccount,
-                &owner_key,
+                &owner2_key,
                 &[],
+                5,
             )
             .unwrap(),
-            vec![&mut account_account, &mut owner_account],
-        )
-        .unwrap();
-
-        // account owner cannot authorize close if close_authority is set
-        assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
-            do_process_instruction(
-                close_account(&program_id, &account_key, &account3_key, &owner_key, &[]).unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut account3_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // close non-native account with close_authority
-        do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
             vec![
+                &mut memo_account_account,
                 &mut account_account,
-                &mut account3_account,
                 &mut owner2_account,
             ],
         )
         .unwrap();
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance() + 2);
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, 0);
 
-        // close native account
         do_process_instruction(
-            close_account(&program_id, &account2_key, &account3_key, &owner_key, &[]).unwrap(),
+            disable_required_transfer_memos(&program_id, &memo_account_key, &owner2_key, &[])
+                .unwrap(),
+            vec![&mut memo_account_account, &mut owner2_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            transfer(
+                &program_id,
+                &account_key,
+                &memo_account_key,
+                &owner_key,
+                &[],
+                10,
+            )
+            .unwrap(),
             vec![
-                &mut account2_account,
-                &mut account3_account,
+                &mut account_account,
+                &mut memo_account_account,
                 &mut owner_account,
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account_account.lamports, 0);
-        assert_eq!(account.amount, 0);
-        assert_eq!(
-            account3_account.lamports,
-            3 * account_minimum_balance() + 2 + 42
-        );
+        let account = Account::unpack_from_slice(&memo_account_account.data).unwrap();
+        assert_eq!(account.amount, 15);
     }
 
     #[test]
-    fn test_native_token() {
+    fn test_non_transferable() {
         let program_id = crate::id();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::NonTransferable]);
+        let account_len =
+            ExtensionType::get_account_len::<Account>(&[ExtensionType::NonTransferableAccount]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
+            &program_id,
+        );
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
-            account_minimum_balance() + 40,
-            Account::get_packed_len(),
+            Rent::default().minimum_balance(account_len),
+            account_len,
             &program_id,
         );
         let account2_key = Pubkey::new_unique();
         let mut account2_account = SolanaAccount::new(
+            Rent::default().minimum_balance(account_len),
+            account_len,
+            &program_id,
+        );
+        let legacy_account_key = Pubkey::new_unique();
+        let mut legacy_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account3_key = Pubkey::new_unique();
-        let mut account3_account = SolanaAccount::new(account_minimum_balance(), 0, &program_id);
-        let owner_key = Pubkey::new_unique();
-        let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
-        let owner3_key = Pubkey::new_unique();
-        let mut rent_sysvar = rent_sysvar();
+        let owner_key = Pubkey::new_unique();
+        let mut owner_account = SolanaAccount::default();
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
+        let mut rent_sysvar = rent_sysvar();
+
+        do_process_instruction(
+            initialize_non_transferable_mint(&program_id, &mint_key).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_non_transferable_mint(&program_id, &mint_key).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(
+            mint.get_extension_types().unwrap(),
+            vec![ExtensionType::NonTransferable]
+        );
+
+        // legacy-sized account can't be marked as non-transferable
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            do_process_instruction(
+                initialize_account(&program_id, &legacy_account_key, &mint_key, &owner_key)
+                    .unwrap(),
+                vec![
+                    &mut legacy_account,
+                    &mut mint_account,
+                    &mut owner_account,
+                    &mut rent_sysvar,
+                ],
+            )
+        );
 
-        // initialize native account
         do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account_key,
-                &crate::native_mint::id(),
-                &owner_key,
-            )
-            .unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
@@ -5262,19 +7671,8 @@ mod tests {
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 40);
-
-        // initialize native account
         do_process_instruction(
-            initialize_account(
-                &program_id,
-                &account2_key,
-                &crate::native_mint::id(),
-                &owner_key,
-            )
-            .unwrap(),
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
                 &mut account2_account,
                 &mut mint_account,
@@ -5283,338 +7681,322 @@ mod tests {
             ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
+        let account = StateWithExtensions::<Account>::unpack(&account_account.data).unwrap();
+        assert_eq!(
+            account.get_extension_types().unwrap(),
+            vec![ExtensionType::NonTransferableAccount]
+        );
 
-        // mint_to unsupported
+        // minting is allowed
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+
+        // transfers are not
         assert_eq!(
-            Err(TokenError::NativeNotSupported.into()),
+            Err(TokenError::NonTransferable.into()),
             do_process_instruction(
-                mint_to(
+                transfer(
                     &program_id,
-                    &crate::native_mint::id(),
                     &account_key,
+                    &account2_key,
                     &owner_key,
                     &[],
-                    42
+                    10
                 )
                 .unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account],
+                vec![
+                    &mut account_account,
+                    &mut account2_account,
+                    &mut owner_account,
+                ],
             )
         );
-
-        // burn unsupported
-        let bogus_mint_key = Pubkey::new_unique();
-        let mut bogus_mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        do_process_instruction(
-            initialize_mint(&program_id, &bogus_mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut bogus_mint_account, &mut rent_sysvar],
-        )
-        .unwrap();
-
         assert_eq!(
-            Err(TokenError::NativeNotSupported.into()),
+            Err(TokenError::NonTransferable.into()),
             do_process_instruction(
-                burn(
+                transfer_checked(
                     &program_id,
                     &account_key,
-                    &bogus_mint_key,
+                    &mint_key,
+                    &account2_key,
                     &owner_key,
                     &[],
-                    42
+                    10,
+                    2,
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
-                    &mut bogus_mint_account,
-                    &mut owner_account
+                    &mut mint_account,
+                    &mut account2_account,
+                    &mut owner_account,
                 ],
             )
         );
 
-        // ensure can't transfer below rent-exempt reserve
+        // nor is delegation
         assert_eq!(
-            Err(TokenError::InsufficientFunds.into()),
+            Err(TokenError::NonTransferable.into()),
             do_process_instruction(
-                transfer(
+                approve(
                     &program_id,
                     &account_key,
-                    &account2_key,
+                    &delegate_key,
                     &owner_key,
                     &[],
-                    50,
+                    10
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
-                    &mut account2_account,
+                    &mut delegate_account,
                     &mut owner_account,
                 ],
             )
         );
-
-        // transfer between native accounts
-        do_process_instruction(
-            transfer(
-                &program_id,
-                &account_key,
-                &account2_key,
-                &owner_key,
-                &[],
-                40,
-            )
-            .unwrap(),
-            vec![
-                &mut account_account,
-                &mut account2_account,
-                &mut owner_account,
-            ],
-        )
-        .unwrap();
-        assert_eq!(account_account.lamports, account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
-        assert_eq!(account2_account.lamports, account_minimum_balance() + 40);
-        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 40);
-
-        // set close authority
-        do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner3_key),
-                AuthorityType::CloseAccount,
-                &owner_key,
-                &[],
+        assert_eq!(
+            Err(TokenError::NonTransferable.into()),
+            do_process_instruction(
+                approve_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &delegate_key,
+                    &owner_key,
+                    &[],
+                    10,
+                    2,
+                )
+                .unwrap(),
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut delegate_account,
+                    &mut owner_account,
+                ],
             )
-            .unwrap(),
-            vec![&mut account_account, &mut owner_account],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.close_authority, COption::Some(owner3_key));
+        );
 
-        // set new account owner
+        // burning and closing are allowed
         do_process_instruction(
-            set_authority(
-                &program_id,
-                &account_key,
-                Some(&owner2_key),
-                AuthorityType::AccountOwner,
-                &owner_key,
-                &[],
-            )
-            .unwrap(),
-            vec![&mut account_account, &mut owner_account],
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
         )
         .unwrap();
-
-        // close authority cleared
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.close_authority, COption::None);
-
-        // close native account
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint.base.supply, 0);
         do_process_instruction(
-            close_account(&program_id, &account_key, &account3_key, &owner2_key, &[]).unwrap(),
+            close_account(&program_id, &account_key, &owner_key, &owner_key, &[]).unwrap(),
             vec![
                 &mut account_account,
-                &mut account3_account,
-                &mut owner2_account,
+                &mut owner_account.clone(),
+                &mut owner_account,
             ],
         )
         .unwrap();
         assert_eq!(account_account.lamports, 0);
-        assert_eq!(account3_account.lamports, 2 * account_minimum_balance());
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, 0);
     }
 
     #[test]
-    fn test_overflow() {
+    fn test_interest_bearing_mint() {
         let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let account2_key = Pubkey::new_unique();
-        let mut account2_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_len =
+            ExtensionType::get_account_len::<Mint>(&[ExtensionType::InterestBearingConfig]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
         );
+        let legacy_mint_key = Pubkey::new_unique();
+        let mut legacy_mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
-        let mint_owner_key = Pubkey::new_unique();
-        let mut mint_owner_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let rate_authority_key = Pubkey::new_unique();
+        let mut rate_authority_account = SolanaAccount::default();
+        let new_rate_authority_key = Pubkey::new_unique();
+        let mut new_rate_authority_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner
+        // legacy-sized mint can't hold the config
+        assert_eq!(
+            Err(ProgramError::InvalidAccountData),
+            do_process_instruction(
+                initialize_interest_bearing_mint(
+                    &program_id,
+                    &legacy_mint_key,
+                    Some(&rate_authority_key),
+                    500,
+                )
+                .unwrap(),
+                vec![&mut legacy_mint_account],
+            )
+        );
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &mint_owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+            initialize_mint(&program_id, &legacy_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut legacy_mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create an account
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![
-                &mut account_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
+            initialize_interest_bearing_mint(
+                &program_id,
+                &mint_key,
+                Some(&rate_authority_key),
+                500,
+            )
+            .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_interest_bearing_mint(&program_id, &mint_key, None, 500).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(
+            mint.get_extension::<InterestBearingConfig>().unwrap(),
+            InterestBearingConfig::new(
+                COption::Some(rate_authority_key),
+                500,
+                Clock::default().unix_timestamp
+            )
+        );
 
-        // create another account
+        // conversions work with and without the config
         do_process_instruction(
-            initialize_account(&program_id, &account2_key, &mint_key, &owner2_key).unwrap(),
-            vec![
-                &mut account2_account,
-                &mut mint_account,
-                &mut owner2_account,
-                &mut rent_sysvar,
-            ],
+            amount_to_ui_amount(&program_id, &mint_key, 1_000).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            ui_amount_to_amount(&program_id, &mint_key, 10.).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            amount_to_ui_amount(&program_id, &legacy_mint_key, 1_000).unwrap(),
+            vec![&mut legacy_mint_account],
         )
         .unwrap();
-
-        // mint the max to an account
         do_process_instruction(
-            mint_to(
-                &program_id,
-                &mint_key,
-                &account_key,
-                &mint_owner_key,
-                &[],
-                u64::MAX,
-            )
-            .unwrap(),
-            vec![
-                &mut mint_account,
-                &mut account_account,
-                &mut mint_owner_account,
-            ],
+            ui_amount_to_amount(&program_id, &legacy_mint_key, 10.).unwrap(),
+            vec![&mut legacy_mint_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u64::MAX);
-
-        // attempt to mint one more to account
         assert_eq!(
             Err(TokenError::Overflow.into()),
             do_process_instruction(
-                mint_to(
-                    &program_id,
-                    &mint_key,
-                    &account_key,
-                    &mint_owner_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut mint_account,
-                    &mut account_account,
-                    &mut mint_owner_account,
-                ],
+                ui_amount_to_amount(&program_id, &mint_key, -1.).unwrap(),
+                vec![&mut mint_account],
             )
         );
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u64::MAX);
 
-        // atttempt to mint one more to the other account
+        // only the rate authority can update the rate
         assert_eq!(
-            Err(TokenError::Overflow.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                mint_to(
+                update_interest_bearing_mint_rate(&program_id, &mint_key, &owner_key, &[], 100)
+                    .unwrap(),
+                vec![&mut mint_account, &mut owner_account],
+            )
+        );
+        do_process_instruction(
+            update_interest_bearing_mint_rate(
+                &program_id,
+                &mint_key,
+                &rate_authority_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut rate_authority_account],
+        )
+        .unwrap();
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        let config = mint.get_extension::<InterestBearingConfig>().unwrap();
+        assert_eq!(config.current_rate, 100);
+        assert_eq!(
+            Err(TokenError::AuthorityTypeNotSupported.into()),
+            do_process_instruction(
+                set_authority(
                     &program_id,
-                    &mint_key,
-                    &account2_key,
-                    &mint_owner_key,
+                    &legacy_mint_key,
+                    Some(&new_rate_authority_key),
+                    AuthorityType::InterestRate,
+                    &owner_key,
                     &[],
-                    1,
                 )
                 .unwrap(),
-                vec![
-                    &mut mint_account,
-                    &mut account2_account,
-                    &mut mint_owner_account,
-                ],
+                vec![&mut legacy_mint_account, &mut owner_account],
             )
         );
 
-        // burn some of the supply
+        // hand over the rate authority, then remove it
         do_process_instruction(
-            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&new_rate_authority_key),
+                AuthorityType::InterestRate,
+                &rate_authority_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut rate_authority_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u64::MAX - 100);
-
         do_process_instruction(
-            mint_to(
+            set_authority(
                 &program_id,
                 &mint_key,
-                &account_key,
-                &mint_owner_key,
+                None,
+                AuthorityType::InterestRate,
+                &new_rate_authority_key,
                 &[],
-                100,
             )
             .unwrap(),
-            vec![
-                &mut mint_account,
-                &mut account_account,
-                &mut mint_owner_account,
-            ],
+            vec![&mut mint_account, &mut new_rate_authority_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.amount, u64::MAX);
-
-        // manipulate account balance to attempt overflow transfer
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.amount = 1;
-        Account::pack(account, &mut account2_account.data).unwrap();
-
         assert_eq!(
-            Err(TokenError::Overflow.into()),
+            Err(TokenError::NoAuthorityExists.into()),
             do_process_instruction(
-                transfer(
+                update_interest_bearing_mint_rate(
                     &program_id,
-                    &account2_key,
-                    &account_key,
-                    &owner2_key,
+                    &mint_key,
+                    &new_rate_authority_key,
                     &[],
-                    1,
+                    0,
                 )
                 .unwrap(),
-                vec![
-                    &mut account2_account,
-                    &mut account_account,
-                    &mut owner2_account,
-                ],
+                vec![&mut mint_account, &mut new_rate_authority_account],
             )
         );
     }
 
     #[test]
-    fn test_frozen() {
+    fn test_permanent_delegate() {
         let program_id = crate::id();
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::PermanentDelegate]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
+            &program_id,
+        );
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
@@ -5629,19 +8011,29 @@ mod tests {
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let delegate_key = Pubkey::new_unique();
+        let mut delegate_account = SolanaAccount::default();
+        let new_delegate_key = Pubkey::new_unique();
+        let mut new_delegate_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint and fund first account
+        do_process_instruction(
+            initialize_permanent_delegate(&program_id, &mint_key, &delegate_key).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
-
-        // create account
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_permanent_delegate(&program_id, &mint_key, &delegate_key).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -5652,8 +8044,6 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // create another account
         do_process_instruction(
             initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -5664,350 +8054,421 @@ mod tests {
             ],
         )
         .unwrap();
-
-        // fund first account
         do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
             vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
 
-        // no transfer if either account is frozen
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account2_account.data).unwrap();
+        // the permanent delegate needs the mint to transfer
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
                 transfer(
                     &program_id,
                     &account_key,
                     &account2_key,
-                    &owner_key,
+                    &delegate_key,
                     &[],
-                    500,
+                    100
                 )
                 .unwrap(),
                 vec![
                     &mut account_account,
                     &mut account2_account,
-                    &mut owner_account,
+                    &mut delegate_account,
                 ],
             )
         );
+        do_process_instruction(
+            transfer_checked(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &account2_key,
+                &delegate_key,
+                &[],
+                100,
+                2,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut account2_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 900);
+        assert_eq!(account.delegate, COption::None);
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
+        assert_eq!(account.amount, 100);
 
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.state = AccountState::Initialized;
-        Account::pack(account, &mut account_account.data).unwrap();
-        let mut account = Account::unpack_unchecked(&account2_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account2_account.data).unwrap();
+        // and can burn without approval
+        do_process_instruction(
+            burn(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &delegate_key,
+                &[],
+                400,
+            )
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut delegate_account,
+            ],
+        )
+        .unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.amount, 500);
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_account.data).unwrap();
+        assert_eq!(mint.base.supply, 600);
+
+        // only the permanent delegate can hand over the authority
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                transfer(
+                set_authority(
                     &program_id,
-                    &account_key,
-                    &account2_key,
+                    &mint_key,
+                    Some(&owner_key),
+                    AuthorityType::PermanentDelegate,
                     &owner_key,
                     &[],
-                    500,
                 )
                 .unwrap(),
+                vec![&mut mint_account, &mut owner_account],
+            )
+        );
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&new_delegate_key),
+                AuthorityType::PermanentDelegate,
+                &delegate_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut delegate_account],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                burn(&program_id, &account_key, &mint_key, &delegate_key, &[], 1).unwrap(),
                 vec![
                     &mut account_account,
-                    &mut account2_account,
-                    &mut owner_account,
+                    &mut mint_account,
+                    &mut delegate_account,
                 ],
             )
         );
+        do_process_instruction(
+            burn(
+                &program_id,
+                &account2_key,
+                &mint_key,
+                &new_delegate_key,
+                &[],
+                100,
+            )
+            .unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut new_delegate_account,
+            ],
+        )
+        .unwrap();
 
-        // no approve if account is frozen
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account_account.data).unwrap();
-        let delegate_key = Pubkey::new_unique();
-        let mut delegate_account = SolanaAccount::default();
+        // once removed, it cannot be set again
+        do_process_instruction(
+            set_authority(
+                &program_id,
+                &mint_key,
+                None,
+                AuthorityType::PermanentDelegate,
+                &new_delegate_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut new_delegate_account],
+        )
+        .unwrap();
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::NoAuthorityExists.into()),
             do_process_instruction(
-                approve(
+                set_authority(
                     &program_id,
-                    &account_key,
-                    &delegate_key,
-                    &owner_key,
+                    &mint_key,
+                    Some(&new_delegate_key),
+                    AuthorityType::PermanentDelegate,
+                    &new_delegate_key,
                     &[],
-                    100
                 )
                 .unwrap(),
-                vec![
-                    &mut account_account,
-                    &mut delegate_account,
-                    &mut owner_account,
-                ],
-            )
-        );
-
-        // no revoke if account is frozen
-        let mut account = Account::unpack_unchecked(&account_account.data).unwrap();
-        account.delegate = COption::Some(delegate_key);
-        account.delegated_amount = 100;
-        Account::pack(account, &mut account_account.data).unwrap();
-        assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
-            do_process_instruction(
-                revoke(&program_id, &account_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut owner_account],
+                vec![&mut mint_account, &mut new_delegate_account],
             )
         );
-
-        // no set authority if account is frozen
-        let new_owner_key = Pubkey::new_unique();
         assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                set_authority(
+                burn(
                     &program_id,
                     &account_key,
-                    Some(&new_owner_key),
-                    AuthorityType::AccountOwner,
-                    &owner_key,
-                    &[]
+                    &mint_key,
+                    &new_delegate_key,
+                    &[],
+                    1,
                 )
                 .unwrap(),
-                vec![&mut account_account, &mut owner_account,],
-            )
-        );
-
-        // no mint_to if destination account is frozen
-        assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
-            do_process_instruction(
-                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 100).unwrap(),
-                vec![&mut mint_account, &mut account_account, &mut owner_account,],
-            )
-        );
-
-        // no burn if account is frozen
-        assert_eq!(
-            Err(TokenError::AccountFrozen.into()),
-            do_process_instruction(
-                burn(&program_id, &account_key, &mint_key, &owner_key, &[], 100).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                vec![
+                    &mut account_account,
+                    &mut mint_account,
+                    &mut new_delegate_account,
+                ],
             )
         );
     }
 
     #[test]
-    fn test_freeze_thaw_dups() {
+    fn test_default_account_state() {
         let program_id = crate::id();
-        let account1_key = Pubkey::new_unique();
-        let mut account1_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_len =
+            ExtensionType::get_account_len::<Mint>(&[ExtensionType::DefaultAccountState]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
         );
-        let account1_info: AccountInfo = (&account1_key, true, &mut account1_account).into();
-        let owner_key = Pubkey::new_unique();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let mint_info: AccountInfo = (&mint_key, true, &mut mint_account).into();
-        let rent_key = rent::id();
-        let mut rent_sysvar = rent_sysvar();
-        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
-
-        // create mint
-        do_process_instruction_dups(
-            initialize_mint(&program_id, &mint_key, &owner_key, Some(&account1_key), 2).unwrap(),
-            vec![mint_info.clone(), rent_info.clone()],
-        )
-        .unwrap();
-
-        // create account
-        do_process_instruction_dups(
-            initialize_account(&program_id, &account1_key, &mint_key, &account1_key).unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-                rent_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // freeze where mint freeze_authority is account
-        do_process_instruction_dups(
-            freeze_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
-
-        // thaw where mint freeze_authority is account
-        let mut account = Account::unpack_unchecked(&account1_info.data.borrow()).unwrap();
-        account.state = AccountState::Frozen;
-        Account::pack(account, &mut account1_info.data.borrow_mut()).unwrap();
-        do_process_instruction_dups(
-            thaw_account(&program_id, &account1_key, &mint_key, &account1_key, &[]).unwrap(),
-            vec![
-                account1_info.clone(),
-                mint_info.clone(),
-                account1_info.clone(),
-            ],
-        )
-        .unwrap();
-    }
-
-    #[test]
-    fn test_freeze_account() {
-        let program_id = crate::id();
         let account_key = Pubkey::new_unique();
         let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let account_owner_key = Pubkey::new_unique();
-        let mut account_owner_account = SolanaAccount::default();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
+            Account::get_packed_len(),
+            &program_id,
+        );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let owner2_key = Pubkey::new_unique();
-        let mut owner2_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let freeze_authority_key = Pubkey::new_unique();
+        let mut freeze_authority_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // create new mint with owner different from account owner
+        assert_eq!(
+            Err(TokenError::InvalidState.into()),
+            do_process_instruction(
+                initialize_default_account_state(
+                    &program_id,
+                    &mint_key,
+                    AccountState::Uninitialized
+                )
+                .unwrap(),
+                vec![&mut mint_account],
+            )
+        );
         do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+            initialize_default_account_state(&program_id, &mint_key, AccountState::Frozen).unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
+
+        // frozen by default requires a freeze authority
+        assert_eq!(
+            Err(TokenError::MintCannotFreeze.into()),
+            do_process_instruction(
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                vec![&mut mint_account, &mut rent_sysvar],
+            )
+        );
+        do_process_instruction(
+            initialize_mint(
+                &program_id,
+                &mint_key,
+                &owner_key,
+                Some(&freeze_authority_key),
+                2,
+            )
+            .unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
 
-        // create account
+        // new accounts start out frozen
         do_process_instruction(
-            initialize_account(&program_id, &account_key, &mint_key, &account_owner_key).unwrap(),
+            initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
                 &mut account_account,
                 &mut mint_account,
-                &mut account_owner_account,
+                &mut owner_account,
                 &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        // mint to account
-        do_process_instruction(
-            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1000).unwrap(),
-            vec![&mut mint_account, &mut account_account, &mut owner_account],
-        )
-        .unwrap();
-
-        // mint cannot freeze
-        assert_eq!(
-            Err(TokenError::MintCannotFreeze.into()),
-            do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
-            )
-        );
-
-        // missing freeze_authority
-        let mut mint = Mint::unpack_unchecked(&mint_account.data).unwrap();
-        mint.freeze_authority = COption::Some(owner_key);
-        Mint::pack(mint, &mut mint_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        assert_eq!(account.state, AccountState::Frozen);
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::AccountFrozen.into()),
             do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+                vec![&mut mint_account, &mut account_account, &mut owner_account],
             )
         );
-
-        // check explicit thaw
-        assert_eq!(
-            Err(TokenError::InvalidState.into()),
-            do_process_instruction(
-                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+        do_process_instruction(
+            thaw_account(
+                &program_id,
+                &account_key,
+                &mint_key,
+                &freeze_authority_key,
+                &[],
             )
-        );
-
-        // freeze
+            .unwrap(),
+            vec![
+                &mut account_account,
+                &mut mint_account,
+                &mut freeze_authority_account,
+            ],
+        )
+        .unwrap();
         do_process_instruction(
-            freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
-        assert_eq!(account.state, AccountState::Frozen);
 
-        // check explicit freeze
+        // only the freeze authority can update the default state
         assert_eq!(
-            Err(TokenError::InvalidState.into()),
+            Err(TokenError::OwnerMismatch.into()),
             do_process_instruction(
-                freeze_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner_account],
+                update_default_account_state(
+                    &program_id,
+                    &mint_key,
+                    &owner_key,
+                    &[],
+                    AccountState::Initialized,
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut owner_account],
             )
         );
-
-        // check thaw authority
         assert_eq!(
-            Err(TokenError::OwnerMismatch.into()),
+            Err(TokenError::InvalidState.into()),
             do_process_instruction(
-                thaw_account(&program_id, &account_key, &mint_key, &owner2_key, &[]).unwrap(),
-                vec![&mut account_account, &mut mint_account, &mut owner2_account],
+                update_default_account_state(
+                    &program_id,
+                    &mint_key,
+                    &freeze_authority_key,
+                    &[],
+                    AccountState::Uninitialized,
+                )
+                .unwrap(),
+                vec![&mut mint_account, &mut freeze_authority_account],
             )
         );
-
-        // thaw
         do_process_instruction(
-            thaw_account(&program_id, &account_key, &mint_key, &owner_key, &[]).unwrap(),
-            vec![&mut account_account, &mut mint_account, &mut owner_account],
+            update_default_account_state(
+                &program_id,
+                &mint_key,
+                &freeze_authority_key,
+                &[],
+                AccountState::Initialized,
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut freeze_authority_account],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+            vec![
+                &mut account2_account,
+                &mut mint_account,
+                &mut owner_account,
+                &mut rent_sysvar,
+            ],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&account_account.data).unwrap();
+        let account = Account::unpack_unchecked(&account2_account.data).unwrap();
         assert_eq!(account.state, AccountState::Initialized);
     }
 
     #[test]
-    fn test_initialize_account2_and_3() {
+    fn test_close_mint() {
         let program_id = crate::id();
-        let account_key = Pubkey::new_unique();
-        let mut account_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
-            &program_id,
-        );
-        let mut account2_account = SolanaAccount::new(
-            account_minimum_balance(),
-            Account::get_packed_len(),
+        let mint_len = ExtensionType::get_account_len::<Mint>(&[ExtensionType::MintCloseAuthority]);
+        let mint_key = Pubkey::new_unique();
+        let mut mint_account = SolanaAccount::new(
+            Rent::default().minimum_balance(mint_len),
+            mint_len,
             &program_id,
         );
-        let mut account3_account = SolanaAccount::new(
+        let legacy_mint_key = Pubkey::new_unique();
+        let mut legacy_mint_account =
+            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
             account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
-        let mint_key = Pubkey::new_unique();
-        let mut mint_account =
-            SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
+        let close_authority_key = Pubkey::new_unique();
+        let mut close_authority_account = SolanaAccount::default();
+        let new_close_authority_key = Pubkey::new_unique();
+        let mut new_close_authority_account = SolanaAccount::default();
+        let dest_key = Pubkey::new_unique();
+        let mut dest_account = SolanaAccount::default();
         let mut rent_sysvar = rent_sysvar();
 
-        // create mint
+        do_process_instruction(
+            initialize_mint(&program_id, &legacy_mint_key, &owner_key, None, 2).unwrap(),
+            vec![&mut legacy_mint_account, &mut rent_sysvar],
+        )
+        .unwrap();
+        do_process_instruction(
+            initialize_mint_close_authority(&program_id, &mint_key, Some(&close_authority_key))
+                .unwrap(),
+            vec![&mut mint_account],
+        )
+        .unwrap();
         do_process_instruction(
             initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
             vec![&mut mint_account, &mut rent_sysvar],
         )
         .unwrap();
+        assert_eq!(
+            Err(TokenError::AlreadyInUse.into()),
+            do_process_instruction(
+                initialize_mint_close_authority(&program_id, &mint_key, None).unwrap(),
+                vec![&mut mint_account],
+            )
+        );
 
+        // mints without a close authority cannot be closed
+        assert_eq!(
+            Err(TokenError::AuthorityTypeNotSupported.into()),
+            do_process_instruction(
+                close_account(&program_id, &legacy_mint_key, &dest_key, &owner_key, &[]).unwrap(),
+                vec![
+                    &mut legacy_mint_account,
+                    &mut dest_account,
+                    &mut owner_account,
+                ],
+            )
+        );
+
+        // nor can mints with supply
         do_process_instruction(
             initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
             vec![
@@ -6018,143 +8479,244 @@ mod tests {
             ],
         )
         .unwrap();
+        do_process_instruction(
+            mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut mint_account, &mut account_account, &mut owner_account],
+        )
+        .unwrap();
+        assert_eq!(
+            Err(TokenError::MintHasSupply.into()),
+            do_process_instruction(
+                close_account(&program_id, &mint_key, &dest_key, &close_authority_key, &[])
+                    .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut dest_account,
+                    &mut close_authority_account,
+                ],
+            )
+        );
+        do_process_instruction(
+            burn(&program_id, &account_key, &mint_key, &owner_key, &[], 1_000).unwrap(),
+            vec![&mut account_account, &mut mint_account, &mut owner_account],
+        )
+        .unwrap();
 
+        // only the close authority may close the mint
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                close_account(&program_id, &mint_key, &dest_key, &owner_key, &[]).unwrap(),
+                vec![&mut mint_account, &mut dest_account, &mut owner_account],
+            )
+        );
         do_process_instruction(
-            initialize_account2(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![&mut account2_account, &mut mint_account, &mut rent_sysvar],
+            set_authority(
+                &program_id,
+                &mint_key,
+                Some(&new_close_authority_key),
+                AuthorityType::CloseMint,
+                &close_authority_key,
+                &[],
+            )
+            .unwrap(),
+            vec![&mut mint_account, &mut close_authority_account],
         )
         .unwrap();
+        assert_eq!(
+            Err(TokenError::OwnerMismatch.into()),
+            do_process_instruction(
+                close_account(&program_id, &mint_key, &dest_key, &close_authority_key, &[])
+                    .unwrap(),
+                vec![
+                    &mut mint_account,
+                    &mut dest_account,
+                    &mut close_authority_account,
+                ],
+            )
+        );
 
-        assert_eq!(account_account, account2_account);
+        // the mint cannot be closed into itself
+        {
+            let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+            let authority_info: AccountInfo = (
+                &new_close_authority_key,
+                true,
+                &mut new_close_authority_account,
+            )
+                .into();
+            assert_eq!(
+                Err(ProgramError::InvalidAccountData),
+                do_process_instruction_dups(
+                    close_account(
+                        &program_id,
+                        &mint_key,
+                        &mint_key,
+                        &new_close_authority_key,
+                        &[],
+                    )
+                    .unwrap(),
+                    vec![mint_info.clone(), mint_info, authority_info],
+                )
+            );
+        }
 
+        let mint_lamports = mint_account.lamports;
         do_process_instruction(
-            initialize_account3(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
-            vec![&mut account3_account, &mut mint_account],
+            close_account(
+                &program_id,
+                &mint_key,
+                &dest_key,
+                &new_close_authority_key,
+                &[],
+            )
+            .unwrap(),
+            vec![
+                &mut mint_account,
+                &mut dest_account,
+                &mut new_close_authority_account,
+            ],
         )
         .unwrap();
-
-        assert_eq!(account_account, account3_account);
+        assert_eq!(mint_account.lamports, 0);
+        assert_eq!(dest_account.lamports, mint_lamports);
+        assert!(mint_account.data.iter().all(|&byte| byte == 0));
     }
 
     #[test]
-    fn test_sync_native() {
+    fn test_batch() {
         let program_id = crate::id();
         let mint_key = Pubkey::new_unique();
         let mut mint_account =
             SolanaAccount::new(mint_minimum_balance(), Mint::get_packed_len(), &program_id);
-        let native_account_key = Pubkey::new_unique();
-        let lamports = 40;
-        let mut native_account = SolanaAccount::new(
-            account_minimum_balance() + lamports,
+        let mint_info: AccountInfo = (&mint_key, false, &mut mint_account).into();
+        let account_key = Pubkey::new_unique();
+        let mut account_account = SolanaAccount::new(
+            account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-        let non_native_account_key = Pubkey::new_unique();
-        let mut non_native_account = SolanaAccount::new(
-            account_minimum_balance() + 50,
+        let account_info: AccountInfo = (&account_key, false, &mut account_account).into();
+        let account2_key = Pubkey::new_unique();
+        let mut account2_account = SolanaAccount::new(
+            account_minimum_balance(),
             Account::get_packed_len(),
             &program_id,
         );
-
+        let account2_info: AccountInfo = (&account2_key, false, &mut account2_account).into();
         let owner_key = Pubkey::new_unique();
         let mut owner_account = SolanaAccount::default();
+        let owner_info: AccountInfo = (&owner_key, true, &mut owner_account).into();
+        let rent_key = rent::id();
         let mut rent_sysvar = rent_sysvar();
+        let rent_info: AccountInfo = (&rent_key, false, &mut rent_sysvar).into();
 
-        // initialize non-native mint
-        do_process_instruction(
-            initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
-            vec![&mut mint_account, &mut rent_sysvar],
+        // set up and use the mint in a single instruction
+        let instruction = batch(
+            &program_id,
+            &[
+                initialize_mint(&program_id, &mint_key, &owner_key, None, 2).unwrap(),
+                initialize_account(&program_id, &account_key, &mint_key, &owner_key).unwrap(),
+                initialize_account(&program_id, &account2_key, &mint_key, &owner_key).unwrap(),
+                mint_to(&program_id, &mint_key, &account_key, &owner_key, &[], 1_000).unwrap(),
+                transfer_checked(
+                    &program_id,
+                    &account_key,
+                    &mint_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    300,
+                    2,
+                )
+                .unwrap(),
+                burn(&program_id, &account_key, &mint_key, &owner_key, &[], 200).unwrap(),
+            ],
         )
         .unwrap();
-
-        // initialize non-native account
-        do_process_instruction(
-            initialize_account(&program_id, &non_native_account_key, &mint_key, &owner_key)
+        assert_eq!(instruction.accounts.len(), 20);
+        let account_infos = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                [
+                    &mint_info,
+                    &account_info,
+                    &account2_info,
+                    &owner_info,
+                    &rent_info,
+                ]
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .map(|info| (*info).clone())
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        do_process_instruction_dups(instruction, account_infos.clone()).unwrap();
+        let account = Account::unpack_unchecked(&account_info.data.borrow()).unwrap();
+        assert_eq!(account.amount, 500);
+        let account = Account::unpack_unchecked(&account2_info.data.borrow()).unwrap();
+        assert_eq!(account.amount, 300);
+        let mint = Mint::unpack_unchecked(&mint_info.data.borrow()).unwrap();
+        assert_eq!(mint.supply, 800);
+
+        // any failing sub-instruction fails the batch
+        let instruction = batch(
+            &program_id,
+            &[
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    100,
+                )
+                .unwrap(),
+                transfer(
+                    &program_id,
+                    &account_key,
+                    &account2_key,
+                    &owner_key,
+                    &[],
+                    1_000,
+                )
                 .unwrap(),
-            vec![
-                &mut non_native_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
             ],
         )
         .unwrap();
-
-        let account = Account::unpack_unchecked(&non_native_account.data).unwrap();
-        assert!(!account.is_native());
-        assert_eq!(account.amount, 0);
-
-        // fail sync non-native
+        let account_infos = vec![
+            account_info.clone(),
+            account2_info.clone(),
+            owner_info.clone(),
+            account_info.clone(),
+            account2_info.clone(),
+            owner_info.clone(),
+        ];
         assert_eq!(
-            Err(TokenError::NonNativeNotSupported.into()),
-            do_process_instruction(
-                sync_native(&program_id, &non_native_account_key,).unwrap(),
-                vec![&mut non_native_account],
-            )
+            Err(TokenError::InsufficientFunds.into()),
+            do_process_instruction_dups(instruction.clone(), account_infos.clone())
         );
-
-        // fail sync uninitialized
         assert_eq!(
-            Err(ProgramError::UninitializedAccount),
-            do_process_instruction(
-                sync_native(&program_id, &native_account_key,).unwrap(),
-                vec![&mut native_account],
-            )
+            Err(ProgramError::NotEnoughAccountKeys),
+            do_process_instruction_dups(instruction, account_infos[..5].to_vec())
         );
 
-        // wrap native account
-        do_process_instruction(
-            initialize_account(
-                &program_id,
-                &native_account_key,
-                &crate::native_mint::id(),
-                &owner_key,
-            )
-            .unwrap(),
-            vec![
-                &mut native_account,
-                &mut mint_account,
-                &mut owner_account,
-                &mut rent_sysvar,
-            ],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert!(account.is_native());
-        assert_eq!(account.amount, lamports);
-
-        // sync, no change
-        do_process_instruction(
-            sync_native(&program_id, &native_account_key).unwrap(),
-            vec![&mut native_account],
-        )
-        .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert_eq!(account.amount, lamports);
-
-        // transfer sol
-        let new_lamports = lamports + 50;
-        native_account.lamports = account_minimum_balance() + new_lamports;
-
-        // success sync
-        do_process_instruction(
-            sync_native(&program_id, &native_account_key).unwrap(),
-            vec![&mut native_account],
+        // batches cannot be nested, and only hold token instructions
+        let instruction = batch(
+            &program_id,
+            &[sync_native(&program_id, &account_key).unwrap()],
         )
         .unwrap();
-        let account = Account::unpack_unchecked(&native_account.data).unwrap();
-        assert_eq!(account.amount, new_lamports);
-
-        // reduce sol
-        native_account.lamports -= 1;
-
-        // fail sync
         assert_eq!(
-            Err(TokenError::InvalidState.into()),
-            do_process_instruction(
-                sync_native(&program_id, &native_account_key,).unwrap(),
-                vec![&mut native_account],
-            )
+            Err(TokenError::InvalidInstruction.into()),
+            batch(&program_id, &[instruction])
+        );
+        let mut instruction = sync_native(&program_id, &account_key).unwrap();
+        instruction.program_id = Pubkey::new_unique();
+        assert_eq!(
+            Err(ProgramError::IncorrectProgramId),
+            batch(&program_id, &[instruction])
         );
     }
 }
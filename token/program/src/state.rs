@@ -1,6 +1,9 @@
 //! State transition types
 
-use crate::instruction::MAX_SIGNERS;
+use crate::{
+    extension::{AccountType, ACCOUNT_TYPE_INDEX},
+    instruction::MAX_SIGNERS,
+};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use num_enum::TryFromPrimitive;
 use solana_program::{
@@ -245,8 +248,58 @@ impl Pack for Multisig {
     }
 }
 
+/// Offset of the account state byte in a token account
+const ACCOUNT_INITIALIZED_INDEX: usize = 108;
+/// Offset of the owner in a token account
+const ACCOUNT_OWNER_INDEX: usize = 32;
+
+/// Checks the account state byte of raw token account data, without
+/// unpacking the rest of the account
+pub fn is_initialized_account(account_data: &[u8]) -> bool {
+    account_data
+        .get(ACCOUNT_INITIALIZED_INDEX)
+        .map(|&state| state != AccountState::Uninitialized as u8)
+        .unwrap_or(false)
+}
+
+/// Accessors for the commonly-used fields of raw token account data, for
+/// legacy-sized accounts as well as accounts with extensions
+pub trait GenericTokenAccount {
+    /// Checks that the data is a valid, initialized token account
+    fn valid_account_data(account_data: &[u8]) -> bool;
+
+    /// Unpacks the owner of the token account, if the data is valid
+    fn unpack_account_owner(account_data: &[u8]) -> Option<Pubkey> {
+        if Self::valid_account_data(account_data) {
+            Some(Pubkey::new(
+                &account_data[ACCOUNT_OWNER_INDEX..ACCOUNT_OWNER_INDEX + 32],
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Unpacks the mint of the token account, if the data is valid
+    fn unpack_account_mint(account_data: &[u8]) -> Option<Pubkey> {
+        if Self::valid_account_data(account_data) {
+            Some(Pubkey::new(&account_data[..32]))
+        } else {
+            None
+        }
+    }
+}
+impl GenericTokenAccount for Account {
+    fn valid_account_data(account_data: &[u8]) -> bool {
+        let valid_len = account_data.len() == Account::LEN
+            || (account_data.len() > ACCOUNT_TYPE_INDEX
+                && account_data.len() != Multisig::LEN
+                && account_data[ACCOUNT_TYPE_INDEX] == AccountType::Account as u8);
+        valid_len && is_initialized_account(account_data)
+    }
+}
+
 // Helpers
-fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
+pub(crate) fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
     let (tag, body) = mut_array_refs![dst, 4, 32];
     match src {
         COption::Some(key) => {
@@ -258,7 +311,7 @@ fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
         }
     }
 }
-fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
+pub(crate) fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
     let (tag, body) = array_refs![src, 4, 32];
     match *tag {
         [0, 0, 0, 0] => Ok(COption::None),